@@ -1,6 +1,7 @@
 use std::result::Result::Ok;
 
 use anyhow::*;
+use gas::prelude::Id;
 use universaldb::prelude::*;
 
 use rivet_runner_protocol as rp;
@@ -10,6 +11,7 @@ use crate::key::KeyWrapper;
 pub struct EntryBuilder {
 	pub key: KeyWrapper,
 	metadata: Option<rp::KvMetadata>,
+	expire_ts: Option<i64>,
 	value: Vec<u8>,
 	next_idx: usize,
 }
@@ -19,6 +21,7 @@ impl EntryBuilder {
 		EntryBuilder {
 			key,
 			metadata: None,
+			expire_ts: None,
 			value: Vec::new(),
 			next_idx: 0,
 		}
@@ -32,6 +35,19 @@ impl EntryBuilder {
 		}
 	}
 
+	pub fn append_expire_ts(&mut self, expire_ts: i64) {
+		self.expire_ts = Some(expire_ts);
+	}
+
+	pub fn expire_ts(&self) -> Option<i64> {
+		self.expire_ts
+	}
+
+	/// Expired entries are hidden from reads until they are reclaimed by the sweeper.
+	pub fn is_expired(&self, now: i64) -> bool {
+		self.expire_ts.map(|ts| ts <= now).unwrap_or_default()
+	}
+
 	pub fn append_chunk(&mut self, idx: usize, chunk: &[u8]) {
 		if idx >= self.next_idx {
 			self.value.extend(chunk);
@@ -145,3 +161,95 @@ impl<'de> TupleUnpack<'de> for EntryMetadataKey {
 		Ok((input, v))
 	}
 }
+
+#[derive(Debug)]
+pub struct EntryExpireTsKey {
+	pub key: KeyWrapper,
+}
+
+impl EntryExpireTsKey {
+	pub fn new(key: KeyWrapper) -> Self {
+		EntryExpireTsKey { key }
+	}
+}
+
+impl FormalKey for EntryExpireTsKey {
+	// Timestamp.
+	type Value = i64;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(i64::from_be_bytes(raw.try_into()?))
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.to_be_bytes().to_vec())
+	}
+}
+
+impl TuplePack for EntryExpireTsKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (&self.key, EXPIRE_TS);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for EntryExpireTsKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (key, data)) = <(KeyWrapper, usize)>::unpack(input, tuple_depth)?;
+		if data != EXPIRE_TS {
+			return Err(PackError::Message("expected EXPIRE_TS data".into()));
+		}
+
+		let v = EntryExpireTsKey { key };
+
+		Ok((input, v))
+	}
+}
+
+/// Key in `pegboard::keys::actor_kv_expire_subspace` indexing an entry by its expiration timestamp.
+#[derive(Debug)]
+pub struct ExpireIdxKey {
+	pub expire_ts: i64,
+	pub actor_id: Id,
+	pub key: KeyWrapper,
+}
+
+impl ExpireIdxKey {
+	pub fn new(expire_ts: i64, actor_id: Id, key: KeyWrapper) -> Self {
+		ExpireIdxKey {
+			expire_ts,
+			actor_id,
+			key,
+		}
+	}
+}
+
+impl TuplePack for ExpireIdxKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (self.expire_ts, self.actor_id, &self.key);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for ExpireIdxKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (expire_ts, actor_id, key)) =
+			<(i64, Id, KeyWrapper)>::unpack(input, tuple_depth)?;
+
+		let v = ExpireIdxKey {
+			expire_ts,
+			actor_id,
+			key,
+		};
+
+		Ok((input, v))
+	}
+}
@@ -0,0 +1,38 @@
+use rivet_error::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(RivetError, Debug, Clone, Deserialize, Serialize)]
+#[error("actor_kv")]
+pub enum ActorKv {
+	#[error(
+		"storage_quota_exceeded",
+		"Not enough space left in the actor's KV storage.",
+		"Not enough space left in the actor's KV storage ({usage} of {limit} bytes used, current payload is {payload_size} bytes)."
+	)]
+	StorageQuotaExceeded {
+		limit: u64,
+		usage: u64,
+		payload_size: u64,
+	},
+
+	#[error(
+		"too_many_keys",
+		"Too many keys in a single request.",
+		"Too many keys in a single request ({count} keys, max {limit})."
+	)]
+	TooManyKeys { limit: u32, count: usize },
+
+	#[error(
+		"value_too_large",
+		"KV value too large.",
+		"KV value too large ({size} bytes, max {limit})."
+	)]
+	ValueTooLarge { limit: u32, size: usize },
+
+	#[error(
+		"payload_too_large",
+		"KV payload too large.",
+		"KV payload too large ({size} bytes, max {limit})."
+	)]
+	PayloadTooLarge { limit: u32, size: usize },
+}
@@ -1,26 +1,36 @@
-use std::result::Result::Ok;
+use std::{collections::HashMap, result::Result::Ok, time::Duration};
 
 use anyhow::*;
-use entry::{EntryBaseKey, EntryBuilder, EntryMetadataKey, EntryValueChunkKey};
-use futures_util::{StreamExt, TryStreamExt};
+use entry::{
+	EntryBaseKey, EntryBuilder, EntryExpireTsKey, EntryMetadataKey, EntryValueChunkKey,
+	ExpireIdxKey,
+};
+use futures_util::{Stream, StreamExt, TryStreamExt};
 use gas::prelude::*;
 use key::{KeyWrapper, ListKeyWrapper};
 use rivet_runner_protocol as rp;
+use rivet_types::namespaces::KvQuota;
+use universaldb::options::MutationType;
 use universaldb::prelude::*;
-use universaldb::tuple::Subspace;
-use utils::{validate_entries, validate_keys};
+use universaldb::tuple::{Subspace, Versionstamp};
+use utils::{validate_entries, validate_keys, validate_txn};
+use watch::ChangeEvent;
 
 mod entry;
+pub mod errors;
 mod key;
+pub mod snapshot;
 mod utils;
+pub mod watch;
 
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 const MAX_KEY_SIZE: usize = 2 * 1024;
-const MAX_VALUE_SIZE: usize = 128 * 1024;
-const MAX_KEYS: usize = 128;
-const MAX_PUT_PAYLOAD_SIZE: usize = 976 * 1024;
-const MAX_STORAGE_SIZE: usize = 1024 * 1024 * 1024; // 1 GiB
 const VALUE_CHUNK_SIZE: usize = 10_000; // 10 KB, not KiB, see https://apple.github.io/foundationdb/blob.html
+/// How often to reclaim expired entries.
+const EXPIRE_SWEEP_INTERVAL: Duration = Duration::from_secs(15);
+/// Max amount of expired entries to reclaim per transaction.
+const EXPIRE_SWEEP_BATCH_SIZE: usize = 1024;
+/// Max amount of sub keys to read per transaction when exporting.
+const EXPORT_BATCH_SIZE: usize = 1024;
 
 fn subspace(actor_id: Id) -> universaldb::utils::Subspace {
 	pegboard::keys::actor_kv_subspace().subspace(&actor_id)
@@ -43,14 +53,16 @@ pub async fn get_subspace_size(db: &universaldb::Database, subspace: &Subspace)
 pub async fn get(
 	db: &universaldb::Database,
 	actor_id: Id,
+	quota: &KvQuota,
 	keys: Vec<rp::KvKey>,
 ) -> Result<(Vec<rp::KvKey>, Vec<rp::KvValue>, Vec<rp::KvMetadata>)> {
-	validate_keys(&keys)?;
+	validate_keys(quota, &keys)?;
 
 	db.run(|tx| {
 		let keys = keys.clone();
 		async move {
 			let tx = tx.with_subspace(subspace(actor_id));
+			let now = utils::now();
 
 			let size_estimate = keys.len().min(1024);
 
@@ -85,12 +97,15 @@ pub async fn get(
 
 				let current_entry = if let Some(inner) = &mut current_entry {
 					if inner.key != key {
-						let (key, value, meta) =
-							std::mem::replace(inner, EntryBuilder::new(key)).build()?;
+						let prev = std::mem::replace(inner, EntryBuilder::new(key));
 
-						keys.push(key);
-						values.push(value);
-						metadata.push(meta);
+						if !prev.is_expired(now) {
+							let (key, value, meta) = prev.build()?;
+
+							keys.push(key);
+							values.push(value);
+							metadata.push(meta);
+						}
 					}
 
 					inner
@@ -106,17 +121,23 @@ pub async fn get(
 					let value = metadata_key.deserialize(entry.value())?;
 
 					current_entry.append_metadata(value);
+				} else if let Ok(expire_ts_key) = tx.unpack::<EntryExpireTsKey>(&entry.key()) {
+					let value = expire_ts_key.deserialize(entry.value())?;
+
+					current_entry.append_expire_ts(value);
 				} else {
 					bail!("unexpected sub key");
 				}
 			}
 
 			if let Some(inner) = current_entry {
-				let (key, value, meta) = inner.build()?;
+				if !inner.is_expired(now) {
+					let (key, value, meta) = inner.build()?;
 
-				keys.push(key);
-				values.push(value);
-				metadata.push(meta);
+					keys.push(key);
+					values.push(value);
+					metadata.push(meta);
+				}
 			}
 
 			Ok((keys, values, metadata))
@@ -127,7 +148,10 @@ pub async fn get(
 	.map_err(Into::<anyhow::Error>::into)
 }
 
-/// Gets keys from the KV store.
+/// Lists keys from the KV store.
+///
+/// Returns a cursor along with the entries if the page was cut short by `limit` or `max_bytes`, which can
+/// be passed back to resume listing after the last returned key.
 #[tracing::instrument(skip_all)]
 pub async fn list(
 	db: &universaldb::Database,
@@ -135,12 +159,40 @@ pub async fn list(
 	query: rp::KvListQuery,
 	reverse: bool,
 	limit: Option<usize>,
-) -> Result<(Vec<rp::KvKey>, Vec<rp::KvValue>, Vec<rp::KvMetadata>)> {
+	cursor: Option<Vec<u8>>,
+	max_bytes: Option<usize>,
+) -> Result<(
+	Vec<rp::KvKey>,
+	Vec<rp::KvValue>,
+	Vec<rp::KvMetadata>,
+	Option<Vec<u8>>,
+)> {
 	utils::validate_list_query(&query)?;
 
 	let limit = limit.unwrap_or(16384);
 	let subspace = subspace(actor_id);
-	let list_range = list_query_range(query, &subspace);
+	let mut list_range = list_query_range(query, &subspace);
+
+	// Resume after the last key of the previous page
+	if let Some(cursor) = cursor {
+		let cursor = utils::ListCursor::deserialize(&cursor)?;
+		ensure!(
+			cursor.reverse == reverse,
+			"cursor direction does not match list direction"
+		);
+
+		let (last_key_start, last_key_end) =
+			subspace.subspace(&KeyWrapper(cursor.last_key)).range();
+		if reverse {
+			list_range.1 = list_range.1.min(last_key_start);
+		} else {
+			list_range.0 = list_range.0.max(last_key_end);
+		}
+
+		if list_range.0 >= list_range.1 {
+			return Ok((Vec::new(), Vec::new(), Vec::new(), None));
+		}
+	}
 
 	db.run(|tx| {
 		let list_range = list_range.clone();
@@ -148,6 +200,7 @@ pub async fn list(
 
 		async move {
 			let tx = tx.with_subspace(subspace);
+			let now = utils::now();
 
 			let mut stream = tx.get_ranges_keyvalues(
 				universaldb::RangeOption {
@@ -162,6 +215,8 @@ pub async fn list(
 			let mut values = Vec::new();
 			let mut metadata = Vec::new();
 			let mut current_entry: Option<EntryBuilder> = None;
+			let mut page_size = 0;
+			let mut truncated = false;
 
 			loop {
 				let Some(entry) = stream.try_next().await? else {
@@ -172,16 +227,25 @@ pub async fn list(
 
 				let curr = if let Some(inner) = &mut current_entry {
 					if inner.key != key {
-						let (key, value, meta) =
-							std::mem::replace(inner, EntryBuilder::new(key)).build()?;
+						let prev = std::mem::replace(inner, EntryBuilder::new(key));
 
-						keys.push(key);
-						values.push(value);
-						metadata.push(meta);
+						if !prev.is_expired(now) {
+							let (key, value, meta) = prev.build()?;
 
-						if keys.len() >= limit {
-							current_entry = None;
-							break;
+							page_size += key.len() + value.len();
+							keys.push(key);
+							values.push(value);
+							metadata.push(meta);
+
+							if keys.len() >= limit
+								|| max_bytes
+									.map(|max_bytes| page_size >= max_bytes)
+									.unwrap_or_default()
+							{
+								truncated = true;
+								current_entry = None;
+								break;
+							}
 						}
 					}
 
@@ -198,20 +262,40 @@ pub async fn list(
 					let value = metadata_key.deserialize(entry.value())?;
 
 					curr.append_metadata(value);
+				} else if let Ok(expire_ts_key) = tx.unpack::<EntryExpireTsKey>(&entry.key()) {
+					let value = expire_ts_key.deserialize(entry.value())?;
+
+					curr.append_expire_ts(value);
 				} else {
 					bail!("unexpected sub key");
 				}
 			}
 
 			if let Some(inner) = current_entry {
-				let (key, value, meta) = inner.build()?;
+				if !inner.is_expired(now) {
+					let (key, value, meta) = inner.build()?;
 
-				keys.push(key);
-				values.push(value);
-				metadata.push(meta);
+					keys.push(key);
+					values.push(value);
+					metadata.push(meta);
+				}
 			}
 
-			Ok((keys, values, metadata))
+			let cursor = if truncated {
+				keys.last()
+					.map(|last_key| {
+						utils::ListCursor {
+							last_key: last_key.clone(),
+							reverse,
+						}
+						.serialize()
+					})
+					.transpose()?
+			} else {
+				None
+			};
+
+			Ok((keys, values, metadata, cursor))
 		}
 	})
 	.custom_instrument(tracing::info_span!("kv_list_tx"))
@@ -223,71 +307,249 @@ pub async fn list(
 #[tracing::instrument(skip_all)]
 pub async fn put(
 	db: &universaldb::Database,
+	ups: &universalpubsub::PubSub,
 	actor_id: Id,
+	quota: &KvQuota,
 	keys: Vec<rp::KvKey>,
 	values: Vec<rp::KvValue>,
+	expire_ts: Option<Vec<Option<i64>>>,
 ) -> Result<()> {
 	let subspace = subspace(actor_id);
 	let total_size = get_subspace_size(&db, &subspace).await? as usize;
 
-	validate_entries(&keys, &values, total_size)?;
+	validate_entries(quota, &keys, &values, expire_ts.as_deref(), total_size)?;
 
-	db.run(|tx| {
-		// TODO: Costly clone
-		let keys = keys.clone();
-		let values = values.clone();
-		let subspace = subspace.clone();
+	// Transactions without writes have no versionstamp
+	if keys.is_empty() {
+		return Ok(());
+	}
 
-		async move {
-			let tx = tx.with_subspace(subspace.clone());
+	let expire_ts = expire_ts.unwrap_or_else(|| vec![None; keys.len()]);
+
+	let (versionstamp, create_ts) = db
+		.run(|tx| {
+			// TODO: Costly clone
+			let keys = keys.clone();
+			let values = values.clone();
+			let expire_ts = expire_ts.clone();
+			let subspace = subspace.clone();
+
+			async move {
+				let tx = tx.with_subspace(subspace.clone());
+				let create_ts = utils::now();
 
-			futures_util::stream::iter(keys.into_iter().zip(values.into_iter()))
-				.map(|(key, value)| {
+				futures_util::stream::iter(
+					keys.into_iter()
+						.zip(values.into_iter())
+						.zip(expire_ts.into_iter()),
+				)
+				.map(|((key, value), expire_ts)| {
 					let tx = tx.clone();
-					let key = KeyWrapper(key.clone());
 					let subspace = subspace.clone();
 
 					async move {
-						// Clear previous key data before setting
-						tx.clear_subspace_range(&subspace.subspace(&key));
-
-						// Set metadata
-						tx.write(
-							&EntryMetadataKey::new(key.clone()),
-							rp::KvMetadata {
-								version: VERSION.as_bytes().to_vec(),
-								create_ts: utils::now(),
-							},
-						)?;
+						write_entry(
+							&tx,
+							&subspace,
+							actor_id,
+							KeyWrapper(key),
+							&value,
+							create_ts,
+							expire_ts,
+						)
+					}
+				})
+				.buffer_unordered(32)
+				.try_collect::<Vec<_>>()
+				.await?;
+
+				Ok((tx.get_versionstamp(), create_ts))
+			}
+		})
+		.custom_instrument(tracing::info_span!("kv_put_tx"))
+		.await?;
+
+	let metadata = rp::KvMetadata {
+		version: entry_version(versionstamp).await?,
+		create_ts,
+	};
+
+	watch::publish(
+		ups,
+		actor_id,
+		ChangeEvent {
+			changes: keys
+				.into_iter()
+				.map(|key| rp::KvChange {
+					key,
+					metadata: Some(metadata.clone()),
+				})
+				.collect(),
+			cleared: false,
+		},
+	)
+	.await;
+
+	Ok(())
+}
+
+/// Evaluates all checks and, if every check passes, applies all ops in a single transaction.
+///
+/// Returns the index of the first failed check along with the current metadata of its key if any check
+/// fails, in which case no ops are applied.
+#[tracing::instrument(skip_all)]
+pub async fn txn(
+	db: &universaldb::Database,
+	ups: &universalpubsub::PubSub,
+	actor_id: Id,
+	quota: &KvQuota,
+	checks: Vec<rp::KvTxnCheck>,
+	ops: Vec<rp::KvTxnOp>,
+) -> Result<rp::KvTxnResponse> {
+	let subspace = subspace(actor_id);
+	let total_size = get_subspace_size(&db, &subspace).await? as usize;
+
+	validate_txn(quota, &checks, &ops, total_size)?;
 
-						// Set key data in chunks
-						for start in (0..value.len()).step_by(VALUE_CHUNK_SIZE) {
-							let idx = start / VALUE_CHUNK_SIZE;
-							let end = (start + VALUE_CHUNK_SIZE).min(value.len());
+	let res = db
+		.run(|tx| {
+			// TODO: Costly clone
+			let checks = checks.clone();
+			let ops = ops.clone();
+			let subspace = subspace.clone();
 
-							tx.set(
-								&subspace.pack(&EntryValueChunkKey::new(key.clone(), idx)),
-								&value.get(start..end).context("bad slice")?,
-							);
+			async move {
+				let tx = tx.with_subspace(subspace.clone());
+
+				// Evaluate checks in order. All reads are serializable so that any concurrent write to a checked
+				// key conflicts with this transaction, causing it to be retried.
+				for (idx, check) in checks.into_iter().enumerate() {
+					let (passed, metadata) = match check {
+						rp::KvTxnCheck::KvTxnCheckAbsent(check) => {
+							let metadata = read_entry_metadata(&tx, KeyWrapper(check.key)).await?;
+
+							(metadata.is_none(), metadata)
 						}
+						rp::KvTxnCheck::KvTxnCheckVersion(check) => {
+							let metadata = read_entry_metadata(&tx, KeyWrapper(check.key)).await?;
 
-						Ok(())
+							(
+								metadata
+									.as_ref()
+									.map(|metadata| metadata.version == check.version)
+									.unwrap_or_default(),
+								metadata,
+							)
+						}
+						rp::KvTxnCheck::KvTxnCheckValue(check) => {
+							match read_entry(&tx, &subspace, KeyWrapper(check.key)).await? {
+								Some((value, metadata)) => (value == check.value, Some(metadata)),
+								None => (false, None),
+							}
+						}
+					};
+
+					if !passed {
+						return Ok(Err(rp::KvTxnCheckFailed {
+							check_index: idx.try_into()?,
+							metadata,
+						}));
 					}
-				})
-				.buffer_unordered(32)
-				.try_collect()
-				.await
+				}
+
+				let create_ts = utils::now();
+
+				for op in ops {
+					match op {
+						rp::KvTxnOp::KvTxnPut(put) => {
+							write_entry(
+								&tx,
+								&subspace,
+								actor_id,
+								KeyWrapper(put.key),
+								&put.value,
+								create_ts,
+								put.expire_ts,
+							)?;
+						}
+						rp::KvTxnOp::KvTxnDelete(delete) => {
+							tx.clear_subspace_range(&subspace.subspace(&KeyWrapper(delete.key)));
+						}
+					}
+				}
+
+				Ok(Ok((tx.get_versionstamp(), create_ts)))
+			}
+		})
+		.custom_instrument(tracing::info_span!("kv_txn_tx"))
+		.await?;
+
+	let (versionstamp, create_ts) = match res {
+		Ok(res) => res,
+		Err(check_failed) => return Ok(rp::KvTxnResponse::KvTxnCheckFailed(check_failed)),
+	};
+
+	// Transactions without writes have no versionstamp
+	if ops.is_empty() {
+		return Ok(rp::KvTxnResponse::KvTxnCommitted(rp::KvTxnCommitted {
+			keys: Vec::new(),
+			metadata: Vec::new(),
+		}));
+	}
+
+	let meta = rp::KvMetadata {
+		version: entry_version(versionstamp).await?,
+		create_ts,
+	};
+	let mut keys = Vec::new();
+	let mut metadata = Vec::new();
+	let mut changes = Vec::new();
+
+	for op in ops {
+		match op {
+			rp::KvTxnOp::KvTxnPut(put) => {
+				changes.push(rp::KvChange {
+					key: put.key.clone(),
+					metadata: Some(meta.clone()),
+				});
+				keys.push(put.key);
+				metadata.push(meta.clone());
+			}
+			rp::KvTxnOp::KvTxnDelete(delete) => {
+				changes.push(rp::KvChange {
+					key: delete.key,
+					metadata: None,
+				});
+			}
 		}
-	})
-	.custom_instrument(tracing::info_span!("kv_put_tx"))
-	.await
-	.map_err(Into::into)
+	}
+
+	watch::publish(
+		ups,
+		actor_id,
+		ChangeEvent {
+			changes,
+			cleared: false,
+		},
+	)
+	.await;
+
+	Ok(rp::KvTxnResponse::KvTxnCommitted(rp::KvTxnCommitted {
+		keys,
+		metadata,
+	}))
 }
 
 /// Deletes keys from the KV store. Cannot be undone.
 #[tracing::instrument(skip_all)]
-pub async fn delete(db: &universaldb::Database, actor_id: Id, keys: Vec<rp::KvKey>) -> Result<()> {
-	validate_keys(&keys)?;
+pub async fn delete(
+	db: &universaldb::Database,
+	ups: &universalpubsub::PubSub,
+	actor_id: Id,
+	quota: &KvQuota,
+	keys: Vec<rp::KvKey>,
+) -> Result<()> {
+	validate_keys(quota, &keys)?;
 
 	db.run(|tx| {
 		let keys = keys.clone();
@@ -302,20 +564,539 @@ pub async fn delete(db: &universaldb::Database, actor_id: Id, keys: Vec<rp::KvKe
 		}
 	})
 	.custom_instrument(tracing::info_span!("kv_delete_tx"))
-	.await
-	.map_err(Into::into)
+	.await?;
+
+	watch::publish(
+		ups,
+		actor_id,
+		ChangeEvent {
+			changes: keys
+				.into_iter()
+				.map(|key| rp::KvChange {
+					key,
+					metadata: None,
+				})
+				.collect(),
+			cleared: false,
+		},
+	)
+	.await;
+
+	Ok(())
 }
 
 /// Deletes all keys from the KV store. Cannot be undone.
 #[tracing::instrument(skip_all)]
-pub async fn delete_all(db: &universaldb::Database, actor_id: Id) -> Result<()> {
+pub async fn delete_all(
+	db: &universaldb::Database,
+	ups: &universalpubsub::PubSub,
+	actor_id: Id,
+) -> Result<()> {
 	db.run(|tx| async move {
 		tx.clear_subspace_range(&subspace(actor_id));
 		Ok(())
 	})
 	.custom_instrument(tracing::info_span!("kv_delete_all_tx"))
+	.await?;
+
+	watch::publish(
+		ups,
+		actor_id,
+		ChangeEvent {
+			changes: Vec::new(),
+			cleared: true,
+		},
+	)
+	.await;
+
+	Ok(())
+}
+
+/// Streams every entry of the KV store for a portable snapshot. Expired entries are skipped.
+///
+/// Entries are read in batches over multiple transactions, so the snapshot is not a consistent point-in-time
+/// view if the KV store is written to concurrently.
+pub fn export(
+	db: universaldb::Database,
+	actor_id: Id,
+) -> impl Stream<Item = Result<snapshot::SnapshotEntry>> + Send + 'static {
+	let subspace = subspace(actor_id);
+	let (start, end) = subspace.range();
+
+	futures_util::stream::try_unfold(Some(start), move |start| {
+		let db = db.clone();
+		let subspace = subspace.clone();
+		let end = end.clone();
+
+		async move {
+			let Some(start) = start else {
+				return Ok::<_, Error>(None);
+			};
+
+			let (batch, next_start) = export_batch(&db, &subspace, start, end).await?;
+
+			Ok(Some((
+				futures_util::stream::iter(batch.into_iter().map(Ok)),
+				next_start,
+			)))
+		}
+	})
+	.try_flatten()
+}
+
+/// Reads a single batch of entries for `export`. Returns where the next batch starts if the batch is full.
+#[tracing::instrument(skip_all)]
+async fn export_batch(
+	db: &universaldb::Database,
+	subspace: &Subspace,
+	start: Vec<u8>,
+	end: Vec<u8>,
+) -> Result<(Vec<snapshot::SnapshotEntry>, Option<Vec<u8>>)> {
+	db.run(|tx| {
+		let subspace = subspace.clone();
+		let start = start.clone();
+		let end = end.clone();
+
+		async move {
+			let tx = tx.with_subspace(subspace.clone());
+			let now = utils::now();
+
+			let mut stream = tx.get_ranges_keyvalues(
+				universaldb::RangeOption {
+					mode: universaldb::options::StreamingMode::WantAll,
+					limit: Some(EXPORT_BATCH_SIZE),
+					..(start, end).into()
+				},
+				Snapshot,
+			);
+
+			let mut builders = Vec::<EntryBuilder>::new();
+			let mut count = 0;
+
+			while let Some(entry) = stream.try_next().await? {
+				count += 1;
+
+				let key = tx.unpack::<EntryBaseKey>(&entry.key())?.key;
+
+				if builders.last().map(|curr| curr.key != key).unwrap_or(true) {
+					builders.push(EntryBuilder::new(key));
+				}
+				let curr = builders.last_mut().expect("must be set");
+
+				if let Ok(chunk_key) = tx.unpack::<EntryValueChunkKey>(&entry.key()) {
+					curr.append_chunk(chunk_key.chunk, entry.value());
+				} else if let Ok(metadata_key) = tx.unpack::<EntryMetadataKey>(&entry.key()) {
+					let value = metadata_key.deserialize(entry.value())?;
+
+					curr.append_metadata(value);
+				} else if let Ok(expire_ts_key) = tx.unpack::<EntryExpireTsKey>(&entry.key()) {
+					let value = expire_ts_key.deserialize(entry.value())?;
+
+					curr.append_expire_ts(value);
+				} else {
+					bail!("unexpected sub key");
+				}
+			}
+
+			// The last entry may have been cut off by the limit, read it again in the next batch
+			let next_start = if count >= EXPORT_BATCH_SIZE {
+				let last = builders.pop().context("empty export batch")?;
+				ensure!(!builders.is_empty(), "entry too large to export");
+
+				Some(subspace.subspace(&last.key).range().0)
+			} else {
+				None
+			};
+
+			let entries = builders
+				.into_iter()
+				.filter(|builder| !builder.is_expired(now))
+				.map(|builder| {
+					let expire_ts = builder.expire_ts();
+					let (key, value, metadata) = builder.build()?;
+
+					Ok(snapshot::SnapshotEntry {
+						key,
+						value,
+						metadata,
+						expire_ts,
+					})
+				})
+				.collect::<Result<Vec<_>>>()?;
+
+			Ok((entries, next_start))
+		}
+	})
+	.custom_instrument(tracing::info_span!("kv_export_tx"))
 	.await
-	.map_err(Into::into)
+}
+
+/// Writes a stream of snapshot entries into the KV store, replacing existing entries with the same key. If
+/// `clear` is set, all existing entries are deleted first. Imported entries keep their create ts but get
+/// a new version. Returns the amount of imported entries.
+///
+/// Entries are written in batches over multiple transactions as they are read from the stream, so a failed
+/// import may be partially applied.
+#[tracing::instrument(skip_all)]
+pub async fn import(
+	db: &universaldb::Database,
+	ups: &universalpubsub::PubSub,
+	actor_id: Id,
+	quota: &KvQuota,
+	entries: impl Stream<Item = Result<snapshot::SnapshotEntry>>,
+	clear: bool,
+) -> Result<usize> {
+	if clear {
+		delete_all(db, ups, actor_id).await?;
+	}
+
+	let subspace = subspace(actor_id);
+	let now = utils::now();
+
+	// Skip entries that expired since the snapshot was taken
+	let mut entries = std::pin::pin!(entries.try_filter(|entry| {
+		std::future::ready(entry.expire_ts.map(|ts| ts > now).unwrap_or(true))
+	}));
+	let mut next = entries.try_next().await?;
+	let mut count = 0;
+
+	while next.is_some() {
+		// Split entries into batches that fit within the limits of a single put
+		let mut batch = Vec::new();
+		let mut payload_size = 0;
+		while let Some(entry) = next.take() {
+			let entry_size = KeyWrapper::tuple_len(&entry.key) + entry.value.len();
+
+			if !batch.is_empty()
+				&& (batch.len() >= quota.max_keys as usize
+					|| payload_size + entry_size > quota.max_put_payload_size as usize)
+			{
+				next = Some(entry);
+				break;
+			}
+
+			payload_size += entry_size;
+			batch.push(entry);
+			next = entries.try_next().await?;
+		}
+		count += batch.len();
+
+		let keys = batch
+			.iter()
+			.map(|entry| entry.key.clone())
+			.collect::<Vec<_>>();
+		let values = batch
+			.iter()
+			.map(|entry| entry.value.clone())
+			.collect::<Vec<_>>();
+		let expire_ts = batch
+			.iter()
+			.map(|entry| entry.expire_ts)
+			.collect::<Vec<_>>();
+
+		let total_size = get_subspace_size(&db, &subspace).await? as usize;
+		validate_entries(quota, &keys, &values, Some(&expire_ts), total_size)?;
+
+		let versionstamp = db
+			.run(|tx| {
+				let subspace = subspace.clone();
+				let batch = &batch;
+
+				async move {
+					let tx = tx.with_subspace(subspace.clone());
+
+					for entry in batch {
+						write_entry(
+							&tx,
+							&subspace,
+							actor_id,
+							KeyWrapper(entry.key.clone()),
+							&entry.value,
+							entry.metadata.create_ts,
+							entry.expire_ts,
+						)?;
+					}
+
+					Ok(tx.get_versionstamp())
+				}
+			})
+			.custom_instrument(tracing::info_span!("kv_import_tx"))
+			.await?;
+
+		let version = entry_version(versionstamp).await?;
+
+		watch::publish(
+			ups,
+			actor_id,
+			ChangeEvent {
+				changes: batch
+					.into_iter()
+					.map(|entry| rp::KvChange {
+						key: entry.key,
+						metadata: Some(rp::KvMetadata {
+							version: version.clone(),
+							create_ts: entry.metadata.create_ts,
+						}),
+					})
+					.collect(),
+				cleared: false,
+			},
+		)
+		.await;
+	}
+
+	Ok(count)
+}
+
+/// Reads a single entry from the given actor subspace.
+async fn read_entry(
+	tx: &universaldb::Transaction,
+	subspace: &Subspace,
+	key: KeyWrapper,
+) -> Result<Option<(rp::KvValue, rp::KvMetadata)>> {
+	let mut stream = tx.get_ranges_keyvalues(
+		universaldb::RangeOption {
+			mode: universaldb::options::StreamingMode::WantAll,
+			..subspace.subspace(&key).range().into()
+		},
+		Serializable,
+	);
+
+	let mut entry_builder = None;
+
+	loop {
+		let Some(entry) = stream.try_next().await? else {
+			break;
+		};
+
+		let curr = entry_builder.get_or_insert_with(|| EntryBuilder::new(key.clone()));
+
+		if let Ok(chunk_key) = tx.unpack::<EntryValueChunkKey>(&entry.key()) {
+			curr.append_chunk(chunk_key.chunk, entry.value());
+		} else if let Ok(metadata_key) = tx.unpack::<EntryMetadataKey>(&entry.key()) {
+			let value = metadata_key.deserialize(entry.value())?;
+
+			curr.append_metadata(value);
+		} else if let Ok(expire_ts_key) = tx.unpack::<EntryExpireTsKey>(&entry.key()) {
+			let value = expire_ts_key.deserialize(entry.value())?;
+
+			curr.append_expire_ts(value);
+		} else {
+			bail!("unexpected sub key");
+		}
+	}
+
+	entry_builder
+		.filter(|entry_builder| !entry_builder.is_expired(utils::now()))
+		.map(|entry_builder| {
+			let (_, value, metadata) = entry_builder.build()?;
+
+			Ok((value, metadata))
+		})
+		.transpose()
+}
+
+/// Reads the metadata of a single entry without reading its value. `tx` must have the actor subspace.
+async fn read_entry_metadata(
+	tx: &universaldb::Transaction,
+	key: KeyWrapper,
+) -> Result<Option<rp::KvMetadata>> {
+	let expire_ts_key = EntryExpireTsKey::new(key.clone());
+	let metadata_key = EntryMetadataKey::new(key);
+
+	let (expire_ts, metadata) = tokio::try_join!(
+		tx.read_opt(&expire_ts_key, Serializable),
+		tx.read_opt(&metadata_key, Serializable),
+	)?;
+
+	if expire_ts.map(|ts| ts <= utils::now()).unwrap_or_default() {
+		return Ok(None);
+	}
+
+	Ok(metadata)
+}
+
+/// Writes a single entry to the given actor subspace, replacing any existing value. The version of the entry
+/// is the versionstamp of the transaction, see `entry_version`.
+fn write_entry(
+	tx: &universaldb::Transaction,
+	subspace: &Subspace,
+	actor_id: Id,
+	key: KeyWrapper,
+	value: &[u8],
+	create_ts: i64,
+	expire_ts: Option<i64>,
+) -> Result<()> {
+	// Clear previous key data before setting
+	tx.clear_subspace_range(&subspace.subspace(&key));
+
+	// Set metadata. The version is filled in with the versionstamp of the transaction on commit.
+	let mut metadata = serde_bare::to_vec(&rp::KvMetadata {
+		version: Versionstamp::incomplete(0).as_bytes().to_vec(),
+		create_ts,
+	})?;
+	// Offset of the version, which is the first field and has a single byte length prefix
+	metadata.extend_from_slice(&1u32.to_le_bytes());
+	tx.atomic_op(
+		&EntryMetadataKey::new(key.clone()),
+		&metadata,
+		MutationType::SetVersionstampedValue,
+	);
+
+	if let Some(expire_ts) = expire_ts {
+		tx.write(&EntryExpireTsKey::new(key.clone()), expire_ts)?;
+
+		// Index the entry so it can be reclaimed by the sweeper. Stale index entries (from keys that were
+		// overwritten or deleted since) are ignored by the sweeper.
+		tx.set(
+			&pegboard::keys::actor_kv_expire_subspace().pack(&ExpireIdxKey::new(
+				expire_ts,
+				actor_id,
+				key.clone(),
+			)),
+			&[],
+		);
+	}
+
+	// Set key data in chunks
+	for start in (0..value.len()).step_by(VALUE_CHUNK_SIZE) {
+		let idx = start / VALUE_CHUNK_SIZE;
+		let end = (start + VALUE_CHUNK_SIZE).min(value.len());
+
+		tx.set(
+			&subspace.pack(&EntryValueChunkKey::new(key.clone(), idx)),
+			&value.get(start..end).context("bad slice")?,
+		);
+	}
+
+	Ok(())
+}
+
+/// Waits for the versionstamp of a committed transaction and returns it as the version of all entries it
+/// wrote. Every write to a key gets a new version, allowing clients to detect concurrent modifications with
+/// `KvTxnCheckVersion`.
+async fn entry_version(
+	versionstamp: impl Future<Output = Result<Versionstamp>>,
+) -> Result<Vec<u8>> {
+	let versionstamp = versionstamp
+		.await
+		.context("failed to get kv transaction versionstamp")?;
+
+	Ok(versionstamp.as_bytes().to_vec())
+}
+
+/// Periodically reclaims expired entries from all actors.
+#[tracing::instrument(skip_all)]
+pub async fn start(_config: rivet_config::Config, pools: rivet_pools::Pools) -> Result<()> {
+	let db = pools.udb()?;
+	let ups = pools.ups()?;
+
+	let mut interval = tokio::time::interval(EXPIRE_SWEEP_INTERVAL);
+	interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+	loop {
+		interval.tick().await;
+
+		match sweep_expired(&db, &ups).await {
+			Ok(0) => {}
+			Ok(count) => tracing::debug!(?count, "reclaimed expired kv entries"),
+			Err(err) => tracing::error!(?err, "failed to reclaim expired kv entries"),
+		}
+	}
+}
+
+/// Clears all entries that have expired. Returns the amount of entries reclaimed.
+///
+/// Storage used by the reclaimed entries is freed immediately, so it is no longer counted by
+/// `get_subspace_size`.
+#[tracing::instrument(skip_all)]
+pub async fn sweep_expired(
+	db: &universaldb::Database,
+	ups: &universalpubsub::PubSub,
+) -> Result<usize> {
+	let mut total = 0;
+
+	loop {
+		let now = utils::now();
+
+		let (reclaimed, more) = db
+			.run(|tx| async move {
+				let expire_subspace = pegboard::keys::actor_kv_expire_subspace();
+				let tx = tx.with_subspace(expire_subspace.clone());
+
+				let mut stream = tx.get_ranges_keyvalues(
+					universaldb::RangeOption {
+						mode: universaldb::options::StreamingMode::WantAll,
+						limit: Some(EXPIRE_SWEEP_BATCH_SIZE),
+						..(
+							expire_subspace.range().0,
+							// Includes all entries with an expire ts of `now`
+							expire_subspace.subspace(&now).range().1,
+						)
+							.into()
+					},
+					// Index keys are only ever cleared by the sweeper
+					Snapshot,
+				);
+
+				let mut count = 0;
+				let mut reclaimed = Vec::new();
+
+				while let Some(entry) = stream.try_next().await? {
+					let idx_key = tx.unpack::<ExpireIdxKey>(entry.key())?;
+					count += 1;
+
+					// Only clear the entry if it was not overwritten since it was indexed
+					let entry_tx = tx.with_subspace(subspace(idx_key.actor_id));
+					let expire_ts = entry_tx
+						.read_opt(&EntryExpireTsKey::new(idx_key.key.clone()), Serializable)
+						.await?;
+					if expire_ts == Some(idx_key.expire_ts) {
+						entry_tx.clear_subspace_range(
+							&subspace(idx_key.actor_id).subspace(&idx_key.key),
+						);
+						reclaimed.push((idx_key.actor_id, idx_key.key.0));
+					}
+
+					tx.clear(entry.key());
+				}
+
+				Ok((reclaimed, count >= EXPIRE_SWEEP_BATCH_SIZE))
+			})
+			.custom_instrument(tracing::info_span!("kv_sweep_expired_tx"))
+			.await?;
+
+		total += reclaimed.len();
+
+		// Notify watchers of each actor
+		let mut changes_by_actor = HashMap::<Id, Vec<rp::KvChange>>::new();
+		for (actor_id, key) in reclaimed {
+			changes_by_actor
+				.entry(actor_id)
+				.or_default()
+				.push(rp::KvChange {
+					key,
+					metadata: None,
+				});
+		}
+		for (actor_id, changes) in changes_by_actor {
+			watch::publish(
+				ups,
+				actor_id,
+				ChangeEvent {
+					changes,
+					cleared: false,
+				},
+			)
+			.await;
+		}
+
+		if !more {
+			break;
+		}
+	}
+
+	Ok(total)
 }
 
 fn list_query_range(query: rp::KvListQuery, subspace: &Subspace) -> (Vec<u8>, Vec<u8>) {
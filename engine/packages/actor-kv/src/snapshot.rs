@@ -0,0 +1,120 @@
+use std::{pin::Pin, result::Result::Ok};
+
+use anyhow::*;
+use futures_util::{Stream, StreamExt, TryStreamExt, stream};
+use rivet_runner_protocol as rp;
+use serde::{Deserialize, Serialize};
+
+/// Version of the snapshot encoding. Bump when changing `SnapshotEntry` or the framing.
+const SNAPSHOT_VERSION: u16 = 2;
+/// Size of the length prefix of every encoded entry.
+const ENTRY_LEN_SIZE: usize = 4;
+
+/// Single entry of a portable dump of an actor's KV, created by `export` and restored by `import`.
+///
+/// A snapshot is encoded as a 2 byte little endian version prefix followed by every entry as a 4 byte little
+/// endian length prefix and the BARE encoded entry, so it can be written and read one entry at a time.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+	pub key: rp::KvKey,
+	/// Entire value, reassembled from its chunks.
+	pub value: rp::KvValue,
+	pub metadata: rp::KvMetadata,
+	pub expire_ts: Option<i64>,
+}
+
+impl SnapshotEntry {
+	/// Size of the key and value.
+	pub fn size(&self) -> usize {
+		self.key.len() + self.value.len()
+	}
+}
+
+/// Encodes a stream of entries into a stream of snapshot bytes. The first item is the version prefix.
+pub fn encode<S>(entries: S) -> impl Stream<Item = Result<Vec<u8>>>
+where
+	S: Stream<Item = Result<SnapshotEntry>>,
+{
+	stream::once(async { Ok(SNAPSHOT_VERSION.to_le_bytes().to_vec()) }).chain(entries.map(
+		|entry| -> Result<Vec<u8>> {
+			let entry = serde_bare::to_vec(&entry?)?;
+			let len = u32::try_from(entry.len()).context("snapshot entry too large")?;
+
+			let mut buf = Vec::with_capacity(ENTRY_LEN_SIZE + entry.len());
+			buf.extend_from_slice(&len.to_le_bytes());
+			buf.extend_from_slice(&entry);
+
+			Ok(buf)
+		},
+	))
+}
+
+/// Decodes a stream of snapshot bytes into a stream of entries. Only a single entry is buffered at a time.
+///
+/// The version prefix is validated before returning, so an invalid snapshot fails before any entry is read.
+pub async fn decode<S, B>(
+	chunks: S,
+) -> Result<impl Stream<Item = Result<SnapshotEntry>> + Send + 'static>
+where
+	S: Stream<Item = Result<B>> + Send + 'static,
+	B: AsRef<[u8]> + Send + 'static,
+{
+	let mut decoder = Decoder {
+		chunks: Box::pin(chunks),
+		buf: Vec::new(),
+	};
+
+	ensure!(decoder.fill(2).await?, "snapshot too short");
+
+	let version = u16::from_le_bytes([decoder.buf[0], decoder.buf[1]]);
+	ensure!(
+		version == SNAPSHOT_VERSION,
+		"unsupported snapshot version: {version}"
+	);
+	decoder.buf.drain(..2);
+
+	Ok(stream::try_unfold(decoder, |mut decoder| async move {
+		Ok::<_, Error>(decoder.next_entry().await?.map(|entry| (entry, decoder)))
+	}))
+}
+
+struct Decoder<S> {
+	chunks: Pin<Box<S>>,
+	buf: Vec<u8>,
+}
+
+impl<S, B> Decoder<S>
+where
+	S: Stream<Item = Result<B>>,
+	B: AsRef<[u8]>,
+{
+	/// Reads chunks until at least `len` bytes are buffered. Returns false if the stream ended before that.
+	async fn fill(&mut self, len: usize) -> Result<bool> {
+		while self.buf.len() < len {
+			let Some(chunk) = self.chunks.try_next().await? else {
+				return Ok(false);
+			};
+
+			self.buf.extend_from_slice(chunk.as_ref());
+		}
+
+		Ok(true)
+	}
+
+	async fn next_entry(&mut self) -> Result<Option<SnapshotEntry>> {
+		if !self.fill(ENTRY_LEN_SIZE).await? {
+			ensure!(self.buf.is_empty(), "snapshot truncated");
+
+			return Ok(None);
+		}
+
+		let len = u32::from_le_bytes(self.buf[..ENTRY_LEN_SIZE].try_into()?) as usize;
+		ensure!(self.fill(ENTRY_LEN_SIZE + len).await?, "snapshot truncated");
+
+		let entry = serde_bare::from_slice(&self.buf[ENTRY_LEN_SIZE..ENTRY_LEN_SIZE + len])
+			.context("invalid snapshot entry")?;
+		self.buf.drain(..ENTRY_LEN_SIZE + len);
+
+		Ok(Some(entry))
+	}
+}
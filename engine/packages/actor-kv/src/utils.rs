@@ -1,11 +1,28 @@
-use std::result::Result::Ok;
+use std::{borrow::Borrow, result::Result::Ok};
 
 use anyhow::*;
 use rivet_runner_protocol as rp;
+use rivet_types::namespaces::KvQuota;
+use serde::{Deserialize, Serialize};
 
-use crate::{
-	MAX_KEY_SIZE, MAX_KEYS, MAX_PUT_PAYLOAD_SIZE, MAX_STORAGE_SIZE, MAX_VALUE_SIZE, key::KeyWrapper,
-};
+use crate::{MAX_KEY_SIZE, errors, key::KeyWrapper};
+
+/// Opaque cursor used to resume a paginated list.
+#[derive(Serialize, Deserialize)]
+pub struct ListCursor {
+	pub last_key: rp::KvKey,
+	pub reverse: bool,
+}
+
+impl ListCursor {
+	pub fn serialize(&self) -> Result<Vec<u8>> {
+		serde_bare::to_vec(self).map_err(Into::into)
+	}
+
+	pub fn deserialize(raw: &[u8]) -> Result<Self> {
+		serde_bare::from_slice(raw).context("invalid list cursor")
+	}
+}
 
 pub fn now() -> i64 {
 	std::time::SystemTime::now()
@@ -40,12 +57,12 @@ pub fn validate_list_query(query: &rp::KvListQuery) -> Result<()> {
 	Ok(())
 }
 
-pub fn validate_keys(keys: &[rp::KvKey]) -> Result<()> {
-	ensure!(keys.len() <= MAX_KEYS, "a maximum of 128 keys is allowed");
+pub fn validate_keys<K: Borrow<rp::KvKey>>(quota: &KvQuota, keys: &[K]) -> Result<()> {
+	validate_count(quota, keys.len())?;
 
 	for key in keys {
 		ensure!(
-			KeyWrapper::tuple_len(key) <= MAX_KEY_SIZE,
+			KeyWrapper::tuple_len(key.borrow()) <= MAX_KEY_SIZE,
 			"key is too long (max 2048 bytes)"
 		);
 	}
@@ -53,48 +70,125 @@ pub fn validate_keys(keys: &[rp::KvKey]) -> Result<()> {
 	Ok(())
 }
 
-pub fn validate_entries(
-	keys: &[rp::KvKey],
-	values: &[rp::KvValue],
+pub fn validate_entries<K: Borrow<rp::KvKey>, V: Borrow<rp::KvValue>>(
+	quota: &KvQuota,
+	keys: &[K],
+	values: &[V],
+	expire_ts: Option<&[Option<i64>]>,
 	total_size: usize,
 ) -> Result<()> {
 	ensure!(
 		keys.len() == values.len(),
 		"Keys list length != values list length"
 	);
-	ensure!(
-		keys.len() <= MAX_KEYS,
-		"A maximum of 128 key-value entries is allowed"
-	);
-	ensure!(
-		values.len() <= MAX_KEYS,
-		"A maximum of 128 key-value entries is allowed"
-	);
-	let payload_size = keys.iter().fold(0, |acc, k| acc + KeyWrapper::tuple_len(k))
-		+ values.iter().fold(0, |acc, v| acc + v.len());
-	ensure!(
-		payload_size <= MAX_PUT_PAYLOAD_SIZE,
-		"total payload is too large (max 976 KiB)"
-	);
-
-	let storage_remaining = MAX_STORAGE_SIZE.saturating_sub(total_size);
-	ensure!(
-		payload_size <= storage_remaining,
-		"not enough space left in storage ({storage_remaining} bytes remaining, current payload is {payload_size} bytes)"
-	);
+	if let Some(expire_ts) = expire_ts {
+		ensure!(
+			keys.len() == expire_ts.len(),
+			"Keys list length != expire ts list length"
+		);
+	}
+	validate_count(quota, keys.len())?;
+	let payload_size = keys
+		.iter()
+		.fold(0, |acc, k| acc + KeyWrapper::tuple_len(k.borrow()))
+		+ values.iter().fold(0, |acc, v| acc + v.borrow().len());
+	validate_payload_size(quota, payload_size, total_size)?;
 
 	for key in keys {
 		ensure!(
-			KeyWrapper::tuple_len(key) <= MAX_KEY_SIZE,
+			KeyWrapper::tuple_len(key.borrow()) <= MAX_KEY_SIZE,
 			"key is too long (max 2048 bytes)"
 		);
 	}
 
 	for value in values {
-		ensure!(
-			value.len() <= MAX_VALUE_SIZE,
-			"value is too large (max 128 KiB)"
-		);
+		validate_value_size(quota, value.borrow())?;
+	}
+
+	Ok(())
+}
+
+pub fn validate_txn(
+	quota: &KvQuota,
+	checks: &[rp::KvTxnCheck],
+	ops: &[rp::KvTxnOp],
+	total_size: usize,
+) -> Result<()> {
+	validate_count(quota, ops.len())?;
+
+	let mut check_keys = Vec::with_capacity(checks.len());
+	for check in checks {
+		match check {
+			rp::KvTxnCheck::KvTxnCheckAbsent(check) => check_keys.push(&check.key),
+			rp::KvTxnCheck::KvTxnCheckVersion(check) => check_keys.push(&check.key),
+			rp::KvTxnCheck::KvTxnCheckValue(check) => {
+				validate_value_size(quota, &check.value)?;
+				check_keys.push(&check.key);
+			}
+		}
+	}
+	validate_keys(quota, &check_keys)?;
+
+	let mut put_keys = Vec::new();
+	let mut put_values = Vec::new();
+	let mut delete_keys = Vec::new();
+	for op in ops {
+		match op {
+			rp::KvTxnOp::KvTxnPut(put) => {
+				put_keys.push(&put.key);
+				put_values.push(&put.value);
+			}
+			rp::KvTxnOp::KvTxnDelete(delete) => delete_keys.push(&delete.key),
+		}
+	}
+	validate_entries(quota, &put_keys, &put_values, None, total_size)?;
+	validate_keys(quota, &delete_keys)?;
+
+	Ok(())
+}
+
+fn validate_count(quota: &KvQuota, count: usize) -> Result<()> {
+	if count > quota.max_keys as usize {
+		return Err(errors::ActorKv::TooManyKeys {
+			limit: quota.max_keys,
+			count,
+		}
+		.build());
+	}
+
+	Ok(())
+}
+
+fn validate_value_size(quota: &KvQuota, value: &rp::KvValue) -> Result<()> {
+	if value.len() > quota.max_value_size as usize {
+		return Err(errors::ActorKv::ValueTooLarge {
+			limit: quota.max_value_size,
+			size: value.len(),
+		}
+		.build());
+	}
+
+	Ok(())
+}
+
+/// Validates the size of a single write and that it fits in the remaining storage.
+fn validate_payload_size(quota: &KvQuota, payload_size: usize, total_size: usize) -> Result<()> {
+	if payload_size > quota.max_put_payload_size as usize {
+		return Err(errors::ActorKv::PayloadTooLarge {
+			limit: quota.max_put_payload_size,
+			size: payload_size,
+		}
+		.build());
+	}
+
+	let storage_remaining = quota.max_storage_size.saturating_sub(total_size as u64);
+	if payload_size as u64 > storage_remaining {
+		return Err(errors::ActorKv::StorageQuotaExceeded {
+			limit: quota.max_storage_size,
+			usage: total_size as u64,
+			payload_size: payload_size as u64,
+		}
+		.build());
 	}
 
 	Ok(())
@@ -0,0 +1,73 @@
+use std::result::Result::Ok;
+
+use anyhow::*;
+use gas::prelude::*;
+use rivet_runner_protocol as rp;
+use serde::{Deserialize, Serialize};
+use universalpubsub::PublishOpts;
+
+/// Published to `ActorKvSubject` after every committed write to an actor's KV.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChangeEvent {
+	pub changes: Vec<rp::KvChange>,
+	/// Set if every key was deleted.
+	pub cleared: bool,
+}
+
+impl ChangeEvent {
+	pub fn serialize(&self) -> Result<Vec<u8>> {
+		serde_bare::to_vec(self).map_err(Into::into)
+	}
+
+	pub fn deserialize(raw: &[u8]) -> Result<Self> {
+		serde_bare::from_slice(raw).context("invalid kv change event")
+	}
+
+	/// Returns a copy of this event with only the changes relevant to the given watch. Returns `None` if
+	/// nothing relevant changed.
+	pub fn filter(&self, key: &[u8], prefix: bool) -> Option<ChangeEvent> {
+		let changes = self
+			.changes
+			.iter()
+			.filter(|change| {
+				if prefix {
+					change.key.starts_with(key)
+				} else {
+					change.key == key
+				}
+			})
+			.cloned()
+			.collect::<Vec<_>>();
+
+		if changes.is_empty() && !self.cleared {
+			return None;
+		}
+
+		Some(ChangeEvent {
+			changes,
+			cleared: self.cleared,
+		})
+	}
+}
+
+/// Notifies watchers of the given actor's KV. Errors are logged instead of returned because the changes
+/// have already been committed at this point.
+pub(crate) async fn publish(ups: &universalpubsub::PubSub, actor_id: Id, event: ChangeEvent) {
+	if event.changes.is_empty() && !event.cleared {
+		return;
+	}
+
+	let subject = pegboard::pubsub_subjects::ActorKvSubject::new(actor_id).to_string();
+
+	let res = match event.serialize() {
+		Ok(payload) => {
+			ups.publish(&subject, &payload, PublishOpts::broadcast())
+				.await
+		}
+		Err(err) => Err(err),
+	};
+
+	if let Err(err) = res {
+		tracing::warn!(?err, ?actor_id, "failed to publish kv change event");
+	}
+}
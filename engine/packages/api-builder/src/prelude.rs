@@ -6,7 +6,7 @@ pub use crate::errors::{ApiForbidden, ApiInternalError, ApiNotFound, ApiUnauthor
 
 // HTTP method handlers
 pub use crate::router::ApiRouter;
-pub use crate::wrappers::{bin, delete, get, patch, post, put};
+pub use crate::wrappers::{bin, delete, get, patch, post, put, stream};
 
 // Common types
 pub use anyhow::Result;
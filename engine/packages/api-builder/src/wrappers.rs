@@ -136,3 +136,55 @@ pub mod bin {
 	create_binary_method_wrapper!(put, axum_put, with_body);
 	create_binary_method_wrapper!(patch, axum_patch, with_body);
 }
+
+/// Wrappers for handlers that stream their request or response body, for bodies too large to buffer in
+/// memory.
+pub mod stream {
+	use axum::http::header;
+
+	use super::*;
+
+	/// Responds with the body returned by the handler as `application/octet-stream`.
+	pub fn get<P, Q, F, Fut>(handler: F) -> axum::routing::MethodRouter<crate::GlobalApiCtx>
+	where
+		P: DeserializeOwned + Send + 'static,
+		Q: DeserializeOwned + Send + 'static,
+		F: FnOnce(ApiCtx, P, Q) -> Fut + Clone + Send + Sync + 'static,
+		Fut: Future<Output = Result<axum::body::Body>> + Send,
+	{
+		axum_get(
+			move |Extension(ctx): Extension<ApiCtx>,
+			      Path(path): Path<P>,
+			      Query(query): Query<Q>| async move {
+				match handler(ctx, path, query).await {
+					Ok(body) => {
+						([(header::CONTENT_TYPE, "application/octet-stream")], body).into_response()
+					}
+					Err(err) => ApiError::from(err).into_response(),
+				}
+			},
+		)
+	}
+
+	/// Passes the raw request body to the handler without buffering it and responds with JSON.
+	pub fn post<P, Q, R, F, Fut>(handler: F) -> axum::routing::MethodRouter<crate::GlobalApiCtx>
+	where
+		P: DeserializeOwned + Send + 'static,
+		Q: DeserializeOwned + Send + 'static,
+		R: Serialize + Send + 'static,
+		F: FnOnce(ApiCtx, P, Q, axum::body::Body) -> Fut + Clone + Send + Sync + 'static,
+		Fut: Future<Output = Result<R>> + Send,
+	{
+		axum_post(
+			move |Extension(ctx): Extension<ApiCtx>,
+			      Path(path): Path<P>,
+			      Query(query): Query<Q>,
+			      body: axum::body::Body| async move {
+				match handler(ctx, path, query, body).await {
+					Ok(response) => Json(response).into_response(),
+					Err(err) => ApiError::from(err).into_response(),
+				}
+			},
+		)
+	}
+}
@@ -28,6 +28,9 @@ pub async fn create(
 			runner_name_selector: body.runner_name_selector,
 			input: body.input.clone(),
 			crash_policy: body.crash_policy,
+			restart_policy: body.restart_policy.unwrap_or_default(),
+			placement: body.placement.unwrap_or_default(),
+			resources: body.resources,
 			// NOTE: This can forward if the user attempts to create an actor with a target dc and this dc
 			// ends up forwarding to another.
 			forward_request: true,
@@ -67,6 +67,8 @@ pub async fn delete(ctx: ApiCtx, path: DeletePath, query: DeleteQuery) -> Result
 		.signal(pegboard::workflows::actor::Destroy {})
 		.to_workflow::<pegboard::workflows::actor::Workflow>()
 		.tag("actor_id", path.actor_id)
+		// Retried delete requests should not deliver more than one destroy signal
+		.idempotency_key("destroy")
 		.send()
 		.await;
 
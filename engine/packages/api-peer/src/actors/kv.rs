@@ -0,0 +1,146 @@
+use anyhow::Result;
+use axum::body::{Body, Bytes};
+use futures_util::{StreamExt, TryStreamExt};
+use gas::prelude::*;
+use pegboard_actor_kv::snapshot;
+use rivet_api_builder::ApiCtx;
+use rivet_util::Id;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Deserialize, Serialize, IntoParams)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct KvQuery {
+	pub namespace: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KvPath {
+	pub actor_id: Id,
+}
+
+#[utoipa::path(
+    get,
+	operation_id = "actors_kv_export",
+    path = "/actors/{actor_id}/kv/export",
+    params(
+        ("actor_id" = Id, Path),
+        KvQuery,
+    ),
+    responses(
+        (status = 200, description = "Snapshot of all entries in the actor's KV store", content_type = "application/octet-stream"),
+    ),
+)]
+#[tracing::instrument(skip_all)]
+pub async fn export(ctx: ApiCtx, path: KvPath, query: KvQuery) -> Result<Body> {
+	verify_actor(&ctx, path.actor_id, query.namespace).await?;
+
+	let entries = pegboard_actor_kv::export((*ctx.udb()?).clone(), path.actor_id);
+
+	// Entries are encoded as they are read, an error aborts the response body
+	Ok(Body::from_stream(snapshot::encode(entries).map(|chunk| {
+		chunk.map(Bytes::from).inspect_err(|err| {
+			tracing::warn!(?err, "failed to export actor kv");
+		})
+	})))
+}
+
+#[derive(Debug, Deserialize, Serialize, IntoParams)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct KvImportQuery {
+	pub namespace: Option<String>,
+	/// Deletes all existing entries before importing.
+	#[serde(default)]
+	pub clear: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+#[schema(as = ActorsKvImportResponse)]
+pub struct KvImportResponse {
+	/// Amount of imported entries. Entries that expired since the snapshot was taken are skipped.
+	pub count: usize,
+}
+
+#[utoipa::path(
+    post,
+	operation_id = "actors_kv_import",
+    path = "/actors/{actor_id}/kv/import",
+    params(
+        ("actor_id" = Id, Path),
+        KvImportQuery,
+    ),
+    request_body(description = "Snapshot from `actors_kv_export`", content_type = "application/octet-stream"),
+    responses(
+        (status = 200, body = KvImportResponse),
+    ),
+)]
+#[tracing::instrument(skip_all)]
+pub async fn import(
+	ctx: ApiCtx,
+	path: KvPath,
+	query: KvImportQuery,
+	body: Body,
+) -> Result<KvImportResponse> {
+	let actor = verify_actor(&ctx, path.actor_id, query.namespace).await?;
+
+	let namespace = ctx
+		.op(namespace::ops::get_global::Input {
+			namespace_ids: vec![actor.namespace_id],
+		})
+		.await?
+		.into_iter()
+		.next()
+		.ok_or_else(|| namespace::errors::Namespace::NotFound.build())?;
+
+	// Entries are decoded and written as the body is received
+	let entries = snapshot::decode(body.into_data_stream().map_err(anyhow::Error::from)).await?;
+
+	let count = pegboard_actor_kv::import(
+		&*ctx.udb()?,
+		&ctx.ups()?,
+		path.actor_id,
+		&namespace.kv_quota,
+		entries,
+		query.clear,
+	)
+	.await?;
+
+	Ok(KvImportResponse { count })
+}
+
+/// Verifies the actor exists and, if a namespace is provided, that the actor belongs to it.
+async fn verify_actor(
+	ctx: &ApiCtx,
+	actor_id: Id,
+	namespace_name: Option<String>,
+) -> Result<rivet_types::actors::Actor> {
+	let actors_res = ctx
+		.op(pegboard::ops::actor::get::Input {
+			actor_ids: vec![actor_id],
+		})
+		.await?;
+
+	let actor = actors_res
+		.actors
+		.into_iter()
+		.next()
+		.ok_or_else(|| pegboard::errors::Actor::NotFound.build())?;
+
+	if let Some(namespace_name) = namespace_name {
+		let namespace = ctx
+			.op(namespace::ops::resolve_for_name_global::Input {
+				name: namespace_name,
+			})
+			.await?
+			.ok_or_else(|| namespace::errors::Namespace::NotFound.build())?;
+
+		if actor.namespace_id != namespace.namespace_id {
+			return Err(pegboard::errors::Actor::NotFound.build());
+		}
+	}
+
+	Ok(actor)
+}
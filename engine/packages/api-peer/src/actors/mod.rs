@@ -1,4 +1,5 @@
 pub mod create;
 pub mod delete;
+pub mod kv;
 pub mod list;
 pub mod list_names;
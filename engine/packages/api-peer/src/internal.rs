@@ -1,7 +1,14 @@
-use anyhow::Result;
+use std::{
+	io::{self, BufWriter, Write},
+	path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use axum::body::{Body, Bytes};
 use gas::prelude::*;
 use rivet_api_builder::ApiCtx;
 use serde::{Deserialize, Serialize};
+use universaldb::driver::rocksdb::backup;
 use universalpubsub::PublishOpts;
 
 #[derive(Serialize, Deserialize)]
@@ -107,3 +114,153 @@ pub async fn epoxy_replica_reconfigure(
 
 	Ok(ReplicaReconfigureResponse {})
 }
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UdbCheckpointRequest {
+	/// Path to write the checkpoint to, relative to the backup directory (`database.file_system.backup_path`).
+	/// Must not exist yet.
+	pub path: PathBuf,
+	/// Writes a gzipped tarball to `path` instead of a checkpoint directory.
+	#[serde(default)]
+	pub tarball: bool,
+}
+
+#[derive(Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct UdbCheckpointResponse {
+	pub path: PathBuf,
+	pub key_count: u64,
+	pub size_bytes: u64,
+}
+
+/// Creates a consistent checkpoint of the database while the engine is running. Only supported by the file
+/// system database.
+#[tracing::instrument(skip_all)]
+pub async fn udb_checkpoint(
+	ctx: ApiCtx,
+	_path: (),
+	_query: (),
+	body: UdbCheckpointRequest,
+) -> Result<UdbCheckpointResponse> {
+	let udb = ctx.udb()?;
+	let path = backup_path(&ctx, &body.path)?;
+
+	let info = if body.tarball {
+		if path.exists() {
+			bail!("path already exists: {}", body.path.display());
+		}
+
+		let parent = path.parent().context("invalid checkpoint path")?;
+		std::fs::create_dir_all(parent).context("failed to create backup directory")?;
+
+		// Create the checkpoint next to the tarball so it ends up on the same file system
+		let temp_dir = tempfile::tempdir_in(parent)
+			.context("failed to create temporary checkpoint directory")?;
+		let checkpoint_path = temp_dir.path().join("checkpoint");
+
+		udb.checkpoint(&checkpoint_path).await?;
+
+		let tarball_path = path.clone();
+		tokio::task::spawn_blocking(move || {
+			let info = backup::validate_checkpoint(&checkpoint_path)?;
+
+			let file = std::fs::File::create(&tarball_path)
+				.with_context(|| format!("failed to create {}", tarball_path.display()))?;
+			backup::write_tarball(&checkpoint_path, file)?;
+
+			// Keep the temporary directory alive until the tarball is written
+			drop(temp_dir);
+
+			anyhow::Ok(info)
+		})
+		.await??
+	} else {
+		udb.checkpoint(&path).await?;
+
+		let checkpoint_path = path.clone();
+		tokio::task::spawn_blocking(move || backup::validate_checkpoint(&checkpoint_path)).await??
+	};
+
+	Ok(UdbCheckpointResponse {
+		path,
+		key_count: info.key_count,
+		size_bytes: info.size_bytes,
+	})
+}
+
+/// Creates a checkpoint and responds with it as a gzipped tarball. The tarball is streamed while it is
+/// written.
+#[tracing::instrument(skip_all)]
+pub async fn udb_checkpoint_tarball(ctx: ApiCtx, _path: (), _query: ()) -> Result<Body> {
+	let udb = ctx.udb()?;
+
+	let temp_dir =
+		tempfile::tempdir().context("failed to create temporary checkpoint directory")?;
+	let checkpoint_path = temp_dir.path().join("checkpoint");
+
+	udb.checkpoint(&checkpoint_path).await?;
+
+	// Validate before responding so a corrupt checkpoint results in an error response
+	let validate_path = checkpoint_path.clone();
+	tokio::task::spawn_blocking(move || backup::validate_checkpoint(&validate_path)).await??;
+
+	let (tx, mut rx) = tokio::sync::mpsc::channel(TARBALL_CHANNEL_SIZE);
+
+	tokio::task::spawn_blocking(move || {
+		let writer = BufWriter::with_capacity(TARBALL_CHUNK_SIZE, ChannelWriter(tx.clone()));
+
+		if let Err(err) = backup::write_tarball(&checkpoint_path, writer) {
+			tracing::warn!(?err, "failed to write checkpoint tarball");
+
+			// Aborts the response body
+			let _ = tx.blocking_send(Err(io::Error::other(format!("{err:#}"))));
+		}
+
+		// Keep the temporary directory alive until the tarball is written
+		drop(temp_dir);
+	});
+
+	Ok(Body::from_stream(futures_util::stream::poll_fn(
+		move |cx| rx.poll_recv(cx),
+	)))
+}
+
+const TARBALL_CHUNK_SIZE: usize = 64 * 1024;
+const TARBALL_CHANNEL_SIZE: usize = 16;
+
+/// Sends everything written to it to the response body.
+struct ChannelWriter(tokio::sync::mpsc::Sender<io::Result<Bytes>>);
+
+impl Write for ChannelWriter {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.0
+			.blocking_send(Ok(Bytes::copy_from_slice(buf)))
+			.map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "response body dropped"))?;
+
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+/// Resolves a checkpoint path relative to the configured backup directory. Absolute paths and paths leaving
+/// the backup directory are rejected.
+fn backup_path(ctx: &ApiCtx, path: &Path) -> Result<PathBuf> {
+	let rivet_config::config::Database::FileSystem(fs) = ctx.config().database() else {
+		bail!("checkpoints are only supported by the file system database");
+	};
+
+	ensure!(
+		path.components().next().is_some()
+			&& path
+				.components()
+				.all(|component| matches!(component, Component::Normal(_))),
+		"checkpoint path must be relative to the backup directory and must not contain `..`: {}",
+		path.display()
+	);
+
+	Ok(fs.backup_path().join(path))
+}
@@ -120,3 +120,51 @@ pub async fn create(
 
 	Ok(CreateResponse { namespace })
 }
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateKvQuotaPath {
+	pub namespace: String,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+#[schema(as = NamespacesUpdateKvQuotaRequest)]
+pub struct UpdateKvQuotaRequest {
+	pub kv_quota: rivet_types::namespaces::KvQuota,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+#[schema(as = NamespacesUpdateKvQuotaResponse)]
+pub struct UpdateKvQuotaResponse {
+	pub namespace: rivet_types::namespaces::Namespace,
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn update_kv_quota(
+	ctx: ApiCtx,
+	path: UpdateKvQuotaPath,
+	_query: (),
+	body: UpdateKvQuotaRequest,
+) -> Result<UpdateKvQuotaResponse> {
+	let namespace = ctx
+		.op(namespace::ops::resolve_for_name_local::Input {
+			name: path.namespace,
+		})
+		.await?
+		.ok_or_else(|| namespace::errors::Namespace::NotFound.build())?;
+
+	ctx.op(namespace::ops::kv_quota::upsert::Input {
+		namespace_id: namespace.namespace_id,
+		kv_quota: body.kv_quota.clone(),
+	})
+	.await?;
+
+	Ok(UpdateKvQuotaResponse {
+		namespace: rivet_types::namespaces::Namespace {
+			kv_quota: body.kv_quota,
+			..namespace
+		},
+	})
+}
@@ -14,6 +14,10 @@ pub async fn router(
 			// MARK: Namespaces
 			.route("/namespaces", get(namespaces::list))
 			.route("/namespaces", post(namespaces::create))
+			.route(
+				"/namespaces/{namespace}/kv-quota",
+				put(namespaces::update_kv_quota),
+			)
 			// MARK: Runner configs
 			.route("/runner-configs", get(runner_configs::list))
 			.route("/runner-configs/{runner_name}", put(runner_configs::upsert))
@@ -26,6 +30,14 @@ pub async fn router(
 			.route("/actors", post(actors::create::create))
 			.route("/actors/{actor_id}", delete(actors::delete::delete))
 			.route("/actors/names", get(actors::list_names::list_names))
+			.route(
+				"/actors/{actor_id}/kv/export",
+				stream::get(actors::kv::export),
+			)
+			.route(
+				"/actors/{actor_id}/kv/import",
+				stream::post(actors::kv::import),
+			)
 			// MARK: Runners
 			.route("/runners", get(runners::list))
 			.route("/runners/names", get(runners::list_names))
@@ -40,6 +52,11 @@ pub async fn router(
 				post(internal::epoxy_replica_reconfigure),
 			)
 			.route("/debug/tracing/config", put(internal::set_tracing_config))
+			.route("/udb/checkpoint", post(internal::udb_checkpoint))
+			.route(
+				"/udb/checkpoint/tarball",
+				stream::get(internal::udb_checkpoint_tarball),
+			)
 	})
 	.await
 }
@@ -7,7 +7,7 @@ use rivet_api_builder::{
 	ApiError,
 	extract::{Extension, Json, Query},
 };
-use rivet_types::actors::CrashPolicy;
+use rivet_types::actors::{CrashPolicy, PlacementConstraints, Resources, RestartPolicy};
 use rivet_util::Id;
 use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, ToSchema};
@@ -32,6 +32,12 @@ pub struct GetOrCreateRequest {
 	pub input: Option<String>,
 	pub runner_name_selector: String,
 	pub crash_policy: CrashPolicy,
+	#[serde(default)]
+	pub restart_policy: Option<RestartPolicy>,
+	#[serde(default)]
+	pub placement: Option<PlacementConstraints>,
+	#[serde(default)]
+	pub resources: Option<Resources>,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -143,6 +149,9 @@ async fn get_or_create_inner(
 			runner_name_selector: body.runner_name_selector,
 			input: body.input.clone(),
 			crash_policy: body.crash_policy,
+			restart_policy: body.restart_policy.unwrap_or_default(),
+			placement: body.placement.unwrap_or_default(),
+			resources: body.resources,
 			forward_request: true,
 			datacenter_name: body.datacenter.clone(),
 		})
@@ -2,7 +2,7 @@ use anyhow::Result;
 use axum::response::{IntoResponse, Response};
 use rivet_api_builder::{
 	ApiError,
-	extract::{Extension, Json, Query},
+	extract::{Extension, Json, Path, Query},
 };
 use rivet_api_peer::namespaces::*;
 use rivet_api_types::namespaces::list::*;
@@ -87,3 +87,52 @@ async fn create_inner(ctx: ApiCtx, body: CreateRequest) -> Result<CreateResponse
 		.await
 	}
 }
+
+#[utoipa::path(
+    put,
+	operation_id = "namespaces_update_kv_quota",
+    path = "/namespaces/{namespace}/kv-quota",
+    params(
+        ("namespace" = String, Path),
+    ),
+	request_body(content = UpdateKvQuotaRequest, content_type = "application/json"),
+    responses(
+        (status = 200, body = UpdateKvQuotaResponse),
+    ),
+	security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip_all)]
+pub async fn update_kv_quota(
+	Extension(ctx): Extension<ApiCtx>,
+	Path(path): Path<UpdateKvQuotaPath>,
+	Json(body): Json<UpdateKvQuotaRequest>,
+) -> Response {
+	match update_kv_quota_inner(ctx, path, body).await {
+		Ok(response) => Json(response).into_response(),
+		Err(err) => ApiError::from(err).into_response(),
+	}
+}
+
+#[tracing::instrument(skip_all)]
+async fn update_kv_quota_inner(
+	ctx: ApiCtx,
+	path: UpdateKvQuotaPath,
+	body: UpdateKvQuotaRequest,
+) -> Result<UpdateKvQuotaResponse> {
+	ctx.auth().await?;
+
+	if ctx.config().is_leader() {
+		rivet_api_peer::namespaces::update_kv_quota(ctx.into(), path, (), body).await
+	} else {
+		let leader_dc = ctx.config().leader_dc()?;
+		request_remote_datacenter::<UpdateKvQuotaResponse>(
+			ctx.config(),
+			leader_dc.datacenter_label,
+			&format!("/namespaces/{}/kv-quota", path.namespace),
+			axum::http::Method::PUT,
+			Option::<&()>::None,
+			Some(&body),
+		)
+		.await
+	}
+}
@@ -22,6 +22,7 @@ use crate::{actors, ctx, datacenters, health, metadata, namespaces, runner_confi
 		runners::list_names,
 		namespaces::list,
 		namespaces::create,
+		namespaces::update_kv_quota,
 		runner_configs::list::list,
 		runner_configs::upsert::upsert,
 		runner_configs::delete::delete,
@@ -56,6 +57,10 @@ pub async fn router(
 			// MARK: Namespaces
 			.route("/namespaces", axum::routing::get(namespaces::list))
 			.route("/namespaces", axum::routing::post(namespaces::create))
+			.route(
+				"/namespaces/{namespace}/kv-quota",
+				axum::routing::put(namespaces::update_kv_quota),
+			)
 			.route("/runner-configs", axum::routing::get(runner_configs::list))
 			.route(
 				"/runner-configs/serverless-health-check",
@@ -19,6 +19,12 @@ pub struct CreateRequest {
 	pub input: Option<String>,
 	pub runner_name_selector: String,
 	pub crash_policy: rivet_types::actors::CrashPolicy,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub restart_policy: Option<rivet_types::actors::RestartPolicy>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub placement: Option<rivet_types::actors::PlacementConstraints>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub resources: Option<rivet_types::actors::Resources>,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
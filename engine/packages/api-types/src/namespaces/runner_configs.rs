@@ -9,6 +9,8 @@ pub struct RunnerConfig {
 	pub kind: RunnerConfigKind,
 	#[serde(default, skip_serializing_if = "Option::is_none")]
 	pub metadata: Option<serde_json::Value>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub allocation_strategy: Option<rivet_types::runner_configs::AllocationStrategy>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -29,7 +31,11 @@ pub enum RunnerConfigKind {
 
 impl Into<rivet_types::runner_configs::RunnerConfig> for RunnerConfig {
 	fn into(self) -> rivet_types::runner_configs::RunnerConfig {
-		let RunnerConfig { kind, metadata } = self;
+		let RunnerConfig {
+			kind,
+			metadata,
+			allocation_strategy,
+		} = self;
 		let kind = match kind {
 			RunnerConfigKind::Normal {} => rivet_types::runner_configs::RunnerConfigKind::Normal {},
 			RunnerConfigKind::Serverless {
@@ -50,6 +56,10 @@ impl Into<rivet_types::runner_configs::RunnerConfig> for RunnerConfig {
 				runners_margin: runners_margin.unwrap_or_default(),
 			},
 		};
-		rivet_types::runner_configs::RunnerConfig { kind, metadata }
+		rivet_types::runner_configs::RunnerConfig {
+			kind,
+			metadata,
+			allocation_strategy: allocation_strategy.unwrap_or_default(),
+		}
 	}
 }
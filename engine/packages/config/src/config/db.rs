@@ -10,6 +10,8 @@ use crate::secret::Secret;
 pub enum Database {
 	Postgres(Postgres),
 	FileSystem(FileSystem),
+	/// Requires the engine to be built with the `fdb` feature.
+	FoundationDb(FoundationDb),
 }
 
 impl Default for Database {
@@ -39,6 +41,17 @@ impl Default for Postgres {
 #[serde(deny_unknown_fields)]
 pub struct FileSystem {
 	pub path: PathBuf,
+	/// Directory that checkpoints created through the API are written to. Defaults to a `backups` directory
+	/// next to `path`.
+	pub backup_path: Option<PathBuf>,
+}
+
+impl FileSystem {
+	pub fn backup_path(&self) -> PathBuf {
+		self.backup_path
+			.clone()
+			.unwrap_or_else(|| self.path.with_file_name("backups"))
+	}
 }
 
 impl Default for FileSystem {
@@ -47,6 +60,18 @@ impl Default for FileSystem {
 			.map(|dir| dir.join("rivet-engine").join("db"))
 			.unwrap_or_else(|| PathBuf::from("./data/db"));
 
-		Self { path: default_path }
+		Self {
+			path: default_path,
+			backup_path: None,
+		}
 	}
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FoundationDb {
+	/// Path to the FoundationDB cluster file. Uses the default cluster file if not set.
+	///
+	/// See: https://apple.github.io/foundationdb/administration.html#default-cluster-file
+	pub cluster_file_path: Option<PathBuf>,
+}
@@ -15,6 +15,7 @@ pub mod pubsub;
 pub mod telemetry;
 pub mod topology;
 pub mod vector;
+pub mod workflows;
 
 pub use api_peer::*;
 pub use api_public::*;
@@ -28,6 +29,7 @@ pub use pubsub::PubSub;
 pub use telemetry::*;
 pub use topology::*;
 pub use vector::*;
+pub use workflows::*;
 
 // IMPORTANT:
 //
@@ -93,6 +95,9 @@ pub struct Root {
 	#[serde(default)]
 	pub vector_http: Option<VectorHttp>,
 
+	#[serde(default)]
+	pub workflows: Option<Workflows>,
+
 	#[serde(default)]
 	pub telemetry: Telemetry,
 }
@@ -111,6 +116,7 @@ impl Default for Root {
 			cache: None,
 			clickhouse: None,
 			vector_http: None,
+			workflows: None,
 			telemetry: Default::default(),
 		}
 	}
@@ -165,6 +171,11 @@ impl Root {
 		self.vector_http.as_ref()
 	}
 
+	pub fn workflows(&self) -> &Workflows {
+		static DEFAULT: LazyLock<Workflows> = LazyLock::new(Workflows::default);
+		self.workflows.as_ref().unwrap_or(&DEFAULT)
+	}
+
 	pub fn validate_and_set_defaults(&mut self) -> Result<()> {
 		// Set default pubsub to Postgres if configured for database
 		if self.pubsub.is_none()
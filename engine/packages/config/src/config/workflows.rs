@@ -0,0 +1,87 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Name of the default worker pool in `run_worker_pools`.
+pub const DEFAULT_WORKER_POOL: &str = "default";
+
+/// Configuration for the workflow engine.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Workflows {
+	/// How long to keep workflows after they complete, are cancelled, or are terminated before deleting
+	/// them along with their history and signals. Workflows are kept forever if not set.
+	pub retention_ms: Option<i64>,
+	/// Per workflow name overrides of `retention_ms`. A negative value keeps workflows of that name forever.
+	#[serde(default)]
+	pub retention_ms_by_workflow: HashMap<String, i64>,
+	/// Max workflows a single worker runs at once. Unlimited if not set.
+	pub max_concurrent_workflows: Option<usize>,
+	/// Per workflow name limits of how many workflows of that name a single worker runs at once.
+	#[serde(default)]
+	pub max_concurrent_workflows_by_workflow: HashMap<String, usize>,
+	/// Per workflow name pull priority. When a worker is close to `max_concurrent_workflows`, workflows with a
+	/// higher priority are pulled first. Defaults to 0.
+	#[serde(default)]
+	pub priority_by_workflow: HashMap<String, i32>,
+	/// Separate worker pools that only pull the given workflow names. Workflow names assigned to a pool are not
+	/// pulled by the default worker.
+	#[serde(default)]
+	pub worker_pools: HashMap<String, WorkerPool>,
+	/// Worker pools started by this process. `default` is the default worker, which pulls all workflows not
+	/// assigned to a pool. Starts the default worker and every pool in `worker_pools` if not set.
+	pub run_worker_pools: Option<Vec<String>>,
+}
+
+impl Workflows {
+	/// Returns the retention of the given workflow name, or `None` if workflows of that name should not be
+	/// deleted.
+	pub fn retention_ms(&self, workflow_name: &str) -> Option<i64> {
+		match self.retention_ms_by_workflow.get(workflow_name) {
+			Some(retention_ms) if *retention_ms < 0 => None,
+			Some(retention_ms) => Some(*retention_ms),
+			None => self.retention_ms,
+		}
+	}
+
+	pub fn max_concurrent_workflows(&self, workflow_name: &str) -> Option<usize> {
+		self.max_concurrent_workflows_by_workflow
+			.get(workflow_name)
+			.copied()
+	}
+
+	pub fn priority(&self, workflow_name: &str) -> i32 {
+		self.priority_by_workflow
+			.get(workflow_name)
+			.copied()
+			.unwrap_or_default()
+	}
+
+	/// Returns the names of the worker pools this process starts.
+	pub fn run_worker_pools(&self) -> Vec<&str> {
+		if let Some(run_worker_pools) = &self.run_worker_pools {
+			run_worker_pools.iter().map(|x| x.as_str()).collect()
+		} else {
+			std::iter::once(DEFAULT_WORKER_POOL)
+				.chain(self.worker_pools.keys().map(|x| x.as_str()))
+				.collect()
+		}
+	}
+
+	/// Returns the name of the worker pool the given workflow name is assigned to.
+	pub fn worker_pool(&self, workflow_name: &str) -> Option<&str> {
+		self.worker_pools
+			.iter()
+			.find(|(_, pool)| pool.workflows.iter().any(|x| x == workflow_name))
+			.map(|(pool_name, _)| pool_name.as_str())
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct WorkerPool {
+	/// Workflow names pulled by this pool.
+	pub workflows: Vec<String>,
+	/// Max workflows a worker of this pool runs at once. Overrides `max_concurrent_workflows`.
+	pub max_concurrent_workflows: Option<usize>,
+}
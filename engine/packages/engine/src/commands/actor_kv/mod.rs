@@ -0,0 +1,155 @@
+use std::path::PathBuf;
+
+use anyhow::*;
+use clap::Parser;
+use futures_util::TryStreamExt;
+use gas::prelude::{StandaloneCtx, db};
+use pegboard_actor_kv::snapshot;
+use rivet_util::Id;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Size of the chunks a snapshot file is read in.
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Parser)]
+pub enum SubCommand {
+	/// Dumps all entries of an actor's KV store to a file.
+	Export {
+		#[clap(index = 1)]
+		actor_id: Id,
+		/// File to write the snapshot to.
+		#[clap(index = 2)]
+		path: PathBuf,
+	},
+	/// Writes all entries from a snapshot file into an actor's KV store. The actor does not have to be the
+	/// one the snapshot was exported from.
+	Import {
+		#[clap(index = 1)]
+		actor_id: Id,
+		/// File to read the snapshot from.
+		#[clap(index = 2)]
+		path: PathBuf,
+		/// Deletes all existing entries before importing.
+		#[clap(long)]
+		clear: bool,
+	},
+}
+
+impl SubCommand {
+	pub async fn execute(self, config: rivet_config::Config) -> Result<()> {
+		let pools = rivet_pools::Pools::new(config.clone()).await?;
+		let db = pools.udb()?;
+
+		match self {
+			Self::Export { actor_id, path } => {
+				let file = tokio::fs::File::create(&path)
+					.await
+					.with_context(|| format!("failed to create {}", path.display()))?;
+				let mut writer = tokio::io::BufWriter::new(file);
+
+				let mut count = 0;
+				let mut size = 0;
+				{
+					let entries =
+						pegboard_actor_kv::export((*db).clone(), actor_id).inspect_ok(|entry| {
+							count += 1;
+							size += entry.size();
+						});
+					let mut chunks = std::pin::pin!(snapshot::encode(entries));
+
+					while let Some(chunk) = chunks.try_next().await? {
+						writer.write_all(&chunk).await.with_context(|| {
+							format!("failed to write snapshot to {}", path.display())
+						})?;
+					}
+				}
+				writer
+					.flush()
+					.await
+					.with_context(|| format!("failed to write snapshot to {}", path.display()))?;
+
+				println!(
+					"Exported {count} entries ({size} bytes) to {}",
+					path.display()
+				);
+
+				Ok(())
+			}
+			Self::Import {
+				actor_id,
+				path,
+				clear,
+			} => {
+				let file = tokio::fs::File::open(&path)
+					.await
+					.with_context(|| format!("failed to read snapshot from {}", path.display()))?;
+				let chunks = futures_util::stream::try_unfold(file, |mut file| async move {
+					let mut buf = vec![0; READ_CHUNK_SIZE];
+					let len = file.read(&mut buf).await?;
+					if len == 0 {
+						return Ok(None);
+					}
+					buf.truncate(len);
+
+					Ok(Some((buf, file)))
+				});
+				let entries = snapshot::decode(chunks).await?;
+
+				let kv_quota = get_kv_quota(config, pools.clone(), actor_id).await?;
+
+				let count = pegboard_actor_kv::import(
+					&db,
+					&pools.ups()?,
+					actor_id,
+					&kv_quota,
+					entries,
+					clear,
+				)
+				.await?;
+
+				println!("Imported {count} entries into actor {actor_id}");
+
+				Ok(())
+			}
+		}
+	}
+}
+
+/// Looks up the KV quota of the namespace the given actor belongs to.
+async fn get_kv_quota(
+	config: rivet_config::Config,
+	pools: rivet_pools::Pools,
+	actor_id: Id,
+) -> Result<rivet_types::namespaces::KvQuota> {
+	let cache = rivet_cache::CacheInner::from_env(&config, pools.clone())?;
+	let ctx = StandaloneCtx::new(
+		db::DatabaseKv::from_pools(pools.clone()).await?,
+		config.clone(),
+		pools,
+		cache,
+		"actor_kv",
+		Id::new_v1(config.dc_label()),
+		Id::new_v1(config.dc_label()),
+	)?;
+
+	let actor = ctx
+		.op(pegboard::ops::actor::get::Input {
+			actor_ids: vec![actor_id],
+		})
+		.await?
+		.actors
+		.into_iter()
+		.next()
+		.with_context(|| format!("actor not found: {actor_id}"))?;
+
+	let namespace = ctx
+		.op(namespace::ops::get_global::Input {
+			namespace_ids: vec![actor.namespace_id],
+		})
+		.await?
+		.into_iter()
+		.next()
+		.with_context(|| format!("namespace not found: {}", actor.namespace_id))?;
+
+	Ok(namespace.kv_quota)
+}
@@ -1,5 +1,10 @@
+use std::path::{Path, PathBuf};
+
 use anyhow::*;
 use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use universaldb::driver::rocksdb::backup;
 
 #[derive(Parser)]
 pub enum SubCommand {
@@ -12,6 +17,42 @@ pub enum SubCommand {
 		#[clap(short = 'q', long)]
 		query: Option<String>,
 	},
+	/// Creates a consistent checkpoint of a running engine's file system database.
+	Backup {
+		/// Path to write the checkpoint to, relative to the engine's backup directory
+		/// (`database.file_system.backup_path`). Local path to write the tarball to with `--download`. Must not
+		/// exist yet.
+		#[clap(index = 1)]
+		path: PathBuf,
+		/// Writes a gzipped tarball instead of a checkpoint directory.
+		#[clap(long)]
+		tarball: bool,
+		/// Downloads the tarball from the engine instead of writing it on the engine's host.
+		#[clap(long, requires = "tarball")]
+		download: bool,
+		/// API peer endpoint
+		#[clap(long, default_value = "http://localhost:6421")]
+		endpoint: String,
+	},
+	/// Replaces the file system database with a checkpoint created by `backup`. The engine must be stopped.
+	Restore {
+		/// Checkpoint directory or tarball.
+		#[clap(index = 1)]
+		path: PathBuf,
+	},
+}
+
+#[derive(Serialize)]
+struct UdbCheckpointRequest {
+	path: PathBuf,
+	tarball: bool,
+}
+
+#[derive(Deserialize)]
+struct UdbCheckpointResponse {
+	path: PathBuf,
+	key_count: u64,
+	size_bytes: u64,
 }
 
 #[derive(ValueEnum, Clone, PartialEq)]
@@ -54,6 +95,115 @@ impl SubCommand {
 
 				Ok(())
 			}
+			Self::Backup {
+				path,
+				tarball,
+				download,
+				endpoint,
+			} => {
+				if download {
+					download_checkpoint(&endpoint, &path).await
+				} else {
+					create_checkpoint(&endpoint, path, tarball).await
+				}
+			}
+			Self::Restore { path } => restore_checkpoint(config, path).await,
 		}
 	}
 }
+
+async fn create_checkpoint(endpoint: &str, path: PathBuf, tarball: bool) -> Result<()> {
+	let client = rivet_pools::reqwest::client_no_timeout().await?;
+	let response = client
+		.post(format!("{endpoint}/udb/checkpoint"))
+		.json(&UdbCheckpointRequest { path, tarball })
+		.send()
+		.await
+		.context("failed to send request")?;
+
+	if !response.status().is_success() {
+		let status = response.status();
+		let body = response.text().await.unwrap_or_default();
+		bail!("Failed to create checkpoint: {} - {}", status, body);
+	}
+
+	let res = response.json::<UdbCheckpointResponse>().await?;
+
+	println!(
+		"Created checkpoint with {} keys ({} bytes) at {}",
+		res.key_count,
+		res.size_bytes,
+		res.path.display()
+	);
+
+	Ok(())
+}
+
+async fn download_checkpoint(endpoint: &str, path: &Path) -> Result<()> {
+	ensure!(!path.exists(), "path already exists: {}", path.display());
+
+	let client = rivet_pools::reqwest::client_no_timeout().await?;
+	let mut response = client
+		.get(format!("{endpoint}/udb/checkpoint/tarball"))
+		.send()
+		.await
+		.context("failed to send request")?;
+
+	if !response.status().is_success() {
+		let status = response.status();
+		let body = response.text().await.unwrap_or_default();
+		bail!("Failed to create checkpoint: {} - {}", status, body);
+	}
+
+	let mut file = tokio::fs::File::create(path)
+		.await
+		.with_context(|| format!("failed to create {}", path.display()))?;
+	let mut size = 0;
+
+	while let Some(chunk) = response.chunk().await? {
+		file.write_all(&chunk).await?;
+		size += chunk.len();
+	}
+
+	file.sync_all().await?;
+
+	println!("Downloaded checkpoint ({size} bytes) to {}", path.display());
+
+	Ok(())
+}
+
+async fn restore_checkpoint(config: rivet_config::Config, path: PathBuf) -> Result<()> {
+	let rivet_config::config::Database::FileSystem(fs) = config.database() else {
+		bail!("restoring is only supported for the file system database");
+	};
+	let db_path = fs.path.clone();
+
+	let output = tokio::task::spawn_blocking(move || {
+		if path.is_dir() {
+			backup::restore_checkpoint(&path, &db_path)
+		} else {
+			// Unpack tarballs to a temporary directory first
+			let temp_dir = tempfile::tempdir().context("failed to create temporary directory")?;
+			let checkpoint_path = temp_dir.path().join("checkpoint");
+
+			let file = std::fs::File::open(&path)
+				.with_context(|| format!("failed to open {}", path.display()))?;
+			backup::unpack_tarball(file, &checkpoint_path)?;
+
+			backup::restore_checkpoint(&checkpoint_path, &db_path)
+		}
+	})
+	.await??;
+
+	println!(
+		"Restored checkpoint with {} keys ({} bytes) to {}",
+		output.info.key_count,
+		output.info.size_bytes,
+		fs.path.display()
+	);
+	if let Some(previous_path) = output.previous_path {
+		println!("Previous database moved to {}", previous_path.display());
+	}
+
+	Ok(())
+}
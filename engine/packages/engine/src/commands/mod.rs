@@ -1,3 +1,4 @@
+pub mod actor_kv;
 pub mod config;
 pub mod db;
 pub mod start;
@@ -1,15 +1,27 @@
-use std::sync::Arc;
+use std::{
+	io::{BufWriter, Write},
+	path::PathBuf,
+	sync::Arc,
+};
 
 use anyhow::*;
 use clap::{Parser, ValueEnum};
-use gas::db::{
-	self, Database,
-	debug::{DatabaseDebug, WorkflowState as DebugWorkflowState},
+use gas::{
+	db::{
+		self, Database,
+		debug::{
+			DatabaseDebug, HistoryData, WorkflowData as DebugWorkflowData,
+			WorkflowState as DebugWorkflowState,
+		},
+	},
+	registry::Registry,
+	replay::{ReplayOutcome, Replayer},
 };
 use rivet_util::Id;
 
 use crate::util::{self, wf::KvPair};
 
+mod schedule;
 mod signal;
 
 #[derive(Parser)]
@@ -32,6 +44,23 @@ pub enum SubCommand {
 	Silence { workflow_ids: Vec<Id> },
 	/// Sets the wake immediate property of a workflow to true.
 	Wake { workflow_ids: Vec<Id> },
+	/// Requests that workflows stop. Workflows can handle the cancellation before stopping.
+	Cancel {
+		workflow_ids: Vec<Id>,
+		/// Reason given to the workflow.
+		#[clap(long, short = 'r')]
+		reason: Option<String>,
+		/// Also cancels all sub workflows.
+		#[clap(long, short = 'p')]
+		propagate: bool,
+	},
+	/// Stops workflows immediately without letting them handle a cancellation.
+	Terminate {
+		workflow_ids: Vec<Id>,
+		/// Reason recorded on the workflow.
+		#[clap(long, short = 'r')]
+		reason: Option<String>,
+	},
 	/// Lists the entire event history of a workflow.
 	History {
 		#[clap(index = 1)]
@@ -48,17 +77,73 @@ pub enum SubCommand {
 		/// Includes create timestamps for events in graph. Two of this flag enables millisecond display.
 		#[clap(short = 't', action = clap::ArgAction::Count, long)]
 		print_ts: u8,
+		/// Output format. `json` prints the full history as a single JSON line and `dot` prints a Graphviz
+		/// graph.
+		#[clap(long, value_enum, default_value_t = HistoryFormat::Tree)]
+		format: HistoryFormat,
+	},
+	/// Exports the histories of all workflows with the given tags, name and state.
+	Export {
+		tags: Vec<KvPair>,
+		/// Workflow name.
+		#[clap(long, short = 'n')]
+		name: Option<String>,
+		#[clap(long, short = 's')]
+		state: Option<WorkflowState>,
+		/// Output format. `json` writes one history per line (readable by `wf replay --file`) and `dot`
+		/// writes one Graphviz graph per workflow.
+		#[clap(long, value_enum, default_value_t = ExportFormat::Json)]
+		format: ExportFormat,
+		/// Includes forgotten events.
+		#[clap(short = 'f', long)]
+		include_forgotten: bool,
+		/// File to write to instead of stdout.
+		#[clap(long, short = 'o')]
+		output: Option<PathBuf>,
+	},
+	/// Replays workflow histories against the workflows compiled into this binary without running activities.
+	/// Prints the first history divergence of each workflow.
+	Replay {
+		workflow_ids: Vec<Id>,
+		/// Replays all workflows with this name.
+		#[clap(long, short = 'n')]
+		name: Option<String>,
+		/// Replays all workflows in this state.
+		#[clap(long, short = 's')]
+		state: Option<WorkflowState>,
+		/// Reads histories from a file (one JSON history per line) instead of the database.
+		#[clap(long, short = 'f')]
+		file: Option<PathBuf>,
+	},
+	/// Migrates the input and state of workflows to the schema version of the workflows compiled into this
+	/// binary. Finished workflows are skipped and running workflows are only checked, since they are migrated
+	/// by their worker.
+	Migrate {
+		workflow_ids: Vec<Id>,
+		/// Migrates all workflows with this name.
+		#[clap(long, short = 'n')]
+		name: Option<String>,
+		/// Migrates all workflows in this state.
+		#[clap(long, short = 's')]
+		state: Option<WorkflowState>,
+		/// Prints which workflows fail to migrate or deserialize without writing anything.
+		#[clap(long)]
+		dry_run: bool,
 	},
 	Signal {
 		#[clap(subcommand)]
 		command: signal::SubCommand,
 	},
+	Schedule {
+		#[clap(subcommand)]
+		command: schedule::SubCommand,
+	},
 }
 
 impl SubCommand {
 	pub async fn execute(self, config: rivet_config::Config) -> Result<()> {
 		let pools = rivet_pools::Pools::new(config.clone()).await?;
-		let db = db::DatabaseKv::from_pools(pools).await? as Arc<dyn DatabaseDebug>;
+		let db = db::DatabaseKv::from_pools(pools.clone()).await? as Arc<dyn DatabaseDebug>;
 
 		match self {
 			Self::Get { workflow_ids } => {
@@ -85,23 +170,297 @@ impl SubCommand {
 			}
 			Self::Silence { workflow_ids } => db.silence_workflows(workflow_ids).await,
 			Self::Wake { workflow_ids } => db.wake_workflows(workflow_ids).await,
+			Self::Cancel {
+				workflow_ids,
+				reason,
+				propagate,
+			} => {
+				let ray_id = Id::new_v1(config.dc_label());
+
+				for workflow_id in workflow_ids {
+					let cancelled_workflow_ids = db
+						.cancel_workflow(
+							ray_id,
+							workflow_id,
+							Id::new_v1(config.dc_label()),
+							reason.as_deref(),
+							propagate,
+						)
+						.await?;
+
+					for workflow_id in cancelled_workflow_ids {
+						println!("cancelled {workflow_id}");
+					}
+				}
+
+				Ok(())
+			}
+			Self::Terminate {
+				workflow_ids,
+				reason,
+			} => {
+				db.terminate_workflows(workflow_ids, reason.as_deref())
+					.await
+			}
 			Self::History {
 				workflow_id,
 				exclude_json,
 				include_forgotten,
 				print_location,
 				print_ts,
+				format,
 			} => {
 				let history = db
 					.get_workflow_history(workflow_id, include_forgotten)
 					.await?;
-				util::wf::print_history(history, exclude_json, print_location, print_ts).await
+
+				match format {
+					HistoryFormat::Tree => {
+						util::wf::print_history(history, exclude_json, print_location, print_ts)
+							.await
+					}
+					HistoryFormat::Json => {
+						let history = history.context("workflow not found")?;
+						println!("{}", serde_json::to_string(&history)?);
+
+						Ok(())
+					}
+					HistoryFormat::Dot => {
+						let history = history.context("workflow not found")?;
+						util::wf::dot::write_history_dot(&mut std::io::stdout().lock(), &history)
+					}
+				}
+			}
+			Self::Export {
+				tags,
+				name,
+				state,
+				format,
+				include_forgotten,
+				output,
+			} => {
+				let workflows = db
+					.find_workflows(
+						&tags
+							.into_iter()
+							.map(|kv| (kv.key, kv.value))
+							.collect::<Vec<_>>(),
+						name.as_deref(),
+						state.map(Into::into),
+					)
+					.await?;
+
+				// Workflows are written as they are read instead of being buffered
+				let mut out: Box<dyn Write + Send> = if let Some(output) = &output {
+					Box::new(BufWriter::new(std::fs::File::create(output)?))
+				} else {
+					Box::new(BufWriter::new(std::io::stdout()))
+				};
+				let mut exported = 0;
+				for workflow in workflows {
+					// Workflow may have been deleted since it was found
+					let Some(history) = db
+						.get_workflow_history(workflow.workflow_id, include_forgotten)
+						.await?
+					else {
+						continue;
+					};
+
+					match format {
+						ExportFormat::Json => {
+							serde_json::to_writer(&mut out, &history)?;
+							writeln!(out)?;
+						}
+						ExportFormat::Dot => util::wf::dot::write_history_dot(&mut out, &history)?,
+					}
+
+					exported += 1;
+				}
+
+				out.flush()?;
+
+				if let Some(output) = output {
+					eprintln!("exported {} workflow(s) to {}", exported, output.display());
+				}
+
+				Ok(())
+			}
+			Self::Replay {
+				workflow_ids,
+				name,
+				state,
+				file,
+			} => {
+				let histories = if let Some(file) = file {
+					let content = tokio::fs::read_to_string(&file).await?;
+
+					content
+						.lines()
+						.filter(|line| !line.trim().is_empty())
+						.map(|line| {
+							serde_json::from_str::<HistoryData>(line).context("invalid history")
+						})
+						.collect::<Result<Vec<_>>>()?
+				} else {
+					let mut workflow_ids = workflow_ids;
+
+					if name.is_some() || state.is_some() {
+						let workflows = db
+							.find_workflows(&[], name.as_deref(), state.map(Into::into))
+							.await?;
+						workflow_ids.extend(workflows.into_iter().map(|wf| wf.workflow_id));
+					}
+
+					ensure!(!workflow_ids.is_empty(), "no workflows to replay");
+
+					let mut histories = Vec::with_capacity(workflow_ids.len());
+					for workflow_id in workflow_ids {
+						let history = db
+							.get_workflow_history(workflow_id, false)
+							.await?
+							.with_context(|| format!("workflow {workflow_id} not found"))?;
+						histories.push(history);
+					}
+
+					histories
+				};
+
+				let cache = rivet_cache::CacheInner::from_env(&config, pools.clone())?;
+				let replayer = Replayer::new(
+					rivet_workflow_worker::registry()?.handle(),
+					config.clone(),
+					pools.clone(),
+					cache,
+				)
+				.with_db(db::DatabaseKv::from_pools(pools).await?);
+
+				let mut reports = Vec::with_capacity(histories.len());
+				for history in histories {
+					reports.push(replayer.replay(history).await);
+				}
+
+				util::wf::print_replay_reports(&reports);
+
+				let diverged = reports
+					.iter()
+					.filter(|report| matches!(report.outcome, ReplayOutcome::Diverged(_)))
+					.count();
+				ensure!(
+					diverged == 0,
+					"{diverged} workflow(s) diverged from their history"
+				);
+
+				Ok(())
+			}
+			Self::Migrate {
+				workflow_ids,
+				name,
+				state,
+				dry_run,
+			} => {
+				let mut workflows = DatabaseDebug::get_workflows(&*db, workflow_ids).await?;
+
+				if name.is_some() || state.is_some() {
+					workflows.extend(
+						db.find_workflows(&[], name.as_deref(), state.map(Into::into))
+							.await?,
+					);
+				}
+
+				workflows.retain(|wf| {
+					!matches!(
+						wf.state,
+						DebugWorkflowState::Complete
+							| DebugWorkflowState::Cancelled
+							| DebugWorkflowState::Terminated
+					)
+				});
+
+				let registry = rivet_workflow_worker::registry()?;
+				let kv_db = db::DatabaseKv::from_pools(pools).await?;
+
+				let mut reports = Vec::with_capacity(workflows.len());
+				for workflow in workflows {
+					let outcome = migrate_workflow(&registry, &*kv_db, &workflow, dry_run)
+						.await
+						.map_err(|err| format!("{err:#}"));
+
+					reports.push(util::wf::MigrationReport {
+						workflow_id: workflow.workflow_id,
+						workflow_name: workflow.workflow_name,
+						from_version: workflow.schema_version,
+						outcome,
+					});
+				}
+
+				util::wf::print_migration_reports(&reports, dry_run);
+
+				let failed = reports
+					.iter()
+					.filter(|report| report.outcome.is_err())
+					.count();
+				ensure!(failed == 0, "{failed} workflow(s) failed to migrate");
+
+				Ok(())
 			}
 			Self::Signal { command } => command.execute(db).await,
+			Self::Schedule { command } => command.execute(db).await,
 		}
 	}
 }
 
+/// Migrates the input and state of a workflow with the migrations of its registered workflow and checks that
+/// the input deserializes. Returns the resulting schema version.
+async fn migrate_workflow(
+	registry: &Registry,
+	db: &dyn Database,
+	workflow: &DebugWorkflowData,
+	dry_run: bool,
+) -> Result<usize> {
+	let registry_workflow = registry.get_workflow(&workflow.workflow_name)?;
+	let migrations = (registry_workflow.migrations)();
+	let schema_version = migrations.version();
+
+	let input = serde_json::value::to_raw_value(&workflow.input)?;
+	let state = serde_json::value::to_raw_value(&workflow.data)?;
+
+	let (input, state) = migrations.migrate(workflow.schema_version, &input, &state)?;
+	(registry_workflow.validate_input)(&input)?;
+
+	if !dry_run
+		&& workflow.schema_version != schema_version
+		&& workflow.state != DebugWorkflowState::Running
+	{
+		// Fails if the workflow started running or was migrated since it was read
+		db.migrate_workflow(
+			workflow.workflow_id,
+			workflow.schema_version,
+			schema_version,
+			&input,
+			&state,
+			false,
+		)
+		.await?;
+	}
+
+	Ok(schema_version)
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[clap(rename_all = "kebab_case")]
+pub enum HistoryFormat {
+	Tree,
+	Json,
+	Dot,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[clap(rename_all = "kebab_case")]
+pub enum ExportFormat {
+	Json,
+	Dot,
+}
+
 #[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[clap(rename_all = "kebab_case")]
 pub enum WorkflowState {
@@ -110,6 +469,8 @@ pub enum WorkflowState {
 	Sleeping,
 	Dead,
 	Silenced,
+	Cancelled,
+	Terminated,
 }
 
 impl From<WorkflowState> for DebugWorkflowState {
@@ -120,6 +481,8 @@ impl From<WorkflowState> for DebugWorkflowState {
 			WorkflowState::Sleeping => DebugWorkflowState::Sleeping,
 			WorkflowState::Dead => DebugWorkflowState::Dead,
 			WorkflowState::Silenced => DebugWorkflowState::Silenced,
+			WorkflowState::Cancelled => DebugWorkflowState::Cancelled,
+			WorkflowState::Terminated => DebugWorkflowState::Terminated,
 		}
 	}
 }
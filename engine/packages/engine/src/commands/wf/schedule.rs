@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use anyhow::*;
+use clap::Parser;
+use gas::db::debug::DatabaseDebug;
+
+use crate::util;
+
+#[derive(Parser)]
+pub enum SubCommand {
+	/// Prints the given schedule(s).
+	Get { schedule_names: Vec<String> },
+	/// Lists all schedules.
+	List {
+		/// Only list schedules that dispatch this workflow.
+		#[clap(long, short = 'n')]
+		workflow_name: Option<String>,
+		/// Prints paragraphs instead of a table.
+		#[clap(long, short = 'p')]
+		pretty: bool,
+	},
+	/// Stops schedules from dispatching workflows.
+	Pause { schedule_names: Vec<String> },
+	/// Resumes paused schedules. Ticks missed while paused are skipped.
+	Resume { schedule_names: Vec<String> },
+	/// Deletes schedules. Already dispatched workflows are not affected.
+	Delete { schedule_names: Vec<String> },
+}
+
+impl SubCommand {
+	pub async fn execute(self, db: Arc<dyn DatabaseDebug>) -> Result<()> {
+		match self {
+			Self::Get { schedule_names } => {
+				let schedules = db.get_schedules(schedule_names).await?;
+				util::wf::schedule::print_schedules(schedules, true).await
+			}
+			Self::List {
+				workflow_name,
+				pretty,
+			} => {
+				let schedules = db.find_schedules(workflow_name.as_deref()).await?;
+				util::wf::schedule::print_schedules(schedules, pretty).await
+			}
+			Self::Pause { schedule_names } => db.pause_schedules(schedule_names).await,
+			Self::Resume { schedule_names } => db.resume_schedules(schedule_names).await,
+			Self::Delete { schedule_names } => db.delete_schedules(schedule_names).await,
+		}
+	}
+}
@@ -35,6 +35,12 @@ pub enum SubCommand {
 	},
 	/// Allows inspection of UDB data
 	Udb(udb::Opts),
+	/// Exports and imports actor KV data
+	#[clap(alias = "kv")]
+	ActorKv {
+		#[clap(subcommand)]
+		command: actor_kv::SubCommand,
+	},
 }
 
 impl SubCommand {
@@ -46,6 +52,7 @@ impl SubCommand {
 			SubCommand::Config { command } => command.execute(config).await,
 			SubCommand::Tracing { command } => command.execute(config).await,
 			SubCommand::Udb(opts) => opts.execute(config).await,
+			SubCommand::ActorKv { command } => command.execute(config).await,
 		}
 	}
 }
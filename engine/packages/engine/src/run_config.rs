@@ -23,6 +23,11 @@ pub fn config(_rivet_config: rivet_config::Config) -> Result<RunConfigData> {
 			ServiceKind::Singleton,
 			|config, pools| Box::pin(pegboard_serverless::start(config, pools)),
 		),
+		Service::new(
+			"pegboard_actor_kv_sweeper",
+			ServiceKind::Singleton,
+			|config, pools| Box::pin(pegboard_actor_kv::start(config, pools)),
+		),
 		// Core services
 		Service::new("tracing_reconfigure", ServiceKind::Core, |config, pools| {
 			Box::pin(rivet_tracing_reconfigure::start(config, pools))
@@ -0,0 +1,367 @@
+use std::{
+	collections::{HashMap, HashSet},
+	io::Write,
+};
+
+use anyhow::*;
+use chrono::{TimeZone, Utc};
+use gas::{
+	db::debug::{Event, EventData, HistoryData},
+	history::{event::SleepState, location::Location},
+};
+use rivet_util::Id;
+
+/// Writes a workflow history to `out` as a Graphviz DOT graph. Events are connected to the previous event in
+/// the same branch, and the first event of a branch or loop iteration is connected to the branch or loop event
+/// it belongs to. Sub workflows and signal send targets are drawn as separate workflow nodes.
+pub fn write_history_dot(out: &mut impl Write, history: &HistoryData) -> Result<()> {
+	writeln!(out, "digraph \"{}\" {{", history.wf.workflow_id)?;
+	writeln!(out, "\tnode [shape=box, fontname=\"monospace\"];")?;
+	writeln!(out, "\tedge [fontname=\"monospace\"];")?;
+	writeln!(
+		out,
+		"\twf [shape=doubleoctagon, label=\"{}\"];",
+		escape(&format!(
+			"{}\n{}",
+			history.wf.workflow_name, history.wf.workflow_id
+		))
+	)?;
+
+	let event_by_location = history
+		.events
+		.iter()
+		.enumerate()
+		.map(|(i, event)| (&event.location, (i, event)))
+		.collect::<HashMap<_, _>>();
+	let mut last_event_by_root = HashMap::<Location, usize>::new();
+	let mut workflow_nodes = HashSet::<Id>::new();
+
+	for (i, event) in history.events.iter().enumerate() {
+		let (shape, color) = event_shape(event);
+		writeln!(
+			out,
+			"\te{i} [shape={shape}, color={color}{}, label=\"{}\"];",
+			if event.forgotten {
+				", style=dashed"
+			} else {
+				""
+			},
+			escape(&event_label(event)?),
+		)?;
+
+		let root = event.location.root();
+
+		if let Some(prev) = last_event_by_root.insert(root, i) {
+			// Previous event in the same branch
+			writeln!(out, "\te{prev} -> e{i};")?;
+		} else if let Some((parent, parent_event, len)) =
+			(1..event.location.len()).rev().find_map(|len| {
+				let prefix = event
+					.location
+					.iter()
+					.take(len)
+					.cloned()
+					.collect::<Location>();
+
+				event_by_location
+					.get(&prefix)
+					.map(|(parent, parent_event)| (*parent, *parent_event, len))
+			}) {
+			// First event of a branch or loop iteration
+			if let (EventData::Loop(_), Some(coord)) = (&parent_event.data, event.location.get(len))
+			{
+				writeln!(
+					out,
+					"\te{parent} -> e{i} [style=dashed, label=\"iteration {}\"];",
+					coord.head().saturating_sub(1)
+				)?;
+			} else {
+				writeln!(out, "\te{parent} -> e{i} [style=dashed];")?;
+			}
+		} else {
+			writeln!(out, "\twf -> e{i};")?;
+		}
+
+		match &event.data {
+			EventData::SubWorkflow(sub_workflow) => {
+				if workflow_nodes.insert(sub_workflow.sub_workflow_id) {
+					writeln!(
+						out,
+						"\twf_{} [shape=doubleoctagon, style=dashed, label=\"{}\"];",
+						sub_workflow.sub_workflow_id,
+						escape(&format!(
+							"{}\n{}",
+							sub_workflow.name, sub_workflow.sub_workflow_id
+						)),
+					)?;
+				}
+
+				writeln!(
+					out,
+					"\te{i} -> wf_{} [style=dashed];",
+					sub_workflow.sub_workflow_id
+				)?;
+			}
+			EventData::SignalSend(signal_send) => {
+				if let Some(workflow_id) = signal_send.workflow_id {
+					if workflow_nodes.insert(workflow_id) {
+						writeln!(
+							out,
+							"\twf_{workflow_id} [shape=doubleoctagon, style=dashed, label=\"{workflow_id}\"];",
+						)?;
+					}
+
+					writeln!(
+						out,
+						"\te{i} -> wf_{workflow_id} [style=dashed, label=\"{}\"];",
+						escape(&signal_send.name)
+					)?;
+				}
+			}
+			_ => {}
+		}
+	}
+
+	writeln!(out, "}}")?;
+
+	Ok(())
+}
+
+fn event_shape(event: &Event) -> (&'static str, &'static str) {
+	match &event.data {
+		EventData::Activity(_) => ("box", "goldenrod"),
+		EventData::Signal(_) => ("cds", "cyan4"),
+		EventData::SignalSend(_) => ("cds", "blue"),
+		EventData::MessageSend(_) => ("cds", "blue"),
+		EventData::SubWorkflow(_) => ("box3d", "green4"),
+		EventData::Loop(_) => ("diamond", "magenta"),
+		EventData::Sleep(_) => ("ellipse", "magenta"),
+		EventData::Removed(_) => ("box", "red"),
+		EventData::VersionCheck => ("box", "red"),
+		EventData::Branch => ("point", "black"),
+		EventData::Empty => ("box", "gray"),
+	}
+}
+
+fn event_label(event: &Event) -> Result<String> {
+	use std::fmt::Write;
+
+	let mut label = String::new();
+
+	if event.forgotten {
+		write!(label, "forgotten ")?;
+	}
+
+	match &event.data {
+		EventData::Activity(activity) => write!(label, "activity {}", activity.name)?,
+		EventData::Signal(signal) => write!(label, "signal receive {}", signal.name)?,
+		EventData::SignalSend(signal_send) => write!(label, "signal send {}", signal_send.name)?,
+		EventData::MessageSend(message_send) => {
+			write!(label, "message send {}", message_send.name)?
+		}
+		EventData::SubWorkflow(sub_workflow) => {
+			write!(label, "sub workflow {}", sub_workflow.name)?
+		}
+		EventData::Loop(_) => write!(label, "loop")?,
+		EventData::Sleep(_) => write!(label, "sleep")?,
+		EventData::Removed(removed) => {
+			write!(label, "removed {}", removed.event_type)?;
+
+			if let Some(name) = &removed.name {
+				write!(label, " {name}")?;
+			}
+		}
+		EventData::VersionCheck => write!(label, "version check")?,
+		EventData::Branch => write!(label, "branch")?,
+		EventData::Empty => write!(label, "empty")?,
+	}
+
+	write!(
+		label,
+		"\nv{} @ {}\n{}",
+		event.version,
+		event.location,
+		format_ts(event.create_ts)?
+	)?;
+
+	match &event.data {
+		EventData::Activity(activity) => {
+			let errors = activity.errors.iter().map(|x| x.count).sum::<usize>();
+			if errors != 0 {
+				write!(label, "\n{errors} error(s)")?;
+			}
+		}
+		EventData::Signal(signal) => write!(label, "\nid {}", signal.signal_id)?,
+		EventData::SignalSend(signal_send) => write!(label, "\nid {}", signal_send.signal_id)?,
+		EventData::Loop(loop_event) => {
+			write!(label, "\n{} iteration(s)", loop_event.iteration)?;
+
+			if loop_event.output.is_some() {
+				write!(label, ", complete")?;
+			}
+		}
+		EventData::Sleep(sleep) => {
+			write!(label, "\nuntil {}", format_ts(sleep.deadline_ts)?)?;
+
+			if !matches!(sleep.state, SleepState::Normal) {
+				write!(label, " ({})", sleep.state)?;
+			}
+		}
+		_ => {}
+	}
+
+	Ok(label)
+}
+
+fn format_ts(ts: i64) -> Result<String> {
+	let datetime = Utc
+		.timestamp_millis_opt(ts)
+		.single()
+		.context("invalid ts")?;
+
+	Ok(datetime.format("%Y-%m-%d %H:%M:%S%.3f").to_string())
+}
+
+fn escape(s: &str) -> String {
+	s.replace('\\', "\\\\")
+		.replace('"', "\\\"")
+		.replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+	use gas::{
+		db::debug::{
+			ActivityEvent, LoopEvent, SignalSendEvent, SubWorkflowEvent, WorkflowData,
+			WorkflowState,
+		},
+		history::location::Coordinate,
+	};
+
+	use super::*;
+
+	fn event(location: &[usize], data: EventData) -> Event {
+		Event {
+			location: location
+				.iter()
+				.map(|coord| Coordinate::simple(*coord))
+				.collect(),
+			version: 1,
+			create_ts: 0,
+			forgotten: false,
+			data,
+		}
+	}
+
+	fn activity(name: &str) -> EventData {
+		EventData::Activity(ActivityEvent {
+			name: name.to_string(),
+			input: serde_json::Value::Null,
+			output: None,
+			errors: Vec::new(),
+		})
+	}
+
+	fn render(workflow_name: &str, events: Vec<Event>) -> String {
+		let history = HistoryData {
+			wf: WorkflowData {
+				workflow_id: Id::new_v1(1),
+				workflow_name: workflow_name.to_string(),
+				tags: serde_json::Value::Null,
+				create_ts: 0,
+				input: serde_json::Value::Null,
+				data: serde_json::Value::Null,
+				schema_version: 0,
+				output: None,
+				error: None,
+				termination: None,
+				state: WorkflowState::Running,
+			},
+			events,
+		};
+
+		let mut out = Vec::new();
+		write_history_dot(&mut out, &history).unwrap();
+
+		String::from_utf8(out).unwrap()
+	}
+
+	#[test]
+	fn branches_and_loops() {
+		let dot = render(
+			"test",
+			vec![
+				event(&[1], activity("a")),
+				event(
+					&[2],
+					EventData::Loop(LoopEvent {
+						state: serde_json::Value::Null,
+						output: None,
+						iteration: 2,
+					}),
+				),
+				event(&[2, 1, 1], activity("b")),
+				event(&[2, 1, 2], activity("c")),
+				event(&[2, 2, 1], activity("d")),
+				event(&[3], activity("e")),
+			],
+		);
+
+		assert!(dot.starts_with("digraph \""));
+		assert!(dot.ends_with("}\n"));
+		assert!(dot.contains("\twf -> e0;\n"));
+		assert!(dot.contains("\te0 -> e1;\n"));
+		assert!(dot.contains("\te1 -> e2 [style=dashed, label=\"iteration 0\"];\n"));
+		assert!(dot.contains("\te2 -> e3;\n"));
+		assert!(dot.contains("\te1 -> e4 [style=dashed, label=\"iteration 1\"];\n"));
+		assert!(dot.contains("\te1 -> e5;\n"));
+	}
+
+	#[test]
+	fn workflow_nodes_are_deduplicated() {
+		let workflow_id = Id::new_v1(1);
+		let dot = render(
+			"test",
+			vec![
+				event(
+					&[1],
+					EventData::SubWorkflow(SubWorkflowEvent {
+						sub_workflow_id: workflow_id,
+						name: "sub".to_string(),
+						tags: serde_json::Value::Null,
+						input: serde_json::Value::Null,
+					}),
+				),
+				event(
+					&[2],
+					EventData::SignalSend(SignalSendEvent {
+						signal_id: Id::new_v1(1),
+						name: "my_signal".to_string(),
+						workflow_id: Some(workflow_id),
+						tags: None,
+						body: serde_json::Value::Null,
+					}),
+				),
+			],
+		);
+
+		assert_eq!(dot.matches(&format!("\twf_{workflow_id} [")).count(), 1);
+		assert!(dot.contains(&format!("\te0 -> wf_{workflow_id} [style=dashed];\n")));
+		assert!(dot.contains(&format!(
+			"\te1 -> wf_{workflow_id} [style=dashed, label=\"my_signal\"];\n"
+		)));
+	}
+
+	#[test]
+	fn labels_are_escaped() {
+		let mut forgotten = event(&[1], activity("say \"hi\""));
+		forgotten.forgotten = true;
+
+		let dot = render("quote\"d\\", vec![forgotten]);
+
+		assert!(dot.contains("label=\"quote\\\"d\\\\\\n"));
+		assert!(dot.contains(
+			"\te0 [shape=box, color=goldenrod, style=dashed, label=\"forgotten activity say \\\"hi\\\"\\nv1 @ {1}\\n1970-01-01 00:00:00.000\"];\n"
+		));
+	}
+}
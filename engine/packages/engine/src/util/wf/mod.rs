@@ -2,10 +2,14 @@ use anyhow::*;
 use chrono::{TimeZone, Utc};
 use gas::db::debug::{Event, EventData, HistoryData, WorkflowState};
 use gas::history::event::SleepState;
+use gas::replay::{ReplayOutcome, ReplayReport};
 use rivet_term::console::{Style, style};
+use rivet_util::Id;
 
 use crate::util::format::{chunk_string, colored_json, indent_string};
 
+pub mod dot;
+pub mod schedule;
 pub mod signal;
 
 #[derive(Debug, Clone)]
@@ -87,6 +91,20 @@ pub async fn print_workflows(
 				);
 			}
 
+			if let Some(termination) = &workflow.termination {
+				let datetime = Utc
+					.timestamp_millis_opt(termination.ts)
+					.single()
+					.context("invalid ts")?;
+				let date = datetime.format("%Y-%m-%d %H:%M:%S%.3f");
+
+				println!("  {} {}", style("stopped at").bold(), style(date).magenta());
+
+				if let Some(reason) = &termination.reason {
+					println!("  {} {}", style("reason").bold(), style(reason).green());
+				}
+			}
+
 			println!(
 				"  {} {}",
 				style("input").bold(),
@@ -113,6 +131,68 @@ pub async fn print_workflows(
 	Ok(())
 }
 
+pub struct MigrationReport {
+	pub workflow_id: Id,
+	pub workflow_name: String,
+	pub from_version: usize,
+	/// Schema version after migrating or the reason the workflow could not be migrated.
+	pub outcome: Result<usize, String>,
+}
+
+pub fn print_migration_reports(reports: &[MigrationReport], dry_run: bool) {
+	if reports.is_empty() {
+		rivet_term::status::success("No workflows to migrate", "");
+		return;
+	}
+
+	if dry_run {
+		rivet_term::status::success("Checked", reports.len());
+	} else {
+		rivet_term::status::success("Migrated", reports.len());
+	}
+
+	for report in reports {
+		let outcome = match &report.outcome {
+			Result::Ok(version) if *version == report.from_version => {
+				style(format!("v{version} up to date")).dim()
+			}
+			Result::Ok(version) => style(format!("v{} -> v{version}", report.from_version)).green(),
+			Result::Err(err) => style(format!("failed: {err}")).red(),
+		};
+
+		println!(
+			"{} {} {}",
+			style(&report.workflow_name).bold(),
+			style(report.workflow_id).dim(),
+			outcome
+		);
+	}
+}
+
+pub fn print_replay_reports(reports: &[ReplayReport]) {
+	if reports.is_empty() {
+		rivet_term::status::success("No workflows replayed", "");
+		return;
+	}
+
+	rivet_term::status::success("Replayed", reports.len());
+
+	for report in reports {
+		let outcome = match &report.outcome {
+			ReplayOutcome::Ok => style("ok".to_string()).green(),
+			ReplayOutcome::Diverged(err) => style(format!("diverged: {err}")).red(),
+			ReplayOutcome::Failed(err) => style(format!("failed: {err}")).yellow(),
+		};
+
+		println!(
+			"{} {} {}",
+			style(&report.workflow_name).bold(),
+			style(report.workflow_id).dim(),
+			outcome
+		);
+	}
+}
+
 pub async fn print_history(
 	history: Option<HistoryData>,
 	exclude_json: bool,
@@ -504,6 +584,24 @@ pub async fn print_history(
 				style(error).green(),
 			);
 		}
+	} else if let (WorkflowState::Cancelled | WorkflowState::Terminated, Some(termination)) =
+		(history.wf.state, history.wf.termination)
+	{
+		println!();
+
+		if termination.forced {
+			println!("{}", style("Workflow terminated").red().bold());
+		} else {
+			println!("{}", style("Workflow cancelled").bright().red().bold());
+		}
+
+		if let Some(reason) = termination.reason {
+			println!(
+				"{} reason {}",
+				style("|").red().dim(),
+				style(reason).green(),
+			);
+		}
 	} else if let WorkflowState::Silenced = history.wf.state {
 		println!();
 
@@ -606,6 +704,8 @@ fn display_state(state: &WorkflowState) -> String {
 		WorkflowState::Sleeping => style("sleeping").yellow().to_string(),
 		WorkflowState::Dead => style("dead").red().to_string(),
 		WorkflowState::Silenced => style("silenced").bright().magenta().to_string(),
+		WorkflowState::Cancelled => style("cancelled").bright().red().to_string(),
+		WorkflowState::Terminated => style("terminated").red().to_string(),
 	}
 }
 
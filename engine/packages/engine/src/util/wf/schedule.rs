@@ -0,0 +1,151 @@
+use anyhow::*;
+use chrono::{Local, TimeZone};
+use rivet_term::console::style;
+
+use gas::db::debug::ScheduleData;
+
+use crate::util::format::{colored_json, indent_string};
+
+pub async fn print_schedules(schedules: Vec<ScheduleData>, pretty: bool) -> Result<()> {
+	if schedules.is_empty() {
+		rivet_term::status::success("No schedules found", "");
+		return Ok(());
+	}
+
+	rivet_term::status::success("Schedules", schedules.len());
+
+	if pretty {
+		for schedule in schedules {
+			println!();
+
+			println!("{}", style(schedule.schedule_name).bold());
+
+			println!("  {} {}", style("workflow").bold(), schedule.workflow_name);
+
+			println!("  {} {}", style("cron").bold(), style(schedule.cron).cyan());
+
+			println!(
+				"  {} {}",
+				style("created at").bold(),
+				style(format_ts(schedule.create_ts)?).magenta()
+			);
+
+			println!(
+				"  {} {}",
+				style("state").bold(),
+				display_paused(&schedule.pause_ts.is_some())
+			);
+
+			if let Some(pause_ts) = schedule.pause_ts {
+				println!(
+					"  {} {}",
+					style("paused at").bold(),
+					style(format_ts(pause_ts)?).magenta()
+				);
+			}
+
+			if let Some(next_ts) = schedule.next_ts {
+				println!(
+					"  {} {}",
+					style("next tick at").bold(),
+					style(format_ts(next_ts)?).magenta()
+				);
+			}
+
+			if let Some(last_dispatch_ts) = schedule.last_dispatch_ts {
+				println!(
+					"  {} {}",
+					style("last dispatched at").bold(),
+					style(format_ts(last_dispatch_ts)?).magenta()
+				);
+			}
+
+			if let Some(last_workflow_id) = schedule.last_workflow_id {
+				println!(
+					"  {} {}",
+					style("last workflow id").bold(),
+					last_workflow_id
+				);
+			}
+
+			if let Some(tags) = &schedule.tags {
+				println!(
+					"  {} {}",
+					style("tags").bold(),
+					&indent_string(&colored_json(&tags)?, "    ", true)
+				);
+			}
+
+			println!(
+				"  {} {}",
+				style("input").bold(),
+				&indent_string(&colored_json(&schedule.input)?, "    ", true)
+			);
+		}
+	} else {
+		table::schedules(schedules)?;
+	}
+
+	Ok(())
+}
+
+fn format_ts(ts: i64) -> Result<String> {
+	let datetime = Local
+		.timestamp_millis_opt(ts)
+		.single()
+		.context("invalid ts")?;
+
+	Ok(datetime.format("%Y-%m-%d %H:%M:%S%.3f").to_string())
+}
+
+fn display_paused(paused: &bool) -> String {
+	if *paused {
+		style("paused").yellow().to_string()
+	} else {
+		style("active").green().to_string()
+	}
+}
+
+mod table {
+	use anyhow::*;
+	use gas::db::debug::ScheduleData;
+	use tabled::Tabled;
+
+	use super::{display_paused, format_ts};
+
+	#[derive(Tabled)]
+	struct ScheduleTableRow {
+		pub schedule_name: String,
+		pub workflow_name: String,
+		pub cron: String,
+		#[tabled(rename = "state", display_with = "display_paused")]
+		pub paused: bool,
+		pub next_tick: String,
+		pub last_workflow_id: String,
+	}
+
+	pub fn schedules(schedules: Vec<ScheduleData>) -> Result<()> {
+		let mut rows = schedules
+			.into_iter()
+			.map(|s| {
+				Ok(ScheduleTableRow {
+					schedule_name: s.schedule_name,
+					workflow_name: s.workflow_name,
+					cron: s.cron,
+					paused: s.pause_ts.is_some(),
+					next_tick: s.next_ts.map(format_ts).transpose()?.unwrap_or_default(),
+					last_workflow_id: s
+						.last_workflow_id
+						.map(|id| id.to_string())
+						.unwrap_or_default(),
+				})
+			})
+			.collect::<Result<Vec<_>>>()?;
+
+		rows.sort_by(|a, b| a.schedule_name.cmp(&b.schedule_name));
+
+		rivet_term::format::table(rows);
+
+		Ok(())
+	}
+}
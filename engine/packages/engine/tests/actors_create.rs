@@ -262,6 +262,67 @@ fn create_actor_malformed_input() {
 	});
 }
 
+#[test]
+fn create_actor_invalid_restart_policy() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _, _runner) =
+			common::setup_test_namespace_with_runner(ctx.leader_dc()).await;
+
+		let response = reqwest::Client::new()
+			.post(format!(
+				"http://127.0.0.1:{}/actors?namespace={}",
+				ctx.leader_dc().guard_port(),
+				namespace
+			))
+			.json(&json!({
+				"name": "test-actor",
+				"runner_name_selector": "test-runner",
+				"crash_policy": "restart",
+				"restart_policy": {
+					"initial_backoff_ms": 10_000,
+					"max_backoff_ms": 1_000,
+				},
+			}))
+			.send()
+			.await
+			.expect("Failed to send request");
+
+		common::assert_error_response(response, "invalid_restart_policy").await;
+	});
+}
+
+#[test]
+fn create_actor_with_restart_policy() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _, _runner) =
+			common::setup_test_namespace_with_runner(ctx.leader_dc()).await;
+
+		let response = reqwest::Client::new()
+			.post(format!(
+				"http://127.0.0.1:{}/actors?namespace={}",
+				ctx.leader_dc().guard_port(),
+				namespace
+			))
+			.json(&json!({
+				"name": "test-actor",
+				"runner_name_selector": "test-runner",
+				"crash_policy": "restart",
+				"restart_policy": {
+					"max_restarts": 3,
+					"on_exhausted": "destroy",
+				},
+			}))
+			.send()
+			.await
+			.expect("Failed to send request");
+		common::assert_success_response(&response);
+
+		let body: serde_json::Value = response.json().await.expect("Failed to parse response");
+		assert_eq!(body["actor"]["restart_count"], json!(0));
+		assert_eq!(body["actor"]["next_restart_ts"], json!(null));
+	});
+}
+
 // MARK: Cross-datacenter tests
 #[test]
 fn create_actor_remote_datacenter_verify() {
@@ -0,0 +1,854 @@
+mod common;
+
+use std::time::Duration;
+
+use futures_util::TryStreamExt;
+use pegboard_actor_kv::watch::ChangeEvent;
+use rivet_runner_protocol as rp;
+use rivet_util::Id;
+use serde_json::json;
+use universaldb::prelude::*;
+use universalpubsub::NextOutput;
+
+// MARK: Export/import
+#[test]
+fn kv_export_import_roundtrip() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _, _runner) =
+			common::setup_test_namespace_with_runner(ctx.leader_dc()).await;
+
+		let src_actor_id = common::create_actor(&namespace, ctx.leader_dc().guard_port()).await;
+		let dst_actor_id = common::create_actor(&namespace, ctx.leader_dc().guard_port()).await;
+
+		let pools = &ctx.leader_dc().pools;
+		let udb = pools.udb().expect("udb");
+		let ups = pools.ups().expect("ups");
+
+		let keys = vec![b"a".to_vec(), b"b".to_vec()];
+		// Large enough to be split into multiple chunks
+		let values = vec![b"hello".to_vec(), vec![7u8; 25_000]];
+
+		pegboard_actor_kv::put(
+			&udb,
+			&ups,
+			src_actor_id.parse::<Id>().expect("valid actor id"),
+			&Default::default(),
+			keys.clone(),
+			values.clone(),
+			None,
+		)
+		.await
+		.expect("failed to put kv entries");
+
+		let response = common::export_actor_kv(
+			&src_actor_id,
+			Some(&namespace),
+			ctx.leader_dc().api_peer_port(),
+		)
+		.await;
+		common::assert_success_response(&response);
+		let snapshot = response
+			.bytes()
+			.await
+			.expect("Failed to read snapshot")
+			.to_vec();
+
+		let response = common::import_actor_kv(
+			&dst_actor_id,
+			Some(&namespace),
+			snapshot,
+			false,
+			ctx.leader_dc().api_peer_port(),
+		)
+		.await;
+		common::assert_success_response(&response);
+		let body: serde_json::Value = response.json().await.expect("Failed to parse response");
+		assert_eq!(body["count"], 2);
+
+		let (got_keys, got_values, _) = pegboard_actor_kv::get(
+			&udb,
+			dst_actor_id.parse::<Id>().expect("valid actor id"),
+			&Default::default(),
+			keys.clone(),
+		)
+		.await
+		.expect("failed to get kv entries");
+
+		assert_eq!(got_keys, keys);
+		assert_eq!(got_values, values);
+	});
+}
+
+#[test]
+fn kv_export_import_multiple_batches() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _, _runner) =
+			common::setup_test_namespace_with_runner(ctx.leader_dc()).await;
+
+		let src_actor_id = common::create_actor(&namespace, ctx.leader_dc().guard_port()).await;
+		let dst_actor_id = common::create_actor(&namespace, ctx.leader_dc().guard_port()).await;
+
+		let pools = &ctx.leader_dc().pools;
+		let udb = pools.udb().expect("udb");
+		let ups = pools.ups().expect("ups");
+
+		// Enough entries to be exported over multiple transactions
+		let keys = (0..1024u32)
+			.map(|i| i.to_be_bytes().to_vec())
+			.collect::<Vec<_>>();
+
+		for batch in keys.chunks(128) {
+			pegboard_actor_kv::put(
+				&udb,
+				&ups,
+				src_actor_id.parse::<Id>().expect("valid actor id"),
+				&Default::default(),
+				batch.to_vec(),
+				batch.to_vec(),
+				None,
+			)
+			.await
+			.expect("failed to put kv entries");
+		}
+
+		let response = common::export_actor_kv(
+			&src_actor_id,
+			Some(&namespace),
+			ctx.leader_dc().api_peer_port(),
+		)
+		.await;
+		common::assert_success_response(&response);
+		let snapshot = response
+			.bytes()
+			.await
+			.expect("Failed to read snapshot")
+			.to_vec();
+
+		let response = common::import_actor_kv(
+			&dst_actor_id,
+			Some(&namespace),
+			snapshot,
+			false,
+			ctx.leader_dc().api_peer_port(),
+		)
+		.await;
+		common::assert_success_response(&response);
+		let body: serde_json::Value = response.json().await.expect("Failed to parse response");
+		assert_eq!(body["count"], keys.len());
+
+		for batch in keys.chunks(128) {
+			let (got_keys, got_values, _) = pegboard_actor_kv::get(
+				&udb,
+				dst_actor_id.parse::<Id>().expect("valid actor id"),
+				&Default::default(),
+				batch.to_vec(),
+			)
+			.await
+			.expect("failed to get kv entries");
+
+			assert_eq!(got_keys, batch);
+			assert_eq!(got_values, batch);
+		}
+	});
+}
+
+#[test]
+fn kv_import_clear_removes_existing_entries() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _, _runner) =
+			common::setup_test_namespace_with_runner(ctx.leader_dc()).await;
+
+		let src_actor_id = common::create_actor(&namespace, ctx.leader_dc().guard_port()).await;
+		let dst_actor_id = common::create_actor(&namespace, ctx.leader_dc().guard_port()).await;
+
+		let pools = &ctx.leader_dc().pools;
+		let udb = pools.udb().expect("udb");
+		let ups = pools.ups().expect("ups");
+
+		pegboard_actor_kv::put(
+			&udb,
+			&ups,
+			dst_actor_id.parse::<Id>().expect("valid actor id"),
+			&Default::default(),
+			vec![b"stale".to_vec()],
+			vec![b"value".to_vec()],
+			None,
+		)
+		.await
+		.expect("failed to put kv entries");
+
+		// Source actor has no entries
+		let response = common::export_actor_kv(
+			&src_actor_id,
+			Some(&namespace),
+			ctx.leader_dc().api_peer_port(),
+		)
+		.await;
+		common::assert_success_response(&response);
+		let snapshot = response
+			.bytes()
+			.await
+			.expect("Failed to read snapshot")
+			.to_vec();
+
+		let response = common::import_actor_kv(
+			&dst_actor_id,
+			Some(&namespace),
+			snapshot,
+			true,
+			ctx.leader_dc().api_peer_port(),
+		)
+		.await;
+		common::assert_success_response(&response);
+
+		let (got_keys, _, _) = pegboard_actor_kv::get(
+			&udb,
+			dst_actor_id.parse::<Id>().expect("valid actor id"),
+			&Default::default(),
+			vec![b"stale".to_vec()],
+		)
+		.await
+		.expect("failed to get kv entries");
+
+		assert!(got_keys.is_empty(), "existing entries should be cleared");
+	});
+}
+
+// MARK: Txn
+#[test]
+fn kv_txn_commits_when_checks_pass() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let actor_id = Id::new_v1(ctx.leader_dc().config.dc_label());
+		let pools = &ctx.leader_dc().pools;
+		let udb = pools.udb().expect("udb");
+		let ups = pools.ups().expect("ups");
+
+		let res = pegboard_actor_kv::txn(
+			&udb,
+			&ups,
+			actor_id,
+			&Default::default(),
+			vec![rp::KvTxnCheck::KvTxnCheckAbsent(rp::KvTxnCheckAbsent {
+				key: b"a".to_vec(),
+			})],
+			vec![
+				rp::KvTxnOp::KvTxnPut(rp::KvTxnPut {
+					key: b"a".to_vec(),
+					value: b"1".to_vec(),
+					expire_ts: None,
+				}),
+				rp::KvTxnOp::KvTxnPut(rp::KvTxnPut {
+					key: b"b".to_vec(),
+					value: b"2".to_vec(),
+					expire_ts: None,
+				}),
+			],
+		)
+		.await
+		.expect("failed to run kv txn");
+
+		let rp::KvTxnResponse::KvTxnCommitted(committed) = res else {
+			panic!("txn should commit");
+		};
+		assert_eq!(committed.keys, vec![b"a".to_vec(), b"b".to_vec()]);
+		assert_eq!(
+			committed.metadata[0].version, committed.metadata[1].version,
+			"keys written in the same txn should share a version"
+		);
+
+		let (keys, values, metadata) = pegboard_actor_kv::get(
+			&udb,
+			actor_id,
+			&Default::default(),
+			vec![b"a".to_vec(), b"b".to_vec()],
+		)
+		.await
+		.expect("failed to get kv entries");
+		assert_eq!(keys, vec![b"a".to_vec(), b"b".to_vec()]);
+		assert_eq!(values, vec![b"1".to_vec(), b"2".to_vec()]);
+		assert_eq!(
+			metadata[0].version, committed.metadata[0].version,
+			"stored version should match the committed version"
+		);
+	});
+}
+
+#[test]
+fn kv_txn_check_failure_applies_no_ops() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let actor_id = Id::new_v1(ctx.leader_dc().config.dc_label());
+		let pools = &ctx.leader_dc().pools;
+		let udb = pools.udb().expect("udb");
+		let ups = pools.ups().expect("ups");
+
+		let put = |value: &[u8]| {
+			rp::KvTxnOp::KvTxnPut(rp::KvTxnPut {
+				key: b"a".to_vec(),
+				value: value.to_vec(),
+				expire_ts: None,
+			})
+		};
+
+		let rp::KvTxnResponse::KvTxnCommitted(first) = pegboard_actor_kv::txn(
+			&udb,
+			&ups,
+			actor_id,
+			&Default::default(),
+			Vec::new(),
+			vec![put(b"1")],
+		)
+		.await
+		.expect("failed to run kv txn") else {
+			panic!("txn should commit");
+		};
+		let rp::KvTxnResponse::KvTxnCommitted(second) = pegboard_actor_kv::txn(
+			&udb,
+			&ups,
+			actor_id,
+			&Default::default(),
+			vec![rp::KvTxnCheck::KvTxnCheckVersion(rp::KvTxnCheckVersion {
+				key: b"a".to_vec(),
+				version: first.metadata[0].version.clone(),
+			})],
+			vec![put(b"2")],
+		)
+		.await
+		.expect("failed to run kv txn") else {
+			panic!("txn with a matching version should commit");
+		};
+		assert!(
+			second.metadata[0].version > first.metadata[0].version,
+			"versions should increase with every write"
+		);
+
+		// The first version is stale now
+		let res = pegboard_actor_kv::txn(
+			&udb,
+			&ups,
+			actor_id,
+			&Default::default(),
+			vec![
+				rp::KvTxnCheck::KvTxnCheckValue(rp::KvTxnCheckValue {
+					key: b"a".to_vec(),
+					value: b"2".to_vec(),
+				}),
+				rp::KvTxnCheck::KvTxnCheckVersion(rp::KvTxnCheckVersion {
+					key: b"a".to_vec(),
+					version: first.metadata[0].version.clone(),
+				}),
+			],
+			vec![
+				put(b"3"),
+				rp::KvTxnOp::KvTxnPut(rp::KvTxnPut {
+					key: b"b".to_vec(),
+					value: b"3".to_vec(),
+					expire_ts: None,
+				}),
+			],
+		)
+		.await
+		.expect("failed to run kv txn");
+
+		let rp::KvTxnResponse::KvTxnCheckFailed(failed) = res else {
+			panic!("txn with a stale version should fail");
+		};
+		assert_eq!(failed.check_index, 1);
+		assert_eq!(
+			failed.metadata.map(|metadata| metadata.version),
+			Some(second.metadata[0].version.clone())
+		);
+
+		let (keys, values, _) = pegboard_actor_kv::get(
+			&udb,
+			actor_id,
+			&Default::default(),
+			vec![b"a".to_vec(), b"b".to_vec()],
+		)
+		.await
+		.expect("failed to get kv entries");
+		assert_eq!(keys, vec![b"a".to_vec()]);
+		assert_eq!(values, vec![b"2".to_vec()], "no ops should be applied");
+	});
+}
+
+#[test]
+fn kv_txn_delete() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let actor_id = Id::new_v1(ctx.leader_dc().config.dc_label());
+		let pools = &ctx.leader_dc().pools;
+		let udb = pools.udb().expect("udb");
+		let ups = pools.ups().expect("ups");
+
+		pegboard_actor_kv::put(
+			&udb,
+			&ups,
+			actor_id,
+			&Default::default(),
+			vec![b"a".to_vec()],
+			vec![b"1".to_vec()],
+			None,
+		)
+		.await
+		.expect("failed to put kv entries");
+
+		let res = pegboard_actor_kv::txn(
+			&udb,
+			&ups,
+			actor_id,
+			&Default::default(),
+			vec![rp::KvTxnCheck::KvTxnCheckValue(rp::KvTxnCheckValue {
+				key: b"a".to_vec(),
+				value: b"1".to_vec(),
+			})],
+			vec![rp::KvTxnOp::KvTxnDelete(rp::KvTxnDelete {
+				key: b"a".to_vec(),
+			})],
+		)
+		.await
+		.expect("failed to run kv txn");
+		assert!(matches!(res, rp::KvTxnResponse::KvTxnCommitted(_)));
+
+		let (keys, _, _) =
+			pegboard_actor_kv::get(&udb, actor_id, &Default::default(), vec![b"a".to_vec()])
+				.await
+				.expect("failed to get kv entries");
+		assert!(keys.is_empty(), "key should be deleted");
+	});
+}
+
+// MARK: Expiration
+#[test]
+fn kv_expired_entries_are_hidden_and_reclaimed() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let actor_id = Id::new_v1(ctx.leader_dc().config.dc_label());
+		let pools = &ctx.leader_dc().pools;
+		let udb = pools.udb().expect("udb");
+		let ups = pools.ups().expect("ups");
+
+		let now = rivet_util::timestamp::now();
+		pegboard_actor_kv::put(
+			&udb,
+			&ups,
+			actor_id,
+			&Default::default(),
+			vec![b"expired".to_vec(), b"live".to_vec(), b"forever".to_vec()],
+			vec![b"1".to_vec(), b"2".to_vec(), b"3".to_vec()],
+			Some(vec![Some(now - 1000), Some(now + 60 * 60 * 1000), None]),
+		)
+		.await
+		.expect("failed to put kv entries");
+
+		let (keys, _, _) = pegboard_actor_kv::get(
+			&udb,
+			actor_id,
+			&Default::default(),
+			vec![b"expired".to_vec(), b"live".to_vec(), b"forever".to_vec()],
+		)
+		.await
+		.expect("failed to get kv entries");
+		assert_eq!(keys, vec![b"live".to_vec(), b"forever".to_vec()]);
+
+		let (keys, _, _, _) = pegboard_actor_kv::list(
+			&udb,
+			actor_id,
+			rp::KvListQuery::KvListAllQuery,
+			false,
+			None,
+			None,
+			None,
+		)
+		.await
+		.expect("failed to list kv entries");
+		assert_eq!(keys, vec![b"forever".to_vec(), b"live".to_vec()]);
+
+		// The engine may have swept the entry already, so the count is not checked
+		pegboard_actor_kv::sweep_expired(&udb, &ups)
+			.await
+			.expect("failed to sweep expired kv entries");
+
+		// Only the metadata, expire ts and value of "live" and the metadata and value of "forever" are left
+		let subspace = pegboard::keys::actor_kv_subspace().subspace(&actor_id);
+		let count = udb
+			.run(|tx| {
+				let subspace = subspace.clone();
+
+				async move {
+					let entries = tx
+						.get_ranges_keyvalues(
+							universaldb::RangeOption {
+								mode: StreamingMode::WantAll,
+								..subspace.range().into()
+							},
+							Serializable,
+						)
+						.try_collect::<Vec<_>>()
+						.await?;
+
+					Ok(entries.len())
+				}
+			})
+			.await
+			.expect("failed to read kv subspace");
+		assert_eq!(count, 5, "expired entry should be reclaimed");
+	});
+}
+
+// MARK: List
+#[test]
+fn kv_list_pagination() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let actor_id = Id::new_v1(ctx.leader_dc().config.dc_label());
+		let pools = &ctx.leader_dc().pools;
+		let udb = pools.udb().expect("udb");
+		let ups = pools.ups().expect("ups");
+
+		let all_keys = [b"a", b"b", b"c", b"d", b"e"]
+			.into_iter()
+			.map(|key| key.to_vec())
+			.collect::<Vec<_>>();
+		pegboard_actor_kv::put(
+			&udb,
+			&ups,
+			actor_id,
+			&Default::default(),
+			all_keys.clone(),
+			vec![vec![0u8; 16]; all_keys.len()],
+			None,
+		)
+		.await
+		.expect("failed to put kv entries");
+
+		for reverse in [false, true] {
+			let mut listed = Vec::new();
+			let mut cursor = None;
+			let mut pages = 0;
+
+			loop {
+				let (keys, _, _, next_cursor) = pegboard_actor_kv::list(
+					&udb,
+					actor_id,
+					rp::KvListQuery::KvListAllQuery,
+					reverse,
+					Some(2),
+					cursor,
+					None,
+				)
+				.await
+				.expect("failed to list kv entries");
+
+				assert!(keys.len() <= 2, "page should respect the limit");
+				listed.extend(keys);
+				pages += 1;
+
+				let Some(next_cursor) = next_cursor else {
+					break;
+				};
+				cursor = Some(next_cursor);
+			}
+
+			let mut expected = all_keys.clone();
+			if reverse {
+				expected.reverse();
+			}
+			assert_eq!(listed, expected, "pages should cover every key once");
+			assert_eq!(pages, 3);
+		}
+	});
+}
+
+#[test]
+fn kv_list_max_bytes() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let actor_id = Id::new_v1(ctx.leader_dc().config.dc_label());
+		let pools = &ctx.leader_dc().pools;
+		let udb = pools.udb().expect("udb");
+		let ups = pools.ups().expect("ups");
+
+		pegboard_actor_kv::put(
+			&udb,
+			&ups,
+			actor_id,
+			&Default::default(),
+			vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()],
+			vec![vec![0u8; 100]; 3],
+			None,
+		)
+		.await
+		.expect("failed to put kv entries");
+
+		// Budget is exceeded by the second entry, which is still returned
+		let (keys, _, _, cursor) = pegboard_actor_kv::list(
+			&udb,
+			actor_id,
+			rp::KvListQuery::KvListAllQuery,
+			false,
+			None,
+			None,
+			Some(150),
+		)
+		.await
+		.expect("failed to list kv entries");
+		assert_eq!(keys, vec![b"a".to_vec(), b"b".to_vec()]);
+		let first_cursor = cursor.expect("truncated page should return a cursor");
+
+		let (keys, _, _, cursor) = pegboard_actor_kv::list(
+			&udb,
+			actor_id,
+			rp::KvListQuery::KvListAllQuery,
+			false,
+			None,
+			Some(first_cursor.clone()),
+			Some(150),
+		)
+		.await
+		.expect("failed to list kv entries");
+		assert_eq!(keys, vec![b"c".to_vec()]);
+		assert!(cursor.is_none(), "last page should not return a cursor");
+
+		// Cursors cannot change the direction of a list
+		let res = pegboard_actor_kv::list(
+			&udb,
+			actor_id,
+			rp::KvListQuery::KvListAllQuery,
+			true,
+			None,
+			Some(first_cursor),
+			None,
+		)
+		.await;
+		assert!(res.is_err(), "cursor direction should be checked");
+	});
+}
+
+// MARK: Watch
+#[test]
+fn kv_changes_are_published() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let actor_id = Id::new_v1(ctx.leader_dc().config.dc_label());
+		let pools = &ctx.leader_dc().pools;
+		let udb = pools.udb().expect("udb");
+		let ups = pools.ups().expect("ups");
+
+		let subject = pegboard::pubsub_subjects::ActorKvSubject::new(actor_id).to_string();
+		let mut sub = ups.subscribe(&subject).await.expect("failed to subscribe");
+
+		pegboard_actor_kv::put(
+			&udb,
+			&ups,
+			actor_id,
+			&Default::default(),
+			vec![b"a".to_vec()],
+			vec![b"1".to_vec()],
+			None,
+		)
+		.await
+		.expect("failed to put kv entries");
+
+		let event = next_change_event(&mut sub).await;
+		assert!(!event.cleared);
+		assert_eq!(event.changes.len(), 1);
+		assert_eq!(event.changes[0].key, b"a".to_vec());
+
+		let (_, _, metadata) =
+			pegboard_actor_kv::get(&udb, actor_id, &Default::default(), vec![b"a".to_vec()])
+				.await
+				.expect("failed to get kv entries");
+		assert_eq!(
+			event.changes[0]
+				.metadata
+				.as_ref()
+				.map(|metadata| &metadata.version),
+			Some(&metadata[0].version),
+			"published version should match the stored version"
+		);
+
+		pegboard_actor_kv::delete(
+			&udb,
+			&ups,
+			actor_id,
+			&Default::default(),
+			vec![b"a".to_vec()],
+		)
+		.await
+		.expect("failed to delete kv entries");
+
+		let event = next_change_event(&mut sub).await;
+		assert_eq!(event.changes.len(), 1);
+		assert!(
+			event.changes[0].metadata.is_none(),
+			"deleted keys should have no metadata"
+		);
+
+		pegboard_actor_kv::delete_all(&udb, &ups, actor_id)
+			.await
+			.expect("failed to delete all kv entries");
+
+		let event = next_change_event(&mut sub).await;
+		assert!(event.cleared);
+		assert!(event.filter(b"anything", false).is_some());
+	});
+}
+
+async fn next_change_event(sub: &mut universalpubsub::Subscriber) -> ChangeEvent {
+	let msg = tokio::time::timeout(Duration::from_secs(5), sub.next())
+		.await
+		.expect("timed out waiting for kv change event")
+		.expect("kv change subscription failed");
+	let NextOutput::Message(msg) = msg else {
+		panic!("kv change subscription ended");
+	};
+
+	ChangeEvent::deserialize(&msg.payload).expect("invalid kv change event")
+}
+
+// MARK: Quotas
+#[test]
+fn kv_quota_update() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _) = common::setup_test_namespace(ctx.leader_dc().guard_port()).await;
+
+		let response = common::update_kv_quota(
+			&namespace,
+			json!({
+				"max_storage_size": 4096,
+				"max_keys": 16,
+				"max_value_size": 1024,
+				"max_put_payload_size": 2048,
+			}),
+			ctx.leader_dc().guard_port(),
+		)
+		.await;
+		common::assert_success_response(&response);
+		let body: serde_json::Value = response.json().await.expect("Failed to parse response");
+
+		assert_eq!(body["namespace"]["kv_quota"]["max_keys"], 16);
+		assert_eq!(body["namespace"]["kv_quota"]["max_storage_size"], 4096);
+	});
+}
+
+#[test]
+fn kv_quota_invalid() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _) = common::setup_test_namespace(ctx.leader_dc().guard_port()).await;
+
+		// Value size larger than payload size
+		let response = common::update_kv_quota(
+			&namespace,
+			json!({
+				"max_storage_size": 4096,
+				"max_keys": 16,
+				"max_value_size": 4096,
+				"max_put_payload_size": 2048,
+			}),
+			ctx.leader_dc().guard_port(),
+		)
+		.await;
+		common::assert_error_response(response, "invalid_update").await;
+	});
+}
+
+#[test]
+fn kv_import_exceeds_storage_quota() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _, _runner) =
+			common::setup_test_namespace_with_runner(ctx.leader_dc()).await;
+
+		let src_actor_id = common::create_actor(&namespace, ctx.leader_dc().guard_port()).await;
+		let dst_actor_id = common::create_actor(&namespace, ctx.leader_dc().guard_port()).await;
+
+		let pools = &ctx.leader_dc().pools;
+		let udb = pools.udb().expect("udb");
+		let ups = pools.ups().expect("ups");
+
+		pegboard_actor_kv::put(
+			&udb,
+			&ups,
+			src_actor_id.parse::<Id>().expect("valid actor id"),
+			&Default::default(),
+			vec![b"a".to_vec()],
+			vec![vec![7u8; 512]],
+			None,
+		)
+		.await
+		.expect("failed to put kv entries");
+
+		let response = common::export_actor_kv(
+			&src_actor_id,
+			Some(&namespace),
+			ctx.leader_dc().api_peer_port(),
+		)
+		.await;
+		common::assert_success_response(&response);
+		let snapshot = response
+			.bytes()
+			.await
+			.expect("Failed to read snapshot")
+			.to_vec();
+
+		let response = common::update_kv_quota(
+			&namespace,
+			json!({
+				"max_storage_size": 128,
+				"max_keys": 16,
+				"max_value_size": 1024,
+				"max_put_payload_size": 2048,
+			}),
+			ctx.leader_dc().guard_port(),
+		)
+		.await;
+		common::assert_success_response(&response);
+
+		let response = common::import_actor_kv(
+			&dst_actor_id,
+			Some(&namespace),
+			snapshot,
+			false,
+			ctx.leader_dc().api_peer_port(),
+		)
+		.await;
+		common::assert_error_response(response, "storage_quota_exceeded").await;
+	});
+}
+
+// MARK: Error cases
+#[test]
+fn kv_export_wrong_namespace() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _, _runner) =
+			common::setup_test_namespace_with_runner(ctx.leader_dc()).await;
+		let (other_namespace, _) = common::setup_test_namespace(ctx.leader_dc().guard_port()).await;
+
+		let actor_id = common::create_actor(&namespace, ctx.leader_dc().guard_port()).await;
+
+		let response = common::export_actor_kv(
+			&actor_id,
+			Some(&other_namespace),
+			ctx.leader_dc().api_peer_port(),
+		)
+		.await;
+		common::assert_error_response(response, "not_found").await;
+	});
+}
+
+#[test]
+fn kv_import_invalid_snapshot() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _, _runner) =
+			common::setup_test_namespace_with_runner(ctx.leader_dc()).await;
+
+		let actor_id = common::create_actor(&namespace, ctx.leader_dc().guard_port()).await;
+
+		let response = common::import_actor_kv(
+			&actor_id,
+			Some(&namespace),
+			b"not a snapshot".to_vec(),
+			false,
+			ctx.leader_dc().api_peer_port(),
+		)
+		.await;
+		assert!(
+			!response.status().is_success(),
+			"importing an invalid snapshot should fail"
+		);
+	});
+}
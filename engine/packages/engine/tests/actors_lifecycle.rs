@@ -163,3 +163,48 @@ async fn actor_lifecycle_with_same_key_inner(ctx: &common::TestCtx, dc_choice: D
 
 	runner.shutdown().await;
 }
+
+#[test]
+fn actor_get_lifecycle() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _, runner) =
+			common::setup_test_namespace_with_runner(ctx.leader_dc()).await;
+
+		let actor_id = common::create_actor(&namespace, ctx.leader_dc().guard_port()).await;
+		let ping_response =
+			common::ping_actor_via_guard(ctx.leader_dc().guard_port(), &actor_id).await;
+		assert_eq!(ping_response["status"], "ok");
+
+		let mut lifecycle = None;
+		for _ in 0..20 {
+			lifecycle = ctx
+				.leader_dc()
+				.workflow_ctx
+				.op(pegboard::ops::actor::get_lifecycle::Input {
+					actor_id: actor_id.parse().expect("Failed to parse actor ID"),
+				})
+				.await
+				.unwrap();
+			if lifecycle.as_ref().is_some_and(|x| x.runner_id.is_some()) {
+				break;
+			}
+
+			tokio::time::sleep(Duration::from_millis(250)).await;
+		}
+
+		let lifecycle = lifecycle.expect("actor should have a lifecycle");
+		assert_eq!(lifecycle.runner_id, Some(runner.runner_id));
+		assert!(!lifecycle.sleeping, "actor should not be sleeping");
+
+		// Unknown actors have no lifecycle
+		let lifecycle = ctx
+			.leader_dc()
+			.workflow_ctx
+			.op(pegboard::ops::actor::get_lifecycle::Input {
+				actor_id: rivet_util::Id::new_v1(ctx.leader_dc().config.dc_label()),
+			})
+			.await
+			.unwrap();
+		assert!(lifecycle.is_none());
+	});
+}
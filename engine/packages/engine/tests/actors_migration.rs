@@ -0,0 +1,170 @@
+mod common;
+
+use std::time::Duration;
+
+#[test]
+fn actor_migration() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _) = common::setup_test_namespace(ctx.leader_dc().guard_port()).await;
+		let runner1 = common::setup_runner(ctx.leader_dc(), &namespace, "key-1", 1, 1).await;
+
+		let actor_id = common::create_actor(&namespace, ctx.leader_dc().guard_port()).await;
+		wait_running(&ctx, &actor_id).await;
+
+		let runner2 = common::setup_runner(ctx.leader_dc(), &namespace, "key-2", 1, 1).await;
+
+		migrate(&ctx, &actor_id).await;
+
+		let mut runner_id = None;
+		for _ in 0..20 {
+			runner_id = get_runner_id(&ctx, &actor_id).await;
+			if runner_id == Some(runner2.runner_id) {
+				break;
+			}
+
+			tokio::time::sleep(Duration::from_millis(250)).await;
+		}
+
+		assert_eq!(
+			runner_id,
+			Some(runner2.runner_id),
+			"actor should be routed to the new runner"
+		);
+
+		let ping_response =
+			common::ping_actor_via_guard(ctx.leader_dc().guard_port(), &actor_id).await;
+		assert_eq!(ping_response["status"], "ok");
+
+		// The old generation is stopped after routing switches
+		tokio::time::sleep(Duration::from_millis(500)).await;
+		assert!(
+			runner2.has_actor(&actor_id).await,
+			"new runner should have the actor"
+		);
+		assert!(
+			!runner1.has_actor(&actor_id).await,
+			"old runner should not have the actor after migrating"
+		);
+		assert_eq!(remaining_slots(&ctx, runner1.runner_id).await, 1);
+	});
+}
+
+#[test]
+fn actor_migration_abort() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _) = common::setup_test_namespace(ctx.leader_dc().guard_port()).await;
+		let runner1 = common::setup_runner(ctx.leader_dc(), &namespace, "key-1", 1, 1).await;
+
+		let actor_id = common::create_actor(&namespace, ctx.leader_dc().guard_port()).await;
+		wait_running(&ctx, &actor_id).await;
+
+		let runner2 = common::setup_runner(ctx.leader_dc(), &namespace, "key-2", 1, 1).await;
+
+		// Destroying the actor before the next generation is running aborts the migration
+		migrate(&ctx, &actor_id).await;
+		common::destroy_actor(&actor_id, &namespace, ctx.leader_dc().guard_port()).await;
+
+		tokio::time::sleep(Duration::from_millis(500)).await;
+		assert!(
+			!runner1.has_actor(&actor_id).await,
+			"old runner should not have the actor after destroy"
+		);
+		assert!(
+			!runner2.has_actor(&actor_id).await,
+			"new runner should not have the actor after the migration was aborted"
+		);
+		assert_eq!(remaining_slots(&ctx, runner1.runner_id).await, 1);
+		assert_eq!(remaining_slots(&ctx, runner2.runner_id).await, 1);
+	});
+}
+
+#[test]
+fn actor_migration_next_generation_lost() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _) = common::setup_test_namespace(ctx.leader_dc().guard_port()).await;
+		let runner1 = common::setup_runner(ctx.leader_dc(), &namespace, "key-1", 1, 1).await;
+
+		let actor_id = common::create_actor(&namespace, ctx.leader_dc().guard_port()).await;
+		wait_running(&ctx, &actor_id).await;
+
+		let runner2 = common::setup_runner(ctx.leader_dc(), &namespace, "key-2", 1, 1).await;
+
+		// Losing the next generation before it is running keeps the current generation
+		migrate(&ctx, &actor_id).await;
+		ctx.leader_dc()
+			.workflow_ctx
+			.signal(pegboard::workflows::actor::Lost {
+				generation: 1,
+				force_reschedule: false,
+			})
+			.to_workflow::<pegboard::workflows::actor::Workflow>()
+			.tag("actor_id", actor_id.parse::<rivet_util::Id>().unwrap())
+			.send()
+			.await
+			.unwrap();
+
+		tokio::time::sleep(Duration::from_millis(500)).await;
+		assert_eq!(
+			get_runner_id(&ctx, &actor_id).await,
+			Some(runner1.runner_id),
+			"actor should still be routed to the old runner"
+		);
+		assert!(
+			runner1.has_actor(&actor_id).await,
+			"old runner should still have the actor"
+		);
+		assert!(
+			!runner2.has_actor(&actor_id).await,
+			"new runner should not have the lost generation"
+		);
+		assert_eq!(remaining_slots(&ctx, runner2.runner_id).await, 1);
+
+		let ping_response =
+			common::ping_actor_via_guard(ctx.leader_dc().guard_port(), &actor_id).await;
+		assert_eq!(ping_response["status"], "ok");
+	});
+}
+
+/// Pings the actor so it is running before it is migrated.
+async fn wait_running(ctx: &common::TestCtx, actor_id: &str) {
+	let ping_response = common::ping_actor_via_guard(ctx.leader_dc().guard_port(), actor_id).await;
+	assert_eq!(ping_response["status"], "ok");
+}
+
+async fn migrate(ctx: &common::TestCtx, actor_id: &str) {
+	ctx.leader_dc()
+		.workflow_ctx
+		.signal(pegboard::workflows::actor::Migrate { generation: 0 })
+		.to_workflow::<pegboard::workflows::actor::Workflow>()
+		.tag("actor_id", actor_id.parse::<rivet_util::Id>().unwrap())
+		.send()
+		.await
+		.unwrap();
+}
+
+async fn get_runner_id(ctx: &common::TestCtx, actor_id: &str) -> Option<rivet_util::Id> {
+	ctx.leader_dc()
+		.workflow_ctx
+		.op(pegboard::ops::actor::get_runner::Input {
+			actor_ids: vec![actor_id.parse().unwrap()],
+		})
+		.await
+		.unwrap()
+		.actors
+		.first()
+		.map(|x| x.runner_id)
+}
+
+async fn remaining_slots(ctx: &common::TestCtx, runner_id: rivet_util::Id) -> u32 {
+	ctx.leader_dc()
+		.workflow_ctx
+		.op(pegboard::ops::runner::get::Input {
+			runner_ids: vec![runner_id],
+		})
+		.await
+		.unwrap()
+		.runners
+		.first()
+		.expect("runner should exist")
+		.remaining_slots
+}
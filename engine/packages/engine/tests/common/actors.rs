@@ -546,3 +546,49 @@ pub async fn ping_actor_websocket_via_guard(guard_port: u16, actor_id: &str) ->
 		"message": "WebSocket bidirectional messaging tested successfully"
 	})
 }
+
+pub async fn export_actor_kv(
+	actor_id: &str,
+	namespace: Option<&str>,
+	api_peer_port: u16,
+) -> reqwest::Response {
+	let client = reqwest::Client::new();
+	let mut url = format!(
+		"http://127.0.0.1:{}/actors/{}/kv/export",
+		api_peer_port, actor_id
+	);
+	if let Some(ns) = namespace {
+		url.push_str(&format!("?namespace={}", ns));
+	}
+
+	client
+		.get(&url)
+		.send()
+		.await
+		.expect("Failed to send export request")
+}
+
+pub async fn import_actor_kv(
+	actor_id: &str,
+	namespace: Option<&str>,
+	snapshot: Vec<u8>,
+	clear: bool,
+	api_peer_port: u16,
+) -> reqwest::Response {
+	let client = reqwest::Client::new();
+	let mut url = format!(
+		"http://127.0.0.1:{}/actors/{}/kv/import?clear={}",
+		api_peer_port, actor_id, clear
+	);
+	if let Some(ns) = namespace {
+		url.push_str(&format!("&namespace={}", ns));
+	}
+
+	client
+		.post(&url)
+		.header("content-type", "application/octet-stream")
+		.body(snapshot)
+		.send()
+		.await
+		.expect("Failed to send import request")
+}
@@ -34,3 +34,22 @@ pub async fn create_namespace(name: &str, guard_port: u16) -> rivet_util::Id {
 
 	namespace_id
 }
+
+pub async fn update_kv_quota(
+	namespace: &str,
+	kv_quota: serde_json::Value,
+	guard_port: u16,
+) -> reqwest::Response {
+	let client = reqwest::Client::new();
+	client
+		.put(format!(
+			"http://127.0.0.1:{}/namespaces/{}/kv-quota",
+			guard_port, namespace
+		))
+		.json(&serde_json::json!({
+			"kv_quota": kv_quota,
+		}))
+		.send()
+		.await
+		.expect("Failed to send kv quota update request")
+}
@@ -0,0 +1,514 @@
+mod common;
+
+use std::time::Duration;
+
+use futures_util::TryStreamExt;
+use rivet_types::{
+	actors::Resources,
+	runner_configs::{AllocationStrategy, RunnerConfig, RunnerConfigKind},
+};
+use serde_json::json;
+
+#[test]
+fn runner_allocation_spread() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, namespace_id) =
+			common::setup_test_namespace(ctx.leader_dc().guard_port()).await;
+
+		ctx.leader_dc()
+			.workflow_ctx
+			.op(namespace::ops::runner_config::upsert::Input {
+				namespace_id,
+				name: "test-runner".to_string(),
+				config: RunnerConfig {
+					kind: RunnerConfigKind::Normal {},
+					metadata: None,
+					allocation_strategy: AllocationStrategy::Spread {},
+				},
+			})
+			.await
+			.unwrap();
+
+		let _runner1 = common::setup_runner(ctx.leader_dc(), &namespace, "key-1", 1, 4).await;
+		let _runner2 = common::setup_runner(ctx.leader_dc(), &namespace, "key-2", 1, 4).await;
+
+		let (runner_id1, runner_id2) = create_two_actors(&ctx, &namespace).await;
+
+		assert_ne!(
+			runner_id1, runner_id2,
+			"actors should be spread across runners"
+		);
+	});
+}
+
+#[test]
+fn runner_allocation_bin_pack() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _) = common::setup_test_namespace(ctx.leader_dc().guard_port()).await;
+		let _runner1 = common::setup_runner(ctx.leader_dc(), &namespace, "key-1", 1, 4).await;
+		let _runner2 = common::setup_runner(ctx.leader_dc(), &namespace, "key-2", 1, 4).await;
+
+		// No runner config, defaults to bin packing
+		let (runner_id1, runner_id2) = create_two_actors(&ctx, &namespace).await;
+
+		assert_eq!(
+			runner_id1, runner_id2,
+			"actors should be packed into the same runner"
+		);
+	});
+}
+
+#[test]
+fn runner_allocation_bin_pack_skips_full_runners() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, namespace_id) =
+			common::setup_test_namespace(ctx.leader_dc().guard_port()).await;
+
+		ctx.leader_dc()
+			.workflow_ctx
+			.op(namespace::ops::runner_config::upsert::Input {
+				namespace_id,
+				name: "test-runner".to_string(),
+				config: RunnerConfig {
+					kind: RunnerConfigKind::Normal {},
+					metadata: None,
+					allocation_strategy: AllocationStrategy::BinPack {},
+				},
+			})
+			.await
+			.unwrap();
+
+		let _runner1 = common::setup_runner(ctx.leader_dc(), &namespace, "key-1", 1, 1).await;
+		let _runner2 = common::setup_runner(ctx.leader_dc(), &namespace, "key-2", 1, 1).await;
+
+		// Each runner only has one slot, so the second actor skips the full runner
+		let (runner_id1, runner_id2) = create_two_actors(&ctx, &namespace).await;
+
+		assert_ne!(
+			runner_id1, runner_id2,
+			"second actor should be allocated to the runner with a free slot"
+		);
+	});
+}
+
+#[test]
+fn runner_allocation_resources_not_advertised() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _) = common::setup_test_namespace(ctx.leader_dc().guard_port()).await;
+		let _runner = common::setup_runner(ctx.leader_dc(), &namespace, "key-1", 1, 4).await;
+
+		// The test runner does not advertise its resources, so only its slots limit allocation
+		let response = reqwest::Client::new()
+			.post(format!(
+				"http://127.0.0.1:{}/actors?namespace={namespace}",
+				ctx.leader_dc().guard_port()
+			))
+			.json(&json!({
+				"name": "test-actor",
+				"key": common::generate_unique_key(),
+				"runner_name_selector": "test-runner",
+				"crash_policy": "destroy",
+				"resources": { "cpu_millicores": 4000, "memory_mib": 8192 },
+			}))
+			.send()
+			.await
+			.expect("Failed to send actor creation request");
+		common::assert_success_response(&response);
+		let body: serde_json::Value = response.json().await.expect("Failed to parse response");
+		let actor_id = body["actor"]["actor_id"]
+			.as_str()
+			.expect("Missing actor_id in response");
+
+		let actors_res = ctx
+			.leader_dc()
+			.workflow_ctx
+			.op(pegboard::ops::actor::get_runner::Input {
+				actor_ids: vec![actor_id.parse().expect("Failed to parse actor ID")],
+			})
+			.await
+			.unwrap();
+
+		assert!(
+			actors_res.actors.first().is_some(),
+			"actor should be allocated to a runner"
+		);
+	});
+}
+
+#[test]
+fn runner_allocation_resources() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _) = common::setup_test_namespace(ctx.leader_dc().guard_port()).await;
+		let runner = common::setup_runner(ctx.leader_dc(), &namespace, "key-1", 1, 4).await;
+		let total = Resources {
+			cpu_millicores: 1000,
+			memory_mib: 1024,
+		};
+		advertise_resources(&ctx, runner.runner_id, total).await;
+
+		let request = json!({ "cpu_millicores": 600, "memory_mib": 512 });
+
+		// Allocating subtracts the requested resources
+		let actor_id1 = create_actor_id(
+			create_actor_with_resources(ctx.leader_dc().guard_port(), &namespace, request.clone())
+				.await,
+		)
+		.await;
+		assert_eq!(
+			get_runner_id(&ctx, &actor_id1).await,
+			Some(runner.runner_id)
+		);
+		assert_eq!(
+			remaining_resources(&ctx, runner.runner_id).await,
+			Resources {
+				cpu_millicores: 400,
+				memory_mib: 512,
+			}
+		);
+
+		// Fits the runner but not what it has left, so it waits for allocation
+		let actor_id2 = create_actor_id(
+			create_actor_with_resources(ctx.leader_dc().guard_port(), &namespace, request).await,
+		)
+		.await;
+		assert_eq!(get_runner_id(&ctx, &actor_id2).await, None);
+
+		// Destroying the first actor returns its resources and the waiting actor takes them
+		common::destroy_actor(&actor_id1, &namespace, ctx.leader_dc().guard_port()).await;
+
+		let mut runner_id2 = None;
+		for _ in 0..20 {
+			runner_id2 = get_runner_id(&ctx, &actor_id2).await;
+			if runner_id2.is_some() {
+				break;
+			}
+
+			tokio::time::sleep(Duration::from_millis(250)).await;
+		}
+		assert_eq!(runner_id2, Some(runner.runner_id));
+		assert_eq!(
+			remaining_resources(&ctx, runner.runner_id).await,
+			Resources {
+				cpu_millicores: 400,
+				memory_mib: 512,
+			}
+		);
+
+		common::destroy_actor(&actor_id2, &namespace, ctx.leader_dc().guard_port()).await;
+
+		let mut remaining = None;
+		for _ in 0..20 {
+			remaining = Some(remaining_resources(&ctx, runner.runner_id).await);
+			if remaining == Some(total) {
+				break;
+			}
+
+			tokio::time::sleep(Duration::from_millis(250)).await;
+		}
+		assert_eq!(
+			remaining,
+			Some(total),
+			"resources should be returned to the runner"
+		);
+	});
+}
+
+#[test]
+fn runner_allocation_resources_exceed_capacity() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _) = common::setup_test_namespace(ctx.leader_dc().guard_port()).await;
+		let runner = common::setup_runner(ctx.leader_dc(), &namespace, "key-1", 1, 4).await;
+		advertise_resources(
+			&ctx,
+			runner.runner_id,
+			Resources {
+				cpu_millicores: 1000,
+				memory_mib: 1024,
+			},
+		)
+		.await;
+
+		let response = create_actor_with_resources(
+			ctx.leader_dc().guard_port(),
+			&namespace,
+			json!({ "cpu_millicores": 2000, "memory_mib": 512 }),
+		)
+		.await;
+
+		common::assert_error_response(response, "insufficient_runner_resources").await;
+	});
+}
+
+#[test]
+fn runner_allocation_migrates_legacy_alloc_idx() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, namespace_id) =
+			common::setup_test_namespace(ctx.leader_dc().guard_port()).await;
+		let runner = common::setup_runner(ctx.leader_dc(), &namespace, "key-1", 1, 4).await;
+		let runner_id = runner.runner_id;
+
+		update_alloc_idx(
+			&ctx,
+			runner_id,
+			pegboard::ops::runner::update_alloc_idx::Action::ClearIdx,
+		)
+		.await;
+
+		// Write the entry the way it was written before the idx was ordered by remaining capacity
+		let udb = ctx.leader_dc().pools.udb().expect("udb");
+		let last_ping_ts = udb
+			.run(|tx| async move {
+				let tx = tx.with_subspace(pegboard::keys::subspace());
+
+				let last_ping_ts = tx
+					.read(
+						&pegboard::keys::runner::LastPingTsKey::new(runner_id),
+						universaldb::utils::IsolationLevel::Serializable,
+					)
+					.await?;
+				let workflow_id = tx
+					.read(
+						&pegboard::keys::runner::WorkflowIdKey::new(runner_id),
+						universaldb::utils::IsolationLevel::Serializable,
+					)
+					.await?;
+
+				tx.write(
+					&pegboard::keys::ns::RunnerAllocIdxKey::new_legacy(
+						namespace_id,
+						"test-runner".to_string(),
+						1,
+						4,
+						last_ping_ts,
+						runner_id,
+					),
+					rivet_data::converted::RunnerAllocIdxKeyData {
+						workflow_id,
+						remaining_slots: 4,
+						total_slots: 4,
+						remaining_cpu_millicores: 0,
+						total_cpu_millicores: 0,
+						remaining_memory_mib: 0,
+						total_memory_mib: 0,
+					},
+				)?;
+
+				Ok(last_ping_ts)
+			})
+			.await
+			.unwrap();
+
+		// Pinging replaces the legacy entry
+		update_alloc_idx(
+			&ctx,
+			runner_id,
+			pegboard::ops::runner::update_alloc_idx::Action::UpdatePing { rtt: 0 },
+		)
+		.await;
+
+		let (legacy_exists, entries) = udb
+			.run(|tx| async move {
+				let tx = tx.with_subspace(pegboard::keys::subspace());
+
+				let legacy_exists = tx
+					.exists(
+						&pegboard::keys::ns::RunnerAllocIdxKey::new_legacy(
+							namespace_id,
+							"test-runner".to_string(),
+							1,
+							4,
+							last_ping_ts,
+							runner_id,
+						),
+						universaldb::utils::IsolationLevel::Serializable,
+					)
+					.await?;
+
+				let runner_alloc_subspace = pegboard::keys::subspace().subspace(
+					&pegboard::keys::ns::RunnerAllocIdxKey::subspace(
+						namespace_id,
+						"test-runner".to_string(),
+					),
+				);
+				let entries = tx
+					.get_ranges_keyvalues(
+						universaldb::RangeOption {
+							mode: universaldb::options::StreamingMode::WantAll,
+							..(&runner_alloc_subspace).into()
+						},
+						universaldb::utils::IsolationLevel::Serializable,
+					)
+					.try_collect::<Vec<_>>()
+					.await?
+					.iter()
+					.map(|entry| tx.unpack::<pegboard::keys::ns::RunnerAllocIdxKey>(entry.key()))
+					.collect::<anyhow::Result<Vec<_>>>()?;
+
+				Ok((legacy_exists, entries))
+			})
+			.await
+			.unwrap();
+
+		assert!(!legacy_exists, "legacy entry should be cleared");
+		assert_eq!(entries.len(), 1, "runner should have a single entry");
+		assert_eq!(entries[0].runner_id, runner_id);
+		assert_eq!(entries[0].remaining_capacity, 1_000_000);
+	});
+}
+
+/// The test runner does not advertise resources, so they are written directly and the runner is re-added to
+/// the alloc idx.
+async fn advertise_resources(ctx: &common::TestCtx, runner_id: rivet_util::Id, total: Resources) {
+	update_alloc_idx(
+		ctx,
+		runner_id,
+		pegboard::ops::runner::update_alloc_idx::Action::ClearIdx,
+	)
+	.await;
+
+	ctx.leader_dc()
+		.pools
+		.udb()
+		.expect("udb")
+		.run(|tx| async move {
+			let tx = tx.with_subspace(pegboard::keys::subspace());
+
+			tx.write(
+				&pegboard::keys::runner::TotalResourcesKey::new(runner_id),
+				total,
+			)?;
+			tx.write(
+				&pegboard::keys::runner::RemainingResourcesKey::new(runner_id),
+				total,
+			)?;
+
+			Ok(())
+		})
+		.await
+		.unwrap();
+
+	update_alloc_idx(
+		ctx,
+		runner_id,
+		pegboard::ops::runner::update_alloc_idx::Action::AddIdx,
+	)
+	.await;
+}
+
+async fn update_alloc_idx(
+	ctx: &common::TestCtx,
+	runner_id: rivet_util::Id,
+	action: pegboard::ops::runner::update_alloc_idx::Action,
+) {
+	ctx.leader_dc()
+		.workflow_ctx
+		.op(pegboard::ops::runner::update_alloc_idx::Input {
+			runners: vec![pegboard::ops::runner::update_alloc_idx::Runner { runner_id, action }],
+		})
+		.await
+		.unwrap();
+}
+
+async fn remaining_resources(ctx: &common::TestCtx, runner_id: rivet_util::Id) -> Resources {
+	ctx.leader_dc()
+		.pools
+		.udb()
+		.expect("udb")
+		.run(|tx| async move {
+			let tx = tx.with_subspace(pegboard::keys::subspace());
+
+			tx.read(
+				&pegboard::keys::runner::RemainingResourcesKey::new(runner_id),
+				universaldb::utils::IsolationLevel::Serializable,
+			)
+			.await
+		})
+		.await
+		.unwrap()
+}
+
+async fn create_actor_with_resources(
+	guard_port: u16,
+	namespace: &str,
+	resources: serde_json::Value,
+) -> reqwest::Response {
+	reqwest::Client::new()
+		.post(format!(
+			"http://127.0.0.1:{guard_port}/actors?namespace={namespace}"
+		))
+		.json(&json!({
+			"name": "test-actor",
+			"key": common::generate_unique_key(),
+			"runner_name_selector": "test-runner",
+			"crash_policy": "destroy",
+			"resources": resources,
+		}))
+		.send()
+		.await
+		.expect("Failed to send actor creation request")
+}
+
+async fn create_actor_id(response: reqwest::Response) -> String {
+	common::assert_success_response(&response);
+
+	let body: serde_json::Value = response
+		.json()
+		.await
+		.expect("Failed to parse JSON response");
+
+	body["actor"]["actor_id"]
+		.as_str()
+		.expect("Missing actor_id in response")
+		.to_string()
+}
+
+async fn get_runner_id(ctx: &common::TestCtx, actor_id: &str) -> Option<rivet_util::Id> {
+	ctx.leader_dc()
+		.workflow_ctx
+		.op(pegboard::ops::actor::get_runner::Input {
+			actor_ids: vec![actor_id.parse().expect("Failed to parse actor ID")],
+		})
+		.await
+		.unwrap()
+		.actors
+		.first()
+		.map(|x| x.runner_id)
+}
+
+async fn create_two_actors(ctx: &common::TestCtx, namespace: &str) -> (String, String) {
+	let mut runner_ids = Vec::new();
+
+	for name in ["actor1", "actor2"] {
+		let actor_id = common::create_actor_with_options(
+			common::CreateActorOptions {
+				namespace: namespace.to_string(),
+				name: name.to_string(),
+				key: None,
+				datacenter: None,
+				..Default::default()
+			},
+			ctx.leader_dc().guard_port(),
+		)
+		.await;
+
+		common::assert_actor_exists(&actor_id, namespace, ctx.leader_dc().guard_port()).await;
+
+		let actors_res = ctx
+			.leader_dc()
+			.workflow_ctx
+			.op(pegboard::ops::actor::get_runner::Input {
+				actor_ids: vec![actor_id.parse().expect("Failed to parse actor ID")],
+			})
+			.await
+			.unwrap();
+		let runner_id = actors_res
+			.actors
+			.first()
+			.map(|x| x.runner_id.to_string())
+			.expect("actor should be allocated to a runner");
+
+		runner_ids.push(runner_id);
+	}
+
+	(runner_ids.remove(0), runner_ids.remove(0))
+}
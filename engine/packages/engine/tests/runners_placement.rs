@@ -0,0 +1,189 @@
+mod common;
+
+use serde_json::json;
+
+#[test]
+fn placement_required_labels_unsatisfiable() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _, _runner) =
+			common::setup_test_namespace_with_runner(ctx.leader_dc()).await;
+
+		let response = create_actor_with_placement(
+			ctx.leader_dc().guard_port(),
+			&namespace,
+			json!({ "labels": { "region": "gpu-less" } }),
+		)
+		.await;
+
+		common::assert_error_response(response, "no_runners_match_placement").await;
+	});
+}
+
+#[test]
+fn placement_anti_affinity() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _) = common::setup_test_namespace(ctx.leader_dc().guard_port()).await;
+		let _runner1 = common::setup_runner(ctx.leader_dc(), &namespace, "key-1", 1, 4).await;
+		let _runner2 = common::setup_runner(ctx.leader_dc(), &namespace, "key-2", 1, 4).await;
+
+		let placement = json!({ "anti_affinity_groups": ["shard-1"] });
+
+		let mut runner_ids = Vec::new();
+		for _ in 0..2 {
+			let response = create_actor_with_placement(
+				ctx.leader_dc().guard_port(),
+				&namespace,
+				placement.clone(),
+			)
+			.await;
+			common::assert_success_response(&response);
+
+			let body: serde_json::Value = response
+				.json()
+				.await
+				.expect("Failed to parse JSON response");
+			let actor_id = body["actor"]["actor_id"]
+				.as_str()
+				.expect("Missing actor_id in response")
+				.parse()
+				.expect("Failed to parse actor ID");
+
+			let actors_res = ctx
+				.leader_dc()
+				.workflow_ctx
+				.op(pegboard::ops::actor::get_runner::Input {
+					actor_ids: vec![actor_id],
+				})
+				.await
+				.unwrap();
+			let runner_id = actors_res
+				.actors
+				.first()
+				.map(|x| x.runner_id)
+				.expect("actor should be allocated to a runner");
+
+			runner_ids.push(runner_id);
+		}
+
+		// Bin packing would otherwise place both actors in the same runner
+		assert_ne!(
+			runner_ids[0], runner_ids[1],
+			"actors in the same anti-affinity group should be on different runners"
+		);
+
+		// Both runners already have an actor in the group
+		let response =
+			create_actor_with_placement(ctx.leader_dc().guard_port(), &namespace, placement).await;
+
+		common::assert_error_response(response, "anti_affinity_unsatisfiable").await;
+	});
+}
+
+#[test]
+fn placement_blocked_actor_does_not_block_queue() {
+	common::run(common::TestOpts::new(1), |ctx| async move {
+		let (namespace, _) = common::setup_test_namespace(ctx.leader_dc().guard_port()).await;
+
+		// Fill the first runner with an unconstrained actor
+		let _runner1 = common::setup_runner(ctx.leader_dc(), &namespace, "key-1", 1, 1).await;
+		let filler_id = create_actor_id(
+			create_actor_with_placement(ctx.leader_dc().guard_port(), &namespace, json!({})).await,
+		)
+		.await;
+		assert!(get_runner_id(&ctx, filler_id).await.is_some());
+
+		// The second runner takes the first actor of the group
+		let _runner2 = common::setup_runner(ctx.leader_dc(), &namespace, "key-2", 1, 4).await;
+		let placement = json!({ "anti_affinity_groups": ["shard-1"] });
+		let grouped_id = create_actor_id(
+			create_actor_with_placement(
+				ctx.leader_dc().guard_port(),
+				&namespace,
+				placement.clone(),
+			)
+			.await,
+		)
+		.await;
+		assert!(get_runner_id(&ctx, grouped_id).await.is_some());
+
+		// Satisfiable by the first runner once it has a free slot, so it waits for allocation
+		let blocked_id = create_actor_id(
+			create_actor_with_placement(ctx.leader_dc().guard_port(), &namespace, placement).await,
+		)
+		.await;
+
+		// The second runner still has slots for actors without constraints
+		let unconstrained_id = create_actor_id(
+			create_actor_with_placement(ctx.leader_dc().guard_port(), &namespace, json!({})).await,
+		)
+		.await;
+
+		let mut unconstrained_runner_id = None;
+		for _ in 0..20 {
+			unconstrained_runner_id = get_runner_id(&ctx, unconstrained_id).await;
+			if unconstrained_runner_id.is_some() {
+				break;
+			}
+
+			tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+		}
+
+		assert!(
+			unconstrained_runner_id.is_some(),
+			"unconstrained actor should not wait behind the blocked actor"
+		);
+		assert!(
+			get_runner_id(&ctx, blocked_id).await.is_none(),
+			"blocked actor should still be waiting for allocation"
+		);
+	});
+}
+
+async fn create_actor_id(response: reqwest::Response) -> rivet_util::Id {
+	common::assert_success_response(&response);
+
+	let body: serde_json::Value = response
+		.json()
+		.await
+		.expect("Failed to parse JSON response");
+
+	body["actor"]["actor_id"]
+		.as_str()
+		.expect("Missing actor_id in response")
+		.parse()
+		.expect("Failed to parse actor ID")
+}
+
+async fn get_runner_id(ctx: &common::TestCtx, actor_id: rivet_util::Id) -> Option<rivet_util::Id> {
+	ctx.leader_dc()
+		.workflow_ctx
+		.op(pegboard::ops::actor::get_runner::Input {
+			actor_ids: vec![actor_id],
+		})
+		.await
+		.unwrap()
+		.actors
+		.first()
+		.map(|x| x.runner_id)
+}
+
+async fn create_actor_with_placement(
+	guard_port: u16,
+	namespace: &str,
+	placement: serde_json::Value,
+) -> reqwest::Response {
+	reqwest::Client::new()
+		.post(format!(
+			"http://127.0.0.1:{guard_port}/actors?namespace={namespace}"
+		))
+		.json(&json!({
+			"name": "test-actor",
+			"key": common::generate_unique_key(),
+			"runner_name_selector": "test-runner",
+			"crash_policy": "destroy",
+			"placement": placement,
+		}))
+		.send()
+		.await
+		.expect("Failed to send actor creation request")
+}
@@ -29,6 +29,11 @@ impl Config {
 	}
 }
 
+#[derive(Default)]
+struct WorkflowConfig {
+	migrations: Option<syn::Path>,
+}
+
 struct MessageConfig {
 	tail_ttl: u64,
 }
@@ -47,9 +52,10 @@ pub fn workflow(attr: TokenStream, item: TokenStream) -> TokenStream {
 		.unwrap_or_else(|| "Workflow".to_string());
 	let item_fn = parse_macro_input!(item as ItemFn);
 
-	if let Err(err) = parse_empty_config(&item_fn.attrs) {
-		return err.into_compile_error().into();
-	}
+	let config = match parse_workflow_config(&item_fn.attrs) {
+		Ok(x) => x,
+		Err(err) => return err.into_compile_error().into(),
+	};
 
 	let ctx_ty = syn::parse_str("&mut WorkflowCtx").unwrap();
 	let TraitFnOutput {
@@ -77,6 +83,14 @@ pub fn workflow(attr: TokenStream, item: TokenStream) -> TokenStream {
 	let fn_body = item_fn.block;
 	let vis = item_fn.vis;
 
+	let migrations_fn = config.migrations.map(|path| {
+		quote! {
+			fn migrations() -> gas::migration::Migrations {
+				#path()
+			}
+		}
+	});
+
 	let expanded = quote! {
 		#vis struct #struct_ident;
 
@@ -91,6 +105,8 @@ pub fn workflow(attr: TokenStream, item: TokenStream) -> TokenStream {
 
 			const NAME: &'static str = #fn_name;
 
+			#migrations_fn
+
 			async fn run(#ctx_ident: #ctx_ty, #input_ident: &Self::Input) -> Result<Self::Output> {
 				#fn_body
 			}
@@ -492,7 +508,9 @@ fn parse_msg_config(attrs: &[syn::Attribute]) -> syn::Result<MessageConfig> {
 	Ok(config)
 }
 
-fn parse_empty_config(attrs: &[syn::Attribute]) -> syn::Result<()> {
+fn parse_workflow_config(attrs: &[syn::Attribute]) -> syn::Result<WorkflowConfig> {
+	let mut config = WorkflowConfig::default();
+
 	for attr in attrs {
 		let syn::Meta::NameValue(name_value) = &attr.meta else {
 			continue;
@@ -500,7 +518,12 @@ fn parse_empty_config(attrs: &[syn::Attribute]) -> syn::Result<()> {
 
 		let ident = name_value.path.require_ident()?;
 
-		if ident != "doc" {
+		// Verify config property
+		if ident == "migrations" {
+			config.migrations = Some(syn::parse::<syn::Path>(
+				name_value.value.to_token_stream().into(),
+			)?);
+		} else if ident != "doc" {
 			return Err(syn::Error::new(
 				name_value.span(),
 				format!("Unknown config property `{ident}`"),
@@ -508,7 +531,7 @@ fn parse_empty_config(attrs: &[syn::Attribute]) -> syn::Result<()> {
 		}
 	}
 
-	Ok(())
+	Ok(config)
 }
 
 struct OptionalIdent {
@@ -0,0 +1,81 @@
+use anyhow::Result;
+use rivet_util::Id;
+
+use crate::{builder::BuilderError, db::DatabaseHandle};
+
+pub struct CancelBuilder {
+	db: DatabaseHandle,
+	config: rivet_config::Config,
+	ray_id: Id,
+	workflow_id: Id,
+	reason: Option<String>,
+	propagate: bool,
+	error: Option<BuilderError>,
+}
+
+impl CancelBuilder {
+	pub(crate) fn new(
+		db: DatabaseHandle,
+		config: rivet_config::Config,
+		ray_id: Id,
+		workflow_id: Id,
+		from_workflow: bool,
+	) -> Self {
+		CancelBuilder {
+			db,
+			config,
+			ray_id,
+			workflow_id,
+			reason: None,
+			propagate: false,
+			error: from_workflow.then_some(BuilderError::CannotDispatchFromOpInWorkflow),
+		}
+	}
+
+	/// Reason given to the workflow with the cancellation.
+	pub fn reason(mut self, reason: impl ToString) -> Self {
+		if self.error.is_some() {
+			return self;
+		}
+
+		self.reason = Some(reason.to_string());
+
+		self
+	}
+
+	/// Also cancels all sub workflows dispatched by this workflow (recursively).
+	pub fn propagate(mut self) -> Self {
+		if self.error.is_some() {
+			return self;
+		}
+
+		self.propagate = true;
+
+		self
+	}
+
+	/// Returns the ids of all workflows that were cancelled.
+	#[tracing::instrument(skip_all, fields(workflow_id=%self.workflow_id))]
+	pub async fn send(self) -> Result<Vec<Id>> {
+		if let Some(err) = self.error {
+			return Err(err.into());
+		}
+
+		tracing::debug!(reason=?self.reason, propagate=%self.propagate, "cancelling workflow");
+
+		let signal_id = Id::new_v1(self.config.dc_label());
+
+		let workflow_ids = self
+			.db
+			.cancel_workflow(
+				self.ray_id,
+				self.workflow_id,
+				signal_id,
+				self.reason.as_deref(),
+				self.propagate,
+			)
+			.await?;
+
+		Ok(workflow_ids)
+	}
+}
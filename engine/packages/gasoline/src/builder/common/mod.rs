@@ -1,5 +1,7 @@
 //! This module contains builders used by all ctx's besides the workflow ctx.
 
+pub mod cancel;
 pub mod message;
+pub mod schedule;
 pub mod signal;
 pub mod workflow;
@@ -0,0 +1,107 @@
+use std::{fmt::Display, marker::PhantomData};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{
+	builder::BuilderError,
+	db::DatabaseHandle,
+	error::WorkflowError,
+	workflow::{Workflow, WorkflowInput},
+};
+
+pub struct ScheduleBuilder<I: WorkflowInput> {
+	db: DatabaseHandle,
+	name: String,
+	cron: String,
+	input: I,
+	tags: serde_json::Map<String, serde_json::Value>,
+	error: Option<BuilderError>,
+	_marker: PhantomData<I>,
+}
+
+impl<I> ScheduleBuilder<I>
+where
+	I: WorkflowInput,
+	<I as WorkflowInput>::Workflow: Workflow<Input = I>,
+{
+	pub(crate) fn new(
+		db: DatabaseHandle,
+		name: impl ToString,
+		cron: impl ToString,
+		input: I,
+		from_workflow: bool,
+	) -> Self {
+		ScheduleBuilder {
+			db,
+			name: name.to_string(),
+			cron: cron.to_string(),
+			input,
+			tags: serde_json::Map::new(),
+			error: from_workflow.then_some(BuilderError::CannotDispatchFromOpInWorkflow),
+			_marker: PhantomData,
+		}
+	}
+
+	/// Tags of every workflow dispatched by this schedule.
+	pub fn tags(mut self, tags: serde_json::Value) -> Self {
+		if self.error.is_some() {
+			return self;
+		}
+
+		match tags {
+			serde_json::Value::Object(map) => {
+				self.tags.extend(map);
+			}
+			_ => self.error = Some(BuilderError::TagsNotMap),
+		}
+
+		self
+	}
+
+	pub fn tag(mut self, k: impl Display, v: impl Serialize) -> Self {
+		if self.error.is_some() {
+			return self;
+		}
+
+		match serde_json::to_value(&v) {
+			Ok(v) => {
+				self.tags.insert(k.to_string(), v);
+			}
+			Err(err) => self.error = Some(err.into()),
+		}
+
+		self
+	}
+
+	/// Creates the schedule or updates the existing schedule with the same name. Safe to call on every
+	/// startup.
+	#[tracing::instrument(skip_all, fields(schedule_name=%self.name, workflow_name=I::Workflow::NAME))]
+	pub async fn register(self) -> Result<()> {
+		if let Some(err) = self.error {
+			return Err(err.into());
+		}
+
+		let no_tags = self.tags.is_empty();
+		let tags = serde_json::Value::Object(self.tags);
+		let tags = if no_tags { None } else { Some(&tags) };
+
+		tracing::debug!(cron=%self.cron, ?tags, "registering schedule");
+
+		let input_val = serde_json::value::to_raw_value(&self.input)
+			.map_err(WorkflowError::SerializeWorkflowInput)?;
+
+		self.db
+			.upsert_schedule(
+				&self.name,
+				I::Workflow::NAME,
+				&self.cron,
+				tags,
+				&input_val,
+				I::Workflow::migrations().version(),
+			)
+			.await?;
+
+		Ok(())
+	}
+}
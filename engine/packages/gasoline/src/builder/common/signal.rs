@@ -18,6 +18,7 @@ pub struct SignalBuilder<T: Signal + Serialize> {
 	to_workflow_name: Option<&'static str>,
 	to_workflow_id: Option<Id>,
 	tags: serde_json::Map<String, serde_json::Value>,
+	idempotency_key: Option<String>,
 	error: Option<BuilderError>,
 }
 
@@ -37,6 +38,7 @@ impl<T: Signal + Serialize> SignalBuilder<T> {
 			to_workflow_name: None,
 			to_workflow_id: None,
 			tags: serde_json::Map::new(),
+			idempotency_key: None,
 			error: from_workflow.then_some(BuilderError::CannotDispatchFromOpInWorkflow),
 		}
 	}
@@ -101,6 +103,18 @@ impl<T: Signal + Serialize> SignalBuilder<T> {
 		self
 	}
 
+	/// Deduplicates this signal against signals with the same key recently sent to the same workflow. If a
+	/// duplicate is found, `send` returns the id of the original signal instead of sending a new one.
+	pub fn idempotency_key(mut self, idempotency_key: impl Display) -> Self {
+		if self.error.is_some() {
+			return self;
+		}
+
+		self.idempotency_key = Some(idempotency_key.to_string());
+
+		self
+	}
+
 	#[tracing::instrument(skip_all, fields(signal_name=T::NAME, signal_id))]
 	pub async fn send(self) -> Result<Id> {
 		if let Some(err) = self.error {
@@ -110,13 +124,11 @@ impl<T: Signal + Serialize> SignalBuilder<T> {
 		let signal_id = Id::new_v1(self.config.dc_label());
 		let start_instant = Instant::now();
 
-		tracing::Span::current().record("signal_id", signal_id.to_string());
-
 		// Serialize input
 		let input_val = serde_json::value::to_raw_value(&self.body)
 			.map_err(WorkflowError::SerializeSignalBody)?;
 
-		match (
+		let signal_id = match (
 			self.to_workflow_name,
 			self.to_workflow_id,
 			self.tags.is_empty(),
@@ -135,15 +147,29 @@ impl<T: Signal + Serialize> SignalBuilder<T> {
 					.ok_or(WorkflowError::WorkflowNotFound)?;
 
 				self.db
-					.publish_signal(self.ray_id, workflow_id, signal_id, T::NAME, &input_val)
-					.await?;
+					.publish_signal(
+						self.ray_id,
+						workflow_id,
+						signal_id,
+						T::NAME,
+						&input_val,
+						self.idempotency_key.as_deref(),
+					)
+					.await?
 			}
 			(None, Some(workflow_id), true) => {
 				tracing::debug!(to_workflow_id=%workflow_id, "dispatching signal via workflow id");
 
 				self.db
-					.publish_signal(self.ray_id, workflow_id, signal_id, T::NAME, &input_val)
-					.await?;
+					.publish_signal(
+						self.ray_id,
+						workflow_id,
+						signal_id,
+						T::NAME,
+						&input_val,
+						self.idempotency_key.as_deref(),
+					)
+					.await?
 			}
 			(None, None, false) => {
 				return Err(BuilderError::InvalidSignalSend(
@@ -169,7 +195,9 @@ impl<T: Signal + Serialize> SignalBuilder<T> {
 				)
 				.into());
 			}
-		}
+		};
+
+		tracing::Span::current().record("signal_id", signal_id.to_string());
 
 		let dt = start_instant.elapsed().as_secs_f64();
 		metrics::SIGNAL_SEND_DURATION.record(
@@ -126,6 +126,7 @@ where
 				workflow_name,
 				tags,
 				&input_val,
+				I::Workflow::migrations().version(),
 				self.unique,
 			)
 			.await?;
@@ -0,0 +1,86 @@
+use anyhow::Result;
+use rivet_util::Id;
+
+use crate::{cancel::Cancel, ctx::WorkflowCtx, history::cursor::HistoryResult, signal::Signal};
+
+pub struct CancelBuilder<'a> {
+	ctx: &'a mut WorkflowCtx,
+	version: usize,
+
+	workflow_id: Id,
+	reason: Option<String>,
+	propagate: bool,
+}
+
+impl<'a> CancelBuilder<'a> {
+	pub(crate) fn new(ctx: &'a mut WorkflowCtx, version: usize, workflow_id: Id) -> Self {
+		CancelBuilder {
+			ctx,
+			version,
+
+			workflow_id,
+			reason: None,
+			propagate: false,
+		}
+	}
+
+	/// Reason given to the workflow with the cancellation.
+	pub fn reason(mut self, reason: impl ToString) -> Self {
+		self.reason = Some(reason.to_string());
+
+		self
+	}
+
+	/// Also cancels all sub workflows dispatched by this workflow (recursively).
+	pub fn propagate(mut self) -> Self {
+		self.propagate = true;
+
+		self
+	}
+
+	#[tracing::instrument(skip_all, fields(workflow_id=%self.workflow_id))]
+	pub async fn send(self) -> Result<()> {
+		self.ctx.check_stop()?;
+
+		// Error for version mismatch. This is done in the builder instead of in `VersionedWorkflowCtx` to
+		// defer the error.
+		self.ctx.compare_version("cancel", self.version)?;
+
+		let history_res = self
+			.ctx
+			.cursor()
+			.compare_signal_send(self.version, Cancel::NAME)?;
+		let location = self.ctx.cursor().current_location_for(&history_res);
+
+		// Cancelled before
+		if let HistoryResult::Event(_) = history_res {
+			tracing::debug!("replaying cancellation");
+		}
+		// Cancel workflow
+		else {
+			tracing::debug!(reason=?self.reason, propagate=%self.propagate, "cancelling workflow");
+
+			let signal_id = Id::new_v1(self.ctx.config().dc_label());
+
+			self.ctx
+				.db()
+				.cancel_workflow_from_workflow(
+					self.ctx.workflow_id(),
+					&location,
+					self.version,
+					self.ctx.ray_id(),
+					self.workflow_id,
+					signal_id,
+					self.reason.as_deref(),
+					self.propagate,
+					self.ctx.loop_location(),
+				)
+				.await?;
+		}
+
+		// Move to next event
+		self.ctx.cursor_mut().update(&location);
+
+		Ok(())
+	}
+}
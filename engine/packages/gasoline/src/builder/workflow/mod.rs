@@ -1,5 +1,6 @@
 //! This module contains builders used specifically by the workflow ctx.
 
+pub mod cancel;
 pub mod message;
 pub mod signal;
 pub mod sub_workflow;
@@ -18,6 +18,7 @@ pub struct SignalBuilder<'a, T: Signal + Serialize> {
 	to_workflow_name: Option<&'static str>,
 	to_workflow_id: Option<Id>,
 	tags: serde_json::Map<String, serde_json::Value>,
+	idempotency_key: Option<String>,
 	error: Option<BuilderError>,
 }
 
@@ -31,6 +32,7 @@ impl<'a, T: Signal + Serialize> SignalBuilder<'a, T> {
 			to_workflow_name: None,
 			to_workflow_id: None,
 			tags: serde_json::Map::new(),
+			idempotency_key: None,
 			error: None,
 		}
 	}
@@ -85,6 +87,17 @@ impl<'a, T: Signal + Serialize> SignalBuilder<'a, T> {
 		self
 	}
 
+	/// See `builder::common::signal::SignalBuilder::idempotency_key`.
+	pub fn idempotency_key(mut self, idempotency_key: impl Display) -> Self {
+		if self.error.is_some() {
+			return self;
+		}
+
+		self.idempotency_key = Some(idempotency_key.to_string());
+
+		self
+	}
+
 	#[tracing::instrument(skip_all, fields(signal_name=T::NAME, signal_id))]
 	pub async fn send(self) -> Result<Id> {
 		self.ctx.check_stop()?;
@@ -118,7 +131,7 @@ impl<'a, T: Signal + Serialize> SignalBuilder<'a, T> {
 			let input_val = serde_json::value::to_raw_value(&self.body)
 				.map_err(WorkflowError::SerializeSignalBody)?;
 
-			match (
+			let signal_id = match (
 				self.to_workflow_name,
 				self.to_workflow_id,
 				self.tags.is_empty(),
@@ -147,9 +160,10 @@ impl<'a, T: Signal + Serialize> SignalBuilder<'a, T> {
 							signal_id,
 							T::NAME,
 							&input_val,
+							self.idempotency_key.as_deref(),
 							self.ctx.loop_location(),
 						)
-						.await?;
+						.await?
 				}
 				(None, Some(workflow_id), true) => {
 					tracing::debug!(
@@ -168,9 +182,10 @@ impl<'a, T: Signal + Serialize> SignalBuilder<'a, T> {
 							signal_id,
 							T::NAME,
 							&input_val,
+							self.idempotency_key.as_deref(),
 							self.ctx.loop_location(),
 						)
-						.await?;
+						.await?
 				}
 				(None, None, false) => {
 					return Err(BuilderError::InvalidSignalSend(
@@ -196,7 +211,7 @@ impl<'a, T: Signal + Serialize> SignalBuilder<'a, T> {
 					)
 					.into());
 				}
-			}
+			};
 
 			let dt = start_instant.elapsed().as_secs_f64();
 			metrics::SIGNAL_SEND_DURATION.record(
@@ -158,6 +158,14 @@ where
 			let input_val = serde_json::value::to_raw_value(input)
 				.map_err(WorkflowError::SerializeWorkflowOutput)?;
 
+			// Span for the dispatch. The sub workflow persists its context and links each of its runs to it
+			let dispatch_span = tracing::info_span!(
+				"sub_workflow_dispatch",
+				%sub_workflow_name,
+				%sub_workflow_id,
+				unique,
+			);
+
 			let actual_sub_workflow_id = ctx
 				.db()
 				.dispatch_sub_workflow(
@@ -169,9 +177,11 @@ where
 					sub_workflow_name,
 					tags.as_ref(),
 					&input_val,
+					I::Workflow::migrations().version(),
 					ctx.loop_location(),
 					unique,
 				)
+				.instrument(dispatch_span)
 				.await?;
 
 			if unique {
@@ -285,6 +295,8 @@ where
 
 			if let Some(output) = workflow.parse_output::<<I as WorkflowInput>::Workflow>()? {
 				return Ok(output);
+			} else if workflow.termination.is_some() {
+				return Err(WorkflowError::SubWorkflowTerminated(sub_workflow_id).into());
 			} else {
 				if retries == 0 {
 					return Err(WorkflowError::SubWorkflowIncomplete(sub_workflow_id).into());
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{error::WorkflowError, signal::Signal};
+
+/// Signal sent to a workflow to request that it stops. The workflow receives it as a
+/// `WorkflowError::Cancelled` error at its next `listen`, `sleep`, or `activity` call, which it can catch
+/// to run cleanup steps. Cancellations that are not caught terminate the workflow.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cancel {
+	pub reason: Option<String>,
+}
+
+impl Signal for Cancel {
+	const NAME: &'static str = "gasoline_cancel";
+}
+
+/// Terminal state of a workflow that was cancelled or terminated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Termination {
+	pub ts: i64,
+	pub reason: Option<String>,
+	/// Set if the workflow was terminated from outside instead of stopping by itself after a cancellation.
+	pub forced: bool,
+}
+
+/// Returns the cancellation if the error was caused by one.
+///
+/// Example:
+/// ```rust,ignore
+/// match ctx.activity(MyActivityInput {}).await {
+/// 	Ok(x) => x,
+/// 	Err(err) if gas::cancel::cancellation(&err).is_some() => {
+/// 		ctx.activity(CleanupInput {}).await?;
+///
+/// 		return Err(err);
+/// 	}
+/// 	Err(err) => return Err(err),
+/// }
+/// ```
+pub fn cancellation(err: &anyhow::Error) -> Option<&Cancel> {
+	err.chain()
+		.find_map(|x| x.downcast_ref::<WorkflowError>())
+		.and_then(|err| match err {
+			WorkflowError::Cancelled(cancel) => Some(cancel),
+			_ => None,
+		})
+}
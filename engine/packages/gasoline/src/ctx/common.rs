@@ -10,10 +10,11 @@ pub const RETRY_TIMEOUT_MS: usize = 1000;
 pub const WORKFLOW_TIMEOUT: Duration = Duration::from_secs(60);
 
 use crate::{
-	ctx::OperationCtx,
+	ctx::{OperationCtx, QueryCtx},
 	db::{DatabaseHandle, WorkflowData},
 	error::WorkflowError,
 	operation::{Operation, OperationInput},
+	query::{Query, QueryInput},
 	utils::tags::AsTags,
 	workflow::Workflow,
 };
@@ -43,6 +44,8 @@ pub async fn wait_for_workflow_output<W: Workflow>(
 				.ok_or(WorkflowError::WorkflowNotFound)?;
 			if let Some(output) = workflow.parse_output::<W>()? {
 				return Ok(output);
+			} else if workflow.termination.is_some() {
+				return Err(WorkflowError::SubWorkflowTerminated(workflow_id).into());
 			}
 
 			// Poll and wait for a wake at the same time
@@ -149,3 +152,42 @@ where
 
 	res.map_err(Into::into)
 }
+
+pub async fn query<I>(
+	db: &DatabaseHandle,
+	workflow_id: Id,
+	input: I,
+) -> Result<<<I as QueryInput>::Query as Query>::Output>
+where
+	I: QueryInput,
+	<I as QueryInput>::Query: Query<Input = I>,
+{
+	tracing::debug!(?workflow_id, ?input, "query call");
+
+	let mut data = db
+		.get_workflow_query_data(workflow_id)
+		.await?
+		.ok_or(WorkflowError::WorkflowNotFound)?;
+
+	let workflow_name = <<I as QueryInput>::Query as Query>::Workflow::NAME;
+	if data.workflow_name != workflow_name {
+		return Err(WorkflowError::QueryWorkflowMismatch(workflow_name, data.workflow_name).into());
+	}
+
+	// Workflows that have not been pulled since a migration was added still have old input and state. These
+	// are migrated in memory only, the worker persists them
+	let migrations = <<I as QueryInput>::Query as Query>::Workflow::migrations();
+	if data.schema_version != migrations.version() {
+		(data.input, data.state) = migrations
+			.migrate(data.schema_version, &data.input, &data.state)
+			.map_err(WorkflowError::MigrationFailed)?;
+	}
+
+	let ctx = QueryCtx::new(workflow_id, data);
+
+	let res = I::Query::handle(&ctx, &input).map_err(WorkflowError::QueryFailure)?;
+
+	tracing::debug!(?res, "query response");
+
+	Ok(res)
+}
@@ -1,12 +1,16 @@
 use rivet_metrics::KeyValue;
 use std::{ops::Deref, time::Instant};
+use tracing::Instrument;
 
 use crate::{
+	cancel::Cancel,
 	ctx::WorkflowCtx,
 	db::SignalData,
 	error::{WorkflowError, WorkflowResult},
 	history::location::Location,
 	metrics,
+	signal::Signal,
+	utils,
 };
 
 /// Indirection struct to prevent invalid implementations of listen traits.
@@ -47,8 +51,23 @@ impl<'a> ListenCtx<'a> {
 			self.used = true;
 		}
 
+		// Cancellations are always listened for
+		let signal_names = signal_names
+			.iter()
+			.copied()
+			.chain(std::iter::once(Cancel::NAME))
+			.collect::<Vec<_>>();
+
 		let start_instant = Instant::now();
 
+		// Span for the receipt, covers pulling the signal. Linked to the span the signal was published in
+		let span = tracing::info_span!(
+			"signal_receive",
+			signal_id = tracing::field::Empty,
+			signal_name = tracing::field::Empty,
+			recv_lag = tracing::field::Empty,
+		);
+
 		// Fetch new pending signal
 		let signal = self
 			.ctx
@@ -56,12 +75,13 @@ impl<'a> ListenCtx<'a> {
 			.pull_next_signal(
 				self.ctx.workflow_id(),
 				self.ctx.name(),
-				signal_names,
+				&signal_names,
 				self.location,
 				self.ctx.version(),
 				self.ctx.loop_location(),
 				self.last_try,
 			)
+			.instrument(span.clone())
 			.await?;
 
 		let dt = start_instant.elapsed().as_secs_f64();
@@ -102,11 +122,18 @@ impl<'a> ListenCtx<'a> {
 			);
 		}
 
-		tracing::debug!(
-			signal_id=%signal.signal_id,
-			signal_name=%signal.signal_name,
-			"signal received",
-		);
+		span.record("signal_id", signal.signal_id.to_string());
+		span.record("signal_name", signal.signal_name.as_str());
+		span.record("recv_lag", recv_lag);
+		utils::trace::link(&span, signal.trace_context.as_deref());
+		span.in_scope(|| tracing::debug!("signal received"));
+
+		if signal.signal_name == Cancel::NAME {
+			let cancel = serde_json::from_str(signal.body.get())
+				.map_err(WorkflowError::DeserializeSignalBody)?;
+
+			return Err(WorkflowError::Cancelled(cancel));
+		}
 
 		Ok(signal)
 	}
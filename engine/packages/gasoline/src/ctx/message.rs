@@ -115,6 +115,7 @@ impl MessageCtx {
 			tags: tags.as_tags()?,
 			ts,
 			body: &body_buf,
+			trace_context: utils::trace::current_trace_context(),
 		};
 		let message_buf = serde_json::to_vec(&message).map_err(WorkflowError::SerializeMessage)?;
 
@@ -323,6 +324,11 @@ where
 
 			// Check if the subscription tags match a subset of the message tags
 			if utils::is_value_subset(&self.tags, &message_wrapper.tags) {
+				utils::trace::link(
+					&tracing::Span::current(),
+					message_wrapper.trace_context.as_deref(),
+				);
+
 				let message = PubsubMessage::<M>::deserialize_from_wrapper(message_wrapper)?;
 				tracing::debug!(?message, "received message");
 
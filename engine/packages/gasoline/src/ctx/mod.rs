@@ -3,6 +3,7 @@ pub(crate) mod common;
 mod listen;
 pub mod message;
 mod operation;
+mod query;
 mod standalone;
 pub mod test;
 mod versioned_workflow;
@@ -12,6 +13,7 @@ pub use activity::ActivityCtx;
 pub use listen::ListenCtx;
 pub use message::MessageCtx;
 pub use operation::OperationCtx;
+pub use query::QueryCtx;
 pub use standalone::StandaloneCtx;
 pub use test::TestCtx;
 pub use versioned_workflow::VersionedWorkflowCtx;
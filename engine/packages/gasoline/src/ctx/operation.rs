@@ -12,6 +12,7 @@ use crate::{
 	error::WorkflowResult,
 	message::Message,
 	operation::{Operation, OperationInput},
+	query::{Query, QueryInput},
 	signal::Signal,
 	utils::tags::AsTags,
 	workflow::{Workflow, WorkflowInput},
@@ -111,6 +112,38 @@ impl OperationCtx {
 		)
 	}
 
+	/// Creates a builder for a schedule that dispatches a workflow on every tick of the given cron expression.
+	/// Schedules are identified by their name.
+	pub fn schedule<I>(
+		&self,
+		name: impl ToString,
+		cron: impl ToString,
+		input: I,
+	) -> builder::schedule::ScheduleBuilder<I>
+	where
+		I: WorkflowInput,
+		<I as WorkflowInput>::Workflow: Workflow<Input = I>,
+	{
+		builder::schedule::ScheduleBuilder::new(
+			self.db.clone(),
+			name,
+			cron,
+			input,
+			self.from_workflow,
+		)
+	}
+
+	/// Creates a workflow cancellation builder.
+	pub fn cancel(&self, workflow_id: Id) -> builder::cancel::CancelBuilder {
+		builder::cancel::CancelBuilder::new(
+			self.db.clone(),
+			self.config.clone(),
+			self.ray_id,
+			workflow_id,
+			self.from_workflow,
+		)
+	}
+
 	#[tracing::instrument(skip_all, fields(operation_name=I::Operation::NAME))]
 	pub async fn op<I>(
 		&self,
@@ -133,6 +166,22 @@ impl OperationCtx {
 		.await
 	}
 
+	/// Runs a read-only query against the persisted state of a workflow.
+	#[tracing::instrument(skip_all, fields(query_name=I::Query::NAME, %workflow_id))]
+	pub async fn query<I>(
+		&self,
+		workflow_id: Id,
+		input: I,
+	) -> Result<<<I as QueryInput>::Query as Query>::Output>
+	where
+		I: QueryInput,
+		<I as QueryInput>::Query: Query<Input = I>,
+	{
+		common::query(&self.db, workflow_id, input)
+			.in_current_span()
+			.await
+	}
+
 	/// Creates a message builder.
 	pub fn msg<M: Message>(&self, body: M) -> builder::message::MessageBuilder<M> {
 		builder::message::MessageBuilder::new(self.msg_ctx.clone(), body)
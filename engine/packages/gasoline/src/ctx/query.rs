@@ -0,0 +1,58 @@
+use rivet_util::Id;
+use serde::de::DeserializeOwned;
+
+use crate::{
+	db::QueryData,
+	error::{WorkflowError, WorkflowResult},
+};
+
+/// Read-only view of a workflow's persisted data given to query handlers.
+pub struct QueryCtx {
+	workflow_id: Id,
+	data: QueryData,
+}
+
+impl QueryCtx {
+	pub(crate) fn new(workflow_id: Id, data: QueryData) -> Self {
+		QueryCtx { workflow_id, data }
+	}
+}
+
+impl QueryCtx {
+	pub fn workflow_id(&self) -> Id {
+		self.workflow_id
+	}
+
+	pub fn workflow_name(&self) -> &str {
+		&self.data.workflow_name
+	}
+
+	pub fn input<T: DeserializeOwned>(&self) -> WorkflowResult<T> {
+		serde_json::from_str(self.data.input.get()).map_err(WorkflowError::DeserializeWorkflowInput)
+	}
+
+	/// Workflow state set with `ctx.state`. Deserializes from `null` if the state was never set.
+	pub fn state<T: DeserializeOwned>(&self) -> WorkflowResult<T> {
+		serde_json::from_str(self.data.state.get()).map_err(WorkflowError::DeserializeWorkflowState)
+	}
+
+	/// Output of the workflow, if it has completed.
+	pub fn output<T: DeserializeOwned>(&self) -> WorkflowResult<Option<T>> {
+		self.data
+			.output
+			.as_ref()
+			.map(|x| serde_json::from_str(x.get()))
+			.transpose()
+			.map_err(WorkflowError::DeserializeWorkflowOutput)
+	}
+
+	/// State of the latest top level loop (`ctx.loope`) of the workflow as of its last completed iteration.
+	pub fn loop_state<T: DeserializeOwned>(&self) -> WorkflowResult<Option<T>> {
+		self.data
+			.loop_state
+			.as_ref()
+			.map(|x| serde_json::from_str(x.get()))
+			.transpose()
+			.map_err(WorkflowError::DeserializeLoopState)
+	}
+}
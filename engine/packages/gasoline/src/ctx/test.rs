@@ -13,6 +13,8 @@ use crate::{
 	message::Message,
 	operation::{Operation, OperationInput},
 	prelude::*,
+	query::{Query, QueryInput},
+	replay::{ReplayReport, Replayer},
 	signal::Signal,
 	utils::tags::AsTags,
 	workflow::{Workflow, WorkflowInput},
@@ -89,6 +91,10 @@ impl TestCtx {
 		})
 	}
 
+	pub fn db(&self) -> &DatabaseHandle {
+		&self.db
+	}
+
 	pub fn debug_db(&self) -> &dyn DatabaseDebug {
 		&*self.debug_db
 	}
@@ -156,6 +162,27 @@ impl TestCtx {
 			.await
 	}
 
+	/// Replays the history of a workflow against the given registry without running activities. Used to test
+	/// that changes to workflow code are compatible with history recorded by a previous version.
+	#[tracing::instrument(skip_all, fields(%workflow_id))]
+	pub async fn replay(&self, reg: Registry, workflow_id: Id) -> Result<ReplayReport> {
+		let history = self
+			.debug_db
+			.get_workflow_history(workflow_id, false)
+			.await?
+			.context("workflow not found")?;
+
+		let replayer = Replayer::new(
+			reg.handle(),
+			self.config.clone(),
+			self.pools.clone(),
+			self.cache.clone(),
+		)
+		.with_db(self.db.clone());
+
+		Ok(replayer.replay(history).await)
+	}
+
 	/// Creates a signal builder.
 	pub fn signal<T: Signal + Serialize>(&self, body: T) -> builder::signal::SignalBuilder<T> {
 		builder::signal::SignalBuilder::new(
@@ -167,6 +194,32 @@ impl TestCtx {
 		)
 	}
 
+	/// Creates a builder for a schedule that dispatches a workflow on every tick of the given cron expression.
+	/// Schedules are identified by their name.
+	pub fn schedule<I>(
+		&self,
+		name: impl ToString,
+		cron: impl ToString,
+		input: I,
+	) -> builder::schedule::ScheduleBuilder<I>
+	where
+		I: WorkflowInput,
+		<I as WorkflowInput>::Workflow: Workflow<Input = I>,
+	{
+		builder::schedule::ScheduleBuilder::new(self.db.clone(), name, cron, input, false)
+	}
+
+	/// Creates a workflow cancellation builder.
+	pub fn cancel(&self, workflow_id: Id) -> builder::cancel::CancelBuilder {
+		builder::cancel::CancelBuilder::new(
+			self.db.clone(),
+			self.config.clone(),
+			self.ray_id,
+			workflow_id,
+			false,
+		)
+	}
+
 	#[tracing::instrument(skip_all, fields(operation_name=I::Operation::NAME))]
 	pub async fn op<I>(
 		&self,
@@ -189,6 +242,22 @@ impl TestCtx {
 		.await
 	}
 
+	/// Runs a read-only query against the persisted state of a workflow.
+	#[tracing::instrument(skip_all, fields(query_name=I::Query::NAME, %workflow_id))]
+	pub async fn query<I>(
+		&self,
+		workflow_id: Id,
+		input: I,
+	) -> Result<<<I as QueryInput>::Query as Query>::Output>
+	where
+		I: QueryInput,
+		<I as QueryInput>::Query: Query<Input = I>,
+	{
+		common::query(&self.db, workflow_id, input)
+			.in_current_span()
+			.await
+	}
+
 	pub fn msg<M: Message>(&self, body: M) -> builder::message::MessageBuilder<M> {
 		builder::message::MessageBuilder::new(self.msg_ctx.clone(), body)
 	}
@@ -1,6 +1,7 @@
 use std::ops::Deref;
 
 use anyhow::Result;
+use rivet_util::Id;
 use serde::{Serialize, de::DeserializeOwned};
 use tracing::Instrument;
 
@@ -113,6 +114,11 @@ impl<'a> VersionedWorkflowCtx<'a> {
 		builder::signal::SignalBuilder::new(self.inner, self.version(), body)
 	}
 
+	/// Creates a workflow cancellation builder.
+	pub fn cancel(&mut self, workflow_id: Id) -> builder::cancel::CancelBuilder {
+		builder::cancel::CancelBuilder::new(self.inner, self.version(), workflow_id)
+	}
+
 	/// Listens for a signal for a short time before setting the workflow to sleep. Once the signal is
 	/// received, the workflow will be woken up and continue.
 	#[tracing::instrument(skip_all, fields(t=std::any::type_name::<T>()))]
@@ -1,6 +1,9 @@
 use std::{
 	ops::Deref,
-	sync::Arc,
+	sync::{
+		Arc,
+		atomic::{AtomicBool, Ordering},
+	},
 	time::{Duration, Instant},
 };
 
@@ -18,6 +21,7 @@ use rivet_metrics::KeyValue;
 use crate::{
 	activity::{Activity, ActivityInput},
 	builder::{WorkflowRepr, workflow as builder},
+	cancel::Cancel,
 	ctx::{ActivityCtx, ListenCtx, MessageCtx, VersionedWorkflowCtx},
 	db::{DatabaseHandle, PulledWorkflowData},
 	error::{WorkflowError, WorkflowResult},
@@ -32,9 +36,13 @@ use crate::{
 	listen::{CustomListener, Listen},
 	message::Message,
 	metrics,
+	migration::Migrations,
 	registry::RegistryHandle,
 	signal::Signal,
-	utils::time::{DurationToMillis, TsToMillis},
+	utils::{
+		self,
+		time::{DurationToMillis, TsToMillis},
+	},
 	workflow::{Workflow, WorkflowInput},
 };
 
@@ -54,6 +62,8 @@ pub struct WorkflowCtx {
 	version: usize,
 	// Used for activity retry backoff
 	wake_deadline_ts: Option<i64>,
+	/// Span that this workflow was dispatched in. Every run of the workflow is linked to it.
+	dispatch_span_ctx: Option<SpanContext>,
 
 	registry: RegistryHandle,
 	db: DatabaseHandle,
@@ -66,6 +76,8 @@ pub struct WorkflowCtx {
 	input: Arc<serde_json::value::RawValue>,
 	/// Data that can be manipulated via activities over the course of the workflows entire lifetime.
 	state: Arc<Mutex<Box<serde_json::value::RawValue>>>,
+	/// Migration version of the input and state.
+	schema_version: usize,
 	/// All events that have ever been recorded on this workflow.
 	event_history: History,
 	cursor: Cursor,
@@ -76,9 +88,14 @@ pub struct WorkflowCtx {
 	msg_ctx: MessageCtx,
 	/// Used to stop workflow execution by the worker.
 	stop: watch::Receiver<()>,
+	/// Set if a cancellation was pending when this workflow was pulled. Cleared once it is received.
+	cancel_requested: Arc<AtomicBool>,
 
 	/// Whether or not this ctx is used as part of a .join
 	parallelized: bool,
+	/// Whether or not this ctx is replaying history for the replay checker. Activities are never ran while
+	/// replaying.
+	replay: bool,
 }
 
 impl WorkflowCtx {
@@ -102,6 +119,10 @@ impl WorkflowCtx {
 			ray_id: data.ray_id,
 			version: 1,
 			wake_deadline_ts: data.wake_deadline_ts,
+			dispatch_span_ctx: data
+				.trace_context
+				.as_deref()
+				.and_then(utils::trace::parse_trace_context),
 
 			registry,
 			db,
@@ -112,6 +133,7 @@ impl WorkflowCtx {
 
 			input: Arc::from(data.input),
 			state: Arc::new(Mutex::new(data.state)),
+			schema_version: data.schema_version,
 
 			event_history: event_history.clone(),
 			cursor: Cursor::new(event_history, Location::empty()),
@@ -119,8 +141,10 @@ impl WorkflowCtx {
 
 			msg_ctx,
 			stop,
+			cancel_requested: Arc::new(AtomicBool::new(data.cancel_requested)),
 
 			parallelized: false,
+			replay: false,
 		})
 	}
 
@@ -150,6 +174,9 @@ impl WorkflowCtx {
 	#[tracing::instrument(name="workflow", skip_all, fields(workflow_id=%self.workflow_id, workflow_name=%self.name, ray_id=%self.ray_id))]
 	pub(crate) async fn run(mut self, parent_span_ctx: SpanContext) -> WorkflowResult<()> {
 		tracing::Span::current().add_link(parent_span_ctx);
+		if let Some(dispatch_span_ctx) = &self.dispatch_span_ctx {
+			tracing::Span::current().add_link(dispatch_span_ctx.clone());
+		}
 
 		tracing::debug!("running workflow");
 
@@ -196,6 +223,34 @@ impl WorkflowCtx {
 					}
 				}
 			}
+			// Workflow did not handle its cancellation
+			Err(err) if err.cancellation().is_some() => {
+				let reason = err.cancellation().and_then(|cancel| cancel.reason.clone());
+
+				tracing::debug!(?reason, "workflow cancelled");
+
+				let mut retries = 0;
+				let mut interval = tokio::time::interval(DB_ACTION_RETRY);
+				interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+				// Retry loop
+				loop {
+					interval.tick().await;
+
+					if let Err(err) = self
+						.db
+						.commit_cancelled_workflow(self.workflow_id, &self.name, reason.as_deref())
+						.await
+					{
+						if retries > MAX_DB_ACTION_RETRIES {
+							return Err(err);
+						}
+						retries += 1;
+					} else {
+						break;
+					}
+				}
+			}
 			Err(err) => {
 				let wake_immediate = err.wake_immediate();
 
@@ -276,16 +331,21 @@ impl WorkflowCtx {
 		Ok(())
 	}
 
-	/// Run then handle the result of an activity.
-	#[tracing::instrument(skip_all, fields(activity_name=%A::NAME, %location))]
+	/// Run then handle the result of an activity. `attempt` is the amount of previous failed attempts.
+	#[tracing::instrument(skip_all, fields(activity_name=%A::NAME, %location, %attempt))]
 	async fn run_activity<A: Activity>(
 		&mut self,
 		input: &A::Input,
 		location: &Location,
 		create_ts: i64,
+		attempt: usize,
 	) -> WorkflowResult<A::Output> {
 		tracing::debug!("running activity");
 
+		if self.replay {
+			return Err(WorkflowError::ReplayEnded);
+		}
+
 		let ctx = ActivityCtx::new(
 			self.workflow_id,
 			self.name.clone(),
@@ -457,6 +517,51 @@ impl WorkflowCtx {
 		self.parallelized = true;
 	}
 
+	pub(crate) fn set_replay(&mut self) {
+		self.replay = true;
+	}
+
+	/// Upgrades the input and state to the current schema version of the workflow and persists them.
+	#[tracing::instrument(skip_all)]
+	pub(crate) async fn migrate(&mut self, migrations: &Migrations) -> WorkflowResult<()> {
+		let schema_version = migrations.version();
+
+		if self.schema_version == schema_version {
+			return Ok(());
+		}
+
+		let mut state = self.state.lock().await;
+
+		let (input, new_state) = migrations
+			.migrate(self.schema_version, &self.input, &state)
+			.map_err(WorkflowError::MigrationFailed)?;
+
+		self.db
+			.migrate_workflow(
+				self.workflow_id,
+				self.schema_version,
+				schema_version,
+				&input,
+				&new_state,
+				true,
+			)
+			.await?;
+
+		tracing::debug!(
+			from=%self.schema_version,
+			to=%schema_version,
+			"migrated workflow input and state"
+		);
+
+		*state = new_state;
+		drop(state);
+
+		self.input = Arc::from(input);
+		self.schema_version = schema_version;
+
+		Ok(())
+	}
+
 	/// Creates a new workflow run with one more depth in the location.
 	/// - **Not to be used directly by workflow users. For implementation uses only.**
 	/// - **Remember to validate latent history after this branch is used.**
@@ -503,6 +608,7 @@ impl WorkflowCtx {
 			ray_id: self.ray_id,
 			version,
 			wake_deadline_ts: self.wake_deadline_ts,
+			dispatch_span_ctx: self.dispatch_span_ctx.clone(),
 
 			registry: self.registry.clone(),
 			db: self.db.clone(),
@@ -513,6 +619,7 @@ impl WorkflowCtx {
 
 			input,
 			state: self.state.clone(),
+			schema_version: self.schema_version,
 
 			event_history: self.event_history.clone(),
 			cursor: Cursor::new(self.event_history.clone(), location),
@@ -520,8 +627,10 @@ impl WorkflowCtx {
 
 			msg_ctx: self.msg_ctx.clone(),
 			stop: self.stop.clone(),
+			cancel_requested: self.cancel_requested.clone(),
 
 			parallelized: self.parallelized,
+			replay: self.replay,
 		}
 	}
 
@@ -548,6 +657,58 @@ impl WorkflowCtx {
 		let _ = self.stop.clone().changed().await;
 		Err(WorkflowError::WorkflowEvicted)
 	}
+
+	/// Errors with `WorkflowError::Cancelled` if this workflow was cancelled. Cancellations are only pulled
+	/// in place of new events so that they are replayed at the same location.
+	pub(crate) async fn check_cancel(&mut self) -> WorkflowResult<()> {
+		let Some(history_res) = self.cursor.compare_cancel() else {
+			return Ok(());
+		};
+		let location = self.cursor.current_location_for(&history_res);
+
+		// Cancellation received before
+		let cancel = if let HistoryResult::Event(signal) = history_res {
+			tracing::debug!("replaying cancellation");
+
+			serde_json::from_str::<Cancel>(signal.body.get())
+				.map_err(WorkflowError::DeserializeSignalBody)?
+		}
+		// Pull pending cancellation
+		else {
+			if !self.cancel_requested.load(Ordering::Acquire) {
+				return Ok(());
+			}
+
+			let signal = self
+				.db
+				.pull_next_signal(
+					self.workflow_id,
+					&self.name,
+					&[Cancel::NAME],
+					&location,
+					self.version,
+					self.loop_location(),
+					false,
+				)
+				.await?;
+
+			self.cancel_requested.store(false, Ordering::Release);
+
+			let Some(signal) = signal else {
+				return Ok(());
+			};
+
+			serde_json::from_str::<Cancel>(signal.body.get())
+				.map_err(WorkflowError::DeserializeSignalBody)?
+		};
+
+		tracing::debug!(reason=?cancel.reason, "cancellation received");
+
+		// Move to next event
+		self.cursor.update(&location);
+
+		Err(WorkflowError::Cancelled(cancel))
+	}
 }
 
 impl WorkflowCtx {
@@ -574,6 +735,7 @@ impl WorkflowCtx {
 		<I as ActivityInput>::Activity: Activity<Input = I>,
 	{
 		self.check_stop()?;
+		self.check_cancel().await?;
 
 		let history_res = self
 			.cursor
@@ -604,7 +766,7 @@ impl WorkflowCtx {
 				}
 
 				match self
-					.run_activity::<I::Activity>(&input, &location, activity.create_ts)
+					.run_activity::<I::Activity>(&input, &location, activity.create_ts, error_count)
 					.await
 				{
 					Err(err) => {
@@ -646,7 +808,7 @@ impl WorkflowCtx {
 		}
 		// This is a new activity
 		else {
-			self.run_activity::<I::Activity>(&input, &location, rivet_util::timestamp::now())
+			self.run_activity::<I::Activity>(&input, &location, rivet_util::timestamp::now(), 0)
 				.await?
 		};
 
@@ -700,11 +862,17 @@ impl WorkflowCtx {
 		builder::signal::SignalBuilder::new(self, self.version, body)
 	}
 
+	/// Creates a workflow cancellation builder.
+	pub fn cancel(&mut self, workflow_id: Id) -> builder::cancel::CancelBuilder {
+		builder::cancel::CancelBuilder::new(self, self.version, workflow_id)
+	}
+
 	/// Listens for a signal for a short time before setting the workflow to sleep. Once the signal is
 	/// received, the workflow will be woken up and continue.
 	#[tracing::instrument(skip_all, fields(t=std::any::type_name::<T>()))]
 	pub async fn listen<T: Listen>(&mut self) -> Result<T> {
 		self.check_stop()?;
+		self.check_cancel().await?;
 
 		let history_res = self.cursor.compare_signal(self.version)?;
 		let location = self.cursor.current_location_for(&history_res);
@@ -743,6 +911,13 @@ impl WorkflowCtx {
 						}
 						retries -= 1;
 					}
+					// The cancellation signal was recorded in place of this listen
+					Err(err @ WorkflowError::Cancelled(_)) => {
+						self.cancel_requested.store(false, Ordering::Release);
+						self.cursor.update(&location);
+
+						return Err(err.into());
+					}
 					Err(err) => return Err(err.into()),
 				}
 
@@ -768,6 +943,7 @@ impl WorkflowCtx {
 		listener: &T,
 	) -> Result<<T as CustomListener>::Output> {
 		self.check_stop()?;
+		self.check_cancel().await?;
 
 		let history_res = self.cursor.compare_signal(self.version)?;
 		let location = self.cursor.current_location_for(&history_res);
@@ -806,6 +982,13 @@ impl WorkflowCtx {
 						}
 						retries -= 1;
 					}
+					// The cancellation signal was recorded in place of this listen
+					Err(err @ WorkflowError::Cancelled(_)) => {
+						self.cancel_requested.store(false, Ordering::Release);
+						self.cursor.update(&location);
+
+						return Err(err.into());
+					}
 					Err(err) => return Err(err.into()),
 				}
 
@@ -1022,9 +1205,10 @@ impl WorkflowCtx {
 		self.sleep_until(ts as i64).await
 	}
 
-	#[tracing::instrument(skip_all, fields(duration))]
+	#[tracing::instrument(skip_all, fields(duration, deadline_ts))]
 	pub async fn sleep_until(&mut self, time: impl TsToMillis) -> Result<()> {
 		self.check_stop()?;
+		self.check_cancel().await?;
 
 		let history_res = self.cursor.compare_sleep(self.version)?;
 		let location = self.cursor.current_location_for(&history_res);
@@ -1033,6 +1217,14 @@ impl WorkflowCtx {
 		let (deadline_ts, replay) = if let HistoryResult::Event(sleep) = history_res {
 			tracing::debug!("replaying sleep");
 
+			// Sleep was cut short by a cancellation, which comes after the sleep event
+			if matches!(sleep.state, SleepState::Interrupted) {
+				self.cursor.update(&location);
+				self.check_cancel().await?;
+
+				return Ok(());
+			}
+
 			(sleep.deadline_ts, true)
 		}
 		// Sleep
@@ -1054,6 +1246,7 @@ impl WorkflowCtx {
 
 		let duration = deadline_ts.saturating_sub(rivet_util::timestamp::now());
 		tracing::Span::current().record("duration", &duration);
+		tracing::Span::current().record("deadline_ts", &deadline_ts);
 
 		// No-op
 		if duration <= 0 {
@@ -1072,6 +1265,29 @@ impl WorkflowCtx {
 		}
 		// Workflow sleep
 		else {
+			// Check for a cancellation before going to sleep. It is pulled at the location after the sleep
+			// event so the cursor is reverted if there is none
+			if self.cancel_requested.load(Ordering::Acquire) {
+				let cursor = self.cursor.clone();
+				self.cursor.update(&location);
+
+				if let Err(err) = self.check_cancel().await {
+					if matches!(err, WorkflowError::Cancelled(_)) {
+						self.db
+							.update_workflow_sleep_event_state(
+								self.workflow_id,
+								&location,
+								SleepState::Interrupted,
+							)
+							.await?;
+					}
+
+					return Err(err.into());
+				}
+
+				self.cursor = cursor;
+			}
+
 			tracing::debug!(%deadline_ts, "sleeping");
 
 			return Err(WorkflowError::Sleep(deadline_ts).into());
@@ -1105,6 +1321,7 @@ impl WorkflowCtx {
 	#[tracing::instrument(skip_all, fields(t=std::any::type_name::<T>(), duration))]
 	pub async fn listen_until<T: Listen>(&mut self, time: impl TsToMillis) -> Result<Option<T>> {
 		self.check_stop()?;
+		self.check_cancel().await?;
 
 		let history_res = self.cursor.compare_sleep(self.version)?;
 		let history_res2 = history_res.equivalent();
@@ -1147,6 +1364,17 @@ impl WorkflowCtx {
 					"replaying signal",
 				);
 
+				// Listen was interrupted by a cancellation
+				if signal.name == Cancel::NAME {
+					let cancel = serde_json::from_str::<Cancel>(signal.body.get())
+						.map_err(WorkflowError::DeserializeSignalBody)?;
+
+					// Move to next event
+					self.cursor.update(&signal_location);
+
+					return Err(WorkflowError::Cancelled(cancel).into());
+				}
+
 				let signal = T::parse(&signal.name, &signal.body)?;
 
 				// Move to next event
@@ -1168,106 +1396,138 @@ impl WorkflowCtx {
 		let duration = deadline_ts.saturating_sub(rivet_util::timestamp::now());
 		tracing::Span::current().record("duration", &duration);
 
-		// Duration is now 0, timeout is over
-		let signal = if duration <= 0 {
-			// After timeout is over, check once for signal
-			if matches!(state, SleepState::Normal) {
-				let mut ctx = ListenCtx::new(self, &signal_location);
+		// Duration in ms, converted before the listen block because it can only return workflow errors
+		let duration_ms = u64::try_from(duration.max(0))?;
 
-				match T::listen(&mut ctx).in_current_span().await {
-					Ok(x) => Some(x),
-					Err(WorkflowError::NoSignalFound(_)) => None,
-					Err(err) => return Err(err.into()),
+		let res = async {
+			// Duration is now 0, timeout is over
+			let signal = if duration <= 0 {
+				// After timeout is over, check once for signal
+				if matches!(state, SleepState::Normal) {
+					let mut ctx = ListenCtx::new(self, &signal_location);
+
+					match T::listen(&mut ctx).in_current_span().await {
+						Ok(x) => Some(x),
+						Err(WorkflowError::NoSignalFound(_)) => None,
+						Err(err) => return Err(err),
+					}
+				} else {
+					None
 				}
-			} else {
-				None
 			}
-		}
-		// Sleep in memory if duration is shorter than the worker tick
-		else if duration < self.db.worker_poll_interval().as_millis() as i64 + 1 {
-			tracing::debug!(%deadline_ts, "sleeping in memory");
+			// Sleep in memory if duration is shorter than the worker tick
+			else if duration < self.db.worker_poll_interval().as_millis() as i64 + 1 {
+				tracing::debug!(%deadline_ts, "sleeping in memory");
 
-			let res = tokio::time::timeout(
-				Duration::from_millis(duration.try_into()?),
-				(async {
-					tracing::debug!("listening for signal with timeout");
+				let res = tokio::time::timeout(
+					Duration::from_millis(duration_ms),
+					(async {
+						tracing::debug!("listening for signal with timeout");
 
-					let mut wake_sub = self.db.wake_sub().await?;
-					let mut interval = tokio::time::interval(self.db.signal_poll_interval());
-					interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+						let mut wake_sub = self.db.wake_sub().await?;
+						let mut interval = tokio::time::interval(self.db.signal_poll_interval());
+						interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
-					// Skip first tick, we wait after the db call instead of before
-					interval.tick().await;
+						// Skip first tick, we wait after the db call instead of before
+						interval.tick().await;
 
-					let mut ctx = ListenCtx::new(self, &signal_location);
+						let mut ctx = ListenCtx::new(self, &signal_location);
 
-					loop {
-						ctx.reset(false);
+						loop {
+							ctx.reset(false);
 
-						match T::listen(&mut ctx).in_current_span().await {
-							// Retry
-							Err(WorkflowError::NoSignalFound(_)) => {}
-							x => return x,
-						}
+							match T::listen(&mut ctx).in_current_span().await {
+								// Retry
+								Err(WorkflowError::NoSignalFound(_)) => {}
+								x => return x,
+							}
 
-						// Poll and wait for a wake at the same time
-						tokio::select! {
-							_ = wake_sub.next() => {},
-							_ = interval.tick() => {},
-							res = self.wait_stop() => res?,
+							// Poll and wait for a wake at the same time
+							tokio::select! {
+								_ = wake_sub.next() => {},
+								_ = interval.tick() => {},
+								res = self.wait_stop() => res?,
+							}
 						}
-					}
-				})
-				.in_current_span(),
-			)
-			.await;
+					})
+					.in_current_span(),
+				)
+				.await;
 
-			match res {
-				Ok(res) => Some(res?),
-				Err(_) => {
-					tracing::debug!("timed out listening for signal");
+				match res {
+					Ok(res) => Some(res?),
+					Err(_) => {
+						tracing::debug!("timed out listening for signal");
 
-					None
+						None
+					}
 				}
 			}
-		}
-		// Workflow sleep for long durations
-		else {
-			tracing::debug!("listening for signal with timeout");
-
-			let mut wake_sub = self.db.wake_sub().await?;
-			let mut retries = self.db.max_signal_poll_retries();
-			let mut interval = tokio::time::interval(self.db.signal_poll_interval());
-			interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+			// Workflow sleep for long durations
+			else {
+				tracing::debug!("listening for signal with timeout");
 
-			// Skip first tick, we wait after the db call instead of before
-			interval.tick().await;
+				let mut wake_sub = self.db.wake_sub().await?;
+				let mut retries = self.db.max_signal_poll_retries();
+				let mut interval = tokio::time::interval(self.db.signal_poll_interval());
+				interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
-			let mut ctx = ListenCtx::new(self, &signal_location);
+				// Skip first tick, we wait after the db call instead of before
+				interval.tick().await;
 
-			loop {
-				ctx.reset(retries == 0);
+				let mut ctx = ListenCtx::new(self, &signal_location);
 
-				match T::listen(&mut ctx).in_current_span().await {
-					Ok(res) => break Some(res),
-					Err(WorkflowError::NoSignalFound(signals)) => {
-						if retries == 0 {
-							return Err(
-								WorkflowError::NoSignalFoundAndSleep(signals, deadline_ts).into()
-							);
+				loop {
+					ctx.reset(retries == 0);
+
+					match T::listen(&mut ctx).in_current_span().await {
+						Ok(res) => break Some(res),
+						Err(WorkflowError::NoSignalFound(signals)) => {
+							if retries == 0 {
+								return Err(WorkflowError::NoSignalFoundAndSleep(
+									signals,
+									deadline_ts,
+								));
+							}
+							retries -= 1;
 						}
-						retries -= 1;
+						Err(err) => return Err(err),
 					}
-					Err(err) => return Err(err.into()),
-				}
 
-				// Poll and wait for a wake at the same time
-				tokio::select! {
-					_ = wake_sub.next() => {},
-					_ = interval.tick() => {},
-					res = self.wait_stop() => res?,
+					// Poll and wait for a wake at the same time
+					tokio::select! {
+						_ = wake_sub.next() => {},
+						_ = interval.tick() => {},
+						res = self.wait_stop() => res?,
+					}
 				}
+			};
+
+			Ok::<_, WorkflowError>(signal)
+		}
+		.await;
+
+		let signal = match res {
+			Ok(signal) => signal,
+			// The cancellation signal was recorded in place of the signal, mark the sleep as interrupted so it
+			// is replayed
+			Err(err @ WorkflowError::Cancelled(_)) => {
+				self.cancel_requested.store(false, Ordering::Release);
+
+				self.db
+					.update_workflow_sleep_event_state(
+						self.workflow_id,
+						&sleep_location,
+						SleepState::Interrupted,
+					)
+					.await?;
+
+				// Move to next event
+				self.cursor.update(&signal_location);
+
+				return Err(err.into());
 			}
+			Err(err) => return Err(err.into()),
 		};
 
 		// Update sleep state
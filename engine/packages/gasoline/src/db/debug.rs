@@ -1,10 +1,14 @@
 use anyhow::*;
 use rivet_util::Id;
+use serde::{Deserialize, Serialize};
 
 use super::Database;
-use crate::history::{
-	event::{RemovedEvent, SleepEvent},
-	location::Location,
+use crate::{
+	cancel::Termination,
+	history::{
+		event::{RemovedEvent, SleepEvent},
+		location::Location,
+	},
 };
 
 #[async_trait::async_trait]
@@ -22,6 +26,10 @@ pub trait DatabaseDebug: Database {
 
 	async fn wake_workflows(&self, workflow_ids: Vec<Id>) -> Result<()>;
 
+	/// Stops workflows immediately without letting them handle a cancellation. Running workflows lose their
+	/// lease and the writes of the worker running them are discarded.
+	async fn terminate_workflows(&self, workflow_ids: Vec<Id>, reason: Option<&str>) -> Result<()>;
+
 	async fn get_workflow_history(
 		&self,
 		workflow_id: Id,
@@ -39,9 +47,22 @@ pub trait DatabaseDebug: Database {
 	) -> Result<Vec<SignalData>>;
 
 	async fn silence_signals(&self, signal_ids: Vec<Id>) -> Result<()>;
+
+	async fn get_schedules(&self, schedule_names: Vec<String>) -> Result<Vec<ScheduleData>>;
+
+	async fn find_schedules(&self, workflow_name: Option<&str>) -> Result<Vec<ScheduleData>>;
+
+	/// Stops schedules from dispatching workflows until they are resumed.
+	async fn pause_schedules(&self, schedule_names: Vec<String>) -> Result<()>;
+
+	/// Resumes paused schedules from their first tick after now. Ticks missed while paused are skipped.
+	async fn resume_schedules(&self, schedule_names: Vec<String>) -> Result<()>;
+
+	/// Deletes schedules. Workflows that were already dispatched are not affected.
+	async fn delete_schedules(&self, schedule_names: Vec<String>) -> Result<()>;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct WorkflowData {
 	pub workflow_id: Id,
 	pub workflow_name: String,
@@ -50,27 +71,35 @@ pub struct WorkflowData {
 	pub input: serde_json::Value,
 	// Internally same as state, renamed to data to avoid confusion
 	pub data: serde_json::Value,
+	/// Migration version of the input and data.
+	#[serde(default)]
+	pub schema_version: usize,
 	pub output: Option<serde_json::Value>,
 	pub error: Option<String>,
+	pub termination: Option<Termination>,
 	pub state: WorkflowState,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum WorkflowState {
 	Complete,
 	Running,
 	Sleeping,
 	Dead,
 	Silenced,
+	/// Stopped after not catching a cancellation.
+	Cancelled,
+	/// Stopped with `DatabaseDebug::terminate_workflows`.
+	Terminated,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct HistoryData {
 	pub wf: WorkflowData,
 	pub events: Vec<Event>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Event {
 	pub location: Location,
 	pub version: usize,
@@ -79,7 +108,7 @@ pub struct Event {
 	pub data: EventData,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum EventData {
 	Activity(ActivityEvent),
 	Signal(SignalEvent),
@@ -125,7 +154,7 @@ impl std::fmt::Display for EventData {
 	}
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ActivityEvent {
 	pub name: String,
 	pub input: serde_json::Value,
@@ -133,14 +162,14 @@ pub struct ActivityEvent {
 	pub errors: Vec<ActivityError>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SignalEvent {
 	pub signal_id: Id,
 	pub name: String,
 	pub body: serde_json::Value,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SignalSendEvent {
 	pub signal_id: Id,
 	pub name: String,
@@ -149,14 +178,14 @@ pub struct SignalSendEvent {
 	pub body: serde_json::Value,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct MessageSendEvent {
 	pub name: String,
 	pub tags: serde_json::Value,
 	pub body: serde_json::Value,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SubWorkflowEvent {
 	pub sub_workflow_id: Id,
 	pub name: String,
@@ -164,7 +193,7 @@ pub struct SubWorkflowEvent {
 	pub input: serde_json::Value,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct LoopEvent {
 	pub state: serde_json::Value,
 	/// If the loop completes, this will be some.
@@ -172,7 +201,7 @@ pub struct LoopEvent {
 	pub iteration: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActivityError {
 	pub error: String,
 	pub count: usize,
@@ -197,3 +226,18 @@ pub enum SignalState {
 	Pending,
 	Silenced,
 }
+
+#[derive(Debug)]
+pub struct ScheduleData {
+	pub schedule_name: String,
+	pub workflow_name: String,
+	pub cron: String,
+	pub tags: Option<serde_json::Value>,
+	pub input: serde_json::Value,
+	pub create_ts: i64,
+	/// Not set if paused.
+	pub next_ts: Option<i64>,
+	pub pause_ts: Option<i64>,
+	pub last_workflow_id: Option<Id>,
+	pub last_dispatch_ts: Option<i64>,
+}
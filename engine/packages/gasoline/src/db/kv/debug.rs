@@ -18,10 +18,11 @@ use universaldb::{
 
 use super::{DatabaseKv, keys, update_metric};
 use crate::{
+	cancel::Termination,
 	db::debug::{
 		ActivityError, ActivityEvent, DatabaseDebug, Event, EventData, HistoryData, LoopEvent,
-		MessageSendEvent, SignalData, SignalEvent, SignalSendEvent, SignalState, SubWorkflowEvent,
-		WorkflowData, WorkflowState,
+		MessageSendEvent, ScheduleData, SignalData, SignalEvent, SignalSendEvent, SignalState,
+		SubWorkflowEvent, WorkflowData, WorkflowState,
 	},
 	error::{WorkflowError, WorkflowResult},
 	history::{
@@ -56,6 +57,8 @@ impl DatabaseKv {
 			let has_wake_condition_key = keys::workflow::HasWakeConditionKey::new(workflow_id);
 			let worker_instance_id_key = keys::workflow::WorkerInstanceIdKey::new(workflow_id);
 			let silence_ts_key = keys::workflow::SilenceTsKey::new(workflow_id);
+			let termination_key = keys::workflow::TerminationKey::new(workflow_id);
+			let schema_version_key = keys::workflow::SchemaVersionKey::new(workflow_id);
 
 			let (
 				tags,
@@ -68,6 +71,8 @@ impl DatabaseKv {
 				has_wake_condition_entry,
 				worker_instance_id_entry,
 				silence_ts_entry,
+				termination_entry,
+				schema_version_entry,
 			) = tokio::try_join!(
 				tx.get_ranges_keyvalues(
 					RangeOption {
@@ -113,6 +118,8 @@ impl DatabaseKv {
 				tx.get(&self.subspace.pack(&has_wake_condition_key), Snapshot),
 				tx.get(&self.subspace.pack(&worker_instance_id_key), Snapshot),
 				tx.get(&self.subspace.pack(&silence_ts_key), Snapshot),
+				tx.get(&self.subspace.pack(&termination_key), Snapshot),
+				tx.get(&self.subspace.pack(&schema_version_key), Snapshot),
 			)?;
 
 			let Some(create_ts_entry) = &create_ts_entry else {
@@ -144,8 +151,23 @@ impl DatabaseKv {
 				None
 			};
 
+			let termination = termination_entry
+				.map(|raw| termination_key.deserialize(&raw))
+				.transpose()?;
+
+			let schema_version = schema_version_entry
+				.map(|raw| schema_version_key.deserialize(&raw))
+				.transpose()?
+				.unwrap_or_default();
+
 			let state = if silence_ts_entry.is_some() {
 				WorkflowState::Silenced
+			} else if let Some(termination) = &termination {
+				if termination.forced {
+					WorkflowState::Terminated
+				} else {
+					WorkflowState::Cancelled
+				}
 			} else if output.is_some() {
 				WorkflowState::Complete
 			} else if worker_instance_id_entry.is_some() {
@@ -163,8 +185,10 @@ impl DatabaseKv {
 				create_ts,
 				input: serde_json::from_str(input.get())?,
 				data: serde_json::from_str(data.get())?,
+				schema_version,
 				output: output.map(|x| serde_json::from_str(x.get())).transpose()?,
 				error,
+				termination,
 				state,
 			});
 		}
@@ -261,6 +285,100 @@ impl DatabaseKv {
 
 		Ok(res)
 	}
+
+	#[tracing::instrument(skip_all)]
+	async fn get_schedules_inner(
+		&self,
+		schedule_names: Vec<String>,
+		tx: &universaldb::RetryableTransaction,
+	) -> Result<Vec<ScheduleData>> {
+		let tx = tx.with_subspace(self.subspace.clone());
+		let mut res = Vec::new();
+
+		// TODO: Parallelize
+		for schedule_name in schedule_names {
+			let input_key = keys::schedule::InputKey::new(schedule_name.clone());
+			let input_subspace = self.subspace.subspace(&input_key);
+
+			let (
+				workflow_name,
+				cron,
+				tags,
+				input_chunks,
+				create_ts,
+				next_ts,
+				pause_ts,
+				last_workflow_id,
+				last_dispatch_ts,
+			) = tokio::try_join!(
+				tx.read_opt(
+					&keys::schedule::WorkflowNameKey::new(schedule_name.clone()),
+					Snapshot
+				),
+				tx.read_opt(
+					&keys::schedule::CronKey::new(schedule_name.clone()),
+					Snapshot
+				),
+				tx.read_opt(
+					&keys::schedule::TagsKey::new(schedule_name.clone()),
+					Snapshot
+				),
+				async {
+					tx.get_ranges_keyvalues(
+						RangeOption {
+							mode: StreamingMode::WantAll,
+							..(&input_subspace).into()
+						},
+						Snapshot,
+					)
+					.try_collect::<Vec<_>>()
+					.await
+				},
+				tx.read_opt(
+					&keys::schedule::CreateTsKey::new(schedule_name.clone()),
+					Snapshot
+				),
+				tx.read_opt(
+					&keys::schedule::NextTsKey::new(schedule_name.clone()),
+					Snapshot
+				),
+				tx.read_opt(
+					&keys::schedule::PauseTsKey::new(schedule_name.clone()),
+					Snapshot
+				),
+				tx.read_opt(
+					&keys::schedule::LastWorkflowIdKey::new(schedule_name.clone()),
+					Snapshot
+				),
+				tx.read_opt(
+					&keys::schedule::LastDispatchTsKey::new(schedule_name.clone()),
+					Snapshot
+				),
+			)?;
+
+			let Some(workflow_name) = workflow_name else {
+				tracing::warn!(%schedule_name, "schedule not found");
+				continue;
+			};
+
+			let input = input_key.combine(input_chunks)?;
+
+			res.push(ScheduleData {
+				schedule_name,
+				workflow_name,
+				cron: cron.context("key should exist")?,
+				tags,
+				input: serde_json::from_str(input.get())?,
+				create_ts: create_ts.context("key should exist")?,
+				next_ts: if pause_ts.is_some() { None } else { next_ts },
+				pause_ts,
+				last_workflow_id,
+				last_dispatch_ts,
+			});
+		}
+
+		Ok(res)
+	}
 }
 
 // NOTE: Most of the reads here are Snapshot because we don't want this to conflict with the actual wf engine.
@@ -388,6 +506,23 @@ impl DatabaseDebug for DatabaseKv {
 								Some(WorkflowState::Silenced) => state_matches = true,
 								_ => state_matches = false,
 							}
+						} else if let Ok(termination_key) =
+							self.subspace
+								.unpack::<keys::workflow::TerminationKey>(entry.key())
+						{
+							let termination = termination_key.deserialize(entry.value())?;
+							let termination_state = if termination.forced {
+								WorkflowState::Terminated
+							} else {
+								WorkflowState::Cancelled
+							};
+
+							match state {
+								// Silence takes precedence
+								Some(WorkflowState::Silenced) => {}
+								Some(state) => state_matches = state == termination_state,
+								None => {}
+							}
 						}
 					}
 
@@ -651,6 +786,7 @@ impl DatabaseDebug for DatabaseKv {
 							keys::workflow::HasWakeConditionKey::new(workflow_id);
 						let error_key = keys::workflow::ErrorKey::new(workflow_id);
 						let silence_ts_key = keys::workflow::SilenceTsKey::new(workflow_id);
+						let termination_key = keys::workflow::TerminationKey::new(workflow_id);
 						let output_key = keys::workflow::OutputKey::new(workflow_id);
 						let output_subspace = self.subspace.subspace(&output_key);
 
@@ -659,6 +795,7 @@ impl DatabaseDebug for DatabaseKv {
 							is_running,
 							has_wake_condition,
 							is_silenced,
+							is_terminated,
 							has_output,
 							error,
 						) = tokio::try_join!(
@@ -666,6 +803,7 @@ impl DatabaseDebug for DatabaseKv {
 							tx.exists(&worker_instance_id_key, Serializable),
 							tx.exists(&has_wake_condition_key, Serializable),
 							tx.exists(&silence_ts_key, Serializable),
+							tx.exists(&termination_key, Serializable),
 							async {
 								tx.get_ranges_keyvalues(
 									RangeOption {
@@ -688,6 +826,7 @@ impl DatabaseDebug for DatabaseKv {
 						}
 
 						ensure!(!has_output, "cannot wake a completed workflow");
+						ensure!(!is_terminated, "cannot wake a terminated workflow");
 
 						tx.write(
 							&keys::wake::WorkflowWakeConditionKey::new(
@@ -723,6 +862,176 @@ impl DatabaseDebug for DatabaseKv {
 		Ok(())
 	}
 
+	#[tracing::instrument(skip_all)]
+	async fn terminate_workflows(&self, workflow_ids: Vec<Id>, reason: Option<&str>) -> Result<()> {
+		let wrote_to_wake_idx = self
+			.pools
+			.udb()?
+			.run(|tx| {
+				let workflow_ids = workflow_ids.clone();
+
+				async move {
+					let mut wrote_to_wake_idx = false;
+
+					// TODO: Parallelize
+					for workflow_id in workflow_ids {
+						let name_key = keys::workflow::NameKey::new(workflow_id);
+						let worker_instance_id_key =
+							keys::workflow::WorkerInstanceIdKey::new(workflow_id);
+						let output_key = keys::workflow::OutputKey::new(workflow_id);
+						let output_subspace = self.subspace.subspace(&output_key);
+						let has_wake_condition_key =
+							keys::workflow::HasWakeConditionKey::new(workflow_id);
+						let silence_ts_key = keys::workflow::SilenceTsKey::new(workflow_id);
+						let termination_key = keys::workflow::TerminationKey::new(workflow_id);
+						let error_key = keys::workflow::ErrorKey::new(workflow_id);
+
+						let Some(name_entry) =
+							tx.get(&self.subspace.pack(&name_key), Serializable).await?
+						else {
+							tracing::warn!(?workflow_id, "workflow not found");
+							continue;
+						};
+
+						let workflow_name = name_key.deserialize(&name_entry)?;
+
+						let wake_conditions_subspace = self.subspace.subspace(
+							&keys::wake::WorkflowWakeConditionKey::subspace_without_ts(
+								workflow_name.clone(),
+							),
+						);
+
+						let (
+							wake_condition_keys,
+							is_running,
+							has_output,
+							has_wake_condition,
+							is_silenced,
+							is_terminated,
+							error_entry,
+						) = tokio::try_join!(
+							// Read wake conditions
+							tx.get_ranges_keyvalues(
+								RangeOption {
+									mode: StreamingMode::WantAll,
+									..(&wake_conditions_subspace).into()
+								},
+								Snapshot,
+							)
+							.map(|res| {
+								let entry = res?;
+
+								Ok((
+									entry.key().to_vec(),
+									self.subspace
+										.unpack::<keys::wake::WorkflowWakeConditionKey>(
+											entry.key(),
+										)?,
+								))
+							})
+							.try_collect::<Vec<_>>(),
+							async {
+								tx.get(&self.subspace.pack(&worker_instance_id_key), Serializable)
+									.await
+									.map(|x| x.is_some())
+							},
+							async {
+								tx.get_ranges_keyvalues(
+									RangeOption {
+										mode: StreamingMode::WantAll,
+										limit: Some(1),
+										..(&output_subspace).into()
+									},
+									Snapshot,
+								)
+								.try_next()
+								.await
+								.map(|x| x.is_some())
+							},
+							async {
+								tx.get(&self.subspace.pack(&has_wake_condition_key), Serializable)
+									.await
+									.map(|x| x.is_some())
+							},
+							async {
+								tx.get(&self.subspace.pack(&silence_ts_key), Serializable)
+									.await
+									.map(|x| x.is_some())
+							},
+							async {
+								tx.get(&self.subspace.pack(&termination_key), Serializable)
+									.await
+									.map(|x| x.is_some())
+							},
+							tx.get(&self.subspace.pack(&error_key), Serializable),
+						)?;
+
+						if is_silenced || is_terminated || has_output {
+							continue;
+						}
+
+						// Clear wake conditions
+						for (raw_key, key) in wake_condition_keys {
+							if key.workflow_id != workflow_id {
+								continue;
+							}
+
+							tx.add_conflict_range(
+								&raw_key,
+								&end_of_key_range(&raw_key),
+								ConflictRangeType::Read,
+							)?;
+
+							tx.clear(&raw_key);
+						}
+
+						// Running workflows are terminated too. This clears their lease and the writes of the
+						// worker running them are discarded.
+						if is_running {
+							tracing::warn!(?workflow_id, "forcibly terminating running workflow");
+						}
+
+						wrote_to_wake_idx |= self
+							.terminate_workflow_inner(
+								workflow_id,
+								&workflow_name,
+								Termination {
+									ts: rivet_util::timestamp::now(),
+									reason: reason.map(ToString::to_string),
+									forced: true,
+								},
+								&tx,
+							)
+							.await?;
+
+						// Clear metric
+						let metric = if is_running {
+							keys::metric::GaugeMetric::WorkflowActive(workflow_name.clone())
+						} else if has_wake_condition {
+							let error =
+								error_key.deserialize(&error_entry.context("key should exist")?)?;
+
+							keys::metric::GaugeMetric::WorkflowDead(workflow_name.clone(), error)
+						} else {
+							keys::metric::GaugeMetric::WorkflowSleeping(workflow_name.clone())
+						};
+
+						update_metric(&tx.with_subspace(self.subspace.clone()), Some(metric), None);
+					}
+
+					Ok(wrote_to_wake_idx)
+				}
+			})
+			.instrument(tracing::info_span!("terminate_workflows_tx"))
+			.await?;
+
+		if wrote_to_wake_idx {
+			self.wake_worker();
+		}
+
+		Ok(())
+	}
+
 	#[tracing::instrument(skip_all)]
 	async fn get_workflow_history(
 		&self,
@@ -1150,6 +1459,201 @@ impl DatabaseDebug for DatabaseKv {
 			.await
 			.map_err(Into::into)
 	}
+
+	#[tracing::instrument(skip_all)]
+	async fn get_schedules(&self, schedule_names: Vec<String>) -> Result<Vec<ScheduleData>> {
+		self.pools
+			.udb()?
+			.run(|tx| {
+				let schedule_names = schedule_names.clone();
+				async move { self.get_schedules_inner(schedule_names, &tx).await }
+			})
+			.await
+			.map_err(Into::into)
+	}
+
+	#[tracing::instrument(skip_all)]
+	async fn find_schedules(&self, workflow_name: Option<&str>) -> Result<Vec<ScheduleData>> {
+		self.pools
+			.udb()?
+			.run(|tx| async move {
+				let data_subspace = self
+					.subspace
+					.subspace(&keys::schedule::DataSubspaceKey::new());
+
+				// NOTE: Full scan of all schedule data, the amount of schedules is expected to be small
+				let schedule_names = tx
+					.get_ranges_keyvalues(
+						RangeOption {
+							mode: StreamingMode::WantAll,
+							..(&data_subspace).into()
+						},
+						Snapshot,
+					)
+					.try_filter_map(|entry| async move {
+						let Ok(workflow_name_key) = self
+							.subspace
+							.unpack::<keys::schedule::WorkflowNameKey>(entry.key())
+						else {
+							return Ok(None);
+						};
+
+						if let Some(workflow_name) = workflow_name {
+							if workflow_name_key.deserialize(entry.value())? != workflow_name {
+								return Ok(None);
+							}
+						}
+
+						Ok(Some(workflow_name_key.schedule_name))
+					})
+					.try_collect::<Vec<_>>()
+					.await?;
+
+				self.get_schedules_inner(schedule_names, &tx).await
+			})
+			.instrument(tracing::info_span!("find_schedules_tx"))
+			.await
+			.map_err(Into::into)
+	}
+
+	#[tracing::instrument(skip_all)]
+	async fn pause_schedules(&self, schedule_names: Vec<String>) -> Result<()> {
+		self.pools
+			.udb()?
+			.run(|tx| {
+				let schedule_names = schedule_names.clone();
+
+				async move {
+					let tx = tx.with_subspace(self.subspace.clone());
+
+					for schedule_name in schedule_names {
+						let pause_ts_key = keys::schedule::PauseTsKey::new(schedule_name.clone());
+
+						let (exists, is_paused, next_ts) = tokio::try_join!(
+							tx.exists(
+								&keys::schedule::WorkflowNameKey::new(schedule_name.clone()),
+								Serializable
+							),
+							tx.exists(&pause_ts_key, Serializable),
+							tx.read_opt(
+								&keys::schedule::NextTsKey::new(schedule_name.clone()),
+								Serializable
+							),
+						)?;
+
+						ensure!(exists, "schedule {schedule_name} not found");
+
+						if is_paused {
+							continue;
+						}
+
+						if let Some(next_ts) = next_ts {
+							tx.delete(&keys::schedule::ByNextTsKey::new(
+								next_ts,
+								schedule_name.clone(),
+							));
+						}
+
+						tx.write(&pause_ts_key, rivet_util::timestamp::now())?;
+					}
+
+					Ok(())
+				}
+			})
+			.instrument(tracing::info_span!("pause_schedules_tx"))
+			.await
+			.map_err(Into::into)
+	}
+
+	#[tracing::instrument(skip_all)]
+	async fn resume_schedules(&self, schedule_names: Vec<String>) -> Result<()> {
+		self.pools
+			.udb()?
+			.run(|tx| {
+				let schedule_names = schedule_names.clone();
+
+				async move {
+					let tx = tx.with_subspace(self.subspace.clone());
+
+					for schedule_name in schedule_names {
+						let pause_ts_key = keys::schedule::PauseTsKey::new(schedule_name.clone());
+
+						let (cron, is_paused) = tokio::try_join!(
+							tx.read_opt(
+								&keys::schedule::CronKey::new(schedule_name.clone()),
+								Serializable
+							),
+							tx.exists(&pause_ts_key, Serializable),
+						)?;
+
+						let cron =
+							cron.with_context(|| format!("schedule {schedule_name} not found"))?;
+
+						if !is_paused {
+							continue;
+						}
+
+						let next_ts =
+							crate::schedule::next_tick_ts(&cron, rivet_util::timestamp::now())?;
+
+						tx.write(
+							&keys::schedule::NextTsKey::new(schedule_name.clone()),
+							next_ts,
+						)?;
+						tx.write(
+							&keys::schedule::ByNextTsKey::new(next_ts, schedule_name.clone()),
+							(),
+						)?;
+						tx.delete(&pause_ts_key);
+					}
+
+					Ok(())
+				}
+			})
+			.instrument(tracing::info_span!("resume_schedules_tx"))
+			.await
+			.map_err(Into::into)
+	}
+
+	#[tracing::instrument(skip_all)]
+	async fn delete_schedules(&self, schedule_names: Vec<String>) -> Result<()> {
+		self.pools
+			.udb()?
+			.run(|tx| {
+				let schedule_names = schedule_names.clone();
+
+				async move {
+					let tx = tx.with_subspace(self.subspace.clone());
+
+					for schedule_name in schedule_names {
+						let next_ts = tx
+							.read_opt(
+								&keys::schedule::NextTsKey::new(schedule_name.clone()),
+								Serializable,
+							)
+							.await?;
+
+						let Some(next_ts) = next_ts else {
+							tracing::warn!(%schedule_name, "schedule not found");
+							continue;
+						};
+
+						tx.delete(&keys::schedule::ByNextTsKey::new(
+							next_ts,
+							schedule_name.clone(),
+						));
+						tx.clear_subspace_range(&self.subspace.subspace(
+							&keys::schedule::DataSubspaceKey::for_schedule(schedule_name),
+						));
+					}
+
+					Ok(())
+				}
+			})
+			.instrument(tracing::info_span!("delete_schedules_tx"))
+			.await
+			.map_err(Into::into)
+	}
 }
 
 // Parses Id in third position, ignores the rest
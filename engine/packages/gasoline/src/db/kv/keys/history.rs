@@ -473,7 +473,7 @@ impl<'de> TupleUnpack<'de> for SignalIdKey {
 pub struct SubWorkflowIdKey {
 	workflow_id: Id,
 	location: Location,
-	forgotten: bool,
+	pub forgotten: bool,
 }
 
 impl SubWorkflowIdKey {
@@ -1,5 +1,6 @@
 pub mod history;
 pub mod metric;
+pub mod schedule;
 pub mod signal;
 pub mod wake;
 pub mod worker_instance;
@@ -0,0 +1,666 @@
+use std::result::Result::Ok;
+
+use anyhow::*;
+use rivet_util::Id;
+use universaldb::prelude::*;
+
+#[derive(Debug)]
+pub struct WorkflowNameKey {
+	pub schedule_name: String,
+}
+
+impl WorkflowNameKey {
+	pub fn new(schedule_name: String) -> Self {
+		WorkflowNameKey { schedule_name }
+	}
+}
+
+impl FormalKey for WorkflowNameKey {
+	type Value = String;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		String::from_utf8(raw.to_vec()).map_err(Into::into)
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.into_bytes())
+	}
+}
+
+impl TuplePack for WorkflowNameKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (SCHEDULE, DATA, &self.schedule_name, WORKFLOW_NAME);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for WorkflowNameKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, schedule_name, data)) =
+			<(usize, usize, String, usize)>::unpack(input, tuple_depth)?;
+		if data != WORKFLOW_NAME {
+			return Err(PackError::Message("expected WORKFLOW_NAME data".into()));
+		}
+
+		let v = WorkflowNameKey { schedule_name };
+
+		Ok((input, v))
+	}
+}
+
+#[derive(Debug)]
+pub struct CronKey {
+	schedule_name: String,
+}
+
+impl CronKey {
+	pub fn new(schedule_name: String) -> Self {
+		CronKey { schedule_name }
+	}
+}
+
+impl FormalKey for CronKey {
+	// Cron expression.
+	type Value = String;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		String::from_utf8(raw.to_vec()).map_err(Into::into)
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.into_bytes())
+	}
+}
+
+impl TuplePack for CronKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (SCHEDULE, DATA, &self.schedule_name, CRON);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for CronKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, schedule_name, data)) =
+			<(usize, usize, String, usize)>::unpack(input, tuple_depth)?;
+		if data != CRON {
+			return Err(PackError::Message("expected CRON data".into()));
+		}
+
+		let v = CronKey { schedule_name };
+
+		Ok((input, v))
+	}
+}
+
+#[derive(Debug)]
+pub struct TagsKey {
+	schedule_name: String,
+}
+
+impl TagsKey {
+	pub fn new(schedule_name: String) -> Self {
+		TagsKey { schedule_name }
+	}
+}
+
+impl FormalKey for TagsKey {
+	// Tags of dispatched workflows.
+	type Value = serde_json::Value;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		serde_json::from_slice(raw).map_err(Into::into)
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		serde_json::to_vec(&value).map_err(Into::into)
+	}
+}
+
+impl TuplePack for TagsKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (SCHEDULE, DATA, &self.schedule_name, TAG);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for TagsKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, schedule_name, data)) =
+			<(usize, usize, String, usize)>::unpack(input, tuple_depth)?;
+		if data != TAG {
+			return Err(PackError::Message("expected TAG data".into()));
+		}
+
+		let v = TagsKey { schedule_name };
+
+		Ok((input, v))
+	}
+}
+
+pub struct InputKey {
+	schedule_name: String,
+}
+
+impl InputKey {
+	pub fn new(schedule_name: String) -> Self {
+		InputKey { schedule_name }
+	}
+
+	pub fn split_ref(&self, value: &serde_json::value::RawValue) -> Result<Vec<Vec<u8>>> {
+		Ok(value
+			.get()
+			.as_bytes()
+			.chunks(universaldb::utils::CHUNK_SIZE)
+			.map(|x| x.to_vec())
+			.collect())
+	}
+}
+
+impl FormalChunkedKey for InputKey {
+	type ChunkKey = InputChunkKey;
+	type Value = Box<serde_json::value::RawValue>;
+
+	fn chunk(&self, chunk: usize) -> Self::ChunkKey {
+		InputChunkKey {
+			schedule_name: self.schedule_name.clone(),
+			chunk,
+		}
+	}
+
+	fn combine(&self, chunks: Vec<Value>) -> Result<Self::Value> {
+		serde_json::value::RawValue::from_string(String::from_utf8(
+			chunks
+				.iter()
+				.map(|x| x.value().iter().map(|x| *x))
+				.flatten()
+				.collect(),
+		)?)
+		.context("failed to combine `InputKey`")
+	}
+
+	fn split(&self, value: Self::Value) -> Result<Vec<Vec<u8>>> {
+		self.split_ref(value.as_ref())
+	}
+}
+
+impl TuplePack for InputKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (SCHEDULE, DATA, &self.schedule_name, INPUT);
+		t.pack(w, tuple_depth)
+	}
+}
+
+pub struct InputChunkKey {
+	schedule_name: String,
+	chunk: usize,
+}
+
+impl TuplePack for InputChunkKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (SCHEDULE, DATA, &self.schedule_name, INPUT, self.chunk);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for InputChunkKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, schedule_name, data, chunk)) =
+			<(usize, usize, String, usize, usize)>::unpack(input, tuple_depth)?;
+		if data != INPUT {
+			return Err(PackError::Message("expected INPUT data".into()));
+		}
+
+		let v = InputChunkKey {
+			schedule_name,
+			chunk,
+		};
+
+		Ok((input, v))
+	}
+}
+
+#[derive(Debug)]
+pub struct CreateTsKey {
+	schedule_name: String,
+}
+
+impl CreateTsKey {
+	pub fn new(schedule_name: String) -> Self {
+		CreateTsKey { schedule_name }
+	}
+}
+
+impl FormalKey for CreateTsKey {
+	// Timestamp.
+	type Value = i64;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(i64::from_be_bytes(raw.try_into()?))
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.to_be_bytes().to_vec())
+	}
+}
+
+impl TuplePack for CreateTsKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (SCHEDULE, DATA, &self.schedule_name, CREATE_TS);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for CreateTsKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, schedule_name, data)) =
+			<(usize, usize, String, usize)>::unpack(input, tuple_depth)?;
+		if data != CREATE_TS {
+			return Err(PackError::Message("expected CREATE_TS data".into()));
+		}
+
+		let v = CreateTsKey { schedule_name };
+
+		Ok((input, v))
+	}
+}
+
+#[derive(Debug)]
+pub struct SchemaVersionKey {
+	schedule_name: String,
+}
+
+impl SchemaVersionKey {
+	pub fn new(schedule_name: String) -> Self {
+		SchemaVersionKey { schedule_name }
+	}
+}
+
+impl FormalKey for SchemaVersionKey {
+	/// Migration version of the input.
+	type Value = usize;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(usize::from_be_bytes(raw.try_into()?))
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.to_be_bytes().to_vec())
+	}
+}
+
+impl TuplePack for SchemaVersionKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (SCHEDULE, DATA, &self.schedule_name, SCHEMA_VERSION);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for SchemaVersionKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, schedule_name, data)) =
+			<(usize, usize, String, usize)>::unpack(input, tuple_depth)?;
+		if data != SCHEMA_VERSION {
+			return Err(PackError::Message("expected SCHEMA_VERSION data".into()));
+		}
+
+		let v = SchemaVersionKey { schedule_name };
+
+		Ok((input, v))
+	}
+}
+
+#[derive(Debug)]
+pub struct NextTsKey {
+	schedule_name: String,
+}
+
+impl NextTsKey {
+	pub fn new(schedule_name: String) -> Self {
+		NextTsKey { schedule_name }
+	}
+}
+
+impl FormalKey for NextTsKey {
+	// Timestamp of the next tick.
+	type Value = i64;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(i64::from_be_bytes(raw.try_into()?))
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.to_be_bytes().to_vec())
+	}
+}
+
+impl TuplePack for NextTsKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (SCHEDULE, DATA, &self.schedule_name, NEXT_TS);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for NextTsKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, schedule_name, data)) =
+			<(usize, usize, String, usize)>::unpack(input, tuple_depth)?;
+		if data != NEXT_TS {
+			return Err(PackError::Message("expected NEXT_TS data".into()));
+		}
+
+		let v = NextTsKey { schedule_name };
+
+		Ok((input, v))
+	}
+}
+
+#[derive(Debug)]
+pub struct PauseTsKey {
+	schedule_name: String,
+}
+
+impl PauseTsKey {
+	pub fn new(schedule_name: String) -> Self {
+		PauseTsKey { schedule_name }
+	}
+}
+
+impl FormalKey for PauseTsKey {
+	// Timestamp.
+	type Value = i64;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(i64::from_be_bytes(raw.try_into()?))
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.to_be_bytes().to_vec())
+	}
+}
+
+impl TuplePack for PauseTsKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (SCHEDULE, DATA, &self.schedule_name, PAUSE_TS);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for PauseTsKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, schedule_name, data)) =
+			<(usize, usize, String, usize)>::unpack(input, tuple_depth)?;
+		if data != PAUSE_TS {
+			return Err(PackError::Message("expected PAUSE_TS data".into()));
+		}
+
+		let v = PauseTsKey { schedule_name };
+
+		Ok((input, v))
+	}
+}
+
+#[derive(Debug)]
+pub struct LastWorkflowIdKey {
+	schedule_name: String,
+}
+
+impl LastWorkflowIdKey {
+	pub fn new(schedule_name: String) -> Self {
+		LastWorkflowIdKey { schedule_name }
+	}
+}
+
+impl FormalKey for LastWorkflowIdKey {
+	type Value = Id;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(Id::from_slice(raw)?)
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.as_bytes().to_vec())
+	}
+}
+
+impl TuplePack for LastWorkflowIdKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (SCHEDULE, DATA, &self.schedule_name, LAST_WORKFLOW_ID);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for LastWorkflowIdKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, schedule_name, data)) =
+			<(usize, usize, String, usize)>::unpack(input, tuple_depth)?;
+		if data != LAST_WORKFLOW_ID {
+			return Err(PackError::Message("expected LAST_WORKFLOW_ID data".into()));
+		}
+
+		let v = LastWorkflowIdKey { schedule_name };
+
+		Ok((input, v))
+	}
+}
+
+#[derive(Debug)]
+pub struct LastDispatchTsKey {
+	schedule_name: String,
+}
+
+impl LastDispatchTsKey {
+	pub fn new(schedule_name: String) -> Self {
+		LastDispatchTsKey { schedule_name }
+	}
+}
+
+impl FormalKey for LastDispatchTsKey {
+	// Timestamp.
+	type Value = i64;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(i64::from_be_bytes(raw.try_into()?))
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.to_be_bytes().to_vec())
+	}
+}
+
+impl TuplePack for LastDispatchTsKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (SCHEDULE, DATA, &self.schedule_name, LAST_DISPATCH_TS);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for LastDispatchTsKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, schedule_name, data)) =
+			<(usize, usize, String, usize)>::unpack(input, tuple_depth)?;
+		if data != LAST_DISPATCH_TS {
+			return Err(PackError::Message("expected LAST_DISPATCH_TS data".into()));
+		}
+
+		let v = LastDispatchTsKey { schedule_name };
+
+		Ok((input, v))
+	}
+}
+
+pub struct DataSubspaceKey {
+	schedule_name: Option<String>,
+}
+
+impl DataSubspaceKey {
+	pub fn new() -> Self {
+		DataSubspaceKey {
+			schedule_name: None,
+		}
+	}
+
+	/// All data of a single schedule.
+	pub fn for_schedule(schedule_name: String) -> Self {
+		DataSubspaceKey {
+			schedule_name: Some(schedule_name),
+		}
+	}
+}
+
+impl TuplePack for DataSubspaceKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let mut offset = VersionstampOffset::None { size: 0 };
+
+		let t = (SCHEDULE, DATA);
+		offset += t.pack(w, tuple_depth)?;
+
+		if let Some(schedule_name) = &self.schedule_name {
+			offset += schedule_name.pack(w, tuple_depth)?;
+		}
+
+		Ok(offset)
+	}
+}
+
+/// Index of unpaused schedules by their next tick.
+#[derive(Debug)]
+pub struct ByNextTsKey {
+	pub next_ts: i64,
+	pub schedule_name: String,
+}
+
+impl ByNextTsKey {
+	pub fn new(next_ts: i64, schedule_name: String) -> Self {
+		ByNextTsKey {
+			next_ts,
+			schedule_name,
+		}
+	}
+
+	pub fn subspace(ts: i64) -> ByNextTsSubspaceKey {
+		ByNextTsSubspaceKey::new(ts)
+	}
+
+	pub fn subspace_without_ts() -> ByNextTsSubspaceKey {
+		ByNextTsSubspaceKey::new_without_ts()
+	}
+}
+
+impl FormalKey for ByNextTsKey {
+	type Value = ();
+
+	fn deserialize(&self, _raw: &[u8]) -> Result<Self::Value> {
+		Ok(())
+	}
+
+	fn serialize(&self, _value: Self::Value) -> Result<Vec<u8>> {
+		Ok(Vec::new())
+	}
+}
+
+impl TuplePack for ByNextTsKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (SCHEDULE, BY_NEXT_TS, self.next_ts, &self.schedule_name);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for ByNextTsKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, next_ts, schedule_name)) =
+			<(usize, usize, i64, String)>::unpack(input, tuple_depth)?;
+		let v = ByNextTsKey {
+			next_ts,
+			schedule_name,
+		};
+
+		Ok((input, v))
+	}
+}
+
+// Structure should match `ByNextTsKey`
+pub struct ByNextTsSubspaceKey {
+	ts: Option<i64>,
+}
+
+impl ByNextTsSubspaceKey {
+	pub fn new(ts: i64) -> Self {
+		ByNextTsSubspaceKey { ts: Some(ts) }
+	}
+
+	pub fn new_without_ts() -> Self {
+		ByNextTsSubspaceKey { ts: None }
+	}
+}
+
+impl TuplePack for ByNextTsSubspaceKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let mut offset = VersionstampOffset::None { size: 0 };
+
+		let t = (SCHEDULE, BY_NEXT_TS);
+		offset += t.pack(w, tuple_depth)?;
+
+		if let Some(ts) = &self.ts {
+			offset += ts.pack(w, tuple_depth)?;
+		}
+
+		Ok(offset)
+	}
+}
@@ -236,6 +236,55 @@ impl<'de> TupleUnpack<'de> for RayIdKey {
 	}
 }
 
+#[derive(Debug)]
+pub struct TraceContextKey {
+	signal_id: Id,
+}
+
+impl TraceContextKey {
+	pub fn new(signal_id: Id) -> Self {
+		TraceContextKey { signal_id }
+	}
+}
+
+impl FormalKey for TraceContextKey {
+	/// Serialized W3C trace context (see `utils::trace`) of the span this signal was published in.
+	type Value = String;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		String::from_utf8(raw.to_vec()).map_err(Into::into)
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.into_bytes())
+	}
+}
+
+impl TuplePack for TraceContextKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (SIGNAL, DATA, self.signal_id, TRACE_CONTEXT);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for TraceContextKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, signal_id, data)) =
+			<(usize, usize, Id, usize)>::unpack(input, tuple_depth)?;
+		if data != TRACE_CONTEXT {
+			return Err(PackError::Message("expected TRACE_CONTEXT data".into()));
+		}
+
+		let v = TraceContextKey { signal_id };
+
+		Ok((input, v))
+	}
+}
+
 #[derive(Debug)]
 pub struct NameKey {
 	signal_id: Id,
@@ -332,11 +381,20 @@ impl<'de> TupleUnpack<'de> for WorkflowIdKey {
 	}
 }
 
-pub struct DataSubspaceKey {}
+pub struct DataSubspaceKey {
+	signal_id: Option<Id>,
+}
 
 impl DataSubspaceKey {
 	pub fn new() -> Self {
-		DataSubspaceKey {}
+		DataSubspaceKey { signal_id: None }
+	}
+
+	/// All data of a single signal.
+	pub fn for_signal(signal_id: Id) -> Self {
+		DataSubspaceKey {
+			signal_id: Some(signal_id),
+		}
 	}
 }
 
@@ -346,8 +404,16 @@ impl TuplePack for DataSubspaceKey {
 		w: &mut W,
 		tuple_depth: TupleDepth,
 	) -> std::io::Result<VersionstampOffset> {
+		let mut offset = VersionstampOffset::None { size: 0 };
+
 		let t = (SIGNAL, DATA);
-		t.pack(w, tuple_depth)
+		offset += t.pack(w, tuple_depth)?;
+
+		if let Some(signal_id) = &self.signal_id {
+			offset += signal_id.pack(w, tuple_depth)?;
+		}
+
+		Ok(offset)
 	}
 }
 
@@ -804,6 +804,10 @@ impl PendingSignalKey {
 	pub fn subspace(workflow_id: Id, signal_name: String) -> PendingSignalSubspaceKey {
 		PendingSignalSubspaceKey::new(workflow_id, signal_name)
 	}
+
+	pub fn subspace_without_name(workflow_id: Id) -> PendingSignalSubspaceKey {
+		PendingSignalSubspaceKey::new_without_name(workflow_id)
+	}
 }
 
 impl FormalKey for PendingSignalKey {
@@ -852,16 +856,24 @@ impl<'de> TupleUnpack<'de> for PendingSignalKey {
 	}
 }
 
+// Structure should match `PendingSignalKey`
 pub struct PendingSignalSubspaceKey {
 	workflow_id: Id,
-	signal_name: String,
+	signal_name: Option<String>,
 }
 
 impl PendingSignalSubspaceKey {
 	pub fn new(workflow_id: Id, signal_name: String) -> Self {
 		PendingSignalSubspaceKey {
 			workflow_id,
-			signal_name,
+			signal_name: Some(signal_name),
+		}
+	}
+
+	pub fn new_without_name(workflow_id: Id) -> Self {
+		PendingSignalSubspaceKey {
+			workflow_id,
+			signal_name: None,
 		}
 	}
 }
@@ -872,14 +884,16 @@ impl TuplePack for PendingSignalSubspaceKey {
 		w: &mut W,
 		tuple_depth: TupleDepth,
 	) -> std::io::Result<VersionstampOffset> {
-		let t = (
-			WORKFLOW,
-			SIGNAL,
-			self.workflow_id,
-			PENDING,
-			&self.signal_name,
-		);
-		t.pack(w, tuple_depth)
+		let mut offset = VersionstampOffset::None { size: 0 };
+
+		let t = (WORKFLOW, SIGNAL, self.workflow_id, PENDING);
+		offset += t.pack(w, tuple_depth)?;
+
+		if let Some(signal_name) = &self.signal_name {
+			offset += signal_name.pack(w, tuple_depth)?;
+		}
+
+		Ok(offset)
 	}
 }
 
@@ -1108,11 +1122,20 @@ impl<'de> TupleUnpack<'de> for WorkerInstanceIdKey {
 	}
 }
 
-pub struct DataSubspaceKey {}
+pub struct DataSubspaceKey {
+	workflow_id: Option<Id>,
+}
 
 impl DataSubspaceKey {
 	pub fn new() -> Self {
-		DataSubspaceKey {}
+		DataSubspaceKey { workflow_id: None }
+	}
+
+	/// All data of a single workflow, including its history.
+	pub fn for_workflow(workflow_id: Id) -> Self {
+		DataSubspaceKey {
+			workflow_id: Some(workflow_id),
+		}
 	}
 }
 
@@ -1122,8 +1145,16 @@ impl TuplePack for DataSubspaceKey {
 		w: &mut W,
 		tuple_depth: TupleDepth,
 	) -> std::io::Result<VersionstampOffset> {
+		let mut offset = VersionstampOffset::None { size: 0 };
+
 		let t = (WORKFLOW, DATA);
-		t.pack(w, tuple_depth)
+		offset += t.pack(w, tuple_depth)?;
+
+		if let Some(workflow_id) = &self.workflow_id {
+			offset += workflow_id.pack(w, tuple_depth)?;
+		}
+
+		Ok(offset)
 	}
 }
 
@@ -1175,3 +1206,555 @@ impl<'de> TupleUnpack<'de> for SilenceTsKey {
 		Ok((input, v))
 	}
 }
+
+#[derive(Debug)]
+pub struct TerminationKey {
+	workflow_id: Id,
+}
+
+impl TerminationKey {
+	pub fn new(workflow_id: Id) -> Self {
+		TerminationKey { workflow_id }
+	}
+}
+
+impl FormalKey for TerminationKey {
+	type Value = crate::cancel::Termination;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		serde_json::from_slice(raw).map_err(Into::into)
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		serde_json::to_vec(&value).map_err(Into::into)
+	}
+}
+
+impl TuplePack for TerminationKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (WORKFLOW, DATA, self.workflow_id, TERMINATION);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for TerminationKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, workflow_id, data)) =
+			<(usize, usize, Id, usize)>::unpack(input, tuple_depth)?;
+		if data != TERMINATION {
+			return Err(PackError::Message("expected TERMINATION data".into()));
+		}
+
+		let v = TerminationKey { workflow_id };
+
+		Ok((input, v))
+	}
+}
+
+#[derive(Debug)]
+pub struct SignalIdempotencyKey {
+	workflow_id: Id,
+	idempotency_key: String,
+}
+
+impl SignalIdempotencyKey {
+	pub fn new(workflow_id: Id, idempotency_key: String) -> Self {
+		SignalIdempotencyKey {
+			workflow_id,
+			idempotency_key,
+		}
+	}
+}
+
+impl FormalKey for SignalIdempotencyKey {
+	/// Signal id, create ts.
+	type Value = (Id, i64);
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		serde_json::from_slice(raw).map_err(Into::into)
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		serde_json::to_vec(&value).map_err(Into::into)
+	}
+}
+
+impl TuplePack for SignalIdempotencyKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (
+			WORKFLOW,
+			DATA,
+			self.workflow_id,
+			SIGNAL_IDEMPOTENCY,
+			&self.idempotency_key,
+		);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for SignalIdempotencyKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, workflow_id, data, idempotency_key)) =
+			<(usize, usize, Id, usize, String)>::unpack(input, tuple_depth)?;
+		if data != SIGNAL_IDEMPOTENCY {
+			return Err(PackError::Message(
+				"expected SIGNAL_IDEMPOTENCY data".into(),
+			));
+		}
+
+		let v = SignalIdempotencyKey {
+			workflow_id,
+			idempotency_key,
+		};
+
+		Ok((input, v))
+	}
+}
+
+#[derive(Debug)]
+pub struct SchemaVersionKey {
+	workflow_id: Id,
+}
+
+impl SchemaVersionKey {
+	pub fn new(workflow_id: Id) -> Self {
+		SchemaVersionKey { workflow_id }
+	}
+}
+
+impl FormalKey for SchemaVersionKey {
+	/// Migration version of the input and state.
+	type Value = usize;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(usize::from_be_bytes(raw.try_into()?))
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.to_be_bytes().to_vec())
+	}
+}
+
+impl TuplePack for SchemaVersionKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (WORKFLOW, DATA, self.workflow_id, SCHEMA_VERSION);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for SchemaVersionKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, workflow_id, data)) =
+			<(usize, usize, Id, usize)>::unpack(input, tuple_depth)?;
+		if data != SCHEMA_VERSION {
+			return Err(PackError::Message("expected SCHEMA_VERSION data".into()));
+		}
+
+		let v = SchemaVersionKey { workflow_id };
+
+		Ok((input, v))
+	}
+}
+
+#[derive(Debug)]
+pub struct TraceContextKey {
+	workflow_id: Id,
+}
+
+impl TraceContextKey {
+	pub fn new(workflow_id: Id) -> Self {
+		TraceContextKey { workflow_id }
+	}
+}
+
+impl FormalKey for TraceContextKey {
+	/// Serialized W3C trace context (see `utils::trace`) of the span this workflow was dispatched in.
+	type Value = String;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		String::from_utf8(raw.to_vec()).map_err(Into::into)
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.into_bytes())
+	}
+}
+
+impl TuplePack for TraceContextKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (WORKFLOW, DATA, self.workflow_id, TRACE_CONTEXT);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for TraceContextKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, workflow_id, data)) =
+			<(usize, usize, Id, usize)>::unpack(input, tuple_depth)?;
+		if data != TRACE_CONTEXT {
+			return Err(PackError::Message("expected TRACE_CONTEXT data".into()));
+		}
+
+		let v = TraceContextKey { workflow_id };
+
+		Ok((input, v))
+	}
+}
+
+/// Marks that the active history of another workflow references this workflow as a sub workflow.
+#[derive(Debug)]
+pub struct ReferencedByKey {
+	workflow_id: Id,
+	pub referencing_workflow_id: Id,
+}
+
+impl ReferencedByKey {
+	pub fn new(workflow_id: Id, referencing_workflow_id: Id) -> Self {
+		ReferencedByKey {
+			workflow_id,
+			referencing_workflow_id,
+		}
+	}
+
+	pub fn subspace(workflow_id: Id) -> ReferencedBySubspaceKey {
+		ReferencedBySubspaceKey::new(workflow_id)
+	}
+}
+
+impl FormalKey for ReferencedByKey {
+	type Value = ();
+
+	fn deserialize(&self, _raw: &[u8]) -> Result<Self::Value> {
+		Ok(())
+	}
+
+	fn serialize(&self, _value: Self::Value) -> Result<Vec<u8>> {
+		Ok(Vec::new())
+	}
+}
+
+impl TuplePack for ReferencedByKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (
+			WORKFLOW,
+			DATA,
+			self.workflow_id,
+			REFERENCED_BY,
+			self.referencing_workflow_id,
+		);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for ReferencedByKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, workflow_id, data, referencing_workflow_id)) =
+			<(usize, usize, Id, usize, Id)>::unpack(input, tuple_depth)?;
+		if data != REFERENCED_BY {
+			return Err(PackError::Message("expected REFERENCED_BY data".into()));
+		}
+
+		let v = ReferencedByKey {
+			workflow_id,
+			referencing_workflow_id,
+		};
+
+		Ok((input, v))
+	}
+}
+
+pub struct ReferencedBySubspaceKey {
+	workflow_id: Id,
+}
+
+impl ReferencedBySubspaceKey {
+	pub fn new(workflow_id: Id) -> Self {
+		ReferencedBySubspaceKey { workflow_id }
+	}
+}
+
+impl TuplePack for ReferencedBySubspaceKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (WORKFLOW, DATA, self.workflow_id, REFERENCED_BY);
+		t.pack(w, tuple_depth)
+	}
+}
+
+/// Timestamp of the workflow's current entry in the retention index (`ByNameAndCompleteTsKey`).
+#[derive(Debug)]
+pub struct CompleteTsKey {
+	workflow_id: Id,
+}
+
+impl CompleteTsKey {
+	pub fn new(workflow_id: Id) -> Self {
+		CompleteTsKey { workflow_id }
+	}
+}
+
+impl FormalKey for CompleteTsKey {
+	// Timestamp.
+	type Value = i64;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(i64::from_be_bytes(raw.try_into()?))
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.to_be_bytes().to_vec())
+	}
+}
+
+impl TuplePack for CompleteTsKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (WORKFLOW, DATA, self.workflow_id, COMPLETE_TS);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for CompleteTsKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, workflow_id, data)) =
+			<(usize, usize, Id, usize)>::unpack(input, tuple_depth)?;
+		if data != COMPLETE_TS {
+			return Err(PackError::Message("expected COMPLETE_TS data".into()));
+		}
+
+		let v = CompleteTsKey { workflow_id };
+
+		Ok((input, v))
+	}
+}
+
+/// Index of stopped (completed, cancelled, or terminated) workflows used for retention.
+#[derive(Debug)]
+pub struct ByNameAndCompleteTsKey {
+	workflow_name: String,
+	pub complete_ts: i64,
+	pub workflow_id: Id,
+}
+
+impl ByNameAndCompleteTsKey {
+	pub fn new(workflow_name: String, complete_ts: i64, workflow_id: Id) -> Self {
+		ByNameAndCompleteTsKey {
+			workflow_name,
+			complete_ts,
+			workflow_id,
+		}
+	}
+
+	pub fn subspace(workflow_name: String, ts: i64) -> ByNameAndCompleteTsSubspaceKey {
+		ByNameAndCompleteTsSubspaceKey::new(workflow_name, ts)
+	}
+
+	pub fn subspace_without_ts(workflow_name: String) -> ByNameAndCompleteTsSubspaceKey {
+		ByNameAndCompleteTsSubspaceKey::new_without_ts(workflow_name)
+	}
+}
+
+impl FormalKey for ByNameAndCompleteTsKey {
+	type Value = ();
+
+	fn deserialize(&self, _raw: &[u8]) -> Result<Self::Value> {
+		Ok(())
+	}
+
+	fn serialize(&self, _value: Self::Value) -> Result<Vec<u8>> {
+		Ok(Vec::new())
+	}
+}
+
+impl TuplePack for ByNameAndCompleteTsKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (
+			WORKFLOW,
+			BY_NAME_AND_COMPLETE_TS,
+			&self.workflow_name,
+			self.complete_ts,
+			self.workflow_id,
+		);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for ByNameAndCompleteTsKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, workflow_name, complete_ts, workflow_id)) =
+			<(usize, usize, String, i64, Id)>::unpack(input, tuple_depth)?;
+		let v = ByNameAndCompleteTsKey {
+			workflow_name,
+			complete_ts,
+			workflow_id,
+		};
+
+		Ok((input, v))
+	}
+}
+
+// Structure should match `ByNameAndCompleteTsKey`
+pub struct ByNameAndCompleteTsSubspaceKey {
+	workflow_name: String,
+	ts: Option<i64>,
+}
+
+impl ByNameAndCompleteTsSubspaceKey {
+	pub fn new(workflow_name: String, ts: i64) -> Self {
+		ByNameAndCompleteTsSubspaceKey {
+			workflow_name,
+			ts: Some(ts),
+		}
+	}
+
+	pub fn new_without_ts(workflow_name: String) -> Self {
+		ByNameAndCompleteTsSubspaceKey {
+			workflow_name,
+			ts: None,
+		}
+	}
+}
+
+impl TuplePack for ByNameAndCompleteTsSubspaceKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let mut offset = VersionstampOffset::None { size: 0 };
+
+		let t = (WORKFLOW, BY_NAME_AND_COMPLETE_TS, &self.workflow_name);
+		offset += t.pack(w, tuple_depth)?;
+
+		if let Some(ts) = &self.ts {
+			offset += ts.pack(w, tuple_depth)?;
+		}
+
+		Ok(offset)
+	}
+}
+
+/// Held by the worker currently deleting stopped workflows of the given name.
+#[derive(Debug)]
+pub struct RetentionGcLockKey {
+	workflow_name: String,
+}
+
+impl RetentionGcLockKey {
+	pub fn new(workflow_name: String) -> Self {
+		RetentionGcLockKey { workflow_name }
+	}
+}
+
+impl FormalKey for RetentionGcLockKey {
+	// Timestamp.
+	type Value = i64;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(i64::from_be_bytes(raw.try_into()?))
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.to_be_bytes().to_vec())
+	}
+}
+
+impl TuplePack for RetentionGcLockKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (WORKFLOW, RETENTION_GC_LOCK, &self.workflow_name);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for RetentionGcLockKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, workflow_name)) = <(usize, usize, String)>::unpack(input, tuple_depth)?;
+		let v = RetentionGcLockKey { workflow_name };
+
+		Ok((input, v))
+	}
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub enum RetentionBackfillCursor {
+	/// Workflows up to and including this one have been backfilled.
+	After(Id),
+	Complete,
+}
+
+/// Progress of indexing workflows that existed before the retention index (`ByNameAndCompleteTsKey`) and
+/// sub workflow references (`ReferencedByKey`) were written.
+#[derive(Debug)]
+pub struct RetentionBackfillCursorKey {}
+
+impl RetentionBackfillCursorKey {
+	pub fn new() -> Self {
+		RetentionBackfillCursorKey {}
+	}
+}
+
+impl FormalKey for RetentionBackfillCursorKey {
+	type Value = RetentionBackfillCursor;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		serde_json::from_slice(raw).map_err(Into::into)
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		serde_json::to_vec(&value).map_err(Into::into)
+	}
+}
+
+impl TuplePack for RetentionBackfillCursorKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (WORKFLOW, RETENTION_BACKFILL_CURSOR);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for RetentionBackfillCursorKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _)) = <(usize, usize)>::unpack(input, tuple_depth)?;
+		let v = RetentionBackfillCursorKey {};
+
+		Ok((input, v))
+	}
+}
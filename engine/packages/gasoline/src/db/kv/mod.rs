@@ -2,7 +2,7 @@
 // TODO: Move code to smaller functions for readability
 
 use std::{
-	collections::{HashMap, HashSet},
+	collections::{HashMap, HashSet, VecDeque},
 	sync::Arc,
 	time::Instant,
 };
@@ -23,8 +23,9 @@ use universaldb::{
 
 use rivet_metrics::KeyValue;
 
-use super::{Database, PulledWorkflowData, SignalData, WorkflowData};
+use super::{Database, PullFilter, PulledWorkflowData, QueryData, SignalData, WorkflowData};
 use crate::{
+	cancel::{Cancel, Termination},
 	error::{WorkflowError, WorkflowResult},
 	history::{
 		event::{
@@ -34,6 +35,8 @@ use crate::{
 		location::Location,
 	},
 	metrics,
+	signal::Signal,
+	utils,
 };
 
 mod debug;
@@ -45,6 +48,16 @@ const WORKER_INSTANCE_LOST_THRESHOLD_MS: i64 = rivet_util::duration::seconds(30)
 const METRICS_LOCK_TIMEOUT_MS: i64 = rivet_util::duration::seconds(30);
 /// For pubsub wake mechanism.
 const WORKER_WAKE_SUBJECT: &str = "gasoline.worker.wake";
+/// How many stopped workflows to delete per workflow name in a single GC transaction.
+const GC_BATCH_SIZE: usize = 100;
+/// How long before overwriting an existing retention GC lock.
+const GC_LOCK_TIMEOUT_MS: i64 = rivet_util::duration::minutes(2);
+/// How many workflows to backfill into the retention index in a single transaction.
+const GC_BACKFILL_BATCH_SIZE: usize = 100;
+/// How many due schedules to dispatch in a single transaction.
+const SCHEDULE_BATCH_SIZE: usize = 32;
+/// How long a signal idempotency key deduplicates signals published to the same workflow.
+const SIGNAL_IDEMPOTENCY_WINDOW_MS: i64 = rivet_util::duration::hours(24);
 
 pub struct DatabaseKv {
 	pools: rivet_pools::Pools,
@@ -102,6 +115,178 @@ impl DatabaseKv {
 		Ok(())
 	}
 
+	/// Reads the active history of a workflow, grouped by the location of the parent of each event.
+	async fn read_active_history(
+		&self,
+		workflow_id: Id,
+		isolation_level: universaldb::utils::IsolationLevel,
+		tx: &universaldb::Transaction,
+	) -> Result<HashMap<Location, Vec<Event>>> {
+		let mut events_by_location: HashMap<Location, Vec<Event>> = HashMap::new();
+		let mut current_event = WorkflowHistoryEventBuilder::new(Location::empty());
+
+		let active_history_subspace =
+			self.subspace
+				.subspace(&keys::history::HistorySubspaceKey::new(
+					workflow_id,
+					keys::history::HistorySubspaceVariant::Active,
+				));
+
+		let mut stream = tx.get_ranges_keyvalues(
+			universaldb::RangeOption {
+				mode: StreamingMode::WantAll,
+				..(&active_history_subspace).into()
+			},
+			isolation_level,
+		);
+
+		loop {
+			let Some(entry) = stream.try_next().await? else {
+				break;
+			};
+
+			// Parse only the wf id and location of the current key
+			let partial_key = self
+				.subspace
+				.unpack::<keys::history::PartialEventKey>(entry.key())?;
+
+			if current_event.location != partial_key.location {
+				if current_event.location.is_empty() {
+					current_event = WorkflowHistoryEventBuilder::new(partial_key.location);
+				} else {
+					// Insert current event builder to into wf events and
+					// reset state
+					let previous_event = std::mem::replace(
+						&mut current_event,
+						WorkflowHistoryEventBuilder::new(partial_key.location),
+					);
+					events_by_location
+						.entry(previous_event.location.root())
+						.or_default()
+						.push(Event::try_from(previous_event)?);
+				}
+			}
+
+			// Parse current key as any event key
+			if let Ok(key) = self
+				.subspace
+				.unpack::<keys::history::EventTypeKey>(entry.key())
+			{
+				let event_type = key.deserialize(entry.value())?;
+
+				current_event.event_type = Some(event_type);
+			} else if let Ok(key) = self
+				.subspace
+				.unpack::<keys::history::VersionKey>(entry.key())
+			{
+				let version = key.deserialize(entry.value())?;
+
+				current_event.version = Some(version);
+			} else if let Ok(key) = self
+				.subspace
+				.unpack::<keys::history::CreateTsKey>(entry.key())
+			{
+				let create_ts = key.deserialize(entry.value())?;
+
+				current_event.create_ts = Some(create_ts);
+			} else if let Ok(key) = self.subspace.unpack::<keys::history::NameKey>(entry.key()) {
+				let name = key.deserialize(entry.value())?;
+
+				current_event.name = Some(name);
+			} else if let Ok(key) = self
+				.subspace
+				.unpack::<keys::history::SignalIdKey>(entry.key())
+			{
+				let signal_id = key.deserialize(entry.value())?;
+
+				current_event.signal_id = Some(signal_id);
+			} else if let Ok(key) = self
+				.subspace
+				.unpack::<keys::history::SubWorkflowIdKey>(entry.key())
+			{
+				let sub_workflow_id = key.deserialize(entry.value())?;
+
+				current_event.sub_workflow_id = Some(sub_workflow_id);
+			} else if let Ok(_key) = self
+				.subspace
+				.unpack::<keys::history::InputChunkKey>(entry.key())
+			{
+				current_event.input_chunks.push(entry);
+			} else if let Ok(_key) = self
+				.subspace
+				.unpack::<keys::history::OutputChunkKey>(entry.key())
+			{
+				current_event.output_chunks.push(entry);
+			} else if let Ok(_key) = self.subspace.unpack::<keys::history::ErrorKey>(entry.key()) {
+				current_event.error_count += 1;
+			} else if let Ok(key) = self
+				.subspace
+				.unpack::<keys::history::IterationKey>(entry.key())
+			{
+				let iteration = key.deserialize(entry.value())?;
+
+				current_event.iteration = Some(iteration);
+			} else if let Ok(key) = self
+				.subspace
+				.unpack::<keys::history::DeadlineTsKey>(entry.key())
+			{
+				let deadline_ts = key.deserialize(entry.value())?;
+
+				current_event.deadline_ts = Some(deadline_ts);
+			} else if let Ok(key) = self
+				.subspace
+				.unpack::<keys::history::SleepStateKey>(entry.key())
+			{
+				let sleep_state = key.deserialize(entry.value())?;
+
+				current_event.sleep_state = Some(sleep_state);
+			} else if let Ok(key) = self
+				.subspace
+				.unpack::<keys::history::InnerEventTypeKey>(entry.key())
+			{
+				let inner_event_type = key.deserialize(entry.value())?;
+
+				current_event.inner_event_type = Some(inner_event_type);
+			}
+
+			// We ignore keys we don't need (like tags)
+		}
+		// Insert final event
+		if !current_event.location.is_empty() {
+			events_by_location
+				.entry(current_event.location.root())
+				.or_default()
+				.push(Event::try_from(current_event)?);
+		}
+
+		Ok(events_by_location)
+	}
+
+	/// Writes the retention index entry of a stopped workflow. Its data is deleted once `ts` is older than the
+	/// workflow's retention.
+	fn write_retention_idx(
+		&self,
+		workflow_id: Id,
+		workflow_name: &str,
+		ts: i64,
+		tx: &universaldb::Transaction,
+	) -> Result<()> {
+		let by_name_and_complete_ts_key =
+			keys::workflow::ByNameAndCompleteTsKey::new(workflow_name.to_string(), ts, workflow_id);
+		tx.set(
+			&self.subspace.pack(&by_name_and_complete_ts_key),
+			&by_name_and_complete_ts_key.serialize(())?,
+		);
+
+		let complete_ts_key = keys::workflow::CompleteTsKey::new(workflow_id);
+		tx.set(
+			&self.subspace.pack(&complete_ts_key),
+			&complete_ts_key.serialize(ts)?,
+		);
+
+		Ok(())
+	}
+
 	fn write_sub_workflow_wake_idx(
 		&self,
 		workflow_id: Id,
@@ -127,13 +312,15 @@ impl DatabaseKv {
 		signal_id: Id,
 		signal_name: &str,
 		body: &serde_json::value::RawValue,
+		idempotency_key: Option<&str>,
 		tx: &universaldb::Transaction,
-	) -> Result<()> {
+	) -> Result<Id> {
 		tracing::debug!(
 			?ray_id,
 			?workflow_id,
 			?signal_id,
 			?signal_name,
+			?idempotency_key,
 			"publishing signal"
 		);
 
@@ -150,6 +337,34 @@ impl DatabaseKv {
 
 		let workflow_name = workflow_name_key.deserialize(&workflow_name_entry)?;
 
+		if let Some(idempotency_key) = idempotency_key {
+			let signal_idempotency_key =
+				keys::workflow::SignalIdempotencyKey::new(workflow_id, idempotency_key.to_string());
+			let now = rivet_util::timestamp::now();
+
+			// Return the original signal if one with the same key was published recently
+			if let Some(entry) = tx
+				.get(&self.subspace.pack(&signal_idempotency_key), Serializable)
+				.await?
+			{
+				let (original_signal_id, create_ts) = signal_idempotency_key.deserialize(&entry)?;
+
+				if now - create_ts < SIGNAL_IDEMPOTENCY_WINDOW_MS {
+					tracing::debug!(
+						?original_signal_id,
+						"signal already published, deduplicating"
+					);
+
+					return Ok(original_signal_id);
+				}
+			}
+
+			tx.set(
+				&self.subspace.pack(&signal_idempotency_key),
+				&signal_idempotency_key.serialize((signal_id, now))?,
+			);
+		}
+
 		// Write name
 		let name_key = keys::signal::NameKey::new(signal_id);
 		tx.set(
@@ -175,59 +390,935 @@ impl DatabaseKv {
 			&pending_signal_key.serialize(())?,
 		);
 
-		// Write create ts
-		let create_ts_key = keys::signal::CreateTsKey::new(signal_id);
-		tx.set(
-			&self.subspace.pack(&create_ts_key),
-			&create_ts_key.serialize(pending_signal_key.ts)?,
-		);
+		// Write create ts
+		let create_ts_key = keys::signal::CreateTsKey::new(signal_id);
+		tx.set(
+			&self.subspace.pack(&create_ts_key),
+			&create_ts_key.serialize(pending_signal_key.ts)?,
+		);
+
+		// Write ray id
+		let ray_id_key = keys::signal::RayIdKey::new(signal_id);
+		tx.set(
+			&self.subspace.pack(&ray_id_key),
+			&ray_id_key.serialize(ray_id)?,
+		);
+
+		// Write trace context so the receiving workflow can link to the publisher
+		if let Some(trace_context) = utils::trace::current_trace_context() {
+			let trace_context_key = keys::signal::TraceContextKey::new(signal_id);
+			tx.set(
+				&self.subspace.pack(&trace_context_key),
+				&trace_context_key.serialize(trace_context)?,
+			);
+		}
+
+		// Write workflow id
+		let workflow_id_key = keys::signal::WorkflowIdKey::new(signal_id);
+		tx.set(
+			&self.subspace.pack(&workflow_id_key),
+			&workflow_id_key.serialize(workflow_id)?,
+		);
+
+		let wake_signal_key =
+			keys::workflow::WakeSignalKey::new(workflow_id, signal_name.to_string());
+
+		// If the workflow currently has a wake signal key for this signal, wake it
+		if tx
+			.get(&self.subspace.pack(&wake_signal_key), Serializable)
+			.await?
+			.is_some()
+		{
+			let mut wake_condition_key = keys::wake::WorkflowWakeConditionKey::new(
+				workflow_name,
+				workflow_id,
+				keys::wake::WakeCondition::Signal { signal_id },
+			);
+			wake_condition_key.ts = pending_signal_key.ts;
+
+			// Add wake condition for workflow
+			tx.set(
+				&self.subspace.pack(&wake_condition_key),
+				&wake_condition_key.serialize(())?,
+			);
+		}
+
+		update_metric(
+			&tx.with_subspace(self.subspace.clone()),
+			None,
+			Some(keys::metric::GaugeMetric::SignalPending(
+				signal_name.to_string(),
+			)),
+		);
+
+		Ok(signal_id)
+	}
+
+	async fn cancel_workflow_inner(
+		&self,
+		ray_id: Id,
+		workflow_id: Id,
+		signal_id: Id,
+		reason: Option<&str>,
+		propagate: bool,
+		tx: &universaldb::Transaction,
+	) -> Result<Vec<Id>> {
+		let body = serde_json::value::to_raw_value(&Cancel {
+			reason: reason.map(|x| x.to_string()),
+		})
+		.map_err(WorkflowError::SerializeSignalBody)?;
+
+		let mut cancelled_workflow_ids = Vec::new();
+		let mut visited = HashSet::new();
+		let mut queue = VecDeque::from([(workflow_id, signal_id)]);
+
+		while let Some((current_workflow_id, signal_id)) = queue.pop_front() {
+			// Unique sub workflows can be shared
+			if !visited.insert(current_workflow_id) {
+				continue;
+			}
+
+			let name_key = keys::workflow::NameKey::new(current_workflow_id);
+			let lease_key = keys::workflow::LeaseKey::new(current_workflow_id);
+			let has_wake_condition_key =
+				keys::workflow::HasWakeConditionKey::new(current_workflow_id);
+			let termination_key = keys::workflow::TerminationKey::new(current_workflow_id);
+			let silence_ts_key = keys::workflow::SilenceTsKey::new(current_workflow_id);
+			let output_subspace = self
+				.subspace
+				.subspace(&keys::workflow::OutputKey::new(current_workflow_id));
+			let pending_cancel_subspace =
+				self.subspace
+					.subspace(&keys::workflow::PendingSignalKey::subspace(
+						current_workflow_id,
+						Cancel::NAME.to_string(),
+					));
+
+			let (
+				name_entry,
+				is_running,
+				has_wake_condition,
+				is_terminated,
+				is_silenced,
+				has_output,
+				is_cancelling,
+			) = tokio::try_join!(
+				tx.get(&self.subspace.pack(&name_key), Serializable),
+				async {
+					tx.get(&self.subspace.pack(&lease_key), Serializable)
+						.await
+						.map(|x| x.is_some())
+				},
+				async {
+					tx.get(&self.subspace.pack(&has_wake_condition_key), Serializable)
+						.await
+						.map(|x| x.is_some())
+				},
+				async {
+					tx.get(&self.subspace.pack(&termination_key), Serializable)
+						.await
+						.map(|x| x.is_some())
+				},
+				async {
+					tx.get(&self.subspace.pack(&silence_ts_key), Serializable)
+						.await
+						.map(|x| x.is_some())
+				},
+				async {
+					tx.get_ranges_keyvalues(
+						universaldb::RangeOption {
+							mode: StreamingMode::WantAll,
+							limit: Some(1),
+							..(&output_subspace).into()
+						},
+						Serializable,
+					)
+					.try_next()
+					.await
+					.map(|x| x.is_some())
+				},
+				async {
+					tx.get_ranges_keyvalues(
+						universaldb::RangeOption {
+							mode: StreamingMode::WantAll,
+							limit: Some(1),
+							..(&pending_cancel_subspace).into()
+						},
+						Serializable,
+					)
+					.try_next()
+					.await
+					.map(|x| x.is_some())
+				},
+			)?;
+
+			let Some(name_entry) = name_entry else {
+				if current_workflow_id == workflow_id {
+					return Err(WorkflowError::WorkflowNotFound.into());
+				}
+
+				tracing::warn!(sub_workflow_id=?current_workflow_id, "sub workflow not found");
+				continue;
+			};
+
+			// Workflows that already stopped cannot be cancelled
+			if has_output || is_terminated || is_silenced {
+				continue;
+			}
+
+			if !is_cancelling {
+				self.publish_signal_inner(
+					ray_id,
+					current_workflow_id,
+					signal_id,
+					Cancel::NAME,
+					&body,
+					None,
+					tx,
+				)
+				.await?;
+
+				// Wake sleeping workflows immediately instead of waiting for a listen on the cancel signal.
+				// Running workflows check for a pending cancel signal in `commit_workflow` and dead workflows
+				// are not woken.
+				if !is_running && has_wake_condition {
+					let workflow_name = name_key.deserialize(&name_entry)?;
+					let wake_condition_key = keys::wake::WorkflowWakeConditionKey::new(
+						workflow_name,
+						current_workflow_id,
+						keys::wake::WakeCondition::Immediate,
+					);
+
+					tx.set(
+						&self.subspace.pack(&wake_condition_key),
+						&wake_condition_key.serialize(())?,
+					);
+				}
+
+				cancelled_workflow_ids.push(current_workflow_id);
+			}
+
+			if propagate {
+				let active_history_subspace =
+					self.subspace
+						.subspace(&keys::history::HistorySubspaceKey::new(
+							current_workflow_id,
+							keys::history::HistorySubspaceVariant::Active,
+						));
+
+				let mut stream = tx.get_ranges_keyvalues(
+					universaldb::RangeOption {
+						mode: StreamingMode::WantAll,
+						..(&active_history_subspace).into()
+					},
+					// Must be a Snapshot to not conflict with the workflow writing to its history
+					Snapshot,
+				);
+
+				while let Some(entry) = stream.try_next().await? {
+					if let Ok(key) = self
+						.subspace
+						.unpack::<keys::history::SubWorkflowIdKey>(entry.key())
+					{
+						let sub_workflow_id = key.deserialize(entry.value())?;
+
+						queue.push_back((sub_workflow_id, Id::new_v1(sub_workflow_id.label())));
+					}
+				}
+			}
+		}
+
+		Ok(cancelled_workflow_ids)
+	}
+
+	/// Writes the terminal state of a workflow and clears all of its indexes. Wakes all workflows waiting on
+	/// this one. Returns true if any workflows were woken.
+	async fn terminate_workflow_inner(
+		&self,
+		workflow_id: Id,
+		workflow_name: &str,
+		termination: Termination,
+		tx: &universaldb::Transaction,
+	) -> Result<bool> {
+		let sub_workflow_wake_subspace = self
+			.subspace
+			.subspace(&keys::wake::SubWorkflowWakeKey::subspace(workflow_id));
+		let tags_subspace = self
+			.subspace
+			.subspace(&keys::workflow::TagKey::subspace(workflow_id));
+		let wake_deadline_key = keys::workflow::WakeDeadlineKey::new(workflow_id);
+		let wake_sub_workflow_key = keys::workflow::WakeSubWorkflowKey::new(workflow_id);
+
+		let mut stream = tx.get_ranges_keyvalues(
+			universaldb::RangeOption {
+				mode: StreamingMode::WantAll,
+				..(&sub_workflow_wake_subspace).into()
+			},
+			// NOTE: Must be Serializable to conflict with `get_sub_workflow`
+			Serializable,
+		);
+
+		let (wrote_to_wake_idx, tag_keys, wake_deadline_entry, wake_sub_workflow_entry) = tokio::try_join!(
+			// Check for other workflows waiting on this one, wake all
+			async {
+				let mut wrote_to_wake_idx = false;
+
+				while let Some(entry) = stream.try_next().await? {
+					let sub_workflow_wake_key = self
+						.subspace
+						.unpack::<keys::wake::SubWorkflowWakeKey>(&entry.key())?;
+					let workflow_name = sub_workflow_wake_key.deserialize(entry.value())?;
+
+					let wake_condition_key = keys::wake::WorkflowWakeConditionKey::new(
+						workflow_name,
+						sub_workflow_wake_key.workflow_id,
+						keys::wake::WakeCondition::SubWorkflow {
+							sub_workflow_id: workflow_id,
+						},
+					);
+
+					// Add wake condition for workflow
+					tx.set(
+						&self.subspace.pack(&wake_condition_key),
+						&wake_condition_key.serialize(())?,
+					);
+
+					// Clear secondary index
+					tx.clear(entry.key());
+
+					wrote_to_wake_idx = true;
+				}
+
+				Ok(wrote_to_wake_idx)
+			},
+			// Read tags
+			tx.get_ranges_keyvalues(
+				universaldb::RangeOption {
+					mode: StreamingMode::WantAll,
+					..(&tags_subspace).into()
+				},
+				Serializable,
+			)
+			.map(|res| {
+				self.subspace
+					.unpack::<keys::workflow::TagKey>(res?.key())
+					.map_err(anyhow::Error::from)
+			})
+			.try_collect::<Vec<_>>(),
+			tx.get(&self.subspace.pack(&wake_deadline_key), Serializable),
+			tx.get(&self.subspace.pack(&wake_sub_workflow_key), Serializable),
+		)?;
+
+		for key in tag_keys {
+			let by_name_and_tag_key = keys::workflow::ByNameAndTagKey::new(
+				workflow_name.to_string(),
+				key.k,
+				key.v,
+				workflow_id,
+			);
+			tx.clear(&self.subspace.pack(&by_name_and_tag_key));
+		}
+
+		// Clear null key
+		{
+			let by_name_and_tag_key =
+				keys::workflow::ByNameAndTagKey::null(workflow_name.to_string(), workflow_id);
+			tx.clear(&self.subspace.pack(&by_name_and_tag_key));
+		}
+
+		// Clear the pending deadline wake condition, if any
+		if let Some(raw) = wake_deadline_entry {
+			let deadline_ts = wake_deadline_key.deserialize(&raw)?;
+
+			let wake_condition_key = keys::wake::WorkflowWakeConditionKey::new(
+				workflow_name.to_string(),
+				workflow_id,
+				keys::wake::WakeCondition::Deadline { deadline_ts },
+			);
+
+			tx.clear(&self.subspace.pack(&wake_condition_key));
+		}
+
+		// Clear sub workflow secondary idx
+		if let Some(entry) = wake_sub_workflow_entry {
+			let sub_workflow_id = wake_sub_workflow_key.deserialize(&entry)?;
+
+			let sub_workflow_wake_key =
+				keys::wake::SubWorkflowWakeKey::new(sub_workflow_id, workflow_id);
+
+			tx.clear(&self.subspace.pack(&sub_workflow_wake_key));
+		}
+
+		// Clear signals secondary index
+		let wake_signals_subspace = self
+			.subspace
+			.subspace(&keys::workflow::WakeSignalKey::subspace(workflow_id));
+		tx.clear_subspace_range(&wake_signals_subspace);
+
+		// Clear "has wake condition"
+		let has_wake_condition_key = keys::workflow::HasWakeConditionKey::new(workflow_id);
+		tx.clear(&self.subspace.pack(&has_wake_condition_key));
+
+		// Clear lease
+		let lease_key = keys::workflow::LeaseKey::new(workflow_id);
+		tx.clear(&self.subspace.pack(&lease_key));
+		let worker_instance_id_key = keys::workflow::WorkerInstanceIdKey::new(workflow_id);
+		tx.clear(&self.subspace.pack(&worker_instance_id_key));
+
+		self.write_retention_idx(workflow_id, workflow_name, termination.ts, tx)?;
+
+		let termination_key = keys::workflow::TerminationKey::new(workflow_id);
+		tx.set(
+			&self.subspace.pack(&termination_key),
+			&termination_key.serialize(termination)?,
+		);
+
+		Ok(wrote_to_wake_idx)
+	}
+
+	/// Indexes a batch of workflows that were written before the retention index and sub workflow references
+	/// existed: writes the retention index entry of stopped workflows and the `ReferencedByKey` of every sub
+	/// workflow in the active history of running workflows. Returns true once all workflows are backfilled.
+	async fn backfill_retention_idx(&self, tx: &universaldb::Transaction) -> Result<bool> {
+		let tx = tx.with_subspace(self.subspace.clone());
+
+		let cursor_key = keys::workflow::RetentionBackfillCursorKey::new();
+		let after_workflow_id = match tx.read_opt(&cursor_key, Serializable).await? {
+			Some(keys::workflow::RetentionBackfillCursor::Complete) => return Ok(true),
+			Some(keys::workflow::RetentionBackfillCursor::After(workflow_id)) => Some(workflow_id),
+			None => None,
+		};
+
+		let (data_start, data_end) = self
+			.subspace
+			.subspace(&keys::workflow::DataSubspaceKey::new())
+			.range();
+		let start = if let Some(after_workflow_id) = after_workflow_id {
+			self.subspace
+				.subspace(&keys::workflow::DataSubspaceKey::for_workflow(
+					after_workflow_id,
+				))
+				.range()
+				.1
+		} else {
+			data_start
+		};
+
+		let mut stream = tx.get_ranges_keyvalues(
+			universaldb::RangeOption {
+				mode: StreamingMode::Iterator,
+				..(start, data_end).into()
+			},
+			// Workflows that stop or dispatch sub workflows concurrently write their own index entries
+			Snapshot,
+		);
+
+		let mut workflows = Vec::<(Id, BackfillWorkflow)>::new();
+		let mut reached_end = true;
+
+		while let Some(entry) = stream.try_next().await? {
+			let workflow_id = *self.subspace.unpack::<debug::JustId>(entry.key())?;
+
+			if workflows.last().map(|(id, _)| *id) != Some(workflow_id) {
+				if workflows.len() >= GC_BACKFILL_BATCH_SIZE {
+					reached_end = false;
+					break;
+				}
+
+				workflows.push((workflow_id, BackfillWorkflow::default()));
+			}
+
+			let (_, workflow) = workflows
+				.last_mut()
+				.context("backfill workflow should exist")?;
+
+			if let Ok(key) = self.subspace.unpack::<keys::workflow::NameKey>(entry.key()) {
+				workflow.name = Some(key.deserialize(entry.value())?);
+			} else if let Ok(key) = self
+				.subspace
+				.unpack::<keys::workflow::TerminationKey>(entry.key())
+			{
+				workflow.termination_ts = Some(key.deserialize(entry.value())?.ts);
+			} else if self
+				.subspace
+				.unpack::<keys::workflow::OutputChunkKey>(entry.key())
+				.is_ok()
+			{
+				workflow.has_output = true;
+			} else if self
+				.subspace
+				.unpack::<keys::workflow::SilenceTsKey>(entry.key())
+				.is_ok()
+			{
+				workflow.is_silenced = true;
+			} else if self
+				.subspace
+				.unpack::<keys::workflow::CompleteTsKey>(entry.key())
+				.is_ok()
+			{
+				workflow.is_indexed = true;
+			} else if let Ok(key) = self
+				.subspace
+				.unpack::<keys::history::SubWorkflowIdKey>(entry.key())
+			{
+				if !key.forgotten {
+					workflow
+						.sub_workflow_ids
+						.push(key.deserialize(entry.value())?);
+				}
+			}
+		}
+
+		let now = rivet_util::timestamp::now();
+
+		for (workflow_id, workflow) in &workflows {
+			let Some(workflow_name) = &workflow.name else {
+				continue;
+			};
+
+			if workflow.termination_ts.is_some() || workflow.has_output {
+				if !workflow.is_indexed {
+					// Completion time was not recorded for completed workflows, start their retention now
+					let ts = workflow.termination_ts.unwrap_or(now);
+					self.write_retention_idx(*workflow_id, workflow_name, ts, &tx)?;
+				}
+			} else if !workflow.is_silenced {
+				for sub_workflow_id in &workflow.sub_workflow_ids {
+					// Sub workflow may have already been deleted
+					if !tx
+						.exists(&keys::workflow::NameKey::new(*sub_workflow_id), Snapshot)
+						.await?
+					{
+						continue;
+					}
+
+					tx.write(
+						&keys::workflow::ReferencedByKey::new(*sub_workflow_id, *workflow_id),
+						(),
+					)?;
+				}
+			}
+		}
+
+		if reached_end {
+			tx.write(
+				&cursor_key,
+				keys::workflow::RetentionBackfillCursor::Complete,
+			)?;
+		} else if let Some((workflow_id, _)) = workflows.last() {
+			tx.write(
+				&cursor_key,
+				keys::workflow::RetentionBackfillCursor::After(*workflow_id),
+			)?;
+		}
+
+		Ok(reached_end)
+	}
+
+	/// Writes to the retention GC lock of the given workflow name. Returns false if another worker holds the
+	/// lock.
+	async fn acquire_gc_lock(&self, workflow_name: &str, refresh: bool) -> WorkflowResult<bool> {
+		self.pools
+			.udb()
+			.map_err(WorkflowError::PoolsGeneric)?
+			.run(|tx| async move {
+				let tx = tx.with_subspace(self.subspace.clone());
+				let lock_key = keys::workflow::RetentionGcLockKey::new(workflow_name.to_string());
+
+				// Read existing lock
+				let lock_expired =
+					if let Some(lock_ts) = tx.read_opt(&lock_key, Serializable).await? {
+						lock_ts < rivet_util::timestamp::now() - GC_LOCK_TIMEOUT_MS
+					} else {
+						true
+					};
+
+				if lock_expired || refresh {
+					// UDB transactions guarantee that if multiple workers are running this query at the same
+					// time only one will succeed which means only one will have the lock.
+					tx.write(&lock_key, rivet_util::timestamp::now())?;
+				}
+
+				Ok(lock_expired || refresh)
+			})
+			.custom_instrument(tracing::info_span!("acquire_gc_lock_tx"))
+			.await
+			.map_err(WorkflowError::Udb)
+	}
+
+	/// Deletes stopped workflows of the given name in batches until none older than `stopped_before_ts` are
+	/// left. Workflows are only deleted once the retention index backfill has completed. Expects the retention
+	/// GC lock of the workflow name to be held.
+	async fn gc_stopped_workflows_locked(
+		&self,
+		workflow_name: &str,
+		stopped_before_ts: i64,
+	) -> WorkflowResult<(usize, usize)> {
+		let udb = self.pools.udb().map_err(WorkflowError::PoolsGeneric)?;
+
+		// Workflows that are kept are moved to the current time in the index. This has to be past
+		// `stopped_before_ts` for the loop below to finish.
+		let stopped_before_ts = stopped_before_ts.min(rivet_util::timestamp::now());
+
+		loop {
+			let backfill_complete = udb
+				.run(|tx| async move { self.backfill_retention_idx(&tx).await })
+				.custom_instrument(tracing::info_span!("backfill_retention_idx_tx"))
+				.await
+				.map_err(WorkflowError::Udb)?;
+
+			if backfill_complete {
+				break;
+			}
+
+			self.acquire_gc_lock(workflow_name, true).await?;
+		}
+
+		let mut deleted_workflow_count = 0;
+		let mut deleted_key_count = 0;
+
+		loop {
+			let index_keys = udb
+				.run(|tx| async move {
+					let index_subspace_start = self
+						.subspace
+						.subspace(
+							&keys::workflow::ByNameAndCompleteTsKey::subspace_without_ts(
+								workflow_name.to_string(),
+							),
+						)
+						.bytes()
+						.iter()
+						.map(|x| *x)
+						// https://github.com/apple/foundationdb/blob/main/design/tuple.md
+						.chain(std::iter::once(0x00))
+						.collect::<Vec<_>>();
+					let index_subspace_end = self
+						.subspace
+						.subspace(&keys::workflow::ByNameAndCompleteTsKey::subspace(
+							workflow_name.to_string(),
+							stopped_before_ts,
+						))
+						.bytes()
+						.to_vec();
+
+					tx.get_ranges_keyvalues(
+						universaldb::RangeOption {
+							mode: StreamingMode::WantAll,
+							limit: Some(GC_BATCH_SIZE),
+							..(index_subspace_start, index_subspace_end).into()
+						},
+						// Not Serializable because we don't want this to conflict with workflows stopping
+						Snapshot,
+					)
+					.map(|res| {
+						self.subspace
+							.unpack::<keys::workflow::ByNameAndCompleteTsKey>(res?.key())
+							.map_err(anyhow::Error::from)
+					})
+					.try_collect::<Vec<_>>()
+					.await
+				})
+				.custom_instrument(tracing::info_span!("list_stopped_workflows_tx"))
+				.await
+				.map_err(WorkflowError::Udb)?;
+
+			let mut batch_workflow_count = 0;
+			let mut batch_key_count = 0;
+
+			// Each workflow is deleted in its own transaction to stay within transaction size limits
+			for index_key in &index_keys {
+				let deleted_keys = udb
+					.run(|tx| async move {
+						self.gc_stopped_workflow_inner(workflow_name, index_key, &tx)
+							.await
+					})
+					.custom_instrument(tracing::info_span!(
+						"gc_stopped_workflow_tx",
+						workflow_id=%index_key.workflow_id
+					))
+					.await
+					.map_err(WorkflowError::Udb)?;
+
+				if let Some(deleted_keys) = deleted_keys {
+					batch_workflow_count += 1;
+					batch_key_count += deleted_keys;
+				}
+			}
+
+			if batch_workflow_count != 0 {
+				let attributes = [KeyValue::new("workflow_name", workflow_name.to_string())];
+
+				metrics::WORKFLOW_GC_DELETED.add(batch_workflow_count as u64, &attributes);
+				metrics::WORKFLOW_GC_KEYS_DELETED.add(batch_key_count as u64, &attributes);
+			}
+
+			deleted_workflow_count += batch_workflow_count;
+			deleted_key_count += batch_key_count;
+
+			// Every index key in the batch was either deleted or moved past `stopped_before_ts`
+			if index_keys.len() < GC_BATCH_SIZE {
+				break;
+			}
+
+			self.acquire_gc_lock(workflow_name, true).await?;
+		}
+
+		Ok((deleted_workflow_count, deleted_key_count))
+	}
+
+	/// Deletes all data of a stopped workflow. Returns the amount of keys deleted, or `None` if the workflow
+	/// was not deleted.
+	async fn gc_stopped_workflow_inner(
+		&self,
+		workflow_name: &str,
+		index_key: &keys::workflow::ByNameAndCompleteTsKey,
+		tx: &universaldb::Transaction,
+	) -> Result<Option<usize>> {
+		let tx = tx.with_subspace(self.subspace.clone());
+		let workflow_id = index_key.workflow_id;
+
+		// Already deleted by another worker
+		if !tx.exists(index_key, Serializable).await? {
+			return Ok(None);
+		}
+
+		let referenced_by_subspace = self
+			.subspace
+			.subspace(&keys::workflow::ReferencedByKey::subspace(workflow_id));
+
+		let (referencing_workflow_ids, is_silenced, is_terminated, has_output) = tokio::try_join!(
+			tx.get_ranges_keyvalues(
+				universaldb::RangeOption {
+					mode: StreamingMode::WantAll,
+					..(&referenced_by_subspace).into()
+				},
+				Serializable,
+			)
+			.map(|res| {
+				self.subspace
+					.unpack::<keys::workflow::ReferencedByKey>(res?.key())
+					.map(|key| key.referencing_workflow_id)
+					.map_err(anyhow::Error::from)
+			})
+			.try_collect::<Vec<_>>(),
+			tx.exists(
+				&keys::workflow::SilenceTsKey::new(workflow_id),
+				Serializable
+			),
+			tx.exists(
+				&keys::workflow::TerminationKey::new(workflow_id),
+				Serializable
+			),
+			self.has_output(workflow_id, &tx),
+		)?;
+
+		// Stale index entry
+		if !is_terminated && !has_output {
+			tx.delete(index_key);
+			return Ok(None);
+		}
+
+		// Sub workflows are kept while any workflow whose history references them can still run, otherwise
+		// that workflow would fail to replay
+		for referencing_workflow_id in referencing_workflow_ids {
+			if self.is_running(referencing_workflow_id, &tx).await? {
+				// Check again after another retention period
+				tx.delete(index_key);
+				self.write_retention_idx(
+					workflow_id,
+					workflow_name,
+					rivet_util::timestamp::now(),
+					&tx,
+				)?;
+
+				return Ok(None);
+			}
+		}
+
+		let mut deleted_keys = 0;
+
+		// Clear all workflow data (including history), collecting the signals it received
+		let data_subspace = self
+			.subspace
+			.subspace(&keys::workflow::DataSubspaceKey::for_workflow(workflow_id));
+		let mut received_signal_ids = Vec::new();
+
+		let mut stream = tx.get_ranges_keyvalues(
+			universaldb::RangeOption {
+				mode: StreamingMode::WantAll,
+				..(&data_subspace).into()
+			},
+			Snapshot,
+		);
+
+		while let Some(entry) = stream.try_next().await? {
+			if let Ok(signal_id_key) = self
+				.subspace
+				.unpack::<keys::history::SignalIdKey>(entry.key())
+			{
+				received_signal_ids.push(signal_id_key.deserialize(entry.value())?);
+			}
 
-		// Write ray id
-		let ray_id_key = keys::signal::RayIdKey::new(signal_id);
-		tx.set(
-			&self.subspace.pack(&ray_id_key),
-			&ray_id_key.serialize(ray_id)?,
-		);
+			deleted_keys += 1;
+		}
 
-		// Write workflow id
-		let workflow_id_key = keys::signal::WorkflowIdKey::new(signal_id);
-		tx.set(
-			&self.subspace.pack(&workflow_id_key),
-			&workflow_id_key.serialize(workflow_id)?,
-		);
+		tx.clear_subspace_range(&data_subspace);
 
-		let wake_signal_key =
-			keys::workflow::WakeSignalKey::new(workflow_id, signal_name.to_string());
+		// Signal send events also have a signal id, those signals belong to the receiving workflow
+		for signal_id in received_signal_ids {
+			let signal_workflow_id = tx
+				.read_opt(&keys::signal::WorkflowIdKey::new(signal_id), Snapshot)
+				.await?;
 
-		// If the workflow currently has a wake signal key for this signal, wake it
-		if tx
-			.get(&self.subspace.pack(&wake_signal_key), Serializable)
-			.await?
-			.is_some()
-		{
+			if signal_workflow_id == Some(workflow_id) {
+				deleted_keys += self.clear_signal_data(signal_id, &tx).await?;
+			}
+		}
+
+		// Clear signals that were never received
+		let pending_signal_subspace =
+			self.subspace
+				.subspace(&keys::workflow::PendingSignalKey::subspace_without_name(
+					workflow_id,
+				));
+		let pending_signal_keys = tx
+			.get_ranges_keyvalues(
+				universaldb::RangeOption {
+					mode: StreamingMode::WantAll,
+					..(&pending_signal_subspace).into()
+				},
+				Serializable,
+			)
+			.map(|res| {
+				self.subspace
+					.unpack::<keys::workflow::PendingSignalKey>(res?.key())
+					.map_err(anyhow::Error::from)
+			})
+			.try_collect::<Vec<_>>()
+			.await?;
+
+		for key in pending_signal_keys {
 			let mut wake_condition_key = keys::wake::WorkflowWakeConditionKey::new(
-				workflow_name,
+				workflow_name.to_string(),
 				workflow_id,
-				keys::wake::WakeCondition::Signal { signal_id },
+				keys::wake::WakeCondition::Signal {
+					signal_id: key.signal_id,
+				},
 			);
-			wake_condition_key.ts = pending_signal_key.ts;
+			wake_condition_key.ts = key.ts;
+			tx.delete(&wake_condition_key);
 
-			// Add wake condition for workflow
-			tx.set(
-				&self.subspace.pack(&wake_condition_key),
-				&wake_condition_key.serialize(())?,
+			deleted_keys += 1 + self.clear_signal_data(key.signal_id, &tx).await?;
+
+			update_metric(
+				&tx,
+				Some(keys::metric::GaugeMetric::SignalPending(key.signal_name)),
+				None,
 			);
 		}
 
-		update_metric(
-			&tx.with_subspace(self.subspace.clone()),
-			None,
-			Some(keys::metric::GaugeMetric::SignalPending(
-				signal_name.to_string(),
-			)),
-		);
+		tx.clear_subspace_range(&pending_signal_subspace);
 
-		Ok(())
+		// Clear wake keys of workflows waiting on this one
+		let sub_workflow_wake_subspace = self
+			.subspace
+			.subspace(&keys::wake::SubWorkflowWakeKey::subspace(workflow_id));
+		tx.clear_subspace_range(&sub_workflow_wake_subspace);
+
+		tx.delete(index_key);
+		deleted_keys += 1;
+
+		// Silenced workflows are already excluded from metrics
+		if has_output && !is_silenced {
+			update_metric(
+				&tx,
+				Some(keys::metric::GaugeMetric::WorkflowComplete(
+					workflow_name.to_string(),
+				)),
+				None,
+			);
+		}
+
+		Ok(Some(deleted_keys))
+	}
+
+	/// Returns true if the workflow exists and has not stopped (completed, cancelled, terminated, or been
+	/// silenced).
+	async fn is_running(&self, workflow_id: Id, tx: &universaldb::Transaction) -> Result<bool> {
+		let tx = tx.with_subspace(self.subspace.clone());
+
+		let (exists, is_silenced, is_terminated, has_output) = tokio::try_join!(
+			tx.exists(&keys::workflow::NameKey::new(workflow_id), Serializable),
+			tx.exists(
+				&keys::workflow::SilenceTsKey::new(workflow_id),
+				Serializable
+			),
+			tx.exists(
+				&keys::workflow::TerminationKey::new(workflow_id),
+				Serializable
+			),
+			self.has_output(workflow_id, &tx),
+		)?;
+
+		Ok(exists && !is_silenced && !is_terminated && !has_output)
+	}
+
+	/// Returns true if the workflow was terminated. Running workflows can be forcibly terminated, in which case
+	/// the writes of the worker that was running it are discarded.
+	async fn is_terminated(&self, workflow_id: Id, tx: &universaldb::Transaction) -> Result<bool> {
+		tx.with_subspace(self.subspace.clone())
+			.exists(
+				&keys::workflow::TerminationKey::new(workflow_id),
+				Serializable,
+			)
+			.await
+	}
+
+	/// Returns true if the workflow has written an output.
+	async fn has_output(&self, workflow_id: Id, tx: &universaldb::Transaction) -> Result<bool> {
+		let output_subspace = self
+			.subspace
+			.subspace(&keys::workflow::OutputKey::new(workflow_id));
+
+		tx.get_ranges_keyvalues(
+			universaldb::RangeOption {
+				mode: StreamingMode::WantAll,
+				limit: Some(1),
+				..(&output_subspace).into()
+			},
+			Serializable,
+		)
+		.try_next()
+		.await
+		.map(|x| x.is_some())
+		.map_err(Into::into)
+	}
+
+	/// Clears all data of a signal. Returns the amount of keys cleared.
+	async fn clear_signal_data(
+		&self,
+		signal_id: Id,
+		tx: &universaldb::Transaction,
+	) -> Result<usize> {
+		let signal_data_subspace = self
+			.subspace
+			.subspace(&keys::signal::DataSubspaceKey::for_signal(signal_id));
+
+		let key_count = tx
+			.get_ranges_keyvalues(
+				universaldb::RangeOption {
+					mode: StreamingMode::WantAll,
+					..(&signal_data_subspace).into()
+				},
+				Snapshot,
+			)
+			.try_fold(0, |acc, _| std::future::ready(Ok(acc + 1)))
+			.await?;
+
+		tx.clear_subspace_range(&signal_data_subspace);
+
+		Ok(key_count)
 	}
 
 	async fn dispatch_workflow_inner(
@@ -237,6 +1328,7 @@ impl DatabaseKv {
 		workflow_name: &str,
 		tags: Option<&serde_json::Value>,
 		input: &serde_json::value::RawValue,
+		schema_version: usize,
 		unique: bool,
 		tx: &universaldb::Transaction,
 	) -> Result<Id> {
@@ -266,6 +1358,14 @@ impl DatabaseKv {
 
 		tx.write(&keys::workflow::RayIdKey::new(workflow_id), ray_id)?;
 
+		// Write trace context so that workflow runs can link to the dispatcher
+		if let Some(trace_context) = utils::trace::current_trace_context() {
+			tx.write(
+				&keys::workflow::TraceContextKey::new(workflow_id),
+				trace_context,
+			)?;
+		}
+
 		// Write tags
 		let tags = tags
 			.map(|x| {
@@ -318,6 +1418,14 @@ impl DatabaseKv {
 			tx.set(&self.subspace.pack(&chunk_key), &chunk);
 		}
 
+		// No schema version means version 0
+		if schema_version != 0 {
+			tx.write(
+				&keys::workflow::SchemaVersionKey::new(workflow_id),
+				schema_version,
+			)?;
+		}
+
 		// Write immediate wake condition
 		tx.write(
 			&keys::wake::WorkflowWakeConditionKey::new(
@@ -592,6 +1700,174 @@ impl Database for DatabaseKv {
 		Ok(())
 	}
 
+	#[tracing::instrument(skip_all, fields(%workflow_name))]
+	async fn gc_stopped_workflows(
+		&self,
+		workflow_name: &str,
+		stopped_before_ts: i64,
+	) -> WorkflowResult<Option<(usize, usize)>> {
+		// Attempt to be the only worker deleting workflows of this name
+		if !self.acquire_gc_lock(workflow_name, false).await? {
+			return Ok(None);
+		}
+
+		let res = self
+			.gc_stopped_workflows_locked(workflow_name, stopped_before_ts)
+			.await;
+
+		// Clear lock
+		self.pools
+			.udb()
+			.map_err(WorkflowError::PoolsGeneric)?
+			.run(|tx| async move {
+				let lock_key = keys::workflow::RetentionGcLockKey::new(workflow_name.to_string());
+				tx.clear(&self.subspace.pack(&lock_key));
+
+				Ok(())
+			})
+			.custom_instrument(tracing::info_span!("clear_gc_lock_tx"))
+			.await
+			.map_err(WorkflowError::Udb)?;
+
+		res.map(Some)
+	}
+
+	#[tracing::instrument(skip_all)]
+	async fn dispatch_due_schedules(&self, worker_instance_id: Id) -> WorkflowResult<usize> {
+		let dispatched = self
+			.pools
+			.udb()
+			.map_err(WorkflowError::PoolsGeneric)?
+			.run(|tx| async move {
+				let now = rivet_util::timestamp::now();
+
+				let index_subspace_start = self
+					.subspace
+					.subspace(&keys::schedule::ByNextTsKey::subspace_without_ts())
+					.bytes()
+					.iter()
+					.map(|x| *x)
+					// https://github.com/apple/foundationdb/blob/main/design/tuple.md
+					.chain(std::iter::once(0x00))
+					.collect::<Vec<_>>();
+				let index_subspace_end = self
+					.subspace
+					.subspace(&keys::schedule::ByNextTsKey::subspace(now))
+					.bytes()
+					.to_vec();
+
+				let due_keys = tx
+					.get_ranges_keyvalues(
+						universaldb::RangeOption {
+							mode: StreamingMode::WantAll,
+							limit: Some(SCHEDULE_BATCH_SIZE),
+							..(index_subspace_start, index_subspace_end).into()
+						},
+						// NOTE: Must be Serializable so that concurrent workers conflict and each tick is only
+						// dispatched once
+						Serializable,
+					)
+					.map(|res| {
+						self.subspace
+							.unpack::<keys::schedule::ByNextTsKey>(res?.key())
+							.map_err(anyhow::Error::from)
+					})
+					.try_collect::<Vec<_>>()
+					.await?;
+
+				let mut dispatched = Vec::new();
+
+				for key in due_keys {
+					let schedule_name = key.schedule_name.clone();
+					let workflow_name_key =
+						keys::schedule::WorkflowNameKey::new(schedule_name.clone());
+					let cron_key = keys::schedule::CronKey::new(schedule_name.clone());
+					let tags_key = keys::schedule::TagsKey::new(schedule_name.clone());
+					let schema_version_key =
+						keys::schedule::SchemaVersionKey::new(schedule_name.clone());
+					let input_key = keys::schedule::InputKey::new(schedule_name.clone());
+					let input_subspace = self.subspace.subspace(&input_key);
+
+					let (workflow_name, cron, tags, schema_version, input_chunks) = {
+						let tx = tx.with_subspace(self.subspace.clone());
+
+						tokio::try_join!(
+							tx.read(&workflow_name_key, Serializable),
+							tx.read(&cron_key, Serializable),
+							tx.read_opt(&tags_key, Serializable),
+							tx.read_opt(&schema_version_key, Serializable),
+							tx.get_ranges_keyvalues(
+								universaldb::RangeOption {
+									mode: StreamingMode::WantAll,
+									..(&input_subspace).into()
+								},
+								Serializable,
+							)
+							.try_collect::<Vec<_>>(),
+						)?
+					};
+
+					let input = input_key.combine(input_chunks)?;
+
+					let workflow_id = self
+						.dispatch_workflow_inner(
+							Id::new_v1(worker_instance_id.label()),
+							Id::new_v1(worker_instance_id.label()),
+							&workflow_name,
+							tags.as_ref(),
+							&input,
+							schema_version.unwrap_or_default(),
+							false,
+							&tx,
+						)
+						.await?;
+
+					// Ticks missed while no worker was running are skipped
+					let next_ts = crate::schedule::next_tick_ts(&cron, now)?;
+
+					let tx = tx.with_subspace(self.subspace.clone());
+
+					tx.delete(&key);
+					tx.write(
+						&keys::schedule::ByNextTsKey::new(next_ts, schedule_name.clone()),
+						(),
+					)?;
+					tx.write(
+						&keys::schedule::NextTsKey::new(schedule_name.clone()),
+						next_ts,
+					)?;
+					tx.write(
+						&keys::schedule::LastWorkflowIdKey::new(schedule_name.clone()),
+						workflow_id,
+					)?;
+					tx.write(
+						&keys::schedule::LastDispatchTsKey::new(schedule_name.clone()),
+						now,
+					)?;
+
+					dispatched.push((schedule_name, workflow_name, workflow_id));
+				}
+
+				Ok(dispatched)
+			})
+			.custom_instrument(tracing::info_span!("dispatch_due_schedules_tx"))
+			.await
+			.map_err(WorkflowError::Udb)?;
+
+		for (schedule_name, workflow_name, workflow_id) in &dispatched {
+			tracing::debug!(%schedule_name, %workflow_name, ?workflow_id, "dispatched scheduled workflow");
+
+			metrics::SCHEDULE_DISPATCHED
+				.add(1, &[KeyValue::new("workflow_name", workflow_name.clone())]);
+		}
+
+		if !dispatched.is_empty() {
+			self.wake_worker();
+		}
+
+		Ok(dispatched.len())
+	}
+
 	#[tracing::instrument(skip_all)]
 	async fn publish_metrics(&self, _worker_instance_id: Id) -> WorkflowResult<()> {
 		// Attempt to be the only worker publishing metrics by writing to the lock key
@@ -793,6 +2069,7 @@ impl Database for DatabaseKv {
 		workflow_name: &str,
 		tags: Option<&serde_json::Value>,
 		input: &serde_json::value::RawValue,
+		schema_version: usize,
 		unique: bool,
 	) -> WorkflowResult<Id> {
 		let workflow_id = self
@@ -806,6 +2083,7 @@ impl Database for DatabaseKv {
 					workflow_name,
 					tags,
 					input,
+					schema_version,
 					unique,
 					&tx,
 				)
@@ -840,6 +2118,8 @@ impl Database for DatabaseKv {
 								let output_subspace = self.subspace.subspace(&output_key);
 								let has_wake_condition_key =
 									keys::workflow::HasWakeConditionKey::new(workflow_id);
+								let termination_key =
+									keys::workflow::TerminationKey::new(workflow_id);
 
 								// Read input and output
 								let (
@@ -847,6 +2127,7 @@ impl Database for DatabaseKv {
 									state_chunks,
 									output_chunks,
 									has_wake_condition_entry,
+									termination_entry,
 								) = tokio::try_join!(
 									tx.get_ranges_keyvalues(
 										universaldb::RangeOption {
@@ -876,6 +2157,7 @@ impl Database for DatabaseKv {
 										&self.subspace.pack(&has_wake_condition_key),
 										Serializable
 									),
+									tx.get(&self.subspace.pack(&termination_key), Serializable),
 								)?;
 
 								if input_chunks.is_empty() {
@@ -895,12 +2177,17 @@ impl Database for DatabaseKv {
 										Some(output_key.combine(output_chunks)?)
 									};
 
+									let termination = termination_entry
+										.map(|raw| termination_key.deserialize(&raw))
+										.transpose()?;
+
 									Ok(Some(WorkflowData {
 										workflow_id,
 										input,
 										state,
 										output,
 										has_wake_condition: has_wake_condition_entry.is_some(),
+										termination,
 									}))
 								}
 							}
@@ -950,12 +2237,13 @@ impl Database for DatabaseKv {
 	async fn pull_workflows(
 		&self,
 		worker_instance_id: Id,
-		filter: &[&str],
+		filter: &[PullFilter<'_>],
+		max: Option<usize>,
 	) -> WorkflowResult<Vec<PulledWorkflowData>> {
 		let start_instant = Instant::now();
 		let owned_filter = filter
 			.into_iter()
-			.map(|x| x.to_string())
+			.map(|x| (x.workflow_name.to_string(), x.limit))
 			.collect::<Vec<_>>();
 
 		let leased_workflows = self
@@ -972,8 +2260,8 @@ impl Database for DatabaseKv {
 					let pull_before = now + i64::try_from(self.worker_poll_interval().as_millis())?;
 
 					// Pull all available wake conditions from all registered wf names
-					let entries = futures_util::stream::iter(owned_filter)
-						.map(|wf_name| {
+					let entries = futures_util::stream::iter(owned_filter.clone())
+						.map(|(wf_name, _)| {
 							let wake_subspace_start = self
 								.subspace
 								.subspace(
@@ -1045,6 +2333,57 @@ impl Database for DatabaseKv {
 						));
 					}
 
+					// Filter out workflows that are already leased before applying limits so they don't take up
+					// the budget. Snapshot because only the workflows that end up being pulled (checked again
+					// below) need to conflict.
+					let mut dedup_workflows = futures_util::stream::iter(dedup_workflows)
+						.map(|(workflow_id, workflow_name, wake_deadline_ts)| {
+							let tx = tx.clone();
+							async move {
+								let lease_key = keys::workflow::LeaseKey::new(workflow_id);
+								let is_leased = tx
+									.get(&self.subspace.pack(&lease_key), Snapshot)
+									.await?
+									.is_some();
+
+								anyhow::Ok((!is_leased).then_some((
+									workflow_id,
+									workflow_name,
+									wake_deadline_ts,
+								)))
+							}
+						})
+						// Keep order for limits
+						.buffered(1024)
+						.try_filter_map(|x| std::future::ready(Ok(x)))
+						.try_collect::<Vec<_>>()
+						.instrument(tracing::trace_span!("filter_leased_workflows"))
+						.await?;
+
+					// Apply limits. Workflows are ordered by filter entry and then by wake ts, so earlier
+					// filter entries and older wake conditions take precedence
+					let mut pulled_by_name = HashMap::<String, usize>::new();
+					let mut pulled = 0;
+					dedup_workflows.retain(|(_, workflow_name, _)| {
+						if max.map(|max| pulled >= max).unwrap_or_default() {
+							return false;
+						}
+
+						let limit = owned_filter
+							.iter()
+							.find(|(wf_name, _)| wf_name == workflow_name)
+							.and_then(|(_, limit)| *limit);
+						let count = pulled_by_name.entry(workflow_name.clone()).or_default();
+						if limit.map(|limit| *count >= limit).unwrap_or_default() {
+							return false;
+						}
+
+						*count += 1;
+						pulled += 1;
+
+						true
+					});
+
 					// Check leases
 					let leased_workflows = futures_util::stream::iter(dedup_workflows)
 						.map(|(workflow_id, workflow_name, wake_deadline_ts)| {
@@ -1200,20 +2539,27 @@ impl Database for DatabaseKv {
 								let ray_id_key = keys::workflow::RayIdKey::new(workflow_id);
 								let input_key = keys::workflow::InputKey::new(workflow_id);
 								let state_key = keys::workflow::StateKey::new(workflow_id);
+								let schema_version_key =
+									keys::workflow::SchemaVersionKey::new(workflow_id);
+								let trace_context_key =
+									keys::workflow::TraceContextKey::new(workflow_id);
 								let input_subspace = self.subspace.subspace(&input_key);
 								let state_subspace = self.subspace.subspace(&state_key);
-								let active_history_subspace = self.subspace.subspace(
-									&keys::history::HistorySubspaceKey::new(
+								let pending_cancel_subspace = self.subspace.subspace(
+									&keys::workflow::PendingSignalKey::subspace(
 										workflow_id,
-										keys::history::HistorySubspaceVariant::Active,
+										Cancel::NAME.to_string(),
 									),
 								);
 
 								let (
 									create_ts_entry,
 									ray_id_entry,
+									schema_version_entry,
+									trace_context_entry,
 									input_chunks,
 									state_chunks,
+									cancel_requested,
 									events,
 								) = tokio::try_join!(
 									async {
@@ -1223,6 +2569,20 @@ impl Database for DatabaseKv {
 									async {
 										tx.get(&self.subspace.pack(&ray_id_key), Serializable).await
 									},
+									async {
+										tx.get(
+											&self.subspace.pack(&schema_version_key),
+											Serializable,
+										)
+										.await
+									},
+									async {
+										tx.get(
+											&self.subspace.pack(&trace_context_key),
+											Serializable,
+										)
+										.await
+									},
 									async {
 										tx.get_ranges_keyvalues(
 											universaldb::RangeOption {
@@ -1246,169 +2606,34 @@ impl Database for DatabaseKv {
 										.await
 									},
 									async {
-										let mut events_by_location: HashMap<Location, Vec<Event>> =
-											HashMap::new();
-										let mut current_event =
-											WorkflowHistoryEventBuilder::new(Location::empty());
-
-										let mut stream = tx.get_ranges_keyvalues(
+										tx.get_ranges_keyvalues(
 											universaldb::RangeOption {
 												mode: StreamingMode::WantAll,
-												..(&active_history_subspace).into()
+												limit: Some(1),
+												..(&pending_cancel_subspace).into()
 											},
-											Serializable,
-										);
-
-										loop {
-											let Some(entry) = stream.try_next().await? else {
-												break;
-											};
-
-											// Parse only the wf id and location of the current key
-											let partial_key = self
-												.subspace
-												.unpack::<keys::history::PartialEventKey>(
-												entry.key(),
-											)?;
-
-											if current_event.location != partial_key.location {
-												if current_event.location.is_empty() {
-													current_event =
-														WorkflowHistoryEventBuilder::new(
-															partial_key.location,
-														);
-												} else {
-													// Insert current event builder to into wf events and
-													// reset state
-													let previous_event = std::mem::replace(
-														&mut current_event,
-														WorkflowHistoryEventBuilder::new(
-															partial_key.location,
-														),
-													);
-													events_by_location
-														.entry(previous_event.location.root())
-														.or_default()
-														.push(Event::try_from(previous_event)?);
-												}
-											}
-
-											// Parse current key as any event key
-											if let Ok(key) =
-												self.subspace.unpack::<keys::history::EventTypeKey>(
-													entry.key(),
-												) {
-												let event_type = key.deserialize(entry.value())?;
-
-												current_event.event_type = Some(event_type);
-											} else if let Ok(key) =
-												self.subspace.unpack::<keys::history::VersionKey>(
-													entry.key(),
-												) {
-												let version = key.deserialize(entry.value())?;
-
-												current_event.version = Some(version);
-											} else if let Ok(key) =
-												self.subspace.unpack::<keys::history::CreateTsKey>(
-													entry.key(),
-												) {
-												let create_ts = key.deserialize(entry.value())?;
-
-												current_event.create_ts = Some(create_ts);
-											} else if let Ok(key) =
-												self.subspace
-													.unpack::<keys::history::NameKey>(entry.key())
-											{
-												let name = key.deserialize(entry.value())?;
-
-												current_event.name = Some(name);
-											} else if let Ok(key) =
-												self.subspace.unpack::<keys::history::SignalIdKey>(
-													entry.key(),
-												) {
-												let signal_id = key.deserialize(entry.value())?;
-
-												current_event.signal_id = Some(signal_id);
-											} else if let Ok(key) = self
-												.subspace
-												.unpack::<keys::history::SubWorkflowIdKey>(
-												entry.key(),
-											) {
-												let sub_workflow_id =
-													key.deserialize(entry.value())?;
-
-												current_event.sub_workflow_id =
-													Some(sub_workflow_id);
-											} else if let Ok(_key) = self
-												.subspace
-												.unpack::<keys::history::InputChunkKey>(
-												entry.key(),
-											) {
-												current_event.input_chunks.push(entry);
-											} else if let Ok(_key) = self
-												.subspace
-												.unpack::<keys::history::OutputChunkKey>(
-												entry.key(),
-											) {
-												current_event.output_chunks.push(entry);
-											} else if let Ok(_key) =
-												self.subspace
-													.unpack::<keys::history::ErrorKey>(entry.key())
-											{
-												current_event.error_count += 1;
-											} else if let Ok(key) =
-												self.subspace.unpack::<keys::history::IterationKey>(
-													entry.key(),
-												) {
-												let iteration = key.deserialize(entry.value())?;
-
-												current_event.iteration = Some(iteration);
-											} else if let Ok(key) = self
-												.subspace
-												.unpack::<keys::history::DeadlineTsKey>(
-												entry.key(),
-											) {
-												let deadline_ts = key.deserialize(entry.value())?;
-
-												current_event.deadline_ts = Some(deadline_ts);
-											} else if let Ok(key) = self
-												.subspace
-												.unpack::<keys::history::SleepStateKey>(
-												entry.key(),
-											) {
-												let sleep_state = key.deserialize(entry.value())?;
-
-												current_event.sleep_state = Some(sleep_state);
-											} else if let Ok(key) = self
-												.subspace
-												.unpack::<keys::history::InnerEventTypeKey>(
-												entry.key(),
-											) {
-												let inner_event_type =
-													key.deserialize(entry.value())?;
-
-												current_event.inner_event_type =
-													Some(inner_event_type);
-											}
-
-											// We ignore keys we don't need (like tags)
-										}
-										// Insert final event
-										if !current_event.location.is_empty() {
-											events_by_location
-												.entry(current_event.location.root())
-												.or_default()
-												.push(Event::try_from(current_event)?);
-										}
-
-										Ok(events_by_location)
-									}
+											// Not Serializable because this is only a hint, the cancel
+											// signal is pulled again when it is received
+											Snapshot,
+										)
+										.try_next()
+										.await
+										.map(|x| x.is_some())
+									},
+									self.read_active_history(workflow_id, Serializable, &tx),
 								)?;
 
 								let create_ts = create_ts_key
 									.deserialize(&create_ts_entry.context("key should exist")?)?;
 								let ray_id = ray_id_key
 									.deserialize(&ray_id_entry.context("key should exist")?)?;
+								let schema_version = schema_version_entry
+									.map(|raw| schema_version_key.deserialize(&raw))
+									.transpose()?
+									.unwrap_or_default();
+								let trace_context = trace_context_entry
+									.map(|raw| trace_context_key.deserialize(&raw))
+									.transpose()?;
 								let input = input_key.combine(input_chunks)?;
 								let state = if state_chunks.is_empty() {
 									serde_json::value::RawValue::NULL.to_owned()
@@ -1423,7 +2648,10 @@ impl Database for DatabaseKv {
 									ray_id,
 									input,
 									state,
+									schema_version,
+									trace_context,
 									wake_deadline_ts,
+									cancel_requested,
 									events,
 								})
 							}
@@ -1488,6 +2716,14 @@ impl Database for DatabaseKv {
 			.map_err(WorkflowError::PoolsGeneric)?
 			.run(|tx| {
 				async move {
+					if self.is_terminated(workflow_id, &tx).await? {
+						tracing::warn!(
+							?workflow_id,
+							"workflow was terminated while running, discarding output"
+						);
+						return Ok(false);
+					}
+
 					let sub_workflow_wake_subspace = self
 						.subspace
 						.subspace(&keys::wake::SubWorkflowWakeKey::subspace(workflow_id));
@@ -1614,6 +2850,13 @@ impl Database for DatabaseKv {
 						keys::workflow::WorkerInstanceIdKey::new(workflow_id);
 					tx.clear(&self.subspace.pack(&worker_instance_id_key));
 
+					self.write_retention_idx(
+						workflow_id,
+						workflow_name,
+						rivet_util::timestamp::now(),
+						&tx,
+					)?;
+
 					update_metric(
 						&tx.with_subspace(self.subspace.clone()),
 						Some(keys::metric::GaugeMetric::WorkflowActive(
@@ -1663,6 +2906,14 @@ impl Database for DatabaseKv {
 			.map_err(WorkflowError::PoolsGeneric)?
 			.run(|tx| {
 				async move {
+					if self.is_terminated(workflow_id, &tx).await? {
+						tracing::warn!(
+							?workflow_id,
+							"workflow was terminated while running, discarding commit"
+						);
+						return Ok(());
+					}
+
 					let wake_deadline_key = keys::workflow::WakeDeadlineKey::new(workflow_id);
 
 					let wake_deadline_entry = tx
@@ -1749,6 +3000,44 @@ impl Database for DatabaseKv {
 						tx.clear(&self.subspace.pack(&has_wake_condition_key));
 					}
 
+					// Wake immediately if a cancel signal was published while this workflow was running.
+					// `cancel_workflow` does not wake running workflows and this workflow might not be
+					// listening for the cancel signal. Dead workflows are not woken.
+					if has_wake_condition && !wake_immediate {
+						let pending_cancel_subspace =
+							self.subspace
+								.subspace(&keys::workflow::PendingSignalKey::subspace(
+									workflow_id,
+									Cancel::NAME.to_string(),
+								));
+
+						let cancel_pending = tx
+							.get_ranges_keyvalues(
+								universaldb::RangeOption {
+									mode: StreamingMode::WantAll,
+									limit: Some(1),
+									..(&pending_cancel_subspace).into()
+								},
+								// NOTE: Must be Serializable to conflict with `cancel_workflow`
+								Serializable,
+							)
+							.try_next()
+							.await?
+							.is_some();
+
+						if cancel_pending {
+							let wake_condition_key = keys::wake::WorkflowWakeConditionKey::new(
+								workflow_name.to_string(),
+								workflow_id,
+								keys::wake::WakeCondition::Immediate,
+							);
+							tx.set(
+								&self.subspace.pack(&wake_condition_key),
+								&wake_condition_key.serialize(())?,
+							);
+						}
+					}
+
 					// Write error
 					let error_key = keys::workflow::ErrorKey::new(workflow_id);
 					tx.set(
@@ -1804,11 +3093,58 @@ impl Database for DatabaseKv {
 		// - the worker is already designed to pull wake conditions frequently
 		self.wake_worker();
 
-		let dt = start_instant.elapsed().as_secs_f64();
-		metrics::COMMIT_WORKFLOW_DURATION.record(
-			dt,
-			&[KeyValue::new("workflow_name", workflow_name.to_string())],
-		);
+		let dt = start_instant.elapsed().as_secs_f64();
+		metrics::COMMIT_WORKFLOW_DURATION.record(
+			dt,
+			&[KeyValue::new("workflow_name", workflow_name.to_string())],
+		);
+
+		Ok(())
+	}
+
+	#[tracing::instrument(skip_all)]
+	async fn commit_cancelled_workflow(
+		&self,
+		workflow_id: Id,
+		workflow_name: &str,
+		reason: Option<&str>,
+	) -> WorkflowResult<()> {
+		let wrote_to_wake_idx = self
+			.pools
+			.udb()
+			.map_err(WorkflowError::PoolsGeneric)?
+			.run(|tx| async move {
+				let wrote_to_wake_idx = self
+					.terminate_workflow_inner(
+						workflow_id,
+						workflow_name,
+						Termination {
+							ts: rivet_util::timestamp::now(),
+							reason: reason.map(|x| x.to_string()),
+							forced: false,
+						},
+						&tx,
+					)
+					.await?;
+
+				update_metric(
+					&tx.with_subspace(self.subspace.clone()),
+					Some(keys::metric::GaugeMetric::WorkflowActive(
+						workflow_name.to_string(),
+					)),
+					None,
+				);
+
+				Ok(wrote_to_wake_idx)
+			})
+			.custom_instrument(tracing::info_span!("commit_cancelled_workflow_tx"))
+			.await
+			.map_err(WorkflowError::Udb)?;
+
+		// Wake worker again in case some other workflow was waiting for this one
+		if wrote_to_wake_idx {
+			self.wake_worker();
+		}
 
 		Ok(())
 	}
@@ -1929,18 +3265,24 @@ impl Database for DatabaseKv {
 							let body_key = keys::signal::BodyKey::new(signal_id);
 							let body_subspace = self.subspace.subspace(&body_key);
 
-							let chunks = tx
-								.get_ranges_keyvalues(
+							let trace_context_key = keys::signal::TraceContextKey::new(signal_id);
+
+							let (chunks, trace_context_entry) = tokio::try_join!(
+								tx.get_ranges_keyvalues(
 									universaldb::RangeOption {
 										mode: StreamingMode::WantAll,
 										..(&body_subspace).into()
 									},
 									Serializable,
 								)
-								.try_collect::<Vec<_>>()
-								.await?;
+								.try_collect::<Vec<_>>(),
+								tx.get(&self.subspace.pack(&trace_context_key), Serializable),
+							)?;
 
 							let body = body_key.combine(chunks)?;
+							let trace_context = trace_context_entry
+								.map(|raw| trace_context_key.deserialize(&raw))
+								.transpose()?;
 
 							// Insert history event
 							keys::history::insert::signal_event(
@@ -1960,6 +3302,7 @@ impl Database for DatabaseKv {
 								signal_name,
 								create_ts: ts,
 								body,
+								trace_context,
 							}))
 						}
 						// No signal found
@@ -2014,9 +3357,16 @@ impl Database for DatabaseKv {
 					let output_subspace = self.subspace.subspace(&output_key);
 					let has_wake_condition_key =
 						keys::workflow::HasWakeConditionKey::new(sub_workflow_id);
+					let termination_key = keys::workflow::TerminationKey::new(sub_workflow_id);
 
 					// Read input and output
-					let (input_chunks, state_chunks, output_chunks, has_wake_condition_entry) = tokio::try_join!(
+					let (
+						input_chunks,
+						state_chunks,
+						output_chunks,
+						has_wake_condition_entry,
+						termination_entry,
+					) = tokio::try_join!(
 						tx.get_ranges_keyvalues(
 							universaldb::RangeOption {
 								mode: StreamingMode::WantAll,
@@ -2042,6 +3392,7 @@ impl Database for DatabaseKv {
 						)
 						.try_collect::<Vec<_>>(),
 						tx.get(&self.subspace.pack(&has_wake_condition_key), Serializable),
+						tx.get(&self.subspace.pack(&termination_key), Serializable),
 					)?;
 
 					if input_chunks.is_empty() {
@@ -2055,7 +3406,14 @@ impl Database for DatabaseKv {
 							state_key.combine(state_chunks)?
 						};
 
-						let output = if output_chunks.is_empty() {
+						let termination = termination_entry
+							.map(|raw| termination_key.deserialize(&raw))
+							.transpose()?;
+
+						// Terminated workflows never complete
+						let output = if termination.is_some() {
+							None
+						} else if output_chunks.is_empty() {
 							// Write sub workflow wake index if the sub workflow is not complete yet. Normally
 							// this is done in `commit_workflow` but without this code there would be a race
 							// condition if the sub workflow completes between after this transaction and
@@ -2082,6 +3440,7 @@ impl Database for DatabaseKv {
 							state,
 							output,
 							has_wake_condition: has_wake_condition_entry.is_some(),
+							termination,
 						}))
 					}
 				}
@@ -2091,6 +3450,101 @@ impl Database for DatabaseKv {
 			.map_err(WorkflowError::Udb)
 	}
 
+	#[tracing::instrument(skip_all, fields(%workflow_id))]
+	async fn get_workflow_query_data(&self, workflow_id: Id) -> WorkflowResult<Option<QueryData>> {
+		self.pools
+			.udb()
+			.map_err(WorkflowError::PoolsGeneric)?
+			.run(|tx| {
+				async move {
+					let name_key = keys::workflow::NameKey::new(workflow_id);
+					let input_key = keys::workflow::InputKey::new(workflow_id);
+					let input_subspace = self.subspace.subspace(&input_key);
+					let state_key = keys::workflow::StateKey::new(workflow_id);
+					let state_subspace = self.subspace.subspace(&state_key);
+					let output_key = keys::workflow::OutputKey::new(workflow_id);
+					let output_subspace = self.subspace.subspace(&output_key);
+					let schema_version_key = keys::workflow::SchemaVersionKey::new(workflow_id);
+
+					// NOTE: Snapshot reads, queries are read-only and must not conflict with the workflow
+					let (
+						name_entry,
+						schema_version_entry,
+						input_chunks,
+						state_chunks,
+						output_chunks,
+						events_by_location,
+					) = tokio::try_join!(
+						tx.get(&self.subspace.pack(&name_key), Snapshot),
+						tx.get(&self.subspace.pack(&schema_version_key), Snapshot),
+						tx.get_ranges_keyvalues(
+							universaldb::RangeOption {
+								mode: StreamingMode::WantAll,
+								..(&input_subspace).into()
+							},
+							Snapshot,
+						)
+						.try_collect::<Vec<_>>(),
+						tx.get_ranges_keyvalues(
+							universaldb::RangeOption {
+								mode: StreamingMode::WantAll,
+								..(&state_subspace).into()
+							},
+							Snapshot,
+						)
+						.try_collect::<Vec<_>>(),
+						tx.get_ranges_keyvalues(
+							universaldb::RangeOption {
+								mode: StreamingMode::WantAll,
+								..(&output_subspace).into()
+							},
+							Snapshot,
+						)
+						.try_collect::<Vec<_>>(),
+						self.read_active_history(workflow_id, Snapshot, &tx),
+					)?;
+
+					let Some(name_entry) = name_entry else {
+						return Ok(None);
+					};
+
+					// State of the last top level loop event
+					let loop_state = events_by_location
+						.get(&Location::empty())
+						.into_iter()
+						.flatten()
+						.rev()
+						.find_map(|event| match &event.data {
+							EventData::Loop(loop_event) => Some(loop_event.state.clone()),
+							_ => None,
+						});
+
+					Ok(Some(QueryData {
+						workflow_name: name_key.deserialize(&name_entry)?,
+						input: input_key.combine(input_chunks)?,
+						state: if state_chunks.is_empty() {
+							serde_json::value::RawValue::NULL.to_owned()
+						} else {
+							state_key.combine(state_chunks)?
+						},
+						output: if output_chunks.is_empty() {
+							None
+						} else {
+							Some(output_key.combine(output_chunks)?)
+						},
+						loop_state,
+						schema_version: schema_version_entry
+							.map(|raw| schema_version_key.deserialize(&raw))
+							.transpose()?
+							.unwrap_or_default(),
+					}))
+				}
+			})
+			.custom_instrument(tracing::info_span!("get_workflow_query_data_tx"))
+			.await
+			.map_err(WorkflowError::Udb)
+	}
+
 	#[tracing::instrument(skip_all)]
 	async fn publish_signal(
 		&self,
@@ -2099,13 +3553,23 @@ impl Database for DatabaseKv {
 		signal_id: Id,
 		signal_name: &str,
 		body: &serde_json::value::RawValue,
-	) -> WorkflowResult<()> {
-		self.pools
+		idempotency_key: Option<&str>,
+	) -> WorkflowResult<Id> {
+		let signal_id = self
+			.pools
 			.udb()
 			.map_err(WorkflowError::PoolsGeneric)?
 			.run(|tx| async move {
-				self.publish_signal_inner(ray_id, workflow_id, signal_id, signal_name, body, &tx)
-					.await
+				self.publish_signal_inner(
+					ray_id,
+					workflow_id,
+					signal_id,
+					signal_name,
+					body,
+					idempotency_key,
+					&tx,
+				)
+				.await
 			})
 			.custom_instrument(tracing::info_span!("publish_signal_tx"))
 			.await
@@ -2113,7 +3577,7 @@ impl Database for DatabaseKv {
 
 		self.wake_worker();
 
-		Ok(())
+		Ok(signal_id)
 	}
 
 	#[tracing::instrument(skip_all)]
@@ -2127,21 +3591,25 @@ impl Database for DatabaseKv {
 		signal_id: Id,
 		signal_name: &str,
 		body: &serde_json::value::RawValue,
+		idempotency_key: Option<&str>,
 		_loop_location: Option<&Location>,
-	) -> WorkflowResult<()> {
-		self.pools
+	) -> WorkflowResult<Id> {
+		let signal_id = self
+			.pools
 			.udb()
 			.map_err(WorkflowError::PoolsGeneric)?
 			.run(|tx| async move {
-				self.publish_signal_inner(
-					ray_id,
-					to_workflow_id,
-					signal_id,
-					signal_name,
-					body,
-					&tx,
-				)
-				.await?;
+				let signal_id = self
+					.publish_signal_inner(
+						ray_id,
+						to_workflow_id,
+						signal_id,
+						signal_name,
+						body,
+						idempotency_key,
+						&tx,
+					)
+					.await?;
 
 				// Insert history event
 				keys::history::insert::signal_send_event(
@@ -2157,7 +3625,7 @@ impl Database for DatabaseKv {
 					to_workflow_id,
 				)?;
 
-				Ok(())
+				Ok(signal_id)
 			})
 			.custom_instrument(tracing::info_span!("publish_signal_from_workflow_tx"))
 			.await
@@ -2165,7 +3633,85 @@ impl Database for DatabaseKv {
 
 		self.wake_worker();
 
-		Ok(())
+		Ok(signal_id)
+	}
+
+	#[tracing::instrument(skip_all, fields(%workflow_id, propagate))]
+	async fn cancel_workflow(
+		&self,
+		ray_id: Id,
+		workflow_id: Id,
+		signal_id: Id,
+		reason: Option<&str>,
+		propagate: bool,
+	) -> WorkflowResult<Vec<Id>> {
+		let cancelled_workflow_ids = self
+			.pools
+			.udb()
+			.map_err(WorkflowError::PoolsGeneric)?
+			.run(|tx| async move {
+				self.cancel_workflow_inner(ray_id, workflow_id, signal_id, reason, propagate, &tx)
+					.await
+			})
+			.custom_instrument(tracing::info_span!("cancel_workflow_tx"))
+			.await
+			.map_err(WorkflowError::Udb)?;
+
+		self.wake_worker();
+
+		Ok(cancelled_workflow_ids)
+	}
+
+	#[tracing::instrument(skip_all, fields(%workflow_id, propagate))]
+	async fn cancel_workflow_from_workflow(
+		&self,
+		from_workflow_id: Id,
+		location: &Location,
+		version: usize,
+		ray_id: Id,
+		workflow_id: Id,
+		signal_id: Id,
+		reason: Option<&str>,
+		propagate: bool,
+		_loop_location: Option<&Location>,
+	) -> WorkflowResult<Vec<Id>> {
+		let cancelled_workflow_ids = self
+			.pools
+			.udb()
+			.map_err(WorkflowError::PoolsGeneric)?
+			.run(|tx| async move {
+				let cancelled_workflow_ids = self
+					.cancel_workflow_inner(ray_id, workflow_id, signal_id, reason, propagate, &tx)
+					.await?;
+
+				let body = serde_json::value::to_raw_value(&Cancel {
+					reason: reason.map(|x| x.to_string()),
+				})
+				.map_err(WorkflowError::SerializeSignalBody)?;
+
+				// Insert history event
+				keys::history::insert::signal_send_event(
+					&self.subspace,
+					&tx,
+					from_workflow_id,
+					&location,
+					version,
+					rivet_util::timestamp::now(),
+					signal_id,
+					Cancel::NAME,
+					&body,
+					workflow_id,
+				)?;
+
+				Ok(cancelled_workflow_ids)
+			})
+			.custom_instrument(tracing::info_span!("cancel_workflow_from_workflow_tx"))
+			.await
+			.map_err(WorkflowError::Udb)?;
+
+		self.wake_worker();
+
+		Ok(cancelled_workflow_ids)
 	}
 
 	#[tracing::instrument(skip_all, fields(%sub_workflow_id, %sub_workflow_name, unique))]
@@ -2179,9 +3725,11 @@ impl Database for DatabaseKv {
 		sub_workflow_name: &str,
 		tags: Option<&serde_json::Value>,
 		input: &serde_json::value::RawValue,
+		schema_version: usize,
 		_loop_location: Option<&Location>,
 		unique: bool,
 	) -> WorkflowResult<Id> {
+		let requested_sub_workflow_id = sub_workflow_id;
 		let sub_workflow_id = self
 			.pools
 			.udb()
@@ -2190,15 +3738,25 @@ impl Database for DatabaseKv {
 				let sub_workflow_id = self
 					.dispatch_workflow_inner(
 						ray_id,
-						sub_workflow_id,
+						requested_sub_workflow_id,
 						sub_workflow_name,
 						tags,
 						input,
+						schema_version,
 						unique,
 						&tx,
 					)
 					.await?;
 
+				// Link the sub workflow to this workflow so it is not garbage collected while this workflow can
+				// still replay its history. Also applies to existing workflows returned by a unique dispatch.
+				let referenced_by_key =
+					keys::workflow::ReferencedByKey::new(sub_workflow_id, from_workflow_id);
+				tx.set(
+					&self.subspace.pack(&referenced_by_key),
+					&referenced_by_key.serialize(())?,
+				);
+
 				// Insert history event
 				keys::history::insert::sub_workflow_event(
 					&self.subspace,
@@ -2224,6 +3782,116 @@ impl Database for DatabaseKv {
 		Ok(sub_workflow_id)
 	}
 
+	#[tracing::instrument(skip_all, fields(%schedule_name, %workflow_name))]
+	async fn upsert_schedule(
+		&self,
+		schedule_name: &str,
+		workflow_name: &str,
+		cron: &str,
+		tags: Option<&serde_json::Value>,
+		input: &serde_json::value::RawValue,
+		schema_version: usize,
+	) -> WorkflowResult<()> {
+		let now = rivet_util::timestamp::now();
+
+		// Validates the cron expression
+		let next_ts = crate::schedule::next_tick_ts(cron, now)?;
+
+		if let Some(tags) = tags {
+			if !tags.is_object() {
+				return Err(WorkflowError::InvalidTags("must be an object".to_string()));
+			}
+		}
+
+		self.pools
+			.udb()
+			.map_err(WorkflowError::PoolsGeneric)?
+			.run(|tx| async move {
+				let tx = tx.with_subspace(self.subspace.clone());
+
+				let cron_key = keys::schedule::CronKey::new(schedule_name.to_string());
+				let next_ts_key = keys::schedule::NextTsKey::new(schedule_name.to_string());
+				let pause_ts_key = keys::schedule::PauseTsKey::new(schedule_name.to_string());
+
+				let (existing_cron, existing_next_ts, is_paused) = tokio::try_join!(
+					tx.read_opt(&cron_key, Serializable),
+					tx.read_opt(&next_ts_key, Serializable),
+					tx.exists(&pause_ts_key, Serializable),
+				)?;
+
+				// Keep the pending tick if the cron is unchanged so that re-registering a schedule (i.e. on
+				// every startup) does not skip ticks
+				let next_ts = match (&existing_cron, existing_next_ts) {
+					(Some(existing_cron), Some(existing_next_ts)) if existing_cron == cron => {
+						existing_next_ts
+					}
+					_ => next_ts,
+				};
+
+				if existing_cron.is_none() {
+					tx.write(
+						&keys::schedule::CreateTsKey::new(schedule_name.to_string()),
+						now,
+					)?;
+				}
+
+				tx.write(
+					&keys::schedule::WorkflowNameKey::new(schedule_name.to_string()),
+					workflow_name.to_string(),
+				)?;
+				tx.write(&cron_key, cron.to_string())?;
+
+				let tags_key = keys::schedule::TagsKey::new(schedule_name.to_string());
+				if let Some(tags) = tags {
+					tx.write(&tags_key, tags.clone())?;
+				} else {
+					tx.delete(&tags_key);
+				}
+
+				// Write input
+				let input_key = keys::schedule::InputKey::new(schedule_name.to_string());
+				tx.clear_subspace_range(&self.subspace.subspace(&input_key));
+
+				for (i, chunk) in input_key.split_ref(input)?.into_iter().enumerate() {
+					let chunk_key = input_key.chunk(i);
+
+					tx.set(&self.subspace.pack(&chunk_key), &chunk);
+				}
+
+				let schema_version_key =
+					keys::schedule::SchemaVersionKey::new(schedule_name.to_string());
+				if schema_version != 0 {
+					tx.write(&schema_version_key, schema_version)?;
+				} else {
+					tx.delete(&schema_version_key);
+				}
+
+				// Move index entry
+				if let Some(existing_next_ts) = existing_next_ts {
+					tx.delete(&keys::schedule::ByNextTsKey::new(
+						existing_next_ts,
+						schedule_name.to_string(),
+					));
+				}
+
+				tx.write(&next_ts_key, next_ts)?;
+
+				if !is_paused {
+					tx.write(
+						&keys::schedule::ByNextTsKey::new(next_ts, schedule_name.to_string()),
+						(),
+					)?;
+				}
+
+				Ok(())
+			})
+			.custom_instrument(tracing::info_span!("upsert_schedule_tx"))
+			.await
+			.map_err(WorkflowError::Udb)?;
+
+		Ok(())
+	}
+
 	#[tracing::instrument(skip_all)]
 	async fn update_workflow_tags(
 		&self,
@@ -2345,6 +4013,84 @@ impl Database for DatabaseKv {
 		Ok(())
 	}
 
+	#[tracing::instrument(skip_all, fields(%workflow_id, schema_version))]
+	async fn migrate_workflow(
+		&self,
+		workflow_id: Id,
+		from_schema_version: usize,
+		schema_version: usize,
+		input: &serde_json::value::RawValue,
+		state: &serde_json::value::RawValue,
+		holds_lease: bool,
+	) -> WorkflowResult<()> {
+		self.pools
+			.udb()
+			.map_err(WorkflowError::PoolsGeneric)?
+			.run(|tx| async move {
+				let (is_leased, current_schema_version) = {
+					let tx = tx.with_subspace(self.subspace.clone());
+
+					tokio::try_join!(
+						tx.exists(&keys::workflow::LeaseKey::new(workflow_id), Serializable),
+						tx.read_opt(
+							&keys::workflow::SchemaVersionKey::new(workflow_id),
+							Serializable
+						),
+					)?
+				};
+
+				if is_leased && !holds_lease {
+					return Err(WorkflowError::WorkflowLeased.into());
+				}
+
+				// No schema version means version 0
+				let current_schema_version = current_schema_version.unwrap_or_default();
+				if current_schema_version != from_schema_version {
+					return Err(WorkflowError::SchemaVersionChanged(
+						from_schema_version,
+						current_schema_version,
+					)
+					.into());
+				}
+
+				let input_key = keys::workflow::InputKey::new(workflow_id);
+				let state_key = keys::workflow::StateKey::new(workflow_id);
+
+				// Replace input
+				tx.clear_subspace_range(&self.subspace.subspace(&input_key));
+
+				for (i, chunk) in input_key.split_ref(input)?.into_iter().enumerate() {
+					let chunk_key = input_key.chunk(i);
+
+					tx.set(&self.subspace.pack(&chunk_key), &chunk);
+				}
+
+				// Replace state. Null state is never written
+				tx.clear_subspace_range(&self.subspace.subspace(&state_key));
+
+				if state.get() != "null" {
+					for (i, chunk) in state_key.split_ref(state)?.into_iter().enumerate() {
+						let chunk_key = state_key.chunk(i);
+
+						tx.set(&self.subspace.pack(&chunk_key), &chunk);
+					}
+				}
+
+				let tx = tx.with_subspace(self.subspace.clone());
+				tx.write(
+					&keys::workflow::SchemaVersionKey::new(workflow_id),
+					schema_version,
+				)?;
+
+				Ok(())
+			})
+			.custom_instrument(tracing::info_span!("migrate_workflow_tx"))
+			.await
+			.map_err(WorkflowError::Udb)?;
+
+		Ok(())
+	}
+
 	#[tracing::instrument(skip_all)]
 	async fn commit_workflow_activity_event(
 		&self,
@@ -2943,6 +4689,18 @@ impl TryFrom<WorkflowHistoryEventBuilder> for RemovedEvent {
 	}
 }
 
+/// Workflow data read by the retention index backfill.
+#[derive(Default)]
+struct BackfillWorkflow {
+	name: Option<String>,
+	termination_ts: Option<i64>,
+	has_output: bool,
+	is_silenced: bool,
+	is_indexed: bool,
+	/// Sub workflows in the active history.
+	sub_workflow_ids: Vec<Id>,
+}
+
 fn value_to_str(v: &serde_json::Value) -> WorkflowResult<String> {
 	match v {
 		serde_json::Value::String(s) => Ok(s.clone()),
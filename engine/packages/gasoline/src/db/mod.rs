@@ -6,6 +6,7 @@ use rivet_util::Id;
 use serde::de::DeserializeOwned;
 
 use crate::{
+	cancel::Termination,
 	error::{WorkflowError, WorkflowResult},
 	history::{
 		event::{Event, EventType, SleepState},
@@ -71,10 +72,27 @@ pub trait Database: Send {
 	/// Function to publish metrics. Called periodically.
 	async fn publish_metrics(&self, worker_instance_id: Id) -> WorkflowResult<()>;
 
+	/// Deletes all workflows with the given name that stopped (completed, cancelled, or terminated) before
+	/// the given timestamp, along with their history, signals, and wake keys. Workflows referenced by the
+	/// history of a running workflow are kept. Only one worker collects a given workflow name at a time.
+	/// Called periodically. Returns the amount of workflows and keys deleted, or `None` if another worker is
+	/// already collecting this workflow name.
+	async fn gc_stopped_workflows(
+		&self,
+		workflow_name: &str,
+		stopped_before_ts: i64,
+	) -> WorkflowResult<Option<(usize, usize)>>;
+
+	/// Dispatches a workflow for each unpaused schedule whose next tick has passed and moves the schedule to
+	/// its following tick. Each tick is dispatched by only one worker. Called periodically. Returns the
+	/// amount of workflows dispatched.
+	async fn dispatch_due_schedules(&self, worker_instance_id: Id) -> WorkflowResult<usize>;
+
 	// MARK: Workflows/signals
 
 	/// Writes a new workflow to the database. If unique is set, this should return the existing workflow ID
-	/// (if one exists) instead of the given workflow ID.
+	/// (if one exists) instead of the given workflow ID. `schema_version` is the migration version of the
+	/// input.
 	async fn dispatch_workflow(
 		&self,
 		ray_id: Id,
@@ -82,6 +100,7 @@ pub trait Database: Send {
 		workflow_name: &str,
 		tags: Option<&serde_json::Value>,
 		input: &serde_json::value::RawValue,
+		schema_version: usize,
 		unique: bool,
 	) -> WorkflowResult<Id>;
 
@@ -96,11 +115,13 @@ pub trait Database: Send {
 	) -> WorkflowResult<Option<Id>>;
 
 	/// Pulls workflows for processing by the worker. Will only pull workflows with names matching the filter.
-	/// Should also update the ping of this worker instance.
+	/// Filter entries earlier in the list take precedence when `max` limits the total amount of workflows
+	/// pulled. Should also update the ping of this worker instance.
 	async fn pull_workflows(
 		&self,
 		worker_instance_id: Id,
-		filter: &[&str],
+		filter: &[PullFilter<'_>],
+		max: Option<usize>,
 	) -> WorkflowResult<Vec<PulledWorkflowData>>;
 
 	/// Mark a workflow as completed.
@@ -123,6 +144,14 @@ pub trait Database: Send {
 		error: &str,
 	) -> WorkflowResult<()>;
 
+	/// Marks a workflow as stopped after it did not catch a cancellation.
+	async fn commit_cancelled_workflow(
+		&self,
+		workflow_id: Id,
+		workflow_name: &str,
+		reason: Option<&str>,
+	) -> WorkflowResult<()>;
+
 	/// Pulls the oldest signal with the given filter.
 	async fn pull_next_signal(
 		&self,
@@ -143,7 +172,11 @@ pub trait Database: Send {
 		sub_workflow_id: Id,
 	) -> WorkflowResult<Option<WorkflowData>>;
 
-	/// Write a new signal to the database.
+	/// Retrieves the data used to answer queries on a workflow.
+	async fn get_workflow_query_data(&self, workflow_id: Id) -> WorkflowResult<Option<QueryData>>;
+
+	/// Write a new signal to the database. If an idempotency key is given and a signal with the same key was
+	/// recently published to the same workflow, nothing is written. Returns the id of the published signal.
 	async fn publish_signal(
 		&self,
 		ray_id: Id,
@@ -151,9 +184,11 @@ pub trait Database: Send {
 		signal_id: Id,
 		signal_name: &str,
 		body: &serde_json::value::RawValue,
-	) -> WorkflowResult<()>;
+		idempotency_key: Option<&str>,
+	) -> WorkflowResult<Id>;
 
-	/// Write a new signal to the database. Contains extra info used to populate the history.
+	/// Write a new signal to the database. Contains extra info used to populate the history. Deduplicates
+	/// the same as `publish_signal`.
 	async fn publish_signal_from_workflow(
 		&self,
 		from_workflow_id: Id,
@@ -164,8 +199,35 @@ pub trait Database: Send {
 		signal_id: Id,
 		signal_name: &str,
 		body: &serde_json::value::RawValue,
+		idempotency_key: Option<&str>,
 		loop_location: Option<&Location>,
-	) -> WorkflowResult<()>;
+	) -> WorkflowResult<Id>;
+
+	/// Publishes a cancel signal to the given workflow. If `propagate` is set, all of its incomplete sub
+	/// workflows are cancelled recursively. Workflows that are stopped or already being cancelled are
+	/// skipped. Returns the ids of the workflows that were cancelled.
+	async fn cancel_workflow(
+		&self,
+		ray_id: Id,
+		workflow_id: Id,
+		signal_id: Id,
+		reason: Option<&str>,
+		propagate: bool,
+	) -> WorkflowResult<Vec<Id>>;
+
+	/// Cancels a workflow from an existing workflow. Contains extra info used to populate the history.
+	async fn cancel_workflow_from_workflow(
+		&self,
+		from_workflow_id: Id,
+		location: &Location,
+		version: usize,
+		ray_id: Id,
+		workflow_id: Id,
+		signal_id: Id,
+		reason: Option<&str>,
+		propagate: bool,
+		loop_location: Option<&Location>,
+	) -> WorkflowResult<Vec<Id>>;
 
 	/// Publish a new workflow from an existing workflow.
 	async fn dispatch_sub_workflow(
@@ -178,10 +240,23 @@ pub trait Database: Send {
 		sub_workflow_name: &str,
 		tags: Option<&serde_json::Value>,
 		input: &serde_json::value::RawValue,
+		schema_version: usize,
 		loop_location: Option<&Location>,
 		unique: bool,
 	) -> WorkflowResult<Id>;
 
+	/// Creates or updates a schedule that dispatches a workflow with the given input on every tick of the cron
+	/// expression. Updating a schedule keeps its pending tick if the cron expression did not change.
+	async fn upsert_schedule(
+		&self,
+		schedule_name: &str,
+		workflow_name: &str,
+		cron: &str,
+		tags: Option<&serde_json::Value>,
+		input: &serde_json::value::RawValue,
+		schema_version: usize,
+	) -> WorkflowResult<()>;
+
 	/// Updates workflow tags.
 	async fn update_workflow_tags(
 		&self,
@@ -197,6 +272,19 @@ pub trait Database: Send {
 		state: &serde_json::value::RawValue,
 	) -> WorkflowResult<()>;
 
+	/// Replaces the input and state of a workflow with their migrated versions. Fails if the stored schema
+	/// version is no longer `from_schema_version`. Unless `holds_lease` is set (the caller is the worker
+	/// running the workflow), also fails if the workflow is leased.
+	async fn migrate_workflow(
+		&self,
+		workflow_id: Id,
+		from_schema_version: usize,
+		schema_version: usize,
+		input: &serde_json::value::RawValue,
+		state: &serde_json::value::RawValue,
+		holds_lease: bool,
+	) -> WorkflowResult<()>;
+
 	// MARK: History
 
 	/// Write a workflow activity event to history.
@@ -291,6 +379,8 @@ pub struct WorkflowData {
 	state: Box<serde_json::value::RawValue>,
 	output: Option<Box<serde_json::value::RawValue>>,
 	pub has_wake_condition: bool,
+	/// Set if the workflow was stopped by a cancellation or terminated.
+	pub termination: Option<Termination>,
 }
 
 impl WorkflowData {
@@ -311,6 +401,13 @@ impl WorkflowData {
 	}
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct PullFilter<'a> {
+	pub workflow_name: &'a str,
+	/// Max workflows of this name to pull.
+	pub limit: Option<usize>,
+}
+
 #[derive(Debug)]
 pub struct PulledWorkflowData {
 	pub workflow_id: Id,
@@ -319,14 +416,34 @@ pub struct PulledWorkflowData {
 	pub ray_id: Id,
 	pub input: Box<serde_json::value::RawValue>,
 	pub state: Box<serde_json::value::RawValue>,
+	/// Migration version of the input and state.
+	pub schema_version: usize,
+	/// Trace context of the span the workflow was dispatched in.
+	pub trace_context: Option<String>,
 	pub wake_deadline_ts: Option<i64>,
+	/// Whether or not a cancel signal was pending when this workflow was pulled.
+	pub cancel_requested: bool,
 
 	pub events: HashMap<Location, Vec<Event>>,
 }
 
+#[derive(Debug)]
+pub struct QueryData {
+	pub workflow_name: String,
+	pub(crate) input: Box<serde_json::value::RawValue>,
+	pub(crate) state: Box<serde_json::value::RawValue>,
+	pub(crate) output: Option<Box<serde_json::value::RawValue>>,
+	/// State of the latest top level loop of the workflow.
+	pub(crate) loop_state: Option<Box<serde_json::value::RawValue>>,
+	/// Migration version of the input and state.
+	pub(crate) schema_version: usize,
+}
+
 pub struct SignalData {
 	pub signal_id: Id,
 	pub signal_name: String,
 	pub body: Box<serde_json::value::RawValue>,
 	pub create_ts: i64,
+	/// Trace context of the span the signal was published in.
+	pub trace_context: Option<String>,
 }
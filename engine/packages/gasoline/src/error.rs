@@ -3,7 +3,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use rivet_util::Id;
 use tokio::time::Instant;
 
-use crate::ctx::common::RETRY_TIMEOUT_MS;
+use crate::{cancel::Cancel, ctx::common::RETRY_TIMEOUT_MS};
 
 pub type WorkflowResult<T> = Result<T, WorkflowError>;
 
@@ -22,6 +22,12 @@ pub enum WorkflowError {
 	#[error("operation failure: {0:?}")]
 	OperationFailure(#[source] anyhow::Error),
 
+	#[error("query failure: {0:?}")]
+	QueryFailure(#[source] anyhow::Error),
+
+	#[error("workflow is not a {0} workflow: {1}")]
+	QueryWorkflowMismatch(&'static str, String),
+
 	#[error("workflow missing from registry: {0}")]
 	WorkflowMissingFromRegistry(String),
 
@@ -52,6 +58,15 @@ pub enum WorkflowError {
 	#[error("deserialize workflow state: {0}")]
 	DeserializeWorkflowState(#[source] serde_json::Error),
 
+	#[error("workflow migration failed: {0:?}")]
+	MigrationFailed(#[source] anyhow::Error),
+
+	#[error("workflow is leased by a worker")]
+	WorkflowLeased,
+
+	#[error("workflow schema version changed: expected {0}, found {1}")]
+	SchemaVersionChanged(usize, usize),
+
 	#[error("state inaccessible: {0}")]
 	WorkflowStateInaccessible(&'static str),
 
@@ -94,6 +109,9 @@ pub enum WorkflowError {
 	#[error("invalid tags: {0}")]
 	InvalidTags(String),
 
+	#[error("invalid cron expression: {0}")]
+	InvalidCron(String),
+
 	#[error("failed to serialize loop state: {0}")]
 	SerializeLoopState(#[source] serde_json::Error),
 
@@ -124,6 +142,12 @@ pub enum WorkflowError {
 	#[error("sub workflow incomplete: {0:?}")]
 	SubWorkflowIncomplete(Id),
 
+	#[error("sub workflow terminated: {0:?}")]
+	SubWorkflowTerminated(Id),
+
+	#[error("workflow cancelled: {}", .0.reason.as_deref().unwrap_or("no reason given"))]
+	Cancelled(Cancel),
+
 	#[error("integer conversion failed")]
 	IntegerConversion,
 
@@ -176,6 +200,9 @@ pub enum WorkflowError {
 
 	#[error("flush channel closed")]
 	FlushChannelClosed,
+
+	#[error("replay reached the end of recorded history")]
+	ReplayEnded,
 }
 
 impl WorkflowError {
@@ -230,6 +257,15 @@ impl WorkflowError {
 		}
 	}
 
+	/// Returns the cancellation if the workflow stopped because of one.
+	pub(crate) fn cancellation(&self) -> Option<&Cancel> {
+		match self {
+			WorkflowError::Cancelled(cancel) => Some(cancel),
+			WorkflowError::WorkflowFailure(err) => crate::cancel::cancellation(err),
+			_ => None,
+		}
+	}
+
 	/// Any error that the workflow can try again on a fixed number of times. Only used for printing.
 	pub(crate) fn is_retryable(&self) -> bool {
 		match self {
@@ -9,7 +9,11 @@ use super::{
 	location::{Coordinate, Location},
 	removed::Removed,
 };
-use crate::error::{WorkflowError, WorkflowResult};
+use crate::{
+	cancel::Cancel,
+	error::{WorkflowError, WorkflowResult},
+	signal::Signal,
+};
 
 // NOTE: Cheap to clone because History is an `Arc`
 /// Allows history traversal and comparison. This does not modify any history throughout the runtime of
@@ -403,6 +407,22 @@ impl Cursor {
 		}
 	}
 
+	/// Returns `Some` if a cancellation should be checked for at the current location. Unlike the other
+	/// compare functions this does not error on mismatched history because a cancellation can be received
+	/// before any event.
+	pub(crate) fn compare_cancel(&self) -> Option<HistoryResult<&SignalEvent>> {
+		if let Some(event) = self.current_event() {
+			match &event.data {
+				EventData::Signal(signal) if signal.name == Cancel::NAME => {
+					Some(HistoryResult::Event(signal))
+				}
+				_ => None,
+			}
+		} else {
+			Some(HistoryResult::New)
+		}
+	}
+
 	/// Returns `Some` if the current event is being replayed.
 	pub fn compare_loop(&self, version: usize) -> WorkflowResult<HistoryResult<&LoopEvent>> {
 		if let Some(event) = self.current_event() {
@@ -1,5 +1,6 @@
 pub mod activity;
 pub mod builder;
+pub mod cancel;
 pub mod ctx;
 pub mod db;
 mod error;
@@ -8,9 +9,13 @@ pub mod history;
 pub mod listen;
 pub mod message;
 pub mod metrics;
+pub mod migration;
 pub mod operation;
 pub mod prelude;
+pub mod query;
 pub mod registry;
+pub mod replay;
+pub mod schedule;
 pub mod signal;
 mod stub;
 pub mod utils;
@@ -100,4 +100,7 @@ pub(crate) struct PubsubMessageWrapper<'a> {
 	pub(crate) ts: i64,
 	#[serde(borrow)]
 	pub(crate) body: &'a serde_json::value::RawValue,
+	/// Trace context of the span the message was published in.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub(crate) trace_context: Option<String>,
 }
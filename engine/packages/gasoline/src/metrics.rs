@@ -129,6 +129,19 @@ lazy_static::lazy_static! {
 		.with_boundaries(BUCKETS.to_vec())
 		.build();
 
+	/// Expected attributes: "workflow_name"
+	pub static ref WORKFLOW_GC_DELETED: Counter<u64> = METER.u64_counter("rivet_gasoline_workflow_gc_deleted")
+		.with_description("Total stopped workflows deleted by the retention GC.")
+		.build();
+	/// Expected attributes: "workflow_name"
+	pub static ref WORKFLOW_GC_KEYS_DELETED: Counter<u64> = METER.u64_counter("rivet_gasoline_workflow_gc_keys_deleted")
+		.with_description("Total database keys (workflow data, history, and signals) deleted by the retention GC.")
+		.build();
+	/// Expected attributes: "workflow_name"
+	pub static ref SCHEDULE_DISPATCHED: Counter<u64> = METER.u64_counter("rivet_gasoline_schedule_dispatched")
+		.with_description("Total workflows dispatched by schedules.")
+		.build();
+
 	/// Expected attributes: "workflow_name"
 	pub static ref LOOP_ITERATION_DURATION: Histogram<f64> = METER.f64_histogram("rivet_gasoline_loop_iteration_duration")
 		.with_description("Total duration of a single loop iteration (excluding its body).")
@@ -0,0 +1,122 @@
+use anyhow::{Context, Result, bail};
+use serde_json::value::RawValue;
+
+/// Upgrades a serialized input or state from the previous schema version to the next.
+pub type MigrationFn = fn(serde_json::Value) -> Result<serde_json::Value>;
+
+/// Versioned upgrade steps for the input and state of a workflow. The schema version of a workflow is the
+/// highest version declared here and is stored alongside the workflow when it is dispatched. Workflows with
+/// an older schema version are migrated when they are pulled by a worker (or with `wf migrate`).
+///
+/// Example:
+/// ```rust,ignore
+/// #[workflow]
+/// #[migrations = migrations]
+/// pub async fn my_workflow(ctx: &mut WorkflowCtx, input: &Input) -> Result<()> {
+/// 	// ...
+/// }
+///
+/// fn migrations() -> Migrations {
+/// 	Migrations::default()
+/// 		// v1: `region` became `datacenter`
+/// 		.input(1, |mut input| {
+/// 			if let Some(obj) = input.as_object_mut() {
+/// 				let region = obj.remove("region").unwrap_or_default();
+/// 				obj.insert("datacenter".into(), region);
+/// 			}
+///
+/// 			Ok(input)
+/// 		})
+/// 		// v2: added `retries`
+/// 		.state(2, |mut state| {
+/// 			state["retries"] = 0.into();
+///
+/// 			Ok(state)
+/// 		})
+/// }
+/// ```
+#[derive(Default, Clone)]
+pub struct Migrations {
+	input: Vec<(usize, MigrationFn)>,
+	state: Vec<(usize, MigrationFn)>,
+}
+
+impl Migrations {
+	/// Adds a step that upgrades the input to the given version.
+	pub fn input(mut self, version: usize, f: MigrationFn) -> Self {
+		self.input.push((version, f));
+		self.input.sort_by_key(|(version, _)| *version);
+
+		self
+	}
+
+	/// Adds a step that upgrades the state to the given version. Steps are not run for workflows that have
+	/// not set their state yet.
+	pub fn state(mut self, version: usize, f: MigrationFn) -> Self {
+		self.state.push((version, f));
+		self.state.sort_by_key(|(version, _)| *version);
+
+		self
+	}
+
+	/// Current schema version.
+	pub fn version(&self) -> usize {
+		self.input
+			.iter()
+			.chain(&self.state)
+			.map(|(version, _)| *version)
+			.max()
+			.unwrap_or_default()
+	}
+
+	/// Upgrades the given input and state from `from_version` to the current schema version.
+	pub fn migrate(
+		&self,
+		from_version: usize,
+		input: &RawValue,
+		state: &RawValue,
+	) -> Result<(Box<RawValue>, Box<RawValue>)> {
+		let version = self.version();
+
+		if from_version > version {
+			bail!(
+				"schema version {from_version} is newer than the latest known version {version}, was the workflow written by a newer release?"
+			);
+		}
+
+		let input =
+			migrate_value(&self.input, from_version, input).context("failed to migrate input")?;
+
+		// Null state means the workflow never set its state
+		let state = if state.get() == "null" {
+			state.to_owned()
+		} else {
+			migrate_value(&self.state, from_version, state).context("failed to migrate state")?
+		};
+
+		Ok((input, state))
+	}
+}
+
+fn migrate_value(
+	steps: &[(usize, MigrationFn)],
+	from_version: usize,
+	value: &RawValue,
+) -> Result<Box<RawValue>> {
+	let mut steps = steps
+		.iter()
+		.filter(|(version, _)| *version > from_version)
+		.peekable();
+
+	if steps.peek().is_none() {
+		return Ok(value.to_owned());
+	}
+
+	let mut value = serde_json::from_str::<serde_json::Value>(value.get())?;
+
+	for (version, f) in steps {
+		value = f(value).with_context(|| format!("migration to version {version} failed"))?;
+	}
+
+	serde_json::value::to_raw_value(&value).map_err(Into::into)
+}
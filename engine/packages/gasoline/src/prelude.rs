@@ -19,7 +19,9 @@ pub use crate::{
 	history::removed::*,
 	listen::{CustomListener, Listen},
 	message::Message as MessageTrait,
+	migration::Migrations,
 	operation::Operation as OperationTrait,
+	query::Query as QueryTrait,
 	registry::Registry,
 	signal::{Signal as SignalTrait, join_signal},
 	stub::{activity, closure, removed, v},
@@ -0,0 +1,24 @@
+//! Read-only queries against the persisted state of a workflow. Queries are answered by the caller from the
+//! workflow's input, state, output, and latest top level loop state, so they work regardless of whether the
+//! workflow is currently running on a worker or sleeping.
+
+use std::fmt::Debug;
+
+use anyhow::Result;
+
+use crate::{ctx::QueryCtx, workflow::Workflow};
+
+pub trait Query {
+	type Input: QueryInput;
+	type Output: Debug + Send;
+	/// Workflow this query can be run against.
+	type Workflow: Workflow;
+
+	const NAME: &'static str;
+
+	fn handle(ctx: &QueryCtx, input: &Self::Input) -> Result<Self::Output>;
+}
+
+pub trait QueryInput: Debug + Send {
+	type Query: Query;
+}
@@ -6,6 +6,7 @@ use tracing::Instrument;
 use crate::{
 	ctx::WorkflowCtx,
 	error::{WorkflowError, WorkflowResult},
+	migration::Migrations,
 	workflow::Workflow,
 };
 
@@ -61,6 +62,9 @@ impl Registry {
 			Arc::new(RegistryWorkflow {
 				run: |ctx| {
 					async move {
+						// Upgrade input and state written by an older version of this workflow
+						ctx.migrate(&W::migrations()).await?;
+
 						// Deserialize input
 						let input = serde_json::from_str(ctx.input().get())
 							.map_err(WorkflowError::DeserializeWorkflowInput)?;
@@ -85,6 +89,12 @@ impl Registry {
 					.in_current_span()
 					.boxed()
 				},
+				migrations: W::migrations,
+				validate_input: |input| {
+					serde_json::from_str::<W::Input>(input.get())
+						.map(|_| ())
+						.map_err(WorkflowError::DeserializeWorkflowInput)
+				},
 			}),
 		);
 
@@ -108,4 +118,7 @@ pub struct RegistryWorkflow {
 	) -> Pin<
 		Box<dyn Future<Output = WorkflowResult<Box<serde_json::value::RawValue>>> + Send + 'a>,
 	>,
+	pub migrations: fn() -> Migrations,
+	/// Checks that the given (migrated) input deserializes into the input type of the workflow.
+	pub validate_input: fn(&serde_json::value::RawValue) -> WorkflowResult<()>,
 }
@@ -0,0 +1,627 @@
+//! Replays recorded workflow histories against the workflows in a registry to check that workflow code changes
+//! are compatible with the history of in-flight workflows.
+//!
+//! Replays never run activities or write to the database. Replaying stops at the first step that is not in the
+//! recorded history (which would normally execute and commit a new event).
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use futures_util::{StreamExt, stream::BoxStream};
+use rivet_util::Id;
+use tokio::sync::watch;
+
+use crate::{
+	ctx::WorkflowCtx,
+	db::{
+		Database, DatabaseHandle, PullFilter, PulledWorkflowData, QueryData, SignalData,
+		WorkflowData, debug,
+	},
+	error::{WorkflowError, WorkflowResult},
+	history::{
+		event::{
+			ActivityEvent, Event, EventData, EventType, LoopEvent, MessageSendEvent, SignalEvent,
+			SignalSendEvent, SleepState, SubWorkflowEvent,
+		},
+		location::Location,
+	},
+	registry::RegistryHandle,
+};
+
+#[derive(Debug)]
+pub struct ReplayReport {
+	pub workflow_id: Id,
+	pub workflow_name: String,
+	pub outcome: ReplayOutcome,
+}
+
+#[derive(Debug)]
+pub enum ReplayOutcome {
+	/// The entire recorded history was replayed without any divergence.
+	Ok,
+	/// The workflow code does not match the recorded history. Contains the first divergence.
+	Diverged(String),
+	/// The history could not be replayed for a reason other than a divergence.
+	Failed(String),
+}
+
+/// Replays workflow histories against a registry.
+pub struct Replayer {
+	registry: RegistryHandle,
+	config: rivet_config::Config,
+	pools: rivet_pools::Pools,
+	cache: rivet_cache::Cache,
+	db: Option<DatabaseHandle>,
+}
+
+impl Replayer {
+	pub fn new(
+		registry: RegistryHandle,
+		config: rivet_config::Config,
+		pools: rivet_pools::Pools,
+		cache: rivet_cache::Cache,
+	) -> Self {
+		Replayer {
+			registry,
+			config,
+			pools,
+			cache,
+			db: None,
+		}
+	}
+
+	/// Database used to read the output of sub workflows. Without it, replays stop at the first sub workflow
+	/// output.
+	pub fn with_db(mut self, db: DatabaseHandle) -> Self {
+		self.db = Some(db);
+		self
+	}
+
+	/// Replays a single workflow history. Forgotten events are ignored.
+	#[tracing::instrument(skip_all, fields(workflow_id=%history.wf.workflow_id, workflow_name=%history.wf.workflow_name))]
+	pub async fn replay(&self, history: debug::HistoryData) -> ReplayReport {
+		let workflow_id = history.wf.workflow_id;
+		let workflow_name = history.wf.workflow_name.clone();
+
+		let outcome = match self.replay_inner(history).await {
+			Ok(outcome) => outcome,
+			Err(err) => ReplayOutcome::Failed(err.to_string()),
+		};
+
+		tracing::debug!(?outcome, "replay finished");
+
+		ReplayReport {
+			workflow_id,
+			workflow_name,
+			outcome,
+		}
+	}
+
+	async fn replay_inner(&self, history: debug::HistoryData) -> Result<ReplayOutcome> {
+		let workflow = self.registry.get_workflow(&history.wf.workflow_name)?;
+
+		let data = PulledWorkflowData {
+			workflow_id: history.wf.workflow_id,
+			workflow_name: history.wf.workflow_name,
+			create_ts: history.wf.create_ts,
+			// Not recorded in history
+			ray_id: Id::nil(),
+			input: serde_json::value::to_raw_value(&history.wf.input)
+				.map_err(WorkflowError::SerializeWorkflowInput)?,
+			state: serde_json::value::to_raw_value(&history.wf.data)
+				.map_err(|err| WorkflowError::DeserializeEventData(err.into()))?,
+			schema_version: history.wf.schema_version,
+			trace_context: None,
+			wake_deadline_ts: None,
+			cancel_requested: false,
+			events: build_history(history.events)?,
+		};
+
+		// Dropping the sender would evict the workflow
+		let (_stop_tx, stop_rx) = watch::channel(());
+
+		let mut ctx = WorkflowCtx::new(
+			self.registry.clone(),
+			Arc::new(ReplayDatabase {
+				inner: self.db.clone(),
+			}),
+			self.config.clone(),
+			self.pools.clone(),
+			self.cache.clone(),
+			data,
+			stop_rx,
+		)?;
+		ctx.set_replay();
+
+		let res = match (workflow.run)(&mut ctx).await {
+			Ok(_) => ctx.cursor().check_clear(),
+			Err(err) => Err(err),
+		};
+
+		let Err(err) = res else {
+			return Ok(ReplayOutcome::Ok);
+		};
+
+		// Find the underlying gasoline error if the workflow wrapped it
+		let inner_err = if let WorkflowError::WorkflowFailure(inner) = &err {
+			inner
+				.chain()
+				.find_map(|x| x.downcast_ref::<WorkflowError>())
+				.unwrap_or(&err)
+		} else {
+			&err
+		};
+
+		Ok(match inner_err {
+			// Recorded history does not match the workflow code
+			WorkflowError::HistoryDiverged(_)
+			| WorkflowError::LatentHistoryFound(_)
+			| WorkflowError::DeserializeWorkflowInput(_)
+			| WorkflowError::DeserializeWorkflowState(_)
+			| WorkflowError::MigrationFailed(_)
+			| WorkflowError::DeserializeActivityOutput(_)
+			| WorkflowError::DeserializeSignalBody(_)
+			| WorkflowError::DeserializeLoopState(_)
+			| WorkflowError::DeserializeLoopOutput(_) => ReplayOutcome::Diverged(inner_err.to_string()),
+			// Workflow stopped at the end of its history the same way it would when running normally
+			WorkflowError::ReplayEnded
+			| WorkflowError::Sleep(_)
+			| WorkflowError::NoSignalFound(_)
+			| WorkflowError::NoSignalFoundAndSleep(_, _)
+			| WorkflowError::SubWorkflowIncomplete(_)
+			| WorkflowError::SubWorkflowTerminated(_)
+			| WorkflowError::Cancelled(_) => ReplayOutcome::Ok,
+			_ => ReplayOutcome::Failed(err.to_string()),
+		})
+	}
+}
+
+/// Converts debug history events into the history format used by the workflow ctx.
+fn build_history(events: Vec<debug::Event>) -> WorkflowResult<HashMap<Location, Vec<Event>>> {
+	let mut events_by_location: HashMap<Location, Vec<Event>> = HashMap::new();
+
+	for event in events {
+		if event.forgotten {
+			continue;
+		}
+
+		let coordinate = event
+			.location
+			.tail()
+			.cloned()
+			.ok_or(WorkflowError::MissingEventData("location"))?;
+
+		let data = match event.data {
+			debug::EventData::Activity(activity) => EventData::Activity(ActivityEvent {
+				name: activity.name,
+				create_ts: event.create_ts,
+				output: activity.output.as_ref().map(to_raw_value).transpose()?,
+				error_count: activity.errors.iter().map(|err| err.count).sum(),
+			}),
+			debug::EventData::Signal(signal) => EventData::Signal(SignalEvent {
+				name: signal.name,
+				body: to_raw_value(&signal.body)?,
+			}),
+			debug::EventData::SignalSend(signal_send) => EventData::SignalSend(SignalSendEvent {
+				signal_id: signal_send.signal_id,
+				name: signal_send.name,
+			}),
+			debug::EventData::MessageSend(message_send) => {
+				EventData::MessageSend(MessageSendEvent {
+					name: message_send.name,
+				})
+			}
+			debug::EventData::SubWorkflow(sub_workflow) => {
+				EventData::SubWorkflow(SubWorkflowEvent {
+					sub_workflow_id: sub_workflow.sub_workflow_id,
+					name: sub_workflow.name,
+				})
+			}
+			debug::EventData::Loop(loop_event) => EventData::Loop(LoopEvent {
+				state: to_raw_value(&loop_event.state)?,
+				output: loop_event.output.as_ref().map(to_raw_value).transpose()?,
+				iteration: loop_event.iteration,
+			}),
+			debug::EventData::Sleep(sleep) => EventData::Sleep(sleep),
+			debug::EventData::Removed(removed) => EventData::Removed(removed),
+			debug::EventData::VersionCheck => EventData::VersionCheck,
+			debug::EventData::Branch => EventData::Branch,
+			debug::EventData::Empty => EventData::Empty,
+		};
+
+		events_by_location
+			.entry(event.location.root())
+			.or_default()
+			.push(Event {
+				coordinate,
+				version: event.version,
+				data,
+			});
+	}
+
+	for events in events_by_location.values_mut() {
+		events.sort_by(|a, b| a.coordinate.cmp(&b.coordinate));
+	}
+
+	Ok(events_by_location)
+}
+
+fn to_raw_value(value: &serde_json::Value) -> WorkflowResult<Box<serde_json::value::RawValue>> {
+	serde_json::value::to_raw_value(value)
+		.map_err(|err| WorkflowError::DeserializeEventData(err.into()))
+}
+
+/// Database used while replaying. All writes end the replay because they only happen for steps that are not
+/// in the recorded history.
+struct ReplayDatabase {
+	/// Used to read sub workflows.
+	inner: Option<DatabaseHandle>,
+}
+
+#[async_trait::async_trait]
+impl Database for ReplayDatabase {
+	async fn from_pools(_pools: rivet_pools::Pools) -> Result<Arc<Self>> {
+		Ok(Arc::new(ReplayDatabase { inner: None }))
+	}
+
+	// Never sleep or poll in memory, stop immediately instead
+	fn worker_poll_interval(&self) -> Duration {
+		Duration::ZERO
+	}
+
+	fn max_signal_poll_retries(&self) -> usize {
+		0
+	}
+
+	fn max_sub_workflow_poll_retries(&self) -> usize {
+		0
+	}
+
+	async fn wake_sub<'a, 'b>(&'a self) -> WorkflowResult<BoxStream<'b, ()>> {
+		Ok(futures_util::stream::pending().boxed())
+	}
+
+	async fn update_worker_ping(&self, _worker_instance_id: Id) -> WorkflowResult<()> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn clear_expired_leases(&self, _worker_instance_id: Id) -> WorkflowResult<()> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn publish_metrics(&self, _worker_instance_id: Id) -> WorkflowResult<()> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn gc_stopped_workflows(
+		&self,
+		_workflow_name: &str,
+		_stopped_before_ts: i64,
+	) -> WorkflowResult<Option<(usize, usize)>> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn dispatch_due_schedules(&self, _worker_instance_id: Id) -> WorkflowResult<usize> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn dispatch_workflow(
+		&self,
+		_ray_id: Id,
+		_workflow_id: Id,
+		_workflow_name: &str,
+		_tags: Option<&serde_json::Value>,
+		_input: &serde_json::value::RawValue,
+		_schema_version: usize,
+		_unique: bool,
+	) -> WorkflowResult<Id> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn get_workflows(&self, workflow_ids: Vec<Id>) -> WorkflowResult<Vec<WorkflowData>> {
+		let Some(inner) = &self.inner else {
+			return Err(WorkflowError::ReplayEnded);
+		};
+
+		inner.get_workflows(workflow_ids).await
+	}
+
+	async fn find_workflow(
+		&self,
+		workflow_name: &str,
+		tags: &serde_json::Value,
+	) -> WorkflowResult<Option<Id>> {
+		let Some(inner) = &self.inner else {
+			return Err(WorkflowError::ReplayEnded);
+		};
+
+		inner.find_workflow(workflow_name, tags).await
+	}
+
+	async fn pull_workflows(
+		&self,
+		_worker_instance_id: Id,
+		_filter: &[PullFilter<'_>],
+		_max: Option<usize>,
+	) -> WorkflowResult<Vec<PulledWorkflowData>> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn complete_workflow(
+		&self,
+		_workflow_id: Id,
+		_workflow_name: &str,
+		_output: &serde_json::value::RawValue,
+	) -> WorkflowResult<()> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn commit_workflow(
+		&self,
+		_workflow_id: Id,
+		_workflow_name: &str,
+		_wake_immediate: bool,
+		_wake_deadline_ts: Option<i64>,
+		_wake_signals: &[&str],
+		_wake_sub_workflow_id: Option<Id>,
+		_error: &str,
+	) -> WorkflowResult<()> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn commit_cancelled_workflow(
+		&self,
+		_workflow_id: Id,
+		_workflow_name: &str,
+		_reason: Option<&str>,
+	) -> WorkflowResult<()> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn pull_next_signal(
+		&self,
+		_workflow_id: Id,
+		_workflow_name: &str,
+		_filter: &[&str],
+		_location: &Location,
+		_version: usize,
+		_loop_location: Option<&Location>,
+		_last_try: bool,
+	) -> WorkflowResult<Option<SignalData>> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn get_sub_workflow(
+		&self,
+		_workflow_id: Id,
+		_workflow_name: &str,
+		sub_workflow_id: Id,
+	) -> WorkflowResult<Option<WorkflowData>> {
+		let Some(inner) = &self.inner else {
+			return Err(WorkflowError::ReplayEnded);
+		};
+
+		// NOTE: `Database::get_sub_workflow` is not used because it writes a wake condition if the sub workflow
+		// is incomplete
+		Ok(inner
+			.get_workflows(vec![sub_workflow_id])
+			.await?
+			.into_iter()
+			.next())
+	}
+
+	async fn get_workflow_query_data(&self, workflow_id: Id) -> WorkflowResult<Option<QueryData>> {
+		let Some(inner) = &self.inner else {
+			return Err(WorkflowError::ReplayEnded);
+		};
+
+		inner.get_workflow_query_data(workflow_id).await
+	}
+
+	async fn publish_signal(
+		&self,
+		_ray_id: Id,
+		_workflow_id: Id,
+		_signal_id: Id,
+		_signal_name: &str,
+		_body: &serde_json::value::RawValue,
+		_idempotency_key: Option<&str>,
+	) -> WorkflowResult<Id> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn publish_signal_from_workflow(
+		&self,
+		_from_workflow_id: Id,
+		_location: &Location,
+		_version: usize,
+		_ray_id: Id,
+		_workflow_id: Id,
+		_signal_id: Id,
+		_signal_name: &str,
+		_body: &serde_json::value::RawValue,
+		_idempotency_key: Option<&str>,
+		_loop_location: Option<&Location>,
+	) -> WorkflowResult<Id> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn cancel_workflow(
+		&self,
+		_ray_id: Id,
+		_workflow_id: Id,
+		_signal_id: Id,
+		_reason: Option<&str>,
+		_propagate: bool,
+	) -> WorkflowResult<Vec<Id>> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn cancel_workflow_from_workflow(
+		&self,
+		_from_workflow_id: Id,
+		_location: &Location,
+		_version: usize,
+		_ray_id: Id,
+		_workflow_id: Id,
+		_signal_id: Id,
+		_reason: Option<&str>,
+		_propagate: bool,
+		_loop_location: Option<&Location>,
+	) -> WorkflowResult<Vec<Id>> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn dispatch_sub_workflow(
+		&self,
+		_ray_id: Id,
+		_workflow_id: Id,
+		_location: &Location,
+		_version: usize,
+		_sub_workflow_id: Id,
+		_sub_workflow_name: &str,
+		_tags: Option<&serde_json::Value>,
+		_input: &serde_json::value::RawValue,
+		_schema_version: usize,
+		_loop_location: Option<&Location>,
+		_unique: bool,
+	) -> WorkflowResult<Id> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn upsert_schedule(
+		&self,
+		_schedule_name: &str,
+		_workflow_name: &str,
+		_cron: &str,
+		_tags: Option<&serde_json::Value>,
+		_input: &serde_json::value::RawValue,
+		_schema_version: usize,
+	) -> WorkflowResult<()> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn update_workflow_tags(
+		&self,
+		_workflow_id: Id,
+		_workflow_name: &str,
+		_tags: &serde_json::Value,
+	) -> WorkflowResult<()> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn update_workflow_state(
+		&self,
+		_workflow_id: Id,
+		_state: &serde_json::value::RawValue,
+	) -> WorkflowResult<()> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn migrate_workflow(
+		&self,
+		_workflow_id: Id,
+		_from_schema_version: usize,
+		_schema_version: usize,
+		_input: &serde_json::value::RawValue,
+		_state: &serde_json::value::RawValue,
+		_holds_lease: bool,
+	) -> WorkflowResult<()> {
+		// Migrations are only applied in memory while replaying
+		Ok(())
+	}
+
+	async fn commit_workflow_activity_event(
+		&self,
+		_workflow_id: Id,
+		_location: &Location,
+		_version: usize,
+		_name: &str,
+		_create_ts: i64,
+		_input: &serde_json::value::RawValue,
+		_output: Result<&serde_json::value::RawValue, &str>,
+		_loop_location: Option<&Location>,
+	) -> WorkflowResult<()> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn commit_workflow_message_send_event(
+		&self,
+		_from_workflow_id: Id,
+		_location: &Location,
+		_version: usize,
+		_tags: &serde_json::Value,
+		_message_name: &str,
+		_body: &serde_json::value::RawValue,
+		_loop_location: Option<&Location>,
+	) -> WorkflowResult<()> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	// NOTE: Not an error so that the events of the last recorded loop iteration are validated. The next
+	// iteration ends the replay when it commits its branch event.
+	async fn upsert_workflow_loop_event(
+		&self,
+		_workflow_id: Id,
+		_workflow_name: &str,
+		_location: &Location,
+		_version: usize,
+		_iteration: usize,
+		_state: &serde_json::value::RawValue,
+		_output: Option<&serde_json::value::RawValue>,
+		_loop_location: Option<&Location>,
+	) -> WorkflowResult<()> {
+		Ok(())
+	}
+
+	async fn commit_workflow_sleep_event(
+		&self,
+		_from_workflow_id: Id,
+		_location: &Location,
+		_version: usize,
+		_deadline_ts: i64,
+		_loop_location: Option<&Location>,
+	) -> WorkflowResult<()> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn update_workflow_sleep_event_state(
+		&self,
+		_from_workflow_id: Id,
+		_location: &Location,
+		_state: SleepState,
+	) -> WorkflowResult<()> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn commit_workflow_branch_event(
+		&self,
+		_from_workflow_id: Id,
+		_location: &Location,
+		_version: usize,
+		_loop_location: Option<&Location>,
+	) -> WorkflowResult<()> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn commit_workflow_removed_event(
+		&self,
+		_from_workflow_id: Id,
+		_location: &Location,
+		_event_type: EventType,
+		_event_name: Option<&str>,
+		_loop_location: Option<&Location>,
+	) -> WorkflowResult<()> {
+		Err(WorkflowError::ReplayEnded)
+	}
+
+	async fn commit_workflow_version_check_event(
+		&self,
+		_from_workflow_id: Id,
+		_location: &Location,
+		_version: usize,
+		_loop_location: Option<&Location>,
+	) -> WorkflowResult<()> {
+		Err(WorkflowError::ReplayEnded)
+	}
+}
@@ -0,0 +1,26 @@
+//! Cron schedules that dispatch workflows. Schedules are stored in the database and each tick is claimed by a
+//! single worker, so a workflow is dispatched exactly once per tick across the cluster. Missed ticks (e.g.
+//! while no workers were running) are collapsed into a single dispatch.
+
+use chrono::{TimeZone, Utc};
+
+use crate::error::{WorkflowError, WorkflowResult};
+
+/// Returns the timestamp of the first tick of the given cron expression after `after_ts`. Expressions use the
+/// standard 5 field syntax with an optional leading seconds field and are evaluated in UTC.
+pub fn next_tick_ts(cron: &str, after_ts: i64) -> WorkflowResult<i64> {
+	let cron = croner::Cron::new(cron)
+		.with_seconds_optional()
+		.parse()
+		.map_err(|err| WorkflowError::InvalidCron(err.to_string()))?;
+	let after = Utc
+		.timestamp_millis_opt(after_ts)
+		.single()
+		.ok_or_else(|| WorkflowError::InvalidCron("timestamp out of range".to_string()))?;
+
+	let next = cron
+		.find_next_occurrence(&after, false)
+		.map_err(|err| WorkflowError::InvalidCron(err.to_string()))?;
+
+	Ok(next.timestamp_millis())
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use opentelemetry::{
+	Context,
+	propagation::TextMapPropagator,
+	trace::{SpanContext, TraceContextExt},
+};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Serializes the OpenTelemetry context of the current span as W3C trace context headers (`traceparent` and
+/// `tracestate`). This is persisted with workflows, signals, and messages so that the spans which later
+/// process them can link back to the span (and request) that created them.
+pub fn current_trace_context() -> Option<String> {
+	serialize_context(&tracing::Span::current().context())
+}
+
+fn serialize_context(cx: &Context) -> Option<String> {
+	if !cx.span().span_context().is_valid() {
+		return None;
+	}
+
+	let mut carrier = HashMap::new();
+	TraceContextPropagator::new().inject_context(cx, &mut carrier);
+
+	serde_json::to_string(&carrier).ok()
+}
+
+/// Parses a trace context written by `current_trace_context`.
+pub fn parse_trace_context(trace_context: &str) -> Option<SpanContext> {
+	let carrier = serde_json::from_str::<HashMap<String, String>>(trace_context).ok()?;
+	let cx = TraceContextPropagator::new().extract(&carrier);
+	let span_ctx = cx.span().span_context().clone();
+
+	span_ctx.is_valid().then_some(span_ctx)
+}
+
+/// Links the given span to the span a persisted trace context was captured in. Invalid or missing trace
+/// contexts are ignored.
+pub fn link(span: &tracing::Span, trace_context: Option<&str>) {
+	if let Some(span_ctx) = trace_context.and_then(parse_trace_context) {
+		span.add_link(span_ctx);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use opentelemetry::trace::{SpanId, TraceFlags, TraceId, TraceState};
+
+	use super::*;
+
+	fn span_ctx(trace_state: TraceState) -> SpanContext {
+		SpanContext::new(
+			TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+			SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+			TraceFlags::SAMPLED,
+			false,
+			trace_state,
+		)
+	}
+
+	#[test]
+	fn roundtrip() {
+		let trace_state =
+			TraceState::from_key_value([("rivet", "abc"), ("vendor", "xyz")]).unwrap();
+		let original = span_ctx(trace_state);
+		let cx = Context::new().with_remote_span_context(original.clone());
+
+		let trace_context = serialize_context(&cx).unwrap();
+		let parsed = parse_trace_context(&trace_context).unwrap();
+
+		assert_eq!(original.trace_id(), parsed.trace_id());
+		assert_eq!(original.span_id(), parsed.span_id());
+		assert_eq!(original.trace_flags(), parsed.trace_flags());
+		assert_eq!(
+			original.trace_state().header(),
+			parsed.trace_state().header()
+		);
+	}
+
+	#[test]
+	fn roundtrip_without_trace_state() {
+		let original = span_ctx(TraceState::default());
+		let cx = Context::new().with_remote_span_context(original.clone());
+
+		let trace_context = serialize_context(&cx).unwrap();
+		let carrier = serde_json::from_str::<HashMap<String, String>>(&trace_context).unwrap();
+		assert_eq!(
+			carrier.get("traceparent").map(String::as_str),
+			Some("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01")
+		);
+
+		let parsed = parse_trace_context(&trace_context).unwrap();
+		assert_eq!(original.trace_id(), parsed.trace_id());
+		assert!(parsed.trace_state().header().is_empty());
+	}
+
+	#[test]
+	fn invalid_context_is_not_serialized() {
+		assert!(serialize_context(&Context::new()).is_none());
+	}
+
+	#[test]
+	fn invalid_trace_context_is_ignored() {
+		assert!(parse_trace_context("").is_none());
+		assert!(
+			parse_trace_context("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01")
+				.is_none()
+		);
+		assert!(parse_trace_context(r#"{"traceparent":"00-zz-00f067aa0ba902b7-01"}"#).is_none());
+		assert!(
+			parse_trace_context(
+				r#"{"traceparent":"00-00000000000000000000000000000000-00f067aa0ba902b7-01"}"#
+			)
+			.is_none()
+		);
+	}
+}
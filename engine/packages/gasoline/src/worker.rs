@@ -3,7 +3,7 @@ use std::{
 	time::{Duration, Instant},
 };
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use futures_util::StreamExt;
 use opentelemetry::trace::TraceContextExt;
 use rivet_util::{Id, signal::TermSignal};
@@ -11,22 +11,42 @@ use tokio::{signal::ctrl_c, sync::watch, task::JoinHandle};
 use tracing::Instrument;
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
-use crate::{ctx::WorkflowCtx, db::DatabaseHandle, error::WorkflowError, registry::RegistryHandle};
+use crate::{
+	ctx::WorkflowCtx,
+	db::{DatabaseHandle, PullFilter},
+	error::WorkflowError,
+	registry::RegistryHandle,
+};
 
 /// How often to run gc and update ping.
 const PING_INTERVAL: Duration = Duration::from_secs(20);
 /// How often to publish metrics.
 const METRICS_INTERVAL: Duration = Duration::from_secs(20);
+/// How often to delete workflows that have been stopped for longer than their retention.
+const RETENTION_GC_INTERVAL: Duration = Duration::from_secs(60);
+/// How often to check for due schedules. Also the smallest effective schedule period.
+const SCHEDULE_INTERVAL: Duration = Duration::from_secs(1);
 /// Time to allow running workflows to shutdown after receiving a SIGINT or SIGTERM.
 const SHUTDOWN_DURATION: Duration = Duration::from_secs(30);
 // How long the pull workflows function can take before shutting down the runtime.
 const PULL_WORKFLOWS_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Used to spawn a new thread that indefinitely polls the database for new workflows. Only pulls workflows
-/// that are registered in its registry. After pulling, the workflows are ran and their state is written to
-/// the database.
+/// that are registered in its registry and belong to its worker pool. After pulling, the workflows are ran
+/// and their state is written to the database.
 pub struct Worker {
 	worker_instance_id: Id,
+	/// Name of the worker pool from the config. `None` for the default pool, which pulls all registered
+	/// workflows that are not assigned to a pool.
+	pool_name: Option<String>,
+	/// Workflow names to pull, sorted by priority.
+	workflow_names: Vec<String>,
+	max_concurrent_workflows: Option<usize>,
+	/// Whether this worker runs the background tasks that are not specific to its pool (clearing expired
+	/// leases, publishing metrics, and dispatching schedules). Only one worker per process needs to run these.
+	shared_tasks: bool,
+	/// Whether to shut down the runtime once this worker has shut down.
+	shutdown_runtime: bool,
 
 	registry: RegistryHandle,
 	db: DatabaseHandle,
@@ -44,8 +64,20 @@ impl Worker {
 		config: rivet_config::Config,
 		pools: rivet_pools::Pools,
 	) -> Self {
+		let workflow_names = registry
+			.workflows
+			.keys()
+			.filter(|workflow_name| config.workflows().worker_pool(workflow_name).is_none())
+			.cloned()
+			.collect::<Vec<_>>();
+
 		Worker {
 			worker_instance_id: Id::new_v1(config.dc_label()),
+			pool_name: None,
+			workflow_names: sort_by_priority(&config, workflow_names),
+			max_concurrent_workflows: config.workflows().max_concurrent_workflows,
+			shared_tasks: true,
+			shutdown_runtime: true,
 
 			registry,
 			db,
@@ -57,11 +89,52 @@ impl Worker {
 		}
 	}
 
+	/// Only pull the workflows of the given worker pool from the config.
+	pub fn with_pool(mut self, pool_name: &str) -> Result<Self> {
+		let Some(pool) = self.config.workflows().worker_pools.get(pool_name) else {
+			bail!("worker pool {pool_name:?} not found in config");
+		};
+
+		let mut workflow_names = Vec::with_capacity(pool.workflows.len());
+		for workflow_name in &pool.workflows {
+			if self.registry.workflows.contains_key(workflow_name) {
+				workflow_names.push(workflow_name.clone());
+			} else {
+				tracing::warn!(%pool_name, %workflow_name, "worker pool workflow not registered, ignoring");
+			}
+		}
+
+		self.pool_name = Some(pool_name.to_string());
+		self.workflow_names = sort_by_priority(&self.config, workflow_names);
+		self.max_concurrent_workflows = pool
+			.max_concurrent_workflows
+			.or(self.config.workflows().max_concurrent_workflows);
+
+		Ok(self)
+	}
+
+	/// Do not run the background tasks that are not specific to this worker's pool. Used when another worker
+	/// in the same process already runs them.
+	pub fn without_shared_tasks(mut self) -> Self {
+		self.shared_tasks = false;
+
+		self
+	}
+
+	/// Do not shut down the runtime after this worker shuts down. Used when the caller runs multiple workers
+	/// and shuts down the runtime itself once all of them have shut down.
+	pub fn without_runtime_shutdown(mut self) -> Self {
+		self.shutdown_runtime = false;
+
+		self
+	}
+
 	/// Polls the database periodically or wakes immediately when `Database::wake` finishes
-	#[tracing::instrument(skip_all, fields(worker_instance_id=%self.worker_instance_id))]
+	#[tracing::instrument(skip_all, fields(worker_instance_id=%self.worker_instance_id, pool_name=?self.pool_name))]
 	pub async fn start(mut self, mut shutdown_rx: Option<watch::Receiver<()>>) -> Result<()> {
 		tracing::debug!(
 			registered_workflows = ?self.registry.size(),
+			pulled_workflows = ?self.workflow_names.len(),
 			"started worker instance",
 		);
 
@@ -77,6 +150,8 @@ impl Worker {
 
 		let mut gc_handle = self.gc();
 		let mut metrics_handle = self.publish_metrics();
+		let mut retention_gc_handle = self.retention_gc();
+		let mut schedule_handle = self.dispatch_schedules();
 
 		let res = loop {
 			let shutdown_fut = async {
@@ -105,6 +180,14 @@ impl Worker {
 					tracing::error!(?res, "metrics task unexpectedly stopped");
 					break Ok(());
 				},
+				res = &mut retention_gc_handle => {
+					tracing::error!(?res, "retention gc task unexpectedly stopped");
+					break Ok(());
+				},
+				res = &mut schedule_handle => {
+					tracing::error!(?res, "schedule task unexpectedly stopped");
+					break Ok(());
+				},
 				res = shutdown_fut => {
 					if res.is_err() {
 						tracing::debug!("shutdown channel dropped, ignoring");
@@ -121,6 +204,8 @@ impl Worker {
 				// Cancel background tasks
 				gc_handle.abort();
 				metrics_handle.abort();
+				retention_gc_handle.abort();
+				schedule_handle.abort();
 
 				break Err(err);
 			}
@@ -129,6 +214,8 @@ impl Worker {
 		// Cancel background tasks
 		gc_handle.abort();
 		metrics_handle.abort();
+		retention_gc_handle.abort();
+		schedule_handle.abort();
 
 		if let Err(err) = &res {
 			tracing::error!(?err, "worker errored, attempting graceful shutdown");
@@ -206,24 +293,71 @@ impl Worker {
 
 		tracing::info!("shutdown complete");
 
-		rivet_runtime::shutdown().await;
+		if self.shutdown_runtime {
+			rivet_runtime::shutdown().await;
+		}
 	}
 
 	/// Query the database for new workflows and run them.
 	#[tracing::instrument(skip_all)]
 	async fn tick(&mut self, cache: &rivet_cache::Cache) -> Result<()> {
-		// Create filter from registered workflow names
+		// Count running workflows. Finished workflows are removed after pulling (see below)
+		let mut running_by_name = HashMap::<&str, usize>::new();
+		let mut running = 0;
+		for wf in self.running_workflows.values() {
+			if !wf.handle.is_finished() {
+				*running_by_name
+					.entry(wf.workflow_name.as_str())
+					.or_default() += 1;
+				running += 1;
+			}
+		}
+
+		let max = self
+			.max_concurrent_workflows
+			.map(|max| max.saturating_sub(running));
+		if max == Some(0) {
+			return Ok(());
+		}
+
+		// Create filter from pulled workflow names, skipping names that are at their concurrency limit
 		let filter = self
-			.registry
-			.workflows
-			.keys()
-			.map(|k| k.as_str())
+			.workflow_names
+			.iter()
+			.filter_map(|workflow_name| {
+				let limit = self
+					.config
+					.workflows()
+					.max_concurrent_workflows(workflow_name)
+					.map(|max| {
+						max.saturating_sub(
+							running_by_name
+								.get(workflow_name.as_str())
+								.copied()
+								.unwrap_or_default(),
+						)
+					});
+
+				if limit == Some(0) {
+					None
+				} else {
+					Some(PullFilter {
+						workflow_name,
+						limit,
+					})
+				}
+			})
 			.collect::<Vec<_>>();
 
+		if filter.is_empty() {
+			return Ok(());
+		}
+
 		// Query awake workflows
 		let workflows = tokio::time::timeout(
 			PULL_WORKFLOWS_TIMEOUT,
-			self.db.pull_workflows(self.worker_instance_id, &filter),
+			self.db
+				.pull_workflows(self.worker_instance_id, &filter, max),
 		)
 		.await
 		.context("took too long pulling workflows, worker cannot continue")??;
@@ -235,6 +369,7 @@ impl Worker {
 
 		for workflow in workflows {
 			let workflow_id = workflow.workflow_id;
+			let workflow_name = workflow.workflow_name.clone();
 
 			if self.running_workflows.contains_key(&workflow_id) {
 				tracing::error!(?workflow_id, "workflow already running");
@@ -284,6 +419,7 @@ impl Worker {
 			self.running_workflows.insert(
 				workflow_id,
 				WorkflowHandle {
+					workflow_name,
 					stop: stop_tx,
 					handle,
 				},
@@ -296,6 +432,7 @@ impl Worker {
 	fn gc(&self) -> JoinHandle<()> {
 		let db = self.db.clone();
 		let worker_instance_id = self.worker_instance_id;
+		let shared_tasks = self.shared_tasks;
 
 		tokio::task::spawn(
 			async move {
@@ -309,6 +446,10 @@ impl Worker {
 						tracing::error!(?err, "unhandled update ping error");
 					}
 
+					if !shared_tasks {
+						continue;
+					}
+
 					if let Err(err) = db.clear_expired_leases(worker_instance_id).await {
 						tracing::error!(?err, "unhandled gc error");
 					}
@@ -321,9 +462,14 @@ impl Worker {
 	fn publish_metrics(&self) -> JoinHandle<()> {
 		let db = self.db.clone();
 		let worker_instance_id = self.worker_instance_id;
+		let shared_tasks = self.shared_tasks;
 
 		tokio::task::spawn(
 			async move {
+				if !shared_tasks {
+					return std::future::pending().await;
+				}
+
 				let mut metrics_interval = tokio::time::interval(METRICS_INTERVAL);
 				metrics_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
@@ -338,9 +484,89 @@ impl Worker {
 			.instrument(tracing::info_span!("worker_metrics_task")),
 		)
 	}
+
+	fn retention_gc(&self) -> JoinHandle<()> {
+		let db = self.db.clone();
+		let config = self.config.clone();
+		let workflow_names = self.workflow_names.clone();
+
+		tokio::task::spawn(
+			async move {
+				let mut gc_interval = tokio::time::interval(RETENTION_GC_INTERVAL);
+				gc_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+				loop {
+					gc_interval.tick().await;
+
+					for workflow_name in &workflow_names {
+						let Some(retention_ms) = config.workflows().retention_ms(workflow_name)
+						else {
+							continue;
+						};
+
+						let stopped_before_ts = rivet_util::timestamp::now() - retention_ms;
+
+						match db
+							.gc_stopped_workflows(workflow_name, stopped_before_ts)
+							.await
+						{
+							Ok(None | Some((0, _))) => {}
+							Ok(Some((workflows, keys))) => {
+								tracing::debug!(%workflow_name, %workflows, %keys, "deleted stopped workflows");
+							}
+							Err(err) => {
+								tracing::error!(?err, %workflow_name, "unhandled retention gc error");
+							}
+						}
+					}
+				}
+			}
+			.instrument(tracing::info_span!("worker_retention_gc_task")),
+		)
+	}
+
+	fn dispatch_schedules(&self) -> JoinHandle<()> {
+		let db = self.db.clone();
+		let worker_instance_id = self.worker_instance_id;
+		let shared_tasks = self.shared_tasks;
+
+		tokio::task::spawn(
+			async move {
+				if !shared_tasks {
+					return std::future::pending().await;
+				}
+
+				let mut schedule_interval = tokio::time::interval(SCHEDULE_INTERVAL);
+				schedule_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+				loop {
+					schedule_interval.tick().await;
+
+					if let Err(err) = db.dispatch_due_schedules(worker_instance_id).await {
+						tracing::error!(?err, "unhandled schedule dispatch error");
+					}
+				}
+			}
+			.instrument(tracing::info_span!("worker_schedule_task")),
+		)
+	}
 }
 
 struct WorkflowHandle {
+	workflow_name: String,
 	stop: watch::Sender<()>,
 	handle: JoinHandle<()>,
 }
+
+/// Sorts workflow names by their configured priority, highest first.
+fn sort_by_priority(config: &rivet_config::Config, mut workflow_names: Vec<String>) -> Vec<String> {
+	workflow_names.sort_by(|a, b| {
+		config
+			.workflows()
+			.priority(b)
+			.cmp(&config.workflows().priority(a))
+			.then_with(|| a.cmp(b))
+	});
+
+	workflow_names
+}
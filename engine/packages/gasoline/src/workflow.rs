@@ -5,7 +5,7 @@ use async_trait::async_trait;
 use serde::{Serialize, de::DeserializeOwned};
 use tokio::sync::MutexGuard;
 
-use crate::ctx::WorkflowCtx;
+use crate::{ctx::WorkflowCtx, migration::Migrations};
 
 #[async_trait]
 pub trait Workflow {
@@ -14,6 +14,11 @@ pub trait Workflow {
 
 	const NAME: &'static str;
 
+	/// Upgrade steps for input and state written by older versions of this workflow.
+	fn migrations() -> Migrations {
+		Migrations::default()
+	}
+
 	async fn run(ctx: &mut WorkflowCtx, input: &Self::Input) -> Result<Self::Output>;
 }
 
@@ -6,9 +6,13 @@ use gasoline as gas;
 mod workflows;
 use workflows::activity_test::*;
 use workflows::basic::*;
+use workflows::cancel_test::*;
 use workflows::eviction_test::*;
+use workflows::gc_test::*;
 use workflows::listen_timeout::*;
 use workflows::loop_test::*;
+use workflows::migration_test::*;
+use workflows::replay_test::*;
 use workflows::signal_test::*;
 use workflows::sleep_test::*;
 use workflows::sub_test::*;
@@ -152,6 +156,311 @@ async fn test_workflow_signal() {
 	assert_eq!(res, "signal_value");
 }
 
+#[tokio::test]
+async fn test_workflow_signal_idempotency() {
+	let mut reg = Registry::new();
+	reg.register_workflow::<SignalTestWorkflow>().unwrap();
+	let test_ctx = TestCtx::new(reg).await.unwrap();
+
+	let workflow_id = test_ctx
+		.workflow(SignalTestInput {})
+		.dispatch()
+		.await
+		.unwrap();
+
+	let signal_id = test_ctx
+		.signal(TestSignal {
+			value: "first".to_string(),
+		})
+		.to_workflow_id(workflow_id)
+		.idempotency_key("key")
+		.send()
+		.await
+		.unwrap();
+
+	// Same key returns the original signal
+	let duplicate_signal_id = test_ctx
+		.signal(TestSignal {
+			value: "second".to_string(),
+		})
+		.to_workflow_id(workflow_id)
+		.idempotency_key("key")
+		.send()
+		.await
+		.unwrap();
+	assert_eq!(signal_id, duplicate_signal_id);
+
+	// Different key publishes a new signal
+	let other_signal_id = test_ctx
+		.signal(TestSignal {
+			value: "third".to_string(),
+		})
+		.to_workflow_id(workflow_id)
+		.idempotency_key("other_key")
+		.send()
+		.await
+		.unwrap();
+	assert_ne!(signal_id, other_signal_id);
+
+	let res = tokio::time::timeout(
+		Duration::from_secs(5),
+		test_ctx.workflow::<SignalTestInput>(workflow_id).output(),
+	)
+	.await
+	.unwrap()
+	.unwrap();
+	assert_eq!(res, "first");
+}
+
+#[tokio::test]
+async fn test_workflow_cancel_handled() {
+	let mut reg = Registry::new();
+	reg.register_workflow::<CancelTestWorkflow>().unwrap();
+	let test_ctx = TestCtx::new(reg).await.unwrap();
+
+	let workflow_id = test_ctx
+		.workflow(CancelTestInput {
+			handle_cancel: true,
+		})
+		.dispatch()
+		.await
+		.unwrap();
+
+	// Cancel the workflow while it is listening
+	test_ctx
+		.cancel(workflow_id)
+		.reason("test")
+		.send()
+		.await
+		.unwrap();
+
+	// Wait for workflow to complete with timeout
+	let res = tokio::time::timeout(
+		Duration::from_secs(5),
+		test_ctx.workflow::<CancelTestInput>(workflow_id).output(),
+	)
+	.await
+	.unwrap()
+	.unwrap();
+	assert_eq!(res, "Cleaned up: test");
+}
+
+#[tokio::test]
+async fn test_workflow_cancel_unhandled() {
+	let mut reg = Registry::new();
+	reg.register_workflow::<CancelTestWorkflow>().unwrap();
+	let test_ctx = TestCtx::new(reg).await.unwrap();
+
+	let workflow_id = test_ctx
+		.workflow(CancelTestInput {
+			handle_cancel: false,
+		})
+		.dispatch()
+		.await
+		.unwrap();
+
+	let cancelled_workflow_ids = test_ctx
+		.cancel(workflow_id)
+		.reason("test")
+		.send()
+		.await
+		.unwrap();
+	assert_eq!(cancelled_workflow_ids, vec![workflow_id]);
+
+	// Waiting on a cancelled workflow errors instead of hanging
+	let res = tokio::time::timeout(
+		Duration::from_secs(5),
+		test_ctx.workflow::<CancelTestInput>(workflow_id).output(),
+	)
+	.await
+	.unwrap();
+	assert!(res.is_err());
+
+	let res = gas::db::debug::DatabaseDebug::get_workflows(test_ctx.debug_db(), vec![workflow_id])
+		.await
+		.unwrap()
+		.into_iter()
+		.next()
+		.unwrap();
+	assert_eq!(res.state, gas::db::debug::WorkflowState::Cancelled);
+	assert_eq!(
+		res.termination.and_then(|termination| termination.reason),
+		Some("test".to_string())
+	);
+}
+
+#[tokio::test]
+async fn test_workflow_terminate() {
+	let mut reg = Registry::new();
+	reg.register_workflow::<CancelTestWorkflow>().unwrap();
+	let test_ctx = TestCtx::new(reg).await.unwrap();
+
+	let workflow_id = test_ctx
+		.workflow(CancelTestInput {
+			handle_cancel: true,
+		})
+		.dispatch()
+		.await
+		.unwrap();
+
+	// Wait for the workflow to go to sleep
+	loop {
+		let res =
+			gas::db::debug::DatabaseDebug::get_workflows(test_ctx.debug_db(), vec![workflow_id])
+				.await
+				.unwrap()
+				.into_iter()
+				.next()
+				.unwrap();
+
+		if res.state == gas::db::debug::WorkflowState::Sleeping {
+			break;
+		}
+
+		tokio::time::sleep(Duration::from_millis(50)).await;
+	}
+
+	gas::db::debug::DatabaseDebug::terminate_workflows(
+		test_ctx.debug_db(),
+		vec![workflow_id],
+		Some("test"),
+	)
+	.await
+	.unwrap();
+
+	let res = gas::db::debug::DatabaseDebug::get_workflows(test_ctx.debug_db(), vec![workflow_id])
+		.await
+		.unwrap()
+		.into_iter()
+		.next()
+		.unwrap();
+	assert_eq!(res.state, gas::db::debug::WorkflowState::Terminated);
+	assert!(res.termination.unwrap().forced);
+}
+
+#[tokio::test]
+async fn test_workflow_terminate_running() {
+	let mut reg = Registry::new();
+	reg.register_workflow::<TerminateTestWorkflow>().unwrap();
+	let test_ctx = TestCtx::new(reg).await.unwrap();
+
+	let workflow_id = test_ctx
+		.workflow(TerminateTestInput {})
+		.dispatch()
+		.await
+		.unwrap();
+
+	// Wait for the worker to pick up the workflow
+	loop {
+		let res =
+			gas::db::debug::DatabaseDebug::get_workflows(test_ctx.debug_db(), vec![workflow_id])
+				.await
+				.unwrap()
+				.into_iter()
+				.next()
+				.unwrap();
+
+		if res.state == gas::db::debug::WorkflowState::Running {
+			break;
+		}
+
+		tokio::time::sleep(Duration::from_millis(50)).await;
+	}
+
+	gas::db::debug::DatabaseDebug::terminate_workflows(
+		test_ctx.debug_db(),
+		vec![workflow_id],
+		Some("test"),
+	)
+	.await
+	.unwrap();
+
+	// The output of the worker that was running the workflow is discarded once its activity finishes
+	tokio::time::sleep(Duration::from_secs(3)).await;
+
+	let res = gas::db::debug::DatabaseDebug::get_workflows(test_ctx.debug_db(), vec![workflow_id])
+		.await
+		.unwrap()
+		.into_iter()
+		.next()
+		.unwrap();
+	assert_eq!(res.state, gas::db::debug::WorkflowState::Terminated);
+	assert!(res.output.is_none());
+	assert!(res.termination.unwrap().forced);
+}
+
+#[tokio::test]
+async fn test_workflow_cancel_propagate() {
+	let mut reg = Registry::new();
+	reg.register_workflow::<CancelParentTestWorkflow>().unwrap();
+	reg.register_workflow::<CancelTestWorkflow>().unwrap();
+	let test_ctx = TestCtx::new(reg).await.unwrap();
+
+	let workflow_id = test_ctx
+		.workflow(CancelParentTestInput {})
+		.dispatch()
+		.await
+		.unwrap();
+
+	// Wait for the parent to dispatch the sub workflow and go to sleep
+	loop {
+		let res =
+			gas::db::debug::DatabaseDebug::get_workflows(test_ctx.debug_db(), vec![workflow_id])
+				.await
+				.unwrap()
+				.into_iter()
+				.next()
+				.unwrap();
+
+		if res.state == gas::db::debug::WorkflowState::Sleeping {
+			break;
+		}
+
+		tokio::time::sleep(Duration::from_millis(50)).await;
+	}
+
+	let cancelled_workflow_ids = test_ctx
+		.cancel(workflow_id)
+		.reason("test")
+		.propagate()
+		.send()
+		.await
+		.unwrap();
+	assert_eq!(cancelled_workflow_ids.len(), 2);
+	assert_eq!(cancelled_workflow_ids[0], workflow_id);
+	let sub_workflow_id = cancelled_workflow_ids[1];
+
+	// Both workflows handle the cancellation
+	for workflow_id in [workflow_id, sub_workflow_id] {
+		let mut state = None;
+
+		for _ in 0..100 {
+			let res = gas::db::debug::DatabaseDebug::get_workflows(
+				test_ctx.debug_db(),
+				vec![workflow_id],
+			)
+			.await
+			.unwrap()
+			.into_iter()
+			.next()
+			.unwrap();
+
+			if res.state == gas::db::debug::WorkflowState::Cancelled {
+				assert_eq!(
+					res.termination.and_then(|termination| termination.reason),
+					Some("test".to_string())
+				);
+				state = Some(res.state);
+				break;
+			}
+
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+
+		assert_eq!(state, Some(gas::db::debug::WorkflowState::Cancelled));
+	}
+}
+
 #[tokio::test]
 async fn test_workflow_loop() {
 	let mut reg = Registry::new();
@@ -175,6 +484,200 @@ async fn test_workflow_loop() {
 	assert_eq!(res, 3);
 }
 
+#[tokio::test]
+async fn test_workflow_query() {
+	let mut reg = Registry::new();
+	reg.register_workflow::<LoopTestWorkflow>().unwrap();
+	reg.register_workflow::<BasicWorkflow>().unwrap();
+	let test_ctx = TestCtx::new(reg).await.unwrap();
+
+	let workflow_id = test_ctx
+		.workflow(LoopWorkflowInput { iterations: 3 })
+		.dispatch()
+		.await
+		.unwrap();
+
+	let res = tokio::time::timeout(
+		Duration::from_secs(5),
+		test_ctx.workflow::<LoopWorkflowInput>(workflow_id).output(),
+	)
+	.await
+	.unwrap()
+	.unwrap();
+	assert_eq!(res, 3);
+
+	// Query reads the persisted loop state and output
+	let progress = test_ctx
+		.query(workflow_id, LoopProgressInput {})
+		.await
+		.unwrap();
+	assert_eq!(
+		progress,
+		LoopProgress {
+			iteration: Some(3),
+			output: Some(3),
+		}
+	);
+
+	// Querying a workflow of a different type fails
+	let basic_workflow_id = test_ctx
+		.workflow(BasicWorkflowInput {
+			value: "test_value".to_string(),
+		})
+		.dispatch()
+		.await
+		.unwrap();
+	assert!(
+		test_ctx
+			.query(basic_workflow_id, LoopProgressInput {})
+			.await
+			.is_err()
+	);
+}
+
+#[tokio::test]
+async fn test_workflow_migration() {
+	let mut reg = Registry::new();
+	reg.register_workflow::<MigrationTestWorkflow>().unwrap();
+	let test_ctx = TestCtx::new(reg).await.unwrap();
+
+	// Dispatch a workflow with input from before the migration was added
+	let db = gas::db::DatabaseKv::from_pools(test_ctx.pools().clone())
+		.await
+		.unwrap();
+	let old_input = serde_json::value::to_raw_value(&serde_json::json!({
+		"region": "atl",
+	}))
+	.unwrap();
+	let workflow_id = db
+		.dispatch_workflow(
+			test_ctx.ray_id(),
+			Id::new_v1(test_ctx.config().dc_label()),
+			MigrationTestWorkflow::NAME,
+			None,
+			&old_input,
+			0,
+			false,
+		)
+		.await
+		.unwrap();
+
+	let res = tokio::time::timeout(
+		Duration::from_secs(5),
+		test_ctx
+			.workflow::<MigrationTestInput>(workflow_id)
+			.output(),
+	)
+	.await
+	.unwrap()
+	.unwrap();
+	assert_eq!(res, "atl");
+
+	// Migrated input is persisted with the new schema version
+	let workflow =
+		gas::db::debug::DatabaseDebug::get_workflows(test_ctx.debug_db(), vec![workflow_id])
+			.await
+			.unwrap()
+			.pop()
+			.unwrap();
+	assert_eq!(workflow.schema_version, 1);
+	assert_eq!(workflow.input, serde_json::json!({ "datacenter": "atl" }));
+
+	// New workflows are dispatched at the current schema version
+	let workflow_id = test_ctx
+		.workflow(MigrationTestInput {
+			datacenter: "sfo".to_string(),
+		})
+		.dispatch()
+		.await
+		.unwrap();
+
+	let res = tokio::time::timeout(
+		Duration::from_secs(5),
+		test_ctx
+			.workflow::<MigrationTestInput>(workflow_id)
+			.output(),
+	)
+	.await
+	.unwrap()
+	.unwrap();
+	assert_eq!(res, "sfo");
+}
+
+#[tokio::test]
+async fn test_workflow_migration_conflicts() {
+	// Not registered, only pulled manually
+	let test_ctx = TestCtx::new(Registry::new()).await.unwrap();
+	let db = test_ctx.db();
+	let worker_instance_id = Id::new_v1(test_ctx.config().dc_label());
+	db.update_worker_ping(worker_instance_id).await.unwrap();
+
+	let input = serde_json::value::to_raw_value(&serde_json::json!({
+		"datacenter": "atl",
+	}))
+	.unwrap();
+	let state = serde_json::value::RawValue::NULL.to_owned();
+	let workflow_id = db
+		.dispatch_workflow(
+			test_ctx.ray_id(),
+			Id::new_v1(test_ctx.config().dc_label()),
+			MigrationTestWorkflow::NAME,
+			None,
+			&input,
+			0,
+			false,
+		)
+		.await
+		.unwrap();
+
+	// Migrating from a stale schema version fails
+	let err = db
+		.migrate_workflow(workflow_id, 1, 2, &input, &state, false)
+		.await
+		.unwrap_err();
+	assert!(
+		matches!(
+			udb_error(&err),
+			Some(WorkflowError::SchemaVersionChanged(1, 0))
+		),
+		"{err:?}"
+	);
+
+	let pulled = db
+		.pull_workflows(
+			worker_instance_id,
+			&[gas::db::PullFilter {
+				workflow_name: MigrationTestWorkflow::NAME,
+				limit: None,
+			}],
+			None,
+		)
+		.await
+		.unwrap();
+	assert_eq!(pulled.len(), 1);
+
+	// Migrating a leased workflow fails unless done by the worker holding the lease
+	let err = db
+		.migrate_workflow(workflow_id, 0, 1, &input, &state, false)
+		.await
+		.unwrap_err();
+	assert!(
+		matches!(udb_error(&err), Some(WorkflowError::WorkflowLeased)),
+		"{err:?}"
+	);
+	db.migrate_workflow(workflow_id, 0, 1, &input, &state, true)
+		.await
+		.unwrap();
+}
+
+/// Returns the workflow error returned from within a database transaction.
+fn udb_error(err: &WorkflowError) -> Option<&WorkflowError> {
+	match err {
+		WorkflowError::Udb(err) => err.downcast_ref(),
+		_ => None,
+	}
+}
+
 #[tokio::test]
 async fn test_workflow_listen_with_timeout() {
 	let mut reg = Registry::new();
@@ -286,3 +789,353 @@ async fn test_workflow_eviction() {
 		sub.next().await.unwrap();
 	}
 }
+
+#[tokio::test]
+async fn test_workflow_schedule() {
+	let mut reg = Registry::new();
+	reg.register_workflow::<BasicWorkflow>().unwrap();
+	let test_ctx = TestCtx::new(reg).await.unwrap();
+
+	// Tick every second
+	test_ctx
+		.schedule(
+			"test_schedule",
+			"* * * * * *",
+			BasicWorkflowInput {
+				value: "scheduled".to_string(),
+			},
+		)
+		.register()
+		.await
+		.unwrap();
+
+	// Wait for the first dispatch
+	let workflow_id = tokio::time::timeout(Duration::from_secs(5), async {
+		loop {
+			let schedule = gas::db::debug::DatabaseDebug::get_schedules(
+				test_ctx.debug_db(),
+				vec!["test_schedule".to_string()],
+			)
+			.await
+			.unwrap()
+			.into_iter()
+			.next()
+			.unwrap();
+
+			if let Some(workflow_id) = schedule.last_workflow_id {
+				break workflow_id;
+			}
+
+			tokio::time::sleep(Duration::from_millis(250)).await;
+		}
+	})
+	.await
+	.unwrap();
+
+	let res = tokio::time::timeout(
+		Duration::from_secs(5),
+		test_ctx
+			.workflow::<BasicWorkflowInput>(workflow_id)
+			.output(),
+	)
+	.await
+	.unwrap()
+	.unwrap();
+	assert_eq!(res, "scheduled");
+
+	// Paused schedules have no next tick
+	gas::db::debug::DatabaseDebug::pause_schedules(
+		test_ctx.debug_db(),
+		vec!["test_schedule".to_string()],
+	)
+	.await
+	.unwrap();
+
+	let schedule = gas::db::debug::DatabaseDebug::get_schedules(
+		test_ctx.debug_db(),
+		vec!["test_schedule".to_string()],
+	)
+	.await
+	.unwrap()
+	.into_iter()
+	.next()
+	.unwrap();
+	assert!(schedule.next_ts.is_none());
+	assert!(schedule.pause_ts.is_some());
+}
+
+#[tokio::test]
+async fn test_workflow_replay() {
+	let mut reg = Registry::new();
+	reg.register_workflow::<ReplayTestWorkflow>().unwrap();
+	let test_ctx = TestCtx::new(reg).await.unwrap();
+
+	let workflow_id = test_ctx
+		.workflow(ReplayTestInput {
+			value: "replay".to_string(),
+		})
+		.dispatch()
+		.await
+		.unwrap();
+
+	let res = tokio::time::timeout(
+		Duration::from_secs(5),
+		test_ctx.workflow::<ReplayTestInput>(workflow_id).output(),
+	)
+	.await
+	.unwrap()
+	.unwrap();
+	assert_eq!(res, "replay");
+
+	// Unchanged workflow code replays its own history
+	let mut reg = Registry::new();
+	reg.register_workflow::<ReplayTestWorkflow>().unwrap();
+	let report = test_ctx.replay(reg, workflow_id).await.unwrap();
+	assert!(
+		matches!(report.outcome, gas::replay::ReplayOutcome::Ok),
+		"{:?}",
+		report.outcome
+	);
+
+	// Changed workflow code diverges at the first activity
+	let mut reg = Registry::new();
+	reg.register_workflow::<ReplayTestWorkflowChanged>()
+		.unwrap();
+	let report = test_ctx.replay(reg, workflow_id).await.unwrap();
+	assert!(
+		matches!(report.outcome, gas::replay::ReplayOutcome::Diverged(_)),
+		"{:?}",
+		report.outcome
+	);
+}
+
+#[tokio::test]
+async fn test_workflow_gc_keeps_referenced_sub_workflow() {
+	let mut reg = Registry::new();
+	reg.register_workflow::<SignalTestWorkflow>().unwrap();
+	reg.register_workflow::<GcParentWorkflow>().unwrap();
+	let test_ctx = TestCtx::new(reg).await.unwrap();
+
+	// Dispatched without a parent, then reused by the parent with a unique dispatch
+	let sub_workflow_id = test_ctx
+		.workflow(SignalTestInput {})
+		.tag("gc_test", "reused")
+		.dispatch()
+		.await
+		.unwrap();
+
+	let parent_workflow_id = test_ctx
+		.workflow(GcParentInput {
+			sub_workflow_tag: "reused".to_string(),
+		})
+		.dispatch()
+		.await
+		.unwrap();
+
+	// Give the parent time to dispatch the sub workflow and start listening
+	tokio::time::sleep(Duration::from_millis(500)).await;
+
+	test_ctx
+		.signal(TestSignal {
+			value: "sub".to_string(),
+		})
+		.to_workflow_id(sub_workflow_id)
+		.send()
+		.await
+		.unwrap();
+	tokio::time::timeout(
+		Duration::from_secs(5),
+		test_ctx
+			.workflow::<SignalTestInput>(sub_workflow_id)
+			.output(),
+	)
+	.await
+	.unwrap()
+	.unwrap();
+
+	tokio::time::sleep(Duration::from_millis(10)).await;
+
+	// Sub workflow is kept while the parent's history references it
+	let res = test_ctx
+		.db()
+		.gc_stopped_workflows(SignalTestWorkflow::NAME, util::timestamp::now())
+		.await
+		.unwrap();
+	assert_eq!(res.map(|(workflows, _)| workflows), Some(0));
+	assert_eq!(
+		test_ctx
+			.get_workflows(vec![sub_workflow_id])
+			.await
+			.unwrap()
+			.len(),
+		1
+	);
+
+	test_ctx
+		.signal(TestSignal {
+			value: "parent".to_string(),
+		})
+		.to_workflow_id(parent_workflow_id)
+		.send()
+		.await
+		.unwrap();
+	let res = tokio::time::timeout(
+		Duration::from_secs(5),
+		test_ctx
+			.workflow::<GcParentInput>(parent_workflow_id)
+			.output(),
+	)
+	.await
+	.unwrap()
+	.unwrap();
+	assert_eq!(res, sub_workflow_id, "parent should reuse the sub workflow");
+
+	tokio::time::sleep(Duration::from_millis(10)).await;
+
+	// Sub workflow is deleted once the parent has stopped
+	let res = test_ctx
+		.db()
+		.gc_stopped_workflows(SignalTestWorkflow::NAME, util::timestamp::now())
+		.await
+		.unwrap();
+	assert_eq!(res.map(|(workflows, _)| workflows), Some(1));
+	assert!(
+		test_ctx
+			.get_workflows(vec![sub_workflow_id])
+			.await
+			.unwrap()
+			.is_empty()
+	);
+}
+
+#[tokio::test]
+async fn test_workflow_gc_deletes_full_backlog() {
+	let mut reg = Registry::new();
+	reg.register_workflow::<BasicWorkflow>().unwrap();
+	let test_ctx = TestCtx::new(reg).await.unwrap();
+
+	// More than a single GC batch
+	let mut workflow_ids = Vec::new();
+	for i in 0..150 {
+		workflow_ids.push(
+			test_ctx
+				.workflow(BasicWorkflowInput {
+					value: i.to_string(),
+				})
+				.dispatch()
+				.await
+				.unwrap(),
+		);
+	}
+
+	for workflow_id in &workflow_ids {
+		tokio::time::timeout(
+			Duration::from_secs(10),
+			test_ctx
+				.workflow::<BasicWorkflowInput>(*workflow_id)
+				.output(),
+		)
+		.await
+		.unwrap()
+		.unwrap();
+	}
+
+	tokio::time::sleep(Duration::from_millis(10)).await;
+
+	// Concurrent runs do not delete the same workflows, the whole backlog is deleted by one of them
+	let stopped_before_ts = util::timestamp::now();
+	let (res1, res2) = tokio::join!(
+		test_ctx
+			.db()
+			.gc_stopped_workflows(BasicWorkflow::NAME, stopped_before_ts),
+		test_ctx
+			.db()
+			.gc_stopped_workflows(BasicWorkflow::NAME, stopped_before_ts),
+	);
+	let deleted = [res1.unwrap(), res2.unwrap()]
+		.into_iter()
+		.flatten()
+		.map(|(workflows, _)| workflows)
+		.sum::<usize>();
+	assert_eq!(deleted, workflow_ids.len());
+
+	assert!(
+		test_ctx
+			.get_workflows(workflow_ids)
+			.await
+			.unwrap()
+			.is_empty()
+	);
+}
+
+#[tokio::test]
+async fn test_workflow_pull_limits_skip_leased() {
+	// Not registered, only pulled manually
+	let test_ctx = TestCtx::new(Registry::new()).await.unwrap();
+	let db = test_ctx.db();
+	let filter = [gas::db::PullFilter {
+		workflow_name: BasicWorkflow::NAME,
+		limit: Some(1),
+	}];
+
+	let worker_instance_id1 = Id::new_v1(test_ctx.config().dc_label());
+	let worker_instance_id2 = Id::new_v1(test_ctx.config().dc_label());
+	db.update_worker_ping(worker_instance_id1).await.unwrap();
+	db.update_worker_ping(worker_instance_id2).await.unwrap();
+
+	let leased_workflow_id = test_ctx
+		.workflow(BasicWorkflowInput {
+			value: "leased".to_string(),
+		})
+		.dispatch()
+		.await
+		.unwrap();
+
+	// Give the leased workflow a deadline wake condition that is not pulled with its lease
+	let pulled = db
+		.pull_workflows(worker_instance_id1, &filter, None)
+		.await
+		.unwrap();
+	assert_eq!(pulled.len(), 1);
+	let deadline_ts = util::timestamp::now() + 6000;
+	db.commit_workflow(
+		leased_workflow_id,
+		BasicWorkflow::NAME,
+		false,
+		Some(deadline_ts),
+		&[],
+		None,
+		"",
+	)
+	.await
+	.unwrap();
+	gas::db::debug::DatabaseDebug::wake_workflows(test_ctx.debug_db(), vec![leased_workflow_id])
+		.await
+		.unwrap();
+	let pulled = db
+		.pull_workflows(worker_instance_id1, &filter, None)
+		.await
+		.unwrap();
+	assert_eq!(pulled.len(), 1);
+
+	// Wait for the deadline to pass while the workflow is still leased
+	tokio::time::sleep(Duration::from_millis(6500)).await;
+
+	let workflow_id = test_ctx
+		.workflow(BasicWorkflowInput {
+			value: "unleased".to_string(),
+		})
+		.dispatch()
+		.await
+		.unwrap();
+
+	// The leased workflow's deadline comes first but must not use up the limit
+	let pulled = db
+		.pull_workflows(worker_instance_id2, &filter, None)
+		.await
+		.unwrap();
+	assert_eq!(
+		pulled.iter().map(|x| x.workflow_id).collect::<Vec<_>>(),
+		vec![workflow_id]
+	);
+}
@@ -0,0 +1,84 @@
+use gas::prelude::*;
+use gasoline as gas;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CancelTestInput {
+	pub handle_cancel: bool,
+}
+
+#[workflow(CancelTestWorkflow)]
+pub async fn cancel_test_workflow(
+	ctx: &mut WorkflowCtx,
+	input: &CancelTestInput,
+) -> Result<String> {
+	match ctx.listen::<CancelTestSignal>().await {
+		Ok(signal) => Ok(signal.value),
+		Err(err) if input.handle_cancel => {
+			let Some(cancel) = gas::cancel::cancellation(&err) else {
+				return Err(err);
+			};
+			let reason = cancel.reason.clone().unwrap_or_default();
+
+			// Workflow steps can still be run after a cancellation is received
+			ctx.activity(CleanupActivityInput { reason }).await
+		}
+		Err(err) => Err(err),
+	}
+}
+
+#[signal("cancel_test_signal")]
+#[derive(Debug)]
+pub struct CancelTestSignal {
+	pub value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Hash)]
+pub struct CleanupActivityInput {
+	pub reason: String,
+}
+
+#[activity(CleanupActivity)]
+pub async fn cleanup_activity(ctx: &ActivityCtx, input: &CleanupActivityInput) -> Result<String> {
+	Ok(format!("Cleaned up: {}", input.reason))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CancelParentTestInput {}
+
+#[workflow(CancelParentTestWorkflow)]
+pub async fn cancel_parent_test_workflow(
+	ctx: &mut WorkflowCtx,
+	_input: &CancelParentTestInput,
+) -> Result<String> {
+	ctx.workflow(CancelTestInput {
+		handle_cancel: false,
+	})
+	.dispatch()
+	.await?;
+
+	let signal = ctx.listen::<CancelTestSignal>().await?;
+
+	Ok(signal.value)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TerminateTestInput {}
+
+#[workflow(TerminateTestWorkflow)]
+pub async fn terminate_test_workflow(
+	ctx: &mut WorkflowCtx,
+	_input: &TerminateTestInput,
+) -> Result<String> {
+	ctx.activity(SlowActivityInput {}).await
+}
+
+#[derive(Debug, Serialize, Deserialize, Hash)]
+pub struct SlowActivityInput {}
+
+/// Keeps the workflow running long enough to be terminated.
+#[activity(SlowActivity)]
+pub async fn slow_activity(_ctx: &ActivityCtx, _input: &SlowActivityInput) -> Result<String> {
+	tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+
+	Ok("done".to_string())
+}
@@ -0,0 +1,24 @@
+use super::signal_test::{SignalTestInput, TestSignal};
+use gas::prelude::*;
+use gasoline as gas;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GcParentInput {
+	pub sub_workflow_tag: String,
+}
+
+#[workflow(GcParentWorkflow)]
+pub async fn gc_parent_workflow(ctx: &mut WorkflowCtx, input: &GcParentInput) -> Result<Id> {
+	// Reuses a running sub workflow with the same tag
+	let sub_workflow_id = ctx
+		.workflow(SignalTestInput {})
+		.tag("gc_test", &input.sub_workflow_tag)
+		.unique()
+		.dispatch()
+		.await?;
+
+	// Keep running until signaled
+	ctx.listen::<TestSignal>().await?;
+
+	Ok(sub_workflow_id)
+}
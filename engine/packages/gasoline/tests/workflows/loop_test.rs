@@ -27,3 +27,33 @@ pub async fn loop_test_workflow(ctx: &mut WorkflowCtx, input: &LoopWorkflowInput
 
 	Ok(iterations)
 }
+
+#[derive(Debug)]
+pub struct LoopProgressInput {}
+
+#[derive(Debug, PartialEq)]
+pub struct LoopProgress {
+	pub iteration: Option<usize>,
+	pub output: Option<usize>,
+}
+
+pub struct LoopProgressQuery;
+
+impl gas::query::QueryInput for LoopProgressInput {
+	type Query = LoopProgressQuery;
+}
+
+impl QueryTrait for LoopProgressQuery {
+	type Input = LoopProgressInput;
+	type Output = LoopProgress;
+	type Workflow = LoopTestWorkflow;
+
+	const NAME: &'static str = "loop_progress";
+
+	fn handle(ctx: &QueryCtx, _input: &Self::Input) -> Result<Self::Output> {
+		Ok(LoopProgress {
+			iteration: ctx.loop_state()?,
+			output: ctx.output()?,
+		})
+	}
+}
@@ -0,0 +1,30 @@
+use gas::prelude::*;
+use gasoline as gas;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MigrationTestInput {
+	pub datacenter: String,
+}
+
+#[workflow(MigrationTestWorkflow)]
+#[migrations = migrations]
+pub async fn migration_test_workflow(
+	_ctx: &mut WorkflowCtx,
+	input: &MigrationTestInput,
+) -> Result<String> {
+	Ok(input.datacenter.clone())
+}
+
+fn migrations() -> Migrations {
+	Migrations::default()
+		// v1: `region` was renamed to `datacenter`
+		.input(1, |mut input| {
+			if let Some(obj) = input.as_object_mut() {
+				if let Some(region) = obj.remove("region") {
+					obj.insert("datacenter".to_string(), region);
+				}
+			}
+
+			Ok(input)
+		})
+}
@@ -0,0 +1,65 @@
+use gas::prelude::*;
+use gasoline as gas;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplayTestInput {
+	pub value: String,
+}
+
+#[workflow(ReplayTestWorkflow)]
+pub async fn replay_test_workflow(
+	ctx: &mut WorkflowCtx,
+	input: &ReplayTestInput,
+) -> Result<String> {
+	let result = ctx
+		.activity(ReplayActivityInput {
+			value: input.value.clone(),
+		})
+		.await?;
+
+	Ok(result)
+}
+
+/// Same name as `ReplayTestWorkflow` but runs a different activity, like a change that breaks history.
+pub struct ReplayTestWorkflowChanged;
+
+#[async_trait::async_trait]
+impl gas::workflow::Workflow for ReplayTestWorkflowChanged {
+	type Input = ReplayTestInput;
+	type Output = String;
+
+	const NAME: &'static str = "replay_test_workflow";
+
+	async fn run(ctx: &mut WorkflowCtx, input: &Self::Input) -> Result<Self::Output> {
+		let result = ctx
+			.activity(ReplayOtherActivityInput {
+				value: input.value.clone(),
+			})
+			.await?;
+
+		Ok(result)
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, Hash)]
+pub struct ReplayActivityInput {
+	pub value: String,
+}
+
+#[activity(ReplayActivity)]
+pub async fn replay_activity(ctx: &ActivityCtx, input: &ReplayActivityInput) -> Result<String> {
+	Ok(input.value.clone())
+}
+
+#[derive(Debug, Serialize, Deserialize, Hash)]
+pub struct ReplayOtherActivityInput {
+	pub value: String,
+}
+
+#[activity(ReplayOtherActivity)]
+pub async fn replay_other_activity(
+	ctx: &ActivityCtx,
+	input: &ReplayOtherActivityInput,
+) -> Result<String> {
+	Ok(input.value.clone())
+}
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use gas::prelude::*;
 use universaldb::prelude::*;
+use vbare::OwnedVersionedData;
 
 pub mod runner_config;
 
@@ -144,6 +145,50 @@ impl<'de> TupleUnpack<'de> for CreateTsKey {
 	}
 }
 
+#[derive(Debug)]
+pub struct KvQuotaKey {
+	namespace_id: Id,
+}
+
+impl KvQuotaKey {
+	pub fn new(namespace_id: Id) -> Self {
+		KvQuotaKey { namespace_id }
+	}
+}
+
+impl FormalKey for KvQuotaKey {
+	type Value = rivet_types::namespaces::KvQuota;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(rivet_data::versioned::NamespaceKvQuota::deserialize_with_embedded_version(raw)?.into())
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		rivet_data::versioned::NamespaceKvQuota::latest(value.into())
+			.serialize_with_embedded_version(rivet_data::NAMESPACE_KV_QUOTA_VERSION)
+	}
+}
+
+impl TuplePack for KvQuotaKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (DATA, self.namespace_id, KV_QUOTA);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for KvQuotaKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, namespace_id, _)) = <(usize, Id, usize)>::unpack(input, tuple_depth)?;
+		let v = KvQuotaKey { namespace_id };
+
+		Ok((input, v))
+	}
+}
+
 #[derive(Debug)]
 pub struct ByNameKey {
 	name: String,
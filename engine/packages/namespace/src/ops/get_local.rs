@@ -45,11 +45,13 @@ pub(crate) async fn get_inner(
 	let name_key = keys::NameKey::new(namespace_id);
 	let display_name_key = keys::DisplayNameKey::new(namespace_id);
 	let create_ts_key = keys::CreateTsKey::new(namespace_id);
+	let kv_quota_key = keys::KvQuotaKey::new(namespace_id);
 
-	let (name, display_name, create_ts) = tokio::try_join!(
+	let (name, display_name, create_ts, kv_quota) = tokio::try_join!(
 		tx.read_opt(&name_key, Serializable),
 		tx.read_opt(&display_name_key, Serializable),
 		tx.read_opt(&create_ts_key, Serializable),
+		tx.read_opt(&kv_quota_key, Serializable),
 	)?;
 
 	// Namespace not found
@@ -65,5 +67,7 @@ pub(crate) async fn get_inner(
 		name,
 		display_name,
 		create_ts,
+		// Namespaces without a configured quota use the defaults
+		kv_quota: kv_quota.unwrap_or_default(),
 	}))
 }
@@ -0,0 +1,79 @@
+use gas::prelude::*;
+use rivet_cache::{CacheKey, RawCacheKey};
+use rivet_types::namespaces::KvQuota;
+use universaldb::utils::IsolationLevel::*;
+
+use crate::{errors, keys};
+
+/// Upper bound for `max_put_payload_size`, keeps a single write well below the 10 MB transaction limit.
+const MAX_PUT_PAYLOAD_SIZE_LIMIT: u32 = 9 * 1024 * 1024;
+
+#[derive(Debug)]
+pub struct Input {
+	pub namespace_id: Id,
+	pub kv_quota: KvQuota,
+}
+
+#[operation]
+pub async fn namespace_kv_quota_upsert(ctx: &OperationCtx, input: &Input) -> Result<()> {
+	if !ctx.config().is_leader() {
+		return Err(errors::Namespace::NotLeader.build());
+	}
+
+	validate(&input.kv_quota).map_err(|err| err.build())?;
+
+	let name = ctx
+		.udb()?
+		.run(|tx| async move {
+			let tx = tx.with_subspace(keys::subspace());
+
+			let Some(name) = tx
+				.read_opt(&keys::NameKey::new(input.namespace_id), Serializable)
+				.await?
+			else {
+				return Ok(None);
+			};
+
+			tx.write(
+				&keys::KvQuotaKey::new(input.namespace_id),
+				input.kv_quota.clone(),
+			)?;
+
+			Ok(Some(name))
+		})
+		.custom_instrument(tracing::info_span!("kv_quota_upsert_tx"))
+		.await?
+		.ok_or_else(|| errors::Namespace::NotFound.build())?;
+
+	// Purge namespace caches in other datacenters so the new quota takes effect
+	ctx.op(internal::ops::cache::purge_global::Input {
+		base_key: "namespace.get_global".to_string(),
+		keys: vec![RawCacheKey::from(input.namespace_id.cache_key())],
+	})
+	.await?;
+	ctx.op(internal::ops::cache::purge_global::Input {
+		base_key: "namespace.resolve_for_name_global".to_string(),
+		keys: vec![RawCacheKey::from(name.cache_key())],
+	})
+	.await?;
+
+	Ok(())
+}
+
+fn validate(kv_quota: &KvQuota) -> std::result::Result<(), errors::Namespace> {
+	let reason = if kv_quota.max_storage_size == 0 {
+		"`max_storage_size` cannot be 0".to_string()
+	} else if kv_quota.max_keys == 0 {
+		"`max_keys` cannot be 0".to_string()
+	} else if kv_quota.max_value_size == 0 {
+		"`max_value_size` cannot be 0".to_string()
+	} else if kv_quota.max_value_size > kv_quota.max_put_payload_size {
+		"`max_value_size` cannot be greater than `max_put_payload_size`".to_string()
+	} else if kv_quota.max_put_payload_size > MAX_PUT_PAYLOAD_SIZE_LIMIT {
+		format!("`max_put_payload_size` cannot be greater than {MAX_PUT_PAYLOAD_SIZE_LIMIT}")
+	} else {
+		return Ok(());
+	};
+
+	Err(errors::Namespace::InvalidUpdate { reason })
+}
@@ -1,5 +1,6 @@
 pub mod get_global;
 pub mod get_local;
+pub mod kv_quota;
 pub mod list;
 pub mod resolve_for_name_global;
 pub mod resolve_for_name_local;
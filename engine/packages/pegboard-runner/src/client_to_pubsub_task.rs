@@ -11,7 +11,7 @@ use std::sync::{Arc, atomic::Ordering};
 use universalpubsub::PublishOpts;
 use vbare::OwnedVersionedData;
 
-use crate::conn::Conn;
+use crate::{conn::Conn, kv_watch};
 
 #[tracing::instrument(skip_all, fields(runner_id=?conn.runner_id, workflow_id=?conn.workflow_id, protocol_version=%conn.protocol_version))]
 pub async fn task(ctx: StandaloneCtx, conn: Arc<Conn>, mut ws_rx: WebSocketReceiver) -> Result<()> {
@@ -25,20 +25,17 @@ pub async fn task(ctx: StandaloneCtx, conn: Arc<Conn>, mut ws_rx: WebSocketRecei
 				);
 
 				// Parse message
-				let msg =
-					match versioned::ToServer::deserialize_version(&data, conn.protocol_version)
-						.and_then(|x| x.into_latest())
-					{
-						Ok(x) => x,
-						Err(err) => {
-							tracing::warn!(
-								?err,
-								data_len = data.len(),
-								"failed to deserialize message"
-							);
-							continue;
-						}
-					};
+				let msg = match versioned::ToServer::deserialize(&data, conn.protocol_version) {
+					Ok(x) => x,
+					Err(err) => {
+						tracing::warn!(
+							?err,
+							data_len = data.len(),
+							"failed to deserialize message"
+						);
+						continue;
+					}
+				};
 
 				handle_message(&ctx, &conn, msg)
 					.await
@@ -146,11 +143,23 @@ async fn handle_message(
 				return Ok(());
 			}
 
+			// Quotas can change at any time so they are fetched for every request
+			let kv_quota = ctx
+				.op(namespace::ops::get_global::Input {
+					namespace_ids: vec![conn.namespace_id],
+				})
+				.await
+				.with_context(|| format!("failed to get namespace: {}", conn.namespace_id))?
+				.into_iter()
+				.next()
+				.ok_or_else(|| namespace::errors::Namespace::NotFound.build())?
+				.kv_quota;
+
 			// TODO: Add queue and bg thread for processing kv ops
 			// Run kv operation
 			match req.data {
 				protocol::KvRequestData::KvGetRequest(body) => {
-					let res = kv::get(&*ctx.udb()?, actor_id, body.keys).await;
+					let res = kv::get(&*ctx.udb()?, actor_id, &kv_quota, body.keys).await;
 
 					let res_msg = versioned::ToClient::latest(
 						protocol::ToClient::ToClientKvResponse(protocol::ToClientKvResponse {
@@ -193,6 +202,11 @@ async fn handle_message(
 							.map(TryInto::try_into)
 							.transpose()
 							.context("KV list limit value overflow")?,
+						body.cursor,
+						body.max_bytes
+							.map(TryInto::try_into)
+							.transpose()
+							.context("KV list max bytes value overflow")?,
 					)
 					.await;
 
@@ -200,12 +214,13 @@ async fn handle_message(
 						protocol::ToClient::ToClientKvResponse(protocol::ToClientKvResponse {
 							request_id: req.request_id,
 							data: match res {
-								Ok((keys, values, metadata)) => {
+								Ok((keys, values, metadata, cursor)) => {
 									protocol::KvResponseData::KvListResponse(
 										protocol::KvListResponse {
 											keys,
 											values,
 											metadata,
+											cursor,
 										},
 									)
 								}
@@ -228,7 +243,16 @@ async fn handle_message(
 						.context("failed to send KV list response to client")?;
 				}
 				protocol::KvRequestData::KvPutRequest(body) => {
-					let res = kv::put(&*ctx.udb()?, actor_id, body.keys, body.values).await;
+					let res = kv::put(
+						&*ctx.udb()?,
+						&ctx.ups()?,
+						actor_id,
+						&kv_quota,
+						body.keys,
+						body.values,
+						body.expire_ts,
+					)
+					.await;
 
 					let res_msg = versioned::ToClient::latest(
 						protocol::ToClient::ToClientKvResponse(protocol::ToClientKvResponse {
@@ -256,7 +280,8 @@ async fn handle_message(
 						.context("failed to send KV put response to client")?;
 				}
 				protocol::KvRequestData::KvDeleteRequest(body) => {
-					let res = kv::delete(&*ctx.udb()?, actor_id, body.keys).await;
+					let res =
+						kv::delete(&*ctx.udb()?, &ctx.ups()?, actor_id, &kv_quota, body.keys).await;
 
 					let res_msg = versioned::ToClient::latest(
 						protocol::ToClient::ToClientKvResponse(protocol::ToClientKvResponse {
@@ -281,8 +306,94 @@ async fn handle_message(
 						.await
 						.context("failed to send KV delete response to client")?;
 				}
+				protocol::KvRequestData::KvTxnRequest(body) => {
+					let res = kv::txn(
+						&*ctx.udb()?,
+						&ctx.ups()?,
+						actor_id,
+						&kv_quota,
+						body.checks,
+						body.ops,
+					)
+					.await;
+
+					let res_msg = versioned::ToClient::latest(
+						protocol::ToClient::ToClientKvResponse(protocol::ToClientKvResponse {
+							request_id: req.request_id,
+							data: match res {
+								Ok(res) => protocol::KvResponseData::KvTxnResponse(res),
+								Err(err) => protocol::KvResponseData::KvErrorResponse(
+									protocol::KvErrorResponse {
+										// TODO: Don't return actual error?
+										message: err.to_string(),
+									},
+								),
+							},
+						}),
+					);
+
+					let res_msg_serialized = res_msg
+						.serialize(conn.protocol_version)
+						.context("failed to serialize KV txn response")?;
+					conn.ws_handle
+						.send(Message::Binary(res_msg_serialized.into()))
+						.await
+						.context("failed to send KV txn response to client")?;
+				}
+				protocol::KvRequestData::KvWatchRequest(body) => {
+					let res = kv_watch::watch(ctx, conn, actor_id, body).await;
+
+					let res_msg = versioned::ToClient::latest(
+						protocol::ToClient::ToClientKvResponse(protocol::ToClientKvResponse {
+							request_id: req.request_id,
+							data: match res {
+								Ok(()) => protocol::KvResponseData::KvWatchResponse,
+								Err(err) => protocol::KvResponseData::KvErrorResponse(
+									protocol::KvErrorResponse {
+										// TODO: Don't return actual error?
+										message: err.to_string(),
+									},
+								),
+							},
+						}),
+					);
+
+					let res_msg_serialized = res_msg
+						.serialize(conn.protocol_version)
+						.context("failed to serialize KV watch response")?;
+					conn.ws_handle
+						.send(Message::Binary(res_msg_serialized.into()))
+						.await
+						.context("failed to send KV watch response to client")?;
+				}
+				protocol::KvRequestData::KvUnwatchRequest(body) => {
+					let res = kv_watch::unwatch(conn, actor_id, body.watch_id).await;
+
+					let res_msg = versioned::ToClient::latest(
+						protocol::ToClient::ToClientKvResponse(protocol::ToClientKvResponse {
+							request_id: req.request_id,
+							data: match res {
+								Ok(()) => protocol::KvResponseData::KvUnwatchResponse,
+								Err(err) => protocol::KvResponseData::KvErrorResponse(
+									protocol::KvErrorResponse {
+										// TODO: Don't return actual error?
+										message: err.to_string(),
+									},
+								),
+							},
+						}),
+					);
+
+					let res_msg_serialized = res_msg
+						.serialize(conn.protocol_version)
+						.context("failed to serialize KV unwatch response")?;
+					conn.ws_handle
+						.send(Message::Binary(res_msg_serialized.into()))
+						.await
+						.context("failed to send KV unwatch response to client")?;
+				}
 				protocol::KvRequestData::KvDropRequest => {
-					let res = kv::delete_all(&*ctx.udb()?, actor_id).await;
+					let res = kv::delete_all(&*ctx.udb()?, &ctx.ups()?, actor_id).await;
 
 					let res_msg = versioned::ToClient::latest(
 						protocol::ToClient::ToClientKvResponse(protocol::ToClientKvResponse {
@@ -319,6 +430,25 @@ async fn handle_message(
 		| protocol::ToServer::ToServerEvents(_)
 		| protocol::ToServer::ToServerAckCommands(_)
 		| protocol::ToServer::ToServerStopping => {
+			// Stop KV watches of actors that stopped
+			if let protocol::ToServer::ToServerEvents(events) = &msg {
+				for event in events {
+					if let protocol::Event::EventActorStateUpdate(
+						protocol::EventActorStateUpdate {
+							actor_id,
+							state: protocol::ActorState::ActorStateStopped(_),
+							..
+						},
+					) = &event.inner
+					{
+						// Invalid ids are reported by the runner workflow
+						if let Ok(actor_id) = Id::parse(actor_id) {
+							kv_watch::unwatch_actor(conn, actor_id).await;
+						}
+					}
+				}
+			}
+
 			ctx.signal(pegboard::workflows::runner::Forward {
 				inner: protocol::ToServer::try_from(msg)
 					.context("failed to convert message for workflow forwarding")?,
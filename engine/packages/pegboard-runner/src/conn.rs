@@ -12,7 +12,7 @@ use std::{
 	sync::{Arc, atomic::AtomicU32},
 	time::Duration,
 };
-use tokio::sync::Mutex;
+use tokio::{sync::Mutex, task::JoinHandle};
 use vbare::OwnedVersionedData;
 
 use crate::{errors::WsError, utils::UrlData};
@@ -27,6 +27,8 @@ pub struct Conn {
 
 	pub workflow_id: Id,
 
+	pub namespace_id: Id,
+
 	pub protocol_version: u16,
 
 	pub ws_handle: WebSocketHandle,
@@ -36,6 +38,10 @@ pub struct Conn {
 	/// Active HTTP & WebSocket requests. They are separate but use the same mechanism to
 	/// maintain state.
 	pub tunnel_active_requests: Mutex<HashMap<RequestId, TunnelActiveRequest>>,
+
+	/// Active KV watches by (actor id, watch id). Each watch has a task forwarding change events to the
+	/// runner.
+	pub kv_watches: Mutex<HashMap<(Id, u32), JoinHandle<()>>>,
 }
 
 #[tracing::instrument(skip_all)]
@@ -83,6 +89,7 @@ pub async fn init_conn(
 				name,
 				version,
 				total_slots,
+				resources,
 				..
 			}) = &packet
 			{
@@ -145,6 +152,7 @@ pub async fn init_conn(
 						key: runner_key.clone(),
 						version: version.clone(),
 						total_slots: *total_slots,
+						resources: resources.clone(),
 					})
 					.tag("runner_id", runner_id)
 					.unique()
@@ -183,9 +191,11 @@ pub async fn init_conn(
 	Ok(Arc::new(Conn {
 		runner_id,
 		workflow_id,
+		namespace_id: namespace.namespace_id,
 		protocol_version,
 		ws_handle,
 		last_rtt: AtomicU32::new(0),
 		tunnel_active_requests: Mutex::new(HashMap::new()),
+		kv_watches: Mutex::new(HashMap::new()),
 	}))
 }
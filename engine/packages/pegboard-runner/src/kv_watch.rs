@@ -0,0 +1,170 @@
+use anyhow::Context;
+use gas::prelude::*;
+use hyper_tungstenite::tungstenite::Message;
+use pegboard_actor_kv::watch::ChangeEvent;
+use rivet_runner_protocol::{self as protocol, versioned};
+use std::sync::Arc;
+use universalpubsub::{NextOutput, Subscriber};
+use vbare::OwnedVersionedData;
+
+use crate::conn::Conn;
+
+/// Max amount of open KV watches per actor.
+const MAX_WATCHES_PER_ACTOR: usize = 64;
+
+/// Subscribes to changes of the target actor's KV and spawns a task forwarding matching changes to the
+/// runner.
+#[tracing::instrument(skip_all, fields(?actor_id, watch_id=%req.watch_id))]
+pub async fn watch(
+	ctx: &StandaloneCtx,
+	conn: &Arc<Conn>,
+	actor_id: Id,
+	req: protocol::KvWatchRequest,
+) -> Result<()> {
+	let target_actor_id = if let Some(target_actor_id) = &req.target_actor_id {
+		let target_actor_id = Id::parse(target_actor_id)?;
+
+		// Actors can only watch actors in the same namespace
+		let actors_res = ctx
+			.op(pegboard::ops::actor::get::Input {
+				actor_ids: vec![target_actor_id],
+			})
+			.await
+			.with_context(|| format!("failed to get actor: {}", target_actor_id))?;
+		let same_namespace = actors_res
+			.actors
+			.first()
+			.map(|x| x.namespace_id == conn.namespace_id)
+			.unwrap_or_default();
+		ensure!(same_namespace, "target actor not found");
+
+		target_actor_id
+	} else {
+		actor_id
+	};
+
+	let watch_id = req.watch_id;
+	let mut kv_watches = conn.kv_watches.lock().await;
+
+	ensure!(
+		!kv_watches.contains_key(&(actor_id, watch_id)),
+		"watch id already in use"
+	);
+	ensure!(
+		kv_watches.keys().filter(|(id, _)| *id == actor_id).count() < MAX_WATCHES_PER_ACTOR,
+		"too many watches (max 64)"
+	);
+
+	// Subscribe before responding so that no changes committed after the response are missed
+	let subject = pegboard::pubsub_subjects::ActorKvSubject::new(target_actor_id).to_string();
+	let sub = ctx
+		.ups()
+		.context("failed to get UPS instance for kv watch")?
+		.subscribe(&subject)
+		.await
+		.with_context(|| format!("failed to subscribe to actor kv topic: {}", subject))?;
+
+	let handle = tokio::spawn(task(conn.clone(), actor_id, req, sub));
+	kv_watches.insert((actor_id, watch_id), handle);
+
+	Ok(())
+}
+
+/// Stops a watch created by `watch`.
+#[tracing::instrument(skip_all, fields(?actor_id, %watch_id))]
+pub async fn unwatch(conn: &Conn, actor_id: Id, watch_id: u32) -> Result<()> {
+	let handle = conn
+		.kv_watches
+		.lock()
+		.await
+		.remove(&(actor_id, watch_id))
+		.context("watch not found")?;
+
+	handle.abort();
+
+	Ok(())
+}
+
+/// Stops all watches of the given actor. Called once the actor stops since its watches are only cleaned up
+/// otherwise when the runner disconnects.
+#[tracing::instrument(skip_all, fields(?actor_id))]
+pub async fn unwatch_actor(conn: &Conn, actor_id: Id) {
+	let mut kv_watches = conn.kv_watches.lock().await;
+
+	kv_watches.retain(|(id, _), handle| {
+		if *id == actor_id {
+			handle.abort();
+			false
+		} else {
+			true
+		}
+	});
+}
+
+/// Stops all watches of this connection.
+pub async fn unwatch_all(conn: &Conn) {
+	let mut kv_watches = conn.kv_watches.lock().await;
+
+	for (_, handle) in kv_watches.drain() {
+		handle.abort();
+	}
+}
+
+#[tracing::instrument(skip_all, fields(runner_id=?conn.runner_id, ?actor_id, watch_id=%req.watch_id))]
+async fn task(conn: Arc<Conn>, actor_id: Id, req: protocol::KvWatchRequest, sub: Subscriber) {
+	if let Err(err) = task_inner(&conn, actor_id, &req, sub).await {
+		tracing::error!(?err, "kv watch task failed");
+	}
+
+	// Remove the entry of this task so it no longer counts towards the watch limit. Only removed if it still
+	// belongs to this task since `unwatch` may have already replaced it with a new watch reusing the same id
+	let task_id = tokio::task::id();
+	let mut kv_watches = conn.kv_watches.lock().await;
+	if kv_watches
+		.get(&(actor_id, req.watch_id))
+		.is_some_and(|handle| handle.id() == task_id)
+	{
+		kv_watches.remove(&(actor_id, req.watch_id));
+	}
+}
+
+async fn task_inner(
+	conn: &Conn,
+	actor_id: Id,
+	req: &protocol::KvWatchRequest,
+	mut sub: Subscriber,
+) -> Result<()> {
+	while let NextOutput::Message(ups_msg) = sub.next().await.context("kv watch sub failed")? {
+		let event = match ChangeEvent::deserialize(&ups_msg.payload) {
+			Result::Ok(x) => x,
+			Err(err) => {
+				tracing::error!(?err, "failed to parse kv change event");
+				continue;
+			}
+		};
+
+		// Ignore changes to keys that are not watched
+		let Some(event) = event.filter(&req.key, req.prefix) else {
+			continue;
+		};
+
+		let msg = versioned::ToClient::latest(protocol::ToClient::ToClientKvWatchEvent(
+			protocol::ToClientKvWatchEvent {
+				actor_id: actor_id.to_string(),
+				watch_id: req.watch_id,
+				changes: event.changes,
+				cleared: event.cleared,
+			},
+		));
+
+		let msg_serialized = msg
+			.serialize(conn.protocol_version)
+			.context("failed to serialize KV watch event")?;
+		conn.ws_handle
+			.send(Message::Binary(msg_serialized.into()))
+			.await
+			.context("failed to send KV watch event to client")?;
+	}
+
+	Ok(())
+}
@@ -18,6 +18,7 @@ use vbare::OwnedVersionedData;
 mod client_to_pubsub_task;
 mod conn;
 mod errors;
+mod kv_watch;
 mod ping_task;
 mod pubsub_to_client_task;
 mod utils;
@@ -130,6 +131,7 @@ impl CustomServeTrait for PegboardRunnerWsCustomServe {
 		pubsub_to_client.abort();
 		client_to_pubsub.abort();
 		ping.abort();
+		kv_watch::unwatch_all(&conn).await;
 
 		// Make runner immediately ineligible when it disconnects
 		let update_alloc_res = self
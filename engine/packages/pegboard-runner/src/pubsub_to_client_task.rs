@@ -66,14 +66,14 @@ pub async fn task(conn: Arc<Conn>, mut sub: Subscriber) -> Result<()> {
 		}
 
 		// Forward raw message to WebSocket
-		let serialized_msg =
-			match versioned::ToClient::latest(msg).serialize_version(conn.protocol_version) {
-				Result::Ok(x) => x,
-				Err(err) => {
-					tracing::error!(?err, "failed to serialize tunnel message");
-					continue;
-				}
-			};
+		let serialized_msg = match versioned::ToClient::latest(msg).serialize(conn.protocol_version)
+		{
+			Result::Ok(x) => x,
+			Err(err) => {
+				tracing::error!(?err, "failed to serialize tunnel message");
+				continue;
+			}
+		};
 		let ws_msg = WsMessage::Binary(serialized_msg.into());
 		conn.ws_handle
 			.send(ws_msg)
@@ -0,0 +1,470 @@
+use std::collections::HashMap;
+
+use futures_util::TryStreamExt;
+use gas::prelude::*;
+use rivet_data::converted::RunnerAllocIdxKeyData;
+use rivet_types::{
+	actors::{PlacementConstraints, Resources},
+	runner_configs::AllocationStrategy,
+};
+use universaldb::{
+	options::{ConflictRangeType, StreamingMode},
+	utils::{FormalChunkedKey, IsolationLevel::*},
+};
+
+use crate::{keys, workflows::runner::RUNNER_ELIGIBLE_THRESHOLD_MS};
+
+/// How many eligible runners `AllocationStrategy::LabelAffinity` reads the labels of before falling back to
+/// the first eligible runner. Each read is a chunked metadata read in the allocation transaction.
+const LABEL_AFFINITY_MAX_CANDIDATES: usize = 16;
+
+pub struct ReservedSlot {
+	pub runner_id: Id,
+	pub runner_workflow_id: Id,
+}
+
+/// Takes a slot (and the requested resources) from a runner chosen by the given strategy that satisfies the
+/// placement constraints of the actor. Only runners with the highest version are considered. The alloc idx is
+/// read with snapshot isolation and only the key of the chosen runner is added as a read conflict so that
+/// concurrent allocations to other runners don't conflict.
+pub async fn reserve_slot(
+	tx: &universaldb::Transaction,
+	namespace_id: Id,
+	runner_name_selector: &str,
+	strategy: &AllocationStrategy,
+	actor_id: Id,
+	placement: &PlacementConstraints,
+	resources: Option<Resources>,
+) -> Result<Option<ReservedSlot>> {
+	let ping_threshold_ts = util::timestamp::now() - RUNNER_ELIGIBLE_THRESHOLD_MS;
+
+	let Some(version) = highest_version(tx, namespace_id, runner_name_selector).await? else {
+		return Ok(None);
+	};
+
+	let runner_alloc_subspace =
+		keys::subspace().subspace(&keys::ns::RunnerAllocIdxKey::subspace_with_version(
+			namespace_id,
+			runner_name_selector.to_string(),
+			version,
+		));
+
+	let candidate = match strategy {
+		AllocationStrategy::Spread {} => {
+			find_runner(
+				tx,
+				runner_alloc_subspace.range(),
+				false,
+				ping_threshold_ts,
+				placement,
+				resources,
+				None,
+			)
+			.await?
+		}
+		// Runners with less remaining capacity are last in the idx. Full runners (0 remaining capacity) are
+		// excluded from the range so the reverse scan starts at the fullest runner with a free slot.
+		AllocationStrategy::BinPack {} => {
+			let (start, _) = runner_alloc_subspace.range();
+			let (end, _) = runner_alloc_subspace.subspace(&0i32).range();
+
+			find_runner(
+				tx,
+				(start, end),
+				true,
+				ping_threshold_ts,
+				placement,
+				resources,
+				None,
+			)
+			.await?
+		}
+		AllocationStrategy::LabelAffinity { labels } => {
+			find_runner(
+				tx,
+				runner_alloc_subspace.range(),
+				false,
+				ping_threshold_ts,
+				placement,
+				resources,
+				Some(labels),
+			)
+			.await?
+		}
+	};
+
+	let Some((old_runner_alloc_key, old_runner_alloc_key_data)) = candidate else {
+		return Ok(None);
+	};
+
+	// Add read conflict only for this key
+	tx.add_conflict_key(&old_runner_alloc_key, ConflictRangeType::Read)?;
+
+	for group in &placement.anti_affinity_groups {
+		// Conflict with concurrent allocations of the same group to this runner
+		let anti_affinity_subspace = keys::subspace().subspace(
+			&keys::runner::AntiAffinityKey::subspace(old_runner_alloc_key.runner_id, group.clone()),
+		);
+		let (start, end) = anti_affinity_subspace.range();
+		tx.add_conflict_range(&start, &end, ConflictRangeType::Read)?;
+
+		tx.write(
+			&keys::runner::AntiAffinityKey::new(
+				old_runner_alloc_key.runner_id,
+				group.clone(),
+				actor_id,
+			),
+			(),
+		)?;
+	}
+
+	// Clear old entry
+	tx.delete(&old_runner_alloc_key);
+
+	let runner_id = old_runner_alloc_key.runner_id;
+	let runner_workflow_id = old_runner_alloc_key_data.workflow_id;
+	let mut new_runner_alloc_key_data = RunnerAllocIdxKeyData {
+		remaining_slots: old_runner_alloc_key_data.remaining_slots.saturating_sub(1),
+		..old_runner_alloc_key_data
+	};
+
+	// Only runners that advertised their resources account for them
+	if let Some(resources) = resources.filter(|_| has_resources(&new_runner_alloc_key_data)) {
+		new_runner_alloc_key_data.remaining_cpu_millicores = new_runner_alloc_key_data
+			.remaining_cpu_millicores
+			.saturating_sub(resources.cpu_millicores);
+		new_runner_alloc_key_data.remaining_memory_mib = new_runner_alloc_key_data
+			.remaining_memory_mib
+			.saturating_sub(resources.memory_mib);
+
+		tx.write(
+			&keys::runner::RemainingResourcesKey::new(runner_id),
+			Resources {
+				cpu_millicores: new_runner_alloc_key_data.remaining_cpu_millicores,
+				memory_mib: new_runner_alloc_key_data.remaining_memory_mib,
+			},
+		)?;
+	}
+
+	// Update runner record
+	tx.write(
+		&keys::runner::RemainingSlotsKey::new(runner_id),
+		new_runner_alloc_key_data.remaining_slots,
+	)?;
+
+	// Write new allocation key with 1 less slot
+	tx.write(
+		&keys::ns::RunnerAllocIdxKey::new(
+			namespace_id,
+			runner_name_selector.to_string(),
+			old_runner_alloc_key.version,
+			new_runner_alloc_key_data.remaining_capacity(),
+			old_runner_alloc_key.last_ping_ts,
+			runner_id,
+		),
+		new_runner_alloc_key_data,
+	)?;
+
+	Ok(Some(ReservedSlot {
+		runner_id,
+		runner_workflow_id,
+	}))
+}
+
+/// Whether only some runners can take the actor. These actors wait in their own alloc queue so that actors
+/// behind them are not blocked while no runner satisfies them.
+pub fn is_constrained(placement: &PlacementConstraints, resources: Option<Resources>) -> bool {
+	!placement.labels.is_empty()
+		|| !placement.anti_affinity_groups.is_empty()
+		|| resources.is_some()
+}
+
+/// Reads the runner metadata labels (string values of the metadata object).
+pub async fn runner_labels(
+	tx: &universaldb::Transaction,
+	runner_id: Id,
+) -> Result<HashMap<String, String>> {
+	let metadata_key = keys::runner::MetadataKey::new(runner_id);
+	let metadata_subspace = keys::subspace().subspace(&metadata_key);
+
+	let metadata_chunks = tx
+		.get_ranges_keyvalues(
+			universaldb::RangeOption {
+				mode: StreamingMode::WantAll,
+				..(&metadata_subspace).into()
+			},
+			// NOTE: Not Serializable, runner metadata is only written on connect
+			Snapshot,
+		)
+		.try_collect::<Vec<_>>()
+		.await?;
+
+	if metadata_chunks.is_empty() {
+		return Ok(HashMap::new());
+	}
+
+	let metadata = metadata_key.combine(metadata_chunks)?.metadata;
+
+	Ok(metadata
+		.into_iter()
+		.filter_map(|(k, v)| match v {
+			serde_json::Value::String(v) => Some((k, v)),
+			_ => None,
+		})
+		.collect())
+}
+
+pub fn matches_labels<'a>(
+	runner_labels: &HashMap<String, String>,
+	mut labels: impl Iterator<Item = (&'a String, &'a String)>,
+) -> bool {
+	labels.all(|(k, v)| runner_labels.get(k).is_some_and(|x| x == v))
+}
+
+pub enum PlacementCheck {
+	Satisfiable,
+	NoMatchingRunner,
+	InsufficientResources,
+	AntiAffinityConflict,
+}
+
+/// Checks if any eligible runner with the highest version satisfies the placement constraints and has enough
+/// total resources for the actor, regardless of its remaining slots and resources.
+pub async fn check_placement(
+	tx: &universaldb::Transaction,
+	namespace_id: Id,
+	runner_name_selector: &str,
+	placement: &PlacementConstraints,
+	resources: Option<Resources>,
+	ping_threshold_ts: i64,
+) -> Result<PlacementCheck> {
+	let Some(version) = highest_version(tx, namespace_id, runner_name_selector).await? else {
+		return Ok(PlacementCheck::NoMatchingRunner);
+	};
+
+	let runner_alloc_subspace =
+		keys::subspace().subspace(&keys::ns::RunnerAllocIdxKey::subspace_with_version(
+			namespace_id,
+			runner_name_selector.to_string(),
+			version,
+		));
+
+	let mut stream = tx.get_ranges_keyvalues(
+		universaldb::RangeOption {
+			mode: StreamingMode::Iterator,
+			..(&runner_alloc_subspace).into()
+		},
+		Snapshot,
+	);
+
+	let mut res = PlacementCheck::NoMatchingRunner;
+
+	while let Some(entry) = stream.try_next().await? {
+		let (runner_alloc_key, runner_alloc_key_data) =
+			tx.read_entry::<keys::ns::RunnerAllocIdxKey>(&entry)?;
+
+		if runner_alloc_key.last_ping_ts < ping_threshold_ts {
+			continue;
+		}
+
+		if !placement.labels.is_empty()
+			&& !matches_labels(
+				&runner_labels(tx, runner_alloc_key.runner_id).await?,
+				placement.labels.iter(),
+			) {
+			continue;
+		}
+
+		if resources
+			.is_some_and(|resources| !fits_total_resources(&runner_alloc_key_data, &resources))
+		{
+			if !matches!(res, PlacementCheck::AntiAffinityConflict) {
+				res = PlacementCheck::InsufficientResources;
+			}
+			continue;
+		}
+
+		if has_anti_affinity_conflict(
+			tx,
+			runner_alloc_key.runner_id,
+			&placement.anti_affinity_groups,
+		)
+		.await?
+		{
+			res = PlacementCheck::AntiAffinityConflict;
+			continue;
+		}
+
+		return Ok(PlacementCheck::Satisfiable);
+	}
+
+	Ok(res)
+}
+
+async fn highest_version(
+	tx: &universaldb::Transaction,
+	namespace_id: Id,
+	runner_name_selector: &str,
+) -> Result<Option<u32>> {
+	let runner_alloc_subspace = keys::subspace().subspace(&keys::ns::RunnerAllocIdxKey::subspace(
+		namespace_id,
+		runner_name_selector.to_string(),
+	));
+
+	// Higher versions are first in the idx
+	let Some(entry) = tx
+		.get_ranges_keyvalues(
+			universaldb::RangeOption {
+				mode: StreamingMode::Exact,
+				limit: Some(1),
+				..(&runner_alloc_subspace).into()
+			},
+			Snapshot,
+		)
+		.try_next()
+		.await?
+	else {
+		return Ok(None);
+	};
+
+	let (runner_alloc_key, _) = tx.read_entry::<keys::ns::RunnerAllocIdxKey>(&entry)?;
+
+	Ok(Some(runner_alloc_key.version))
+}
+
+/// Scans the given range of the alloc idx for a runner with available slots that satisfies the placement
+/// constraints. If preferred labels are given, runners matching them are chosen over the first eligible
+/// runner, up to `LABEL_AFFINITY_MAX_CANDIDATES` runners.
+async fn find_runner(
+	tx: &universaldb::Transaction,
+	range: (Vec<u8>, Vec<u8>),
+	reverse: bool,
+	ping_threshold_ts: i64,
+	placement: &PlacementConstraints,
+	resources: Option<Resources>,
+	preferred_labels: Option<&HashMap<String, String>>,
+) -> Result<Option<(keys::ns::RunnerAllocIdxKey, RunnerAllocIdxKeyData)>> {
+	let mut stream = tx.get_ranges_keyvalues(
+		universaldb::RangeOption {
+			mode: StreamingMode::Iterator,
+			reverse,
+			..range.into()
+		},
+		// NOTE: This is not Serializable because we don't want to conflict with all of the keys, just the one
+		// we choose
+		Snapshot,
+	);
+
+	let mut fallback = None;
+	let mut label_candidates = 0;
+
+	while let Some(entry) = stream.try_next().await? {
+		let (runner_alloc_key, runner_alloc_key_data) =
+			tx.read_entry::<keys::ns::RunnerAllocIdxKey>(&entry)?;
+
+		// Full runners are last in the idx
+		if runner_alloc_key_data.is_full() {
+			break;
+		}
+
+		// Scan by last ping
+		if runner_alloc_key.last_ping_ts < ping_threshold_ts {
+			continue;
+		}
+
+		if resources.is_some_and(|resources| !fits_resources(&runner_alloc_key_data, &resources)) {
+			continue;
+		}
+
+		let labels = if placement.labels.is_empty() && preferred_labels.is_none() {
+			HashMap::new()
+		} else {
+			runner_labels(tx, runner_alloc_key.runner_id).await?
+		};
+
+		if !matches_labels(&labels, placement.labels.iter())
+			|| has_anti_affinity_conflict(
+				tx,
+				runner_alloc_key.runner_id,
+				&placement.anti_affinity_groups,
+			)
+			.await?
+		{
+			continue;
+		}
+
+		match preferred_labels {
+			Some(preferred_labels) if !matches_labels(&labels, preferred_labels.iter()) => {
+				if fallback.is_none() {
+					fallback = Some((runner_alloc_key, runner_alloc_key_data));
+				}
+
+				label_candidates += 1;
+				if label_candidates >= LABEL_AFFINITY_MAX_CANDIDATES {
+					break;
+				}
+			}
+			_ => return Ok(Some((runner_alloc_key, runner_alloc_key_data))),
+		}
+	}
+
+	Ok(fallback)
+}
+
+/// Whether the runner advertised its resources.
+fn has_resources(runner_alloc_key_data: &RunnerAllocIdxKeyData) -> bool {
+	runner_alloc_key_data.total_cpu_millicores != 0 || runner_alloc_key_data.total_memory_mib != 0
+}
+
+/// Whether the runner has enough of the requested resources left. Resources the runner did not advertise are
+/// not limited.
+fn fits_resources(runner_alloc_key_data: &RunnerAllocIdxKeyData, resources: &Resources) -> bool {
+	(runner_alloc_key_data.total_cpu_millicores == 0
+		|| runner_alloc_key_data.remaining_cpu_millicores >= resources.cpu_millicores)
+		&& (runner_alloc_key_data.total_memory_mib == 0
+			|| runner_alloc_key_data.remaining_memory_mib >= resources.memory_mib)
+}
+
+/// Whether the runner could ever fit the requested resources, regardless of what it has left.
+fn fits_total_resources(
+	runner_alloc_key_data: &RunnerAllocIdxKeyData,
+	resources: &Resources,
+) -> bool {
+	(runner_alloc_key_data.total_cpu_millicores == 0
+		|| runner_alloc_key_data.total_cpu_millicores >= resources.cpu_millicores)
+		&& (runner_alloc_key_data.total_memory_mib == 0
+			|| runner_alloc_key_data.total_memory_mib >= resources.memory_mib)
+}
+
+/// Whether the runner is already running an actor in any of the given anti-affinity groups.
+async fn has_anti_affinity_conflict(
+	tx: &universaldb::Transaction,
+	runner_id: Id,
+	groups: &[String],
+) -> Result<bool> {
+	for group in groups {
+		let anti_affinity_subspace = keys::subspace().subspace(
+			&keys::runner::AntiAffinityKey::subspace(runner_id, group.clone()),
+		);
+
+		let exists = tx
+			.get_ranges_keyvalues(
+				universaldb::RangeOption {
+					mode: StreamingMode::Exact,
+					limit: Some(1),
+					..(&anti_affinity_subspace).into()
+				},
+				// NOTE: Not Serializable, a read conflict is added for the chosen runner in `reserve_slot`
+				Snapshot,
+			)
+			.try_next()
+			.await?
+			.is_some();
+
+		if exists {
+			return Ok(true);
+		}
+	}
+
+	Ok(false)
+}
@@ -63,6 +63,41 @@ pub enum Actor {
 		namespace: String,
 		runner_name: String,
 	},
+
+	#[error(
+		"invalid_placement",
+		"Invalid placement constraints.",
+		"Invalid placement constraints: {reason}"
+	)]
+	InvalidPlacement { reason: String },
+
+	#[error(
+		"no_runners_match_placement",
+		"No runners have all of the labels required by the actor's placement constraints.",
+		"No connected runners with name '{runner_name}' have all of the labels required by the actor's placement constraints."
+	)]
+	NoRunnersMatchPlacement { runner_name: String },
+
+	#[error(
+		"insufficient_runner_resources",
+		"No runners have enough total resources for the actor's resource request.",
+		"No connected runners with name '{runner_name}' matching the actor's placement constraints have enough total resources for the actor's resource request."
+	)]
+	InsufficientRunnerResources { runner_name: String },
+
+	#[error(
+		"anti_affinity_unsatisfiable",
+		"Every runner matching the actor's placement constraints is already running an actor in one of its anti-affinity groups.",
+		"Every connected runner with name '{runner_name}' matching the actor's placement constraints is already running an actor in one of its anti-affinity groups."
+	)]
+	AntiAffinityUnsatisfiable { runner_name: String },
+
+	#[error(
+		"invalid_restart_policy",
+		"Invalid restart policy.",
+		"Invalid restart policy: {reason}"
+	)]
+	InvalidRestartPolicy { reason: String },
 }
 
 #[derive(RivetError, Debug, Clone, Deserialize, Serialize)]
@@ -3,6 +3,7 @@ use std::result::Result::Ok;
 use anyhow::*;
 use gas::prelude::*;
 use universaldb::prelude::*;
+use vbare::OwnedVersionedData;
 
 #[derive(Debug)]
 pub struct CreateTsKey {
@@ -313,3 +314,99 @@ impl<'de> TupleUnpack<'de> for NamespaceIdKey {
 		Ok((input, v))
 	}
 }
+
+#[derive(Debug)]
+pub struct PlacementKey {
+	actor_id: Id,
+}
+
+impl PlacementKey {
+	pub fn new(actor_id: Id) -> Self {
+		PlacementKey { actor_id }
+	}
+}
+
+impl FormalKey for PlacementKey {
+	type Value = rivet_types::actors::PlacementConstraints;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(
+			rivet_data::versioned::PegboardActorPlacement::deserialize_with_embedded_version(raw)?
+				.into(),
+		)
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		rivet_data::versioned::PegboardActorPlacement::latest(value.into())
+			.serialize_with_embedded_version(rivet_data::PEGBOARD_ACTOR_PLACEMENT_VERSION)
+	}
+}
+
+impl TuplePack for PlacementKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (ACTOR, DATA, self.actor_id, PLACEMENT);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for PlacementKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, actor_id, _)) = <(usize, usize, Id, usize)>::unpack(input, tuple_depth)?;
+
+		let v = PlacementKey { actor_id };
+
+		Ok((input, v))
+	}
+}
+
+#[derive(Debug)]
+pub struct ResourcesKey {
+	actor_id: Id,
+}
+
+impl ResourcesKey {
+	pub fn new(actor_id: Id) -> Self {
+		ResourcesKey { actor_id }
+	}
+}
+
+impl FormalKey for ResourcesKey {
+	type Value = rivet_types::actors::Resources;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(
+			rivet_data::versioned::PegboardResources::deserialize_with_embedded_version(raw)?
+				.into(),
+		)
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		rivet_data::versioned::PegboardResources::latest(value.into())
+			.serialize_with_embedded_version(rivet_data::PEGBOARD_RESOURCES_VERSION)
+	}
+}
+
+impl TuplePack for ResourcesKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (ACTOR, DATA, self.actor_id, RESOURCES);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for ResourcesKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, actor_id, _)) = <(usize, usize, Id, usize)>::unpack(input, tuple_depth)?;
+
+		let v = ResourcesKey { actor_id };
+
+		Ok((input, v))
+	}
+}
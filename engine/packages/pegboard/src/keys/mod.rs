@@ -12,3 +12,8 @@ pub fn subspace() -> universaldb::utils::Subspace {
 pub fn actor_kv_subspace() -> universaldb::utils::Subspace {
 	universaldb::utils::Subspace::new(&(RIVET, PEGBOARD, ACTOR_KV))
 }
+
+/// Index of actor KV entries by expiration timestamp, used to reclaim expired entries.
+pub fn actor_kv_expire_subspace() -> universaldb::utils::Subspace {
+	universaldb::utils::Subspace::new(&(RIVET, PEGBOARD, ACTOR_KV_EXPIRE))
+}
@@ -10,7 +10,7 @@ pub struct RunnerAllocIdxKey {
 	pub namespace_id: Id,
 	pub name: String,
 	pub version: u32,
-	pub remaining_millislots: u32,
+	pub remaining_capacity: u32,
 	pub last_ping_ts: i64,
 	pub runner_id: Id,
 }
@@ -20,7 +20,7 @@ impl RunnerAllocIdxKey {
 		namespace_id: Id,
 		name: String,
 		version: u32,
-		remaining_millislots: u32,
+		remaining_capacity: u32,
 		last_ping_ts: i64,
 		runner_id: Id,
 	) -> Self {
@@ -28,16 +28,45 @@ impl RunnerAllocIdxKey {
 			namespace_id,
 			name,
 			version,
-			remaining_millislots,
+			remaining_capacity,
 			last_ping_ts,
 			runner_id,
 		}
 	}
 
+	/// Key of an entry written before the idx was ordered by remaining capacity, which is ordered by the
+	/// remaining slots of the runner instead. These entries are replaced the next time the runner is updated
+	/// (see `update_alloc_idx`).
+	pub fn new_legacy(
+		namespace_id: Id,
+		name: String,
+		version: u32,
+		remaining_slots: u32,
+		last_ping_ts: i64,
+		runner_id: Id,
+	) -> Self {
+		RunnerAllocIdxKey::new(
+			namespace_id,
+			name,
+			version,
+			remaining_slots,
+			last_ping_ts,
+			runner_id,
+		)
+	}
+
 	pub fn subspace(namespace_id: Id, name: String) -> RunnerAllocIdxSubspaceKey {
 		RunnerAllocIdxSubspaceKey::new(namespace_id, name)
 	}
 
+	pub fn subspace_with_version(
+		namespace_id: Id,
+		name: String,
+		version: u32,
+	) -> RunnerAllocIdxSubspaceKey {
+		RunnerAllocIdxSubspaceKey::new_with_version(namespace_id, name, version)
+	}
+
 	pub fn entire_subspace() -> RunnerAllocIdxSubspaceKey {
 		RunnerAllocIdxSubspaceKey::entire()
 	}
@@ -72,8 +101,8 @@ impl TuplePack for RunnerAllocIdxKey {
 			&self.name,
 			// Stored in reverse order (higher versions are first)
 			-(self.version as i32),
-			// Stored in reverse order (higher remaining slots are first)
-			-(self.remaining_millislots as i32),
+			// Stored in reverse order (higher remaining capacity is first)
+			-(self.remaining_capacity as i32),
 			self.last_ping_ts,
 			self.runner_id,
 		);
@@ -85,14 +114,14 @@ impl<'de> TupleUnpack<'de> for RunnerAllocIdxKey {
 	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
 		let (
 			input,
-			(_, _, namespace_id, name, version, remaining_millislots, last_ping_ts, runner_id),
+			(_, _, namespace_id, name, version, remaining_capacity, last_ping_ts, runner_id),
 		) = <(usize, usize, Id, String, i32, i32, i64, Id)>::unpack(input, tuple_depth)?;
 
 		let v = RunnerAllocIdxKey {
 			namespace_id,
 			name,
 			version: -version as u32,
-			remaining_millislots: -remaining_millislots as u32,
+			remaining_capacity: -remaining_capacity as u32,
 			last_ping_ts,
 			runner_id,
 		};
@@ -104,6 +133,7 @@ impl<'de> TupleUnpack<'de> for RunnerAllocIdxKey {
 pub struct RunnerAllocIdxSubspaceKey {
 	pub namespace_id: Option<Id>,
 	pub name: Option<String>,
+	pub version: Option<u32>,
 }
 
 impl RunnerAllocIdxSubspaceKey {
@@ -111,6 +141,15 @@ impl RunnerAllocIdxSubspaceKey {
 		RunnerAllocIdxSubspaceKey {
 			namespace_id: Some(namespace_id),
 			name: Some(name),
+			version: None,
+		}
+	}
+
+	pub fn new_with_version(namespace_id: Id, name: String, version: u32) -> Self {
+		RunnerAllocIdxSubspaceKey {
+			namespace_id: Some(namespace_id),
+			name: Some(name),
+			version: Some(version),
 		}
 	}
 
@@ -118,6 +157,7 @@ impl RunnerAllocIdxSubspaceKey {
 		RunnerAllocIdxSubspaceKey {
 			namespace_id: None,
 			name: None,
+			version: None,
 		}
 	}
 }
@@ -138,6 +178,11 @@ impl TuplePack for RunnerAllocIdxSubspaceKey {
 
 			if let Some(name) = &self.name {
 				offset += name.pack(w, tuple_depth)?;
+
+				if let Some(version) = &self.version {
+					// Stored in reverse order, see `RunnerAllocIdxKey`
+					offset += (-(*version as i32)).pack(w, tuple_depth)?;
+				}
 			}
 		}
 
@@ -145,19 +190,29 @@ impl TuplePack for RunnerAllocIdxSubspaceKey {
 	}
 }
 
+/// Actors waiting for allocation. Actors with placement constraints or resource requests wait in a separate
+/// queue (`constrained`) so they don't hold up actors that any runner can take.
 #[derive(Debug)]
 pub struct PendingActorByRunnerNameSelectorKey {
 	pub namespace_id: Id,
 	pub runner_name_selector: String,
+	pub constrained: bool,
 	pub ts: i64,
 	pub actor_id: Id,
 }
 
 impl PendingActorByRunnerNameSelectorKey {
-	pub fn new(namespace_id: Id, runner_name_selector: String, ts: i64, actor_id: Id) -> Self {
+	pub fn new(
+		namespace_id: Id,
+		runner_name_selector: String,
+		constrained: bool,
+		ts: i64,
+		actor_id: Id,
+	) -> Self {
 		PendingActorByRunnerNameSelectorKey {
 			namespace_id,
 			runner_name_selector,
+			constrained,
 			ts,
 			actor_id,
 		}
@@ -166,8 +221,21 @@ impl PendingActorByRunnerNameSelectorKey {
 	pub fn subspace(
 		namespace_id: Id,
 		runner_name_selector: String,
+		constrained: bool,
 	) -> PendingActorByRunnerNameSelectorSubspaceKey {
-		PendingActorByRunnerNameSelectorSubspaceKey::new(namespace_id, runner_name_selector)
+		PendingActorByRunnerNameSelectorSubspaceKey::new(
+			namespace_id,
+			runner_name_selector,
+			constrained,
+		)
+	}
+}
+
+fn pending_actor_queue(constrained: bool) -> usize {
+	if constrained {
+		PENDING_CONSTRAINED_ACTOR_BY_RUNNER_NAME_SELECTOR
+	} else {
+		PENDING_ACTOR_BY_RUNNER_NAME_SELECTOR
 	}
 }
 
@@ -192,7 +260,7 @@ impl TuplePack for PendingActorByRunnerNameSelectorKey {
 	) -> std::io::Result<VersionstampOffset> {
 		let t = (
 			NAMESPACE,
-			PENDING_ACTOR_BY_RUNNER_NAME_SELECTOR,
+			pending_actor_queue(self.constrained),
 			self.namespace_id,
 			&self.runner_name_selector,
 			self.ts,
@@ -204,12 +272,13 @@ impl TuplePack for PendingActorByRunnerNameSelectorKey {
 
 impl<'de> TupleUnpack<'de> for PendingActorByRunnerNameSelectorKey {
 	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
-		let (input, (_, _, namespace_id, runner_name_selector, ts, actor_id)) =
+		let (input, (_, queue, namespace_id, runner_name_selector, ts, actor_id)) =
 			<(usize, usize, Id, String, i64, Id)>::unpack(input, tuple_depth)?;
 
 		let v = PendingActorByRunnerNameSelectorKey {
 			namespace_id,
 			runner_name_selector,
+			constrained: queue == PENDING_CONSTRAINED_ACTOR_BY_RUNNER_NAME_SELECTOR,
 			ts,
 			actor_id,
 		};
@@ -221,13 +290,15 @@ impl<'de> TupleUnpack<'de> for PendingActorByRunnerNameSelectorKey {
 pub struct PendingActorByRunnerNameSelectorSubspaceKey {
 	pub namespace_id: Id,
 	pub runner_name_selector: String,
+	pub constrained: bool,
 }
 
 impl PendingActorByRunnerNameSelectorSubspaceKey {
-	pub fn new(namespace_id: Id, runner_name_selector: String) -> Self {
+	pub fn new(namespace_id: Id, runner_name_selector: String, constrained: bool) -> Self {
 		PendingActorByRunnerNameSelectorSubspaceKey {
 			namespace_id,
 			runner_name_selector,
+			constrained,
 		}
 	}
 }
@@ -240,7 +311,7 @@ impl TuplePack for PendingActorByRunnerNameSelectorSubspaceKey {
 	) -> std::io::Result<VersionstampOffset> {
 		let t = (
 			NAMESPACE,
-			PENDING_ACTOR_BY_RUNNER_NAME_SELECTOR,
+			pending_actor_queue(self.constrained),
 			self.namespace_id,
 			&self.runner_name_selector,
 		);
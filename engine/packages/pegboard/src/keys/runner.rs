@@ -183,6 +183,102 @@ impl<'de> TupleUnpack<'de> for TotalSlotsKey {
 	}
 }
 
+#[derive(Debug)]
+pub struct RemainingResourcesKey {
+	runner_id: Id,
+}
+
+impl RemainingResourcesKey {
+	pub fn new(runner_id: Id) -> Self {
+		RemainingResourcesKey { runner_id }
+	}
+}
+
+impl FormalKey for RemainingResourcesKey {
+	type Value = rivet_types::actors::Resources;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(
+			rivet_data::versioned::PegboardResources::deserialize_with_embedded_version(raw)?
+				.into(),
+		)
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		rivet_data::versioned::PegboardResources::latest(value.into())
+			.serialize_with_embedded_version(rivet_data::PEGBOARD_RESOURCES_VERSION)
+	}
+}
+
+impl TuplePack for RemainingResourcesKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (RUNNER, DATA, self.runner_id, REMAINING_RESOURCES);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for RemainingResourcesKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, runner_id, _)) =
+			<(usize, usize, Id, usize)>::unpack(input, tuple_depth)?;
+		let v = RemainingResourcesKey { runner_id };
+
+		Ok((input, v))
+	}
+}
+
+#[derive(Debug)]
+pub struct TotalResourcesKey {
+	runner_id: Id,
+}
+
+impl TotalResourcesKey {
+	pub fn new(runner_id: Id) -> Self {
+		TotalResourcesKey { runner_id }
+	}
+}
+
+impl FormalKey for TotalResourcesKey {
+	type Value = rivet_types::actors::Resources;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(
+			rivet_data::versioned::PegboardResources::deserialize_with_embedded_version(raw)?
+				.into(),
+		)
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		rivet_data::versioned::PegboardResources::latest(value.into())
+			.serialize_with_embedded_version(rivet_data::PEGBOARD_RESOURCES_VERSION)
+	}
+}
+
+impl TuplePack for TotalResourcesKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (RUNNER, DATA, self.runner_id, TOTAL_RESOURCES);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for TotalResourcesKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, runner_id, _)) =
+			<(usize, usize, Id, usize)>::unpack(input, tuple_depth)?;
+		let v = TotalResourcesKey { runner_id };
+
+		Ok((input, v))
+	}
+}
+
 #[derive(Debug)]
 pub struct ActorKey {
 	runner_id: Id,
@@ -282,6 +378,93 @@ impl TuplePack for ActorSubspaceKey {
 	}
 }
 
+/// Marks that the runner is running an actor in the given anti-affinity group.
+#[derive(Debug)]
+pub struct AntiAffinityKey {
+	runner_id: Id,
+	group: String,
+	actor_id: Id,
+}
+
+impl AntiAffinityKey {
+	pub fn new(runner_id: Id, group: String, actor_id: Id) -> Self {
+		AntiAffinityKey {
+			runner_id,
+			group,
+			actor_id,
+		}
+	}
+
+	pub fn subspace(runner_id: Id, group: String) -> AntiAffinitySubspaceKey {
+		AntiAffinitySubspaceKey::new(runner_id, group)
+	}
+}
+
+impl FormalKey for AntiAffinityKey {
+	type Value = ();
+
+	fn deserialize(&self, _raw: &[u8]) -> Result<Self::Value> {
+		Ok(())
+	}
+
+	fn serialize(&self, _value: Self::Value) -> Result<Vec<u8>> {
+		Ok(Vec::new())
+	}
+}
+
+impl TuplePack for AntiAffinityKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (
+			RUNNER,
+			ANTI_AFFINITY,
+			self.runner_id,
+			&self.group,
+			self.actor_id,
+		);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for AntiAffinityKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, runner_id, group, actor_id)) =
+			<(usize, usize, Id, String, Id)>::unpack(input, tuple_depth)?;
+		let v = AntiAffinityKey {
+			runner_id,
+			group,
+			actor_id,
+		};
+
+		Ok((input, v))
+	}
+}
+
+pub struct AntiAffinitySubspaceKey {
+	runner_id: Id,
+	group: String,
+}
+
+impl AntiAffinitySubspaceKey {
+	fn new(runner_id: Id, group: String) -> Self {
+		AntiAffinitySubspaceKey { runner_id, group }
+	}
+}
+
+impl TuplePack for AntiAffinitySubspaceKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (RUNNER, ANTI_AFFINITY, self.runner_id, &self.group);
+		t.pack(w, tuple_depth)
+	}
+}
+
 #[derive(Debug)]
 pub struct WorkflowIdKey {
 	runner_id: Id,
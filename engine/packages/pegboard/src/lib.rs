@@ -1,5 +1,6 @@
 use gas::prelude::*;
 
+mod allocation;
 pub mod errors;
 pub mod keys;
 mod metrics;
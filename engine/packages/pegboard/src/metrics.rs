@@ -11,24 +11,24 @@ lazy_static::lazy_static! {
 		.with_description("Duplicate client event that was attempted to be inserted.")
 		.build();
 
-	/// Expected attributes: "client_id", "flavor", "state"
+	/// Expected attributes: "runner_id", "runner_name"
 	pub static ref CLIENT_CPU_TOTAL: Gauge<f64> = METER.f64_gauge("rivet_pegboard_client_cpu_total")
-		.with_description("Total millicores of cpu available on a client.")
+		.with_description("Total millicores of cpu advertised by a runner.")
 		.build();
 
-	/// Expected attributes: "client_id", "flavor", "state"
+	/// Expected attributes: "runner_id", "runner_name"
 	pub static ref CLIENT_MEMORY_TOTAL: Gauge<f64> = METER.f64_gauge("rivet_pegboard_client_memory_total")
-		.with_description("Total MiB of memory available on a client.")
+		.with_description("Total MiB of memory advertised by a runner.")
 		.build();
 
-	/// Expected attributes: "client_id", "flavor", "state"
+	/// Expected attributes: "runner_id", "runner_name"
 	pub static ref CLIENT_CPU_ALLOCATED: Gauge<f64> = METER.f64_gauge("rivet_pegboard_client_cpu_allocated")
-		.with_description("Total millicores of cpu allocated on a client.")
+		.with_description("Total millicores of cpu allocated to actors on a runner.")
 		.build();
 
-	/// Expected attributes: "client_id", "flavor", "state"
+	/// Expected attributes: "runner_id", "runner_name"
 	pub static ref CLIENT_MEMORY_ALLOCATED: Gauge<f64> = METER.f64_gauge("rivet_pegboard_client_memory_allocated")
-		.with_description("Total MiB of memory allocated on a client.")
+		.with_description("Total MiB of memory allocated to actors on a runner.")
 		.build();
 
 	/// Has no expected attributes
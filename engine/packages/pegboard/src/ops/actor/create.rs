@@ -1,7 +1,7 @@
 use anyhow::Result;
 use gas::prelude::*;
 use rivet_api_util::{Method, request_remote_datacenter};
-use rivet_types::actors::{Actor, CrashPolicy};
+use rivet_types::actors::{Actor, CrashPolicy, PlacementConstraints, Resources, RestartPolicy};
 
 #[derive(Debug)]
 pub struct Input {
@@ -11,6 +11,9 @@ pub struct Input {
 	pub key: Option<String>,
 	pub runner_name_selector: String,
 	pub crash_policy: CrashPolicy,
+	pub restart_policy: RestartPolicy,
+	pub placement: PlacementConstraints,
+	pub resources: Option<Resources>,
 	pub input: Option<String>,
 	/// If true, will handle ForwardToDatacenter errors by forwarding the request to the correct datacenter.
 	/// Used by api-public. api-peer should set this to false.
@@ -49,6 +52,9 @@ pub async fn pegboard_actor_create(ctx: &OperationCtx, input: &Input) -> Result<
 		runner_name_selector: input.runner_name_selector.clone(),
 		input: input.input.clone(),
 		crash_policy: input.crash_policy,
+		restart_policy: input.restart_policy,
+		placement: input.placement.clone(),
+		resources: input.resources,
 	})
 	.tag("actor_id", input.actor_id)
 	.dispatch()
@@ -76,7 +82,10 @@ pub async fn pegboard_actor_create(ctx: &OperationCtx, input: &Input) -> Result<
 						input.key.clone(),
 						input.runner_name_selector.clone(),
 						input.input.clone(),
-					input.crash_policy
+						input.crash_policy,
+						input.restart_policy,
+						input.placement.clone(),
+						input.resources,
 					).await;
 				}
 			}
@@ -116,6 +125,9 @@ async fn forward_to_datacenter(
 	runner_name_selector: String,
 	input: Option<String>,
 	crash_policy: CrashPolicy,
+	restart_policy: RestartPolicy,
+	placement: PlacementConstraints,
+	resources: Option<Resources>,
 ) -> Result<Output> {
 	// Get the datacenter configuration
 	let _target_dc = ctx
@@ -149,6 +161,9 @@ async fn forward_to_datacenter(
 			input,
 			runner_name_selector,
 			crash_policy,
+			restart_policy: (restart_policy != RestartPolicy::default()).then_some(restart_policy),
+			placement: (!placement.is_empty()).then_some(placement),
+			resources,
 		}),
 	)
 	.await?;
@@ -92,6 +92,9 @@ pub async fn pegboard_actor_get(ctx: &OperationCtx, input: &Input) -> Result<Out
 			sleep_ts: actor_state.sleep_ts,
 			connectable_ts: actor_state.connectable_ts,
 			destroy_ts: actor_state.destroy_ts,
+
+			restart_count: actor_state.restart_count,
+			next_restart_ts: actor_state.next_restart_ts,
 		});
 	}
 
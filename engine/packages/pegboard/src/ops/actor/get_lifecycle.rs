@@ -0,0 +1,37 @@
+use gas::prelude::*;
+use universaldb::utils::IsolationLevel::*;
+
+use crate::{
+	keys,
+	workflows::actor::{GetLifecycleInput, Lifecycle},
+};
+
+#[derive(Debug)]
+pub struct Input {
+	pub actor_id: Id,
+}
+
+/// Reads the lifecycle state of an actor from its workflow. Returns `None` if the actor does not exist or has
+/// not finished being created yet.
+#[operation]
+pub async fn pegboard_actor_get_lifecycle(
+	ctx: &OperationCtx,
+	input: &Input,
+) -> Result<Option<Lifecycle>> {
+	let workflow_id = ctx
+		.udb()?
+		.run(|tx| async move {
+			let tx = tx.with_subspace(keys::subspace());
+
+			tx.read_opt(&keys::actor::WorkflowIdKey::new(input.actor_id), Snapshot)
+				.await
+		})
+		.custom_instrument(tracing::info_span!("actor_get_lifecycle_tx"))
+		.await?;
+
+	let Some(workflow_id) = workflow_id else {
+		return Ok(None);
+	};
+
+	ctx.query(workflow_id, GetLifecycleInput {}).await
+}
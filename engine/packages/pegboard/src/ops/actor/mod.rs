@@ -2,6 +2,7 @@ pub mod create;
 pub mod get;
 pub mod get_for_gateway;
 pub mod get_for_key;
+pub mod get_lifecycle;
 pub mod get_reservation_for_key;
 pub mod get_runner;
 pub mod list_for_ns;
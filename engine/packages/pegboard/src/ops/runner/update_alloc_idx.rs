@@ -1,8 +1,9 @@
 use gas::prelude::*;
+use rivet_metrics::KeyValue;
 use universaldb::options::ConflictRangeType;
 use universaldb::utils::IsolationLevel::*;
 
-use crate::{keys, workflows::runner::RUNNER_ELIGIBLE_THRESHOLD_MS};
+use crate::{keys, metrics, workflows::runner::RUNNER_ELIGIBLE_THRESHOLD_MS};
 
 #[derive(Debug)]
 pub struct Input {
@@ -45,7 +46,7 @@ pub enum RunnerEligibility {
 
 #[operation]
 pub async fn pegboard_runner_update_alloc_idx(ctx: &OperationCtx, input: &Input) -> Result<Output> {
-	let notifications = ctx
+	let (notifications, resource_usage) = ctx
 		.udb()?
 		.run(|tx| {
 			let runners = input.runners.clone();
@@ -53,6 +54,7 @@ pub async fn pegboard_runner_update_alloc_idx(ctx: &OperationCtx, input: &Input)
 			async move {
 				let tx = tx.with_subspace(keys::subspace());
 				let mut notifications = Vec::new();
+				let mut resource_usage = Vec::new();
 
 				// TODO: Parallelize
 				for runner in &runners {
@@ -63,6 +65,10 @@ pub async fn pegboard_runner_update_alloc_idx(ctx: &OperationCtx, input: &Input)
 					let remaining_slots_key =
 						keys::runner::RemainingSlotsKey::new(runner.runner_id);
 					let total_slots_key = keys::runner::TotalSlotsKey::new(runner.runner_id);
+					let remaining_resources_key =
+						keys::runner::RemainingResourcesKey::new(runner.runner_id);
+					let total_resources_key =
+						keys::runner::TotalResourcesKey::new(runner.runner_id);
 					let last_ping_ts_key = keys::runner::LastPingTsKey::new(runner.runner_id);
 					let expired_ts_key = keys::runner::ExpiredTsKey::new(runner.runner_id);
 
@@ -73,6 +79,8 @@ pub async fn pegboard_runner_update_alloc_idx(ctx: &OperationCtx, input: &Input)
 						version_entry,
 						remaining_slots_entry,
 						total_slots_entry,
+						remaining_resources_entry,
+						total_resources_entry,
 						last_ping_ts_entry,
 						expired_ts_entry,
 					) = tokio::try_join!(
@@ -82,6 +90,8 @@ pub async fn pegboard_runner_update_alloc_idx(ctx: &OperationCtx, input: &Input)
 						tx.read_opt(&version_key, Serializable),
 						tx.read_opt(&remaining_slots_key, Serializable),
 						tx.read_opt(&total_slots_key, Serializable),
+						tx.read_opt(&remaining_resources_key, Serializable),
+						tx.read_opt(&total_resources_key, Serializable),
 						tx.read_opt(&last_ping_ts_key, Serializable),
 						tx.read_opt(&expired_ts_key, Serializable),
 					)?;
@@ -124,33 +134,59 @@ pub async fn pegboard_runner_update_alloc_idx(ctx: &OperationCtx, input: &Input)
 						}
 					}
 
-					let remaining_millislots = (remaining_slots * 1000) / total_slots;
+					// Resources are zero if the runner did not advertise them
+					let remaining_resources = remaining_resources_entry.unwrap_or_default();
+					let total_resources = total_resources_entry.unwrap_or_default();
+					let runner_alloc_key_data = || rivet_data::converted::RunnerAllocIdxKeyData {
+						workflow_id,
+						remaining_slots,
+						total_slots,
+						remaining_cpu_millicores: remaining_resources.cpu_millicores,
+						total_cpu_millicores: total_resources.cpu_millicores,
+						remaining_memory_mib: remaining_resources.memory_mib,
+						total_memory_mib: total_resources.memory_mib,
+					};
+					let remaining_capacity = runner_alloc_key_data().remaining_capacity();
+
+					if total_resources != Default::default() {
+						resource_usage.push((
+							runner.runner_id,
+							name.clone(),
+							remaining_resources,
+							total_resources,
+						));
+					}
 
 					let old_alloc_key = keys::ns::RunnerAllocIdxKey::new(
 						namespace_id,
 						name.clone(),
 						version,
-						remaining_millislots,
+						remaining_capacity,
+						old_last_ping_ts,
+						runner.runner_id,
+					);
+					// Replaced by `old_alloc_key` below if it still exists
+					let legacy_alloc_key = keys::ns::RunnerAllocIdxKey::new_legacy(
+						namespace_id,
+						name.clone(),
+						version,
+						remaining_slots,
 						old_last_ping_ts,
 						runner.runner_id,
 					);
 
 					// Add read conflict
 					tx.add_conflict_key(&old_alloc_key, ConflictRangeType::Read)?;
+					tx.add_conflict_key(&legacy_alloc_key, ConflictRangeType::Read)?;
 
 					match runner.action {
 						Action::ClearIdx => {
+							tx.delete(&legacy_alloc_key);
 							tx.delete(&old_alloc_key);
 						}
 						Action::AddIdx => {
-							tx.write(
-								&old_alloc_key,
-								rivet_data::converted::RunnerAllocIdxKeyData {
-									workflow_id,
-									remaining_slots,
-									total_slots,
-								},
-							)?;
+							tx.delete(&legacy_alloc_key);
+							tx.write(&old_alloc_key, runner_alloc_key_data())?;
 						}
 						Action::UpdatePing { rtt } => {
 							let last_ping_ts = util::timestamp::now();
@@ -161,9 +197,15 @@ pub async fn pegboard_runner_update_alloc_idx(ctx: &OperationCtx, input: &Input)
 							let last_rtt_key = keys::runner::LastRttKey::new(runner.runner_id);
 							tx.write(&last_rtt_key, rtt)?;
 
+							let (exists, legacy_exists) = tokio::try_join!(
+								tx.exists(&old_alloc_key, Serializable),
+								tx.exists(&legacy_alloc_key, Serializable),
+							)?;
+
 							// Only update allocation idx if it existed before
-							if tx.exists(&old_alloc_key, Serializable).await? {
+							if exists || legacy_exists {
 								// Clear old key
+								tx.delete(&legacy_alloc_key);
 								tx.delete(&old_alloc_key);
 
 								tx.write(
@@ -171,15 +213,11 @@ pub async fn pegboard_runner_update_alloc_idx(ctx: &OperationCtx, input: &Input)
 										namespace_id,
 										name.clone(),
 										version,
-										remaining_millislots,
+										remaining_capacity,
 										last_ping_ts,
 										runner.runner_id,
 									),
-									rivet_data::converted::RunnerAllocIdxKeyData {
-										workflow_id,
-										remaining_slots,
-										total_slots,
-									},
+									runner_alloc_key_data(),
 								)?;
 
 								if last_ping_ts.saturating_sub(old_last_ping_ts)
@@ -196,11 +234,31 @@ pub async fn pegboard_runner_update_alloc_idx(ctx: &OperationCtx, input: &Input)
 					}
 				}
 
-				Ok(notifications)
+				Ok((notifications, resource_usage))
 			}
 		})
 		.custom_instrument(tracing::info_span!("runner_update_alloc_idx_tx"))
 		.await?;
 
+	for (runner_id, name, remaining, total) in resource_usage {
+		let attrs = [
+			KeyValue::new("runner_id", runner_id.to_string()),
+			KeyValue::new("runner_name", name),
+		];
+
+		metrics::CLIENT_CPU_TOTAL.record(total.cpu_millicores as f64, &attrs);
+		metrics::CLIENT_MEMORY_TOTAL.record(total.memory_mib as f64, &attrs);
+		metrics::CLIENT_CPU_ALLOCATED.record(
+			total
+				.cpu_millicores
+				.saturating_sub(remaining.cpu_millicores) as f64,
+			&attrs,
+		);
+		metrics::CLIENT_MEMORY_ALLOCATED.record(
+			total.memory_mib.saturating_sub(remaining.memory_mib) as f64,
+			&attrs,
+		);
+	}
+
 	Ok(Output { notifications })
 }
@@ -31,3 +31,19 @@ impl std::fmt::Display for GatewayReceiverSubject {
 		write!(f, "pegboard.gateway.{}", self.gateway_id)
 	}
 }
+
+pub struct ActorKvSubject {
+	actor_id: Id,
+}
+
+impl ActorKvSubject {
+	pub fn new(actor_id: Id) -> Self {
+		Self { actor_id }
+	}
+}
+
+impl std::fmt::Display for ActorKvSubject {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "pegboard.actor_kv.{}", self.actor_id)
+	}
+}
@@ -209,64 +209,134 @@ pub(crate) async fn clear_slot(
 	// consistency during rescheduling and forced deletion.
 	tx.delete(&keys::runner::ActorKey::new(runner_id, actor_id));
 
+	if let Some(placement) = tx
+		.read_opt(&keys::actor::PlacementKey::new(actor_id), Serializable)
+		.await?
+	{
+		for group in placement.anti_affinity_groups {
+			tx.delete(&keys::runner::AntiAffinityKey::new(
+				runner_id, group, actor_id,
+			));
+		}
+	}
+
 	let runner_workflow_id_key = keys::runner::WorkflowIdKey::new(runner_id);
 	let runner_version_key = keys::runner::VersionKey::new(runner_id);
 	let runner_remaining_slots_key = keys::runner::RemainingSlotsKey::new(runner_id);
 	let runner_total_slots_key = keys::runner::TotalSlotsKey::new(runner_id);
+	let runner_remaining_resources_key = keys::runner::RemainingResourcesKey::new(runner_id);
+	let runner_total_resources_key = keys::runner::TotalResourcesKey::new(runner_id);
 	let runner_last_ping_ts_key = keys::runner::LastPingTsKey::new(runner_id);
+	let actor_resources_key = keys::actor::ResourcesKey::new(actor_id);
 
 	let (
 		runner_workflow_id,
 		runner_version,
 		runner_remaining_slots,
 		runner_total_slots,
+		runner_remaining_resources,
+		runner_total_resources,
 		runner_last_ping_ts,
+		actor_resources,
 	) = tokio::try_join!(
 		tx.read(&runner_workflow_id_key, Serializable),
 		tx.read(&runner_version_key, Serializable),
 		tx.read(&runner_remaining_slots_key, Serializable),
 		tx.read(&runner_total_slots_key, Serializable),
+		tx.read_opt(&runner_remaining_resources_key, Serializable),
+		tx.read_opt(&runner_total_resources_key, Serializable),
 		tx.read(&runner_last_ping_ts_key, Serializable),
+		tx.read_opt(&actor_resources_key, Serializable),
 	)?;
 
-	let old_runner_remaining_millislots = (runner_remaining_slots * 1000) / runner_total_slots;
+	// Resources are zero if the runner did not advertise them
+	let runner_remaining_resources = runner_remaining_resources.unwrap_or_default();
+	let runner_total_resources = runner_total_resources.unwrap_or_default();
+
+	let old_runner_alloc_key_data = rivet_data::converted::RunnerAllocIdxKeyData {
+		workflow_id: runner_workflow_id,
+		remaining_slots: runner_remaining_slots,
+		total_slots: runner_total_slots,
+		remaining_cpu_millicores: runner_remaining_resources.cpu_millicores,
+		total_cpu_millicores: runner_total_resources.cpu_millicores,
+		remaining_memory_mib: runner_remaining_resources.memory_mib,
+		total_memory_mib: runner_total_resources.memory_mib,
+	};
+
 	let new_runner_remaining_slots = runner_remaining_slots + 1;
 
 	// Write new remaining slots
 	tx.write(&runner_remaining_slots_key, new_runner_remaining_slots)?;
 
+	// Return the resources of the actor if the runner accounts for them
+	let new_runner_remaining_resources = match actor_resources {
+		Some(actor_resources) if runner_total_resources != Default::default() => {
+			let new_runner_remaining_resources = rivet_types::actors::Resources {
+				cpu_millicores: runner_remaining_resources
+					.cpu_millicores
+					.saturating_add(actor_resources.cpu_millicores)
+					.min(runner_total_resources.cpu_millicores),
+				memory_mib: runner_remaining_resources
+					.memory_mib
+					.saturating_add(actor_resources.memory_mib)
+					.min(runner_total_resources.memory_mib),
+			};
+
+			tx.write(
+				&runner_remaining_resources_key,
+				new_runner_remaining_resources,
+			)?;
+
+			new_runner_remaining_resources
+		}
+		_ => runner_remaining_resources,
+	};
+
 	let old_runner_alloc_key = keys::ns::RunnerAllocIdxKey::new(
 		namespace_id,
 		runner_name_selector.to_string(),
 		runner_version,
-		old_runner_remaining_millislots,
+		old_runner_alloc_key_data.remaining_capacity(),
 		runner_last_ping_ts,
 		runner_id,
 	);
+	// Replaced by the new key below if it still exists
+	let legacy_runner_alloc_key = keys::ns::RunnerAllocIdxKey::new_legacy(
+		namespace_id,
+		runner_name_selector.to_string(),
+		runner_version,
+		old_runner_alloc_key_data.remaining_slots,
+		runner_last_ping_ts,
+		runner_id,
+	);
+
+	let (exists, legacy_exists) = tokio::try_join!(
+		tx.exists(&old_runner_alloc_key, Serializable),
+		tx.exists(&legacy_runner_alloc_key, Serializable),
+	)?;
 
 	// Only update allocation idx if it existed before
-	if tx.exists(&old_runner_alloc_key, Serializable).await? {
+	if exists || legacy_exists {
 		// Clear old key
+		tx.delete(&legacy_runner_alloc_key);
 		tx.delete(&old_runner_alloc_key);
 
-		let new_remaining_millislots = (new_runner_remaining_slots * 1000) / runner_total_slots;
+		let new_runner_alloc_key_data = rivet_data::converted::RunnerAllocIdxKeyData {
+			remaining_slots: new_runner_remaining_slots,
+			remaining_cpu_millicores: new_runner_remaining_resources.cpu_millicores,
+			remaining_memory_mib: new_runner_remaining_resources.memory_mib,
+			..old_runner_alloc_key_data
+		};
 		let new_runner_alloc_key = keys::ns::RunnerAllocIdxKey::new(
 			namespace_id,
 			runner_name_selector.to_string(),
 			runner_version,
-			new_remaining_millislots,
+			new_runner_alloc_key_data.remaining_capacity(),
 			runner_last_ping_ts,
 			runner_id,
 		);
 
-		tx.write(
-			&new_runner_alloc_key,
-			rivet_data::converted::RunnerAllocIdxKeyData {
-				workflow_id: runner_workflow_id,
-				remaining_slots: new_runner_remaining_slots,
-				total_slots: runner_total_slots,
-			},
-		)?;
+		tx.write(&new_runner_alloc_key, new_runner_alloc_key_data)?;
 	}
 
 	if for_serverless {
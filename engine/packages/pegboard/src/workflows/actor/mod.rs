@@ -1,7 +1,7 @@
 use futures_util::FutureExt;
 use gas::prelude::*;
 use rivet_runner_protocol as protocol;
-use rivet_types::actors::CrashPolicy;
+use rivet_types::actors::{CrashPolicy, PlacementConstraints, Resources, RestartPolicy};
 
 use crate::{errors, workflows::runner::AllocatePendingActorsInput};
 
@@ -10,15 +10,10 @@ mod destroy;
 mod runtime;
 mod setup;
 
-/// Time to delay an actor from rescheduling after a rescheduling failure.
-const BASE_RETRY_TIMEOUT_MS: usize = 2000;
 /// How long to wait after creating and not receiving a starting state before setting actor as lost.
 const ACTOR_START_THRESHOLD_MS: i64 = util::duration::seconds(30);
 /// How long to wait after stopping and not receiving a stop state before setting actor as lost.
 const ACTOR_STOP_THRESHOLD_MS: i64 = util::duration::seconds(30);
-/// How long an actor goes without retries before it's retry count is reset to 0, effectively resetting its
-/// backoff to 0.
-const RETRY_RESET_DURATION_MS: i64 = util::duration::minutes(10);
 
 #[derive(Clone, Debug, Serialize, Deserialize, Hash)]
 pub struct Input {
@@ -29,6 +24,12 @@ pub struct Input {
 	pub namespace_id: Id,
 	pub runner_name_selector: String,
 	pub crash_policy: CrashPolicy,
+	#[serde(default)]
+	pub restart_policy: RestartPolicy,
+	#[serde(default)]
+	pub placement: PlacementConstraints,
+	#[serde(default)]
+	pub resources: Option<Resources>,
 
 	/// Arbitrary user string.
 	pub input: Option<String>,
@@ -42,6 +43,12 @@ pub struct State {
 	pub namespace_id: Id,
 	pub runner_name_selector: String,
 	pub crash_policy: CrashPolicy,
+	#[serde(default)]
+	pub restart_policy: RestartPolicy,
+	#[serde(default)]
+	pub placement: PlacementConstraints,
+	#[serde(default)]
+	pub resources: Option<Resources>,
 
 	pub create_ts: i64,
 	pub create_complete_ts: Option<i64>,
@@ -60,6 +67,14 @@ pub struct State {
 	pub pending_allocation_ts: Option<i64>,
 	pub destroy_ts: Option<i64>,
 
+	/// Crash restarts since `restart_window_start_ts`.
+	#[serde(default)]
+	pub restart_count: u32,
+	#[serde(default)]
+	pub restart_window_start_ts: Option<i64>,
+	#[serde(default)]
+	pub next_restart_ts: Option<i64>,
+
 	// Null if not allocated
 	pub runner_id: Option<Id>,
 	pub runner_workflow_id: Option<Id>,
@@ -72,6 +87,9 @@ impl State {
 		namespace_id: Id,
 		runner_name_selector: String,
 		crash_policy: CrashPolicy,
+		restart_policy: RestartPolicy,
+		placement: PlacementConstraints,
+		resources: Option<Resources>,
 		create_ts: i64,
 	) -> Self {
 		State {
@@ -81,6 +99,9 @@ impl State {
 			namespace_id,
 			runner_name_selector,
 			crash_policy,
+			restart_policy,
+			placement,
+			resources,
 
 			create_ts,
 			create_complete_ts: None,
@@ -95,6 +116,10 @@ impl State {
 			complete_ts: None,
 			destroy_ts: None,
 
+			restart_count: 0,
+			restart_window_start_ts: None,
+			next_restart_ts: None,
+
 			runner_id: None,
 			runner_workflow_id: None,
 		}
@@ -122,6 +147,10 @@ pub async fn pegboard_actor(ctx: &mut WorkflowCtx, input: &Input) -> Result<()>
 			key: input.key.clone(),
 			namespace_id: input.namespace_id,
 			input: input.input.clone(),
+			runner_name_selector: input.runner_name_selector.clone(),
+			restart_policy: input.restart_policy,
+			placement: input.placement.clone(),
+			resources: input.resources,
 		})
 		.await?;
 
@@ -142,6 +171,9 @@ pub async fn pegboard_actor(ctx: &mut WorkflowCtx, input: &Input) -> Result<()>
 		namespace_id: input.namespace_id,
 		runner_name_selector: input.runner_name_selector.clone(),
 		crash_policy: input.crash_policy,
+		restart_policy: input.restart_policy,
+		placement: input.placement.clone(),
+		resources: input.resources,
 		create_ts: ctx.create_ts(),
 	})
 	.await?;
@@ -264,9 +296,13 @@ pub async fn pegboard_actor(ctx: &mut WorkflowCtx, input: &Input) -> Result<()>
 						} else {
 							tracing::warn!(actor_id=?input.actor_id, "actor lost");
 
-							// Fake signal
+							// Fake signal. While migrating, the gc timeout belongs to the next generation
 							Main::Lost(Lost {
-								generation: state.generation,
+								generation: state
+									.migration
+									.as_ref()
+									.map(|migration| migration.generation)
+									.unwrap_or(state.generation),
 								force_reschedule: false,
 							})
 						}
@@ -290,8 +326,18 @@ pub async fn pegboard_actor(ctx: &mut WorkflowCtx, input: &Input) -> Result<()>
 
 					match sig {
 						Main::Event(sig) => {
+							let generation = crate::utils::event_generation(&sig.inner);
+
+							if state.migration.as_ref().map(|migration| migration.generation)
+								== Some(generation)
+							{
+								handle_migration_event(ctx, &input, state, sig.inner).await?;
+
+								return Ok(Loop::Continue);
+							}
+
 							// Ignore state updates for previous generations
-							if crate::utils::event_generation(&sig.inner) != state.generation {
+							if generation != state.generation {
 								return Ok(Loop::Continue);
 							}
 
@@ -306,6 +352,8 @@ pub async fn pegboard_actor(ctx: &mut WorkflowCtx, input: &Input) -> Result<()>
 									..
 								}) => match intent {
 									protocol::ActorIntent::ActorIntentSleep => {
+										abort_migration(ctx, &input, state, true).await?;
+
 										if !state.sleeping {
 											state.gc_timeout_ts =
 												Some(util::timestamp::now() + ACTOR_STOP_THRESHOLD_MS);
@@ -329,6 +377,8 @@ pub async fn pegboard_actor(ctx: &mut WorkflowCtx, input: &Input) -> Result<()>
 										}
 									}
 									protocol::ActorIntent::ActorIntentStop => {
+										abort_migration(ctx, &input, state, true).await?;
+
 										state.gc_timeout_ts =
 											Some(util::timestamp::now() + ACTOR_STOP_THRESHOLD_MS);
 
@@ -371,6 +421,8 @@ pub async fn pegboard_actor(ctx: &mut WorkflowCtx, input: &Input) -> Result<()>
 									protocol::ActorState::ActorStateStopped(
 										protocol::ActorStateStopped { code, .. },
 									) => {
+										abort_migration(ctx, &input, state, true).await?;
+
 										if let Some(res) =
 											handle_stopped(ctx, &input, state, Some(code), false, false)
 												.await?
@@ -393,7 +445,7 @@ pub async fn pegboard_actor(ctx: &mut WorkflowCtx, input: &Input) -> Result<()>
 									state.sleeping = false;
 									state.will_wake = false;
 
-									match runtime::reschedule_actor(ctx, &input, state, false).await? {
+									match runtime::reschedule_actor(ctx, &input, state, false, false).await? {
 										runtime::SpawnActorOutput::Allocated { .. } => {},
 										runtime::SpawnActorOutput::Sleep => {
 											state.sleeping = true;
@@ -428,18 +480,53 @@ pub async fn pegboard_actor(ctx: &mut WorkflowCtx, input: &Input) -> Result<()>
 							}
 						}
 						Main::Lost(sig) => {
+							// The next generation was lost before the migration completed, keep running the
+							// current generation
+							if state.migration.as_ref().map(|migration| migration.generation)
+								== Some(sig.generation)
+							{
+								abort_migration(ctx, &input, state, true).await?;
+
+								return Ok(Loop::Continue);
+							}
+
 							// Ignore state updates for previous generations
 							if sig.generation != state.generation {
 								return Ok(Loop::Continue);
 							}
 
+							abort_migration(ctx, &input, state, true).await?;
+
 							if let Some(res) =
 								handle_stopped(ctx, &input, state, None, true, sig.force_reschedule).await?
 							{
 								return Ok(Loop::Break(res));
 							}
 						}
+						Main::Migrate(sig) => {
+							// Ignore migrations for previous generations or if already migrating
+							if sig.generation != state.generation || state.migration.is_some() {
+								return Ok(Loop::Continue);
+							}
+
+							// Only running actors are migrated, anything else (and actors that can't be
+							// allocated elsewhere) is handled as if the runner was lost
+							let migrating = !state.sleeping
+								&& state.gc_timeout_ts.is_none()
+								&& state.runner_workflow_id.is_some()
+								&& start_migration(ctx, &input, state).await?;
+
+							if !migrating {
+								if let Some(res) =
+									handle_stopped(ctx, &input, state, None, true, false).await?
+								{
+									return Ok(Loop::Break(res));
+								}
+							}
+						}
 						Main::Destroy(_) => {
+							abort_migration(ctx, &input, state, true).await?;
+
 							return Ok(Loop::Break(runtime::LifecycleRes {
 								generation: state.generation,
 								kill: true,
@@ -555,7 +642,7 @@ async fn handle_stopped(
 
 	// Reschedule no matter what
 	if force_reschedule {
-		match runtime::reschedule_actor(ctx, &input, state, true).await? {
+		match runtime::reschedule_actor(ctx, &input, state, true, false).await? {
 			runtime::SpawnActorOutput::Allocated { .. } => {}
 			// NOTE: This should be unreachable because force_reschedule is true
 			runtime::SpawnActorOutput::Sleep => {
@@ -578,11 +665,13 @@ async fn handle_stopped(
 
 		match (input.crash_policy, failed) {
 			(CrashPolicy::Restart, true) => {
-				match runtime::reschedule_actor(ctx, &input, state, false).await? {
+				match runtime::reschedule_actor(ctx, &input, state, false, true).await? {
 					runtime::SpawnActorOutput::Allocated { .. } => {}
-					// NOTE: Its not possible for `SpawnActorOutput::Sleep` to be returned here, the crash
-					// policy is `Restart`.
-					runtime::SpawnActorOutput::Sleep | runtime::SpawnActorOutput::Destroy => {
+					// Restarts exhausted
+					runtime::SpawnActorOutput::Sleep => {
+						state.sleeping = true;
+					}
+					runtime::SpawnActorOutput::Destroy => {
 						// Destroyed early
 						return Ok(Some(runtime::LifecycleRes {
 							generation: state.generation,
@@ -617,7 +706,7 @@ async fn handle_stopped(
 	else if state.will_wake {
 		state.sleeping = false;
 
-		match runtime::reschedule_actor(ctx, &input, state, false).await? {
+		match runtime::reschedule_actor(ctx, &input, state, false, false).await? {
 			runtime::SpawnActorOutput::Allocated { .. } => {}
 			runtime::SpawnActorOutput::Sleep => {
 				state.sleeping = true;
@@ -645,6 +734,225 @@ async fn handle_stopped(
 	Ok(None)
 }
 
+/// Starts the next generation of a running actor on another runner. The current generation keeps running
+/// and receiving traffic until the next generation reports `ActorStateRunning`. Returns false if there is no
+/// availability.
+async fn start_migration(
+	ctx: &mut WorkflowCtx,
+	input: &Input,
+	state: &mut runtime::LifecycleState,
+) -> Result<bool> {
+	let next_generation = state.next_generation();
+
+	let Some(allocate_res) = ctx
+		.activity(runtime::AllocateMigrationInput {
+			actor_id: input.actor_id,
+			generation: next_generation,
+		})
+		.await?
+	else {
+		tracing::debug!(actor_id=?input.actor_id, "no availability to migrate actor");
+
+		return Ok(false);
+	};
+
+	tracing::debug!(
+		actor_id=?input.actor_id,
+		generation=%next_generation,
+		runner_id=?allocate_res.runner_id,
+		"migrating actor",
+	);
+
+	ctx.signal(crate::workflows::runner::Command {
+		inner: runtime::start_actor_command(input, next_generation)?,
+	})
+	.to_workflow_id(allocate_res.runner_workflow_id)
+	.send()
+	.await?;
+
+	state.last_migration_generation = next_generation;
+	state.migration = Some(runtime::MigrationState {
+		generation: next_generation,
+		runner_id: allocate_res.runner_id,
+		runner_workflow_id: allocate_res.runner_workflow_id,
+	});
+	state.gc_timeout_ts = Some(util::timestamp::now() + ACTOR_START_THRESHOLD_MS);
+
+	Ok(true)
+}
+
+/// Handles events from the next generation of a migrating actor.
+async fn handle_migration_event(
+	ctx: &mut WorkflowCtx,
+	input: &Input,
+	state: &mut runtime::LifecycleState,
+	event: protocol::Event,
+) -> Result<()> {
+	match event {
+		protocol::Event::EventActorStateUpdate(protocol::EventActorStateUpdate {
+			state: protocol::ActorState::ActorStateRunning,
+			..
+		}) => {
+			let Some(migration) = state.migration.take() else {
+				return Ok(());
+			};
+
+			let old_generation = state.generation;
+			let old_runner_workflow_id = state.runner_workflow_id;
+
+			// Start the handoff before traffic is routed to the new generation so the state is handed off as
+			// early as possible. The runner handles the handoff before the stop command sent below.
+			if let Some(old_runner_workflow_id) = old_runner_workflow_id {
+				ctx.signal(crate::workflows::runner::Command {
+					inner: protocol::Command::CommandHandoffActor(protocol::CommandHandoffActor {
+						actor_id: input.actor_id.to_string(),
+						generation: old_generation,
+						new_generation: migration.generation,
+						new_runner_id: migration.runner_id.to_string(),
+					}),
+				})
+				.to_workflow_id(old_runner_workflow_id)
+				.send()
+				.await?;
+			}
+
+			// Flip gateway routing to the new runner
+			ctx.activity(runtime::CompleteMigrationInput {
+				actor_id: input.actor_id,
+				runner_id: migration.runner_id,
+				runner_workflow_id: migration.runner_workflow_id,
+			})
+			.await?;
+
+			state.generation = migration.generation;
+			state.runner_id = Some(migration.runner_id);
+			state.runner_workflow_id = Some(migration.runner_workflow_id);
+			state.gc_timeout_ts = None;
+
+			// Only stop the old generation once traffic is routed to the new one
+			if let Some(old_runner_workflow_id) = old_runner_workflow_id {
+				ctx.signal(crate::workflows::runner::Command {
+					inner: protocol::Command::CommandStopActor(protocol::CommandStopActor {
+						actor_id: input.actor_id.to_string(),
+						generation: old_generation,
+					}),
+				})
+				.to_workflow_id(old_runner_workflow_id)
+				.send()
+				.await?;
+			}
+
+			ctx.msg(Ready {
+				runner_id: migration.runner_id,
+			})
+			.tag("actor_id", input.actor_id)
+			.send()
+			.await?;
+		}
+		protocol::Event::EventActorStateUpdate(protocol::EventActorStateUpdate {
+			state: protocol::ActorState::ActorStateStopped(_),
+			..
+		}) => {
+			tracing::warn!(
+				actor_id=?input.actor_id,
+				"next generation stopped before migration completed",
+			);
+
+			abort_migration(ctx, input, state, false).await?;
+		}
+		_ => {
+			tracing::debug!(
+				actor_id=?input.actor_id,
+				"ignoring event from next generation before migration completed",
+			);
+		}
+	}
+
+	Ok(())
+}
+
+/// Stops the next generation of a migrating actor and releases its slot. The current generation is left
+/// running. Does nothing if the actor is not migrating.
+async fn abort_migration(
+	ctx: &mut WorkflowCtx,
+	input: &Input,
+	state: &mut runtime::LifecycleState,
+	stop: bool,
+) -> Result<()> {
+	let Some(migration) = state.migration.take() else {
+		return Ok(());
+	};
+
+	tracing::debug!(actor_id=?input.actor_id, generation=%migration.generation, "aborting migration");
+
+	state.gc_timeout_ts = None;
+
+	if stop {
+		ctx.signal(crate::workflows::runner::Command {
+			inner: protocol::Command::CommandStopActor(protocol::CommandStopActor {
+				actor_id: input.actor_id.to_string(),
+				generation: migration.generation,
+			}),
+		})
+		.to_workflow_id(migration.runner_workflow_id)
+		.send()
+		.await?;
+	}
+
+	ctx.activity(runtime::ClearMigrationInput {
+		actor_id: input.actor_id,
+		runner_id: migration.runner_id,
+	})
+	.await?;
+
+	Ok(())
+}
+
+#[derive(Debug)]
+pub struct GetLifecycleInput {}
+
+/// Snapshot of the actor's lifecycle as of its last completed lifecycle loop iteration.
+#[derive(Debug)]
+pub struct Lifecycle {
+	pub generation: u32,
+	pub runner_id: Option<Id>,
+	pub runner_workflow_id: Option<Id>,
+	pub sleeping: bool,
+	pub alarm_ts: Option<i64>,
+	pub gc_timeout_ts: Option<i64>,
+}
+
+/// Reads the lifecycle state of an actor workflow. Returns `None` if the actor has not finished being
+/// created yet.
+pub struct GetLifecycle;
+
+impl gas::query::QueryInput for GetLifecycleInput {
+	type Query = GetLifecycle;
+}
+
+impl QueryTrait for GetLifecycle {
+	type Input = GetLifecycleInput;
+	type Output = Option<Lifecycle>;
+	type Workflow = Workflow;
+
+	const NAME: &'static str = "pegboard_actor_get_lifecycle";
+
+	fn handle(ctx: &QueryCtx, _input: &Self::Input) -> Result<Self::Output> {
+		let Some(state) = ctx.loop_state::<runtime::LifecycleState>()? else {
+			return Ok(None);
+		};
+
+		Ok(Some(Lifecycle {
+			generation: state.generation,
+			runner_id: state.runner_id,
+			runner_workflow_id: state.runner_workflow_id,
+			sleeping: state.sleeping,
+			alarm_ts: state.alarm_ts,
+			gc_timeout_ts: state.gc_timeout_ts,
+		}))
+	}
+}
+
 #[message("pegboard_actor_create_complete")]
 pub struct CreateComplete {}
 
@@ -682,6 +990,13 @@ pub struct Lost {
 	pub force_reschedule: bool,
 }
 
+/// Sent when the actor's runner starts draining. The actor is started on another runner before the current
+/// generation is stopped.
+#[signal("pegboard_actor_migrate")]
+pub struct Migrate {
+	pub generation: u32,
+}
+
 #[signal("pegboard_actor_destroy")]
 pub struct Destroy {}
 
@@ -701,5 +1016,6 @@ join_signal!(Main {
 	Event(Event),
 	Wake,
 	Lost,
+	Migrate,
 	Destroy,
 });
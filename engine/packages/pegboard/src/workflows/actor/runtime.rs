@@ -1,25 +1,28 @@
 use base64::Engine;
 use base64::prelude::BASE64_STANDARD;
 use futures_util::StreamExt;
-use futures_util::TryStreamExt;
 use gas::prelude::*;
+use rand::Rng;
 use rivet_metrics::KeyValue;
 use rivet_runner_protocol as protocol;
 use rivet_types::{
-	actors::CrashPolicy, keys::namespace::runner_config::RunnerConfigVariant,
+	actors::{CrashPolicy, RestartExhaustedAction, RestartPolicy},
+	keys::namespace::runner_config::RunnerConfigVariant,
 	runner_configs::RunnerConfigKind,
 };
 use std::time::Instant;
-use universaldb::options::{ConflictRangeType, MutationType, StreamingMode};
+use universaldb::options::{MutationType, StreamingMode};
 use universaldb::utils::{FormalKey, IsolationLevel::*};
 
-use crate::{keys, metrics, workflows::runner::RUNNER_ELIGIBLE_THRESHOLD_MS};
+use crate::{allocation, keys, metrics};
 
 use super::{
-	ACTOR_START_THRESHOLD_MS, Allocate, BASE_RETRY_TIMEOUT_MS, Destroy, Input, PendingAllocation,
-	RETRY_RESET_DURATION_MS, State, destroy,
+	ACTOR_START_THRESHOLD_MS, Allocate, Destroy, Input, PendingAllocation, State, destroy,
 };
 
+/// Max random delay added to the restart backoff so that actors crashing together don't restart together.
+const RESTART_JITTER_MS: u64 = 500;
+
 #[derive(Deserialize, Serialize)]
 pub struct LifecycleState {
 	pub generation: u32,
@@ -39,6 +42,14 @@ pub struct LifecycleState {
 	pub gc_timeout_ts: Option<i64>,
 
 	pub reschedule_state: RescheduleState,
+
+	/// Set while the next generation of this actor is starting on another runner.
+	#[serde(default)]
+	pub migration: Option<MigrationState>,
+	/// Generation of the last migration. Generations are not reused after a failed migration so late events
+	/// from it are not mistaken for a later generation.
+	#[serde(default)]
+	pub last_migration_generation: u32,
 }
 
 impl LifecycleState {
@@ -53,6 +64,8 @@ impl LifecycleState {
 			alarm_ts: None,
 			gc_timeout_ts: Some(util::timestamp::now() + ACTOR_START_THRESHOLD_MS),
 			reschedule_state: RescheduleState::default(),
+			migration: None,
+			last_migration_generation: 0,
 		}
 	}
 
@@ -67,8 +80,21 @@ impl LifecycleState {
 			alarm_ts: None,
 			gc_timeout_ts: None,
 			reschedule_state: RescheduleState::default(),
+			migration: None,
+			last_migration_generation: 0,
 		}
 	}
+
+	pub fn next_generation(&self) -> u32 {
+		self.generation.max(self.last_migration_generation) + 1
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MigrationState {
+	pub generation: u32,
+	pub runner_id: Id,
+	pub runner_workflow_id: Id,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -97,6 +123,7 @@ async fn update_runner(ctx: &ActivityCtx, input: &UpdateRunnerInput) -> Result<(
 
 	state.sleep_ts = None;
 	state.pending_allocation_ts = None;
+	state.next_restart_ts = None;
 	state.runner_id = Some(input.runner_id);
 	state.runner_workflow_id = Some(input.runner_workflow_id);
 
@@ -135,6 +162,9 @@ async fn allocate_actor(
 	let namespace_id = state.namespace_id;
 	let crash_policy = state.crash_policy;
 	let runner_name_selector = &state.runner_name_selector;
+	let placement = &state.placement;
+	let resources = state.resources;
+	let constrained = allocation::is_constrained(placement, resources);
 
 	// Check if valid serverless config exists for the current ns + runner name
 	let runner_config_res = ctx
@@ -150,14 +180,16 @@ async fn allocate_actor(
 			_ => false,
 		})
 		.unwrap_or_default();
+	let allocation_strategy = &runner_config_res
+		.first()
+		.map(|runner| runner.config.allocation_strategy.clone())
+		.unwrap_or_default();
 
 	// NOTE: This txn should closely resemble the one found in the allocate_pending_actors activity of the
 	// client wf
 	let (for_serverless, res) = ctx
 		.udb()?
 		.run(|tx| async move {
-			let ping_threshold_ts = util::timestamp::now() - RUNNER_ELIGIBLE_THRESHOLD_MS;
-
 			// Check if runner is an serverless runner
 			let for_serverless = tx
 				.with_subspace(namespace::keys::subspace())
@@ -184,121 +216,32 @@ async fn allocate_actor(
 				);
 			}
 
-			// Check if a queue exists
-			let pending_actor_subspace = keys::subspace().subspace(
-				&keys::ns::PendingActorByRunnerNameSelectorKey::subspace(
-					namespace_id,
-					runner_name_selector.clone(),
-				),
-			);
-			let queue_exists = tx
-				.get_ranges_keyvalues(
-					universaldb::RangeOption {
-						mode: StreamingMode::Exact,
-						limit: Some(1),
-						..(&pending_actor_subspace).into()
-					},
-					// NOTE: This is not Serializable because we don't want to conflict with other
-					// inserts/clears to this range
-					Snapshot,
-				)
-				.next()
-				.await
-				.is_some();
+			// Check if a queue exists. Constrained actors always try to allocate because the actors in the
+			// queue may not be able to use the same runners.
+			let queue_exists = !constrained
+				&& unconstrained_queue_exists(&tx, namespace_id, runner_name_selector).await;
 
 			if !queue_exists {
-				let runner_alloc_subspace =
-					keys::subspace().subspace(&keys::ns::RunnerAllocIdxKey::subspace(
-						namespace_id,
-						runner_name_selector.clone(),
-					));
-
-				let mut stream = tx.get_ranges_keyvalues(
-					universaldb::RangeOption {
-						mode: StreamingMode::Iterator,
-						..(&runner_alloc_subspace).into()
-					},
-					// NOTE: This is not Serializable because we don't want to conflict with all of the
-					// keys, just the one we choose
-					Snapshot,
-				);
-
-				let mut highest_version = None;
-
-				loop {
-					let Some(entry) = stream.try_next().await? else {
-						break;
-					};
-
-					let (old_runner_alloc_key, old_runner_alloc_key_data) =
-						tx.read_entry::<keys::ns::RunnerAllocIdxKey>(&entry)?;
-
-					if let Some(highest_version) = highest_version {
-						// We have passed all of the runners with the highest version. This is reachable if
-						// the ping of the highest version workers makes them ineligible
-						if old_runner_alloc_key.version < highest_version {
-							break;
-						}
-					} else {
-						highest_version = Some(old_runner_alloc_key.version);
-					}
-
-					// An empty runner means we have reached the end of the runners with the highest version
-					if old_runner_alloc_key.remaining_millislots == 0 {
-						break;
-					}
-
-					// Scan by last ping
-					if old_runner_alloc_key.last_ping_ts < ping_threshold_ts {
-						continue;
-					}
-
-					// Add read conflict only for this key
-					tx.add_conflict_key(&old_runner_alloc_key, ConflictRangeType::Read)?;
-
-					// Clear old entry
-					tx.delete(&old_runner_alloc_key);
-
-					let new_remaining_slots =
-						old_runner_alloc_key_data.remaining_slots.saturating_sub(1);
-					let new_remaining_millislots =
-						(new_remaining_slots * 1000) / old_runner_alloc_key_data.total_slots;
-
-					// Write new allocation key with 1 less slot
-					tx.write(
-						&keys::ns::RunnerAllocIdxKey::new(
-							namespace_id,
-							runner_name_selector.clone(),
-							old_runner_alloc_key.version,
-							new_remaining_millislots,
-							old_runner_alloc_key.last_ping_ts,
-							old_runner_alloc_key.runner_id,
-						),
-						rivet_data::converted::RunnerAllocIdxKeyData {
-							workflow_id: old_runner_alloc_key_data.workflow_id,
-							remaining_slots: new_remaining_slots,
-							total_slots: old_runner_alloc_key_data.total_slots,
-						},
-					)?;
-
-					// Update runner record
-					tx.write(
-						&keys::runner::RemainingSlotsKey::new(old_runner_alloc_key.runner_id),
-						new_remaining_slots,
-					)?;
-
+				if let Some(allocation::ReservedSlot {
+					runner_id,
+					runner_workflow_id,
+				}) = allocation::reserve_slot(
+					&tx,
+					namespace_id,
+					runner_name_selector,
+					allocation_strategy,
+					input.actor_id,
+					placement,
+					resources,
+				)
+				.await?
+				{
 					// Set runner id of actor
-					tx.write(
-						&keys::actor::RunnerIdKey::new(input.actor_id),
-						old_runner_alloc_key.runner_id,
-					)?;
+					tx.write(&keys::actor::RunnerIdKey::new(input.actor_id), runner_id)?;
 
 					// Insert actor index key
 					tx.write(
-						&keys::runner::ActorKey::new(
-							old_runner_alloc_key.runner_id,
-							input.actor_id,
-						),
+						&keys::runner::ActorKey::new(runner_id, input.actor_id),
 						input.generation,
 					)?;
 
@@ -308,8 +251,8 @@ async fn allocate_actor(
 					return Ok((
 						for_serverless,
 						AllocateActorOutput::Allocated {
-							runner_id: old_runner_alloc_key.runner_id,
-							runner_workflow_id: old_runner_alloc_key_data.workflow_id,
+							runner_id,
+							runner_workflow_id,
 						},
 					));
 				}
@@ -332,6 +275,7 @@ async fn allocate_actor(
 						&keys::ns::PendingActorByRunnerNameSelectorKey::new(
 							namespace_id,
 							runner_name_selector.clone(),
+							constrained,
 							pending_allocation_ts,
 							input.actor_id,
 						),
@@ -369,6 +313,7 @@ async fn allocate_actor(
 		} => {
 			state.sleep_ts = None;
 			state.pending_allocation_ts = None;
+			state.next_restart_ts = None;
 			state.runner_id = Some(*runner_id);
 			state.runner_workflow_id = Some(*runner_workflow_id);
 		}
@@ -388,6 +333,220 @@ async fn allocate_actor(
 	Ok(res)
 }
 
+/// Whether any unconstrained actors are waiting for allocation. Actors in the constrained queue are ignored
+/// because they only block the runners they can't use anyway.
+async fn unconstrained_queue_exists(
+	tx: &universaldb::Transaction,
+	namespace_id: Id,
+	runner_name_selector: &str,
+) -> bool {
+	let pending_actor_subspace =
+		keys::subspace().subspace(&keys::ns::PendingActorByRunnerNameSelectorKey::subspace(
+			namespace_id,
+			runner_name_selector.to_string(),
+			false,
+		));
+
+	tx.get_ranges_keyvalues(
+		universaldb::RangeOption {
+			mode: StreamingMode::Exact,
+			limit: Some(1),
+			..(&pending_actor_subspace).into()
+		},
+		// NOTE: This is not Serializable because we don't want to conflict with other inserts/clears to this
+		// range
+		Snapshot,
+	)
+	.next()
+	.await
+	.is_some()
+}
+
+#[derive(Debug, Serialize, Deserialize, Hash)]
+pub struct AllocateMigrationInput {
+	pub actor_id: Id,
+	pub generation: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AllocateMigrationOutput {
+	pub runner_id: Id,
+	pub runner_workflow_id: Id,
+}
+
+/// Reserves a slot for the next generation of a running actor on another runner. Unlike `allocate_actor`, this
+/// does not touch the actor's runner id key so the gateway keeps routing to the current generation until
+/// the migration completes. Returns `None` if there is no availability, in which case the actor is not
+/// migrated.
+#[activity(AllocateMigration)]
+pub async fn allocate_migration(
+	ctx: &ActivityCtx,
+	input: &AllocateMigrationInput,
+) -> Result<Option<AllocateMigrationOutput>> {
+	let state = ctx.state::<State>()?;
+	let namespace_id = state.namespace_id;
+	let runner_name_selector = &state.runner_name_selector;
+	let placement = &state.placement;
+	let resources = state.resources;
+	let for_serverless = state.for_serverless;
+
+	let runner_config_res = ctx
+		.op(namespace::ops::runner_config::get::Input {
+			runners: vec![(namespace_id, runner_name_selector.clone())],
+			bypass_cache: false,
+		})
+		.await?;
+	let allocation_strategy = &runner_config_res
+		.first()
+		.map(|runner| runner.config.allocation_strategy.clone())
+		.unwrap_or_default();
+
+	ctx.udb()?
+		.run(|tx| async move {
+			let tx = tx.with_subspace(keys::subspace());
+
+			// Don't take a slot from actors waiting in the queue
+			if unconstrained_queue_exists(&tx, namespace_id, runner_name_selector).await {
+				return Ok(None);
+			}
+
+			let Some(allocation::ReservedSlot {
+				runner_id,
+				runner_workflow_id,
+			}) = allocation::reserve_slot(
+				&tx,
+				namespace_id,
+				runner_name_selector,
+				allocation_strategy,
+				input.actor_id,
+				placement,
+				resources,
+			)
+			.await?
+			else {
+				return Ok(None);
+			};
+
+			// Insert actor index key for the new generation. The old generation's key is cleared when the
+			// migration completes.
+			tx.write(
+				&keys::runner::ActorKey::new(runner_id, input.actor_id),
+				input.generation,
+			)?;
+
+			// Both generations hold a slot until the migration completes
+			if for_serverless {
+				tx.atomic_op(
+					&rivet_types::keys::pegboard::ns::ServerlessDesiredSlotsKey::new(
+						namespace_id,
+						runner_name_selector.clone(),
+					),
+					&1i64.to_le_bytes(),
+					MutationType::Add,
+				);
+			}
+
+			Ok(Some(AllocateMigrationOutput {
+				runner_id,
+				runner_workflow_id,
+			}))
+		})
+		.custom_instrument(tracing::info_span!("actor_allocate_migration_tx"))
+		.await
+}
+
+#[derive(Debug, Serialize, Deserialize, Hash)]
+pub struct CompleteMigrationInput {
+	pub actor_id: Id,
+	pub runner_id: Id,
+	pub runner_workflow_id: Id,
+}
+
+/// Routes the actor to the runner of the new generation and releases the slot of the old generation in the
+/// same txn.
+#[activity(CompleteMigration)]
+pub async fn complete_migration(ctx: &ActivityCtx, input: &CompleteMigrationInput) -> Result<()> {
+	let mut state = ctx.state::<State>()?;
+	let namespace_id = state.namespace_id;
+	let runner_name_selector = &state.runner_name_selector;
+	let old_runner_id = state.runner_id;
+	let for_serverless = state.for_serverless;
+
+	ctx.udb()?
+		.run(|tx| async move {
+			let tx = tx.with_subspace(keys::subspace());
+
+			if let Some(old_runner_id) = old_runner_id {
+				destroy::clear_slot(
+					input.actor_id,
+					namespace_id,
+					runner_name_selector,
+					old_runner_id,
+					for_serverless,
+					&tx,
+				)
+				.await?;
+			}
+
+			tx.write(
+				&keys::actor::RunnerIdKey::new(input.actor_id),
+				input.runner_id,
+			)?;
+
+			Ok(())
+		})
+		.custom_instrument(tracing::info_span!("actor_complete_migration_tx"))
+		.await?;
+
+	state.runner_id = Some(input.runner_id);
+	state.runner_workflow_id = Some(input.runner_workflow_id);
+
+	Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, Hash)]
+pub struct ClearMigrationInput {
+	pub actor_id: Id,
+	pub runner_id: Id,
+}
+
+/// Releases the slot reserved by `allocate_migration` after a failed migration.
+#[activity(ClearMigration)]
+pub async fn clear_migration(ctx: &ActivityCtx, input: &ClearMigrationInput) -> Result<()> {
+	let state = ctx.state::<State>()?;
+	let namespace_id = state.namespace_id;
+	let runner_name_selector = &state.runner_name_selector;
+	let current_runner_id = state.runner_id;
+	let for_serverless = state.for_serverless;
+
+	ctx.udb()?
+		.run(|tx| async move {
+			let tx = tx.with_subspace(keys::subspace());
+
+			destroy::clear_slot(
+				input.actor_id,
+				namespace_id,
+				runner_name_selector,
+				input.runner_id,
+				for_serverless,
+				&tx,
+			)
+			.await?;
+
+			// `clear_slot` also clears the runner id key, restore it for the current generation
+			if let Some(current_runner_id) = current_runner_id {
+				tx.write(
+					&keys::actor::RunnerIdKey::new(input.actor_id),
+					current_runner_id,
+				)?;
+			}
+
+			Ok(())
+		})
+		.custom_instrument(tracing::info_span!("actor_clear_migration_tx"))
+		.await
+}
+
 #[derive(Debug, Serialize, Deserialize, Hash)]
 pub struct SetNotConnectableInput {
 	pub actor_id: Id,
@@ -465,6 +624,27 @@ pub async fn deallocate(ctx: &ActivityCtx, input: &DeallocateInput) -> Result<De
 	})
 }
 
+pub fn start_actor_command(input: &Input, generation: u32) -> Result<protocol::Command> {
+	Ok(protocol::Command::CommandStartActor(
+		protocol::CommandStartActor {
+			actor_id: input.actor_id.to_string(),
+			generation,
+			config: protocol::ActorConfig {
+				name: input.name.clone(),
+				key: input.key.clone(),
+				// HACK: We should not use dynamic timestamp here, but we don't validate if signal data
+				// changes (like activity inputs) so this is fine for now.
+				create_ts: util::timestamp::now(),
+				input: input
+					.input
+					.as_ref()
+					.map(|x| BASE64_STANDARD.decode(x))
+					.transpose()?,
+			},
+		},
+	))
+}
+
 #[derive(Debug)]
 pub enum SpawnActorOutput {
 	Allocated {
@@ -502,22 +682,7 @@ pub async fn spawn_actor(
 				.await?;
 
 			ctx.signal(crate::workflows::runner::Command {
-				inner: protocol::Command::CommandStartActor(protocol::CommandStartActor {
-					actor_id: input.actor_id.to_string(),
-					generation,
-					config: protocol::ActorConfig {
-						name: input.name.clone(),
-						key: input.key.clone(),
-						// HACK: We should not use dynamic timestamp here, but we don't validate if signal data
-						// changes (like activity inputs) so this is fine for now.
-						create_ts: util::timestamp::now(),
-						input: input
-							.input
-							.as_ref()
-							.map(|x| BASE64_STANDARD.decode(x))
-							.transpose()?,
-					},
-				}),
+				inner: start_actor_command(input, generation)?,
 			})
 			.to_workflow_id(runner_workflow_id)
 			.send()
@@ -548,22 +713,7 @@ pub async fn spawn_actor(
 					.await?;
 
 					ctx.signal(crate::workflows::runner::Command {
-						inner: protocol::Command::CommandStartActor(protocol::CommandStartActor {
-							actor_id: input.actor_id.to_string(),
-							generation,
-							config: protocol::ActorConfig {
-								name: input.name.clone(),
-								key: input.key.clone(),
-								// HACK: We should not use dynamic timestamp here, but we don't validate if signal data
-								// changes (like activity inputs) so this is fine for now.
-								create_ts: util::timestamp::now(),
-								input: input
-									.input
-									.as_ref()
-									.map(|x| BASE64_STANDARD.decode(x))
-									.transpose()?,
-							},
-						}),
+						inner: start_actor_command(input, generation)?,
 					})
 					.to_workflow_id(sig.runner_workflow_id)
 					.send()
@@ -608,28 +758,22 @@ pub async fn spawn_actor(
 }
 
 /// Wrapper around `spawn_actor` that handles rescheduling retries. Returns true if the actor should be
-/// destroyed.
+/// destroyed. `crashed` should be set when restarting the actor after a crash so that it counts towards the
+/// max restarts of the restart policy. Once exhausted, the actor is put to sleep or destroyed instead.
 pub async fn reschedule_actor(
 	ctx: &mut WorkflowCtx,
 	input: &Input,
 	state: &mut LifecycleState,
 	force_reschedule: bool,
+	crashed: bool,
 ) -> Result<SpawnActorOutput> {
 	tracing::debug!(actor_id=?input.actor_id, "rescheduling actor");
 
-	// Determine next backoff sleep duration
-	let mut backoff = util::backoff::Backoff::new_at(
-		8,
-		None,
-		BASE_RETRY_TIMEOUT_MS,
-		500,
-		state.reschedule_state.retry_count,
-	);
-
 	let (now, reset) = ctx
 		.v(2)
 		.activity(CompareRetryInput {
 			last_retry_ts: state.reschedule_state.last_retry_ts,
+			restart_window_ms: input.restart_policy.restart_window_ms as i64,
 		})
 		.await?;
 
@@ -640,22 +784,46 @@ pub async fn reschedule_actor(
 	};
 	state.reschedule_state.last_retry_ts = now;
 
-	// Don't sleep for first retry
-	if state.reschedule_state.retry_count > 0 {
-		let next = backoff.step().expect("should not have max retry");
+	let restart_state = ctx
+		.v(2)
+		.activity(SetRestartStateInput {
+			now,
+			retry_count: state.reschedule_state.retry_count,
+			crashed,
+			restart_policy: input.restart_policy,
+		})
+		.await?;
+
+	if restart_state.exhausted {
+		tracing::debug!(actor_id=?input.actor_id, "actor exhausted its restarts");
 
-		// Sleep for backoff or destroy early
-		if let Some(_sig) = ctx
-			.listen_with_timeout::<Destroy>(Instant::from(next) - Instant::now())
-			.await?
-		{
+		return match input.restart_policy.on_exhausted {
+			RestartExhaustedAction::Sleep => {
+				ctx.activity(SetSleepingInput {
+					actor_id: input.actor_id,
+				})
+				.await?;
+
+				Ok(SpawnActorOutput::Sleep)
+			}
+			RestartExhaustedAction::Destroy => {
+				ctx.activity(SetCompleteInput {}).await?;
+
+				Ok(SpawnActorOutput::Destroy)
+			}
+		};
+	}
+
+	// Sleep for backoff or destroy early
+	if let Some(next_restart_ts) = restart_state.next_restart_ts {
+		if let Some(_sig) = ctx.listen_until::<Destroy>(next_restart_ts).await? {
 			tracing::debug!("destroying before actor start");
 
 			return Ok(SpawnActorOutput::Destroy);
 		}
 	}
 
-	let next_generation = state.generation + 1;
+	let next_generation = state.next_generation();
 	let spawn_res = spawn_actor(
 		ctx,
 		&input,
@@ -693,21 +861,27 @@ pub async fn clear_pending_allocation(
 	ctx: &ActivityCtx,
 	input: &ClearPendingAllocationInput,
 ) -> Result<bool> {
-	// Clear self from alloc queue
+	// Clear self from alloc queue. Both queues are checked because the queue of actors enqueued before
+	// constrained actors got their own queue can't be derived from the actor's state.
 	let cleared = ctx
 		.udb()?
 		.run(|tx| async move {
-			let pending_alloc_key =
-				keys::subspace().pack(&keys::ns::PendingActorByRunnerNameSelectorKey::new(
-					input.namespace_id,
-					input.runner_name_selector.clone(),
-					input.pending_allocation_ts,
-					input.actor_id,
-				));
+			let mut exists = false;
+
+			for constrained in [false, true] {
+				let pending_alloc_key =
+					keys::subspace().pack(&keys::ns::PendingActorByRunnerNameSelectorKey::new(
+						input.namespace_id,
+						input.runner_name_selector.clone(),
+						constrained,
+						input.pending_allocation_ts,
+						input.actor_id,
+					));
 
-			let exists = tx.get(&pending_alloc_key, Serializable).await?.is_some();
+				exists |= tx.get(&pending_alloc_key, Serializable).await?.is_some();
 
-			tx.clear(&pending_alloc_key);
+				tx.clear(&pending_alloc_key);
+			}
 
 			Ok(exists)
 		})
@@ -720,14 +894,131 @@ pub async fn clear_pending_allocation(
 #[derive(Debug, Serialize, Deserialize, Hash)]
 struct CompareRetryInput {
 	last_retry_ts: i64,
+	#[serde(default = "default_restart_window_ms")]
+	restart_window_ms: i64,
+}
+
+fn default_restart_window_ms() -> i64 {
+	RestartPolicy::default().restart_window_ms as i64
 }
 
 #[activity(CompareRetry)]
 async fn compare_retry(ctx: &ActivityCtx, input: &CompareRetryInput) -> Result<(i64, bool)> {
 	let now = util::timestamp::now();
 
-	// If the last retry ts is more than RETRY_RESET_DURATION_MS, reset retry count
-	Ok((now, input.last_retry_ts < now - RETRY_RESET_DURATION_MS))
+	// If the last retry ts is more than the restart window, reset retry count
+	Ok((now, input.last_retry_ts < now - input.restart_window_ms))
+}
+
+#[derive(Debug, Serialize, Deserialize, Hash)]
+struct SetRestartStateInput {
+	now: i64,
+	retry_count: usize,
+	crashed: bool,
+	restart_policy: RestartPolicy,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SetRestartStateOutput {
+	exhausted: bool,
+	next_restart_ts: Option<i64>,
+}
+
+/// Counts crash restarts against the restart policy and determines when the actor should be restarted.
+#[activity(SetRestartState)]
+async fn set_restart_state(
+	ctx: &ActivityCtx,
+	input: &SetRestartStateInput,
+) -> Result<SetRestartStateOutput> {
+	let mut state = ctx.state::<State>()?;
+
+	let restart = next_restart(
+		&input.restart_policy,
+		RestartCounter {
+			restart_count: state.restart_count,
+			restart_window_start_ts: state.restart_window_start_ts,
+		},
+		input.now,
+		input.retry_count,
+		input.crashed,
+		rand::thread_rng().gen_range(0..RESTART_JITTER_MS),
+	);
+
+	state.restart_count = restart.counter.restart_count;
+	state.restart_window_start_ts = restart.counter.restart_window_start_ts;
+	state.next_restart_ts = restart.next_restart_ts;
+
+	Ok(SetRestartStateOutput {
+		exhausted: restart.exhausted.is_some(),
+		next_restart_ts: restart.next_restart_ts,
+	})
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RestartCounter {
+	restart_count: u32,
+	restart_window_start_ts: Option<i64>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct NextRestart {
+	counter: RestartCounter,
+	/// What to do with the actor instead of restarting it, set once the restarts are exhausted.
+	exhausted: Option<RestartExhaustedAction>,
+	next_restart_ts: Option<i64>,
+}
+
+/// Counts a restart against the restart policy. Only crashes count towards `max_restarts`, the backoff is
+/// based on `retry_count` which counts all reschedules. `jitter_ms` is added to the backoff.
+fn next_restart(
+	restart_policy: &RestartPolicy,
+	mut counter: RestartCounter,
+	now: i64,
+	retry_count: usize,
+	crashed: bool,
+	jitter_ms: u64,
+) -> NextRestart {
+	if crashed {
+		// Start a new window if the previous one has passed
+		let window_passed = counter
+			.restart_window_start_ts
+			.is_none_or(|start_ts| now - start_ts > restart_policy.restart_window_ms as i64);
+		if window_passed {
+			counter.restart_window_start_ts = Some(now);
+			counter.restart_count = 0;
+		}
+
+		if restart_policy
+			.max_restarts
+			.is_some_and(|max_restarts| counter.restart_count >= max_restarts)
+		{
+			return NextRestart {
+				counter,
+				exhausted: Some(restart_policy.on_exhausted),
+				next_restart_ts: None,
+			};
+		}
+
+		counter.restart_count += 1;
+	}
+
+	// Don't back off for first retry
+	let next_restart_ts = (retry_count > 0).then(|| {
+		// Doubles with every retry after the first
+		let exponent = (retry_count - 1).min(32) as u32;
+		let backoff_ms = restart_policy
+			.initial_backoff_ms
+			.saturating_mul(1 << exponent)
+			.min(restart_policy.max_backoff_ms);
+
+		now + backoff_ms.saturating_add(jitter_ms) as i64
+	});
+
+	NextRestart {
+		counter,
+		exhausted: None,
+		next_restart_ts,
+	}
 }
 
 #[derive(Debug, Serialize, Deserialize, Hash)]
@@ -799,3 +1090,113 @@ pub async fn set_complete(ctx: &ActivityCtx, input: &SetCompleteInput) -> Result
 
 	Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+	use rivet_types::actors::{RestartExhaustedAction, RestartPolicy};
+
+	use super::{RestartCounter, next_restart};
+
+	const NOW: i64 = 1_000_000_000;
+
+	fn policy(max_restarts: Option<u32>, on_exhausted: RestartExhaustedAction) -> RestartPolicy {
+		RestartPolicy {
+			initial_backoff_ms: 1_000,
+			max_backoff_ms: 8_000,
+			max_restarts,
+			restart_window_ms: 60_000,
+			on_exhausted,
+		}
+	}
+
+	fn counter(restart_count: u32, restart_window_start_ts: Option<i64>) -> RestartCounter {
+		RestartCounter {
+			restart_count,
+			restart_window_start_ts,
+		}
+	}
+
+	#[test]
+	fn exhausts_restarts() {
+		for on_exhausted in [
+			RestartExhaustedAction::Sleep,
+			RestartExhaustedAction::Destroy,
+		] {
+			let policy = policy(Some(2), on_exhausted);
+
+			let res = next_restart(&policy, counter(0, None), NOW, 0, true, 0);
+			assert_eq!(res.exhausted, None);
+			assert_eq!(res.counter, counter(1, Some(NOW)));
+
+			let res = next_restart(&policy, res.counter, NOW + 1, 1, true, 0);
+			assert_eq!(res.exhausted, None);
+			assert_eq!(res.counter, counter(2, Some(NOW)));
+
+			let res = next_restart(&policy, res.counter, NOW + 2, 2, true, 0);
+			assert_eq!(res.exhausted, Some(on_exhausted));
+			assert_eq!(res.next_restart_ts, None);
+			assert_eq!(res.counter, counter(2, Some(NOW)));
+		}
+	}
+
+	#[test]
+	fn unlimited_restarts() {
+		let policy = policy(None, RestartExhaustedAction::Sleep);
+
+		let res = next_restart(&policy, counter(1_000, Some(NOW)), NOW, 0, true, 0);
+		assert_eq!(res.exhausted, None);
+		assert_eq!(res.counter.restart_count, 1_001);
+	}
+
+	#[test]
+	fn restarts_without_crash_are_not_counted() {
+		let policy = policy(Some(1), RestartExhaustedAction::Destroy);
+
+		let res = next_restart(&policy, counter(1, Some(NOW)), NOW, 0, false, 0);
+		assert_eq!(res.exhausted, None);
+		assert_eq!(res.counter, counter(1, Some(NOW)));
+	}
+
+	#[test]
+	fn resets_after_window() {
+		let policy = policy(Some(1), RestartExhaustedAction::Destroy);
+
+		// Still within the window
+		let res = next_restart(&policy, counter(1, Some(NOW)), NOW + 60_000, 0, true, 0);
+		assert_eq!(res.exhausted, Some(RestartExhaustedAction::Destroy));
+
+		// Window has passed
+		let res = next_restart(&policy, counter(1, Some(NOW)), NOW + 60_001, 0, true, 0);
+		assert_eq!(res.exhausted, None);
+		assert_eq!(res.counter, counter(1, Some(NOW + 60_001)));
+	}
+
+	#[test]
+	fn backoff_grows_and_is_capped() {
+		let policy = policy(None, RestartExhaustedAction::Sleep);
+		let backoff = |retry_count| {
+			next_restart(&policy, counter(0, None), NOW, retry_count, false, 0)
+				.next_restart_ts
+				.map(|ts| ts - NOW)
+		};
+
+		assert_eq!(backoff(0), None);
+		assert_eq!(backoff(1), Some(1_000));
+		assert_eq!(backoff(2), Some(2_000));
+		assert_eq!(backoff(3), Some(4_000));
+		assert_eq!(backoff(4), Some(8_000));
+		assert_eq!(backoff(5), Some(8_000));
+		assert_eq!(backoff(100), Some(8_000));
+	}
+
+	#[test]
+	fn jitter_is_added_to_backoff() {
+		let policy = policy(None, RestartExhaustedAction::Sleep);
+
+		let res = next_restart(&policy, counter(0, None), NOW, 0, false, 499);
+		assert_eq!(res.next_restart_ts, None);
+
+		let res = next_restart(&policy, counter(0, None), NOW, 10, false, 499);
+		assert_eq!(res.next_restart_ts, Some(NOW + 8_499));
+	}
+}
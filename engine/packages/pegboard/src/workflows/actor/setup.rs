@@ -1,13 +1,20 @@
 use gas::prelude::*;
 use rivet_data::converted::ActorNameKeyData;
-use rivet_types::actors::CrashPolicy;
+use rivet_types::{
+	actors::{CrashPolicy, PlacementConstraints, Resources, RestartPolicy},
+	runner_configs::RunnerConfigKind,
+};
 use universaldb::utils::IsolationLevel::*;
 
 use super::State;
 
-use crate::{errors, keys};
+use crate::{allocation, errors, keys, workflows::runner::RUNNER_ELIGIBLE_THRESHOLD_MS};
 
 const MAX_INPUT_SIZE: usize = util::file_size::mebibytes(4) as usize;
+const MAX_ANTI_AFFINITY_GROUPS: usize = 16;
+const MAX_ANTI_AFFINITY_GROUP_SIZE: usize = 256;
+const MAX_RESTART_BACKOFF_MS: u64 = util::duration::days(1) as u64;
+const MAX_RESTART_WINDOW_MS: u64 = util::duration::days(30) as u64;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Hash)]
 pub struct ValidateInput {
@@ -15,6 +22,14 @@ pub struct ValidateInput {
 	pub name: String,
 	pub key: Option<String>,
 	pub input: Option<String>,
+	#[serde(default)]
+	pub runner_name_selector: String,
+	#[serde(default)]
+	pub restart_policy: RestartPolicy,
+	#[serde(default)]
+	pub placement: PlacementConstraints,
+	#[serde(default)]
+	pub resources: Option<Resources>,
 }
 
 #[activity(Validate)]
@@ -55,9 +70,120 @@ pub async fn validate(
 		}
 	}
 
+	if let Err(reason) = validate_restart_policy(&input.restart_policy) {
+		return Ok(Err(errors::Actor::InvalidRestartPolicy {
+			reason: reason.to_string(),
+		}));
+	}
+
+	if !input.placement.is_empty() || input.resources.is_some() {
+		return validate_placement(ctx, input).await;
+	}
+
 	Ok(Ok(()))
 }
 
+fn validate_restart_policy(
+	restart_policy: &RestartPolicy,
+) -> std::result::Result<(), &'static str> {
+	if restart_policy.initial_backoff_ms > restart_policy.max_backoff_ms {
+		return Err("`initial_backoff_ms` must not be greater than `max_backoff_ms`");
+	}
+	if restart_policy.max_backoff_ms > MAX_RESTART_BACKOFF_MS {
+		return Err("`max_backoff_ms` must not be greater than 1 day");
+	}
+	if restart_policy.restart_window_ms > MAX_RESTART_WINDOW_MS {
+		return Err("`restart_window_ms` must not be greater than 30 days");
+	}
+
+	Ok(())
+}
+
+/// Fails early if no runner could ever satisfy the placement constraints or fit the requested resources.
+/// Runners that are only temporarily full are not considered, the actor waits in the pending queue for those
+/// like any other actor.
+async fn validate_placement(
+	ctx: &ActivityCtx,
+	input: &ValidateInput,
+) -> Result<std::result::Result<(), errors::Actor>> {
+	let placement = &input.placement;
+
+	if placement.anti_affinity_groups.len() > MAX_ANTI_AFFINITY_GROUPS {
+		return Ok(Err(errors::Actor::InvalidPlacement {
+			reason: format!("too many anti-affinity groups (max {MAX_ANTI_AFFINITY_GROUPS})"),
+		}));
+	}
+
+	for group in &placement.anti_affinity_groups {
+		if group.is_empty() {
+			return Ok(Err(errors::Actor::InvalidPlacement {
+				reason: "anti-affinity group cannot be empty".to_string(),
+			}));
+		}
+		if group.len() > MAX_ANTI_AFFINITY_GROUP_SIZE {
+			return Ok(Err(errors::Actor::InvalidPlacement {
+				reason: format!(
+					"anti-affinity group too large (max {MAX_ANTI_AFFINITY_GROUP_SIZE} bytes)"
+				),
+			}));
+		}
+	}
+
+	// Serverless runners are started on demand, there may not be any runners to check against yet
+	let runner_config_res = ctx
+		.op(namespace::ops::runner_config::get::Input {
+			runners: vec![(input.namespace_id, input.runner_name_selector.clone())],
+			bypass_cache: false,
+		})
+		.await?;
+	if runner_config_res
+		.first()
+		.is_some_and(|runner| matches!(runner.config.kind, RunnerConfigKind::Serverless { .. }))
+	{
+		return Ok(Ok(()));
+	}
+
+	let placement_check = ctx
+		.udb()?
+		.run(|tx| async move {
+			let tx = tx.with_subspace(keys::subspace());
+			let ping_threshold_ts = util::timestamp::now() - RUNNER_ELIGIBLE_THRESHOLD_MS;
+
+			allocation::check_placement(
+				&tx,
+				input.namespace_id,
+				&input.runner_name_selector,
+				placement,
+				input.resources,
+				ping_threshold_ts,
+			)
+			.await
+		})
+		.custom_instrument(tracing::info_span!("actor_check_placement_tx"))
+		.await?;
+
+	match placement_check {
+		allocation::PlacementCheck::Satisfiable => Ok(Ok(())),
+		// Without placement constraints the actor waits for a runner to connect like any other actor
+		allocation::PlacementCheck::NoMatchingRunner if placement.is_empty() => Ok(Ok(())),
+		allocation::PlacementCheck::NoMatchingRunner => {
+			Ok(Err(errors::Actor::NoRunnersMatchPlacement {
+				runner_name: input.runner_name_selector.clone(),
+			}))
+		}
+		allocation::PlacementCheck::InsufficientResources => {
+			Ok(Err(errors::Actor::InsufficientRunnerResources {
+				runner_name: input.runner_name_selector.clone(),
+			}))
+		}
+		allocation::PlacementCheck::AntiAffinityConflict => {
+			Ok(Err(errors::Actor::AntiAffinityUnsatisfiable {
+				runner_name: input.runner_name_selector.clone(),
+			}))
+		}
+	}
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Hash)]
 pub struct InitStateAndUdbInput {
 	pub actor_id: Id,
@@ -66,6 +192,12 @@ pub struct InitStateAndUdbInput {
 	pub namespace_id: Id,
 	pub runner_name_selector: String,
 	pub crash_policy: CrashPolicy,
+	#[serde(default)]
+	pub restart_policy: RestartPolicy,
+	#[serde(default)]
+	pub placement: PlacementConstraints,
+	#[serde(default)]
+	pub resources: Option<Resources>,
 	pub create_ts: i64,
 }
 
@@ -79,6 +211,9 @@ pub async fn insert_state_and_db(ctx: &ActivityCtx, input: &InitStateAndUdbInput
 		input.namespace_id,
 		input.runner_name_selector.clone(),
 		input.crash_policy,
+		input.restart_policy,
+		input.placement.clone(),
+		input.resources,
 		input.create_ts,
 	));
 
@@ -99,6 +234,19 @@ pub async fn insert_state_and_db(ctx: &ActivityCtx, input: &InitStateAndUdbInput
 				input.namespace_id,
 			)?;
 
+			// Read by the runner workflow when allocating pending actors
+			if !input.placement.is_empty() {
+				tx.write(
+					&keys::actor::PlacementKey::new(input.actor_id),
+					input.placement.clone(),
+				)?;
+			}
+
+			// Read when allocating pending actors and when returning resources to the runner
+			if let Some(resources) = input.resources {
+				tx.write(&keys::actor::ResourcesKey::new(input.actor_id), resources)?;
+			}
+
 			Ok(())
 		})
 		.custom_instrument(tracing::info_span!("actor_insert_tx"))
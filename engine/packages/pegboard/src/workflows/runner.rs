@@ -9,7 +9,7 @@ use universaldb::{
 use universalpubsub::PublishOpts;
 use vbare::OwnedVersionedData;
 
-use crate::{keys, workflows::actor::Allocate};
+use crate::{allocation, keys, workflows::actor::Allocate};
 
 /// How long after last ping before considering a runner ineligible for allocation.
 pub const RUNNER_ELIGIBLE_THRESHOLD_MS: i64 = util::duration::seconds(10);
@@ -27,6 +27,8 @@ pub struct Input {
 	pub key: String,
 	pub version: u32,
 	pub total_slots: u32,
+	#[serde(default)]
+	pub resources: Option<protocol::RunnerResources>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -139,6 +141,7 @@ pub async fn pegboard_runner(ctx: &mut WorkflowCtx, input: &Input) -> Result<()>
 									key: input.key.clone(),
 									version: input.version,
 									total_slots: input.total_slots,
+									resources: input.resources.clone(),
 									create_ts: ctx.create_ts(),
 								})
 								.await?;
@@ -247,17 +250,30 @@ pub async fn pegboard_runner(ctx: &mut WorkflowCtx, input: &Input) -> Result<()>
 									})
 									.await?;
 
-								// Set all remaining actors to lost immediately
 								if !actors.is_empty() {
+									// Migrate all remaining actors to other runners (v2). Previously all remaining
+									// actors were set to lost immediately (v1)
+									let migrate = ctx.check_version(2).await? > 1;
+
 									for (actor_id, generation) in &actors {
-										ctx.signal(crate::workflows::actor::Lost {
-											generation: *generation,
-											force_reschedule: false,
-										})
-										.to_workflow::<crate::workflows::actor::Workflow>()
-										.tag("actor_id", actor_id)
-										.send()
-										.await?;
+										if migrate {
+											ctx.signal(crate::workflows::actor::Migrate {
+												generation: *generation,
+											})
+											.to_workflow::<crate::workflows::actor::Workflow>()
+											.tag("actor_id", actor_id)
+											.send()
+											.await?;
+										} else {
+											ctx.signal(crate::workflows::actor::Lost {
+												generation: *generation,
+												force_reschedule: false,
+											})
+											.to_workflow::<crate::workflows::actor::Workflow>()
+											.tag("actor_id", actor_id)
+											.send()
+											.await?;
+										}
 									}
 								}
 							}
@@ -496,6 +512,7 @@ struct InsertDbInput {
 	key: String,
 	version: u32,
 	total_slots: u32,
+	resources: Option<protocol::RunnerResources>,
 	create_ts: i64,
 }
 
@@ -506,11 +523,20 @@ async fn insert_db(ctx: &ActivityCtx, input: &InsertDbInput) -> Result<()> {
 			let tx = tx.with_subspace(keys::subspace());
 
 			let remaining_slots_key = keys::runner::RemainingSlotsKey::new(input.runner_id);
+			let remaining_resources_key = keys::runner::RemainingResourcesKey::new(input.runner_id);
+			let total_resources_key = keys::runner::TotalResourcesKey::new(input.runner_id);
 			let last_ping_ts_key = keys::runner::LastPingTsKey::new(input.runner_id);
 			let workflow_id_key = keys::runner::WorkflowIdKey::new(input.runner_id);
 
-			let (remaining_slots_entry, last_ping_ts_entry) = tokio::try_join!(
+			let (
+				remaining_slots_entry,
+				remaining_resources_entry,
+				total_resources_entry,
+				last_ping_ts_entry,
+			) = tokio::try_join!(
 				tx.read_opt(&remaining_slots_key, Serializable),
+				tx.read_opt(&remaining_resources_key, Serializable),
+				tx.read_opt(&total_resources_key, Serializable),
 				tx.read_opt(&last_ping_ts_key, Serializable),
 			)?;
 			let now = util::timestamp::now();
@@ -519,102 +545,143 @@ async fn insert_db(ctx: &ActivityCtx, input: &InsertDbInput) -> Result<()> {
 			let existing = if let (Some(remaining_slots), Some(last_ping_ts)) =
 				(remaining_slots_entry, last_ping_ts_entry)
 			{
-				Some((remaining_slots, last_ping_ts))
+				// Resources are zero if the runner did not advertise them
+				Some((
+					remaining_slots,
+					remaining_resources_entry.unwrap_or_default(),
+					total_resources_entry.unwrap_or_default(),
+					last_ping_ts,
+				))
 			} else {
 				// Initial insert
 				None
 			};
 
-			let (remaining_slots, last_ping_ts) = if let Some(existing) = existing {
-				existing
-			}
-			// NOTE: These properties are only inserted once
-			else {
-				tx.write(&workflow_id_key, ctx.workflow_id())?;
+			let (remaining_slots, remaining_resources, total_resources, last_ping_ts) =
+				if let Some(existing) = existing {
+					existing
+				}
+				// NOTE: These properties are only inserted once
+				else {
+					tx.write(&workflow_id_key, ctx.workflow_id())?;
 
-				tx.write(
-					&keys::runner::NamespaceIdKey::new(input.runner_id),
-					input.namespace_id,
-				)?;
+					tx.write(
+						&keys::runner::NamespaceIdKey::new(input.runner_id),
+						input.namespace_id,
+					)?;
 
-				tx.write(
-					&keys::runner::NameKey::new(input.runner_id),
-					input.name.clone(),
-				)?;
+					tx.write(
+						&keys::runner::NameKey::new(input.runner_id),
+						input.name.clone(),
+					)?;
 
-				tx.write(
-					&keys::runner::KeyKey::new(input.runner_id),
-					input.key.clone(),
-				)?;
+					tx.write(
+						&keys::runner::KeyKey::new(input.runner_id),
+						input.key.clone(),
+					)?;
 
-				tx.write(
-					&keys::runner::VersionKey::new(input.runner_id),
-					input.version,
-				)?;
+					tx.write(
+						&keys::runner::VersionKey::new(input.runner_id),
+						input.version,
+					)?;
 
-				tx.write(&remaining_slots_key, input.total_slots)?;
+					tx.write(&remaining_slots_key, input.total_slots)?;
 
-				tx.write(
-					&keys::runner::TotalSlotsKey::new(input.runner_id),
-					input.total_slots,
-				)?;
+					tx.write(
+						&keys::runner::TotalSlotsKey::new(input.runner_id),
+						input.total_slots,
+					)?;
 
-				tx.write(
-					&keys::runner::CreateTsKey::new(input.runner_id),
-					input.create_ts,
-				)?;
+					let total_resources = input
+						.resources
+						.as_ref()
+						.map(|resources| rivet_types::actors::Resources {
+							cpu_millicores: resources.cpu_millicores,
+							memory_mib: resources.memory_mib,
+						})
+						.unwrap_or_default();
 
-				tx.write(&last_ping_ts_key, now)?;
+					if input.resources.is_some() {
+						tx.write(&remaining_resources_key, total_resources)?;
+						tx.write(&total_resources_key, total_resources)?;
+					}
 
-				// Populate ns indexes
-				tx.write(
-					&keys::ns::ActiveRunnerKey::new(
-						input.namespace_id,
-						input.create_ts,
-						input.runner_id,
-					),
-					ctx.workflow_id(),
-				)?;
-				tx.write(
-					&keys::ns::ActiveRunnerByNameKey::new(
-						input.namespace_id,
-						input.name.clone(),
-						input.create_ts,
-						input.runner_id,
-					),
-					ctx.workflow_id(),
-				)?;
-				tx.write(
-					&keys::ns::AllRunnerKey::new(
-						input.namespace_id,
-						input.create_ts,
-						input.runner_id,
-					),
-					ctx.workflow_id(),
-				)?;
-				tx.write(
-					&keys::ns::AllRunnerByNameKey::new(
-						input.namespace_id,
-						input.name.clone(),
+					tx.write(
+						&keys::runner::CreateTsKey::new(input.runner_id),
 						input.create_ts,
-						input.runner_id,
-					),
-					ctx.workflow_id(),
-				)?;
+					)?;
 
-				// Write name into namespace runner names list
-				tx.write(
-					&keys::ns::RunnerNameKey::new(input.namespace_id, input.name.clone()),
-					(),
-				)?;
+					tx.write(&last_ping_ts_key, now)?;
 
-				(input.total_slots, now)
-			};
+					// Populate ns indexes
+					tx.write(
+						&keys::ns::ActiveRunnerKey::new(
+							input.namespace_id,
+							input.create_ts,
+							input.runner_id,
+						),
+						ctx.workflow_id(),
+					)?;
+					tx.write(
+						&keys::ns::ActiveRunnerByNameKey::new(
+							input.namespace_id,
+							input.name.clone(),
+							input.create_ts,
+							input.runner_id,
+						),
+						ctx.workflow_id(),
+					)?;
+					tx.write(
+						&keys::ns::AllRunnerKey::new(
+							input.namespace_id,
+							input.create_ts,
+							input.runner_id,
+						),
+						ctx.workflow_id(),
+					)?;
+					tx.write(
+						&keys::ns::AllRunnerByNameKey::new(
+							input.namespace_id,
+							input.name.clone(),
+							input.create_ts,
+							input.runner_id,
+						),
+						ctx.workflow_id(),
+					)?;
+
+					// Write name into namespace runner names list
+					tx.write(
+						&keys::ns::RunnerNameKey::new(input.namespace_id, input.name.clone()),
+						(),
+					)?;
+
+					(input.total_slots, total_resources, total_resources, now)
+				};
 
 			// Set last connect ts
 			tx.write(&keys::runner::ConnectedTsKey::new(input.runner_id), now)?;
 
-			let remaining_millislots = (remaining_slots * 1000) / input.total_slots;
+			let runner_alloc_key_data = rivet_data::converted::RunnerAllocIdxKeyData {
+				workflow_id: ctx.workflow_id(),
+				remaining_slots,
+				total_slots: input.total_slots,
+				remaining_cpu_millicores: remaining_resources.cpu_millicores,
+				total_cpu_millicores: total_resources.cpu_millicores,
+				remaining_memory_mib: remaining_resources.memory_mib,
+				total_memory_mib: total_resources.memory_mib,
+			};
+
+			// Clear the legacy entry of a reconnecting runner (same as the `update_alloc_idx` op with `AddIdx`)
+			if existing.is_some() {
+				tx.delete(&keys::ns::RunnerAllocIdxKey::new_legacy(
+					input.namespace_id,
+					input.name.clone(),
+					input.version,
+					remaining_slots,
+					last_ping_ts,
+					input.runner_id,
+				));
+			}
 
 			// Insert into index (same as the `update_alloc_idx` op with `AddIdx`)
 			tx.write(
@@ -622,15 +689,11 @@ async fn insert_db(ctx: &ActivityCtx, input: &InsertDbInput) -> Result<()> {
 					input.namespace_id,
 					input.name.clone(),
 					input.version,
-					remaining_millislots,
+					runner_alloc_key_data.remaining_capacity(),
 					last_ping_ts,
 					input.runner_id,
 				),
-				rivet_data::converted::RunnerAllocIdxKeyData {
-					workflow_id: ctx.workflow_id(),
-					remaining_slots,
-					total_slots: input.total_slots,
-				},
+				runner_alloc_key_data,
 			)?;
 
 			Ok(())
@@ -965,6 +1028,17 @@ pub(crate) async fn allocate_pending_actors(
 	ctx: &ActivityCtx,
 	input: &AllocatePendingActorsInput,
 ) -> Result<AllocatePendingActorsOutput> {
+	let runner_config_res = ctx
+		.op(namespace::ops::runner_config::get::Input {
+			runners: vec![(input.namespace_id, input.name.clone())],
+			bypass_cache: false,
+		})
+		.await?;
+	let allocation_strategy = &runner_config_res
+		.first()
+		.map(|runner| runner.config.allocation_strategy.clone())
+		.unwrap_or_default();
+
 	// NOTE: This txn should closely resemble the one found in the allocate_actor activity of the actor wf
 	let res = ctx
 		.udb()?
@@ -972,136 +1046,90 @@ pub(crate) async fn allocate_pending_actors(
 			let tx = tx.with_subspace(keys::subspace());
 			let mut results = Vec::new();
 
-			let pending_actor_subspace = keys::subspace().subspace(
-				&keys::ns::PendingActorByRunnerNameSelectorKey::subspace(
-					input.namespace_id,
-					input.name.clone(),
-				),
-			);
-			let mut queue_stream = tx.get_ranges_keyvalues(
-				universaldb::RangeOption {
-					mode: StreamingMode::Iterator,
-					..(&pending_actor_subspace).into()
-				},
-				// NOTE: This is not Serializable because we don't want to conflict with all of the keys, just
-				// the one we choose
-				Snapshot,
-			);
-			let ping_threshold_ts = util::timestamp::now() - RUNNER_ELIGIBLE_THRESHOLD_MS;
-
-			'queue_loop: loop {
-				let Some(queue_entry) = queue_stream.try_next().await? else {
-					break;
-				};
-
-				let (queue_key, generation) =
-					tx.read_entry::<keys::ns::PendingActorByRunnerNameSelectorKey>(&queue_entry)?;
-
-				let runner_alloc_subspace = keys::subspace().subspace(
-					&keys::ns::RunnerAllocIdxKey::subspace(input.namespace_id, input.name.clone()),
+			// Unconstrained actors are allocated first, constrained actors take the remaining slots they fit in
+			for constrained in [false, true] {
+				let pending_actor_subspace = keys::subspace().subspace(
+					&keys::ns::PendingActorByRunnerNameSelectorKey::subspace(
+						input.namespace_id,
+						input.name.clone(),
+						constrained,
+					),
 				);
-
-				let mut stream = tx.get_ranges_keyvalues(
+				let mut queue_stream = tx.get_ranges_keyvalues(
 					universaldb::RangeOption {
 						mode: StreamingMode::Iterator,
-						// Containers bin pack so we reverse the order
-						reverse: true,
-						..(&runner_alloc_subspace).into()
+						..(&pending_actor_subspace).into()
 					},
-					// NOTE: This is not Serializable because we don't want to conflict with all of the
-					// keys, just the one we choose
+					// NOTE: This is not Serializable because we don't want to conflict with all of the keys, just
+					// the one we choose
 					Snapshot,
 				);
 
-				let mut highest_version = None;
-
 				loop {
-					let Some(entry) = stream.try_next().await? else {
+					let Some(queue_entry) = queue_stream.try_next().await? else {
 						break;
 					};
 
-					let (old_runner_alloc_key, old_runner_alloc_key_data) =
-						tx.read_entry::<keys::ns::RunnerAllocIdxKey>(&entry)?;
-
-					if let Some(highest_version) = highest_version {
-						// We have passed all of the runners with the highest version. This is reachable if
-						// the ping of the highest version workers makes them ineligible
-						if old_runner_alloc_key.version < highest_version {
-							break;
-						}
-					} else {
-						highest_version = Some(old_runner_alloc_key.version);
-					}
+					let (queue_key, generation) = tx
+						.read_entry::<keys::ns::PendingActorByRunnerNameSelectorKey>(
+						&queue_entry,
+					)?;
 
-					// An empty runner means we have reached the end of the runners with the highest version
-					if old_runner_alloc_key.remaining_millislots == 0 {
-						break;
-					}
+					// NOTE: Placement constraints and resources do not change after the actor is created
+					let placement = tx
+						.read_opt(
+							&keys::actor::PlacementKey::new(queue_key.actor_id),
+							Snapshot,
+						)
+						.await?
+						.unwrap_or_default();
+					let resources = tx
+						.read_opt(
+							&keys::actor::ResourcesKey::new(queue_key.actor_id),
+							Snapshot,
+						)
+						.await?;
 
-					// Scan by last ping
-					if old_runner_alloc_key.last_ping_ts < ping_threshold_ts {
+					let Some(allocation::ReservedSlot {
+						runner_id,
+						runner_workflow_id,
+					}) = allocation::reserve_slot(
+						&tx,
+						input.namespace_id,
+						&input.name,
+						allocation_strategy,
+						queue_key.actor_id,
+						&placement,
+						resources,
+					)
+					.await?
+					else {
 						continue;
-					}
-
-					// Add read conflict only for this runner key
-					tx.add_conflict_key(&old_runner_alloc_key, ConflictRangeType::Read)?;
-					tx.delete(&old_runner_alloc_key);
+					};
 
 					// Add read conflict for the queue key
 					tx.add_conflict_key(&queue_key, ConflictRangeType::Read)?;
 					tx.delete(&queue_key);
 
-					let new_remaining_slots =
-						old_runner_alloc_key_data.remaining_slots.saturating_sub(1);
-					let new_remaining_millislots =
-						(new_remaining_slots * 1000) / old_runner_alloc_key_data.total_slots;
-
-					// Write new allocation key with 1 less slot
-					tx.write(
-						&keys::ns::RunnerAllocIdxKey::new(
-							input.namespace_id,
-							input.name.clone(),
-							old_runner_alloc_key.version,
-							new_remaining_millislots,
-							old_runner_alloc_key.last_ping_ts,
-							old_runner_alloc_key.runner_id,
-						),
-						rivet_data::converted::RunnerAllocIdxKeyData {
-							workflow_id: old_runner_alloc_key_data.workflow_id,
-							remaining_slots: new_remaining_slots,
-							total_slots: old_runner_alloc_key_data.total_slots,
-						},
-					)?;
-
-					// Update runner record
-					tx.write(
-						&keys::runner::RemainingSlotsKey::new(old_runner_alloc_key.runner_id),
-						new_remaining_slots,
-					)?;
-
 					// Set runner id of actor
 					tx.write(
 						&keys::actor::RunnerIdKey::new(queue_key.actor_id),
-						old_runner_alloc_key.runner_id,
+						runner_id,
 					)?;
 
 					// Insert actor index key
 					tx.write(
-						&keys::runner::ActorKey::new(
-							old_runner_alloc_key.runner_id,
-							queue_key.actor_id,
-						),
+						&keys::runner::ActorKey::new(runner_id, queue_key.actor_id),
 						generation,
 					)?;
 
 					results.push(ActorAllocation {
 						actor_id: queue_key.actor_id,
 						signal: Allocate {
-							runner_id: old_runner_alloc_key.runner_id,
-							runner_workflow_id: old_runner_alloc_key_data.workflow_id,
+							runner_id,
+							runner_workflow_id,
 						},
 					});
-					continue 'queue_loop;
 				}
 			}
 
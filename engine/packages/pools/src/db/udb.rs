@@ -27,6 +27,14 @@ pub async fn setup(config: Config) -> Result<Option<UdbPool>> {
 			Arc::new(universaldb::driver::RocksDbDatabaseDriver::new(fs.path.clone()).await?)
 				as universaldb::DatabaseDriverHandle
 		}
+		#[cfg(feature = "fdb")]
+		config::Database::FoundationDb(fdb) => Arc::new(
+			universaldb::driver::FdbDatabaseDriver::new(fdb.cluster_file_path.clone()).await?,
+		) as universaldb::DatabaseDriverHandle,
+		#[cfg(not(feature = "fdb"))]
+		config::Database::FoundationDb(_) => {
+			bail!("foundationdb is not supported, build with the `fdb` feature enabled")
+		}
 	};
 
 	tracing::debug!("udb started");
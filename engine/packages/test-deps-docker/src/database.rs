@@ -8,6 +8,7 @@ use crate::DockerRunConfig;
 pub enum TestDatabase {
 	Postgres,
 	FileSystem,
+	FoundationDb,
 }
 
 impl TestDatabase {
@@ -17,6 +18,7 @@ impl TestDatabase {
 			Ok(val) => match val.as_str() {
 				"postgres" => TestDatabase::Postgres,
 				"filesystem" => TestDatabase::FileSystem,
+				"foundationdb" => TestDatabase::FoundationDb,
 				_ => TestDatabase::FileSystem, // Default
 			},
 			Err(_) => TestDatabase::FileSystem, // Default
@@ -80,14 +82,114 @@ impl TestDatabase {
 				std::fs::create_dir_all(&temp_dir)?;
 
 				let config = rivet_config::config::Database::FileSystem(
-					rivet_config::config::db::FileSystem { path: temp_dir },
+					rivet_config::config::db::FileSystem {
+						path: temp_dir,
+						backup_path: None,
+					},
 				);
 
 				Ok((config, None))
 			}
+			TestDatabase::FoundationDb => {
+				let container_name = format!("test-foundationdb-{test_id}-{dc_label}");
+
+				// Check if container already exists and get its port
+				let (port, existing_container_id) = if let Some(container_id) =
+					crate::check_container_exists(&container_name).await?
+				{
+					let existing_port = crate::get_container_port(&container_name)
+						.await?
+						.context("foundationdb port")?;
+					(existing_port, Some(container_id))
+				} else {
+					let new_port = portpicker::pick_unused_port().context("foundationdb port")?;
+					(new_port, None)
+				};
+
+				// FDB clients connect to the address the server advertises, so the server has to listen on
+				// the same port inside and outside of the container
+				let temp_dir =
+					std::env::temp_dir().join(format!("rivet-test-{}-{}", test_id, dc_label));
+				std::fs::create_dir_all(&temp_dir)?;
+				let cluster_file_path = temp_dir.join("fdb.cluster");
+				std::fs::write(
+					&cluster_file_path,
+					format!("docker:docker@127.0.0.1:{port}"),
+				)?;
+
+				let config = rivet_config::config::Database::FoundationDb(
+					rivet_config::config::db::FoundationDb {
+						cluster_file_path: Some(cluster_file_path),
+					},
+				);
+
+				let docker_config = DockerRunConfig {
+					image: "foundationdb/foundationdb:7.1.61".to_string(),
+					container_name: container_name.clone(),
+					port_mapping: (port, port),
+					env_vars: vec![
+						("FDB_NETWORKING_MODE".to_string(), "host".to_string()),
+						("FDB_PORT".to_string(), port.to_string()),
+					],
+					container_id: existing_container_id,
+				};
+
+				Ok((config, Some(docker_config)))
+			}
 		}
 	}
 
+	/// Creates a new single node database in a freshly started FoundationDB container and waits for it to
+	/// become available.
+	pub async fn wait_for_foundationdb_ready(
+		container_name: &str,
+		max_attempts: u32,
+	) -> Result<()> {
+		let mut configured = false;
+
+		for attempt in 1..=max_attempts {
+			tracing::debug!(attempt, max_attempts, "Checking if FoundationDB is ready");
+
+			let command = if configured {
+				"status minimal"
+			} else {
+				"configure new single memory"
+			};
+			let output = tokio::process::Command::new("docker")
+				.arg("exec")
+				.arg(container_name)
+				.arg("fdbcli")
+				.arg("--timeout")
+				.arg("5")
+				.arg("--exec")
+				.arg(command)
+				.output()
+				.await?;
+			let stdout = String::from_utf8_lossy(&output.stdout);
+
+			if configured {
+				if stdout.contains("The database is available") {
+					tracing::debug!("FoundationDB is ready");
+					return Ok(());
+				}
+			} else if output.status.success() || stdout.contains("Database already exists") {
+				configured = true;
+				continue;
+			}
+
+			tracing::debug!(%stdout, attempt, "FoundationDB not ready yet");
+
+			if attempt < max_attempts {
+				sleep(Duration::from_millis(500)).await;
+			}
+		}
+
+		anyhow::bail!(
+			"FoundationDB failed to become ready after {} attempts",
+			max_attempts
+		)
+	}
+
 	/// Wait for Postgres to be ready to accept connections
 	pub async fn wait_for_postgres_ready(port: u16, max_attempts: u32) -> Result<()> {
 		use std::str::FromStr;
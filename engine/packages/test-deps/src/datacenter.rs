@@ -46,6 +46,16 @@ pub async fn setup_single_datacenter(
 			);
 			TestDatabase::wait_for_postgres_ready(docker_config.port_mapping.0, 10).await?;
 		}
+
+		// If FoundationDB was just started, create the database
+		if was_started && test_database == TestDatabase::FoundationDb {
+			tracing::info!(
+				dc = dc.datacenter_label,
+				port = docker_config.port_mapping.0,
+				"waiting for FoundationDB to be ready"
+			);
+			TestDatabase::wait_for_foundationdb_ready(&docker_config.container_name, 20).await?;
+		}
 	}
 
 	// Setup pubsub
@@ -21,6 +21,13 @@ pub struct Actor {
 	pub connectable_ts: Option<i64>,
 	pub sleep_ts: Option<i64>,
 	pub destroy_ts: Option<i64>,
+
+	/// Number of times the actor was restarted after crashing in the current restart window.
+	#[serde(default)]
+	pub restart_count: u32,
+	/// When the actor will be restarted, if a restart is pending.
+	#[serde(default)]
+	pub next_restart_ts: Option<i64>,
 }
 
 #[derive(Debug, Copy, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
@@ -32,6 +39,114 @@ pub enum CrashPolicy {
 	Destroy,
 }
 
+/// Controls how an actor is restarted after crashing when its crash policy is `restart`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct RestartPolicy {
+	/// Backoff before the second restart in a row. The first restart is immediate and the backoff doubles
+	/// with every restart after that.
+	pub initial_backoff_ms: u64,
+	/// Upper bound of the backoff.
+	pub max_backoff_ms: u64,
+	/// Max amount of restarts within `restart_window_ms`. Unlimited if not set.
+	pub max_restarts: Option<u32>,
+	/// The restart count and backoff are reset after this much time passes without a restart.
+	pub restart_window_ms: u64,
+	/// What to do with the actor once it crashes after using up `max_restarts`.
+	pub on_exhausted: RestartExhaustedAction,
+}
+
+impl Default for RestartPolicy {
+	fn default() -> Self {
+		RestartPolicy {
+			initial_backoff_ms: 2_000,
+			max_backoff_ms: 512_000,
+			max_restarts: None,
+			restart_window_ms: util::duration::minutes(10) as u64,
+			on_exhausted: RestartExhaustedAction::default(),
+		}
+	}
+}
+
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartExhaustedAction {
+	#[default]
+	Sleep,
+	Destroy,
+}
+
+/// Resources requested by an actor or advertised by a runner.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Resources {
+	/// CPU in thousandths of a core.
+	pub cpu_millicores: u32,
+	/// Memory in MiB.
+	pub memory_mib: u32,
+}
+
+/// Restricts which runners an actor can be allocated to.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PlacementConstraints {
+	/// Runner metadata labels the runner must have. Only string metadata values are matched.
+	#[serde(default)]
+	#[schema(value_type = std::collections::HashMap<String, String>)]
+	pub labels: util::serde::HashableMap<String, String>,
+	/// The actor will not be allocated to a runner that is already running an actor in any of these groups.
+	#[serde(default)]
+	pub anti_affinity_groups: Vec<String>,
+}
+
+impl PlacementConstraints {
+	pub fn is_empty(&self) -> bool {
+		self.labels.is_empty() && self.anti_affinity_groups.is_empty()
+	}
+}
+
+impl From<Resources> for rivet_data::generated::pegboard_resources_v1::Resources {
+	fn from(value: Resources) -> Self {
+		rivet_data::generated::pegboard_resources_v1::Resources {
+			cpu_millicores: value.cpu_millicores,
+			memory_mib: value.memory_mib,
+		}
+	}
+}
+
+impl From<rivet_data::generated::pegboard_resources_v1::Resources> for Resources {
+	fn from(value: rivet_data::generated::pegboard_resources_v1::Resources) -> Self {
+		Resources {
+			cpu_millicores: value.cpu_millicores,
+			memory_mib: value.memory_mib,
+		}
+	}
+}
+
+impl From<PlacementConstraints>
+	for rivet_data::generated::pegboard_actor_placement_v1::PlacementConstraints
+{
+	fn from(value: PlacementConstraints) -> Self {
+		rivet_data::generated::pegboard_actor_placement_v1::PlacementConstraints {
+			labels: value.labels.into_iter().collect(),
+			anti_affinity_groups: value.anti_affinity_groups,
+		}
+	}
+}
+
+impl From<rivet_data::generated::pegboard_actor_placement_v1::PlacementConstraints>
+	for PlacementConstraints
+{
+	fn from(
+		value: rivet_data::generated::pegboard_actor_placement_v1::PlacementConstraints,
+	) -> Self {
+		PlacementConstraints {
+			labels: value.labels.into_iter().collect(),
+			anti_affinity_groups: value.anti_affinity_groups,
+		}
+	}
+}
+
 #[derive(Debug, Deserialize, Serialize, Hash, ToSchema)]
 pub struct ActorName {
 	pub metadata: serde_json::Map<String, serde_json::Value>,
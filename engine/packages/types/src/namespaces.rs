@@ -7,4 +7,52 @@ pub struct Namespace {
 	pub name: String,
 	pub display_name: String,
 	pub create_ts: i64,
+	#[serde(default)]
+	pub kv_quota: KvQuota,
+}
+
+/// Limits applied to the KV store of every actor in a namespace.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct KvQuota {
+	/// Max total size of all entries of a single actor, in bytes.
+	pub max_storage_size: u64,
+	/// Max amount of entries per request.
+	pub max_keys: u32,
+	/// Max size of a single value, in bytes.
+	pub max_value_size: u32,
+	/// Max total size of all entries in a single write, in bytes.
+	pub max_put_payload_size: u32,
+}
+
+impl Default for KvQuota {
+	fn default() -> Self {
+		KvQuota {
+			max_storage_size: 1024 * 1024 * 1024, // 1 GiB
+			max_keys: 128,
+			max_value_size: 128 * 1024,
+			max_put_payload_size: 976 * 1024,
+		}
+	}
+}
+
+impl From<KvQuota> for rivet_data::generated::namespace_kv_quota_v1::KvQuota {
+	fn from(value: KvQuota) -> Self {
+		rivet_data::generated::namespace_kv_quota_v1::KvQuota {
+			max_storage_size: value.max_storage_size,
+			max_keys: value.max_keys,
+			max_value_size: value.max_value_size,
+			max_put_payload_size: value.max_put_payload_size,
+		}
+	}
+}
+
+impl From<rivet_data::generated::namespace_kv_quota_v1::KvQuota> for KvQuota {
+	fn from(value: rivet_data::generated::namespace_kv_quota_v1::KvQuota) -> Self {
+		KvQuota {
+			max_storage_size: value.max_storage_size,
+			max_keys: value.max_keys,
+			max_value_size: value.max_value_size,
+			max_put_payload_size: value.max_put_payload_size,
+		}
+	}
 }
@@ -9,6 +9,8 @@ pub struct RunnerConfig {
 	pub kind: RunnerConfigKind,
 	#[serde(default, skip_serializing_if = "Option::is_none")]
 	pub metadata: Option<serde_json::Value>,
+	#[serde(default)]
+	pub allocation_strategy: AllocationStrategy,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -27,14 +29,81 @@ pub enum RunnerConfigKind {
 	},
 }
 
-impl From<RunnerConfig> for rivet_data::generated::namespace_runner_config_v2::RunnerConfig {
+/// How pegboard picks a runner when allocating an actor. Only runners with the highest version are
+/// considered.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AllocationStrategy {
+	/// Fills the runner with the least remaining slots first.
+	BinPack {},
+	/// Picks the runner with the most remaining slots.
+	Spread {},
+	/// Prefers runners whose metadata contains all of the given labels (string values), spreading between
+	/// them. Falls back to any runner if none match.
+	LabelAffinity { labels: HashMap<String, String> },
+}
+
+impl Default for AllocationStrategy {
+	fn default() -> Self {
+		AllocationStrategy::BinPack {}
+	}
+}
+
+impl From<AllocationStrategy>
+	for rivet_data::generated::namespace_runner_config_v3::AllocationStrategy
+{
+	fn from(value: AllocationStrategy) -> Self {
+		match value {
+			AllocationStrategy::BinPack {} => {
+				rivet_data::generated::namespace_runner_config_v3::AllocationStrategy::BinPack
+			}
+			AllocationStrategy::Spread {} => {
+				rivet_data::generated::namespace_runner_config_v3::AllocationStrategy::Spread
+			}
+			AllocationStrategy::LabelAffinity { labels } => {
+				rivet_data::generated::namespace_runner_config_v3::AllocationStrategy::LabelAffinity(
+					rivet_data::generated::namespace_runner_config_v3::LabelAffinity {
+						labels: labels.into(),
+					},
+				)
+			}
+		}
+	}
+}
+
+impl From<rivet_data::generated::namespace_runner_config_v3::AllocationStrategy>
+	for AllocationStrategy
+{
+	fn from(value: rivet_data::generated::namespace_runner_config_v3::AllocationStrategy) -> Self {
+		match value {
+			rivet_data::generated::namespace_runner_config_v3::AllocationStrategy::BinPack => {
+				AllocationStrategy::BinPack {}
+			}
+			rivet_data::generated::namespace_runner_config_v3::AllocationStrategy::Spread => {
+				AllocationStrategy::Spread {}
+			}
+			rivet_data::generated::namespace_runner_config_v3::AllocationStrategy::LabelAffinity(o) => {
+				AllocationStrategy::LabelAffinity {
+					labels: o.labels.into(),
+				}
+			}
+		}
+	}
+}
+
+impl From<RunnerConfig> for rivet_data::generated::namespace_runner_config_v3::RunnerConfig {
 	fn from(value: RunnerConfig) -> Self {
-		let RunnerConfig { kind, metadata } = value;
-		rivet_data::generated::namespace_runner_config_v2::RunnerConfig {
+		let RunnerConfig {
+			kind,
+			metadata,
+			allocation_strategy,
+		} = value;
+		rivet_data::generated::namespace_runner_config_v3::RunnerConfig {
 			metadata: metadata.and_then(|value| serde_json::to_string(&value).ok()),
+			allocation_strategy: allocation_strategy.into(),
 			kind: match kind {
 				RunnerConfigKind::Normal {} => {
-					rivet_data::generated::namespace_runner_config_v2::RunnerConfigKind::Normal
+					rivet_data::generated::namespace_runner_config_v3::RunnerConfigKind::Normal
 				}
 				RunnerConfigKind::Serverless {
 					url,
@@ -45,8 +114,8 @@ impl From<RunnerConfig> for rivet_data::generated::namespace_runner_config_v2::R
 					max_runners,
 					runners_margin,
 				} => {
-					rivet_data::generated::namespace_runner_config_v2::RunnerConfigKind::Serverless(
-						rivet_data::generated::namespace_runner_config_v2::Serverless {
+					rivet_data::generated::namespace_runner_config_v3::RunnerConfigKind::Serverless(
+						rivet_data::generated::namespace_runner_config_v3::Serverless {
 							url,
 							headers: headers.into(),
 							request_lifespan,
@@ -62,17 +131,21 @@ impl From<RunnerConfig> for rivet_data::generated::namespace_runner_config_v2::R
 	}
 }
 
-impl From<rivet_data::generated::namespace_runner_config_v2::RunnerConfig> for RunnerConfig {
-	fn from(value: rivet_data::generated::namespace_runner_config_v2::RunnerConfig) -> Self {
-		let rivet_data::generated::namespace_runner_config_v2::RunnerConfig { metadata, kind } =
-			value;
+impl From<rivet_data::generated::namespace_runner_config_v3::RunnerConfig> for RunnerConfig {
+	fn from(value: rivet_data::generated::namespace_runner_config_v3::RunnerConfig) -> Self {
+		let rivet_data::generated::namespace_runner_config_v3::RunnerConfig {
+			metadata,
+			kind,
+			allocation_strategy,
+		} = value;
 		RunnerConfig {
 			metadata: metadata.and_then(|raw| serde_json::from_str(&raw).ok()),
+			allocation_strategy: allocation_strategy.into(),
 			kind: match kind {
-				rivet_data::generated::namespace_runner_config_v2::RunnerConfigKind::Normal => {
+				rivet_data::generated::namespace_runner_config_v3::RunnerConfigKind::Normal => {
 					RunnerConfigKind::Normal {}
 				}
-				rivet_data::generated::namespace_runner_config_v2::RunnerConfigKind::Serverless(
+				rivet_data::generated::namespace_runner_config_v3::RunnerConfigKind::Serverless(
 					o,
 				) => RunnerConfigKind::Serverless {
 					url: o.url,
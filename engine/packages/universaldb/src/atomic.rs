@@ -18,7 +18,7 @@ pub fn apply_atomic_op(
 		MutationType::ByteMax => Some(apply_byte_max(current, param)),
 		MutationType::CompareAndClear => apply_compare_and_clear(current, param),
 		MutationType::SetVersionstampedKey | MutationType::SetVersionstampedValue => {
-			// TODO: impl versionstamps
+			// Drivers fill in the versionstamp on commit, see `substitute_versionstamp_at_offset`
 			Some(param.to_vec())
 		}
 		// Deprecated operations (fallback to bitwise operations)
@@ -1,4 +1,4 @@
-use std::future::Future;
+use std::{future::Future, path::Path};
 
 use anyhow::{Context, Result, anyhow};
 use futures_util::FutureExt;
@@ -49,4 +49,10 @@ impl Database {
 	pub fn set_option(&self, opt: DatabaseOption) -> Result<()> {
 		self.driver.set_option(opt)
 	}
+
+	/// Creates a consistent copy of the database at the given path while it is in use. The path must not
+	/// exist yet. Only supported by the RocksDB driver.
+	pub async fn checkpoint(&self, path: &Path) -> Result<()> {
+		self.driver.checkpoint(path).await
+	}
 }
@@ -0,0 +1,162 @@
+use std::{
+	path::PathBuf,
+	sync::{Arc, Mutex, OnceLock},
+};
+
+use anyhow::{Context, Result};
+use foundationdb::{FdbError, api::NetworkAutoStop};
+
+use crate::{
+	RetryableTransaction, Transaction,
+	driver::{BoxFut, DatabaseDriver, Erased},
+	error::DatabaseError,
+	options::DatabaseOption,
+	tx_config::TransactionConfig,
+	utils::MaybeCommitted,
+};
+
+use super::transaction::FdbTransactionDriver;
+
+/// The FDB client network thread can only be started once per process. It is never stopped since the
+/// handle lives for the lifetime of the process.
+static NETWORK: OnceLock<NetworkAutoStop> = OnceLock::new();
+
+pub struct FdbDatabaseDriver {
+	db: Arc<foundationdb::Database>,
+	/// Only used for the retry limit in `run`, FDB enforces the other limits itself.
+	config: Mutex<TransactionConfig>,
+}
+
+impl FdbDatabaseDriver {
+	pub async fn new(cluster_file_path: Option<PathBuf>) -> Result<Self> {
+		tracing::info!(?cluster_file_path, "starting foundationdb driver");
+
+		// SAFETY: The network is only booted once and is never dropped
+		NETWORK.get_or_init(|| unsafe { foundationdb::boot() });
+
+		let cluster_file_path = cluster_file_path
+			.map(|path| {
+				path.to_str()
+					.map(ToString::to_string)
+					.context("cluster file path is not valid utf-8")
+			})
+			.transpose()?;
+		let db = foundationdb::Database::new(cluster_file_path.as_deref())
+			.context("failed to open foundationdb")?;
+
+		Ok(FdbDatabaseDriver {
+			db: Arc::new(db),
+			config: Mutex::new(TransactionConfig::default()),
+		})
+	}
+
+	fn create_native_trx(&self) -> Result<foundationdb::Transaction> {
+		self.db
+			.create_trx()
+			.context("failed to create foundationdb transaction")
+	}
+}
+
+impl DatabaseDriver for FdbDatabaseDriver {
+	fn create_trx(&self) -> Result<Transaction> {
+		let config = *self.config.lock().expect("poisoned lock");
+		let driver = FdbTransactionDriver::new(self.create_native_trx()?, config)?;
+
+		Ok(Transaction::new(Arc::new(driver)))
+	}
+
+	fn run<'a>(
+		&'a self,
+		closure: Box<dyn Fn(RetryableTransaction) -> BoxFut<'a, Result<Erased>> + Send + Sync + 'a>,
+	) -> BoxFut<'a, Result<Erased>> {
+		Box::pin(async move {
+			let mut maybe_committed = MaybeCommitted(false);
+			// Options set on the transaction are kept between attempts
+			let mut config = *self.config.lock().expect("poisoned lock");
+			let mut tx = self.create_native_trx()?;
+			let mut attempt = 0;
+
+			loop {
+				let driver = Arc::new(FdbTransactionDriver::new(tx, config)?);
+				let mut retryable = RetryableTransaction::new(Transaction::new(driver.clone()));
+				retryable.maybe_committed = maybe_committed;
+
+				// Execute transaction. FDB enforces its own 5 second transaction timeout.
+				let error = match closure(retryable.clone()).await {
+					Ok(res) => match retryable.inner.driver.commit_ref().await {
+						Ok(_) => return Ok(res),
+						Err(e) => e,
+					},
+					Err(e) => e,
+				};
+				config = driver.limits.config();
+
+				if let Some(fdb_error) = error
+					.chain()
+					.find_map(|x| x.downcast_ref::<FdbError>())
+					.copied()
+				{
+					// FDB decides whether the error is retryable and backs off before handing back the reset
+					// transaction
+					tx = match driver.on_error(fdb_error).await {
+						Ok(tx) => tx,
+						Err(_) => return Err(error),
+					};
+
+					if fdb_error.is_maybe_committed() {
+						maybe_committed = MaybeCommitted(true);
+					}
+				} else if let Some(db_error) = error
+					.chain()
+					.find_map(|x| x.downcast_ref::<DatabaseError>())
+					.filter(|db_error| db_error.is_retryable())
+				{
+					if db_error.is_maybe_committed() {
+						maybe_committed = MaybeCommitted(true);
+					}
+
+					tokio::time::sleep(config.retry_backoff(attempt)).await;
+					tx = self.create_native_trx()?;
+				} else {
+					return Err(error);
+				}
+
+				attempt += 1;
+				if config.retries_exhausted(attempt) {
+					return Err(DatabaseError::MaxRetriesReached.into());
+				}
+			}
+		})
+	}
+
+	fn set_option(&self, opt: DatabaseOption) -> Result<()> {
+		let mut config = self.config.lock().expect("poisoned lock");
+		let mut new_config = *config;
+		new_config.set_database_option(opt.clone())?;
+
+		let native_opt = match opt {
+			DatabaseOption::TransactionTimeout(ms) => {
+				foundationdb::options::DatabaseOption::TransactionTimeout(ms)
+			}
+			DatabaseOption::TransactionSizeLimit(bytes) => {
+				foundationdb::options::DatabaseOption::TransactionSizeLimit(bytes)
+			}
+			// Used by the backoff in `on_error`
+			DatabaseOption::TransactionMaxRetryDelay(ms) => {
+				foundationdb::options::DatabaseOption::TransactionMaxRetryDelay(ms)
+			}
+			// Handled by `run`
+			DatabaseOption::TransactionRetryLimit(_) => {
+				*config = new_config;
+				return Ok(());
+			}
+		};
+
+		self.db
+			.set_option(native_opt)
+			.context("failed to set foundationdb database option")?;
+		*config = new_config;
+
+		Ok(())
+	}
+}
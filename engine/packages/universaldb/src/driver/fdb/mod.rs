@@ -0,0 +1,4 @@
+mod database;
+mod transaction;
+
+pub use database::FdbDatabaseDriver;
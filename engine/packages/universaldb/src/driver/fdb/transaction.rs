@@ -0,0 +1,417 @@
+use std::{
+	borrow::Cow,
+	future::Future,
+	pin::Pin,
+	sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use foundationdb::FdbError;
+
+use crate::{
+	driver::TransactionDriver,
+	error::DatabaseError,
+	key_selector::KeySelector,
+	options::{ConflictRangeType, MutationType, StreamingMode, TransactionOption},
+	range_option::RangeOption,
+	tuple::Versionstamp,
+	tx_config::{TransactionConfig, TransactionLimits},
+	utils::IsolationLevel,
+	value::{KeyValue, Slice, Value, Values},
+};
+
+pub struct FdbTransactionDriver {
+	/// Taken when the transaction is committed or cancelled. Reads hold a clone of the `Arc` while they are
+	/// in flight, so a commit issued during a read fails instead of racing with it.
+	tx: Mutex<Option<Arc<foundationdb::Transaction>>>,
+	/// Set if the commit failed, kept so the transaction can be handed back to FDB by `on_error`.
+	failed_commit: Mutex<Option<foundationdb::TransactionCommitError>>,
+	/// Only used for the retry loop in `run`, FDB enforces the other limits itself.
+	pub(super) limits: TransactionLimits,
+}
+
+impl FdbTransactionDriver {
+	/// Applies the options of the given config since they are kept between attempts of `run`.
+	pub fn new(tx: foundationdb::Transaction, config: TransactionConfig) -> Result<Self> {
+		if let Some(timeout) = config.timeout {
+			tx.set_option(foundationdb::options::TransactionOption::Timeout(
+				timeout.as_millis().try_into()?,
+			))
+			.context("failed to set foundationdb transaction timeout")?;
+		}
+
+		if let Some(max_retry_delay) = config.max_retry_delay {
+			tx.set_option(foundationdb::options::TransactionOption::MaxRetryDelay(
+				max_retry_delay.as_millis().try_into()?,
+			))
+			.context("failed to set foundationdb transaction max retry delay")?;
+		}
+
+		tx.set_option(foundationdb::options::TransactionOption::SizeLimit(
+			config.size_limit.try_into()?,
+		))
+		.context("failed to set foundationdb transaction size limit")?;
+
+		Ok(FdbTransactionDriver {
+			tx: Mutex::new(Some(Arc::new(tx))),
+			failed_commit: Mutex::new(None),
+			limits: TransactionLimits::new(config),
+		})
+	}
+
+	fn tx(&self) -> Result<Arc<foundationdb::Transaction>> {
+		self.tx
+			.lock()
+			.expect("poisoned lock")
+			.clone()
+			.ok_or_else(|| DatabaseError::UsedDuringCommit.into())
+	}
+
+	/// Takes ownership of the inner transaction. Fails if any operation is still in flight.
+	fn take(&self) -> Result<Option<foundationdb::Transaction>> {
+		let Some(tx) = self.tx.lock().expect("poisoned lock").take() else {
+			return Ok(None);
+		};
+
+		Arc::try_unwrap(tx)
+			.map(Some)
+			.map_err(|_| DatabaseError::UsedDuringCommit.into())
+	}
+
+	/// Runs a write operation. Write operations cannot return errors, so writes issued after commit are
+	/// logged and dropped.
+	fn write(&self, f: impl FnOnce(&foundationdb::Transaction)) {
+		match self.tx() {
+			Ok(tx) => f(&tx),
+			Err(err) => tracing::warn!(?err, "dropping write issued after commit"),
+		}
+	}
+
+	async fn commit_inner(&self) -> Result<()> {
+		let Some(tx) = self.take()? else {
+			return Ok(());
+		};
+
+		match tx.commit().await {
+			Ok(_) => Ok(()),
+			Err(err) => {
+				let fdb_error = *err;
+				*self.failed_commit.lock().expect("poisoned lock") = Some(err);
+
+				Err(fdb_error.into())
+			}
+		}
+	}
+
+	/// Hands the error of a failed attempt to FDB. If the error is retryable, FDB backs off and returns the
+	/// reset transaction for the next attempt.
+	pub(super) async fn on_error(&self, err: FdbError) -> Result<foundationdb::Transaction> {
+		let failed_commit = self.failed_commit.lock().expect("poisoned lock").take();
+
+		let tx = if let Some(failed_commit) = failed_commit {
+			failed_commit.on_error().await
+		} else {
+			let tx = self
+				.take()?
+				.context("foundationdb transaction was already cancelled")?;
+
+			tx.on_error(err).await
+		};
+
+		tx.context("foundationdb transaction is not retryable")
+	}
+}
+
+impl TransactionDriver for FdbTransactionDriver {
+	fn atomic_op(&self, key: &[u8], param: &[u8], op_type: MutationType) {
+		self.write(|tx| tx.atomic_op(key, param, convert_mutation_type(op_type)));
+	}
+
+	fn get<'a>(
+		&'a self,
+		key: &[u8],
+		isolation_level: IsolationLevel,
+	) -> Pin<Box<dyn Future<Output = Result<Option<Slice>>> + Send + 'a>> {
+		let key = key.to_vec();
+
+		Box::pin(async move {
+			let tx = self.tx()?;
+			let value = tx
+				.get(&key, is_snapshot(isolation_level))
+				.await
+				.context("failed to get key from foundationdb")?;
+
+			Ok(value.map(|value| Slice::from(value.to_vec())))
+		})
+	}
+
+	fn get_key<'a>(
+		&'a self,
+		selector: &KeySelector<'a>,
+		isolation_level: IsolationLevel,
+	) -> Pin<Box<dyn Future<Output = Result<Slice>> + Send + 'a>> {
+		let selector = convert_key_selector(selector);
+
+		Box::pin(async move {
+			let tx = self.tx()?;
+			let key = tx
+				.get_key(&selector, is_snapshot(isolation_level))
+				.await
+				.context("failed to resolve key selector in foundationdb")?;
+
+			Ok(Slice::from(key.to_vec()))
+		})
+	}
+
+	fn get_range<'a>(
+		&'a self,
+		opt: &RangeOption<'a>,
+		iteration: usize,
+		isolation_level: IsolationLevel,
+	) -> Pin<Box<dyn Future<Output = Result<Values>> + Send + 'a>> {
+		let opt = foundationdb::RangeOption {
+			begin: convert_key_selector(&opt.begin),
+			end: convert_key_selector(&opt.end),
+			limit: opt.limit,
+			target_bytes: opt.target_bytes,
+			mode: convert_streaming_mode(opt.mode),
+			reverse: opt.reverse,
+			..Default::default()
+		};
+
+		Box::pin(async move {
+			let tx = self.tx()?;
+			let values = tx
+				.get_range(&opt, iteration, is_snapshot(isolation_level))
+				.await
+				.context("failed to get range from foundationdb")?;
+
+			Ok(Values::with_more(
+				values
+					.iter()
+					.map(|kv| KeyValue::new(kv.key().to_vec(), kv.value().to_vec()))
+					.collect(),
+				values.more(),
+			))
+		})
+	}
+
+	fn get_ranges_keyvalues<'a>(
+		&'a self,
+		opt: RangeOption<'a>,
+		isolation_level: IsolationLevel,
+	) -> crate::value::Stream<'a, Value> {
+		use futures_util::{StreamExt, TryStreamExt, stream};
+
+		// Fetch the range in batches, increasing the iteration each time so FDB can grow the batch size
+		// according to the streaming mode
+		let batches = stream::unfold(Some((opt, 1)), move |state| async move {
+			let (opt, iteration) = state?;
+
+			match self.get_range(&opt, iteration, isolation_level).await {
+				Ok(values) => {
+					let next = opt
+						.next_range(&values)
+						.map(|next_opt| (next_opt, iteration + 1));
+
+					Some((Ok(values), next))
+				}
+				Err(err) => Some((Err(err), None)),
+			}
+		});
+
+		Box::pin(
+			batches
+				.map_ok(|values| {
+					stream::iter(values.into_iter().map(|kv| Ok(Value::from_keyvalue(kv))))
+				})
+				.try_flatten(),
+		)
+	}
+
+	fn set(&self, key: &[u8], value: &[u8]) {
+		// Versionstamps are only filled in by the `SetVersionstampedKey` and `SetVersionstampedValue` atomic
+		// ops
+		self.write(|tx| tx.set(key, value));
+	}
+
+	fn clear(&self, key: &[u8]) {
+		self.write(|tx| tx.clear(key));
+	}
+
+	fn clear_range(&self, begin: &[u8], end: &[u8]) {
+		self.write(|tx| tx.clear_range(begin, end));
+	}
+
+	fn commit(self: Box<Self>) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+		Box::pin(async move { self.commit_inner().await })
+	}
+
+	fn reset(&mut self) {
+		let mut guard = self.tx.lock().expect("poisoned lock");
+
+		if let Some(tx) = guard.as_mut().and_then(Arc::get_mut) {
+			tx.reset();
+		} else {
+			tracing::warn!("cannot reset foundationdb transaction while it is in use");
+		}
+	}
+
+	fn cancel(&self) {
+		match self.take() {
+			Ok(Some(tx)) => {
+				tx.cancel();
+			}
+			Ok(None) => {}
+			Err(err) => tracing::warn!(?err, "failed to cancel foundationdb transaction"),
+		}
+	}
+
+	fn add_conflict_range(
+		&self,
+		begin: &[u8],
+		end: &[u8],
+		conflict_type: ConflictRangeType,
+	) -> Result<()> {
+		self.tx()?
+			.add_conflict_range(begin, end, convert_conflict_range_type(conflict_type))
+			.context("failed to add conflict range in foundationdb")
+	}
+
+	fn get_estimated_range_size_bytes<'a>(
+		&'a self,
+		begin: &'a [u8],
+		end: &'a [u8],
+	) -> Pin<Box<dyn Future<Output = Result<i64>> + Send + 'a>> {
+		Box::pin(async move {
+			let tx = self.tx()?;
+			tx.get_estimated_range_size_bytes(begin, end)
+				.await
+				.context("failed to get estimated range size from foundationdb")
+		})
+	}
+
+	fn commit_ref(&self) -> Pin<Box<dyn Future<Output = Result<()>> + Send + '_>> {
+		Box::pin(self.commit_inner())
+	}
+
+	fn watch(&self, key: &[u8]) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+		// The watch future is independent of the transaction, it only needs to be created before commit
+		let watch = self.tx().map(|tx| tx.watch(key));
+
+		Box::pin(async move { watch?.await.context("failed to watch key in foundationdb") })
+	}
+
+	fn get_versionstamp(&self) -> Pin<Box<dyn Future<Output = Result<Versionstamp>> + Send>> {
+		// Like watches, the versionstamp future only needs to be created before commit
+		let versionstamp = self.tx().map(|tx| tx.get_versionstamp());
+
+		Box::pin(async move {
+			let versionstamp = versionstamp?
+				.await
+				.context("failed to get foundationdb versionstamp")?;
+			let tx_versionstamp = <[u8; 10]>::try_from(&versionstamp[..])
+				.context("invalid foundationdb versionstamp")?;
+
+			Ok(Versionstamp::complete(tx_versionstamp, 0))
+		})
+	}
+
+	fn get_read_version(&self) -> Pin<Box<dyn Future<Output = Result<i64>> + Send + '_>> {
+		Box::pin(async move {
+			let tx = self.tx()?;
+			tx.get_read_version()
+				.await
+				.context("failed to get foundationdb read version")
+		})
+	}
+
+	fn set_read_version(&self, version: i64) -> Result<()> {
+		self.tx()?.set_read_version(version);
+
+		Ok(())
+	}
+
+	fn set_option(&self, opt: TransactionOption) -> Result<()> {
+		let native_opt = match &opt {
+			TransactionOption::Timeout(ms) => {
+				Some(foundationdb::options::TransactionOption::Timeout(*ms))
+			}
+			TransactionOption::SizeLimit(bytes) => {
+				Some(foundationdb::options::TransactionOption::SizeLimit(*bytes))
+			}
+			// Used by the backoff in `on_error`
+			TransactionOption::MaxRetryDelay(ms) => {
+				Some(foundationdb::options::TransactionOption::MaxRetryDelay(*ms))
+			}
+			_ => None,
+		};
+
+		self.limits.set_option(opt)?;
+
+		if let Some(native_opt) = native_opt {
+			self.tx()?
+				.set_option(native_opt)
+				.context("failed to set foundationdb transaction option")?;
+		}
+
+		Ok(())
+	}
+}
+
+fn is_snapshot(isolation_level: IsolationLevel) -> bool {
+	matches!(isolation_level, IsolationLevel::Snapshot)
+}
+
+fn convert_key_selector(selector: &KeySelector) -> foundationdb::KeySelector<'static> {
+	foundationdb::KeySelector::new(
+		Cow::Owned(selector.key().to_vec()),
+		selector.or_equal(),
+		selector.offset(),
+	)
+}
+
+fn convert_streaming_mode(mode: StreamingMode) -> foundationdb::options::StreamingMode {
+	use foundationdb::options::StreamingMode as Fdb;
+
+	match mode {
+		StreamingMode::WantAll => Fdb::WantAll,
+		StreamingMode::Iterator => Fdb::Iterator,
+		StreamingMode::Exact => Fdb::Exact,
+		StreamingMode::Small => Fdb::Small,
+		StreamingMode::Medium => Fdb::Medium,
+		StreamingMode::Large => Fdb::Large,
+		StreamingMode::Serial => Fdb::Serial,
+	}
+}
+
+fn convert_mutation_type(op_type: MutationType) -> foundationdb::options::MutationType {
+	use foundationdb::options::MutationType as Fdb;
+
+	match op_type {
+		MutationType::Add => Fdb::Add,
+		MutationType::And => Fdb::And,
+		MutationType::BitAnd => Fdb::BitAnd,
+		MutationType::Or => Fdb::Or,
+		MutationType::BitOr => Fdb::BitOr,
+		MutationType::Xor => Fdb::Xor,
+		MutationType::BitXor => Fdb::BitXor,
+		MutationType::AppendIfFits => Fdb::AppendIfFits,
+		MutationType::Max => Fdb::Max,
+		MutationType::Min => Fdb::Min,
+		MutationType::SetVersionstampedKey => Fdb::SetVersionstampedKey,
+		MutationType::SetVersionstampedValue => Fdb::SetVersionstampedValue,
+		MutationType::ByteMin => Fdb::ByteMin,
+		MutationType::ByteMax => Fdb::ByteMax,
+		MutationType::CompareAndClear => Fdb::CompareAndClear,
+	}
+}
+
+fn convert_conflict_range_type(
+	conflict_type: ConflictRangeType,
+) -> foundationdb::options::ConflictRangeType {
+	match conflict_type {
+		ConflictRangeType::Read => foundationdb::options::ConflictRangeType::Read,
+		ConflictRangeType::Write => foundationdb::options::ConflictRangeType::Write,
+	}
+}
@@ -1,19 +1,25 @@
-use std::{any::Any, future::Future, pin::Pin, sync::Arc};
+use std::{any::Any, future::Future, path::Path, pin::Pin, sync::Arc};
 
 use anyhow::{Result, bail};
 
 use crate::{
+	error::DatabaseError,
 	key_selector::KeySelector,
-	options::{ConflictRangeType, DatabaseOption, MutationType},
+	options::{ConflictRangeType, DatabaseOption, MutationType, TransactionOption},
 	range_option::RangeOption,
 	transaction::{RetryableTransaction, Transaction},
+	tuple::Versionstamp,
 	utils::IsolationLevel,
 	value::{Slice, Value, Values},
 };
 
+#[cfg(feature = "fdb")]
+mod fdb;
 mod postgres;
 pub mod rocksdb;
 
+#[cfg(feature = "fdb")]
+pub use fdb::FdbDatabaseDriver;
 pub use postgres::PostgresDatabaseDriver;
 pub use rocksdb::RocksDbDatabaseDriver;
 
@@ -29,6 +35,13 @@ pub trait DatabaseDriver: Send + Sync {
 		closure: Box<dyn Fn(RetryableTransaction) -> BoxFut<'a, Result<Erased>> + Send + Sync + 'a>,
 	) -> BoxFut<'a, Result<Erased>>;
 	fn set_option(&self, opt: DatabaseOption) -> Result<()>;
+
+	// Backups
+	fn checkpoint<'a>(&'a self, _path: &'a Path) -> BoxFut<'a, Result<()>> {
+		Box::pin(async move {
+			bail!("checkpoints are only supported by the rocksdb driver");
+		})
+	}
 }
 
 pub trait TransactionDriver: Send + Sync {
@@ -84,4 +97,29 @@ pub trait TransactionDriver: Send + Sync {
 			bail!("`commit_ref` unimplemented");
 		})
 	}
+
+	// Watches and versions
+	fn watch(&self, _key: &[u8]) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+		Box::pin(async move {
+			bail!("`watch` unimplemented");
+		})
+	}
+	fn get_versionstamp(&self) -> Pin<Box<dyn Future<Output = Result<Versionstamp>> + Send>> {
+		Box::pin(async move {
+			bail!("`get_versionstamp` unimplemented");
+		})
+	}
+	fn get_read_version(&self) -> Pin<Box<dyn Future<Output = Result<i64>> + Send + '_>> {
+		Box::pin(async move {
+			bail!("`get_read_version` unimplemented");
+		})
+	}
+	/// Only the FDB driver keeps the history needed to read at a previous version.
+	fn set_read_version(&self, _version: i64) -> Result<()> {
+		Err(DatabaseError::ReadVersionUnsupported.into())
+	}
+
+	fn set_option(&self, _opt: TransactionOption) -> Result<()> {
+		bail!("`set_option` unimplemented");
+	}
 }
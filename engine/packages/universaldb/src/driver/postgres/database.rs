@@ -1,8 +1,5 @@
 use std::{
-	sync::{
-		Arc,
-		atomic::{AtomicI32, Ordering},
-	},
+	sync::{Arc, Mutex},
 	time::Duration,
 };
 
@@ -16,18 +13,21 @@ use crate::{
 	driver::{BoxFut, DatabaseDriver, Erased},
 	error::DatabaseError,
 	options::DatabaseOption,
-	utils::{MaybeCommitted, calculate_tx_retry_backoff},
+	tx_config::TransactionConfig,
+	utils::MaybeCommitted,
+	watch::WatchRegistry,
 };
 
-use super::transaction::PostgresTransactionDriver;
+use super::{transaction::PostgresTransactionDriver, watch};
 
-const TXN_TIMEOUT: Duration = Duration::from_secs(5);
 const GC_INTERVAL: Duration = Duration::from_secs(5);
 
 pub struct PostgresDatabaseDriver {
 	pool: Arc<Pool>,
-	max_retries: AtomicI32,
+	config: Mutex<TransactionConfig>,
 	gc_handle: JoinHandle<()>,
+	watches: WatchRegistry,
+	watch_handle: JoinHandle<()>,
 }
 
 impl PostgresDatabaseDriver {
@@ -36,7 +36,7 @@ impl PostgresDatabaseDriver {
 
 		// Create deadpool config from connection string
 		let mut config = Config::new();
-		config.url = Some(connection_string);
+		config.url = Some(connection_string.clone());
 		config.pool = Some(PoolConfig {
 			max_size: 64,
 			..Default::default()
@@ -142,6 +142,32 @@ impl PostgresDatabaseDriver {
 		.await
 		.context("failed to create index on conflict_ranges ts column")?;
 
+		// Keys with watches on any node, see `watch::INSERT_WATCHED_KEYS_QUERY`. Writes only notify keys in this
+		// table.
+		conn.execute(
+			"CREATE UNLOGGED TABLE IF NOT EXISTS watched_keys (
+				key BYTEA NOT NULL,
+				expire_ts timestamp NOT NULL
+			)",
+			&[],
+		)
+		.await
+		.context("failed to create watched_keys table")?;
+
+		conn.execute(
+			"CREATE INDEX IF NOT EXISTS idx_watched_keys_key ON watched_keys (key)",
+			&[],
+		)
+		.await
+		.context("failed to create index on watched_keys key column")?;
+
+		conn.execute(
+			"CREATE INDEX IF NOT EXISTS idx_watched_keys_expire_ts ON watched_keys (expire_ts)",
+			&[],
+		)
+		.await
+		.context("failed to create index on watched_keys expire_ts column")?;
+
 		let gc_handle = tokio::spawn(async move {
 			let mut interval = tokio::time::interval(GC_INTERVAL);
 			interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
@@ -160,23 +186,46 @@ impl PostgresDatabaseDriver {
 				{
 					tracing::error!(?err, "failed postgres gc task");
 				}
+
+				// Delete watched keys that were not refreshed
+				if let Err(err) = conn
+					.execute("DELETE FROM watched_keys WHERE expire_ts < now()", &[])
+					.await
+				{
+					tracing::error!(?err, "failed postgres gc task");
+				}
 			}
 		});
 
+		let watches = WatchRegistry::default();
+		let watch_handle = tokio::spawn(watch::listen(connection_string, watches.clone()));
+
 		Ok(PostgresDatabaseDriver {
 			pool: Arc::new(pool),
-			max_retries: AtomicI32::new(100),
+			config: Mutex::new(TransactionConfig::default()),
 			gc_handle,
+			watches,
+			watch_handle,
 		})
 	}
 }
 
-impl DatabaseDriver for PostgresDatabaseDriver {
-	fn create_trx(&self) -> Result<Transaction> {
+impl PostgresDatabaseDriver {
+	fn create_trx_driver(&self, config: TransactionConfig) -> Arc<PostgresTransactionDriver> {
 		// Pass the connection pool to the transaction driver
-		Ok(Transaction::new(Arc::new(PostgresTransactionDriver::new(
+		Arc::new(PostgresTransactionDriver::new(
 			self.pool.clone(),
-		))))
+			self.watches.clone(),
+			config,
+		))
+	}
+}
+
+impl DatabaseDriver for PostgresDatabaseDriver {
+	fn create_trx(&self) -> Result<Transaction> {
+		let config = *self.config.lock().expect("poisoned lock");
+
+		Ok(Transaction::new(self.create_trx_driver(config)))
 	}
 
 	fn run<'a>(
@@ -185,23 +234,24 @@ impl DatabaseDriver for PostgresDatabaseDriver {
 	) -> BoxFut<'a, Result<Erased>> {
 		Box::pin(async move {
 			let mut maybe_committed = MaybeCommitted(false);
-			let max_retries = self.max_retries.load(Ordering::SeqCst);
+			// Options set on the transaction are kept between attempts
+			let mut config = *self.config.lock().expect("poisoned lock");
+			let mut attempt = 0;
 
-			for attempt in 0..max_retries {
-				let tx = self.create_trx()?;
-				let mut retryable = RetryableTransaction::new(tx);
+			while !config.retries_exhausted(attempt) {
+				let driver = self.create_trx_driver(config);
+				let mut retryable = RetryableTransaction::new(Transaction::new(driver.clone()));
 				retryable.maybe_committed = maybe_committed;
 
 				// Execute transaction
-				let error =
-					match tokio::time::timeout(TXN_TIMEOUT, closure(retryable.clone())).await {
-						Ok(Ok(res)) => match retryable.inner.driver.commit_ref().await {
-							Ok(_) => return Ok(res),
-							Err(e) => e,
-						},
-						Ok(Err(e)) => e,
-						Err(_) => anyhow::Error::from(DatabaseError::TransactionTooOld),
-					};
+				let error = match driver.limits.with_timeout(closure(retryable.clone())).await {
+					Ok(res) => match retryable.inner.driver.commit_ref().await {
+						Ok(_) => return Ok(res),
+						Err(e) => e,
+					},
+					Err(e) => e,
+				};
+				config = driver.limits.config();
 
 				let chain = error
 					.chain()
@@ -214,8 +264,8 @@ impl DatabaseDriver for PostgresDatabaseDriver {
 							maybe_committed = MaybeCommitted(true);
 						}
 
-						let backoff_ms = calculate_tx_retry_backoff(attempt as usize);
-						tokio::time::sleep(tokio::time::Duration::from_millis(backoff_ms)).await;
+						tokio::time::sleep(config.retry_backoff(attempt)).await;
+						attempt += 1;
 						continue;
 					}
 				}
@@ -228,17 +278,16 @@ impl DatabaseDriver for PostgresDatabaseDriver {
 	}
 
 	fn set_option(&self, opt: DatabaseOption) -> Result<()> {
-		match opt {
-			DatabaseOption::TransactionRetryLimit(limit) => {
-				self.max_retries.store(limit, Ordering::SeqCst);
-				Ok(())
-			}
-		}
+		self.config
+			.lock()
+			.expect("poisoned lock")
+			.set_database_option(opt)
 	}
 }
 
 impl Drop for PostgresDatabaseDriver {
 	fn drop(&mut self) {
 		self.gc_handle.abort();
+		self.watch_handle.abort();
 	}
 }
@@ -1,5 +1,6 @@
 mod database;
 mod transaction;
 mod transaction_task;
+mod watch;
 
 pub use database::PostgresDatabaseDriver;
@@ -14,11 +14,15 @@ use tokio::sync::{OnceCell, mpsc, oneshot};
 use crate::{
 	driver::TransactionDriver,
 	key_selector::KeySelector,
-	options::{ConflictRangeType, MutationType},
+	options::{ConflictRangeType, MutationType, TransactionOption},
 	range_option::RangeOption,
+	tuple::Versionstamp,
+	tx_config::{TransactionConfig, TransactionLimits},
 	tx_ops::TransactionOperations,
 	utils::IsolationLevel,
 	value::{Slice, Value, Values},
+	versionstamp::PendingVersionstamps,
+	watch::{PendingWatches, WatchRegistry},
 };
 
 use super::transaction_task::{TransactionCommand, TransactionTask};
@@ -28,15 +32,23 @@ pub struct PostgresTransactionDriver {
 	operations: TransactionOperations,
 	committed: AtomicBool,
 	tx_sender: OnceCell<mpsc::Sender<TransactionCommand>>,
+	watches: WatchRegistry,
+	pending_watches: PendingWatches,
+	pending_versionstamps: PendingVersionstamps,
+	pub(super) limits: TransactionLimits,
 }
 
 impl PostgresTransactionDriver {
-	pub fn new(pool: Arc<Pool>) -> Self {
+	pub fn new(pool: Arc<Pool>, watches: WatchRegistry, config: TransactionConfig) -> Self {
 		PostgresTransactionDriver {
 			pool,
 			operations: TransactionOperations::default(),
 			committed: AtomicBool::new(false),
 			tx_sender: OnceCell::new(),
+			watches,
+			pending_watches: PendingWatches::default(),
+			pending_versionstamps: PendingVersionstamps::default(),
+			limits: TransactionLimits::new(config),
 		}
 	}
 
@@ -222,6 +234,7 @@ impl TransactionDriver for PostgresTransactionDriver {
 			self.committed.store(true, Ordering::SeqCst);
 
 			let (operations, conflict_ranges) = self.operations.consume();
+			self.limits.check_commit(&operations, &conflict_ranges)?;
 
 			let tx_sender = self.ensure_transaction().await?;
 
@@ -231,22 +244,29 @@ impl TransactionDriver for PostgresTransactionDriver {
 				.send(TransactionCommand::Commit {
 					operations,
 					conflict_ranges,
+					watched_keys: self.pending_watches.keys(),
 					response: response_tx,
 				})
 				.await
 				.context("failed to send postgres transaction command")?;
 
 			// Wait for commit response
-			response_rx
+			let tx_versionstamp = response_rx
 				.await
 				.context("failed to receive postgres commit response")??;
 
+			self.pending_watches.register(&self.watches);
+			self.pending_versionstamps.resolve(tx_versionstamp);
+
 			Ok(())
 		})
 	}
 
 	fn reset(&mut self) {
 		self.operations.clear_all();
+		self.pending_watches.clear();
+		self.pending_versionstamps.clear();
+		self.limits.reset();
 		self.committed.store(false, Ordering::SeqCst);
 
 		// Replace tx sender to get a new txn version
@@ -255,6 +275,8 @@ impl TransactionDriver for PostgresTransactionDriver {
 
 	fn cancel(&self) {
 		self.operations.clear_all();
+		self.pending_watches.clear();
+		self.pending_versionstamps.clear();
 		self.committed.store(true, Ordering::SeqCst); // Prevent future commits
 
 		// Transaction will be rolled back when dropped
@@ -311,6 +333,7 @@ impl TransactionDriver for PostgresTransactionDriver {
 			self.committed.store(true, Ordering::SeqCst);
 
 			let (operations, conflict_ranges) = self.operations.consume();
+			self.limits.check_commit(&operations, &conflict_ranges)?;
 
 			// We have operations but no transaction - create one just for commit
 			let tx_sender = self.ensure_transaction().await?;
@@ -321,17 +344,53 @@ impl TransactionDriver for PostgresTransactionDriver {
 				.send(TransactionCommand::Commit {
 					operations,
 					conflict_ranges,
+					watched_keys: self.pending_watches.keys(),
 					response: response_tx,
 				})
 				.await
 				.context("failed to send postgres transaction command")?;
 
 			// Wait for commit response
-			response_rx
+			let tx_versionstamp = response_rx
 				.await
 				.context("failed to receive postgres commit response")??;
 
+			self.pending_watches.register(&self.watches);
+			self.pending_versionstamps.resolve(tx_versionstamp);
+
 			Ok(())
 		})
 	}
+
+	fn watch(&self, key: &[u8]) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+		Box::pin(self.pending_watches.add(key))
+	}
+
+	fn get_versionstamp(&self) -> Pin<Box<dyn Future<Output = Result<Versionstamp>> + Send>> {
+		Box::pin(self.pending_versionstamps.add())
+	}
+
+	fn get_read_version(&self) -> Pin<Box<dyn Future<Output = Result<i64>> + Send + '_>> {
+		Box::pin(async move {
+			let tx_sender = self.ensure_transaction().await?;
+
+			// Send query command
+			let (response_tx, response_rx) = oneshot::channel();
+			tx_sender
+				.send(TransactionCommand::GetReadVersion {
+					response: response_tx,
+				})
+				.await
+				.context("failed to send postgres command")?;
+
+			// Wait for response
+			response_rx
+				.await
+				.context("failed to receive postgres read version response")
+		})
+	}
+
+	fn set_option(&self, opt: TransactionOption) -> Result<()> {
+		self.limits.set_option(opt)
+	}
 }
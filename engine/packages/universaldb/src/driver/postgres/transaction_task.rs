@@ -6,10 +6,15 @@ use tokio_postgres::IsolationLevel;
 use crate::{
 	atomic::apply_atomic_op,
 	error::DatabaseError,
-	options::ConflictRangeType,
+	options::{ConflictRangeType, MutationType},
 	tx_ops::Operation,
 	value::{KeyValue, Slice, Values},
-	versionstamp::substitute_versionstamp_if_incomplete,
+	versionstamp::substitute_versionstamp_at_offset,
+};
+
+use super::watch::{
+	INSERT_WATCHED_KEYS_QUERY, MAX_PAYLOAD_SIZE, NOTIFY_ALL_PAYLOAD, NOTIFY_WATCHED_KEYS_QUERY,
+	WATCH_CHANNEL, changed_keys,
 };
 
 pub enum TransactionCommand {
@@ -39,13 +44,18 @@ pub enum TransactionCommand {
 	Commit {
 		operations: Vec<Operation>,
 		conflict_ranges: Vec<(Vec<u8>, Vec<u8>, ConflictRangeType)>,
-		response: oneshot::Sender<Result<()>>,
+		/// Keys of the watches created by this transaction.
+		watched_keys: Vec<Vec<u8>>,
+		response: oneshot::Sender<Result<[u8; 10]>>,
 	},
 	GetEstimatedRangeSize {
 		begin: Vec<u8>,
 		end: Vec<u8>,
 		response: oneshot::Sender<Result<i64>>,
 	},
+	GetReadVersion {
+		response: oneshot::Sender<i64>,
+	},
 }
 
 /// TransactionTask runs in a separate tokio task to manage a PostgreSQL transaction.
@@ -152,10 +162,11 @@ impl TransactionTask {
 				TransactionCommand::Commit {
 					operations,
 					conflict_ranges,
+					watched_keys,
 					response,
 				} => {
 					let result = self
-						.handle_commit(tx, start_version, operations, conflict_ranges)
+						.handle_commit(tx, start_version, operations, conflict_ranges, watched_keys)
 						.await;
 
 					let _ = response.send(result);
@@ -173,6 +184,9 @@ impl TransactionTask {
 
 					let _ = response.send(result);
 				}
+				TransactionCommand::GetReadVersion { response } => {
+					let _ = response.send(start_version);
+				}
 			}
 		}
 
@@ -330,8 +344,9 @@ impl TransactionTask {
 		tx: Transaction<'_>,
 		start_version: i64,
 		operations: Vec<Operation>,
-		conflict_ranges: Vec<(Vec<u8>, Vec<u8>, ConflictRangeType)>,
-	) -> Result<()> {
+		mut conflict_ranges: Vec<(Vec<u8>, Vec<u8>, ConflictRangeType)>,
+		watched_keys: Vec<Vec<u8>>,
+	) -> Result<[u8; 10]> {
 		// // Defer all constraint checks until commit
 		// tx.execute("SET CONSTRAINTS ALL DEFERRED", &[])
 		// 	.await
@@ -347,6 +362,15 @@ impl TransactionTask {
 			.context("failed to get postgres txn commit_version")?
 			.get::<_, i64>(0);
 
+		// Writes only notify watched keys they can see (see `NOTIFY_WATCHED_KEYS_QUERY`). Transactions that started
+		// before this one commits can't see its watched keys, so they conflict with it instead and retry.
+		for key in &watched_keys {
+			let mut end = key.clone();
+			end.push(0);
+
+			conflict_ranges.push((key.clone(), end, ConflictRangeType::Read));
+		}
+
 		let mut begins = Vec::with_capacity(conflict_ranges.len());
 		let mut ends = Vec::with_capacity(conflict_ranges.len());
 		let mut conflict_types = Vec::with_capacity(conflict_ranges.len());
@@ -386,12 +410,49 @@ impl TransactionTask {
 		.await
 		.map_err(map_postgres_error)?;
 
+		// The commit version is unique across all nodes, so it is used as the versionstamp filled in by
+		// versionstamped atomic ops
+		let mut tx_versionstamp = [0u8; 10];
+		tx_versionstamp[..8].copy_from_slice(&commit_version.to_be_bytes());
+
+		if !watched_keys.is_empty() {
+			let stmt = tx
+				.prepare_cached(INSERT_WATCHED_KEYS_QUERY)
+				.await
+				.map_err(map_postgres_error)?;
+
+			tx.execute(&stmt, &[&watched_keys])
+				.await
+				.map_err(map_postgres_error)?;
+		}
+
+		// Only sends a notification if a changed key is watched. Notifications are only delivered if the
+		// transaction commits.
+		let (keys, range_begins, range_ends) = changed_keys(&operations, &tx_versionstamp)?;
+		if !keys.is_empty() || !range_begins.is_empty() {
+			let stmt = tx
+				.prepare_cached(NOTIFY_WATCHED_KEYS_QUERY)
+				.await
+				.map_err(map_postgres_error)?;
+
+			tx.execute(
+				&stmt,
+				&[
+					&WATCH_CHANNEL,
+					&MAX_PAYLOAD_SIZE,
+					&NOTIFY_ALL_PAYLOAD,
+					&keys,
+					&range_begins,
+					&range_ends,
+				],
+			)
+			.await
+			.map_err(map_postgres_error)?;
+		}
+
 		for op in operations {
 			match op {
 				Operation::Set { key, value } => {
-					// TODO: versionstamps need to be calculated on the sql side, not in rust
-					let value = substitute_versionstamp_if_incomplete(value.clone(), 0);
-
 					// // Poor man's upsert, you cant use ON CONFLICT with deferred constraints
 					// let query = "WITH updated AS (
 					// 		UPDATE kv
@@ -425,6 +486,36 @@ impl TransactionTask {
 						.await
 						.map_err(map_postgres_error)?;
 				}
+				Operation::AtomicOp {
+					key,
+					param,
+					op_type: MutationType::SetVersionstampedKey,
+				} => {
+					let key = substitute_versionstamp_at_offset(&key, &tx_versionstamp)
+						.map_err(|err| anyhow!(err))?;
+
+					let query = "INSERT INTO kv (key, value) VALUES ($1, $2) ON CONFLICT (key) DO UPDATE SET value = $2";
+					let stmt = tx.prepare_cached(query).await.map_err(map_postgres_error)?;
+
+					tx.execute(&stmt, &[&key, &param])
+						.await
+						.map_err(map_postgres_error)?;
+				}
+				Operation::AtomicOp {
+					key,
+					param,
+					op_type: MutationType::SetVersionstampedValue,
+				} => {
+					let value = substitute_versionstamp_at_offset(&param, &tx_versionstamp)
+						.map_err(|err| anyhow!(err))?;
+
+					let query = "INSERT INTO kv (key, value) VALUES ($1, $2) ON CONFLICT (key) DO UPDATE SET value = $2";
+					let stmt = tx.prepare_cached(query).await.map_err(map_postgres_error)?;
+
+					tx.execute(&stmt, &[&key, &value])
+						.await
+						.map_err(map_postgres_error)?;
+				}
 				Operation::AtomicOp {
 					key,
 					param,
@@ -487,7 +578,9 @@ impl TransactionTask {
 			}
 		}
 
-		tx.commit().await.map_err(map_postgres_error)
+		tx.commit().await.map_err(map_postgres_error)?;
+
+		Ok(tx_versionstamp)
 	}
 
 	async fn fail_receiver(&mut self) {
@@ -508,6 +601,8 @@ impl TransactionTask {
 				TransactionCommand::GetEstimatedRangeSize { response, .. } => {
 					let _ = response.send(Err(anyhow!("postgres transaction connection failed")));
 				}
+				// Dropping the response fails the request
+				TransactionCommand::GetReadVersion { .. } => {}
 			}
 		}
 	}
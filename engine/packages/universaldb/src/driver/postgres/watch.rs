@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use futures_util::{StreamExt, stream};
+use tokio_postgres::{AsyncMessage, NoTls};
+
+use crate::{
+	options::MutationType, tx_ops::Operation, versionstamp::substitute_versionstamp_at_offset,
+	watch::WatchRegistry,
+};
+
+/// Channel used with `LISTEN`/`NOTIFY` to broadcast changed keys to all nodes.
+pub const WATCH_CHANNEL: &str = "udb_watch";
+
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often the keys watched by this node are inserted into `watched_keys` again. Must be less than the
+/// expiration in `INSERT_WATCHED_KEYS_QUERY`.
+const WATCHED_KEYS_REFRESH_INTERVAL: Duration = Duration::from_secs(20);
+
+/// Postgres rejects `NOTIFY` payloads of 8000 bytes or more.
+pub const MAX_PAYLOAD_SIZE: i32 = 7999;
+
+/// Payload that resolves every watch. Sent when the changed keys do not fit in a single payload.
+pub const NOTIFY_ALL_PAYLOAD: &str = "*";
+
+/// Marks keys as watched. Rows expire unless the node that watches the key inserts it again, so the watches of
+/// nodes that stopped are eventually no longer notified. Takes the keys as `$1`.
+pub const INSERT_WATCHED_KEYS_QUERY: &str = "
+	INSERT INTO watched_keys (key, expire_ts)
+	SELECT key, now() + interval '60 seconds'
+	FROM UNNEST($1::bytea[]) AS t(key)";
+
+/// Sends a single notification with all watched keys changed by a transaction, or none if no changed key is
+/// watched. Takes the channel, max payload size, and notify all payload as `$1` to `$3`, the changed keys as `$4`,
+/// and the begins and ends of the cleared ranges as `$5` and `$6`.
+pub const NOTIFY_WATCHED_KEYS_QUERY: &str = "
+	SELECT pg_notify($1, CASE WHEN octet_length(payload) > $2 THEN $3 ELSE payload END)
+	FROM (
+		SELECT string_agg(DISTINCT encode(key, 'hex'), ',') AS payload
+		FROM watched_keys
+		WHERE
+			expire_ts > now()
+			AND (
+				key = ANY($4::bytea[])
+				OR EXISTS (
+					SELECT 1
+					FROM UNNEST($5::bytea[], $6::bytea[]) AS r(begin_key, end_key)
+					WHERE key >= r.begin_key AND key < r.end_key
+				)
+			)
+	) AS t
+	WHERE payload IS NOT NULL";
+
+/// Listens for changes committed by any node and resolves the matching watches. Runs forever.
+pub async fn listen(connection_string: String, watches: WatchRegistry) {
+	loop {
+		if let Err(err) = listen_inner(&connection_string, &watches).await {
+			tracing::error!(?err, "postgres watch listener failed");
+		}
+
+		tokio::time::sleep(RECONNECT_INTERVAL).await;
+	}
+}
+
+async fn listen_inner(connection_string: &str, watches: &WatchRegistry) -> Result<()> {
+	// Uses a dedicated connection because pooled connections do not surface notifications
+	let (client, mut connection) = tokio_postgres::connect(connection_string, NoTls)
+		.await
+		.context("failed to connect to postgres")?;
+
+	// The connection has to be polled for `LISTEN` to complete, so it is driven in a separate task
+	let conn_watches = watches.clone();
+	let conn_handle = tokio::spawn(async move {
+		let mut messages = stream::poll_fn(move |cx| connection.poll_message(cx));
+
+		while let Some(message) = messages.next().await {
+			if let AsyncMessage::Notification(notification) =
+				message.context("postgres watch connection failed")?
+			{
+				handle_payload(&conn_watches, notification.payload());
+			}
+		}
+
+		anyhow::Ok(())
+	});
+
+	client
+		.batch_execute(&format!("LISTEN {WATCH_CHANNEL}"))
+		.await
+		.context("failed to listen for postgres watch notifications")?;
+
+	// Changes committed while not listening were missed
+	watches.notify_all();
+
+	let refresh = async {
+		let mut interval = tokio::time::interval(WATCHED_KEYS_REFRESH_INTERVAL);
+		interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+		loop {
+			interval.tick().await;
+
+			let keys = watches.keys();
+			if keys.is_empty() {
+				continue;
+			}
+
+			client
+				.execute(INSERT_WATCHED_KEYS_QUERY, &[&keys])
+				.await
+				.context("failed to refresh postgres watched keys")?;
+		}
+	};
+
+	tokio::select! {
+		res = conn_handle => res.context("postgres watch connection task failed")??,
+		res = refresh => return res,
+	}
+
+	bail!("postgres watch connection closed");
+}
+
+fn handle_payload(watches: &WatchRegistry, payload: &str) {
+	if payload == NOTIFY_ALL_PAYLOAD {
+		watches.notify_all();
+		return;
+	}
+
+	let Some(keys) = payload
+		.split(',')
+		.map(decode_hex)
+		.collect::<Option<Vec<_>>>()
+	else {
+		tracing::warn!(%payload, "invalid postgres watch payload");
+		watches.notify_all();
+		return;
+	};
+
+	for key in keys {
+		watches.notify_key(&key);
+	}
+}
+
+/// Returns the keys and ranges (as begins and ends) changed by the given operations. Keys of versionstamped
+/// key ops are returned with the versionstamp filled in.
+pub fn changed_keys(
+	operations: &[Operation],
+	tx_versionstamp: &[u8; 10],
+) -> Result<(Vec<Vec<u8>>, Vec<Vec<u8>>, Vec<Vec<u8>>)> {
+	let mut keys = Vec::new();
+	let mut begins = Vec::new();
+	let mut ends = Vec::new();
+
+	for op in operations {
+		match op {
+			Operation::AtomicOp {
+				key,
+				op_type: MutationType::SetVersionstampedKey,
+				..
+			} => keys.push(
+				substitute_versionstamp_at_offset(key, tx_versionstamp)
+					.map_err(|err| anyhow!(err))?,
+			),
+			Operation::Set { key, .. }
+			| Operation::Clear { key }
+			| Operation::AtomicOp { key, .. } => keys.push(key.clone()),
+			Operation::ClearRange { begin, end } => {
+				begins.push(begin.clone());
+				ends.push(end.clone());
+			}
+		}
+	}
+
+	Ok((keys, begins, ends))
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+	if s.len() % 2 != 0 {
+		return None;
+	}
+
+	(0..s.len())
+		.step_by(2)
+		.map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+		.collect()
+}
@@ -0,0 +1,211 @@
+use std::{
+	fs,
+	io::{Read, Write},
+	path::{Path, PathBuf},
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result, bail, ensure};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use rocksdb::{
+	DB, IteratorMode, OptimisticTransactionDB, Options, ReadOptions, checkpoint::Checkpoint,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct CheckpointInfo {
+	pub key_count: u64,
+	/// Total size of all keys and values.
+	pub size_bytes: u64,
+}
+
+#[derive(Debug)]
+pub struct RestoreOutput {
+	pub info: CheckpointInfo,
+	/// Where the replaced database was moved to, if there was one.
+	pub previous_path: Option<PathBuf>,
+}
+
+/// Creates a checkpoint of a live database. Files are hard linked if the path is on the same file system as
+/// the database, otherwise they are copied.
+pub(super) fn create_checkpoint(db: &OptimisticTransactionDB, path: &Path) -> Result<()> {
+	ensure!(
+		!path.exists(),
+		"checkpoint path already exists: {}",
+		path.display()
+	);
+
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent).context("failed to create checkpoint parent directory")?;
+	}
+
+	// Flushes the memtables before creating the checkpoint, so it does not depend on the WAL
+	Checkpoint::new(db)
+		.context("failed to create rocksdb checkpoint object")?
+		.create_checkpoint(path)
+		.context("failed to create rocksdb checkpoint")?;
+
+	tracing::info!(path=%path.display(), "created rocksdb checkpoint");
+
+	Ok(())
+}
+
+/// Opens the checkpoint read only and reads every key with checksum verification.
+pub fn validate_checkpoint(path: &Path) -> Result<CheckpointInfo> {
+	ensure!(
+		path.join("CURRENT").is_file(),
+		"not a rocksdb checkpoint: {}",
+		path.display()
+	);
+
+	let mut opts = Options::default();
+	opts.set_paranoid_checks(true);
+
+	let db = DB::open_for_read_only(&opts, path, false).context("failed to open checkpoint")?;
+
+	let mut read_opts = ReadOptions::default();
+	read_opts.set_verify_checksums(true);
+	read_opts.fill_cache(false);
+
+	let mut info = CheckpointInfo {
+		key_count: 0,
+		size_bytes: 0,
+	};
+
+	for entry in db.iterator_opt(IteratorMode::Start, read_opts) {
+		let (key, value) = entry.context("failed to read checkpoint")?;
+
+		info.key_count += 1;
+		info.size_bytes += (key.len() + value.len()) as u64;
+	}
+
+	Ok(info)
+}
+
+/// Validates the checkpoint and swaps it in place of the database at `db_path`. The previous database is
+/// kept next to it. Fails if the database is in use.
+pub fn restore_checkpoint(checkpoint_path: &Path, db_path: &Path) -> Result<RestoreOutput> {
+	// Held until the database is swapped so the engine can't open it in the meantime
+	let _lock = lock_db(db_path).context("stop the engine before restoring")?;
+
+	let ts = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.context("system time before unix epoch")?
+		.as_millis();
+
+	// Copy the checkpoint next to the database first so the swap is a rename on the same file system
+	let staging_path = sibling_path(db_path, &format!("restore-{ts}"))?;
+	copy_dir(checkpoint_path, &staging_path).context("failed to copy checkpoint")?;
+
+	// Validate the copy instead of the source so corruption while copying is caught too
+	let info = match validate_checkpoint(&staging_path) {
+		Ok(info) => info,
+		Err(err) => {
+			if let Err(err) = fs::remove_dir_all(&staging_path) {
+				tracing::warn!(?err, path=%staging_path.display(), "failed to remove staging directory");
+			}
+
+			return Err(err);
+		}
+	};
+
+	let previous_path = if db_path.exists() {
+		let previous_path = sibling_path(db_path, &format!("bak-{ts}"))?;
+		fs::rename(db_path, &previous_path).context("failed to move previous database")?;
+
+		Some(previous_path)
+	} else {
+		None
+	};
+
+	fs::rename(&staging_path, db_path).context("failed to move checkpoint into place")?;
+
+	tracing::info!(
+		checkpoint=%checkpoint_path.display(),
+		db=%db_path.display(),
+		key_count=info.key_count,
+		"restored rocksdb checkpoint"
+	);
+
+	Ok(RestoreOutput {
+		info,
+		previous_path,
+	})
+}
+
+/// Writes the checkpoint as a gzipped tarball.
+pub fn write_tarball(checkpoint_path: &Path, writer: impl Write) -> Result<()> {
+	let mut builder = tar::Builder::new(GzEncoder::new(writer, Compression::default()));
+	builder
+		.append_dir_all(".", checkpoint_path)
+		.context("failed to add checkpoint to tarball")?;
+	builder
+		.into_inner()
+		.context("failed to write tarball")?
+		.finish()
+		.context("failed to finish tarball")?;
+
+	Ok(())
+}
+
+/// Unpacks a tarball created by `write_tarball` into a new directory.
+pub fn unpack_tarball(reader: impl Read, path: &Path) -> Result<()> {
+	ensure!(!path.exists(), "path already exists: {}", path.display());
+
+	tar::Archive::new(GzDecoder::new(reader))
+		.unpack(path)
+		.context("failed to unpack tarball")?;
+
+	Ok(())
+}
+
+/// Takes an exclusive lock on a file next to the database, held by the engine while the database is open.
+/// RocksDB's own `LOCK` file can only be checked by opening the database.
+pub(super) fn lock_db(db_path: &Path) -> Result<fs::File> {
+	let lock_path = sibling_path(db_path, "lock")?;
+
+	if let Some(parent) = lock_path.parent() {
+		fs::create_dir_all(parent).context("failed to create database parent directory")?;
+	}
+
+	let file = fs::OpenOptions::new()
+		.create(true)
+		.truncate(false)
+		.write(true)
+		.open(&lock_path)
+		.with_context(|| format!("failed to open {}", lock_path.display()))?;
+
+	match file.try_lock() {
+		Ok(()) => Ok(file),
+		Err(fs::TryLockError::WouldBlock) => {
+			bail!("database is in use: {}", db_path.display())
+		}
+		Err(fs::TryLockError::Error(err)) => {
+			Err(err).with_context(|| format!("failed to lock {}", lock_path.display()))
+		}
+	}
+}
+
+fn sibling_path(path: &Path, suffix: &str) -> Result<PathBuf> {
+	let file_name = path
+		.file_name()
+		.with_context(|| format!("invalid database path: {}", path.display()))?;
+
+	Ok(path.with_file_name(format!("{}.{suffix}", file_name.to_string_lossy())))
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+	fs::create_dir_all(to)?;
+
+	for entry in fs::read_dir(from)? {
+		let entry = entry?;
+		let dest = to.join(entry.file_name());
+
+		if entry.file_type()?.is_dir() {
+			copy_dir(&entry.path(), &dest)?;
+		} else {
+			fs::copy(entry.path(), dest)?;
+		}
+	}
+
+	Ok(())
+}
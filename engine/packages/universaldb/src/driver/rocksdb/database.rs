@@ -1,10 +1,6 @@
 use std::{
-	path::PathBuf,
-	sync::{
-		Arc,
-		atomic::{AtomicI32, Ordering},
-	},
-	time::Duration,
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex},
 };
 
 use anyhow::{Context, Result};
@@ -15,19 +11,23 @@ use crate::{
 	driver::{BoxFut, DatabaseDriver, Erased},
 	error::DatabaseError,
 	options::DatabaseOption,
-	utils::{MaybeCommitted, calculate_tx_retry_backoff},
+	tx_config::TransactionConfig,
+	utils::MaybeCommitted,
+	watch::WatchRegistry,
 };
 
 use super::{
-	transaction::RocksDbTransactionDriver, transaction_conflict_tracker::TransactionConflictTracker,
+	backup, transaction::RocksDbTransactionDriver,
+	transaction_conflict_tracker::TransactionConflictTracker,
 };
 
-const TXN_TIMEOUT: Duration = Duration::from_secs(5);
-
 pub struct RocksDbDatabaseDriver {
 	db: Arc<OptimisticTransactionDB>,
-	max_retries: AtomicI32,
+	/// Held while the database is open, see `backup::lock_db`.
+	_lock: std::fs::File,
+	config: Mutex<TransactionConfig>,
 	txn_conflict_tracker: TransactionConflictTracker,
+	watches: WatchRegistry,
 }
 
 impl RocksDbDatabaseDriver {
@@ -37,6 +37,8 @@ impl RocksDbDatabaseDriver {
 		// Create directory if it doesn't exist
 		std::fs::create_dir_all(&db_path).context("failed to create database directory")?;
 
+		let lock = backup::lock_db(&db_path)?;
+
 		// Configure RocksDB options
 		let mut opts = Options::default();
 		opts.create_if_missing(true);
@@ -50,18 +52,28 @@ impl RocksDbDatabaseDriver {
 
 		Ok(RocksDbDatabaseDriver {
 			db: Arc::new(db),
-			max_retries: AtomicI32::new(100),
+			_lock: lock,
+			config: Mutex::new(TransactionConfig::default()),
 			txn_conflict_tracker: TransactionConflictTracker::new(),
+			watches: WatchRegistry::default(),
 		})
 	}
+
+	fn create_trx_driver(&self, config: TransactionConfig) -> Arc<RocksDbTransactionDriver> {
+		Arc::new(RocksDbTransactionDriver::new(
+			self.db.clone(),
+			self.txn_conflict_tracker.clone(),
+			self.watches.clone(),
+			config,
+		))
+	}
 }
 
 impl DatabaseDriver for RocksDbDatabaseDriver {
 	fn create_trx(&self) -> Result<Transaction> {
-		Ok(Transaction::new(Arc::new(RocksDbTransactionDriver::new(
-			self.db.clone(),
-			self.txn_conflict_tracker.clone(),
-		))))
+		let config = *self.config.lock().expect("poisoned lock");
+
+		Ok(Transaction::new(self.create_trx_driver(config)))
 	}
 
 	fn run<'a>(
@@ -70,23 +82,24 @@ impl DatabaseDriver for RocksDbDatabaseDriver {
 	) -> BoxFut<'a, Result<Erased>> {
 		Box::pin(async move {
 			let mut maybe_committed = MaybeCommitted(false);
-			let max_retries = self.max_retries.load(Ordering::SeqCst);
+			// Options set on the transaction are kept between attempts
+			let mut config = *self.config.lock().expect("poisoned lock");
+			let mut attempt = 0;
 
-			for attempt in 0..max_retries {
-				let tx = self.create_trx()?;
-				let mut retryable = RetryableTransaction::new(tx);
+			while !config.retries_exhausted(attempt) {
+				let driver = self.create_trx_driver(config);
+				let mut retryable = RetryableTransaction::new(Transaction::new(driver.clone()));
 				retryable.maybe_committed = maybe_committed;
 
 				// Execute transaction
-				let error =
-					match tokio::time::timeout(TXN_TIMEOUT, closure(retryable.clone())).await {
-						Ok(Ok(res)) => match retryable.inner.driver.commit_ref().await {
-							Ok(_) => return Ok(res),
-							Err(e) => e,
-						},
-						Ok(Err(e)) => e,
-						Err(_) => anyhow::Error::from(DatabaseError::TransactionTooOld),
-					};
+				let error = match driver.limits.with_timeout(closure(retryable.clone())).await {
+					Ok(res) => match retryable.inner.driver.commit_ref().await {
+						Ok(_) => return Ok(res),
+						Err(e) => e,
+					},
+					Err(e) => e,
+				};
+				config = driver.limits.config();
 
 				let chain = error
 					.chain()
@@ -99,8 +112,8 @@ impl DatabaseDriver for RocksDbDatabaseDriver {
 							maybe_committed = MaybeCommitted(true);
 						}
 
-						let backoff_ms = calculate_tx_retry_backoff(attempt as usize);
-						tokio::time::sleep(tokio::time::Duration::from_millis(backoff_ms)).await;
+						tokio::time::sleep(config.retry_backoff(attempt)).await;
+						attempt += 1;
 						continue;
 					}
 				}
@@ -113,12 +126,19 @@ impl DatabaseDriver for RocksDbDatabaseDriver {
 	}
 
 	fn set_option(&self, opt: DatabaseOption) -> Result<()> {
-		match opt {
-			DatabaseOption::TransactionRetryLimit(limit) => {
-				self.max_retries.store(limit, Ordering::SeqCst);
-				Ok(())
-			}
-		}
+		self.config
+			.lock()
+			.expect("poisoned lock")
+			.set_database_option(opt)
+	}
+
+	fn checkpoint<'a>(&'a self, path: &'a Path) -> BoxFut<'a, Result<()>> {
+		Box::pin(async move {
+			let db = self.db.clone();
+			let path = path.to_path_buf();
+
+			tokio::task::spawn_blocking(move || backup::create_checkpoint(&db, &path)).await?
+		})
 	}
 }
 
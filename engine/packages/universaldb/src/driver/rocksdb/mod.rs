@@ -1,3 +1,4 @@
+pub mod backup;
 mod database;
 mod transaction;
 mod transaction_conflict_tracker;
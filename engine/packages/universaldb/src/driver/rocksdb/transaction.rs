@@ -14,11 +14,15 @@ use tokio::sync::{OnceCell, mpsc, oneshot};
 use crate::{
 	driver::TransactionDriver,
 	key_selector::KeySelector,
-	options::{ConflictRangeType, MutationType},
+	options::{ConflictRangeType, MutationType, TransactionOption},
 	range_option::RangeOption,
+	tuple::Versionstamp,
+	tx_config::{TransactionConfig, TransactionLimits},
 	tx_ops::TransactionOperations,
 	utils::IsolationLevel,
 	value::{Slice, Value, Values},
+	versionstamp::PendingVersionstamps,
+	watch::{PendingWatches, WatchRegistry},
 };
 
 use super::{
@@ -33,12 +37,18 @@ pub struct RocksDbTransactionDriver {
 	tx_sender: OnceCell<mpsc::Sender<TransactionCommand>>,
 	txn_conflict_tracker: TransactionConflictTracker,
 	start_version: u64,
+	watches: WatchRegistry,
+	pending_watches: PendingWatches,
+	pending_versionstamps: PendingVersionstamps,
+	pub(super) limits: TransactionLimits,
 }
 
 impl RocksDbTransactionDriver {
 	pub fn new(
 		db: Arc<OptimisticTransactionDB>,
 		txn_conflict_tracker: TransactionConflictTracker,
+		watches: WatchRegistry,
+		config: TransactionConfig,
 	) -> Self {
 		let start_version = txn_conflict_tracker.next_global_version();
 
@@ -49,6 +59,10 @@ impl RocksDbTransactionDriver {
 			tx_sender: OnceCell::new(),
 			txn_conflict_tracker,
 			start_version,
+			watches,
+			pending_watches: PendingWatches::default(),
+			pending_versionstamps: PendingVersionstamps::default(),
+			limits: TransactionLimits::new(config),
 		}
 	}
 
@@ -62,6 +76,7 @@ impl RocksDbTransactionDriver {
 				let task = TransactionTask::new(
 					self.db.clone(),
 					self.txn_conflict_tracker.clone(),
+					self.watches.clone(),
 					receiver,
 				);
 				tokio::spawn(task.run());
@@ -238,6 +253,7 @@ impl TransactionDriver for RocksDbTransactionDriver {
 			self.committed.store(true, Ordering::SeqCst);
 
 			let (operations, conflict_ranges) = self.operations.consume();
+			self.limits.check_commit(&operations, &conflict_ranges)?;
 
 			let tx_sender = self.ensure_transaction().await?;
 
@@ -254,16 +270,22 @@ impl TransactionDriver for RocksDbTransactionDriver {
 				.context("failed to send rocksdb transaction command")?;
 
 			// Wait for commit response
-			response_rx
+			let tx_versionstamp = response_rx
 				.await
 				.context("failed to receive rocksdb commit response")??;
 
+			self.pending_watches.register(&self.watches);
+			self.pending_versionstamps.resolve(tx_versionstamp);
+
 			Ok(())
 		})
 	}
 
 	fn reset(&mut self) {
 		self.operations.clear_all();
+		self.pending_watches.clear();
+		self.pending_versionstamps.clear();
+		self.limits.reset();
 		self.committed.store(false, Ordering::SeqCst);
 
 		self.start_version = self.txn_conflict_tracker.next_global_version();
@@ -271,6 +293,8 @@ impl TransactionDriver for RocksDbTransactionDriver {
 
 	fn cancel(&self) {
 		self.operations.clear_all();
+		self.pending_watches.clear();
+		self.pending_versionstamps.clear();
 		self.committed.store(true, Ordering::SeqCst); // Prevent future commits
 
 		// Tx sender will be stopped back when dropped
@@ -327,6 +351,7 @@ impl TransactionDriver for RocksDbTransactionDriver {
 			self.committed.store(true, Ordering::SeqCst);
 
 			let (operations, conflict_ranges) = self.operations.consume();
+			self.limits.check_commit(&operations, &conflict_ranges)?;
 
 			// We have operations but no transaction - create one just for commit
 			let tx_sender = self.ensure_transaction().await?;
@@ -344,11 +369,30 @@ impl TransactionDriver for RocksDbTransactionDriver {
 				.context("failed to send rocksdb transaction command")?;
 
 			// Wait for commit response
-			response_rx
+			let tx_versionstamp = response_rx
 				.await
 				.context("failed to receive rocksdb commit response")??;
 
+			self.pending_watches.register(&self.watches);
+			self.pending_versionstamps.resolve(tx_versionstamp);
+
 			Ok(())
 		})
 	}
+
+	fn watch(&self, key: &[u8]) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+		Box::pin(self.pending_watches.add(key))
+	}
+
+	fn get_versionstamp(&self) -> Pin<Box<dyn Future<Output = Result<Versionstamp>> + Send>> {
+		Box::pin(self.pending_versionstamps.add())
+	}
+
+	fn get_read_version(&self) -> Pin<Box<dyn Future<Output = Result<i64>> + Send + '_>> {
+		Box::pin(async move { Ok(self.start_version as i64) })
+	}
+
+	fn set_option(&self, opt: TransactionOption) -> Result<()> {
+		self.limits.set_option(opt)
+	}
 }
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use anyhow::{Context, Result, bail};
+use anyhow::{Context, Result, anyhow, bail};
 use rocksdb::{
 	OptimisticTransactionDB, ReadOptions, Transaction as RocksDbTransaction, WriteOptions,
 };
@@ -11,10 +11,11 @@ use crate::{
 	atomic::apply_atomic_op,
 	error::DatabaseError,
 	key_selector::KeySelector,
-	options::ConflictRangeType,
+	options::{ConflictRangeType, MutationType},
 	tx_ops::Operation,
 	value::{KeyValue, Slice, Values},
-	versionstamp::substitute_versionstamp_if_incomplete,
+	versionstamp::{generate_versionstamp, substitute_versionstamp_at_offset},
+	watch::WatchRegistry,
 };
 
 pub enum TransactionCommand {
@@ -43,7 +44,7 @@ pub enum TransactionCommand {
 		start_version: u64,
 		operations: Vec<Operation>,
 		conflict_ranges: Vec<(Vec<u8>, Vec<u8>, ConflictRangeType)>,
-		response: oneshot::Sender<Result<()>>,
+		response: oneshot::Sender<Result<[u8; 10]>>,
 	},
 	GetEstimatedRangeSize {
 		begin: Vec<u8>,
@@ -57,6 +58,7 @@ pub enum TransactionCommand {
 pub struct TransactionTask {
 	db: Arc<OptimisticTransactionDB>,
 	txn_conflict_tracker: TransactionConflictTracker,
+	watches: WatchRegistry,
 	receiver: mpsc::Receiver<TransactionCommand>,
 }
 
@@ -64,11 +66,13 @@ impl TransactionTask {
 	pub fn new(
 		db: Arc<OptimisticTransactionDB>,
 		txn_conflict_tracker: TransactionConflictTracker,
+		watches: WatchRegistry,
 		receiver: mpsc::Receiver<TransactionCommand>,
 	) -> Self {
 		TransactionTask {
 			db,
 			txn_conflict_tracker,
+			watches,
 			receiver,
 		}
 	}
@@ -310,20 +314,19 @@ impl TransactionTask {
 		start_version: u64,
 		operations: Vec<Operation>,
 		conflict_ranges: Vec<(Vec<u8>, Vec<u8>, ConflictRangeType)>,
-	) -> Result<()> {
+	) -> Result<[u8; 10]> {
 		// Create a new transaction for this commit
 		let txn = self.create_transaction();
 
+		// Versionstamp filled in by versionstamped atomic ops, shared by all ops of this transaction
+		let mut tx_versionstamp = [0u8; 10];
+		tx_versionstamp.copy_from_slice(&generate_versionstamp(0).as_bytes()[..10]);
+
 		// Apply all operations to the transaction
-		for op in operations {
+		for op in &operations {
 			match op {
 				Operation::Set { key, value } => {
-					// Substitute versionstamp if incomplete
-					// For now, just use the simple substitution - we can improve this later
-					// to ensure all versionstamps in a transaction have the same base timestamp
-					let value = substitute_versionstamp_if_incomplete(value.clone(), 0);
-
-					txn.put(key, &value)
+					txn.put(key, value)
 						.context("failed to set key in rocksdb")?;
 				}
 				Operation::Clear { key } => {
@@ -334,7 +337,7 @@ impl TransactionTask {
 					// RocksDB doesn't have a native clear_range, so we need to iterate and delete
 					let read_opts = ReadOptions::default();
 					let iter = txn.iterator_opt(
-						rocksdb::IteratorMode::From(&begin, rocksdb::Direction::Forward),
+						rocksdb::IteratorMode::From(begin, rocksdb::Direction::Forward),
 						read_opts,
 					);
 
@@ -347,6 +350,28 @@ impl TransactionTask {
 							.context("failed to delete key in range from rocksdb")?;
 					}
 				}
+				Operation::AtomicOp {
+					key,
+					param,
+					op_type: MutationType::SetVersionstampedKey,
+				} => {
+					let key = substitute_versionstamp_at_offset(key, &tx_versionstamp)
+						.map_err(|err| anyhow!(err))?;
+
+					txn.put(&key, param)
+						.context("failed to set versionstamped key in rocksdb")?;
+				}
+				Operation::AtomicOp {
+					key,
+					param,
+					op_type: MutationType::SetVersionstampedValue,
+				} => {
+					let value = substitute_versionstamp_at_offset(param, &tx_versionstamp)
+						.map_err(|err| anyhow!(err))?;
+
+					txn.put(key, &value)
+						.context("failed to set versionstamped value in rocksdb")?;
+				}
 				Operation::AtomicOp {
 					key,
 					param,
@@ -355,12 +380,12 @@ impl TransactionTask {
 					// Get the current value from the database
 					let read_opts = ReadOptions::default();
 					let current_value = txn
-						.get_opt(&key, &read_opts)
+						.get_opt(key, &read_opts)
 						.context("failed to get current value for atomic operation")?;
 
 					// Apply the atomic operation
 					let current_slice = current_value.as_deref();
-					let new_value = apply_atomic_op(current_slice, &param, op_type);
+					let new_value = apply_atomic_op(current_slice, param, *op_type);
 
 					// Store the result
 					if let Some(new_value) = &new_value {
@@ -384,7 +409,11 @@ impl TransactionTask {
 
 		// Commit the transaction (this consumes txn)
 		match txn.commit() {
-			Ok(_) => Ok(()),
+			Ok(_) => {
+				self.watches.notify_operations(&operations);
+
+				Ok(tx_versionstamp)
+			}
 			Err(e) => {
 				// If the txn failed due to a rocksdb error, remove it from the conflict tracker
 				self.txn_conflict_tracker.remove(start_version).await;
@@ -12,6 +12,21 @@ pub enum DatabaseError {
 
 	#[error("operation issued while a commit was outstanding")]
 	UsedDuringCommit,
+
+	#[error("watch cancelled because its transaction was not committed")]
+	WatchCancelled,
+
+	#[error("versionstamp unavailable because its transaction was not committed")]
+	VersionstampCancelled,
+
+	#[error("transaction timed out")]
+	TransactionTimedOut,
+
+	#[error("transaction exceeds its size limit")]
+	TransactionTooLarge,
+
+	#[error("driver cannot read at a previous version")]
+	ReadVersionUnsupported,
 }
 
 impl DatabaseError {
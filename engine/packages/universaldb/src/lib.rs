@@ -8,10 +8,12 @@ pub mod options;
 pub mod prelude;
 pub mod range_option;
 mod transaction;
+pub(crate) mod tx_config;
 pub(crate) mod tx_ops;
 pub mod utils;
 pub mod value;
 pub mod versionstamp;
+pub(crate) mod watch;
 
 pub use database::Database;
 pub use driver::DatabaseDriverHandle;
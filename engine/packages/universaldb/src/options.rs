@@ -25,22 +25,22 @@ pub enum DatabaseOption {
 	// ///
 	// /// Sets the maximum escaped length of key and value fields to be logged to the trace file via the LOG_TRANSACTION option. This sets the ``transaction_logging_max_field_length`` option of each transaction created by this database. See the transaction option description for more information.
 	// TransactionLoggingMaxFieldLength(i32),
-	// /// value in milliseconds of timeout
-	// ///
-	// /// Set a timeout in milliseconds which, when elapsed, will cause each transaction automatically to be cancelled. This sets the ``timeout`` option of each transaction created by this database. See the transaction option description for more information. Using this option requires that the API version is 610 or higher.
-	// TransactionTimeout(i32),
+	/// value in milliseconds of timeout
+	///
+	/// Set a timeout in milliseconds which, when elapsed, will cause each transaction automatically to be cancelled. This sets the ``timeout`` option of each transaction created by this database. See the transaction option description for more information. Using this option requires that the API version is 610 or higher.
+	TransactionTimeout(i32),
 	/// number of times to retry
 	///
 	/// Set a maximum number of retries after which additional calls to ``onError`` will throw the most recently seen error code. This sets the ``retry_limit`` option of each transaction created by this database. See the transaction option description for more information.
 	TransactionRetryLimit(i32),
-	// /// value in milliseconds of maximum delay
-	// ///
-	// /// Set the maximum amount of backoff delay incurred in the call to ``onError`` if the error is retryable. This sets the ``max_retry_delay`` option of each transaction created by this database. See the transaction option description for more information.
-	// TransactionMaxRetryDelay(i32),
-	// /// value in bytes
-	// ///
-	// /// Set the maximum transaction size in bytes. This sets the ``size_limit`` option on each transaction created by this database. See the transaction option description for more information.
-	// TransactionSizeLimit(i32),
+	/// value in milliseconds of maximum delay
+	///
+	/// Set the maximum amount of backoff delay incurred in the call to ``onError`` if the error is retryable. This sets the ``max_retry_delay`` option of each transaction created by this database. See the transaction option description for more information.
+	TransactionMaxRetryDelay(i32),
+	/// value in bytes
+	///
+	/// Set the maximum transaction size in bytes. This sets the ``size_limit`` option on each transaction created by this database. See the transaction option description for more information.
+	TransactionSizeLimit(i32),
 	// /// The read version will be committed, and usually will be the latest committed, but might not be the latest committed in the event of a simultaneous fault and misbehaving clock.
 	// TransactionCausalReadRisky,
 	// /// Deprecated. Addresses returned by get_addresses_for_key include the port when enabled. As of api version 630, this option is enabled by default and setting this has no effect.
@@ -5,9 +5,9 @@ use anyhow::{Context, Result};
 use crate::{
 	driver::TransactionDriver,
 	key_selector::KeySelector,
-	options::{ConflictRangeType, MutationType},
+	options::{ConflictRangeType, MutationType, TransactionOption},
 	range_option::RangeOption,
-	tuple::{self, TuplePack, TupleUnpack},
+	tuple::{self, TuplePack, TupleUnpack, Versionstamp},
 	utils::{
 		CherryPick, FormalKey, IsolationLevel, MaybeCommitted, OptSliceExt, Subspace,
 		end_of_key_range,
@@ -261,6 +261,54 @@ impl Transaction {
 	) -> Pin<Box<dyn Future<Output = Result<i64>> + Send + 'a>> {
 		self.driver.get_estimated_range_size_bytes(begin, end)
 	}
+
+	/// Returns a future that resolves once the value of `key` changes. The watch is only registered once
+	/// this transaction commits and fails with `DatabaseError::WatchCancelled` if it never does. The future
+	/// may resolve spuriously, so callers should re-read the key after it resolves.
+	pub fn watch(&self, key: &[u8]) -> impl Future<Output = Result<()>> + Send + 'static {
+		self.driver.watch(key)
+	}
+
+	/// Same as `watch` but for a key packed with this transaction's subspace.
+	pub fn watch_key<T: TuplePack>(
+		&self,
+		key: &T,
+	) -> impl Future<Output = Result<()>> + Send + 'static {
+		self.driver.watch(&self.subspace.pack(key))
+	}
+
+	/// Returns a future that resolves to the versionstamp of this transaction (with a user version of 0) once
+	/// it commits. This is the versionstamp filled in by `SetVersionstampedKey` and `SetVersionstampedValue`
+	/// ops. Must be called before committing and fails with `DatabaseError::VersionstampCancelled` if the
+	/// transaction never commits.
+	pub fn get_versionstamp(&self) -> impl Future<Output = Result<Versionstamp>> + Send + 'static {
+		self.driver.get_versionstamp()
+	}
+
+	/// Version at which this transaction reads the database. Read versions of later transactions are never
+	/// lower. Can only be passed to `set_read_version` on the FoundationDB driver.
+	pub async fn get_read_version(&self) -> Result<i64> {
+		self.driver.get_read_version().await
+	}
+
+	/// Sets the version at which this transaction reads the database. Passing the read version of a previous
+	/// transaction allows scans split over multiple transactions to observe the same data. Must be called
+	/// before any reads.
+	///
+	/// Only supported by the FoundationDB driver. The RocksDB and Postgres drivers only keep the latest value of
+	/// each key, so reading at a previous version is out of scope for them and they return
+	/// `DatabaseError::ReadVersionUnsupported`.
+	pub fn set_read_version(&self, version: i64) -> Result<()> {
+		self.driver.set_read_version(version)
+	}
+
+	/// Sets an option on this transaction. Options are kept when `Database::run` retries the transaction.
+	///
+	/// The RocksDB and Postgres drivers only support `Timeout`, `RetryLimit`, `MaxRetryDelay` and
+	/// `SizeLimit`. Regardless of the timeout, transactions that write must commit within 5 seconds.
+	pub fn set_option(&self, opt: TransactionOption) -> Result<()> {
+		self.driver.set_option(opt)
+	}
 }
 
 pub struct InformalTransaction<'t> {
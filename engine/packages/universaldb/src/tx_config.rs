@@ -0,0 +1,223 @@
+use std::{
+	future::Future,
+	sync::Mutex,
+	time::{Duration, Instant},
+};
+
+use anyhow::{Result, bail, ensure};
+
+use crate::{
+	error::DatabaseError,
+	options::{ConflictRangeType, DatabaseOption, TransactionOption},
+	tx_ops::Operation,
+	utils::calculate_tx_retry_backoff,
+};
+
+/// Transactions without an explicit timeout fail with `TransactionTooOld` after this long, like FDB's MVCC
+/// window.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Transactions that write must commit within this long of being created regardless of their timeout,
+/// because drivers only keep conflict history for a limited amount of time.
+pub const MAX_WRITE_AGE: Duration = DEFAULT_TIMEOUT;
+
+const DEFAULT_RETRY_LIMIT: i32 = 100;
+
+/// Same limits as FDB.
+const DEFAULT_SIZE_LIMIT: usize = 10_000_000;
+const MIN_SIZE_LIMIT: usize = 32;
+
+/// Limits of a transaction. Set for all transactions with `DatabaseOption` and for a single transaction
+/// with `TransactionOption`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TransactionConfig {
+	/// `None` uses `DEFAULT_TIMEOUT`, zero disables the timeout.
+	pub timeout: Option<Duration>,
+	/// Max number of attempts in `run`. Negative values disable the limit.
+	pub retry_limit: i32,
+	/// `None` does not clamp the retry backoff.
+	pub max_retry_delay: Option<Duration>,
+	/// Max size of all keys and values written, ranges cleared, and conflict ranges, in bytes.
+	pub size_limit: usize,
+}
+
+impl Default for TransactionConfig {
+	fn default() -> Self {
+		TransactionConfig {
+			timeout: None,
+			retry_limit: DEFAULT_RETRY_LIMIT,
+			max_retry_delay: None,
+			size_limit: DEFAULT_SIZE_LIMIT,
+		}
+	}
+}
+
+impl TransactionConfig {
+	pub fn set_database_option(&mut self, opt: DatabaseOption) -> Result<()> {
+		match opt {
+			DatabaseOption::TransactionTimeout(ms) => self.set_timeout(ms),
+			DatabaseOption::TransactionRetryLimit(limit) => {
+				self.retry_limit = limit;
+				Ok(())
+			}
+			DatabaseOption::TransactionMaxRetryDelay(ms) => self.set_max_retry_delay(ms),
+			DatabaseOption::TransactionSizeLimit(bytes) => self.set_size_limit(bytes),
+		}
+	}
+
+	pub fn set_transaction_option(&mut self, opt: TransactionOption) -> Result<()> {
+		match opt {
+			TransactionOption::Timeout(ms) => self.set_timeout(ms),
+			TransactionOption::RetryLimit(limit) => {
+				self.retry_limit = limit;
+				Ok(())
+			}
+			TransactionOption::MaxRetryDelay(ms) => self.set_max_retry_delay(ms),
+			TransactionOption::SizeLimit(bytes) => self.set_size_limit(bytes),
+			opt => bail!("unsupported transaction option: {opt:?}"),
+		}
+	}
+
+	fn set_timeout(&mut self, ms: i32) -> Result<()> {
+		ensure!(ms >= 0, "transaction timeout must not be negative");
+
+		self.timeout = Some(Duration::from_millis(ms as u64));
+
+		Ok(())
+	}
+
+	fn set_max_retry_delay(&mut self, ms: i32) -> Result<()> {
+		ensure!(ms >= 0, "transaction max retry delay must not be negative");
+
+		self.max_retry_delay = Some(Duration::from_millis(ms as u64));
+
+		Ok(())
+	}
+
+	fn set_size_limit(&mut self, bytes: i32) -> Result<()> {
+		let bytes = usize::try_from(bytes).unwrap_or_default();
+		ensure!(
+			(MIN_SIZE_LIMIT..=DEFAULT_SIZE_LIMIT).contains(&bytes),
+			"transaction size limit must be between {MIN_SIZE_LIMIT} and {DEFAULT_SIZE_LIMIT} bytes"
+		);
+
+		self.size_limit = bytes;
+
+		Ok(())
+	}
+
+	/// Returns true if `run` should not make another attempt.
+	pub fn retries_exhausted(&self, attempt: usize) -> bool {
+		usize::try_from(self.retry_limit).is_ok_and(|limit| attempt >= limit)
+	}
+
+	pub fn retry_backoff(&self, attempt: usize) -> Duration {
+		let backoff = Duration::from_millis(calculate_tx_retry_backoff(attempt));
+
+		match self.max_retry_delay {
+			Some(max_retry_delay) => backoff.min(max_retry_delay),
+			None => backoff,
+		}
+	}
+}
+
+/// Tracks the config and age of a single transaction.
+pub(crate) struct TransactionLimits {
+	config: Mutex<TransactionConfig>,
+	start: Mutex<Instant>,
+}
+
+impl TransactionLimits {
+	pub fn new(config: TransactionConfig) -> Self {
+		TransactionLimits {
+			config: Mutex::new(config),
+			start: Mutex::new(Instant::now()),
+		}
+	}
+
+	pub fn config(&self) -> TransactionConfig {
+		*self.config.lock().expect("poisoned lock")
+	}
+
+	pub fn set_option(&self, opt: TransactionOption) -> Result<()> {
+		self.config
+			.lock()
+			.expect("poisoned lock")
+			.set_transaction_option(opt)
+	}
+
+	/// Restarts the timeout. Options are kept, like FDB.
+	pub fn reset(&self) {
+		*self.start.lock().expect("poisoned lock") = Instant::now();
+	}
+
+	fn elapsed(&self) -> Duration {
+		self.start.lock().expect("poisoned lock").elapsed()
+	}
+
+	/// Runs the future until the timeout of the transaction elapses. The timeout is read again once it
+	/// elapses since the transaction may change it while running.
+	pub async fn with_timeout<T>(&self, fut: impl Future<Output = Result<T>>) -> Result<T> {
+		tokio::pin!(fut);
+
+		loop {
+			let timeout = self.config().timeout.unwrap_or(DEFAULT_TIMEOUT);
+			if timeout.is_zero() {
+				return fut.await;
+			}
+
+			let remaining = timeout.saturating_sub(self.elapsed());
+			if remaining.is_zero() {
+				return Err(self.timeout_error());
+			}
+
+			tokio::select! {
+				res = &mut fut => return res,
+				_ = tokio::time::sleep(remaining) => {}
+			}
+		}
+	}
+
+	fn timeout_error(&self) -> anyhow::Error {
+		if self.config().timeout.is_some() {
+			DatabaseError::TransactionTimedOut.into()
+		} else {
+			DatabaseError::TransactionTooOld.into()
+		}
+	}
+
+	/// Validates the size and age of a transaction before committing.
+	pub fn check_commit(
+		&self,
+		operations: &[Operation],
+		conflict_ranges: &[(Vec<u8>, Vec<u8>, ConflictRangeType)],
+	) -> Result<()> {
+		if operations.is_empty() {
+			return Ok(());
+		}
+
+		if self.elapsed() > MAX_WRITE_AGE {
+			return Err(DatabaseError::TransactionTooOld.into());
+		}
+
+		let size = operations
+			.iter()
+			.map(|op| match op {
+				Operation::Set { key, value } => key.len() + value.len(),
+				Operation::Clear { key } => key.len(),
+				Operation::ClearRange { begin, end } => begin.len() + end.len(),
+				Operation::AtomicOp { key, param, .. } => key.len() + param.len(),
+			})
+			.sum::<usize>()
+			+ conflict_ranges
+				.iter()
+				.map(|(begin, end, _)| begin.len() + end.len())
+				.sum::<usize>();
+
+		if size > self.config().size_limit {
+			return Err(DatabaseError::TransactionTooLarge.into());
+		}
+
+		Ok(())
+	}
+}
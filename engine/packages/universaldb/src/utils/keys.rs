@@ -131,4 +131,30 @@ define_keys! {
 	// 103 - RESERVED BY EE
 	// 104 - RESERVED BY EE
 	// 105 - RESERVED BY EE
+	(106, EXPIRE_TS, "expire_ts"),
+	(107, ACTOR_KV_EXPIRE, "actor_kv_expire"),
+	(108, KV_QUOTA, "kv_quota"),
+	(109, TERMINATION, "termination"),
+	(110, BY_NAME_AND_COMPLETE_TS, "by_name_and_complete_ts"),
+	(111, REFERENCED_BY, "referenced_by"),
+	(112, SCHEDULE, "schedule"),
+	(113, WORKFLOW_NAME, "workflow_name"),
+	(114, CRON, "cron"),
+	(115, NEXT_TS, "next_ts"),
+	(116, PAUSE_TS, "pause_ts"),
+	(117, LAST_WORKFLOW_ID, "last_workflow_id"),
+	(118, LAST_DISPATCH_TS, "last_dispatch_ts"),
+	(119, BY_NEXT_TS, "by_next_ts"),
+	(120, SIGNAL_IDEMPOTENCY, "signal_idempotency"),
+	(121, SCHEMA_VERSION, "schema_version"),
+	(122, TRACE_CONTEXT, "trace_context"),
+	(123, PLACEMENT, "placement"),
+	(124, ANTI_AFFINITY, "anti_affinity"),
+	(125, RESOURCES, "resources"),
+	(126, REMAINING_RESOURCES, "remaining_resources"),
+	(127, TOTAL_RESOURCES, "total_resources"),
+	(128, COMPLETE_TS, "complete_ts"),
+	(129, RETENTION_GC_LOCK, "retention_gc_lock"),
+	(130, RETENTION_BACKFILL_CURSOR, "retention_backfill_cursor"),
+	(131, PENDING_CONSTRAINED_ACTOR_BY_RUNNER_NAME_SELECTOR, "pending_constrained_actor_by_runner_name_selector"),
 }
@@ -1,9 +1,19 @@
 use std::{
-	sync::atomic::{AtomicU16, AtomicU64, Ordering},
+	future::Future,
+	sync::{
+		Mutex,
+		atomic::{AtomicU16, AtomicU64, Ordering},
+	},
 	time::{SystemTime, UNIX_EPOCH},
 };
 
-use crate::tuple::{TuplePack, Versionstamp, pack_with_versionstamp};
+use anyhow::Result;
+use tokio::sync::oneshot;
+
+use crate::{
+	error::DatabaseError,
+	tuple::{TuplePack, Versionstamp, pack_with_versionstamp},
+};
 
 static TRANSACTION_COUNTER: AtomicU16 = AtomicU16::new(0);
 static LAST_TIMESTAMP: AtomicU64 = AtomicU64::new(0);
@@ -171,3 +181,73 @@ pub fn substitute_versionstamp_if_incomplete(mut value: Vec<u8>, user_version: u
 
 	value
 }
+
+/// Fills in the transaction versionstamp of a `SetVersionstampedKey` key or `SetVersionstampedValue` param.
+/// The last 4 bytes are the little endian position of the 10 byte versionstamp to fill in and are removed,
+/// like in FoundationDB.
+pub fn substitute_versionstamp_at_offset(
+	data: &[u8],
+	tx_versionstamp: &[u8; 10],
+) -> Result<Vec<u8>, String> {
+	if data.len() < 4 {
+		return Err("Data too short to contain versionstamp offset".to_string());
+	}
+
+	let (data, offset_bytes) = data.split_at(data.len() - 4);
+	let offset = u32::from_le_bytes([
+		offset_bytes[0],
+		offset_bytes[1],
+		offset_bytes[2],
+		offset_bytes[3],
+	]) as usize;
+
+	let mut data = data.to_vec();
+	let len = data.len();
+	data.get_mut(offset..offset + 10)
+		.ok_or_else(|| {
+			format!(
+				"Invalid versionstamp offset: {} exceeds data length {}",
+				offset, len
+			)
+		})?
+		.copy_from_slice(tx_versionstamp);
+
+	Ok(data)
+}
+
+/// Requests for the versionstamp of a single transaction, used by drivers that fill in versionstamps
+/// themselves. Like FDB, the requests resolve once the transaction commits and fail if it is reset,
+/// cancelled, or dropped without committing.
+#[derive(Default)]
+pub(crate) struct PendingVersionstamps {
+	senders: Mutex<Vec<oneshot::Sender<[u8; 10]>>>,
+}
+
+impl PendingVersionstamps {
+	pub fn add(&self) -> impl Future<Output = Result<Versionstamp>> + Send + 'static {
+		let (sender, receiver) = oneshot::channel();
+		self.senders.lock().expect("poisoned lock").push(sender);
+
+		async move {
+			let tx_versionstamp = receiver
+				.await
+				.map_err(|_| DatabaseError::VersionstampCancelled)?;
+
+			Ok(Versionstamp::complete(tx_versionstamp, 0))
+		}
+	}
+
+	/// Resolves all requests with the versionstamp of the transaction. Called after a successful commit.
+	pub fn resolve(&self, tx_versionstamp: [u8; 10]) {
+		let senders = std::mem::take(&mut *self.senders.lock().expect("poisoned lock"));
+
+		for sender in senders {
+			let _ = sender.send(tx_versionstamp);
+		}
+	}
+
+	/// Cancels all requests of this transaction.
+	pub fn clear(&self) {
+		self.senders.lock().expect("poisoned lock").clear();
+	}
+}
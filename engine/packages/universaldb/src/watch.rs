@@ -0,0 +1,134 @@
+use std::{
+	collections::BTreeMap,
+	future::Future,
+	ops::Bound,
+	sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use tokio::sync::oneshot;
+
+use crate::{error::DatabaseError, tx_ops::Operation};
+
+/// Watches of all committed transactions, used by drivers without native watch support. Drivers notify the
+/// registry of every key changed by a commit.
+#[derive(Clone, Default)]
+pub(crate) struct WatchRegistry {
+	watches: Arc<Mutex<BTreeMap<Vec<u8>, Vec<oneshot::Sender<()>>>>>,
+}
+
+impl WatchRegistry {
+	fn register(&self, key: Vec<u8>, sender: oneshot::Sender<()>) {
+		let mut watches = self.watches.lock().expect("poisoned lock");
+		let senders = watches.entry(key).or_default();
+
+		// Prune watches that were dropped before their key changed
+		senders.retain(|sender| !sender.is_closed());
+		senders.push(sender);
+	}
+
+	/// Keys with at least one watch that has not been dropped.
+	pub fn keys(&self) -> Vec<Vec<u8>> {
+		let mut watches = self.watches.lock().expect("poisoned lock");
+
+		watches.retain(|_, senders| {
+			senders.retain(|sender| !sender.is_closed());
+			!senders.is_empty()
+		});
+
+		watches.keys().cloned().collect()
+	}
+
+	pub fn notify_key(&self, key: &[u8]) {
+		let senders = self.watches.lock().expect("poisoned lock").remove(key);
+
+		for sender in senders.into_iter().flatten() {
+			let _ = sender.send(());
+		}
+	}
+
+	pub fn notify_range(&self, begin: &[u8], end: &[u8]) {
+		if begin >= end {
+			return;
+		}
+
+		let mut watches = self.watches.lock().expect("poisoned lock");
+		let keys = watches
+			.range::<[u8], _>((Bound::Included(begin), Bound::Excluded(end)))
+			.map(|(key, _)| key.clone())
+			.collect::<Vec<_>>();
+
+		for key in keys {
+			for sender in watches.remove(&key).into_iter().flatten() {
+				let _ = sender.send(());
+			}
+		}
+	}
+
+	/// Resolves every watch. Used when changes may have been missed.
+	pub fn notify_all(&self) {
+		let watches = std::mem::take(&mut *self.watches.lock().expect("poisoned lock"));
+
+		for sender in watches.into_values().flatten() {
+			let _ = sender.send(());
+		}
+	}
+
+	pub fn notify_operations(&self, operations: &[Operation]) {
+		for op in operations {
+			match op {
+				Operation::Set { key, .. }
+				| Operation::Clear { key }
+				| Operation::AtomicOp { key, .. } => self.notify_key(key),
+				Operation::ClearRange { begin, end } => self.notify_range(begin, end),
+			}
+		}
+	}
+}
+
+/// Watches created by a single transaction. Like FDB, watches are only registered once their transaction
+/// commits and fail if it is reset, cancelled, or dropped without committing.
+#[derive(Default)]
+pub(crate) struct PendingWatches {
+	watches: Mutex<Vec<(Vec<u8>, oneshot::Sender<()>)>>,
+}
+
+impl PendingWatches {
+	pub fn add(&self, key: &[u8]) -> impl Future<Output = Result<()>> + Send + 'static {
+		let (sender, receiver) = oneshot::channel();
+		self.watches
+			.lock()
+			.expect("poisoned lock")
+			.push((key.to_vec(), sender));
+
+		async move {
+			receiver.await.map_err(|_| DatabaseError::WatchCancelled)?;
+
+			Ok(())
+		}
+	}
+
+	/// Keys watched by this transaction.
+	pub fn keys(&self) -> Vec<Vec<u8>> {
+		self.watches
+			.lock()
+			.expect("poisoned lock")
+			.iter()
+			.map(|(key, _)| key.clone())
+			.collect()
+	}
+
+	/// Moves all watches of this transaction to the registry. Called after a successful commit.
+	pub fn register(&self, registry: &WatchRegistry) {
+		let watches = std::mem::take(&mut *self.watches.lock().expect("poisoned lock"));
+
+		for (key, sender) in watches {
+			registry.register(key, sender);
+		}
+	}
+
+	/// Cancels all watches of this transaction.
+	pub fn clear(&self) {
+		self.watches.lock().expect("poisoned lock").clear();
+	}
+}
@@ -1,10 +1,10 @@
 use anyhow::Result;
 use rivet_test_deps_docker::TestDatabase;
-use std::{borrow::Cow, sync::Arc};
+use std::{borrow::Cow, sync::Arc, time::Duration};
 use universaldb::{
 	Database,
 	key_selector::KeySelector,
-	options::{ConflictRangeType, StreamingMode},
+	options::{ConflictRangeType, MutationType, StreamingMode},
 	range_option::RangeOption,
 	tuple::{Element, Subspace, Versionstamp, pack_with_versionstamp},
 	utils::IsolationLevel::*,
@@ -42,7 +42,7 @@ async fn test_postgres_driver() {
 		.unwrap();
 	let db = Database::new(Arc::new(driver));
 
-	run_all_tests(db).await
+	run_all_tests(db, false).await
 }
 
 #[tokio::test]
@@ -61,10 +61,39 @@ async fn test_rocksdb_driver() {
 		.unwrap();
 	let db = Database::new(Arc::new(driver));
 
-	run_all_tests(db).await;
+	run_all_tests(db, false).await;
 }
 
-async fn run_all_tests(db: universaldb::Database) {
+#[cfg(feature = "fdb")]
+#[tokio::test]
+async fn test_fdb_driver() {
+	let _ = tracing_subscriber::fmt::try_init();
+
+	let (db_config, docker_config) = TestDatabase::FoundationDb
+		.config(Uuid::new_v4(), 1)
+		.await
+		.unwrap();
+	let mut docker_config = docker_config.unwrap();
+	docker_config.start().await.unwrap();
+	TestDatabase::wait_for_foundationdb_ready(&docker_config.container_name, 20)
+		.await
+		.unwrap();
+
+	let rivet_config::config::Database::FoundationDb(fdb_config) = db_config else {
+		unreachable!();
+	};
+
+	let driver = universaldb::driver::FdbDatabaseDriver::new(fdb_config.cluster_file_path)
+		.await
+		.unwrap();
+	let db = Database::new(Arc::new(driver));
+
+	run_all_tests(db, true).await;
+}
+
+/// `versioned_reads` is set for drivers that can read at a previous version, see
+/// `Transaction::set_read_version`.
+async fn run_all_tests(db: universaldb::Database, versioned_reads: bool) {
 	// Clear test namespace before tests
 	clear_test_namespace(&db).await.unwrap();
 
@@ -116,22 +145,31 @@ async fn run_all_tests(db: universaldb::Database) {
 	clear_test_namespace(&db).await.unwrap();
 
 	// Test versionstamp functionality
-	// TODO: Versionstamp tests expect FoundationDB-specific behavior
-	// where all versionstamps in a transaction have the same transaction version.
-	// This doesn't apply to RocksDB or PostgreSQL drivers.
-	// test_versionstamps(&db).await;
-	// clear_test_namespace(&db).await.unwrap();
+	test_versionstamps(&db).await;
+	clear_test_namespace(&db).await.unwrap();
+
+	// Test plain sets of versionstamped values
+	test_versionstamps_plain_set(&db).await;
+	clear_test_namespace(&db).await.unwrap();
 
 	// Test database options
 	test_database_options(&db).await;
 	clear_test_namespace(&db).await.unwrap();
+
+	// Test watches
+	test_watches(&db).await;
+	clear_test_namespace(&db).await.unwrap();
+
+	// Test read versions
+	test_read_version(&db, versioned_reads).await;
+	clear_test_namespace(&db).await.unwrap();
 }
 
 async fn test_database_options(db: &Database) {
 	use std::sync::Arc;
 	use std::sync::atomic::{AtomicU32, Ordering};
 	use universaldb::error::DatabaseError;
-	use universaldb::options::DatabaseOption;
+	use universaldb::options::{DatabaseOption, TransactionOption};
 
 	// Test setting transaction retry limit
 	db.set_option(DatabaseOption::TransactionRetryLimit(5))
@@ -200,6 +238,60 @@ async fn test_database_options(db: &Database) {
 	// Reset to a reasonable retry limit
 	db.set_option(DatabaseOption::TransactionRetryLimit(100))
 		.unwrap();
+
+	// Test that the transaction timeout is respected
+	db.set_option(DatabaseOption::TransactionTimeout(100))
+		.unwrap();
+
+	let result = db
+		.run(|_tx| async move {
+			tokio::time::sleep(Duration::from_millis(300)).await;
+			Ok(())
+		})
+		.await;
+	assert!(result.is_err(), "Transaction should time out");
+
+	// Test that a transaction can raise its own timeout
+	let result = db
+		.run(|tx| async move {
+			tx.set_option(TransactionOption::Timeout(2000))?;
+			tokio::time::sleep(Duration::from_millis(300)).await;
+			Ok(())
+		})
+		.await;
+	assert!(
+		result.is_ok(),
+		"Transaction timeout should override the database timeout"
+	);
+
+	db.set_option(DatabaseOption::TransactionTimeout(5000))
+		.unwrap();
+
+	// Test that the transaction size limit is respected
+	db.set_option(DatabaseOption::TransactionSizeLimit(1000))
+		.unwrap();
+
+	let result = db
+		.run(|tx| async move {
+			let test_subspace = Subspace::from("test");
+			tx.set(&test_subspace.pack(&("large",)), &[0; 2000]);
+			Ok(())
+		})
+		.await;
+	assert!(result.is_err(), "Transaction should exceed the size limit");
+
+	db.set_option(DatabaseOption::TransactionSizeLimit(10_000_000))
+		.unwrap();
+
+	// Test that invalid options are rejected
+	assert!(
+		db.set_option(DatabaseOption::TransactionSizeLimit(1))
+			.is_err()
+	);
+	assert!(
+		db.set_option(DatabaseOption::TransactionTimeout(-1))
+			.is_err()
+	);
 }
 
 async fn clear_test_namespace(db: &Database) -> Result<()> {
@@ -2364,22 +2456,22 @@ async fn test_atomic_nonexistent_keys(db: &Database) {
 }
 
 async fn test_versionstamps(db: &Database) {
-	// Test 1: Basic versionstamp insertion and ordering within a single transaction
+	// Test 1: All versionstamped values written in the same transaction get the same transaction versionstamp
+	// and keep their user version
 	db.run(|tx| async move {
 		let test_subspace = Subspace::from("test_vs");
 
-		// Create multiple values with incomplete versionstamps in the same transaction
-		// Insert multiple entries with versionstamps
-		for i in 0..3 {
-			let incomplete = Versionstamp::from([0xff; 12]);
+		for i in 0..10 {
 			let tuple = vec![
 				Element::String("vs_test".into()),
-				Element::Versionstamp(incomplete),
-				Element::Int(i),
+				Element::Versionstamp(Versionstamp::incomplete(i)),
 			];
-			let key = test_subspace.pack(&(format!("entry_{}", i),));
-			let value = pack_with_versionstamp(&tuple);
-			tx.set(&key, &value);
+			let key = test_subspace.pack(&("entry", i));
+			tx.informal().atomic_op(
+				&key,
+				&pack_with_versionstamp(&tuple),
+				MutationType::SetVersionstampedValue,
+			);
 		}
 
 		Ok(())
@@ -2387,97 +2479,92 @@ async fn test_versionstamps(db: &Database) {
 	.await
 	.unwrap();
 
-	// Verify that versionstamps were substituted and have the same transaction version
-	// but different user versions (counter values)
-	let results = db
-		.run(|tx| async move {
-			let test_subspace = Subspace::from("test_vs");
-			let (begin, end) = test_subspace.range();
+	let results = read_versionstamps(db, "entry").await;
+	assert_eq!(results.len(), 10, "Expected 10 entries with versionstamps");
 
-			let range_opt = RangeOption {
-				begin: KeySelector::first_greater_or_equal(Cow::Owned(begin)),
-				end: KeySelector::first_greater_or_equal(Cow::Owned(end)),
-				..RangeOption::default()
-			};
+	for (i, vs) in results.iter().enumerate() {
+		assert!(
+			vs.is_complete(),
+			"Versionstamp should be complete after commit"
+		);
+		assert_eq!(
+			&vs.as_bytes()[..10],
+			&results[0].as_bytes()[..10],
+			"All entries should have the same transaction versionstamp"
+		);
+		assert_eq!(
+			vs.user_version(),
+			i as u16,
+			"User version should be preserved"
+		);
+	}
 
-			let values = tx.get_range(&range_opt, 1, Serializable).await?;
-			let mut results = Vec::new();
-
-			for kv in values.into_iter() {
-				let key = kv.key().to_vec();
-				let value = kv.value().to_vec();
-				let unpacked: Vec<Element> = universaldb::tuple::unpack(&value).unwrap();
-				if unpacked.len() >= 3 {
-					if let Element::Versionstamp(vs) = &unpacked[1] {
-						if let Element::Int(i) = &unpacked[2] {
-							results.push((key, vs.clone(), *i));
-						}
-					}
-				}
-			}
+	// Test 2: Versionstamps increase across transactions
+	for i in 0..3 {
+		db.run(|tx| async move {
+			let test_subspace = Subspace::from("test_vs");
+			let tuple = vec![
+				Element::String("multi_tx".into()),
+				Element::Versionstamp(Versionstamp::incomplete(0)),
+			];
+			let key = test_subspace.pack(&("tx", i));
+			tx.informal().atomic_op(
+				&key,
+				&pack_with_versionstamp(&tuple),
+				MutationType::SetVersionstampedValue,
+			);
 
-			Ok(results)
+			Ok(())
 		})
 		.await
 		.unwrap();
-
-	// All entries should have versionstamps
-	assert_eq!(results.len(), 3, "Expected 3 entries with versionstamps");
-
-	// Check if versionstamp substitution is supported by checking if any are complete
-	let has_substitution = results.iter().any(|(_, vs, _)| vs.is_complete());
-
-	if !has_substitution {
-		// Memory driver doesn't support versionstamp substitution
-		// Skip the remaining versionstamp tests
-		println!("Skipping versionstamp tests - driver doesn't support substitution");
-		return;
 	}
 
-	// Extract versionstamps and verify they're complete
-	for (_, vs, _) in &results {
+	let results = read_versionstamps(db, "tx").await;
+	assert_eq!(
+		results.len(),
+		3,
+		"Expected 3 entries from multiple transactions"
+	);
+
+	for i in 1..results.len() {
 		assert!(
-			vs.is_complete(),
-			"Versionstamp should be complete after substitution"
+			results[i].as_bytes() > results[i - 1].as_bytes(),
+			"Versionstamps should increase across transactions"
 		);
 	}
 
-	// Test 2: Versionstamp ordering across multiple transactions
-	// Insert entries in separate transactions to get different transaction versions
-	let mut tx_versionstamps = Vec::new();
+	// Test 3: Versionstamped keys get the transaction versionstamp, in the same transaction as a versionstamped
+	// value
+	db.run(|tx| async move {
+		let test_subspace = Subspace::from("test_vs");
 
-	for i in 0..3 {
-		let vs = db
-			.run(|tx| async move {
-				let test_subspace = Subspace::from("test_vs");
-				let incomplete = Versionstamp::from([0xff; 12]);
-
-				let tuple = vec![
-					Element::String("multi_tx".into()),
-					Element::Versionstamp(incomplete),
-					Element::Int(i),
-				];
-				let key = test_subspace.pack(&(format!("tx_{}", i),));
-				let value = pack_with_versionstamp(&tuple);
-				tx.set(&key, &value);
-
-				Ok(i)
-			})
-			.await
-			.unwrap();
+		let key_tuple = ("vs_key", Versionstamp::incomplete(7));
+		tx.informal().atomic_op(
+			&test_subspace.pack_with_versionstamp(&key_tuple),
+			b"value",
+			MutationType::SetVersionstampedKey,
+		);
 
-		tx_versionstamps.push(vs);
+		let tuple = vec![
+			Element::String("with_key".into()),
+			Element::Versionstamp(Versionstamp::incomplete(0)),
+		];
+		tx.informal().atomic_op(
+			&test_subspace.pack(&("with_key", 0)),
+			&pack_with_versionstamp(&tuple),
+			MutationType::SetVersionstampedValue,
+		);
 
-		// Small delay to ensure different timestamps
-		tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
-	}
+		Ok(())
+	})
+	.await
+	.unwrap();
 
-	// Read back and verify ordering
-	let multi_tx_results = db
+	let (key_vs, value) = db
 		.run(|tx| async move {
-			let test_subspace = Subspace::from("test_vs");
-			let begin = test_subspace.pack(&("tx_",));
-			let end = test_subspace.pack(&("tx_z",));
+			let test_subspace = Subspace::from("test_vs").subspace(&("vs_key",));
+			let (begin, end) = test_subspace.range();
 
 			let range_opt = RangeOption {
 				begin: KeySelector::first_greater_or_equal(Cow::Owned(begin)),
@@ -2486,107 +2573,189 @@ async fn test_versionstamps(db: &Database) {
 			};
 
 			let values = tx.get_range(&range_opt, 1, Serializable).await?;
-			let mut results = Vec::new();
+			assert_eq!(values.len(), 1, "Expected 1 versionstamped key");
 
-			for kv in values.into_iter() {
-				let unpacked: Vec<Element> = universaldb::tuple::unpack(kv.value()).unwrap();
-				if let Element::Versionstamp(vs) = &unpacked[1] {
-					results.push((kv.key().to_vec(), vs.as_bytes().to_vec()));
-				}
-			}
+			let kv = values.into_iter().next().unwrap();
+			let (vs,) = test_subspace.unpack::<(Versionstamp,)>(kv.key())?;
 
-			Ok(results)
+			Ok((vs, kv.value().to_vec()))
 		})
 		.await
 		.unwrap();
+	let value_vs = read_versionstamps(db, "with_key").await;
 
+	assert!(
+		key_vs.is_complete(),
+		"Versionstamped key should be complete"
+	);
+	assert_eq!(key_vs.user_version(), 7, "User version should be preserved");
+	assert_eq!(value, b"value");
 	assert_eq!(
-		multi_tx_results.len(),
-		3,
-		"Expected 3 entries from multiple transactions"
+		&key_vs.as_bytes()[..10],
+		&value_vs[0].as_bytes()[..10],
+		"Versionstamped keys and values should have the same transaction versionstamp"
 	);
 
-	// Versionstamps from later transactions should be greater
-	for i in 1..multi_tx_results.len() {
-		let prev_vs = &multi_tx_results[i - 1].1;
-		let curr_vs = &multi_tx_results[i].1;
-		assert!(
-			curr_vs > prev_vs,
-			"Versionstamps should increase across transactions"
-		);
-	}
+	// Test 4: Plain sets are written as is, including complete and incomplete versionstamps
+	let complete = generate_versionstamp(999);
+	db.run(|tx| {
+		let complete = complete.clone();
 
-	// Test 3: Already complete versionstamps should not be modified
-	db.run(|tx| async move {
-		let test_subspace = Subspace::from("test_vs");
-
-		// Create a complete versionstamp manually
-		let complete_vs = generate_versionstamp(999);
+		async move {
+			let test_subspace = Subspace::from("test_vs");
 
-		let tuple = vec![
-			Element::String("complete_vs".into()),
-			Element::Versionstamp(complete_vs),
-			Element::Int(42),
-		];
+			let tuple = vec![
+				Element::String("plain".into()),
+				Element::Versionstamp(complete),
+			];
+			tx.set(
+				&test_subspace.pack(&("plain", 0)),
+				&pack_with_versionstamp(&tuple),
+			);
 
-		// Pack with versionstamp (this adds the offset)
-		let value = pack_with_versionstamp(&tuple);
-		let key = test_subspace.pack(&("complete_entry",));
-		tx.set(&key, &value);
+			// Packed without the offset since it is not a versionstamped op
+			let tuple = vec![
+				Element::String("plain".into()),
+				Element::Versionstamp(Versionstamp::incomplete(1)),
+			];
+			tx.set(
+				&test_subspace.pack(&("plain", 1)),
+				&universaldb::tuple::pack(&tuple),
+			);
 
-		Ok(())
+			Ok(())
+		}
 	})
 	.await
 	.unwrap();
 
-	// Read back and verify the versionstamp remains unchanged
-	let complete_result = db
+	let results = read_versionstamps(db, "plain").await;
+	assert_eq!(results.len(), 2, "Expected 2 plain entries");
+	assert_eq!(
+		results[0].as_bytes(),
+		complete.as_bytes(),
+		"Complete versionstamp should not be modified"
+	);
+	assert!(
+		!results[1].is_complete(),
+		"Incomplete versionstamp should not be filled in by a plain set"
+	);
+	assert_eq!(results[1].user_version(), 1);
+
+	// Test 5: The versionstamp of a transaction resolves after commit to the one filled in by its versionstamped
+	// ops
+	let tx_versionstamp = db
 		.run(|tx| async move {
 			let test_subspace = Subspace::from("test_vs");
-			let key = test_subspace.pack(&("complete_entry",));
-			let value = tx.get(&key, Serializable).await?.unwrap();
 
-			let unpacked: Vec<Element> = universaldb::tuple::unpack(&value).unwrap();
-			if let Element::Versionstamp(vs) = &unpacked[1] {
-				Ok((vs.user_version(), vs.is_complete()))
-			} else {
-				panic!("Expected versionstamp element");
-			}
+			let tuple = vec![
+				Element::String("get_vs".into()),
+				Element::Versionstamp(Versionstamp::incomplete(3)),
+			];
+			tx.informal().atomic_op(
+				&test_subspace.pack(&("get_vs", 0)),
+				&pack_with_versionstamp(&tuple),
+				MutationType::SetVersionstampedValue,
+			);
+
+			Ok(tx.get_versionstamp())
 		})
 		.await
+		.unwrap()
+		.await
 		.unwrap();
 
-	assert_eq!(complete_result.0, 999, "User version should remain 999");
-	assert!(complete_result.1, "Versionstamp should still be complete");
+	let results = read_versionstamps(db, "get_vs").await;
+	assert!(tx_versionstamp.is_complete());
+	assert_eq!(tx_versionstamp.user_version(), 0);
+	assert_eq!(
+		&tx_versionstamp.as_bytes()[..10],
+		&results[0].as_bytes()[..10],
+		"Transaction versionstamp should match the filled in versionstamp"
+	);
+
+	// Test 6: Requesting the versionstamp of a transaction that is never committed fails
+	let tx = db.create_trx().unwrap();
+	let versionstamp = tx.get_versionstamp();
+	tx.cancel();
+	drop(tx);
+	assert!(
+		versionstamp.await.is_err(),
+		"Versionstamp of a cancelled transaction should not resolve"
+	);
+}
 
-	// Test 4: Verify correct count and order within a transaction
-	// Insert 10 entries in one transaction and verify they have sequential counters
+/// Reads the versionstamps of all values written with the given prefix by `test_versionstamps`, ordered by key.
+async fn read_versionstamps(db: &Database, prefix: &'static str) -> Vec<Versionstamp> {
 	db.run(|tx| async move {
-		let test_subspace = Subspace::from("test_vs");
+		let test_subspace = Subspace::from("test_vs").subspace(&(prefix,));
+		let (begin, end) = test_subspace.range();
 
-		for i in 0..10 {
-			let incomplete = Versionstamp::from([0xff; 12]);
-			let tuple = vec![
-				Element::String("count_test".into()),
-				Element::Versionstamp(incomplete),
-				Element::Int(i),
-			];
-			let key = test_subspace.pack(&(format!("count_{:02}", i),));
-			let value = pack_with_versionstamp(&tuple);
-			tx.set(&key, &value);
+		let range_opt = RangeOption {
+			begin: KeySelector::first_greater_or_equal(Cow::Owned(begin)),
+			end: KeySelector::first_greater_or_equal(Cow::Owned(end)),
+			..RangeOption::default()
+		};
+
+		let values = tx.get_range(&range_opt, 1, Serializable).await?;
+		let mut results = Vec::new();
+
+		for kv in values.into_iter() {
+			let unpacked: Vec<Element> = universaldb::tuple::unpack(kv.value()).unwrap();
+			let Element::Versionstamp(vs) = &unpacked[1] else {
+				panic!("Expected versionstamp element");
+			};
+
+			results.push(vs.clone());
 		}
 
-		Ok(())
+		Ok(results)
+	})
+	.await
+	.unwrap()
+}
+
+/// Regression test for plain sets of values packed with `pack_with_versionstamp`. These used to have their
+/// incomplete versionstamps filled in on the RocksDB and Postgres drivers, they are now written as is like on
+/// FDB.
+async fn test_versionstamps_plain_set(db: &Database) {
+	// Incomplete and complete versionstamps in the same transaction
+	let mut written = Vec::new();
+	for i in 0..3 {
+		let tuple = vec![
+			Element::String("plain_set".into()),
+			Element::Versionstamp(Versionstamp::from([0xff; 12])),
+			Element::Int(i),
+		];
+		written.push(pack_with_versionstamp(&tuple));
+	}
+	let tuple = vec![
+		Element::String("plain_set".into()),
+		Element::Versionstamp(generate_versionstamp(555)),
+		Element::Int(3),
+	];
+	written.push(pack_with_versionstamp(&tuple));
+
+	db.run(|tx| {
+		let written = written.clone();
+
+		async move {
+			let test_subspace = Subspace::from("test_vs");
+
+			for (i, value) in written.iter().enumerate() {
+				tx.set(&test_subspace.pack(&("plain_set", i)), value);
+			}
+
+			Ok(())
+		}
 	})
 	.await
 	.unwrap();
 
-	// Read back and verify count and ordering
-	let count_results = db
+	let stored = db
 		.run(|tx| async move {
-			let test_subspace = Subspace::from("test_vs");
-			let begin = test_subspace.pack(&("count_",));
-			let end = test_subspace.pack(&("count_z",));
+			let test_subspace = Subspace::from("test_vs").subspace(&("plain_set",));
+			let (begin, end) = test_subspace.range();
 
 			let range_opt = RangeOption {
 				begin: KeySelector::first_greater_or_equal(Cow::Owned(begin)),
@@ -2595,138 +2764,196 @@ async fn test_versionstamps(db: &Database) {
 			};
 
 			let values = tx.get_range(&range_opt, 1, Serializable).await?;
-			let mut results = Vec::new();
 
-			for kv in values.into_iter() {
-				let unpacked: Vec<Element> = universaldb::tuple::unpack(kv.value()).unwrap();
-				if let (Element::Versionstamp(vs), Element::Int(idx)) = (&unpacked[1], &unpacked[2])
-				{
-					results.push((vs.as_bytes().to_vec(), *idx));
-				}
-			}
-
-			Ok(results)
+			Ok(values
+				.into_iter()
+				.map(|kv| kv.value().to_vec())
+				.collect::<Vec<_>>())
 		})
 		.await
 		.unwrap();
 
-	assert_eq!(count_results.len(), 10, "Expected exactly 10 entries");
+	assert_eq!(stored, written, "Plain sets should store values as is");
 
-	// All should have the same transaction version (first 8 bytes)
-	let first_tx_version = &count_results[0].0[0..8];
-	for (vs_bytes, _) in &count_results {
-		assert_eq!(
-			&vs_bytes[0..8],
-			first_tx_version,
-			"All entries should have same transaction version"
+	// The incomplete versionstamps are still incomplete, the offset suffix is kept
+	for value in &stored[..3] {
+		let unpacked: Vec<Element> = universaldb::tuple::unpack(&value[..value.len() - 4]).unwrap();
+		let Element::Versionstamp(vs) = &unpacked[1] else {
+			panic!("Expected versionstamp element");
+		};
+
+		assert!(
+			!vs.is_complete(),
+			"Incomplete versionstamp should not be filled in by a plain set"
 		);
 	}
 
-	// The counter portion (bytes 8-10) should be sequential
-	let mut counters: Vec<u16> = count_results
-		.iter()
-		.map(|(vs_bytes, _)| u16::from_be_bytes([vs_bytes[8], vs_bytes[9]]))
-		.collect();
-	counters.sort();
+	// The complete versionstamp is not modified
+	let unpacked: Vec<Element> = universaldb::tuple::unpack(&stored[3]).unwrap();
+	let Element::Versionstamp(vs) = &unpacked[1] else {
+		panic!("Expected versionstamp element");
+	};
+	assert_eq!(
+		vs.user_version(),
+		555,
+		"Complete versionstamp should keep its user version"
+	);
+}
 
-	// Verify they are sequential (might not start at 0 due to other operations)
-	for i in 1..counters.len() {
-		assert_eq!(
-			counters[i],
-			counters[i - 1] + 1,
-			"Counters should be sequential"
-		);
-	}
+async fn test_watches(db: &Database) {
+	// Register a watch
+	let watch = db
+		.run(|tx| async move {
+			let test_subspace = Subspace::from("test");
+			let key = test_subspace.pack(&("watched",));
+			Ok(tx.watch(&key))
+		})
+		.await
+		.unwrap();
+	let mut watch = tokio::spawn(watch);
 
-	// Test 5: Mixed incomplete and complete versionstamps in same transaction
+	// Changing a different key does not resolve the watch
 	db.run(|tx| async move {
-		let test_subspace = Subspace::from("test_vs");
+		let test_subspace = Subspace::from("test");
+		let key = test_subspace.pack(&("other",));
+		tx.set(&key, b"value");
+		Ok(())
+	})
+	.await
+	.unwrap();
 
-		// Insert an incomplete versionstamp
-		let incomplete = Versionstamp::from([0xff; 12]);
-		let tuple1 = vec![
-			Element::String("mixed".into()),
-			Element::Versionstamp(incomplete),
-			Element::Int(1),
-		];
-		let key1 = test_subspace.pack(&("mixed_incomplete",));
-		let value1 = pack_with_versionstamp(&tuple1);
-		tx.set(&key1, &value1);
-
-		// Insert a complete versionstamp
-		let complete = generate_versionstamp(555);
-		let tuple2 = vec![
-			Element::String("mixed".into()),
-			Element::Versionstamp(complete),
-			Element::Int(2),
-		];
-		let key2 = test_subspace.pack(&("mixed_complete",));
-		let value2 = pack_with_versionstamp(&tuple2);
-		tx.set(&key2, &value2);
+	assert!(
+		tokio::time::timeout(Duration::from_millis(500), &mut watch)
+			.await
+			.is_err(),
+		"watch resolved without its key changing"
+	);
 
+	// Changing the watched key resolves the watch
+	db.run(|tx| async move {
+		let test_subspace = Subspace::from("test");
+		let key = test_subspace.pack(&("watched",));
+		tx.set(&key, b"value");
 		Ok(())
 	})
 	.await
 	.unwrap();
 
-	// Verify both were stored correctly
-	let mixed_results = db
-		.run(|tx| async move {
-			let test_subspace = Subspace::from("test_vs");
-			let begin = test_subspace.pack(&("mixed_",));
-			let end = test_subspace.pack(&("mixed_z",));
+	tokio::time::timeout(Duration::from_secs(5), watch)
+		.await
+		.expect("watch did not resolve")
+		.unwrap()
+		.unwrap();
 
-			let range_opt = RangeOption {
-				begin: KeySelector::first_greater_or_equal(Cow::Owned(begin)),
-				end: KeySelector::first_greater_or_equal(Cow::Owned(end)),
-				..RangeOption::default()
-			};
+	// Clearing a range containing the watched key resolves the watch
+	let watch = db
+		.run(|tx| async move {
+			let test_subspace = Subspace::from("test");
+			let key = test_subspace.pack(&("watched",));
+			Ok(tx.watch(&key))
+		})
+		.await
+		.unwrap();
 
-			let values = tx.get_range(&range_opt, 1, Serializable).await?;
-			let mut results = Vec::new();
-
-			for kv in values.into_iter() {
-				let unpacked: Vec<Element> = universaldb::tuple::unpack(kv.value()).unwrap();
-				if let Element::Versionstamp(vs) = &unpacked[1] {
-					results.push((
-						String::from_utf8_lossy(kv.key()).to_string(),
-						vs.user_version(),
-						vs.is_complete(),
-					));
-				}
-			}
+	clear_test_namespace(db).await.unwrap();
 
-			Ok(results)
-		})
+	tokio::time::timeout(Duration::from_secs(5), watch)
 		.await
+		.expect("watch did not resolve")
 		.unwrap();
 
-	assert_eq!(mixed_results.len(), 2, "Expected 2 mixed entries");
+	// Watches of cancelled transactions fail
+	let tx = db.create_trx().unwrap();
+	let key = Subspace::from("test").pack(&("watched",));
+	let watch = tx.watch(&key);
+	tx.cancel();
+	drop(tx);
 
-	// Find and verify each entry
-	let incomplete_entry = mixed_results
-		.iter()
-		.find(|(k, _, _)| k.contains("mixed_incomplete"))
-		.expect("Should find incomplete entry");
 	assert!(
-		incomplete_entry.2,
-		"Incomplete versionstamp should be substituted and complete"
-	);
-	assert_eq!(
-		incomplete_entry.1, 0,
-		"Substituted versionstamp should have user_version 0"
+		tokio::time::timeout(Duration::from_secs(5), watch)
+			.await
+			.expect("watch did not resolve")
+			.is_err(),
+		"watch of a cancelled transaction should fail"
 	);
+}
 
-	let complete_entry = mixed_results
-		.iter()
-		.find(|(k, _, _)| k.contains("mixed_complete"))
-		.expect("Should find complete entry");
-	assert!(
-		complete_entry.2,
-		"Complete versionstamp should remain complete"
-	);
-	assert_eq!(
-		complete_entry.1, 555,
-		"Complete versionstamp should keep user_version 555"
-	);
+async fn test_read_version(db: &Database, versioned_reads: bool) {
+	let version = db
+		.run(|tx| async move { tx.get_read_version().await })
+		.await
+		.unwrap();
+
+	let later_version = db
+		.run(|tx| async move { tx.get_read_version().await })
+		.await
+		.unwrap();
+	assert!(later_version >= version, "read versions must not decrease");
+
+	if !versioned_reads {
+		// Setting a read version must fail instead of silently reading the latest data
+		let err = db
+			.run(|tx| async move { tx.set_read_version(version) })
+			.await
+			.unwrap_err();
+		assert!(
+			matches!(
+				err.downcast_ref::<universaldb::error::DatabaseError>(),
+				Some(universaldb::error::DatabaseError::ReadVersionUnsupported)
+			),
+			"set_read_version should be unsupported"
+		);
+
+		return;
+	}
+
+	db.run(|tx| async move {
+		let test_subspace = Subspace::from("test");
+		let key = test_subspace.pack(&("read_version",));
+		tx.set(&key, b"old");
+		Ok(())
+	})
+	.await
+	.unwrap();
+
+	let version = db
+		.run(|tx| async move { tx.get_read_version().await })
+		.await
+		.unwrap();
+
+	db.run(|tx| async move {
+		let test_subspace = Subspace::from("test");
+		let key = test_subspace.pack(&("read_version",));
+		tx.set(&key, b"new");
+		Ok(())
+	})
+	.await
+	.unwrap();
+
+	// Reads at the previous version do not observe the newer write
+	let value = db
+		.run(|tx| async move {
+			tx.set_read_version(version)?;
+			assert_eq!(tx.get_read_version().await?, version);
+
+			let test_subspace = Subspace::from("test");
+			let key = test_subspace.pack(&("read_version",));
+			tx.get(&key, Snapshot).await
+		})
+		.await
+		.unwrap();
+	assert_eq!(value, Some(b"old".to_vec().into()));
+
+	// Transactions can write after reading at a previous version
+	db.run(|tx| async move {
+		tx.set_read_version(version)?;
+
+		let test_subspace = Subspace::from("test");
+		let key = test_subspace.pack(&("read_version",));
+		tx.get(&key, Snapshot).await?;
+		tx.set(&key, b"value");
+		Ok(())
+	})
+	.await
+	.unwrap();
 }
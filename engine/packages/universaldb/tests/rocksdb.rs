@@ -4,7 +4,7 @@ use anyhow::Context;
 use futures_util::StreamExt;
 use rivet_test_deps_docker::TestDatabase;
 use rocksdb::{OptimisticTransactionDB, Options, WriteOptions};
-use universaldb::{Database, utils::IsolationLevel::*};
+use universaldb::{Database, driver::rocksdb::backup, utils::IsolationLevel::*};
 use uuid::Uuid;
 
 #[tokio::test]
@@ -137,6 +137,95 @@ async fn rocksdb_udb() {
 		.await;
 }
 
+#[tokio::test]
+async fn rocksdb_checkpoint() {
+	let _ = tracing_subscriber::fmt()
+		.with_env_filter("debug")
+		.with_test_writer()
+		.try_init();
+
+	let temp_dir = tempfile::tempdir().unwrap();
+	let db_path = temp_dir.path().join("db");
+	let checkpoint_path = temp_dir.path().join("checkpoint");
+	let tarball_path = temp_dir.path().join("checkpoint.tar.gz");
+	let unpacked_path = temp_dir.path().join("unpacked");
+	let restored_path = temp_dir.path().join("restored");
+
+	let driver = universaldb::driver::RocksDbDatabaseDriver::new(db_path.clone())
+		.await
+		.unwrap();
+	let db = Database::new(Arc::new(driver));
+
+	for i in 0..10u8 {
+		db.run(|tx| async move {
+			tx.set(&[1, i], &[i; 16]);
+			Ok(())
+		})
+		.await
+		.unwrap();
+	}
+
+	// Checkpoint while the database is open
+	db.checkpoint(&checkpoint_path).await.unwrap();
+	assert!(
+		db.checkpoint(&checkpoint_path).await.is_err(),
+		"checkpoint path should not be overwritten"
+	);
+
+	// Writes after the checkpoint are not included
+	db.run(|tx| async move {
+		tx.set(&[2], &[2]);
+		Ok(())
+	})
+	.await
+	.unwrap();
+
+	let info = backup::validate_checkpoint(&checkpoint_path).unwrap();
+	assert_eq!(info.key_count, 10);
+	assert_eq!(info.size_bytes, 10 * (2 + 16));
+
+	// Round trip through a tarball
+	backup::write_tarball(
+		&checkpoint_path,
+		std::fs::File::create(&tarball_path).unwrap(),
+	)
+	.unwrap();
+	backup::unpack_tarball(std::fs::File::open(&tarball_path).unwrap(), &unpacked_path).unwrap();
+	assert_eq!(
+		backup::validate_checkpoint(&unpacked_path)
+			.unwrap()
+			.key_count,
+		10
+	);
+
+	// Restoring over a database in use fails
+	assert!(backup::restore_checkpoint(&unpacked_path, &db_path).is_err());
+
+	// Invalid checkpoints are rejected
+	assert!(backup::validate_checkpoint(temp_dir.path()).is_err());
+
+	let output = backup::restore_checkpoint(&unpacked_path, &restored_path).unwrap();
+	assert_eq!(output.info.key_count, 10);
+	assert!(output.previous_path.is_none());
+
+	let driver = universaldb::driver::RocksDbDatabaseDriver::new(restored_path)
+		.await
+		.unwrap();
+	let restored_db = Database::new(Arc::new(driver));
+
+	let (first, missing) = restored_db
+		.run(|tx| async move {
+			let first = tx.get(&[1, 0], Serializable).await?;
+			let missing = tx.get(&[2], Serializable).await?;
+
+			Ok((first.map(|x| x.to_vec()), missing))
+		})
+		.await
+		.unwrap();
+	assert_eq!(first, Some(vec![0; 16]));
+	assert!(missing.is_none());
+}
+
 pub fn calculate_tx_retry_backoff(attempt: usize) -> u64 {
 	let base_backoff_ms = 2_u64.pow((attempt as u32).min(10)) * 10;
 
@@ -182,3 +182,34 @@ fn test_versionstamp_incomplete_preserves_user_version() {
 		_ => panic!("Expected versionstamp"),
 	}
 }
+
+#[test]
+fn test_substitute_versionstamp_at_offset() {
+	let tuple = vec![
+		Element::String("mykey".into()),
+		Element::Versionstamp(Versionstamp::incomplete(7)),
+		Element::Int(42),
+	];
+	let packed = pack_with_versionstamp(&tuple);
+
+	let tx_versionstamp = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+	let substituted = substitute_versionstamp_at_offset(&packed, &tx_versionstamp).unwrap();
+
+	// The offset is removed
+	assert_eq!(substituted.len(), packed.len() - 4);
+
+	let unpacked: Vec<Element> = unpack(&substituted).unwrap();
+	match &unpacked[1] {
+		Element::Versionstamp(vs) => {
+			assert!(vs.is_complete());
+			assert_eq!(&vs.as_bytes()[..10], &tx_versionstamp);
+			assert_eq!(vs.user_version(), 7);
+		}
+		_ => panic!("Expected versionstamp"),
+	}
+	assert!(matches!(unpacked[2], Element::Int(42)));
+
+	// Invalid offsets
+	assert!(substitute_versionstamp_at_offset(&[1, 2, 3], &tx_versionstamp).is_err());
+	assert!(substitute_versionstamp_at_offset(&[0, 0, 0, 0, 0, 0, 0], &tx_versionstamp).is_err());
+}
@@ -1,15 +1,78 @@
 use anyhow::Result;
+use gas::prelude::futures_util::StreamExt;
 use gas::prelude::*;
+use rivet_config::config::workflows::DEFAULT_WORKER_POOL;
 
-#[tracing::instrument(skip_all)]
-pub async fn start(config: rivet_config::Config, pools: rivet_pools::Pools) -> Result<()> {
+/// All workflows ran by the workflow worker.
+pub fn registry() -> Result<Registry> {
 	let reg = pegboard::registry()?
 		.merge(namespace::registry()?)?
 		.merge(epoxy::registry()?)?;
 
+	Ok(reg)
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn start(config: rivet_config::Config, pools: rivet_pools::Pools) -> Result<()> {
+	let reg = registry()?.handle();
+
 	let db = db::DatabaseKv::from_pools(pools.clone()).await?;
-	let worker = Worker::new(reg.handle(), db, config, pools);
 
-	// Start worker
-	worker.start(None).await
+	ensure!(
+		!config
+			.workflows()
+			.worker_pools
+			.contains_key(DEFAULT_WORKER_POOL),
+		"worker pool name {DEFAULT_WORKER_POOL:?} is reserved for the default worker",
+	);
+
+	// Start a worker for each pool this process runs
+	let mut workers = Vec::new();
+	for pool_name in config.workflows().run_worker_pools() {
+		let worker = Worker::new(reg.clone(), db.clone(), config.clone(), pools.clone());
+		let worker = if pool_name == DEFAULT_WORKER_POOL {
+			worker
+		} else {
+			worker.with_pool(pool_name)?
+		};
+
+		// The runtime is shut down once all workers have shut down
+		let worker = worker.without_runtime_shutdown();
+
+		// Only the first worker runs the tasks shared by all pools
+		workers.push(if workers.is_empty() {
+			worker
+		} else {
+			worker.without_shared_tasks()
+		});
+	}
+	ensure!(!workers.is_empty(), "no worker pools to run");
+
+	// Shared by all workers so a stopping worker gracefully stops the other pools instead of dropping them
+	let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(());
+
+	// Start workers
+	let mut worker_futs = workers
+		.into_iter()
+		.map(|worker| worker.start(Some(shutdown_rx.clone())))
+		.collect::<futures_util::stream::FuturesUnordered<_>>();
+
+	// Wait for all workers to drain before returning the first error
+	let mut res = Ok(());
+	while let Some(worker_res) = worker_futs.next().await {
+		// Stop the other workers once any worker stops
+		let _ = shutdown_tx.send(());
+
+		if let Err(err) = worker_res {
+			if res.is_ok() {
+				res = Err(err);
+			} else {
+				tracing::error!(?err, "additional worker errored during shutdown");
+			}
+		}
+	}
+
+	rivet_runtime::shutdown().await;
+
+	res
 }
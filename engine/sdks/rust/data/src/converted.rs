@@ -7,28 +7,73 @@ pub struct RunnerAllocIdxKeyData {
 	pub workflow_id: Id,
 	pub remaining_slots: u32,
 	pub total_slots: u32,
+	/// Zero if the runner did not advertise its resources.
+	pub remaining_cpu_millicores: u32,
+	pub total_cpu_millicores: u32,
+	pub remaining_memory_mib: u32,
+	pub total_memory_mib: u32,
 }
 
-impl TryFrom<pegboard_namespace_runner_alloc_idx_v1::Data> for RunnerAllocIdxKeyData {
+impl RunnerAllocIdxKeyData {
+	/// Remaining capacity of the runner in millionths, based on whichever of slots, cpu, and memory is the
+	/// most used. The runner alloc idx is ordered by this value.
+	///
+	/// Rounded up so that only full runners (see `is_full`) have a remaining capacity of zero.
+	pub fn remaining_capacity(&self) -> u32 {
+		if self.is_full() {
+			return 0;
+		}
+
+		[
+			(self.remaining_slots, self.total_slots),
+			(self.remaining_cpu_millicores, self.total_cpu_millicores),
+			(self.remaining_memory_mib, self.total_memory_mib),
+		]
+		.into_iter()
+		.filter(|(_, total)| *total != 0)
+		.map(|(remaining, total)| {
+			((remaining.min(total) as u64 * 1_000_000).div_ceil(total as u64)) as u32
+		})
+		.min()
+		.unwrap_or_default()
+	}
+
+	/// Whether the runner has no slots left or used up any of the resources it advertised.
+	pub fn is_full(&self) -> bool {
+		self.remaining_slots == 0
+			|| (self.total_cpu_millicores != 0 && self.remaining_cpu_millicores == 0)
+			|| (self.total_memory_mib != 0 && self.remaining_memory_mib == 0)
+	}
+}
+
+impl TryFrom<pegboard_namespace_runner_alloc_idx_v2::Data> for RunnerAllocIdxKeyData {
 	type Error = anyhow::Error;
 
-	fn try_from(value: pegboard_namespace_runner_alloc_idx_v1::Data) -> Result<Self> {
+	fn try_from(value: pegboard_namespace_runner_alloc_idx_v2::Data) -> Result<Self> {
 		Ok(RunnerAllocIdxKeyData {
 			workflow_id: Id::from_slice(&value.workflow_id)?,
 			remaining_slots: value.remaining_slots,
 			total_slots: value.total_slots,
+			remaining_cpu_millicores: value.remaining_cpu_millicores,
+			total_cpu_millicores: value.total_cpu_millicores,
+			remaining_memory_mib: value.remaining_memory_mib,
+			total_memory_mib: value.total_memory_mib,
 		})
 	}
 }
 
-impl TryFrom<RunnerAllocIdxKeyData> for pegboard_namespace_runner_alloc_idx_v1::Data {
+impl TryFrom<RunnerAllocIdxKeyData> for pegboard_namespace_runner_alloc_idx_v2::Data {
 	type Error = anyhow::Error;
 
 	fn try_from(value: RunnerAllocIdxKeyData) -> Result<Self> {
-		Ok(pegboard_namespace_runner_alloc_idx_v1::Data {
+		Ok(pegboard_namespace_runner_alloc_idx_v2::Data {
 			workflow_id: value.workflow_id.as_bytes(),
 			remaining_slots: value.remaining_slots,
 			total_slots: value.total_slots,
+			remaining_cpu_millicores: value.remaining_cpu_millicores,
+			total_cpu_millicores: value.total_cpu_millicores,
+			remaining_memory_mib: value.remaining_memory_mib,
+			total_memory_mib: value.total_memory_mib,
 		})
 	}
 }
@@ -135,3 +180,51 @@ impl TryFrom<ActorNameKeyData> for pegboard_namespace_actor_name_v1::Data {
 		})
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn data(remaining_slots: u32, total_slots: u32) -> RunnerAllocIdxKeyData {
+		RunnerAllocIdxKeyData {
+			workflow_id: Id::nil(),
+			remaining_slots,
+			total_slots,
+			remaining_cpu_millicores: 0,
+			total_cpu_millicores: 0,
+			remaining_memory_mib: 0,
+			total_memory_mib: 0,
+		}
+	}
+
+	#[test]
+	fn remaining_capacity_is_only_zero_when_full() {
+		assert_eq!(data(0, 4).remaining_capacity(), 0);
+		assert!(data(0, 4).is_full());
+
+		// Would round down to zero with less precision
+		assert_eq!(data(1, 5_000_000).remaining_capacity(), 1);
+		assert!(!data(1, 5_000_000).is_full());
+	}
+
+	#[test]
+	fn remaining_capacity_orders_large_runners() {
+		assert!(data(1, 2000).remaining_capacity() < data(2, 2000).remaining_capacity());
+		assert!(data(1999, 2000).remaining_capacity() < data(2000, 2000).remaining_capacity());
+		assert_eq!(data(2000, 2000).remaining_capacity(), 1_000_000);
+	}
+
+	#[test]
+	fn remaining_capacity_uses_most_used_resource() {
+		let data = RunnerAllocIdxKeyData {
+			remaining_cpu_millicores: 500,
+			total_cpu_millicores: 1000,
+			remaining_memory_mib: 0,
+			total_memory_mib: 1024,
+			..data(4, 4)
+		};
+
+		assert_eq!(data.remaining_capacity(), 0);
+		assert!(data.is_full());
+	}
+}
@@ -5,7 +5,10 @@ pub mod versioned;
 pub const PEGBOARD_RUNNER_ADDRESS_VERSION: u16 = 1;
 pub const PEGBOARD_RUNNER_METADATA_VERSION: u16 = 1;
 pub const PEGBOARD_NAMESPACE_ACTOR_BY_KEY_VERSION: u16 = 1;
-pub const PEGBOARD_NAMESPACE_RUNNER_ALLOC_IDX_VERSION: u16 = 1;
-pub const PEGBOARD_NAMESPACE_RUNNER_CONFIG_VERSION: u16 = 2;
+pub const PEGBOARD_NAMESPACE_RUNNER_ALLOC_IDX_VERSION: u16 = 2;
+pub const PEGBOARD_NAMESPACE_RUNNER_CONFIG_VERSION: u16 = 3;
 pub const PEGBOARD_NAMESPACE_RUNNER_BY_KEY_VERSION: u16 = 1;
 pub const PEGBOARD_NAMESPACE_ACTOR_NAME_VERSION: u16 = 1;
+pub const NAMESPACE_KV_QUOTA_VERSION: u16 = 1;
+pub const PEGBOARD_ACTOR_PLACEMENT_VERSION: u16 = 1;
+pub const PEGBOARD_RESOURCES_VERSION: u16 = 1;
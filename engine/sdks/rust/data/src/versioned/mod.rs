@@ -9,18 +9,19 @@ pub use namespace_runner_config::*;
 
 pub enum RunnerAllocIdxKeyData {
 	V1(pegboard_namespace_runner_alloc_idx_v1::Data),
+	V2(pegboard_namespace_runner_alloc_idx_v2::Data),
 }
 
 impl OwnedVersionedData for RunnerAllocIdxKeyData {
-	type Latest = pegboard_namespace_runner_alloc_idx_v1::Data;
+	type Latest = pegboard_namespace_runner_alloc_idx_v2::Data;
 
-	fn latest(latest: pegboard_namespace_runner_alloc_idx_v1::Data) -> Self {
-		RunnerAllocIdxKeyData::V1(latest)
+	fn latest(latest: pegboard_namespace_runner_alloc_idx_v2::Data) -> Self {
+		RunnerAllocIdxKeyData::V2(latest)
 	}
 
 	fn into_latest(self) -> Result<Self::Latest> {
 		#[allow(irrefutable_let_patterns)]
-		if let RunnerAllocIdxKeyData::V1(data) = self {
+		if let RunnerAllocIdxKeyData::V2(data) = self {
 			Ok(data)
 		} else {
 			bail!("version not latest");
@@ -30,6 +31,7 @@ impl OwnedVersionedData for RunnerAllocIdxKeyData {
 	fn deserialize_version(payload: &[u8], version: u16) -> Result<Self> {
 		match version {
 			1 => Ok(RunnerAllocIdxKeyData::V1(serde_bare::from_slice(payload)?)),
+			2 => Ok(RunnerAllocIdxKeyData::V2(serde_bare::from_slice(payload)?)),
 			_ => bail!("invalid version: {version}"),
 		}
 	}
@@ -37,6 +39,47 @@ impl OwnedVersionedData for RunnerAllocIdxKeyData {
 	fn serialize_version(self, _version: u16) -> Result<Vec<u8>> {
 		match self {
 			RunnerAllocIdxKeyData::V1(data) => serde_bare::to_vec(&data).map_err(Into::into),
+			RunnerAllocIdxKeyData::V2(data) => serde_bare::to_vec(&data).map_err(Into::into),
+		}
+	}
+
+	fn deserialize_converters() -> Vec<impl Fn(Self) -> Result<Self>> {
+		vec![Self::v1_to_v2]
+	}
+
+	fn serialize_converters() -> Vec<impl Fn(Self) -> Result<Self>> {
+		vec![Self::v2_to_v1]
+	}
+}
+
+impl RunnerAllocIdxKeyData {
+	fn v1_to_v2(self) -> Result<Self> {
+		match self {
+			RunnerAllocIdxKeyData::V1(data) => Ok(RunnerAllocIdxKeyData::V2(
+				pegboard_namespace_runner_alloc_idx_v2::Data {
+					workflow_id: data.workflow_id,
+					remaining_slots: data.remaining_slots,
+					total_slots: data.total_slots,
+					remaining_cpu_millicores: 0,
+					total_cpu_millicores: 0,
+					remaining_memory_mib: 0,
+					total_memory_mib: 0,
+				},
+			)),
+			value @ RunnerAllocIdxKeyData::V2(_) => Ok(value),
+		}
+	}
+
+	fn v2_to_v1(self) -> Result<Self> {
+		match self {
+			RunnerAllocIdxKeyData::V2(data) => Ok(RunnerAllocIdxKeyData::V1(
+				pegboard_namespace_runner_alloc_idx_v1::Data {
+					workflow_id: data.workflow_id,
+					remaining_slots: data.remaining_slots,
+					total_slots: data.total_slots,
+				},
+			)),
+			value @ RunnerAllocIdxKeyData::V1(_) => Ok(value),
 		}
 	}
 }
@@ -176,3 +219,105 @@ impl OwnedVersionedData for ActorNameKeyData {
 		}
 	}
 }
+
+pub enum NamespaceKvQuota {
+	V1(namespace_kv_quota_v1::KvQuota),
+}
+
+impl OwnedVersionedData for NamespaceKvQuota {
+	type Latest = namespace_kv_quota_v1::KvQuota;
+
+	fn latest(latest: namespace_kv_quota_v1::KvQuota) -> Self {
+		NamespaceKvQuota::V1(latest)
+	}
+
+	fn into_latest(self) -> Result<Self::Latest> {
+		#[allow(irrefutable_let_patterns)]
+		if let NamespaceKvQuota::V1(data) = self {
+			Ok(data)
+		} else {
+			bail!("version not latest");
+		}
+	}
+
+	fn deserialize_version(payload: &[u8], version: u16) -> Result<Self> {
+		match version {
+			1 => Ok(NamespaceKvQuota::V1(serde_bare::from_slice(payload)?)),
+			_ => bail!("invalid version: {version}"),
+		}
+	}
+
+	fn serialize_version(self, _version: u16) -> Result<Vec<u8>> {
+		match self {
+			NamespaceKvQuota::V1(data) => serde_bare::to_vec(&data).map_err(Into::into),
+		}
+	}
+}
+
+pub enum PegboardActorPlacement {
+	V1(pegboard_actor_placement_v1::PlacementConstraints),
+}
+
+impl OwnedVersionedData for PegboardActorPlacement {
+	type Latest = pegboard_actor_placement_v1::PlacementConstraints;
+
+	fn latest(latest: pegboard_actor_placement_v1::PlacementConstraints) -> Self {
+		PegboardActorPlacement::V1(latest)
+	}
+
+	fn into_latest(self) -> Result<Self::Latest> {
+		#[allow(irrefutable_let_patterns)]
+		if let PegboardActorPlacement::V1(data) = self {
+			Ok(data)
+		} else {
+			bail!("version not latest");
+		}
+	}
+
+	fn deserialize_version(payload: &[u8], version: u16) -> Result<Self> {
+		match version {
+			1 => Ok(PegboardActorPlacement::V1(serde_bare::from_slice(payload)?)),
+			_ => bail!("invalid version: {version}"),
+		}
+	}
+
+	fn serialize_version(self, _version: u16) -> Result<Vec<u8>> {
+		match self {
+			PegboardActorPlacement::V1(data) => serde_bare::to_vec(&data).map_err(Into::into),
+		}
+	}
+}
+
+pub enum PegboardResources {
+	V1(pegboard_resources_v1::Resources),
+}
+
+impl OwnedVersionedData for PegboardResources {
+	type Latest = pegboard_resources_v1::Resources;
+
+	fn latest(latest: pegboard_resources_v1::Resources) -> Self {
+		PegboardResources::V1(latest)
+	}
+
+	fn into_latest(self) -> Result<Self::Latest> {
+		#[allow(irrefutable_let_patterns)]
+		if let PegboardResources::V1(data) = self {
+			Ok(data)
+		} else {
+			bail!("version not latest");
+		}
+	}
+
+	fn deserialize_version(payload: &[u8], version: u16) -> Result<Self> {
+		match version {
+			1 => Ok(PegboardResources::V1(serde_bare::from_slice(payload)?)),
+			_ => bail!("invalid version: {version}"),
+		}
+	}
+
+	fn serialize_version(self, _version: u16) -> Result<Vec<u8>> {
+		match self {
+			PegboardResources::V1(data) => serde_bare::to_vec(&data).map_err(Into::into),
+		}
+	}
+}
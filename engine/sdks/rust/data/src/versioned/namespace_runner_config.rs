@@ -6,18 +6,19 @@ use crate::generated::*;
 pub enum NamespaceRunnerConfig {
 	V1(namespace_runner_config_v1::Data),
 	V2(namespace_runner_config_v2::RunnerConfig),
+	V3(namespace_runner_config_v3::RunnerConfig),
 }
 
 impl OwnedVersionedData for NamespaceRunnerConfig {
-	type Latest = namespace_runner_config_v2::RunnerConfig;
+	type Latest = namespace_runner_config_v3::RunnerConfig;
 
-	fn latest(latest: namespace_runner_config_v2::RunnerConfig) -> Self {
-		NamespaceRunnerConfig::V2(latest)
+	fn latest(latest: namespace_runner_config_v3::RunnerConfig) -> Self {
+		NamespaceRunnerConfig::V3(latest)
 	}
 
 	fn into_latest(self) -> Result<Self::Latest> {
 		#[allow(irrefutable_let_patterns)]
-		if let NamespaceRunnerConfig::V2(data) = self {
+		if let NamespaceRunnerConfig::V3(data) = self {
 			Ok(data)
 		} else {
 			bail!("version not latest");
@@ -28,6 +29,7 @@ impl OwnedVersionedData for NamespaceRunnerConfig {
 		match version {
 			1 => Ok(NamespaceRunnerConfig::V1(serde_bare::from_slice(payload)?)),
 			2 => Ok(NamespaceRunnerConfig::V2(serde_bare::from_slice(payload)?)),
+			3 => Ok(NamespaceRunnerConfig::V3(serde_bare::from_slice(payload)?)),
 			_ => bail!("invalid version: {version}"),
 		}
 	}
@@ -36,15 +38,16 @@ impl OwnedVersionedData for NamespaceRunnerConfig {
 		match self {
 			NamespaceRunnerConfig::V1(data) => serde_bare::to_vec(&data).map_err(Into::into),
 			NamespaceRunnerConfig::V2(data) => serde_bare::to_vec(&data).map_err(Into::into),
+			NamespaceRunnerConfig::V3(data) => serde_bare::to_vec(&data).map_err(Into::into),
 		}
 	}
 
 	fn deserialize_converters() -> Vec<impl Fn(Self) -> Result<Self>> {
-		vec![Self::v1_to_v2]
+		vec![Self::v1_to_v2, Self::v2_to_v3]
 	}
 
 	fn serialize_converters() -> Vec<impl Fn(Self) -> Result<Self>> {
-		vec![Self::v2_to_v1]
+		vec![Self::v3_to_v2, Self::v2_to_v1]
 	}
 }
 
@@ -79,13 +82,109 @@ impl NamespaceRunnerConfig {
 					},
 				))
 			}
-			value @ NamespaceRunnerConfig::V2(_) => Ok(value),
+			value @ (NamespaceRunnerConfig::V2(_) | NamespaceRunnerConfig::V3(_)) => Ok(value),
+		}
+	}
+
+	fn v2_to_v3(self) -> Result<Self> {
+		match self {
+			NamespaceRunnerConfig::V2(config) => {
+				let namespace_runner_config_v2::RunnerConfig { kind, metadata } = config;
+
+				Ok(NamespaceRunnerConfig::V3(
+					namespace_runner_config_v3::RunnerConfig {
+						kind: match kind {
+							namespace_runner_config_v2::RunnerConfigKind::Serverless(
+								serverless,
+							) => {
+								let namespace_runner_config_v2::Serverless {
+									url,
+									headers,
+									request_lifespan,
+									slots_per_runner,
+									min_runners,
+									max_runners,
+									runners_margin,
+								} = serverless;
+
+								namespace_runner_config_v3::RunnerConfigKind::Serverless(
+									namespace_runner_config_v3::Serverless {
+										url,
+										headers,
+										request_lifespan,
+										slots_per_runner,
+										min_runners,
+										max_runners,
+										runners_margin,
+									},
+								)
+							}
+							namespace_runner_config_v2::RunnerConfigKind::Normal => {
+								namespace_runner_config_v3::RunnerConfigKind::Normal
+							}
+						},
+						metadata,
+						allocation_strategy:
+							namespace_runner_config_v3::AllocationStrategy::BinPack,
+					},
+				))
+			}
+			value => Ok(value),
+		}
+	}
+
+	fn v3_to_v2(self) -> Result<Self> {
+		match self {
+			NamespaceRunnerConfig::V3(config) => {
+				let namespace_runner_config_v3::RunnerConfig {
+					kind,
+					metadata,
+					allocation_strategy: _,
+				} = config;
+
+				Ok(NamespaceRunnerConfig::V2(
+					namespace_runner_config_v2::RunnerConfig {
+						kind: match kind {
+							namespace_runner_config_v3::RunnerConfigKind::Serverless(
+								serverless,
+							) => {
+								let namespace_runner_config_v3::Serverless {
+									url,
+									headers,
+									request_lifespan,
+									slots_per_runner,
+									min_runners,
+									max_runners,
+									runners_margin,
+								} = serverless;
+
+								namespace_runner_config_v2::RunnerConfigKind::Serverless(
+									namespace_runner_config_v2::Serverless {
+										url,
+										headers,
+										request_lifespan,
+										slots_per_runner,
+										min_runners,
+										max_runners,
+										runners_margin,
+									},
+								)
+							}
+							namespace_runner_config_v3::RunnerConfigKind::Normal => {
+								namespace_runner_config_v2::RunnerConfigKind::Normal
+							}
+						},
+						metadata,
+					},
+				))
+			}
+			value => Ok(value),
 		}
 	}
 
 	fn v2_to_v1(self) -> Result<Self> {
 		match self {
-			NamespaceRunnerConfig::V1(_) => Ok(self),
+			NamespaceRunnerConfig::V1(_) | NamespaceRunnerConfig::V3(_) => Ok(self),
 			NamespaceRunnerConfig::V2(config) => {
 				let namespace_runner_config_v2::RunnerConfig { metadata, kind } = config;
 
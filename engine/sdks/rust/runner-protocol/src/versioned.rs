@@ -1,22 +1,26 @@
 use anyhow::{Ok, Result, bail};
 use vbare::OwnedVersionedData;
 
-use crate::{PROTOCOL_VERSION, generated::v1};
+use crate::{
+	PROTOCOL_VERSION,
+	generated::{v1, v2},
+};
 
 pub enum ToClient {
 	V1(v1::ToClient),
+	V2(v2::ToClient),
 }
 
 impl OwnedVersionedData for ToClient {
-	type Latest = v1::ToClient;
+	type Latest = v2::ToClient;
 
-	fn latest(latest: v1::ToClient) -> Self {
-		ToClient::V1(latest)
+	fn latest(latest: v2::ToClient) -> Self {
+		ToClient::V2(latest)
 	}
 
 	fn into_latest(self) -> Result<Self::Latest> {
 		#[allow(irrefutable_let_patterns)]
-		if let ToClient::V1(data) = self {
+		if let ToClient::V2(data) = self {
 			Ok(data)
 		} else {
 			bail!("version not latest");
@@ -26,6 +30,7 @@ impl OwnedVersionedData for ToClient {
 	fn deserialize_version(payload: &[u8], version: u16) -> Result<Self> {
 		match version {
 			1 => Ok(ToClient::V1(serde_bare::from_slice(payload)?)),
+			2 => Ok(ToClient::V2(serde_bare::from_slice(payload)?)),
 			_ => bail!("invalid version: {version}"),
 		}
 	}
@@ -33,24 +38,128 @@ impl OwnedVersionedData for ToClient {
 	fn serialize_version(self, _version: u16) -> Result<Vec<u8>> {
 		match self {
 			ToClient::V1(data) => serde_bare::to_vec(&data).map_err(Into::into),
+			ToClient::V2(data) => serde_bare::to_vec(&data).map_err(Into::into),
 		}
 	}
+
+	fn deserialize_converters() -> Vec<impl Fn(Self) -> Result<Self>> {
+		vec![Self::v1_to_v2]
+	}
+
+	fn serialize_converters() -> Vec<impl Fn(Self) -> Result<Self>> {
+		vec![Self::v2_to_v1]
+	}
+}
+
+impl ToClient {
+	fn v1_to_v2(self) -> Result<Self> {
+		let ToClient::V1(data) = self else {
+			bail!("unexpected version");
+		};
+
+		let data = match data {
+			v1::ToClient::ToClientInit(init) => v2::ToClient::ToClientInit(v2::ToClientInit {
+				runner_id: init.runner_id,
+				last_event_idx: init.last_event_idx,
+				metadata: v2::ProtocolMetadata {
+					runner_lost_threshold: init.metadata.runner_lost_threshold,
+				},
+			}),
+			v1::ToClient::ToClientClose => v2::ToClient::ToClientClose,
+			v1::ToClient::ToClientCommands(commands) => v2::ToClient::ToClientCommands(
+				commands
+					.into_iter()
+					.map(|wrapper| v2::CommandWrapper {
+						index: wrapper.index,
+						inner: wrapper.inner.into(),
+					})
+					.collect(),
+			),
+			v1::ToClient::ToClientAckEvents(ack) => {
+				v2::ToClient::ToClientAckEvents(v2::ToClientAckEvents {
+					last_event_idx: ack.last_event_idx,
+				})
+			}
+			v1::ToClient::ToClientKvResponse(res) => {
+				v2::ToClient::ToClientKvResponse(v2::ToClientKvResponse {
+					request_id: res.request_id,
+					data: res.data.into(),
+				})
+			}
+			v1::ToClient::ToClientTunnelMessage(msg) => {
+				v2::ToClient::ToClientTunnelMessage(msg.into())
+			}
+		};
+
+		Ok(ToClient::V2(data))
+	}
+
+	fn v2_to_v1(self) -> Result<Self> {
+		let ToClient::V2(data) = self else {
+			bail!("unexpected version");
+		};
+
+		let data = match data {
+			v2::ToClient::ToClientInit(init) => v1::ToClient::ToClientInit(v1::ToClientInit {
+				runner_id: init.runner_id,
+				last_event_idx: init.last_event_idx,
+				metadata: v1::ProtocolMetadata {
+					runner_lost_threshold: init.metadata.runner_lost_threshold,
+				},
+			}),
+			v2::ToClient::ToClientClose => v1::ToClient::ToClientClose,
+			v2::ToClient::ToClientCommands(commands) => v1::ToClient::ToClientCommands(
+				commands
+					.into_iter()
+					// v1 runners cannot hand off state, the actor is stopped by the following
+					// `CommandStopActor` instead
+					.filter(|wrapper| !matches!(wrapper.inner, v2::Command::CommandHandoffActor(_)))
+					.map(|wrapper| {
+						Ok(v1::CommandWrapper {
+							index: wrapper.index,
+							inner: wrapper.inner.try_into()?,
+						})
+					})
+					.collect::<Result<_>>()?,
+			),
+			v2::ToClient::ToClientAckEvents(ack) => {
+				v1::ToClient::ToClientAckEvents(v1::ToClientAckEvents {
+					last_event_idx: ack.last_event_idx,
+				})
+			}
+			v2::ToClient::ToClientKvResponse(res) => {
+				v1::ToClient::ToClientKvResponse(v1::ToClientKvResponse {
+					request_id: res.request_id,
+					data: res.data.try_into()?,
+				})
+			}
+			v2::ToClient::ToClientTunnelMessage(msg) => {
+				v1::ToClient::ToClientTunnelMessage(msg.into())
+			}
+			v2::ToClient::ToClientKvWatchEvent(_) => {
+				bail!("KV watches are not supported in protocol v1")
+			}
+		};
+
+		Ok(ToClient::V1(data))
+	}
 }
 
 pub enum ToServer {
 	V1(v1::ToServer),
+	V2(v2::ToServer),
 }
 
 impl OwnedVersionedData for ToServer {
-	type Latest = v1::ToServer;
+	type Latest = v2::ToServer;
 
-	fn latest(latest: v1::ToServer) -> Self {
-		ToServer::V1(latest)
+	fn latest(latest: v2::ToServer) -> Self {
+		ToServer::V2(latest)
 	}
 
 	fn into_latest(self) -> Result<Self::Latest> {
 		#[allow(irrefutable_let_patterns)]
-		if let ToServer::V1(data) = self {
+		if let ToServer::V2(data) = self {
 			Ok(data)
 		} else {
 			bail!("version not latest");
@@ -60,6 +169,7 @@ impl OwnedVersionedData for ToServer {
 	fn deserialize_version(payload: &[u8], version: u16) -> Result<Self> {
 		match version {
 			1 => Ok(ToServer::V1(serde_bare::from_slice(payload)?)),
+			2 => Ok(ToServer::V2(serde_bare::from_slice(payload)?)),
 			_ => bail!("invalid version: {version}"),
 		}
 	}
@@ -67,24 +177,155 @@ impl OwnedVersionedData for ToServer {
 	fn serialize_version(self, _version: u16) -> Result<Vec<u8>> {
 		match self {
 			ToServer::V1(data) => serde_bare::to_vec(&data).map_err(Into::into),
+			ToServer::V2(data) => serde_bare::to_vec(&data).map_err(Into::into),
 		}
 	}
+
+	fn deserialize_converters() -> Vec<impl Fn(Self) -> Result<Self>> {
+		vec![Self::v1_to_v2]
+	}
+
+	fn serialize_converters() -> Vec<impl Fn(Self) -> Result<Self>> {
+		vec![Self::v2_to_v1]
+	}
+}
+
+impl ToServer {
+	fn v1_to_v2(self) -> Result<Self> {
+		let ToServer::V1(data) = self else {
+			bail!("unexpected version");
+		};
+
+		let data = match data {
+			v1::ToServer::ToServerInit(init) => v2::ToServer::ToServerInit(v2::ToServerInit {
+				name: init.name,
+				version: init.version,
+				total_slots: init.total_slots,
+				last_command_idx: init.last_command_idx,
+				prepopulate_actor_names: init.prepopulate_actor_names.map(|names| {
+					names
+						.into_iter()
+						.map(|(name, actor_name)| {
+							(
+								name,
+								v2::ActorName {
+									metadata: actor_name.metadata,
+								},
+							)
+						})
+						.collect()
+				}),
+				metadata: init.metadata,
+				resources: None,
+			}),
+			v1::ToServer::ToServerEvents(events) => v2::ToServer::ToServerEvents(
+				events
+					.into_iter()
+					.map(|wrapper| v2::EventWrapper {
+						index: wrapper.index,
+						inner: wrapper.inner.into(),
+					})
+					.collect(),
+			),
+			v1::ToServer::ToServerAckCommands(ack) => {
+				v2::ToServer::ToServerAckCommands(v2::ToServerAckCommands {
+					last_command_idx: ack.last_command_idx,
+				})
+			}
+			v1::ToServer::ToServerStopping => v2::ToServer::ToServerStopping,
+			v1::ToServer::ToServerPing(ping) => {
+				v2::ToServer::ToServerPing(v2::ToServerPing { ts: ping.ts })
+			}
+			v1::ToServer::ToServerKvRequest(req) => {
+				v2::ToServer::ToServerKvRequest(v2::ToServerKvRequest {
+					actor_id: req.actor_id,
+					request_id: req.request_id,
+					data: req.data.into(),
+				})
+			}
+			v1::ToServer::ToServerTunnelMessage(msg) => {
+				v2::ToServer::ToServerTunnelMessage(msg.into())
+			}
+		};
+
+		Ok(ToServer::V2(data))
+	}
+
+	fn v2_to_v1(self) -> Result<Self> {
+		let ToServer::V2(data) = self else {
+			bail!("unexpected version");
+		};
+
+		let data = match data {
+			v2::ToServer::ToServerInit(init) => v1::ToServer::ToServerInit(v1::ToServerInit {
+				name: init.name,
+				version: init.version,
+				total_slots: init.total_slots,
+				last_command_idx: init.last_command_idx,
+				prepopulate_actor_names: init.prepopulate_actor_names.map(|names| {
+					names
+						.into_iter()
+						.map(|(name, actor_name)| {
+							(
+								name,
+								v1::ActorName {
+									metadata: actor_name.metadata,
+								},
+							)
+						})
+						.collect()
+				}),
+				metadata: init.metadata,
+			}),
+			v2::ToServer::ToServerEvents(events) => v1::ToServer::ToServerEvents(
+				events
+					.into_iter()
+					.map(|wrapper| v1::EventWrapper {
+						index: wrapper.index,
+						inner: wrapper.inner.into(),
+					})
+					.collect(),
+			),
+			v2::ToServer::ToServerAckCommands(ack) => {
+				v1::ToServer::ToServerAckCommands(v1::ToServerAckCommands {
+					last_command_idx: ack.last_command_idx,
+				})
+			}
+			v2::ToServer::ToServerStopping => v1::ToServer::ToServerStopping,
+			v2::ToServer::ToServerPing(ping) => {
+				v1::ToServer::ToServerPing(v1::ToServerPing { ts: ping.ts })
+			}
+			v2::ToServer::ToServerKvRequest(req) => {
+				v1::ToServer::ToServerKvRequest(v1::ToServerKvRequest {
+					actor_id: req.actor_id,
+					request_id: req.request_id,
+					data: req.data.try_into()?,
+				})
+			}
+			v2::ToServer::ToServerTunnelMessage(msg) => {
+				v1::ToServer::ToServerTunnelMessage(msg.into())
+			}
+		};
+
+		Ok(ToServer::V1(data))
+	}
 }
 
 pub enum ToGateway {
 	V1(v1::ToGateway),
+	V2(v2::ToGateway),
 }
 
 impl OwnedVersionedData for ToGateway {
-	type Latest = v1::ToGateway;
+	type Latest = v2::ToGateway;
 
-	fn latest(latest: v1::ToGateway) -> Self {
-		ToGateway::V1(latest)
+	fn latest(latest: v2::ToGateway) -> Self {
+		ToGateway::V2(latest)
 	}
 
 	fn into_latest(self) -> Result<Self::Latest> {
 		#[allow(irrefutable_let_patterns)]
-		if let ToGateway::V1(data) = self {
+		if let ToGateway::V2(data) = self {
 			Ok(data)
 		} else {
 			bail!("version not latest");
@@ -94,6 +335,7 @@ impl OwnedVersionedData for ToGateway {
 	fn deserialize_version(payload: &[u8], version: u16) -> Result<Self> {
 		match version {
 			1 => Ok(ToGateway::V1(serde_bare::from_slice(payload)?)),
+			2 => Ok(ToGateway::V2(serde_bare::from_slice(payload)?)),
 			_ => bail!("invalid version: {version}"),
 		}
 	}
@@ -101,30 +343,60 @@ impl OwnedVersionedData for ToGateway {
 	fn serialize_version(self, _version: u16) -> Result<Vec<u8>> {
 		match self {
 			ToGateway::V1(data) => serde_bare::to_vec(&data).map_err(Into::into),
+			ToGateway::V2(data) => serde_bare::to_vec(&data).map_err(Into::into),
 		}
 	}
+
+	fn deserialize_converters() -> Vec<impl Fn(Self) -> Result<Self>> {
+		vec![Self::v1_to_v2]
+	}
+
+	fn serialize_converters() -> Vec<impl Fn(Self) -> Result<Self>> {
+		vec![Self::v2_to_v1]
+	}
 }
 
 impl ToGateway {
 	pub fn serialize(self) -> Result<Vec<u8>> {
 		<Self as OwnedVersionedData>::serialize(self, PROTOCOL_VERSION)
 	}
+
+	fn v1_to_v2(self) -> Result<Self> {
+		let ToGateway::V1(data) = self else {
+			bail!("unexpected version");
+		};
+
+		Ok(ToGateway::V2(v2::ToGateway {
+			message: data.message.into(),
+		}))
+	}
+
+	fn v2_to_v1(self) -> Result<Self> {
+		let ToGateway::V2(data) = self else {
+			bail!("unexpected version");
+		};
+
+		Ok(ToGateway::V1(v1::ToGateway {
+			message: data.message.into(),
+		}))
+	}
 }
 
 pub enum ToServerlessServer {
 	V1(v1::ToServerlessServer),
+	V2(v2::ToServerlessServer),
 }
 
 impl OwnedVersionedData for ToServerlessServer {
-	type Latest = v1::ToServerlessServer;
+	type Latest = v2::ToServerlessServer;
 
-	fn latest(latest: v1::ToServerlessServer) -> Self {
-		ToServerlessServer::V1(latest)
+	fn latest(latest: v2::ToServerlessServer) -> Self {
+		ToServerlessServer::V2(latest)
 	}
 
 	fn into_latest(self) -> Result<Self::Latest> {
 		#[allow(irrefutable_let_patterns)]
-		if let ToServerlessServer::V1(data) = self {
+		if let ToServerlessServer::V2(data) = self {
 			Ok(data)
 		} else {
 			bail!("version not latest");
@@ -134,6 +406,7 @@ impl OwnedVersionedData for ToServerlessServer {
 	fn deserialize_version(payload: &[u8], version: u16) -> Result<Self> {
 		match version {
 			1 => Ok(ToServerlessServer::V1(serde_bare::from_slice(payload)?)),
+			2 => Ok(ToServerlessServer::V2(serde_bare::from_slice(payload)?)),
 			_ => bail!("invalid version: {version}"),
 		}
 	}
@@ -141,6 +414,602 @@ impl OwnedVersionedData for ToServerlessServer {
 	fn serialize_version(self, _version: u16) -> Result<Vec<u8>> {
 		match self {
 			ToServerlessServer::V1(data) => serde_bare::to_vec(&data).map_err(Into::into),
+			ToServerlessServer::V2(data) => serde_bare::to_vec(&data).map_err(Into::into),
+		}
+	}
+
+	fn deserialize_converters() -> Vec<impl Fn(Self) -> Result<Self>> {
+		vec![Self::v1_to_v2]
+	}
+
+	fn serialize_converters() -> Vec<impl Fn(Self) -> Result<Self>> {
+		vec![Self::v2_to_v1]
+	}
+}
+
+impl ToServerlessServer {
+	fn v1_to_v2(self) -> Result<Self> {
+		let ToServerlessServer::V1(data) = self else {
+			bail!("unexpected version");
+		};
+
+		let data = match data {
+			v1::ToServerlessServer::ToServerlessServerInit(init) => {
+				v2::ToServerlessServer::ToServerlessServerInit(v2::ToServerlessServerInit {
+					runner_id: init.runner_id,
+				})
+			}
+		};
+
+		Ok(ToServerlessServer::V2(data))
+	}
+
+	fn v2_to_v1(self) -> Result<Self> {
+		let ToServerlessServer::V2(data) = self else {
+			bail!("unexpected version");
+		};
+
+		let data = match data {
+			v2::ToServerlessServer::ToServerlessServerInit(init) => {
+				v1::ToServerlessServer::ToServerlessServerInit(v1::ToServerlessServerInit {
+					runner_id: init.runner_id,
+				})
+			}
+		};
+
+		Ok(ToServerlessServer::V1(data))
+	}
+}
+
+// MARK: Conversions
+
+impl From<v1::Command> for v2::Command {
+	fn from(value: v1::Command) -> Self {
+		match value {
+			v1::Command::CommandStartActor(start) => {
+				v2::Command::CommandStartActor(v2::CommandStartActor {
+					actor_id: start.actor_id,
+					generation: start.generation,
+					config: v2::ActorConfig {
+						name: start.config.name,
+						key: start.config.key,
+						create_ts: start.config.create_ts,
+						input: start.config.input,
+					},
+				})
+			}
+			v1::Command::CommandStopActor(stop) => {
+				v2::Command::CommandStopActor(v2::CommandStopActor {
+					actor_id: stop.actor_id,
+					generation: stop.generation,
+				})
+			}
+		}
+	}
+}
+
+impl TryFrom<v2::Command> for v1::Command {
+	type Error = anyhow::Error;
+
+	fn try_from(value: v2::Command) -> Result<Self> {
+		Ok(match value {
+			v2::Command::CommandStartActor(start) => {
+				v1::Command::CommandStartActor(v1::CommandStartActor {
+					actor_id: start.actor_id,
+					generation: start.generation,
+					config: v1::ActorConfig {
+						name: start.config.name,
+						key: start.config.key,
+						create_ts: start.config.create_ts,
+						input: start.config.input,
+					},
+				})
+			}
+			v2::Command::CommandStopActor(stop) => {
+				v1::Command::CommandStopActor(v1::CommandStopActor {
+					actor_id: stop.actor_id,
+					generation: stop.generation,
+				})
+			}
+			v2::Command::CommandHandoffActor(_) => {
+				bail!("actor handoff is not supported in protocol v1")
+			}
+		})
+	}
+}
+
+impl From<v1::Event> for v2::Event {
+	fn from(value: v1::Event) -> Self {
+		match value {
+			v1::Event::EventActorIntent(intent) => {
+				v2::Event::EventActorIntent(v2::EventActorIntent {
+					actor_id: intent.actor_id,
+					generation: intent.generation,
+					intent: match intent.intent {
+						v1::ActorIntent::ActorIntentSleep => v2::ActorIntent::ActorIntentSleep,
+						v1::ActorIntent::ActorIntentStop => v2::ActorIntent::ActorIntentStop,
+					},
+				})
+			}
+			v1::Event::EventActorStateUpdate(update) => {
+				v2::Event::EventActorStateUpdate(v2::EventActorStateUpdate {
+					actor_id: update.actor_id,
+					generation: update.generation,
+					state: match update.state {
+						v1::ActorState::ActorStateRunning => v2::ActorState::ActorStateRunning,
+						v1::ActorState::ActorStateStopped(stopped) => {
+							v2::ActorState::ActorStateStopped(v2::ActorStateStopped {
+								code: match stopped.code {
+									v1::StopCode::Ok => v2::StopCode::Ok,
+									v1::StopCode::Error => v2::StopCode::Error,
+								},
+								message: stopped.message,
+							})
+						}
+					},
+				})
+			}
+			v1::Event::EventActorSetAlarm(alarm) => {
+				v2::Event::EventActorSetAlarm(v2::EventActorSetAlarm {
+					actor_id: alarm.actor_id,
+					generation: alarm.generation,
+					alarm_ts: alarm.alarm_ts,
+				})
+			}
+		}
+	}
+}
+
+impl From<v2::Event> for v1::Event {
+	fn from(value: v2::Event) -> Self {
+		match value {
+			v2::Event::EventActorIntent(intent) => {
+				v1::Event::EventActorIntent(v1::EventActorIntent {
+					actor_id: intent.actor_id,
+					generation: intent.generation,
+					intent: match intent.intent {
+						v2::ActorIntent::ActorIntentSleep => v1::ActorIntent::ActorIntentSleep,
+						v2::ActorIntent::ActorIntentStop => v1::ActorIntent::ActorIntentStop,
+					},
+				})
+			}
+			v2::Event::EventActorStateUpdate(update) => {
+				v1::Event::EventActorStateUpdate(v1::EventActorStateUpdate {
+					actor_id: update.actor_id,
+					generation: update.generation,
+					state: match update.state {
+						v2::ActorState::ActorStateRunning => v1::ActorState::ActorStateRunning,
+						v2::ActorState::ActorStateStopped(stopped) => {
+							v1::ActorState::ActorStateStopped(v1::ActorStateStopped {
+								code: match stopped.code {
+									v2::StopCode::Ok => v1::StopCode::Ok,
+									v2::StopCode::Error => v1::StopCode::Error,
+								},
+								message: stopped.message,
+							})
+						}
+					},
+				})
+			}
+			v2::Event::EventActorSetAlarm(alarm) => {
+				v1::Event::EventActorSetAlarm(v1::EventActorSetAlarm {
+					actor_id: alarm.actor_id,
+					generation: alarm.generation,
+					alarm_ts: alarm.alarm_ts,
+				})
+			}
+		}
+	}
+}
+
+impl From<v1::KvMetadata> for v2::KvMetadata {
+	fn from(value: v1::KvMetadata) -> Self {
+		v2::KvMetadata {
+			version: value.version,
+			create_ts: value.create_ts,
+		}
+	}
+}
+
+impl From<v2::KvMetadata> for v1::KvMetadata {
+	fn from(value: v2::KvMetadata) -> Self {
+		v1::KvMetadata {
+			version: value.version,
+			create_ts: value.create_ts,
+		}
+	}
+}
+
+impl From<v1::KvRequestData> for v2::KvRequestData {
+	fn from(value: v1::KvRequestData) -> Self {
+		match value {
+			v1::KvRequestData::KvGetRequest(req) => {
+				v2::KvRequestData::KvGetRequest(v2::KvGetRequest { keys: req.keys })
+			}
+			v1::KvRequestData::KvListRequest(req) => {
+				v2::KvRequestData::KvListRequest(v2::KvListRequest {
+					query: match req.query {
+						v1::KvListQuery::KvListAllQuery => v2::KvListQuery::KvListAllQuery,
+						v1::KvListQuery::KvListRangeQuery(range) => {
+							v2::KvListQuery::KvListRangeQuery(v2::KvListRangeQuery {
+								start: range.start,
+								end: range.end,
+								exclusive: range.exclusive,
+							})
+						}
+						v1::KvListQuery::KvListPrefixQuery(prefix) => {
+							v2::KvListQuery::KvListPrefixQuery(v2::KvListPrefixQuery {
+								key: prefix.key,
+							})
+						}
+					},
+					reverse: req.reverse,
+					limit: req.limit,
+					cursor: None,
+					max_bytes: None,
+				})
+			}
+			v1::KvRequestData::KvPutRequest(req) => {
+				v2::KvRequestData::KvPutRequest(v2::KvPutRequest {
+					keys: req.keys,
+					values: req.values,
+					expire_ts: None,
+				})
+			}
+			v1::KvRequestData::KvDeleteRequest(req) => {
+				v2::KvRequestData::KvDeleteRequest(v2::KvDeleteRequest { keys: req.keys })
+			}
+			v1::KvRequestData::KvDropRequest => v2::KvRequestData::KvDropRequest,
+		}
+	}
+}
+
+impl TryFrom<v2::KvRequestData> for v1::KvRequestData {
+	type Error = anyhow::Error;
+
+	fn try_from(value: v2::KvRequestData) -> Result<Self> {
+		Ok(match value {
+			v2::KvRequestData::KvGetRequest(req) => {
+				v1::KvRequestData::KvGetRequest(v1::KvGetRequest { keys: req.keys })
+			}
+			v2::KvRequestData::KvListRequest(req) => {
+				if req.cursor.is_some() || req.max_bytes.is_some() {
+					bail!("KV list pagination is not supported in protocol v1");
+				}
+
+				v1::KvRequestData::KvListRequest(v1::KvListRequest {
+					query: match req.query {
+						v2::KvListQuery::KvListAllQuery => v1::KvListQuery::KvListAllQuery,
+						v2::KvListQuery::KvListRangeQuery(range) => {
+							v1::KvListQuery::KvListRangeQuery(v1::KvListRangeQuery {
+								start: range.start,
+								end: range.end,
+								exclusive: range.exclusive,
+							})
+						}
+						v2::KvListQuery::KvListPrefixQuery(prefix) => {
+							v1::KvListQuery::KvListPrefixQuery(v1::KvListPrefixQuery {
+								key: prefix.key,
+							})
+						}
+					},
+					reverse: req.reverse,
+					limit: req.limit,
+				})
+			}
+			v2::KvRequestData::KvPutRequest(req) => {
+				if req.expire_ts.is_some() {
+					bail!("KV entry expiration is not supported in protocol v1");
+				}
+
+				v1::KvRequestData::KvPutRequest(v1::KvPutRequest {
+					keys: req.keys,
+					values: req.values,
+				})
+			}
+			v2::KvRequestData::KvDeleteRequest(req) => {
+				v1::KvRequestData::KvDeleteRequest(v1::KvDeleteRequest { keys: req.keys })
+			}
+			v2::KvRequestData::KvDropRequest => v1::KvRequestData::KvDropRequest,
+			v2::KvRequestData::KvTxnRequest(_) => {
+				bail!("KV transactions are not supported in protocol v1")
+			}
+			v2::KvRequestData::KvWatchRequest(_) | v2::KvRequestData::KvUnwatchRequest(_) => {
+				bail!("KV watches are not supported in protocol v1")
+			}
+		})
+	}
+}
+
+impl From<v1::KvResponseData> for v2::KvResponseData {
+	fn from(value: v1::KvResponseData) -> Self {
+		match value {
+			v1::KvResponseData::KvErrorResponse(res) => {
+				v2::KvResponseData::KvErrorResponse(v2::KvErrorResponse {
+					message: res.message,
+				})
+			}
+			v1::KvResponseData::KvGetResponse(res) => {
+				v2::KvResponseData::KvGetResponse(v2::KvGetResponse {
+					keys: res.keys,
+					values: res.values,
+					metadata: res.metadata.into_iter().map(Into::into).collect(),
+				})
+			}
+			v1::KvResponseData::KvListResponse(res) => {
+				v2::KvResponseData::KvListResponse(v2::KvListResponse {
+					keys: res.keys,
+					values: res.values,
+					metadata: res.metadata.into_iter().map(Into::into).collect(),
+					cursor: None,
+				})
+			}
+			v1::KvResponseData::KvPutResponse => v2::KvResponseData::KvPutResponse,
+			v1::KvResponseData::KvDeleteResponse => v2::KvResponseData::KvDeleteResponse,
+			v1::KvResponseData::KvDropResponse => v2::KvResponseData::KvDropResponse,
+		}
+	}
+}
+
+impl TryFrom<v2::KvResponseData> for v1::KvResponseData {
+	type Error = anyhow::Error;
+
+	fn try_from(value: v2::KvResponseData) -> Result<Self> {
+		Ok(match value {
+			v2::KvResponseData::KvErrorResponse(res) => {
+				v1::KvResponseData::KvErrorResponse(v1::KvErrorResponse {
+					message: res.message,
+				})
+			}
+			v2::KvResponseData::KvGetResponse(res) => {
+				v1::KvResponseData::KvGetResponse(v1::KvGetResponse {
+					keys: res.keys,
+					values: res.values,
+					metadata: res.metadata.into_iter().map(Into::into).collect(),
+				})
+			}
+			v2::KvResponseData::KvListResponse(res) => {
+				v1::KvResponseData::KvListResponse(v1::KvListResponse {
+					keys: res.keys,
+					values: res.values,
+					metadata: res.metadata.into_iter().map(Into::into).collect(),
+				})
+			}
+			v2::KvResponseData::KvPutResponse => v1::KvResponseData::KvPutResponse,
+			v2::KvResponseData::KvDeleteResponse => v1::KvResponseData::KvDeleteResponse,
+			v2::KvResponseData::KvDropResponse => v1::KvResponseData::KvDropResponse,
+			v2::KvResponseData::KvTxnResponse(_) => {
+				bail!("KV transactions are not supported in protocol v1")
+			}
+			v2::KvResponseData::KvWatchResponse | v2::KvResponseData::KvUnwatchResponse => {
+				bail!("KV watches are not supported in protocol v1")
+			}
+		})
+	}
+}
+
+impl From<v1::ToClientTunnelMessage> for v2::ToClientTunnelMessage {
+	fn from(value: v1::ToClientTunnelMessage) -> Self {
+		v2::ToClientTunnelMessage {
+			request_id: value.request_id,
+			message_id: value.message_id,
+			message_kind: match value.message_kind {
+				v1::ToClientTunnelMessageKind::TunnelAck => {
+					v2::ToClientTunnelMessageKind::TunnelAck
+				}
+				v1::ToClientTunnelMessageKind::ToClientRequestStart(start) => {
+					v2::ToClientTunnelMessageKind::ToClientRequestStart(v2::ToClientRequestStart {
+						actor_id: start.actor_id,
+						method: start.method,
+						path: start.path,
+						headers: start.headers,
+						body: start.body,
+						stream: start.stream,
+					})
+				}
+				v1::ToClientTunnelMessageKind::ToClientRequestChunk(chunk) => {
+					v2::ToClientTunnelMessageKind::ToClientRequestChunk(v2::ToClientRequestChunk {
+						body: chunk.body,
+						finish: chunk.finish,
+					})
+				}
+				v1::ToClientTunnelMessageKind::ToClientRequestAbort => {
+					v2::ToClientTunnelMessageKind::ToClientRequestAbort
+				}
+				v1::ToClientTunnelMessageKind::ToClientWebSocketOpen(open) => {
+					v2::ToClientTunnelMessageKind::ToClientWebSocketOpen(
+						v2::ToClientWebSocketOpen {
+							actor_id: open.actor_id,
+							path: open.path,
+							headers: open.headers,
+						},
+					)
+				}
+				v1::ToClientTunnelMessageKind::ToClientWebSocketMessage(msg) => {
+					v2::ToClientTunnelMessageKind::ToClientWebSocketMessage(
+						v2::ToClientWebSocketMessage {
+							data: msg.data,
+							binary: msg.binary,
+						},
+					)
+				}
+				v1::ToClientTunnelMessageKind::ToClientWebSocketClose(close) => {
+					v2::ToClientTunnelMessageKind::ToClientWebSocketClose(
+						v2::ToClientWebSocketClose {
+							code: close.code,
+							reason: close.reason,
+						},
+					)
+				}
+			},
+			gateway_reply_to: value.gateway_reply_to,
+		}
+	}
+}
+
+impl From<v2::ToClientTunnelMessage> for v1::ToClientTunnelMessage {
+	fn from(value: v2::ToClientTunnelMessage) -> Self {
+		v1::ToClientTunnelMessage {
+			request_id: value.request_id,
+			message_id: value.message_id,
+			message_kind: match value.message_kind {
+				v2::ToClientTunnelMessageKind::TunnelAck => {
+					v1::ToClientTunnelMessageKind::TunnelAck
+				}
+				v2::ToClientTunnelMessageKind::ToClientRequestStart(start) => {
+					v1::ToClientTunnelMessageKind::ToClientRequestStart(v1::ToClientRequestStart {
+						actor_id: start.actor_id,
+						method: start.method,
+						path: start.path,
+						headers: start.headers,
+						body: start.body,
+						stream: start.stream,
+					})
+				}
+				v2::ToClientTunnelMessageKind::ToClientRequestChunk(chunk) => {
+					v1::ToClientTunnelMessageKind::ToClientRequestChunk(v1::ToClientRequestChunk {
+						body: chunk.body,
+						finish: chunk.finish,
+					})
+				}
+				v2::ToClientTunnelMessageKind::ToClientRequestAbort => {
+					v1::ToClientTunnelMessageKind::ToClientRequestAbort
+				}
+				v2::ToClientTunnelMessageKind::ToClientWebSocketOpen(open) => {
+					v1::ToClientTunnelMessageKind::ToClientWebSocketOpen(
+						v1::ToClientWebSocketOpen {
+							actor_id: open.actor_id,
+							path: open.path,
+							headers: open.headers,
+						},
+					)
+				}
+				v2::ToClientTunnelMessageKind::ToClientWebSocketMessage(msg) => {
+					v1::ToClientTunnelMessageKind::ToClientWebSocketMessage(
+						v1::ToClientWebSocketMessage {
+							data: msg.data,
+							binary: msg.binary,
+						},
+					)
+				}
+				v2::ToClientTunnelMessageKind::ToClientWebSocketClose(close) => {
+					v1::ToClientTunnelMessageKind::ToClientWebSocketClose(
+						v1::ToClientWebSocketClose {
+							code: close.code,
+							reason: close.reason,
+						},
+					)
+				}
+			},
+			gateway_reply_to: value.gateway_reply_to,
+		}
+	}
+}
+
+impl From<v1::ToServerTunnelMessage> for v2::ToServerTunnelMessage {
+	fn from(value: v1::ToServerTunnelMessage) -> Self {
+		v2::ToServerTunnelMessage {
+			request_id: value.request_id,
+			message_id: value.message_id,
+			message_kind: match value.message_kind {
+				v1::ToServerTunnelMessageKind::TunnelAck => {
+					v2::ToServerTunnelMessageKind::TunnelAck
+				}
+				v1::ToServerTunnelMessageKind::ToServerResponseStart(start) => {
+					v2::ToServerTunnelMessageKind::ToServerResponseStart(
+						v2::ToServerResponseStart {
+							status: start.status,
+							headers: start.headers,
+							body: start.body,
+							stream: start.stream,
+						},
+					)
+				}
+				v1::ToServerTunnelMessageKind::ToServerResponseChunk(chunk) => {
+					v2::ToServerTunnelMessageKind::ToServerResponseChunk(
+						v2::ToServerResponseChunk {
+							body: chunk.body,
+							finish: chunk.finish,
+						},
+					)
+				}
+				v1::ToServerTunnelMessageKind::ToServerResponseAbort => {
+					v2::ToServerTunnelMessageKind::ToServerResponseAbort
+				}
+				v1::ToServerTunnelMessageKind::ToServerWebSocketOpen => {
+					v2::ToServerTunnelMessageKind::ToServerWebSocketOpen
+				}
+				v1::ToServerTunnelMessageKind::ToServerWebSocketMessage(msg) => {
+					v2::ToServerTunnelMessageKind::ToServerWebSocketMessage(
+						v2::ToServerWebSocketMessage {
+							data: msg.data,
+							binary: msg.binary,
+						},
+					)
+				}
+				v1::ToServerTunnelMessageKind::ToServerWebSocketClose(close) => {
+					v2::ToServerTunnelMessageKind::ToServerWebSocketClose(
+						v2::ToServerWebSocketClose {
+							code: close.code,
+							reason: close.reason,
+						},
+					)
+				}
+			},
+		}
+	}
+}
+
+impl From<v2::ToServerTunnelMessage> for v1::ToServerTunnelMessage {
+	fn from(value: v2::ToServerTunnelMessage) -> Self {
+		v1::ToServerTunnelMessage {
+			request_id: value.request_id,
+			message_id: value.message_id,
+			message_kind: match value.message_kind {
+				v2::ToServerTunnelMessageKind::TunnelAck => {
+					v1::ToServerTunnelMessageKind::TunnelAck
+				}
+				v2::ToServerTunnelMessageKind::ToServerResponseStart(start) => {
+					v1::ToServerTunnelMessageKind::ToServerResponseStart(
+						v1::ToServerResponseStart {
+							status: start.status,
+							headers: start.headers,
+							body: start.body,
+							stream: start.stream,
+						},
+					)
+				}
+				v2::ToServerTunnelMessageKind::ToServerResponseChunk(chunk) => {
+					v1::ToServerTunnelMessageKind::ToServerResponseChunk(
+						v1::ToServerResponseChunk {
+							body: chunk.body,
+							finish: chunk.finish,
+						},
+					)
+				}
+				v2::ToServerTunnelMessageKind::ToServerResponseAbort => {
+					v1::ToServerTunnelMessageKind::ToServerResponseAbort
+				}
+				v2::ToServerTunnelMessageKind::ToServerWebSocketOpen => {
+					v1::ToServerTunnelMessageKind::ToServerWebSocketOpen
+				}
+				v2::ToServerTunnelMessageKind::ToServerWebSocketMessage(msg) => {
+					v1::ToServerTunnelMessageKind::ToServerWebSocketMessage(
+						v1::ToServerWebSocketMessage {
+							data: msg.data,
+							binary: msg.binary,
+						},
+					)
+				}
+				v2::ToServerTunnelMessageKind::ToServerWebSocketClose(close) => {
+					v1::ToServerTunnelMessageKind::ToServerWebSocketClose(
+						v1::ToServerWebSocketClose {
+							code: close.code,
+							reason: close.reason,
+						},
+					)
+				}
+			},
 		}
 	}
 }